@@ -0,0 +1,54 @@
+//! Criterion benchmarks for the execution engine
+//! 
+//! Run with `cargo bench`. Covers webhook dispatch latency, FunLogic throughput
+//! and SimpleTableWriter insert rates through the full in-process HTTP stack,
+//! plus the compile-time structures on the webhook hot path.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use mechaway::{
+    bench::{bench_workflows, BenchHarness},
+    workflow::{pins::PinExpr, webhook_index::WebhookRouteIndex, Workflow},
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Webhook route index lookups and pin parsing (no I/O)
+fn compiled_structures(c: &mut Criterion) {
+    let workflow: Workflow = serde_json::from_value(bench_workflows().remove(0)).expect("bench workflow");
+    let index = WebhookRouteIndex::build(&workflow).expect("route index");
+
+    let mut group = c.benchmark_group("compiled");
+    group.bench_function("webhook_index_resolve", |b| {
+        b.iter(|| index.resolve("POST", std::hint::black_box("/ping")))
+    });
+    group.bench_function("pin_parse", |b| {
+        b.iter(|| PinExpr::parse(std::hint::black_box("$json.customer.address.city")))
+    });
+    group.finish();
+}
+
+/// End-to-end webhook requests per scenario through the Axum router
+fn webhook_scenarios(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().expect("tokio runtime");
+    let harness = runtime.block_on(BenchHarness::start()).expect("bench harness");
+    let seq = AtomicUsize::new(0);
+
+    let mut group = c.benchmark_group("webhook");
+    group.throughput(Throughput::Elements(1));
+    for scenario in ["webhook", "funlogic", "simpletable"] {
+        let client = harness.client();
+        group.bench_function(scenario, |b| {
+            b.to_async(&runtime).iter(|| {
+                let client = client.clone();
+                let seq = seq.fetch_add(1, Ordering::Relaxed);
+                async move {
+                    let status = client.request(scenario, seq).await.expect("bench request");
+                    assert!(status.is_success(), "{} returned {}", scenario, status);
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, compiled_structures, webhook_scenarios);
+criterion_main!(benches);
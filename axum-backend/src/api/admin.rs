@@ -0,0 +1,193 @@
+//! Administrative endpoints
+//! 
+//! Runtime operations on the server process itself: engine status for
+//! operators, changing log verbosity without a restart, running SQLite
+//! maintenance outside its window and toggling maintenance mode.
+
+use crate::{
+    api::{error::ApiError, oauth::OAUTH_CALLBACK_ROUTE, workflows::AppState},
+    logging::log_handle,
+};
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+    routing::{get, post},
+    Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+/// Request body for changing the log filter
+#[derive(Debug, Deserialize)]
+pub struct LogLevelRequest {
+    /// `RUST_LOG`-style directives (e.g. "info,mechaway::runtime=debug")
+    pub filter: String,
+}
+
+/// Request body for toggling maintenance mode
+#[derive(Debug, Deserialize)]
+pub struct MaintenanceModeRequest {
+    pub enabled: bool,
+    /// Response body of rejected requests (default: { "error": "maintenance", "message": "..." })
+    #[serde(default)]
+    pub body: Option<Value>,
+    /// Retry-After hint in seconds (default: the execution queue's)
+    #[serde(default)]
+    pub retry_after_secs: Option<u64>,
+}
+
+/// Create admin routes
+pub fn create_admin_routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/admin/status", get(get_status))
+        .route("/api/admin/log-level", get(get_log_level).put(set_log_level))
+        .route("/api/admin/maintenance/run", post(run_maintenance))
+        .route("/api/admin/maintenance-mode", get(get_maintenance_mode).put(set_maintenance_mode))
+}
+
+/// Engine status for operators
+/// 
+/// GET /api/admin/status
+/// Returns: { "uptime_secs": 3600, "workflows": 12, "registry_generation": 41, "scheduler_jobs": 3, "mqtt_subscriptions": 1, "opcua_subscriptions": 0, "serial_triggers": 0, "ble_triggers": 0,
+///            "execution_queue": { "running": 2, "queued": 0, ... },
+///            "database_pools": { "default": { "project_db": { "size": 4, "idle": 3 } } },
+///            "memory": { "rss_bytes": 52428800, "peak_rss_bytes": 61865984 }, "maintenance_mode": false, "role": "primary" }
+async fn get_status(State(state): State<AppState>) -> Json<Value> {
+    Json(json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "uptime_secs": state.started_at.elapsed().as_secs(),
+        "workflows": state.registry.list_workflow_ids().len(),
+        "registry_generation": state.registry.generation(),
+        "scheduler_jobs": state.scheduler.job_count().await,
+        "mqtt_subscriptions": state.mqtt.subscription_count().await,
+        "opcua_subscriptions": state.opcua.subscription_count().await,
+        "serial_triggers": state.serial.reader_count().await,
+        "ble_triggers": state.ble.listener_count().await,
+        "execution_queue": state.queue.stats(),
+        "database_pools": state.project_db_manager.pool_stats().await,
+        "memory": memory_usage(),
+        "maintenance_mode": state.maintenance_mode.is_enabled(),
+        "role": if state.project_db_manager.is_read_only() { "follower" } else { "primary" },
+    }))
+}
+
+/// Resident memory of this process (Linux /proc; null elsewhere)
+fn memory_usage() -> Option<Value> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    // Values are reported as "VmRSS:    51200 kB"
+    let kib = |field: &str| -> Option<u64> {
+        status.lines()
+            .find_map(|line| line.strip_prefix(field))
+            .and_then(|rest| rest.split_whitespace().next())
+            .and_then(|value| value.parse().ok())
+    };
+    Some(json!({
+        "rss_bytes": kib("VmRSS:")? * 1024,
+        "peak_rss_bytes": kib("VmHWM:").map(|value| value * 1024),
+    }))
+}
+
+/// Get the active log filter
+/// 
+/// GET /api/admin/log-level
+/// Returns: { "filter": "info" }
+async fn get_log_level() -> Result<Json<Value>, StatusCode> {
+    // Not available when the embedding process installed its own subscriber
+    let handle = log_handle().ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+    Ok(Json(json!({ "filter": handle.filter() })))
+}
+
+/// Replace the active log filter without restarting
+/// 
+/// PUT /api/admin/log-level
+/// Body: { "filter": "info,mechaway::runtime=debug" }
+/// Returns: { "filter": "info,mechaway::runtime=debug" }
+async fn set_log_level(Json(request): Json<LogLevelRequest>) -> Result<Json<Value>, StatusCode> {
+    let handle = log_handle().ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+
+    if let Err(e) = handle.set_filter(&request.filter) {
+        tracing::warn!("⚠️ Rejected log filter change: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    tracing::info!("🔊 Log filter changed to '{}'", request.filter);
+
+    Ok(Json(json!({ "filter": handle.filter() })))
+}
+
+/// Run SQLite maintenance on every project database now
+/// 
+/// POST /api/admin/maintenance/run
+/// Returns: { "databases": [{ "project": "default", "database": "simpletable.db",
+///            "bytes_before": 52428800, "bytes_after": 8388608, "bytes_reclaimed": 44040192, ... }] }
+async fn run_maintenance(State(state): State<AppState>) -> Json<Value> {
+    Json(json!({ "databases": state.maintenance.run().await }))
+}
+
+/// Maintenance mode state and the executions still draining
+/// 
+/// GET /api/admin/maintenance-mode
+/// Returns: { "enabled": true, "since": "...", "body": {...}, "retry_after_secs": 60, "running": 2, "queued": 0 }
+async fn get_maintenance_mode(State(state): State<AppState>) -> Json<Value> {
+    Json(maintenance_mode_json(&state))
+}
+
+/// Switch maintenance mode on or off
+/// 
+/// PUT /api/admin/maintenance-mode
+/// Body: { "enabled": true, "body": { "error": "maintenance", "message": "Back at 06:00 UTC" }, "retry_after_secs": 600 }
+/// While it is on, webhook, form, chat and WebSocket requests get 503 with `body` and
+/// Retry-After, and schedules and subscription triggers start no runs. Runs in flight
+/// finish normally; poll this endpoint until "running" and "queued" reach 0.
+async fn set_maintenance_mode(
+    State(state): State<AppState>,
+    Json(request): Json<MaintenanceModeRequest>,
+) -> Json<Value> {
+    if request.enabled {
+        state.maintenance_mode.enable(request.body, request.retry_after_secs);
+        tracing::warn!("🚧 Maintenance mode on - rejecting new trigger ingress");
+    } else if state.maintenance_mode.disable() {
+        tracing::info!("✅ Maintenance mode off - accepting trigger ingress");
+    }
+
+    Json(maintenance_mode_json(&state))
+}
+
+fn maintenance_mode_json(state: &AppState) -> Value {
+    let queue = state.queue.stats();
+    let mut body = match state.maintenance_mode.current() {
+        Some(notice) => json!({ "enabled": true, "since": notice.since, "body": notice.body, "retry_after_secs": notice.retry_after_secs }),
+        None => json!({ "enabled": false }),
+    };
+    body["running"] = json!(queue.running);
+    body["queued"] = json!(queue.queued);
+    body
+}
+
+/// GET routes that write state (rejected on followers like any other write)
+const WRITING_GET_ROUTES: &[&str] = &[OAUTH_CALLBACK_ROUTE];
+
+/// Middleware of follower instances rejecting every request that could write
+/// (anything but GET, HEAD and OPTIONS, and the GET routes that write)
+pub async fn reject_writes(request: Request, next: Next) -> Response {
+    let writes = match request.method() {
+        &Method::GET | &Method::HEAD => request.extensions().get::<MatchedPath>()
+            .is_some_and(|route| WRITING_GET_ROUTES.contains(&route.as_str())),
+        &Method::OPTIONS => false,
+        _ => true,
+    };
+    if writes {
+        return ApiError::ReadOnly.into_response();
+    }
+    next.run(request).await
+}
+
+/// Middleware rejecting trigger ingress while the server is in maintenance mode
+pub async fn reject_during_maintenance(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    match state.maintenance_mode.current() {
+        Some(notice) => ApiError::Maintenance(notice).into_response(),
+        None => next.run(request).await,
+    }
+}
@@ -0,0 +1,210 @@
+//! OpenAI-compatible chat endpoints for ChatTrigger nodes
+//! 
+//! A ChatTrigger node publishes its workflow as a "model", so chat UIs and
+//! OpenAI SDKs can use Mechaway as their provider (base URL http://host/v1):
+//! 
+//! { "model": "support-bot", "api_key": "$secret.chat_api_key", "reply_field": "reply" }
+//! 
+//! POST /v1/chat/completions runs the workflow with the request body as its
+//! item (plus "last_message", the text of the last user message) and answers
+//! with the workflow's reply as a chat.completion, or as server-sent
+//! chat.completion.chunk events when "stream" is true. GET /v1/models lists
+//! the models the caller's key can use.
+
+use crate::api::{git_webhooks::constant_time_eq, webhooks::WebhookAppState};
+use crate::workflow::types::{ExecutionContext, Node};
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::{get, post},
+    Router,
+};
+use serde_json::{json, Value};
+
+/// Reply fields tried (in order) when the node sets no "reply_field"
+const REPLY_FIELDS: &[&str] = &["content", "reply", "message", "text", "output"];
+
+/// Create OpenAI-compatible chat routes
+pub fn create_chat_routes() -> Router<WebhookAppState> {
+    Router::new()
+        .route("/v1/models", get(list_models))
+        .route("/v1/chat/completions", post(chat_completions))
+}
+
+/// Error in the OpenAI error format
+fn error_response(status: StatusCode, error_type: &str, message: impl Into<String>) -> Response {
+    (status, Json(json!({
+        "error": { "message": message.into(), "type": error_type, "param": null, "code": null }
+    }))).into_response()
+}
+
+/// Whether the request's bearer token opens a ChatTrigger or MCPTrigger node (nodes without "api_key" are public)
+pub fn authorized(state: &WebhookAppState, project_slug: &str, node: &Node, headers: &HeaderMap) -> anyhow::Result<bool> {
+    let Some(pin) = node.params.get("api_key").and_then(|k| k.as_str()) else {
+        return Ok(true);
+    };
+    let expected = state.engine.executor().resolve_secret(project_slug, pin)?;
+    let presented = headers.get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    Ok(constant_time_eq(presented.as_bytes(), expected.as_bytes()))
+}
+
+/// List the models served by ChatTrigger nodes
+/// 
+/// GET /v1/models
+async fn list_models(State(state): State<WebhookAppState>, headers: HeaderMap) -> Response {
+    let mut data = Vec::new();
+    for (model, workflow_id, node_id) in state.app_state.registry.get_chat_models() {
+        let Some(workflow) = state.app_state.registry.get_workflow(&workflow_id) else { continue };
+        let Some(node) = workflow.workflow.nodes.iter().find(|node| node.id == node_id) else { continue };
+        if matches!(authorized(&state, &workflow.workflow.project, node, &headers), Ok(true)) {
+            data.push(json!({ "id": model, "object": "model", "created": 0, "owned_by": workflow_id }));
+        }
+    }
+    Json(json!({ "object": "list", "data": data })).into_response()
+}
+
+/// Run the workflow behind a model and answer as a chat completion
+/// 
+/// POST /v1/chat/completions
+/// Body: { "model": "support-bot", "messages": [{ "role": "user", "content": "Hi" }], "stream": false }
+async fn chat_completions(State(state): State<WebhookAppState>, headers: HeaderMap, body: Bytes) -> Response {
+    if let Err(shed) = state.app_state.queue.check_admission() {
+        tracing::warn!("🚦 Shedding chat completion request: {}", shed);
+        return error_response(StatusCode::TOO_MANY_REQUESTS, "rate_limit_error", shed.to_string());
+    }
+
+    let request: Value = match serde_json::from_slice(&body) {
+        Ok(request @ Value::Object(_)) => request,
+        Ok(_) => return error_response(StatusCode::BAD_REQUEST, "invalid_request_error", "request body must be a JSON object"),
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, "invalid_request_error", format!("invalid JSON body: {}", e)),
+    };
+    let Some(model) = request.get("model").and_then(|m| m.as_str()).map(|m| m.to_string()) else {
+        return error_response(StatusCode::BAD_REQUEST, "invalid_request_error", "missing 'model'");
+    };
+    let Some(messages) = request.get("messages").and_then(|m| m.as_array()).filter(|m| !m.is_empty()) else {
+        return error_response(StatusCode::BAD_REQUEST, "invalid_request_error", "'messages' must be a non-empty array");
+    };
+    let stream = request.get("stream").and_then(|s| s.as_bool()).unwrap_or(false);
+
+    // Model names should be unique; when they aren't, the highest-priority trigger serves them
+    let target = state.app_state.registry.get_chat_models().into_iter()
+        .find(|(name, _, _)| *name == model)
+        .and_then(|(_, workflow_id, node_id)| Some((state.app_state.registry.get_workflow(&workflow_id)?, node_id)));
+    let not_found = || error_response(StatusCode::NOT_FOUND, "invalid_request_error", format!("The model '{}' does not exist", model));
+    let Some((compiled_workflow, node_id)) = target else {
+        return not_found();
+    };
+    let Some(node) = compiled_workflow.workflow.nodes.iter().find(|node| node.id == node_id) else {
+        return not_found();
+    };
+    match authorized(&state, &compiled_workflow.workflow.project, node, &headers) {
+        Ok(true) => {}
+        // Unauthorized callers can't tell existing models from missing ones
+        Ok(false) if headers.contains_key(header::AUTHORIZATION) => return not_found(),
+        Ok(false) => return error_response(StatusCode::UNAUTHORIZED, "invalid_request_error", "missing API key (Authorization: Bearer ...)"),
+        Err(e) => {
+            tracing::error!("❌ ChatTrigger '{}' api_key unavailable: {}", node.id, e);
+            return error_response(StatusCode::SERVICE_UNAVAILABLE, "server_error", "model credentials unavailable");
+        }
+    }
+    let reply_field: Option<Vec<String>> = node.params.get("reply_field")
+        .and_then(|f| f.as_str())
+        .map(|f| f.split('.').map(|segment| segment.to_string()).collect());
+
+    tracing::info!("💬 Chat completion request for model '{}' ({} messages)", model, messages.len());
+    let mut item = request.clone();
+    item["last_message"] = last_user_message(messages).map(Value::String).unwrap_or(Value::Null);
+
+    let workflow_id = compiled_workflow.workflow.id.clone();
+    let mut context = ExecutionContext::from_webhook_data(workflow_id.clone(), item, compiled_workflow.workflow.project.clone());
+    context.metadata.insert("trigger_type".to_string(), json!("chat"));
+    context.metadata.insert("trigger_node_id".to_string(), json!(node_id));
+
+    let result = match state.app_state.queue.execute(&compiled_workflow, &node_id, context).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(e)) => {
+            tracing::error!("❌ Chat workflow failed: {} - Error: {}", workflow_id, e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "server_error", "the workflow behind this model failed");
+        }
+        Err(shed) => {
+            tracing::warn!("🚦 Shedding chat completion request for '{}': {}", model, shed);
+            return error_response(StatusCode::SERVICE_UNAVAILABLE, "server_error", shed.to_string());
+        }
+    };
+
+    let output = result.data.into_iter().next().unwrap_or(Value::Null);
+    let content = reply_content(&output, reply_field.as_deref());
+    let usage = output.get("usage").filter(|u| u.is_object()).cloned()
+        .unwrap_or_else(|| json!({ "prompt_tokens": 0, "completion_tokens": 0, "total_tokens": 0 }));
+    let id = format!("chatcmpl-{}", uuid::Uuid::new_v4().simple());
+    let created = chrono::Utc::now().timestamp();
+
+    if !stream {
+        return Json(json!({
+            "id": id,
+            "object": "chat.completion",
+            "created": created,
+            "model": model,
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": content },
+                "finish_reason": "stop",
+            }],
+            "usage": usage,
+        })).into_response();
+    }
+
+    // The reply is complete once the workflow finishes, so it's sent as one content chunk
+    let chunk = |delta: Value, finish_reason: Value| json!({
+        "id": id,
+        "object": "chat.completion.chunk",
+        "created": created,
+        "model": model,
+        "choices": [{ "index": 0, "delta": delta, "finish_reason": finish_reason }],
+    });
+    let events = [
+        chunk(json!({ "role": "assistant", "content": content }), Value::Null),
+        chunk(json!({}), json!("stop")),
+    ];
+    let mut sse = String::new();
+    for event in events {
+        sse.push_str(&format!("data: {}\n\n", event));
+    }
+    sse.push_str("data: [DONE]\n\n");
+    (
+        [(header::CONTENT_TYPE, "text/event-stream"), (header::CACHE_CONTROL, "no-cache")],
+        sse,
+    ).into_response()
+}
+
+/// Text of the last user message (string content or the text parts of a content array)
+fn last_user_message(messages: &[Value]) -> Option<String> {
+    let message = messages.iter().rev().find(|m| m.get("role").and_then(|r| r.as_str()) == Some("user"))?;
+    match message.get("content")? {
+        Value::String(text) => Some(text.clone()),
+        Value::Array(parts) => Some(parts.iter()
+            .filter_map(|part| part.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n")),
+        _ => None,
+    }
+}
+
+/// Assistant message text from the workflow's first output item
+fn reply_content(output: &Value, reply_field: Option<&[String]>) -> String {
+    let reply = match reply_field {
+        Some(path) => path.iter().try_fold(output, |value, segment| value.get(segment.as_str())),
+        None if output.is_string() => Some(output),
+        None => REPLY_FIELDS.iter().find_map(|field| output.get(*field).filter(|value| value.is_string())),
+    };
+    match reply.unwrap_or(output) {
+        Value::String(text) => text.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
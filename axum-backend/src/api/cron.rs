@@ -0,0 +1,103 @@
+//! Cron schedule preview endpoint
+//! 
+//! Parses a CronTrigger / PollingTrigger schedule with the same parser the
+//! scheduler and the save-time validation use, and lists its next fire times,
+//! so editors can show feedback before the workflow is saved. Calendar
+//! constraints in the body (runtime/schedule_calendar.rs) leave out the ticks
+//! the scheduler would skip.
+
+use crate::{
+    api::{workflows::AppState, ApiError},
+    project::Project,
+    runtime::schedule_calendar::ScheduleConstraints,
+    workflow::params::{parse_cron, parse_timezone},
+};
+use axum::{extract::State, routing::post, Json, Router};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+/// Fire times returned when the request doesn't set `count`
+const DEFAULT_PREVIEW_COUNT: usize = 5;
+/// Upper bound of `count`
+const MAX_PREVIEW_COUNT: usize = 100;
+/// Ticks examined at most while looking for runs the constraints allow
+const MAX_PREVIEW_SCAN: usize = 100_000;
+
+/// Body of the preview endpoint
+#[derive(Debug, Deserialize)]
+pub struct CronPreviewRequest {
+    /// Cron expression with seconds ("0 0 2 * * *")
+    pub schedule: String,
+    /// IANA timezone the schedule fires in (default UTC)
+    #[serde(default)]
+    pub timezone: Option<String>,
+    /// Number of fire times (default 5, at most 100)
+    #[serde(default)]
+    pub count: Option<usize>,
+    /// List fire times after this instant instead of now
+    #[serde(default)]
+    pub after: Option<DateTime<Utc>>,
+    /// Project whose holiday calendar "holiday_calendar" names
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Calendar constraints ("only_business_days", "business_hours", ...)
+    #[serde(flatten)]
+    pub constraints: Map<String, Value>,
+}
+
+/// Create cron preview routes
+pub fn create_cron_routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/cron/preview", post(preview_cron))
+}
+
+/// List the next fire times of a cron schedule
+/// 
+/// POST /api/cron/preview
+/// Body: { "schedule": "0 30 9 * * *", "timezone": "Europe/Berlin", "count": 3,
+///         "only_business_days": true, "holiday_calendar": "berlin", "project": "shop" }
+/// Returns: { "schedule": "0 30 9 * * *", "timezone": "Europe/Berlin",
+///            "next": ["2026-10-19T09:30:00+02:00", "2026-10-20T09:30:00+02:00", "2026-10-21T09:30:00+02:00"] }
+/// Invalid schedules, timezones and constraints get 400 validation_failed, as does a
+/// holiday calendar without "project" or one that doesn't exist.
+async fn preview_cron(
+    State(state): State<AppState>,
+    Json(request): Json<CronPreviewRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let schedule = parse_cron(&request.schedule).map_err(|e| ApiError::ValidationFailed(e.to_string()))?;
+    let timezone = parse_timezone(request.timezone.as_deref()).map_err(|e| ApiError::ValidationFailed(e.to_string()))?;
+    let count = request.count.unwrap_or(DEFAULT_PREVIEW_COUNT);
+    if count == 0 || count > MAX_PREVIEW_COUNT {
+        return Err(ApiError::ValidationFailed(format!("count must be between 1 and {}", MAX_PREVIEW_COUNT)));
+    }
+    let constraints = ScheduleConstraints::from_node_params(&Value::Object(request.constraints))
+        .map_err(|e| ApiError::ValidationFailed(e.to_string()))?;
+
+    let holidays = match &constraints.holiday_calendar {
+        Some(name) => {
+            let project = request.project.as_deref()
+                .filter(|project| Project::is_valid_slug(project))
+                .ok_or_else(|| ApiError::ValidationFailed("holiday_calendar needs a valid 'project'".to_string()))?;
+            let calendar = state.project_db_manager.calendar_get(project, name).await
+                .map_err(|e| ApiError::internal("Failed to load holiday calendar", e))?
+                .ok_or_else(|| ApiError::ValidationFailed(format!("Holiday calendar '{}' not found in project '{}'", name, project)))?;
+            Some(calendar)
+        }
+        None => None,
+    };
+
+    let after = request.after.unwrap_or_else(Utc::now).with_timezone(&timezone);
+    let next: Vec<String> = schedule.after(&after)
+        .take(MAX_PREVIEW_SCAN)
+        .filter(|time| constraints.blocked(time.with_timezone(&Utc), timezone, holidays.as_ref()).is_none())
+        .take(count)
+        .map(|time| time.to_rfc3339())
+        .collect();
+
+    Ok(Json(json!({
+        "schedule": request.schedule,
+        "timezone": timezone.name(),
+        "next": next,
+    })))
+}
@@ -0,0 +1,186 @@
+//! Step-through debugger endpoints
+//! 
+//! Opens paused debug sessions for a workflow and advances them one node at a
+//! time (or up to the next breakpoint), returning each node's input and output.
+//! Nodes execute for real against the project's databases and services.
+
+use crate::{
+    api::workflows::AppState,
+    workflow::{
+        environments::{ENVIRONMENT_HEADER, ENVIRONMENT_METADATA_KEY},
+        types::ExecutionContext,
+    },
+};
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::Json,
+    routing::{delete, get, post, put},
+    Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Request body for starting a debug session
+#[derive(Debug, Deserialize)]
+pub struct StartDebugRequest {
+    /// Entry node (defaults to the workflow's first entry point)
+    #[serde(default)]
+    pub start_node: Option<String>,
+    /// Trigger payload, becomes `[input]` like a webhook request body
+    #[serde(default)]
+    pub input: Value,
+    #[serde(default)]
+    pub query: HashMap<String, String>,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Node IDs to pause before when continuing
+    #[serde(default)]
+    pub breakpoints: Vec<String>,
+}
+
+/// Request body for replacing a session's breakpoints
+#[derive(Debug, Deserialize)]
+pub struct BreakpointsRequest {
+    pub breakpoints: Vec<String>,
+}
+
+/// Create debugger routes
+pub fn create_debug_routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/workflows/{id}/debug/start", post(start_debug_session))
+        .route("/api/debug/{session}", get(get_debug_session))
+        .route("/api/debug/{session}", delete(close_debug_session))
+        .route("/api/debug/{session}/step", post(step_debug_session))
+        .route("/api/debug/{session}/continue", post(continue_debug_session))
+        .route("/api/debug/{session}/breakpoints", put(set_breakpoints))
+}
+
+/// Start a paused debug session
+/// 
+/// POST /api/workflows/:id/debug/start
+/// Body: { "input": {...}, "breakpoints": ["write_db"], "start_node": "hook" }
+/// X-Mechaway-Environment debugs the variant of one of the workflow's environment profiles
+/// Returns: { "session_id": "...", "status": "paused", "next_node": "...", ... }
+async fn start_debug_session(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<StartDebugRequest>,
+) -> Result<Json<Value>, StatusCode> {
+    let workflow = state.registry.get_workflow(&id).ok_or(StatusCode::NOT_FOUND)?;
+
+    let start_node = match payload.start_node {
+        Some(start) => start,
+        None => workflow.start_node_ids.first().cloned().ok_or(StatusCode::BAD_REQUEST)?,
+    };
+
+    let mut context = ExecutionContext::from_webhook_data(
+        workflow.workflow.id.clone(),
+        payload.input,
+        workflow.workflow.project.clone(),
+    );
+    context.query = payload.query;
+    context.headers = payload.headers;
+    if let Some(environment) = headers.get(ENVIRONMENT_HEADER).and_then(|v| v.to_str().ok()) {
+        context.metadata.insert(ENVIRONMENT_METADATA_KEY.to_string(), json!(environment));
+    }
+    let workflow = state.debugger.engine().select_environment(&workflow, &mut context).await
+        .map_err(|e| {
+            tracing::warn!("Failed to start debug session for workflow {}: {}", id, e);
+            StatusCode::BAD_REQUEST
+        })?
+        .clone();
+
+    match state.debugger.start(workflow, &start_node, context, payload.breakpoints).await {
+        Ok(view) => Ok(Json(json!(view))),
+        Err(e) => {
+            tracing::warn!("Failed to start debug session for workflow {}: {}", id, e);
+            Err(StatusCode::BAD_REQUEST)
+        }
+    }
+}
+
+/// Get session state and step history
+/// 
+/// GET /api/debug/:session
+/// Returns: { "session": {...}, "history": [{ "node_id": "...", "input": [...], "output": [...] }] }
+async fn get_debug_session(
+    State(state): State<AppState>,
+    Path(session_id): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    let session = state.debugger.get(&session_id).await.ok_or(StatusCode::NOT_FOUND)?;
+    let session = session.lock().await;
+
+    Ok(Json(json!({
+        "session": session.view(),
+        "history": session.history(),
+    })))
+}
+
+/// Execute the next node
+/// 
+/// POST /api/debug/:session/step
+/// Returns: { "step": { "node_id": "...", "input": [...], "output": [...], "error": null }, "session": {...} }
+async fn step_debug_session(
+    State(state): State<AppState>,
+    Path(session_id): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    let session = state.debugger.get(&session_id).await.ok_or(StatusCode::NOT_FOUND)?;
+    let mut session = session.lock().await;
+
+    match session.step(state.debugger.engine()).await {
+        Ok(record) => Ok(Json(json!({ "step": record, "session": session.view() }))),
+        // The session already finished
+        Err(_) => Err(StatusCode::CONFLICT),
+    }
+}
+
+/// Execute nodes until the next breakpoint or the end of the workflow
+/// 
+/// POST /api/debug/:session/continue
+/// Returns: { "steps": [...], "session": {...} }
+async fn continue_debug_session(
+    State(state): State<AppState>,
+    Path(session_id): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    let session = state.debugger.get(&session_id).await.ok_or(StatusCode::NOT_FOUND)?;
+    let mut session = session.lock().await;
+
+    match session.continue_run(state.debugger.engine()).await {
+        Ok(records) => Ok(Json(json!({ "steps": records, "session": session.view() }))),
+        Err(_) => Err(StatusCode::CONFLICT),
+    }
+}
+
+/// Replace the session's breakpoints
+/// 
+/// PUT /api/debug/:session/breakpoints
+/// Body: { "breakpoints": ["transform", "write_db"] }
+async fn set_breakpoints(
+    State(state): State<AppState>,
+    Path(session_id): Path<String>,
+    Json(payload): Json<BreakpointsRequest>,
+) -> Result<Json<Value>, StatusCode> {
+    let session = state.debugger.get(&session_id).await.ok_or(StatusCode::NOT_FOUND)?;
+    let mut session = session.lock().await;
+    session.set_breakpoints(payload.breakpoints);
+
+    Ok(Json(json!(session.view())))
+}
+
+/// Close a debug session
+/// 
+/// DELETE /api/debug/:session
+async fn close_debug_session(
+    State(state): State<AppState>,
+    Path(session_id): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    if state.debugger.remove(&session_id).await {
+        tracing::info!("🐞 Debug session {} closed", session_id);
+        Ok(Json(json!({ "message": "Debug session closed" })))
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
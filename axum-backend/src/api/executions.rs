@@ -0,0 +1,182 @@
+//! Execution history and replay endpoints
+//! 
+//! Lists recorded executions per project and re-runs the current version of a
+//! workflow against the exact trigger payload of an earlier execution, either
+//! for real (through the execution queue) or in the isolated test sandbox.
+
+use crate::{
+    api::workflows::AppState,
+    runtime::{executor::Termination, test_runner::WorkflowTestRunner, trace::TRACE_METADATA_KEY},
+    workflow::{
+        environments::{ENVIRONMENT_HEADER, ENVIRONMENT_METADATA_KEY},
+        test_cases::NodeMock,
+    },
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{collections::HashMap, sync::Arc};
+
+/// Default and maximum page size for execution listings
+const DEFAULT_LIST_LIMIT: u32 = 50;
+const MAX_LIST_LIMIT: u32 = 500;
+
+/// Query parameters shared by execution endpoints
+#[derive(Debug, Deserialize)]
+pub struct ExecutionQuery {
+    /// Project owning the executions (default: "default")
+    #[serde(default = "default_project")]
+    pub project: String,
+    #[serde(default)]
+    pub workflow_id: Option<String>,
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+fn default_project() -> String {
+    "default".to_string()
+}
+
+/// Request body for replaying an execution
+#[derive(Debug, Default, Deserialize)]
+pub struct ReplayRequest {
+    /// Run in the isolated test sandbox instead of for real
+    #[serde(default)]
+    pub test_mode: bool,
+    /// Node mocks (test mode only)
+    #[serde(default)]
+    pub mocks: HashMap<String, NodeMock>,
+}
+
+/// Create execution history routes
+pub fn create_execution_routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/executions", get(list_executions))
+        .route("/api/executions/{id}", get(get_execution))
+        .route("/api/executions/{id}/replay", post(replay_execution))
+}
+
+/// List recent executions
+/// 
+/// GET /api/executions?project=default&workflow_id=wf-orders&limit=50
+/// Returns: { "executions": [{ "id": "...", "status": "failed", "payload": {...}, ... }] }
+async fn list_executions(
+    State(state): State<AppState>,
+    Query(query): Query<ExecutionQuery>,
+) -> Result<Json<Value>, StatusCode> {
+    let limit = query.limit.unwrap_or(DEFAULT_LIST_LIMIT).min(MAX_LIST_LIMIT);
+
+    match state.project_db_manager.list_executions(&query.project, query.workflow_id.as_deref(), limit).await {
+        Ok(executions) => Ok(Json(json!({ "executions": executions }))),
+        Err(e) => {
+            tracing::error!("Failed to list executions for project {}: {}", query.project, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Get one execution with its trigger payload
+/// 
+/// GET /api/executions/:id?project=default
+async fn get_execution(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<ExecutionQuery>,
+) -> Result<Json<Value>, StatusCode> {
+    match state.project_db_manager.get_execution(&query.project, &id).await {
+        Ok(Some(execution)) => Ok(Json(json!(execution))),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to get execution {}: {}", id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Replay an execution against the current workflow version
+/// 
+/// POST /api/executions/:id/replay?project=default
+/// Body (optional): { "test_mode": true, "mocks": { "charge": { "data": [{ "ok": true }] } } }
+/// Returns: { "replay_of": "...", "execution_id": "...", "status": "success|stopped|failed", "data": [...], "error": "..." }
+/// Successful runs add "termination", e.g. { "status": "stopped_early", "node_id": "...", "reason": "..." },
+/// and the run "trace" ({ "nodes": [...], "branches": [...] })
+/// X-Mechaway-Environment replays against one of the workflow's environment profiles
+async fn replay_execution(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<ExecutionQuery>,
+    headers: HeaderMap,
+    payload: Option<Json<ReplayRequest>>,
+) -> Result<Json<Value>, StatusCode> {
+    let request = payload.map(|Json(request)| request).unwrap_or_default();
+
+    let execution = match state.project_db_manager.get_execution(&query.project, &id).await {
+        Ok(Some(execution)) => execution,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to load execution {} for replay: {}", id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    // Replays always use the current version of the workflow
+    let workflow = state.registry.get_workflow(&execution.workflow_id).ok_or(StatusCode::NOT_FOUND)?;
+    let mut context = execution.payload.into_context(
+        workflow.workflow.id.clone(),
+        workflow.workflow.project.clone(),
+    );
+    context.metadata.insert("trigger_type".to_string(), json!("replay"));
+    context.metadata.insert("replay_of".to_string(), json!(id));
+    if let Some(environment) = headers.get(ENVIRONMENT_HEADER).and_then(|v| v.to_str().ok()) {
+        if !workflow.environments.contains_key(environment) {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        context.metadata.insert(ENVIRONMENT_METADATA_KEY.to_string(), json!(environment));
+    }
+    let execution_id = uuid::Uuid::new_v4().to_string();
+
+    tracing::info!("🔁 Replaying execution {} of workflow '{}' (test mode: {})",
+        id, execution.workflow_id, request.test_mode);
+
+    let (execution_id, outcome) = if request.test_mode {
+        context.mocks = Some(Arc::new(request.mocks));
+        let runner = WorkflowTestRunner::new(Arc::clone(&state.registry)).map_err(|e| {
+            tracing::error!("Failed to create test runner: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        // Sandbox runs are not recorded
+        (Value::Null, runner.execute_context(&workflow, &execution.start_node_id, context).await)
+    } else {
+        context.metadata.insert("execution_id".to_string(), json!(execution_id));
+        match state.queue.execute(&workflow, &execution.start_node_id, context).await {
+            Ok(result) => (json!(execution_id), result),
+            Err(_) => return Err(StatusCode::SERVICE_UNAVAILABLE),
+        }
+    };
+
+    let termination = Termination::of_run(&outcome);
+    Ok(Json(match outcome {
+        Ok(mut result) => json!({
+            "replay_of": id,
+            "execution_id": execution_id,
+            "test_mode": request.test_mode,
+            "status": termination.status(),
+            "termination": termination,
+            "trace": result.metadata.remove(TRACE_METADATA_KEY),
+            "data": result.data,
+        }),
+        Err(e) => json!({
+            "replay_of": id,
+            "execution_id": execution_id,
+            "test_mode": request.test_mode,
+            "status": "failed",
+            "error": e.to_string(),
+        }),
+    }))
+}
@@ -0,0 +1,173 @@
+//! Expression preview endpoint
+//! 
+//! Evaluates a pin expression or param template against a sample context with
+//! the executor's own pin logic, so editor previews match what nodes resolve at
+//! run time. `$secret.` pins are never resolved (they stay literals, as in
+//! input pins).
+
+use crate::{
+    api::workflows::AppState,
+    project::Project,
+    workflow::{pins::PinExpr, types::ExecutionContext},
+};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+    routing::post,
+    Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::{collections::HashMap, sync::Arc};
+
+/// How the expression is evaluated
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PreviewMode {
+    /// Input pin (`"inputs": [...]`)
+    #[default]
+    Pin,
+    /// Param template: whole-string pins keep their JSON type, `{{pin}}` interpolates
+    Template,
+}
+
+/// Sample execution context of a preview
+#[derive(Debug, Default, Deserialize)]
+pub struct SampleContext {
+    /// Data items (`$json` reads the first one)
+    #[serde(default)]
+    pub data: Vec<Value>,
+    #[serde(default)]
+    pub query: HashMap<String, String>,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Webhook path parameters (`$params.id`)
+    #[serde(default)]
+    pub params: Map<String, Value>,
+    /// Execution metadata (e.g. "memory", "device_id")
+    #[serde(default)]
+    pub metadata: Map<String, Value>,
+    /// Workflow variables; defaults to the stored variables of `workflow_id`
+    #[serde(default)]
+    pub vars: Option<Map<String, Value>>,
+    /// Output items of upstream nodes by node id (`$node("fetch").total`)
+    #[serde(default)]
+    pub node_outputs: HashMap<String, Vec<Value>>,
+}
+
+/// Body of the evaluate endpoint
+#[derive(Debug, Deserialize)]
+pub struct EvaluateRequest {
+    /// Pin expression string, or any JSON value in template mode
+    pub expression: Value,
+    #[serde(default)]
+    pub mode: PreviewMode,
+    /// Project for `$device` pins (default "default")
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Workflow whose variables `$vars` pins read
+    #[serde(default)]
+    pub workflow_id: Option<String>,
+    #[serde(default)]
+    pub context: SampleContext,
+}
+
+/// Create expression preview routes
+pub fn create_expression_routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/expressions/evaluate", post(evaluate_expression))
+}
+
+/// Evaluate an expression against a sample context
+/// 
+/// POST /api/expressions/evaluate
+/// Body: { "expression": "$json.user.name", "mode": "pin", "workflow_id": "wf-orders",
+///         "context": { "data": [{ "user": { "name": "Ada" } }], "query": {}, "headers": {}, "params": {}, "vars": {} } }
+/// Returns: { "kind": "json_path", "value": "Ada" }
+/// Errors (422): { "kind": "lua", "error": "Safe Lua execution failed: syntax error: pin:1: unexpected symbol near '+'", "position": 16 }
+async fn evaluate_expression(
+    State(state): State<AppState>,
+    Json(request): Json<EvaluateRequest>,
+) -> Result<Json<Value>, Response> {
+    let project = request.project.unwrap_or_else(|| "default".to_string());
+    if !Project::is_valid_slug(&project) {
+        return Err(error_response(StatusCode::BAD_REQUEST, json!({ "error": format!("Invalid project slug '{}'", project) })));
+    }
+
+    let vars = match (request.context.vars, &request.workflow_id) {
+        (Some(vars), _) => vars,
+        (None, Some(workflow_id)) => state.storage.get_variables(workflow_id).await.map_err(|e| {
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, json!({ "error": e.to_string() }))
+        })?,
+        (None, None) => Map::new(),
+    };
+
+    let workflow_id = request.workflow_id.unwrap_or_else(|| "preview".to_string());
+    let mut context = ExecutionContext::from_array_data(workflow_id, request.context.data, project);
+    context.query = request.context.query;
+    context.headers = request.context.headers.into_iter()
+        .map(|(name, value)| (name.to_lowercase(), value))
+        .collect();
+    context.metadata.extend(request.context.metadata);
+    if !request.context.params.is_empty() {
+        context.metadata.insert("path_params".to_string(), Value::Object(request.context.params));
+    }
+    context.vars = Arc::new(Value::Object(vars));
+    context.node_outputs = request.context.node_outputs.into_iter()
+        .map(|(node_id, items)| (node_id, Arc::new(items)))
+        .collect();
+
+    let executor = state.debugger.engine().executor();
+    let (kind, result) = match request.mode {
+        PreviewMode::Pin => {
+            let Some(expression) = request.expression.as_str() else {
+                return Err(error_response(StatusCode::BAD_REQUEST, json!({ "error": "Pin expressions must be strings" })));
+            };
+            let pin = PinExpr::parse(expression);
+            (pin.kind(), executor.preview_pin(&pin, &context))
+        }
+        PreviewMode::Template => ("template", executor.preview_template(&request.expression, &context)),
+    };
+
+    match result {
+        Ok(value) => Ok(Json(json!({ "kind": kind, "value": value }))),
+        Err(e) => {
+            let error = e.to_string();
+            let position = error_position(&request.expression, &error);
+            Err(error_response(StatusCode::UNPROCESSABLE_ENTITY, json!({ "kind": kind, "error": error, "position": position })))
+        }
+    }
+}
+
+/// Character offset of a Lua syntax error ("... near 'token'" / "near <eof>") in the expression
+/// 
+/// Templates report the offset within the first string that contains the token.
+fn error_position(expression: &Value, error: &str) -> Option<usize> {
+    let near = error.rsplit_once("near ")?.1;
+    let token = match near.strip_prefix('\'') {
+        Some(quoted) => Some(quoted.split_once('\'')?.0),
+        None if near.starts_with("<eof>") => None,
+        None => return None,
+    };
+    match (expression, token) {
+        (Value::String(text), None) => Some(text.chars().count()),
+        (Value::String(text), Some(token)) => text.find(token).map(|byte| text[..byte].chars().count()),
+        (other, Some(token)) => error_position(&Value::String(find_string(other, token)?.to_string()), error),
+        (_, None) => None,
+    }
+}
+
+/// First string in a JSON value containing `needle`
+fn find_string<'a>(value: &'a Value, needle: &str) -> Option<&'a str> {
+    match value {
+        Value::String(text) if text.contains(needle) => Some(text),
+        Value::Array(items) => items.iter().find_map(|item| find_string(item, needle)),
+        Value::Object(map) => map.values().find_map(|item| find_string(item, needle)),
+        _ => None,
+    }
+}
+
+fn error_response(status: StatusCode, body: Value) -> Response {
+    (status, Json(body)).into_response()
+}
@@ -0,0 +1,534 @@
+//! Hosted HTML forms for FormTrigger nodes
+//! 
+//! A FormTrigger node declares its fields and gets a generated form page:
+//! 
+//! { "path": "/feedback", "title": "Feedback", "submit_label": "Send",
+//!   "fields": [{ "name": "email", "type": "email", "required": true },
+//!              { "name": "rating", "type": "number", "min": 1, "max": 5 },
+//!              { "name": "topic", "type": "select", "options": ["Bug", "Idea"] },
+//!              { "name": "message", "type": "textarea", "max_length": 2000 }] }
+//! 
+//! GET /form/{workflow_id}/{path} serves the form, POST validates the
+//! submission on the server (the browser checks are only a convenience) and
+//! runs the workflow with one item of typed field values. Invalid submissions
+//! get the form back with the entered values and an error under each field.
+
+use crate::api::webhooks::WebhookAppState;
+use crate::workflow::{
+    registry::CompiledWorkflow,
+    types::{ExecutionContext, Node, NodeType},
+};
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Redirect, Response},
+    routing::get,
+    Form, Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+
+fn default_title() -> String {
+    "Form".to_string()
+}
+
+fn default_submit_label() -> String {
+    "Submit".to_string()
+}
+
+fn default_success_message() -> String {
+    "Thank you! Your response has been recorded.".to_string()
+}
+
+/// FormTrigger node params
+#[derive(Debug, Clone, Deserialize)]
+pub struct FormSpec {
+    /// Form path below /form/{workflow_id}/
+    pub path: String,
+    #[serde(default = "default_title")]
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub fields: Vec<FormField>,
+    #[serde(default = "default_submit_label")]
+    pub submit_label: String,
+    /// Shown after a successful submission (unless redirect_url is set)
+    #[serde(default = "default_success_message")]
+    pub success_message: String,
+    /// Where to send the browser after a successful submission
+    #[serde(default)]
+    pub redirect_url: Option<String>,
+}
+
+/// Input type of a form field
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    #[default]
+    Text,
+    Textarea,
+    Email,
+    Url,
+    Tel,
+    Number,
+    Date,
+    Select,
+    Radio,
+    Checkbox,
+    Hidden,
+}
+
+/// Choice of a select / radio field ("Bug" or { "value": "bug", "label": "Bug report" })
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum FieldOption {
+    Value(String),
+    Labeled { value: String, label: String },
+}
+
+impl FieldOption {
+    fn value(&self) -> &str {
+        match self {
+            FieldOption::Value(value) | FieldOption::Labeled { value, .. } => value,
+        }
+    }
+
+    fn label(&self) -> &str {
+        match self {
+            FieldOption::Value(label) | FieldOption::Labeled { label, .. } => label,
+        }
+    }
+}
+
+/// One declared form field
+#[derive(Debug, Clone, Deserialize)]
+pub struct FormField {
+    /// Key of the value in the submitted item
+    pub name: String,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default, rename = "type")]
+    pub field_type: FieldType,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    pub placeholder: Option<String>,
+    /// Hint shown under the input
+    #[serde(default)]
+    pub help: Option<String>,
+    /// Prefilled value
+    #[serde(default)]
+    pub default: Option<Value>,
+    #[serde(default)]
+    pub min_length: Option<usize>,
+    #[serde(default)]
+    pub max_length: Option<usize>,
+    /// Bounds of number fields
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+    /// Choices of select / radio fields
+    #[serde(default)]
+    pub options: Vec<FieldOption>,
+}
+
+impl FormSpec {
+    /// Parse and check a FormTrigger node's params
+    pub fn from_node_params(params: &Value) -> anyhow::Result<Self> {
+        let spec: FormSpec = serde_json::from_value(params.clone())
+            .map_err(|e| anyhow::anyhow!("invalid form definition: {}", e))?;
+        if spec.fields.is_empty() {
+            return Err(anyhow::anyhow!("form has no fields"));
+        }
+
+        let mut names = HashSet::new();
+        for field in &spec.fields {
+            if field.name.is_empty() || !field.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+                return Err(anyhow::anyhow!("invalid field name '{}' (use letters, digits, '_' and '-')", field.name));
+            }
+            if !names.insert(field.name.as_str()) {
+                return Err(anyhow::anyhow!("duplicate field '{}'", field.name));
+            }
+            if matches!(field.field_type, FieldType::Select | FieldType::Radio) && field.options.is_empty() {
+                return Err(anyhow::anyhow!("field '{}' needs 'options'", field.name));
+            }
+        }
+        Ok(spec)
+    }
+
+    /// Check a submission, returning the typed item or an error message per field
+    pub fn validate(&self, submitted: &HashMap<String, String>) -> Result<Map<String, Value>, HashMap<String, String>> {
+        let mut item = Map::new();
+        let mut errors = HashMap::new();
+        for field in &self.fields {
+            let raw = submitted.get(&field.name).map(|value| value.trim()).unwrap_or_default();
+            match field.parse(raw) {
+                Ok(value) => {
+                    item.insert(field.name.clone(), value);
+                }
+                Err(message) => {
+                    errors.insert(field.name.clone(), message);
+                }
+            }
+        }
+        if errors.is_empty() { Ok(item) } else { Err(errors) }
+    }
+
+    /// The form page, prefilled with `values` and showing `errors` under their fields
+    pub fn render(&self, values: &HashMap<String, String>, errors: &HashMap<String, String>, banner: Option<&str>) -> String {
+        let mut body = format!("<h1>{}</h1>\n", escape_html(&self.title));
+        if let Some(description) = &self.description {
+            body.push_str(&format!("<p class=\"description\">{}</p>\n", escape_html(description)));
+        }
+        if let Some(banner) = banner {
+            body.push_str(&format!("<p class=\"banner\">{}</p>\n", escape_html(banner)));
+        }
+        body.push_str("<form method=\"post\">\n");
+        for field in &self.fields {
+            // Defaults only fill a fresh form (an unchecked box is simply absent from a submission)
+            let value = match values.get(&field.name) {
+                Some(value) => value.clone(),
+                None if values.is_empty() => field.default_text(),
+                None => String::new(),
+            };
+            body.push_str(&field.render(&value, errors.get(&field.name).map(|e| e.as_str())));
+        }
+        body.push_str(&format!("<button type=\"submit\">{}</button>\n</form>\n", escape_html(&self.submit_label)));
+        page(&self.title, &body)
+    }
+
+    /// The page shown after a successful submission
+    pub fn render_success(&self) -> String {
+        page(&self.title, &format!("<h1>{}</h1>\n<p>{}</p>\n", escape_html(&self.title), escape_html(&self.success_message)))
+    }
+}
+
+impl FormField {
+    fn label(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.name)
+    }
+
+    fn default_text(&self) -> String {
+        match &self.default {
+            None | Some(Value::Null) => String::new(),
+            Some(Value::String(text)) => text.clone(),
+            Some(Value::Bool(checked)) => if *checked { "on".to_string() } else { String::new() },
+            Some(other) => other.to_string(),
+        }
+    }
+
+    /// Typed value of a (trimmed) submitted text
+    fn parse(&self, raw: &str) -> Result<Value, String> {
+        if self.field_type == FieldType::Checkbox {
+            let checked = matches!(raw, "on" | "true" | "1" | "yes");
+            if self.required && !checked {
+                return Err("This box must be checked".to_string());
+            }
+            return Ok(Value::Bool(checked));
+        }
+        if raw.is_empty() {
+            return if self.required { Err("This field is required".to_string()) } else { Ok(Value::Null) };
+        }
+
+        let length = raw.chars().count();
+        if let Some(min_length) = self.min_length.filter(|min| length < *min) {
+            return Err(format!("Enter at least {} characters", min_length));
+        }
+        if let Some(max_length) = self.max_length.filter(|max| length > *max) {
+            return Err(format!("Enter at most {} characters", max_length));
+        }
+
+        match self.field_type {
+            FieldType::Email => {
+                let valid = match raw.split_once('@') {
+                    Some((local, domain)) => !local.is_empty() && domain.contains('.') && !domain.starts_with('.')
+                        && !domain.ends_with('.') && !domain.contains('@') && !raw.contains(char::is_whitespace),
+                    None => false,
+                };
+                if !valid {
+                    return Err("Enter a valid email address".to_string());
+                }
+            }
+            FieldType::Url => {
+                let valid = reqwest::Url::parse(raw).map(|url| matches!(url.scheme(), "http" | "https")).unwrap_or(false);
+                if !valid {
+                    return Err("Enter a valid http(s) URL".to_string());
+                }
+            }
+            FieldType::Number => {
+                let number = raw.parse::<f64>().ok().filter(|n| n.is_finite())
+                    .ok_or_else(|| "Enter a number".to_string())?;
+                if let Some(min) = self.min.filter(|min| number < *min) {
+                    return Err(format!("Enter a number of at least {}", min));
+                }
+                if let Some(max) = self.max.filter(|max| number > *max) {
+                    return Err(format!("Enter a number of at most {}", max));
+                }
+                return Ok(match raw.parse::<i64>() {
+                    Ok(integer) => json!(integer),
+                    Err(_) => json!(number),
+                });
+            }
+            FieldType::Date => {
+                if chrono::NaiveDate::parse_from_str(raw, "%Y-%m-%d").is_err() {
+                    return Err("Enter a date (YYYY-MM-DD)".to_string());
+                }
+            }
+            FieldType::Select | FieldType::Radio => {
+                if !self.options.iter().any(|option| option.value() == raw) {
+                    return Err("Choose one of the options".to_string());
+                }
+            }
+            FieldType::Text | FieldType::Textarea | FieldType::Tel | FieldType::Hidden | FieldType::Checkbox => {}
+        }
+        Ok(Value::String(raw.to_string()))
+    }
+
+    /// One labeled input with its help text and error
+    fn render(&self, value: &str, error: Option<&str>) -> String {
+        let name = escape_html(&self.name);
+        let mut attributes = String::new();
+        if self.required {
+            attributes.push_str(" required");
+        }
+        if let Some(placeholder) = &self.placeholder {
+            attributes.push_str(&format!(" placeholder=\"{}\"", escape_html(placeholder)));
+        }
+        if let Some(min_length) = self.min_length {
+            attributes.push_str(&format!(" minlength=\"{}\"", min_length));
+        }
+        if let Some(max_length) = self.max_length {
+            attributes.push_str(&format!(" maxlength=\"{}\"", max_length));
+        }
+
+        let input = match self.field_type {
+            FieldType::Hidden => return format!("<input type=\"hidden\" name=\"{}\" value=\"{}\">\n", name, escape_html(value)),
+            FieldType::Textarea => format!("<textarea id=\"{0}\" name=\"{0}\" rows=\"5\"{1}>{2}</textarea>", name, attributes, escape_html(value)),
+            FieldType::Select => {
+                let mut options = String::from("<option value=\"\"></option>");
+                for option in &self.options {
+                    let selected = if option.value() == value { " selected" } else { "" };
+                    options.push_str(&format!("<option value=\"{}\"{}>{}</option>",
+                        escape_html(option.value()), selected, escape_html(option.label())));
+                }
+                format!("<select id=\"{0}\" name=\"{0}\"{1}>{2}</select>", name, attributes, options)
+            }
+            FieldType::Radio => {
+                let mut choices = String::new();
+                for option in &self.options {
+                    let checked = if option.value() == value { " checked" } else { "" };
+                    choices.push_str(&format!("<label class=\"choice\"><input type=\"radio\" name=\"{}\" value=\"{}\"{}{}> {}</label>",
+                        name, escape_html(option.value()), checked, attributes, escape_html(option.label())));
+                }
+                choices
+            }
+            FieldType::Checkbox => {
+                let checked = if matches!(value, "on" | "true" | "1" | "yes") { " checked" } else { "" };
+                format!("<label class=\"choice\"><input type=\"checkbox\" id=\"{0}\" name=\"{0}\"{1}{2}> {3}</label>",
+                    name, checked, attributes, escape_html(self.label()))
+            }
+            field_type => {
+                let input_type = match field_type {
+                    FieldType::Email => "email",
+                    FieldType::Url => "url",
+                    FieldType::Tel => "tel",
+                    FieldType::Number => "number",
+                    FieldType::Date => "date",
+                    _ => "text",
+                };
+                if field_type == FieldType::Number {
+                    attributes.push_str(" step=\"any\"");
+                    if let Some(min) = self.min {
+                        attributes.push_str(&format!(" min=\"{}\"", min));
+                    }
+                    if let Some(max) = self.max {
+                        attributes.push_str(&format!(" max=\"{}\"", max));
+                    }
+                }
+                format!("<input type=\"{0}\" id=\"{1}\" name=\"{1}\" value=\"{2}\"{3}>", input_type, name, escape_html(value), attributes)
+            }
+        };
+
+        let mut html = String::from("<div class=\"field\">\n");
+        if self.field_type != FieldType::Checkbox {
+            let marker = if self.required { " <span class=\"required\">*</span>" } else { "" };
+            html.push_str(&format!("<label for=\"{}\">{}{}</label>\n", name, escape_html(self.label()), marker));
+        }
+        html.push_str(&input);
+        html.push('\n');
+        if let Some(help) = &self.help {
+            html.push_str(&format!("<small>{}</small>\n", escape_html(help)));
+        }
+        if let Some(error) = error {
+            html.push_str(&format!("<p class=\"error\">{}</p>\n", escape_html(error)));
+        }
+        html.push_str("</div>\n");
+        html
+    }
+}
+
+/// Create hosted form routes
+pub fn create_form_routes() -> Router<WebhookAppState> {
+    Router::new()
+        // Format: /form/{workflow_id}/{form_path}
+        .route("/form/{workflow_id}/{*path}", get(show_form).post(submit_form))
+}
+
+/// Serve a FormTrigger's form
+/// 
+/// GET /form/{workflow_id}/{path}
+async fn show_form(
+    State(state): State<WebhookAppState>,
+    Path((workflow_id, form_path)): Path<(String, String)>,
+) -> Response {
+    let (_, _, spec) = match resolve_form(&state, &workflow_id, &form_path) {
+        Ok(form) => form,
+        Err(unavailable) => return unavailable.into_response(),
+    };
+    Html(spec.render(&HashMap::new(), &HashMap::new(), None)).into_response()
+}
+
+/// Validate a form submission and run the workflow with it
+/// 
+/// POST /form/{workflow_id}/{path} (application/x-www-form-urlencoded)
+async fn submit_form(
+    State(state): State<WebhookAppState>,
+    Path((workflow_id, form_path)): Path<(String, String)>,
+    request_headers: HeaderMap,
+    Form(submitted): Form<Vec<(String, String)>>,
+) -> Response {
+    let (compiled_workflow, node_id, spec) = match resolve_form(&state, &workflow_id, &form_path) {
+        Ok(form) => form,
+        Err(unavailable) => return unavailable.into_response(),
+    };
+    // Repeated names (e.g. a checkbox with a hidden fallback) keep the last value
+    let submitted: HashMap<String, String> = submitted.into_iter().collect();
+
+    if let Err(shed) = state.app_state.queue.check_admission() {
+        tracing::warn!("🚦 Shedding form submission {}/{}: {}", workflow_id, form_path, shed);
+        return (StatusCode::SERVICE_UNAVAILABLE, Html(spec.render(&submitted, &HashMap::new(),
+            Some("We're busy right now, please try again in a moment.")))).into_response();
+    }
+
+    let item = match spec.validate(&submitted) {
+        Ok(item) => item,
+        Err(errors) => {
+            tracing::debug!("📝 Form {}/{} submission has {} invalid field(s)", workflow_id, form_path, errors.len());
+            return (StatusCode::UNPROCESSABLE_ENTITY, Html(spec.render(&submitted, &errors, None))).into_response();
+        }
+    };
+
+    tracing::info!("📝 Form submission received: {}/{}", workflow_id, form_path);
+    let project_slug = compiled_workflow.workflow.project.clone();
+    let mut context = ExecutionContext::from_webhook_data(workflow_id.clone(), Value::Object(item), project_slug);
+    context.headers = request_headers.iter()
+        .filter_map(|(name, value)| Some((name.as_str().to_string(), value.to_str().ok()?.to_string())))
+        .collect();
+    context.metadata.insert("trigger_type".to_string(), json!("form"));
+    context.metadata.insert("trigger_node_id".to_string(), json!(node_id));
+
+    let failure = match state.app_state.queue.execute(&compiled_workflow, &node_id, context).await {
+        Ok(Ok(_)) => None,
+        Ok(Err(e)) => {
+            tracing::error!("❌ Form-triggered workflow failed: {} - Error: {}", workflow_id, e);
+            Some((StatusCode::UNPROCESSABLE_ENTITY, "Your response could not be processed, please try again."))
+        }
+        Err(shed) => {
+            tracing::warn!("🚦 Shedding form submission {}/{}: {}", workflow_id, form_path, shed);
+            Some((StatusCode::SERVICE_UNAVAILABLE, "We're busy right now, please try again in a moment."))
+        }
+    };
+    if let Some((status, message)) = failure {
+        return (status, Html(spec.render(&submitted, &HashMap::new(), Some(message)))).into_response();
+    }
+
+    match &spec.redirect_url {
+        Some(url) => Redirect::to(url).into_response(),
+        None => Html(spec.render_success()).into_response(),
+    }
+}
+
+/// Why a form can't be served
+enum FormUnavailable {
+    NotFound,
+    Misconfigured,
+}
+
+impl IntoResponse for FormUnavailable {
+    fn into_response(self) -> Response {
+        match self {
+            FormUnavailable::NotFound => (StatusCode::NOT_FOUND,
+                Html(page("Not found", "<h1>Not found</h1>\n<p>This form does not exist.</p>\n"))).into_response(),
+            FormUnavailable::Misconfigured => (StatusCode::INTERNAL_SERVER_ERROR,
+                Html(page("Unavailable", "<h1>Unavailable</h1>\n<p>This form is misconfigured.</p>\n"))).into_response(),
+        }
+    }
+}
+
+/// Find the workflow and its FormTrigger node serving `form_path`
+fn resolve_form(state: &WebhookAppState, workflow_id: &str, form_path: &str) -> Result<(CompiledWorkflow, String, FormSpec), FormUnavailable> {
+    let compiled_workflow = state.app_state.registry.get_workflow(workflow_id).ok_or(FormUnavailable::NotFound)?;
+    let node: &Node = compiled_workflow.workflow.nodes.iter()
+        .find(|node| matches!(node.node_type, NodeType::FormTrigger)
+            && node.params.get("path").and_then(|p| p.as_str()).map(|p| p.trim_start_matches('/')) == Some(form_path.trim_start_matches('/')))
+        .ok_or_else(|| {
+            tracing::warn!("❌ No form found for path '{}' in workflow '{}'", form_path, workflow_id);
+            FormUnavailable::NotFound
+        })?;
+    let spec = FormSpec::from_node_params(&node.params).map_err(|e| {
+        tracing::error!("❌ FormTrigger node '{}' of workflow '{}': {}", node.id, workflow_id, e);
+        FormUnavailable::Misconfigured
+    })?;
+    let node_id = node.id.clone();
+    Ok((compiled_workflow, node_id, spec))
+}
+
+/// Complete HTML document with the built-in stylesheet
+fn page(title: &str, body: &str) -> String {
+    format!(r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{}</title>
+<style>
+body {{ font-family: system-ui, sans-serif; background: #f5f5f7; color: #1d1d1f; margin: 0; padding: 2rem 1rem; }}
+main {{ max-width: 34rem; margin: 0 auto; background: #fff; border-radius: 12px; padding: 2rem; box-shadow: 0 1px 4px rgba(0,0,0,.08); }}
+h1 {{ font-size: 1.5rem; margin-top: 0; }}
+.field {{ margin-bottom: 1.25rem; }}
+label {{ display: block; font-weight: 600; margin-bottom: .35rem; }}
+label.choice {{ font-weight: normal; }}
+input, select, textarea {{ width: 100%; box-sizing: border-box; padding: .55rem .65rem; border: 1px solid #c7c7cc; border-radius: 8px; font: inherit; }}
+input[type=checkbox], input[type=radio] {{ width: auto; margin-right: .4rem; }}
+small {{ display: block; color: #6e6e73; margin-top: .3rem; }}
+.required, .error {{ color: #c9302c; }}
+.error {{ margin: .3rem 0 0; }}
+.banner {{ background: #fdecea; color: #c9302c; padding: .75rem; border-radius: 8px; }}
+button {{ background: #0a66ff; color: #fff; border: 0; border-radius: 8px; padding: .65rem 1.4rem; font: inherit; cursor: pointer; }}
+</style>
+</head>
+<body>
+<main>
+{}</main>
+</body>
+</html>
+"#, escape_html(title), body)
+}
+
+/// Escape text for HTML content and attribute values
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,224 @@
+//! GitHub / GitLab webhook triggers
+//! 
+//! A Webhook node with a "github" or "gitlab" param verifies the provider's
+//! authentication and pre-extracts the common fields:
+//! 
+//! { "path": "/repo-events", "method": "POST",
+//!   "github": { "secret": "$secret.github_webhook_secret", "events": ["push", "pull_request.opened"] } }
+//! 
+//! { "path": "/repo-events", "method": "POST",
+//!   "gitlab": { "token": "$secret.gitlab_webhook_token", "events": ["push", "merge_request"] } }
+//! 
+//! GitHub requests must carry a valid X-Hub-Signature-256 (HMAC-SHA256 of the
+//! body), GitLab requests the configured X-Gitlab-Token. Events are named
+//! "{event}" or "{event}.{action}" (GitHub X-GitHub-Event, GitLab object_kind);
+//! a filter entry without an action matches every action. Filtered-out events
+//! and GitHub pings are acknowledged with 200 without running the workflow.
+//! The payload is passed through unchanged with a "git" object added:
+//! { provider, event, action, repository, branch, tag, sha, author, delivery_id }.
+
+use axum::http::HeaderMap;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::Sha256;
+
+/// Webhook node params key "github"
+#[derive(Debug, Clone, Deserialize)]
+pub struct GitHubSettings {
+    /// Webhook secret, as a $secret. pin
+    pub secret: String,
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+/// Webhook node params key "gitlab"
+#[derive(Debug, Clone, Deserialize)]
+pub struct GitLabSettings {
+    /// Secret token, as a $secret. pin
+    pub token: String,
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+/// Provider-aware webhook settings
+#[derive(Debug, Clone)]
+pub enum GitWebhook {
+    GitHub(GitHubSettings),
+    GitLab(GitLabSettings),
+}
+
+/// Why a request was rejected (reported to the caller)
+#[derive(Debug)]
+pub enum GitRejection {
+    MissingHeader(&'static str),
+    SignatureMismatch,
+    TokenMismatch,
+}
+
+impl std::fmt::Display for GitRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GitRejection::MissingHeader(header) => write!(f, "missing {} header", header),
+            GitRejection::SignatureMismatch => write!(f, "X-Hub-Signature-256 does not match the payload"),
+            GitRejection::TokenMismatch => write!(f, "X-Gitlab-Token does not match"),
+        }
+    }
+}
+
+/// Verified event, ready to run or to acknowledge
+#[derive(Debug)]
+pub struct GitEvent {
+    /// "git" object added to the payload
+    pub git: Value,
+    /// "{event}" or "{event}.{action}"
+    pub name: String,
+    /// Whether the workflow should run (false: filtered out or GitHub ping)
+    pub accepted: bool,
+}
+
+impl GitWebhook {
+    /// Read the provider settings of a Webhook node (None for plain webhooks)
+    pub fn from_node_params(params: &Value) -> anyhow::Result<Option<Self>> {
+        let setting = |key: &str| params.get(key).filter(|v| !v.is_null()).cloned();
+        let webhook = match (setting("github"), setting("gitlab")) {
+            (None, None) => return Ok(None),
+            (Some(_), Some(_)) => return Err(anyhow::anyhow!("A webhook can't be both 'github' and 'gitlab'")),
+            (Some(github), None) => GitWebhook::GitHub(serde_json::from_value(github)
+                .map_err(|e| anyhow::anyhow!("Invalid 'github': {}", e))?),
+            (None, Some(gitlab)) => GitWebhook::GitLab(serde_json::from_value(gitlab)
+                .map_err(|e| anyhow::anyhow!("Invalid 'gitlab': {}", e))?),
+        };
+        if !webhook.secret_pin().starts_with("$secret.") {
+            return Err(anyhow::anyhow!("Webhook secrets must be $secret. pins, got '{}'", webhook.secret_pin()));
+        }
+        Ok(Some(webhook))
+    }
+
+    /// Secret pin holding the webhook secret / token
+    pub fn secret_pin(&self) -> &str {
+        match self {
+            GitWebhook::GitHub(settings) => &settings.secret,
+            GitWebhook::GitLab(settings) => &settings.token,
+        }
+    }
+
+    /// Authenticate the request and extract the event
+    pub fn verify(&self, headers: &HeaderMap, body: &[u8], payload: &Value, secret: &str) -> Result<GitEvent, GitRejection> {
+        let header = |name: &'static str| headers.get(name).and_then(|v| v.to_str().ok())
+            .ok_or(GitRejection::MissingHeader(name));
+
+        match self {
+            GitWebhook::GitHub(settings) => {
+                let signature = header("x-hub-signature-256")?;
+                let signature = signature.strip_prefix("sha256=").and_then(|hex_sig| hex::decode(hex_sig).ok())
+                    .ok_or(GitRejection::SignatureMismatch)?;
+                let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+                mac.update(body);
+                mac.verify_slice(&signature).map_err(|_| GitRejection::SignatureMismatch)?;
+
+                let event = header("x-github-event")?;
+                let delivery_id = headers.get("x-github-delivery").and_then(|v| v.to_str().ok());
+                let git = github_fields(event, delivery_id, payload);
+                let name = event_name(&git);
+                let accepted = event != "ping" && matches_filter(&settings.events, &git);
+                Ok(GitEvent { git, name, accepted })
+            }
+            GitWebhook::GitLab(settings) => {
+                let token = header("x-gitlab-token")?;
+                if !constant_time_eq(token.as_bytes(), secret.as_bytes()) {
+                    return Err(GitRejection::TokenMismatch);
+                }
+
+                let delivery_id = headers.get("x-gitlab-event-uuid").and_then(|v| v.to_str().ok());
+                let git = gitlab_fields(delivery_id, payload);
+                let name = event_name(&git);
+                let accepted = matches_filter(&settings.events, &git);
+                Ok(GitEvent { git, name, accepted })
+            }
+        }
+    }
+}
+
+/// Extracted fields of a GitHub event
+fn github_fields(event: &str, delivery_id: Option<&str>, payload: &Value) -> Value {
+    let text = |pointer: &str| payload.pointer(pointer).and_then(|v| v.as_str());
+    let git_ref = text("/ref").or_else(|| text("/pull_request/head/ref")).or_else(|| text("/check_suite/head_branch"));
+    // create/delete events send a bare ref name with ref_type
+    let (branch, tag) = match (git_ref, text("/ref_type")) {
+        (Some(name), Some("tag")) => (None, Some(name)),
+        (Some(name), Some(_)) => (Some(name), None),
+        (Some(git_ref), None) => split_ref(git_ref),
+        (None, _) => (None, None),
+    };
+    json!({
+        "provider": "github",
+        "event": event,
+        "action": text("/action"),
+        "repository": text("/repository/full_name"),
+        "branch": branch,
+        "tag": tag,
+        "sha": text("/after").or_else(|| text("/pull_request/head/sha")).or_else(|| text("/check_suite/head_sha")),
+        "author": text("/head_commit/author/username").or_else(|| text("/sender/login")),
+        "delivery_id": delivery_id,
+    })
+}
+
+/// Extracted fields of a GitLab event (event = object_kind)
+fn gitlab_fields(delivery_id: Option<&str>, payload: &Value) -> Value {
+    let text = |pointer: &str| payload.pointer(pointer).and_then(|v| v.as_str());
+    let (branch, tag) = match text("/ref") {
+        Some(git_ref) => split_ref(git_ref),
+        None => match text("/object_attributes/source_branch").or_else(|| text("/object_attributes/ref")) {
+            Some(branch) if payload.pointer("/object_attributes/tag") == Some(&Value::Bool(true)) => (None, Some(branch)),
+            branch => (branch, None),
+        },
+    };
+    json!({
+        "provider": "gitlab",
+        "event": text("/object_kind"),
+        "action": text("/object_attributes/action"),
+        "repository": text("/project/path_with_namespace"),
+        "branch": branch,
+        "tag": tag,
+        "sha": text("/checkout_sha")
+            .or_else(|| text("/object_attributes/last_commit/id"))
+            .or_else(|| text("/object_attributes/sha")),
+        "author": text("/user_username").or_else(|| text("/user/username")),
+        "delivery_id": delivery_id,
+    })
+}
+
+/// "refs/heads/main" -> branch "main", "refs/tags/v1" -> tag "v1"
+fn split_ref(git_ref: &str) -> (Option<&str>, Option<&str>) {
+    if let Some(branch) = git_ref.strip_prefix("refs/heads/") {
+        (Some(branch), None)
+    } else if let Some(tag) = git_ref.strip_prefix("refs/tags/") {
+        (None, Some(tag))
+    } else {
+        (Some(git_ref), None)
+    }
+}
+
+fn event_name(git: &Value) -> String {
+    let event = git["event"].as_str().unwrap_or("unknown");
+    match git["action"].as_str() {
+        Some(action) => format!("{}.{}", event, action),
+        None => event.to_string(),
+    }
+}
+
+/// Empty filter accepts everything; "event" matches any action, "event.action" one
+fn matches_filter(events: &[String], git: &Value) -> bool {
+    let event = git["event"].as_str().unwrap_or_default();
+    let action = git["action"].as_str();
+    events.is_empty() || events.iter().any(|entry| match entry.split_once('.') {
+        Some((entry_event, entry_action)) => entry_event == event && Some(entry_action) == action,
+        None => entry == event,
+    })
+}
+
+/// Compare secrets without leaking the position of the first difference
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,250 @@
+//! MCP (Model Context Protocol) server endpoint for MCPTrigger nodes
+//!
+//! Every MCPTrigger node publishes its workflow as a tool, so AI agents and MCP
+//! clients (Claude Desktop, IDE assistants, agent SDKs) can call workflows:
+//!
+//! { "tool": "lookup_order", "description": "Look up an order by its ID",
+//!   "arguments": { "order_id": { "type": "string", "required": true } } }
+//!
+//! POST /mcp takes JSON-RPC 2.0 messages (MCP streamable HTTP transport):
+//! initialize, ping, tools/list and tools/call. A tool call runs the workflow
+//! with the arguments as its item (also under "mcp", for `$mcp.order_id` pins)
+//! and answers with the output items as the tool result; a failed run is a
+//! result with "isError": true. Responses are JSON, or a single server-sent
+//! event for clients that only accept text/event-stream. The endpoint is
+//! stateless: no sessions, and no server-initiated stream on GET.
+
+use crate::api::{chat::authorized, webhooks::WebhookAppState};
+use crate::workflow::{
+    mcp::McpToolParams,
+    registry::CompiledWorkflow,
+    types::{ExecutionContext, Node},
+};
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::post,
+    Router,
+};
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
+
+/// Protocol revisions this server speaks, newest first
+const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// Server error (queue shedding load, tool credentials unavailable)
+const SERVER_ERROR: i64 = -32000;
+
+/// Create the MCP endpoint
+pub fn create_mcp_routes() -> Router<WebhookAppState> {
+    Router::new().route("/mcp", post(handle_message).get(no_stream))
+}
+
+/// JSON-RPC error of one request
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+}
+
+/// An active MCPTrigger node serving a tool
+struct Tool {
+    compiled: CompiledWorkflow,
+    node: Node,
+    params: McpToolParams,
+}
+
+/// GET /mcp: server-initiated streams aren't offered (the transport allows answering 405)
+async fn no_stream() -> Response {
+    (StatusCode::METHOD_NOT_ALLOWED, [(header::ALLOW, "POST")]).into_response()
+}
+
+/// Handle one JSON-RPC message
+///
+/// POST /mcp
+/// Body: { "jsonrpc": "2.0", "id": 1, "method": "tools/call", "params": { "name": "lookup_order", "arguments": { "order_id": "A-17" } } }
+async fn handle_message(State(state): State<WebhookAppState>, headers: HeaderMap, body: Bytes) -> Response {
+    let message: Value = match serde_json::from_slice(&body) {
+        Ok(message) => message,
+        Err(e) => return rpc_response(&headers, StatusCode::BAD_REQUEST, error_message(Value::Null, RpcError::new(PARSE_ERROR, format!("invalid JSON: {}", e)))),
+    };
+    let Some(method) = message.get("method").and_then(|m| m.as_str()).filter(|_| message.get("jsonrpc") == Some(&json!("2.0"))) else {
+        // Responses from the client only answer server requests, which this server never sends
+        if message.get("result").is_some() || message.get("error").is_some() {
+            return StatusCode::ACCEPTED.into_response();
+        }
+        let id = message.get("id").cloned().unwrap_or(Value::Null);
+        return rpc_response(&headers, StatusCode::BAD_REQUEST, error_message(id, RpcError::new(INVALID_REQUEST, "expected a JSON-RPC 2.0 request object (batches are not supported)")));
+    };
+    // Notifications (notifications/initialized, notifications/cancelled, ...) get no response
+    let Some(id) = message.get("id").cloned() else {
+        return StatusCode::ACCEPTED.into_response();
+    };
+    let params = message.get("params").cloned().unwrap_or_else(|| json!({}));
+
+    let result = match method {
+        "initialize" => Ok(initialize(&params)),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(list_tools(&state, &headers)),
+        "tools/call" => call_tool(&state, &headers, &params).await,
+        other => Err(RpcError::new(METHOD_NOT_FOUND, format!("method '{}' not found", other))),
+    };
+    let message = match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => error_message(id, error),
+    };
+    rpc_response(&headers, StatusCode::OK, message)
+}
+
+fn error_message(id: Value, error: RpcError) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": error.code, "message": error.message } })
+}
+
+/// JSON response, or one `message` event for clients accepting only text/event-stream
+fn rpc_response(headers: &HeaderMap, status: StatusCode, message: Value) -> Response {
+    let accept = headers.get(header::ACCEPT).and_then(|a| a.to_str().ok()).unwrap_or_default();
+    if accept.contains("text/event-stream") && !accept.contains("application/json") {
+        return (
+            status,
+            [(header::CONTENT_TYPE, "text/event-stream"), (header::CACHE_CONTROL, "no-cache")],
+            format!("event: message\ndata: {}\n\n", message),
+        ).into_response();
+    }
+    (status, Json(message)).into_response()
+}
+
+/// Answer the handshake with the client's protocol revision when supported, else the newest
+fn initialize(params: &Value) -> Value {
+    let requested = params.get("protocolVersion").and_then(|v| v.as_str());
+    let version = requested
+        .filter(|requested| PROTOCOL_VERSIONS.contains(requested))
+        .unwrap_or(PROTOCOL_VERSIONS[0]);
+    json!({
+        "protocolVersion": version,
+        "capabilities": { "tools": { "listChanged": false } },
+        "serverInfo": { "name": "mechaway", "version": env!("CARGO_PKG_VERSION") },
+        "instructions": "Each tool runs a Mechaway workflow with the tool arguments as its input.",
+    })
+}
+
+/// Tools serving each name (the highest-priority trigger of a name serves it)
+fn serving_tools(state: &WebhookAppState) -> Vec<Tool> {
+    let registry = &state.app_state.registry;
+    let mut seen = HashSet::new();
+    registry.get_mcp_tools().into_iter()
+        .filter(|(tool, _, _)| seen.insert(tool.clone()))
+        .filter_map(|(_, workflow_id, node_id)| {
+            let compiled = registry.get_workflow(&workflow_id)?;
+            let node = compiled.workflow.nodes.iter().find(|node| node.id == node_id)?.clone();
+            match McpToolParams::from_node_params(&node.params) {
+                Ok(params) => Some(Tool { compiled, node, params }),
+                Err(e) => {
+                    tracing::warn!("⚠️ MCPTrigger '{}' of workflow '{}' not served: {}", node_id, workflow_id, e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// tools/list: the tools the caller's key can use
+fn list_tools(state: &WebhookAppState, headers: &HeaderMap) -> Value {
+    let tools: Vec<Value> = serving_tools(state).into_iter()
+        .filter(|tool| matches!(authorized(state, &tool.compiled.workflow.project, &tool.node, headers), Ok(true)))
+        .map(|tool| json!({
+            "name": tool.params.tool,
+            "description": tool.params.description.clone().unwrap_or_else(|| tool.compiled.workflow.name.clone()),
+            "inputSchema": tool.params.input_schema(&tool.compiled.workflow, &tool.node.id),
+        }))
+        .collect();
+    json!({ "tools": tools })
+}
+
+/// tools/call: run the workflow behind a tool
+async fn call_tool(state: &WebhookAppState, headers: &HeaderMap, params: &Value) -> Result<Value, RpcError> {
+    let name = params.get("name").and_then(|n| n.as_str())
+        .ok_or_else(|| RpcError::new(INVALID_PARAMS, "missing tool 'name'"))?;
+    let arguments = match params.get("arguments") {
+        None | Some(Value::Null) => Map::new(),
+        Some(Value::Object(arguments)) => arguments.clone(),
+        Some(_) => return Err(RpcError::new(INVALID_PARAMS, "'arguments' must be an object")),
+    };
+
+    let unknown = || RpcError::new(INVALID_PARAMS, format!("Unknown tool: {}", name));
+    let tool = serving_tools(state).into_iter()
+        .find(|tool| tool.params.tool == name)
+        .ok_or_else(unknown)?;
+    let workflow = &tool.compiled.workflow;
+    match authorized(state, &workflow.project, &tool.node, headers) {
+        Ok(true) => {}
+        // Unauthorized callers can't tell existing tools from missing ones
+        Ok(false) if headers.contains_key(header::AUTHORIZATION) => return Err(unknown()),
+        Ok(false) => return Err(RpcError::new(INVALID_PARAMS, "missing API key (Authorization: Bearer ...)")),
+        Err(e) => {
+            tracing::error!("❌ MCPTrigger '{}' api_key unavailable: {}", tool.node.id, e);
+            return Err(RpcError::new(SERVER_ERROR, "tool credentials unavailable"));
+        }
+    }
+
+    let schema = tool.params.input_schema(workflow, &tool.node.id);
+    let missing: Vec<&str> = schema.get("required").and_then(|r| r.as_array()).into_iter().flatten()
+        .filter_map(|required| required.as_str())
+        .filter(|required| !arguments.contains_key(*required))
+        .collect();
+    if !missing.is_empty() {
+        return Err(RpcError::new(INVALID_PARAMS, format!("missing required argument(s): {}", missing.join(", "))));
+    }
+
+    state.app_state.queue.check_admission()
+        .map_err(|shed| RpcError::new(SERVER_ERROR, shed.to_string()))?;
+
+    tracing::info!("🧰 MCP tool call '{}' -> workflow '{}'", name, workflow.id);
+    let mut item = Value::Object(arguments.clone());
+    item["mcp"] = Value::Object(arguments);
+    let mut context = ExecutionContext::from_webhook_data(workflow.id.clone(), item, workflow.project.clone());
+    context.metadata.insert("trigger_type".to_string(), json!("mcp"));
+    context.metadata.insert("trigger_node_id".to_string(), json!(tool.node.id));
+    context.metadata.insert("mcp_tool".to_string(), json!(name));
+
+    match state.app_state.queue.execute(&tool.compiled, &tool.node.id, context).await {
+        Ok(Ok(result)) => Ok(tool_result(result.data)),
+        Ok(Err(e)) => {
+            tracing::error!("❌ MCP tool workflow failed: {} - Error: {}", workflow.id, e);
+            Ok(json!({ "content": [{ "type": "text", "text": format!("Workflow failed: {}", e) }], "isError": true }))
+        }
+        Err(shed) => {
+            tracing::warn!("🚦 Shedding MCP tool call '{}': {}", name, shed);
+            Err(RpcError::new(SERVER_ERROR, shed.to_string()))
+        }
+    }
+}
+
+/// Tool result from the output items: a single string item as text, anything else as JSON
+/// (a single object item is also returned as structured content)
+fn tool_result(mut data: Vec<Value>) -> Value {
+    let output = match data.len() {
+        1 => data.remove(0),
+        _ => Value::Array(data),
+    };
+    let text = match &output {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+    let mut result = json!({ "content": [{ "type": "text", "text": text }], "isError": false });
+    if output.is_object() {
+        result["structuredContent"] = output;
+    }
+    result
+}
@@ -0,0 +1,30 @@
+//! Runtime metrics endpoints
+//! 
+//! Exposes in-memory engine counters (execution queue depth, shed requests,
+//! SQLite maintenance, external PostgreSQL pools, scheduler dispatch latency) as JSON for operators and external scrapers.
+
+use crate::api::workflows::AppState;
+use axum::{extract::State, response::Json, routing::get, Router};
+use serde_json::{json, Value};
+
+/// Create runtime metrics routes
+pub fn create_metrics_routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/metrics", get(get_metrics))
+}
+
+/// Get runtime metrics
+/// 
+/// GET /api/metrics
+/// Returns: { "execution_queue": { "running": 3, "queued": 0, "shed_throttled_total": 12, ... },
+///            "db_maintenance": { "runs_total": 4, "bytes_reclaimed_total": 10485760, "databases": {...} },
+///            "external_pg_pools": { "pools": 1, "max_pools": 32, "by_pool": [{ "key": "pg-3f2a...", "in_use": 2, ... }] },
+///            "scheduler": { "spread_secs": 30, "dispatched_total": 120, "avg_latency_ms": 4, "max_latency_ms": 85, ... } }
+async fn get_metrics(State(state): State<AppState>) -> Json<Value> {
+    Json(json!({
+        "execution_queue": state.queue.stats(),
+        "db_maintenance": state.maintenance.stats(),
+        "external_pg_pools": state.project_db_manager.pg_pools().stats(),
+        "scheduler": state.scheduler.dispatch_stats(),
+    }))
+}
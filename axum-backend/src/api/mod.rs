@@ -1,10 +1,13 @@
-/// HTTP API Layer
-/// 
-/// This module provides the REST API endpoints for workflow management
-/// and dynamic webhook execution. It handles:
-/// - Workflow CRUD operations
-/// - Dynamic webhook route registration  
-/// - Execution triggering and response handling
+//! HTTP API Layer
+//! 
+//! This module provides the REST API endpoints for workflow management
+//! and dynamic webhook execution. It handles:
+//! - Workflow CRUD operations
+//! - Dynamic webhook route registration  
+//! - Execution triggering and response handling
+
+// Structured API errors (codes, IntoResponse)
+pub mod error;
 
 // Workflow management endpoints (POST/GET/PUT/DELETE)
 pub mod workflows;
@@ -12,6 +15,76 @@ pub mod workflows;
 // Dynamic webhook execution endpoints
 pub mod webhooks;
 
+// Timestamp + nonce replay protection for webhook triggers
+pub mod replay_protection;
+
+// Stripe signature verification and event typing for webhook triggers
+pub mod stripe_webhook;
+
+// GitHub / GitLab authentication and event extraction for webhook triggers
+pub mod git_webhooks;
+
+// Hosted HTML forms for FormTrigger nodes
+pub mod forms;
+
+// OpenAI-compatible chat completions for ChatTrigger nodes
+pub mod chat;
+
+// WebSocket endpoints for WebSocketTrigger nodes
+pub mod websocket;
+
+// MCP server endpoint (tools) for MCPTrigger nodes
+pub mod mcp;
+
+// Project settings endpoints
+pub mod projects;
+
+// Runtime metrics endpoints
+pub mod metrics;
+
+// Step-through debugger endpoints
+pub mod debug;
+
+// Execution history and replay endpoints
+pub mod executions;
+
+// Administrative endpoints (log level)
+pub mod admin;
+
+// Execution profiling endpoints
+pub mod stats;
+
+// OAuth2 authorization-code credential endpoints
+pub mod oauth;
+
+// Project secret endpoints (values behind $secret. pins)
+pub mod secrets;
+
+// Workflow template gallery and instantiation endpoints
+pub mod templates;
+
+// Expression preview endpoint (pins and param templates)
+pub mod expressions;
+
+// Cron schedule preview endpoint
+pub mod cron;
+
 // Re-export router builders
+pub use error::ApiError;
 pub use workflows::create_workflow_routes;
 pub use webhooks::create_webhook_routes;
+pub use forms::create_form_routes;
+pub use chat::create_chat_routes;
+pub use websocket::create_websocket_routes;
+pub use mcp::create_mcp_routes;
+pub use projects::create_project_routes;
+pub use metrics::create_metrics_routes;
+pub use debug::create_debug_routes;
+pub use executions::create_execution_routes;
+pub use admin::create_admin_routes;
+pub use stats::create_stats_routes;
+pub use oauth::create_oauth_routes;
+pub use secrets::create_secret_routes;
+pub use templates::create_template_routes;
+pub use expressions::create_expression_routes;
+pub use cron::create_cron_routes;
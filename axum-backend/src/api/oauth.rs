@@ -0,0 +1,153 @@
+//! OAuth2 credential endpoints
+//! 
+//! Registers authorization-code credentials per project, starts the
+//! authorization flow and receives the provider redirect. Tokens stay on the
+//! server (sealed with MECHAWAY_MASTER_KEY); HTTPClient nodes use them via the
+//! "oauth" param.
+
+use crate::{
+    api::workflows::AppState,
+    project::{
+        crypto::MasterKeyMissing,
+        oauth::{OAuthCredentialConfig, OAuthError},
+    },
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+    routing::{get, post, put},
+    Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+/// Query parameters of the provider redirect
+#[derive(Debug, Deserialize)]
+pub struct CallbackQuery {
+    #[serde(default)]
+    pub code: Option<String>,
+    #[serde(default)]
+    pub state: Option<String>,
+    /// Set by the provider when the user denied access or the request was invalid
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub error_description: Option<String>,
+}
+
+/// Route of the provider redirect (a GET that stores the tokens)
+pub const OAUTH_CALLBACK_ROUTE: &str = "/api/projects/{slug}/oauth/callback";
+
+/// Create OAuth credential routes
+pub fn create_oauth_routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/projects/{slug}/oauth", get(list_credentials))
+        .route(OAUTH_CALLBACK_ROUTE, get(oauth_callback))
+        .route("/api/projects/{slug}/oauth/{name}", put(put_credential).delete(delete_credential))
+        .route("/api/projects/{slug}/oauth/{name}/authorize", post(authorize_credential))
+}
+
+/// List OAuth credentials and their authorization status (no secrets)
+/// 
+/// GET /api/projects/{slug}/oauth
+/// Returns: { "credentials": [{ "name": "google", "authorized": true, "expires_at": "...", ... }] }
+async fn list_credentials(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<Json<serde_json::Value>, Response> {
+    match state.project_db_manager.oauth_list(&slug).await {
+        Ok(credentials) => Ok(Json(json!({ "credentials": credentials }))),
+        Err(e) => Err(oauth_error_response(&slug, e)),
+    }
+}
+
+/// Create or update an OAuth credential
+/// 
+/// PUT /api/projects/{slug}/oauth/{name}
+/// Body: { "auth_url": "...", "token_url": "...", "client_id": "...", "client_secret": "...",
+///         "scopes": ["..."], "redirect_uri": "https://host/api/projects/{slug}/oauth/callback" }
+/// Returns: { "name": "google", "updated": true }
+async fn put_credential(
+    State(state): State<AppState>,
+    Path((slug, name)): Path<(String, String)>,
+    Json(config): Json<OAuthCredentialConfig>,
+) -> Result<Json<serde_json::Value>, Response> {
+    match state.project_db_manager.oauth_upsert(&slug, &name, &config).await {
+        Ok(()) => {
+            tracing::info!("🔑 OAuth credential '{}' saved for project {}", name, slug);
+            Ok(Json(json!({ "name": name, "updated": true })))
+        }
+        Err(e) => Err(oauth_error_response(&slug, e)),
+    }
+}
+
+/// Delete an OAuth credential and its tokens
+/// 
+/// DELETE /api/projects/{slug}/oauth/{name}
+/// Returns: { "name": "google", "deleted": true }
+async fn delete_credential(
+    State(state): State<AppState>,
+    Path((slug, name)): Path<(String, String)>,
+) -> Result<Json<serde_json::Value>, Response> {
+    match state.project_db_manager.oauth_delete(&slug, &name).await {
+        Ok(true) => Ok(Json(json!({ "name": name, "deleted": true }))),
+        Ok(false) => Err(StatusCode::NOT_FOUND.into_response()),
+        Err(e) => Err(oauth_error_response(&slug, e)),
+    }
+}
+
+/// Start the authorization flow
+/// 
+/// POST /api/projects/{slug}/oauth/{name}/authorize
+/// Returns: { "authorization_url": "https://provider/authorize?...&state=..." } (open it in a browser)
+async fn authorize_credential(
+    State(state): State<AppState>,
+    Path((slug, name)): Path<(String, String)>,
+) -> Result<Json<serde_json::Value>, Response> {
+    match state.project_db_manager.oauth_begin(&slug, &name).await {
+        Ok(url) => Ok(Json(json!({ "authorization_url": url }))),
+        Err(e) => Err(oauth_error_response(&slug, e)),
+    }
+}
+
+/// Receive the provider redirect and exchange the code for tokens
+/// 
+/// GET /api/projects/{slug}/oauth/callback?code=...&state=...
+/// Returns: { "name": "google", "authorized": true }
+async fn oauth_callback(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    Query(query): Query<CallbackQuery>,
+) -> Result<Json<serde_json::Value>, Response> {
+    if let Some(error) = query.error {
+        tracing::warn!("❌ OAuth authorization denied for project {}: {}", slug, error);
+        return Err((StatusCode::BAD_REQUEST, Json(json!({
+            "error": error,
+            "error_description": query.error_description,
+        }))).into_response());
+    }
+    let (Some(code), Some(oauth_state)) = (query.code, query.state) else {
+        return Err((StatusCode::BAD_REQUEST, Json(json!({ "error": "Missing code or state" }))).into_response());
+    };
+
+    match state.project_db_manager.oauth_complete(&slug, &code, &oauth_state).await {
+        Ok(name) => Ok(Json(json!({ "name": name, "authorized": true }))),
+        Err(e) => Err(oauth_error_response(&slug, e)),
+    }
+}
+
+/// Map OAuth failures to 404 / 422 / 502, and 503 when no master key is configured
+fn oauth_error_response(slug: &str, error: anyhow::Error) -> Response {
+    let status = match error.downcast_ref::<OAuthError>() {
+        Some(OAuthError::NotFound(_)) => StatusCode::NOT_FOUND,
+        Some(OAuthError::Invalid(_)) => StatusCode::UNPROCESSABLE_ENTITY,
+        Some(OAuthError::Provider(_)) => StatusCode::BAD_GATEWAY,
+        None if error.is::<MasterKeyMissing>() => StatusCode::SERVICE_UNAVAILABLE,
+        None => {
+            tracing::error!("Failed OAuth operation for project {}: {}", slug, error);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    (status, Json(json!({ "error": error.to_string() }))).into_response()
+}
@@ -0,0 +1,784 @@
+//! Project management REST API endpoints
+//! 
+//! Exposes project-scoped settings such as the cross-project caller allowlist,
+//! failure alert rules, retention limits, the default environment, simpletable storage and GeoIP databases, the generated OpenAPI
+//! document for the project's webhook endpoints, the workflow <-> table lineage graph, the state of its MQTT / WebSocket devices, its store-and-forward outbox
+//! the feature flags read by FeatureFlag nodes (feature_flags table) and the holiday
+//! calendars of scheduled triggers (holiday_calendars table).
+//! Settings are stored per project in {slug}/project.db (project_metadata table).
+
+use crate::{
+    api::workflows::AppState,
+    project::{calendars::{is_calendar_name, HolidayCalendar}, flags::FeatureFlag, simpletable_pg::SimpleTableSettings, Project},
+    runtime::{alerts::AlertingSettings, feature_flag::is_flag_name, geoip::GeoIpSettings, janitor::RetentionSettings},
+    workflow::{environments::project_environment, lineage::project_lineage, openapi::project_openapi},
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    routing::{delete, get, post},
+    Router,
+};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Create project management routes
+pub fn create_project_routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/projects/{slug}/settings", get(get_project_settings).put(update_project_settings))
+        .route("/api/projects/{slug}/alerting", get(get_project_alerting).put(update_project_alerting))
+        .route("/api/projects/{slug}/retention", get(get_project_retention).put(update_project_retention))
+        .route("/api/projects/{slug}/retention/run", post(run_project_retention))
+        .route("/api/projects/{slug}/environment", get(get_project_environment).put(update_project_environment))
+        .route("/api/projects/{slug}/simpletable", get(get_project_simpletable).put(update_project_simpletable))
+        .route("/api/projects/{slug}/geoip", get(get_project_geoip).put(update_project_geoip))
+        .route("/api/projects/{slug}/openapi.json", get(get_project_openapi))
+        .route("/api/projects/{slug}/lineage", get(get_project_lineage))
+        .route("/api/projects/{slug}/devices", get(get_project_devices))
+        .route("/api/projects/{slug}/outbox", get(get_project_outbox))
+        .route("/api/projects/{slug}/outbox/flush", post(flush_project_outbox))
+        .route("/api/projects/{slug}/outbox/{id}", delete(delete_outbox_entry))
+        .route("/api/projects/{slug}/outbox/{id}/retry", post(retry_outbox_entry))
+        .route("/api/projects/{slug}/flags", get(list_feature_flags))
+        .route("/api/projects/{slug}/flags/{name}", get(get_feature_flag).put(update_feature_flag).delete(delete_feature_flag))
+        .route("/api/projects/{slug}/flags/{name}/evaluate", post(evaluate_feature_flag))
+        .route("/api/projects/{slug}/calendars", get(list_holiday_calendars))
+        .route("/api/projects/{slug}/calendars/{name}", get(get_holiday_calendar).put(update_holiday_calendar).delete(delete_holiday_calendar))
+}
+
+/// Get project settings
+/// 
+/// GET /api/projects/{slug}/settings
+/// Returns: { "allowed_callers": ["ecommerce"], ... } (simpletable connection string redacted)
+async fn get_project_settings(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    if !Project::is_valid_slug(&slug) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match state.project_db_manager.get_project_settings(&slug).await {
+        Ok(mut settings) => {
+            SimpleTableSettings::redact_project_settings(&mut settings);
+            Ok(Json(settings))
+        }
+        Err(e) => {
+            tracing::error!("Failed to get settings for project {}: {}", slug, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Replace project settings
+/// 
+/// PUT /api/projects/{slug}/settings
+/// Body: { "allowed_callers": ["ecommerce", "analytics"] }
+/// A redacted simpletable connection string keeps the stored one.
+async fn update_project_settings(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    Json(mut settings): Json<Value>,
+) -> Result<Json<Value>, StatusCode> {
+    if !Project::is_valid_slug(&slug) || !settings.is_object() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let stored = state.project_db_manager.get_project_settings(&slug).await.map_err(|e| {
+        tracing::error!("Failed to get settings for project {}: {}", slug, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    SimpleTableSettings::restore_project_settings(&mut settings, &stored);
+
+    if let Err(e) = state.project_db_manager.save_project_settings(&slug, &settings).await {
+        tracing::error!("Failed to save settings for project {}: {}", slug, e);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    tracing::info!("⚙️ Updated settings for project: {}", slug);
+
+    SimpleTableSettings::redact_project_settings(&mut settings);
+    Ok(Json(settings))
+}
+
+/// Get failure alert rules
+/// 
+/// GET /api/projects/{slug}/alerting
+/// Returns: { "rules": [{ "name": "orders-broken", "threshold": 5, "window_secs": 300, "targets": [...] }] }
+async fn get_project_alerting(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    if !Project::is_valid_slug(&slug) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match state.project_db_manager.get_project_settings(&slug).await {
+        Ok(settings) => Ok(Json(json!(AlertingSettings::from_project_settings(&settings)))),
+        Err(e) => {
+            tracing::error!("Failed to get alerting for project {}: {}", slug, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Replace failure alert rules (stored under "alerting" in the project settings)
+/// 
+/// PUT /api/projects/{slug}/alerting
+/// Body: { "rules": [{ "name": "orders-broken", "workflow_id": "wf-orders", "threshold": 5, "window_secs": 300,
+///                     "targets": [{ "type": "webhook", "url": "https://ops.example.com/hook" },
+///                                 { "type": "slack", "webhook_url": "https://hooks.slack.com/..." },
+///                                 { "type": "email", "to": ["ops@example.com"] }] }] }
+async fn update_project_alerting(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    Json(alerting): Json<AlertingSettings>,
+) -> Result<Json<Value>, StatusCode> {
+    if !Project::is_valid_slug(&slug) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if let Err(e) = alerting.validate() {
+        tracing::warn!("❌ Invalid alerting rules for project {}: {}", slug, e);
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let mut settings = state.project_db_manager.get_project_settings(&slug).await.map_err(|e| {
+        tracing::error!("Failed to get settings for project {}: {}", slug, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    settings["alerting"] = json!(alerting);
+
+    if let Err(e) = state.project_db_manager.save_project_settings(&slug, &settings).await {
+        tracing::error!("Failed to save alerting for project {}: {}", slug, e);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    tracing::info!("🚨 Updated {} alert rule(s) for project: {}", alerting.rules.len(), slug);
+
+    Ok(Json(json!(alerting)))
+}
+
+/// Get retention limits
+/// 
+/// GET /api/projects/{slug}/retention
+/// Returns: { "execution_days": 30, "upload_days": 7, "upload_max_bytes": 1073741824, "dead_letter_days": 14 }
+async fn get_project_retention(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    if !Project::is_valid_slug(&slug) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match state.project_db_manager.get_project_settings(&slug).await {
+        Ok(settings) => Ok(Json(json!(RetentionSettings::from_project_settings(&settings)))),
+        Err(e) => {
+            tracing::error!("Failed to get retention for project {}: {}", slug, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Replace retention limits (stored under "retention" in the project settings)
+/// 
+/// PUT /api/projects/{slug}/retention
+/// Body: { "execution_days": 30, "upload_days": 7, "upload_max_bytes": 1073741824, "dead_letter_days": 14 }
+/// Omitted fields keep that data indefinitely.
+async fn update_project_retention(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    Json(retention): Json<RetentionSettings>,
+) -> Result<Json<Value>, StatusCode> {
+    if !Project::is_valid_slug(&slug) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if let Err(e) = retention.validate() {
+        tracing::warn!("❌ Invalid retention settings for project {}: {}", slug, e);
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let mut settings = state.project_db_manager.get_project_settings(&slug).await.map_err(|e| {
+        tracing::error!("Failed to get settings for project {}: {}", slug, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    settings["retention"] = json!(retention);
+
+    if let Err(e) = state.project_db_manager.save_project_settings(&slug, &settings).await {
+        tracing::error!("Failed to save retention for project {}: {}", slug, e);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    tracing::info!("🧹 Updated retention settings for project: {}", slug);
+
+    Ok(Json(json!(retention)))
+}
+
+/// Get the project's default environment
+/// 
+/// GET /api/projects/{slug}/environment
+/// Returns: { "environment": "staging" } (null when runs use the plain workflow definitions)
+async fn get_project_environment(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    if !Project::is_valid_slug(&slug) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match state.project_db_manager.get_project_settings(&slug).await {
+        Ok(settings) => Ok(Json(json!({ "environment": project_environment(&settings) }))),
+        Err(e) => {
+            tracing::error!("Failed to get environment for project {}: {}", slug, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Set the project's default environment (stored under "environment" in the project settings)
+/// 
+/// PUT /api/projects/{slug}/environment
+/// Body: { "environment": "staging" } or { "environment": null }
+/// Workflows with a profile of that name run its variant; the others run unchanged.
+async fn update_project_environment(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    Json(body): Json<Value>,
+) -> Result<Json<Value>, StatusCode> {
+    if !Project::is_valid_slug(&slug) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let environment = match body.get("environment") {
+        None | Some(Value::Null) => None,
+        Some(Value::String(name)) if Project::is_valid_slug(name) => Some(name.clone()),
+        Some(_) => return Err(StatusCode::UNPROCESSABLE_ENTITY),
+    };
+
+    let mut settings = state.project_db_manager.get_project_settings(&slug).await.map_err(|e| {
+        tracing::error!("Failed to get settings for project {}: {}", slug, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    match &environment {
+        Some(name) => settings["environment"] = json!(name),
+        None => {
+            if let Some(settings) = settings.as_object_mut() {
+                settings.remove("environment");
+            }
+        }
+    }
+
+    if let Err(e) = state.project_db_manager.save_project_settings(&slug, &settings).await {
+        tracing::error!("Failed to save environment for project {}: {}", slug, e);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    tracing::info!("🌐 Project {} now runs in environment {:?}", slug, environment);
+
+    Ok(Json(json!({ "environment": environment })))
+}
+
+/// Apply the retention limits now instead of waiting for the janitor
+/// 
+/// POST /api/projects/{slug}/retention/run
+/// Returns: { "project": "default", "executions_deleted": 120, "kv_expired_deleted": 4,
+///            "uploads_deleted": 2, "upload_bytes_freed": 52311, "dead_letters_deleted": 3, "vacuumed": true }
+async fn run_project_retention(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    if !Project::is_valid_slug(&slug) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match state.janitor.run_project(&slug).await {
+        Ok(report) => Ok(Json(json!(report))),
+        Err(e) => {
+            tracing::error!("Retention run failed for project {}: {}", slug, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Get simpletable storage backend
+/// 
+/// GET /api/projects/{slug}/simpletable
+/// Returns: { "backend": "sqlite" } or { "backend": "postgres", "connection_string": "$secret.simpletable_dsn", "schema": "mway_shop" }
+/// (plaintext connection strings come back as "[redacted]")
+async fn get_project_simpletable(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    if !Project::is_valid_slug(&slug) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match state.project_db_manager.get_project_settings(&slug).await {
+        Ok(settings) => Ok(Json(json!(SimpleTableSettings::from_project_settings(&settings).redacted()))),
+        Err(e) => {
+            tracing::error!("Failed to get simpletable settings for project {}: {}", slug, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Switch simpletable storage (stored under "simpletable" in the project settings)
+/// 
+/// PUT /api/projects/{slug}/simpletable
+/// Body: { "backend": "postgres", "connection_string": "$secret.simpletable_dsn", "schema": "mway_shop" }
+/// A plaintext DSN ("postgres://user:pass@db:5432/app") is accepted too; "[redacted]" keeps the stored one.
+/// Existing rows are not migrated between backends.
+async fn update_project_simpletable(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    Json(mut simpletable): Json<SimpleTableSettings>,
+) -> Result<Json<Value>, StatusCode> {
+    if !Project::is_valid_slug(&slug) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut settings = state.project_db_manager.get_project_settings(&slug).await.map_err(|e| {
+        tracing::error!("Failed to get settings for project {}: {}", slug, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    simpletable.restore_redacted(&SimpleTableSettings::from_project_settings(&settings));
+    if let Err(e) = simpletable.validate() {
+        tracing::warn!("❌ Invalid simpletable settings for project {}: {}", slug, e);
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+    settings["simpletable"] = json!(simpletable);
+
+    if let Err(e) = state.project_db_manager.save_project_settings(&slug, &settings).await {
+        tracing::error!("Failed to save simpletable settings for project {}: {}", slug, e);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    tracing::info!("🗄️ Simpletable backend for project {} set to {:?}", slug, simpletable.backend);
+
+    Ok(Json(json!(simpletable.redacted())))
+}
+
+/// Get GeoIP database paths
+/// 
+/// GET /api/projects/{slug}/geoip
+/// Returns: { "city_db": "/var/lib/geoip/GeoLite2-City.mmdb", "asn_db": "GeoLite2-ASN.mmdb" }
+async fn get_project_geoip(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    if !Project::is_valid_slug(&slug) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match state.project_db_manager.get_project_settings(&slug).await {
+        Ok(settings) => Ok(Json(json!(GeoIpSettings::from_project_settings(&settings)))),
+        Err(e) => {
+            tracing::error!("Failed to get GeoIP settings for project {}: {}", slug, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Set the GeoIP databases used by GeoIP nodes (stored under "geoip" in the project settings)
+/// 
+/// PUT /api/projects/{slug}/geoip
+/// Body: { "city_db": "/var/lib/geoip/GeoLite2-City.mmdb", "asn_db": "GeoLite2-ASN.mmdb" }
+/// Relative paths are inside the project directory; each file must open as the matching database type.
+async fn update_project_geoip(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    Json(geoip): Json<GeoIpSettings>,
+) -> Result<Json<Value>, StatusCode> {
+    if !Project::is_valid_slug(&slug) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let project_dir = state.project_db_manager.project_dir(&slug).map_err(|_| StatusCode::BAD_REQUEST)?;
+    // Opening a City database reads the whole file
+    let validation = {
+        let geoip = geoip.clone();
+        tokio::task::spawn_blocking(move || geoip.validate(&project_dir)).await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    };
+    if let Err(e) = validation {
+        tracing::warn!("❌ Invalid GeoIP settings for project {}: {}", slug, e);
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let mut settings = state.project_db_manager.get_project_settings(&slug).await.map_err(|e| {
+        tracing::error!("Failed to get settings for project {}: {}", slug, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    settings["geoip"] = json!(geoip);
+
+    if let Err(e) = state.project_db_manager.save_project_settings(&slug, &settings).await {
+        tracing::error!("Failed to save GeoIP settings for project {}: {}", slug, e);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    tracing::info!("🌍 Updated GeoIP databases for project: {}", slug);
+
+    Ok(Json(json!(geoip)))
+}
+
+/// OpenAPI 3 document for the project's active webhook routes
+/// 
+/// GET /api/projects/{slug}/openapi.json
+/// Returns: { "openapi": "3.0.3", "paths": { "/webhook/wf-orders/orders/{id}": { "post": {...} } }, ... }
+async fn get_project_openapi(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    if !Project::is_valid_slug(&slug) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    Ok(Json(project_openapi(&slug, &state.registry.get_all_workflows())))
+}
+
+/// Which workflows read and write which SimpleTable / PostgreSQL tables
+/// 
+/// GET /api/projects/{slug}/lineage
+/// Returns: { "project": "shop", "workflows": [{ "id", "name" }],
+///            "tables": [{ "id": "simpletable:orders", "store": "simpletable", "name": "orders", "readers": [...], "writers": [...] }],
+///            "edges": [{ "workflow_id", "node_id", "table": "simpletable:orders", "access": "read|write" }] }
+async fn get_project_lineage(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    if !Project::is_valid_slug(&slug) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    Ok(Json(json!(project_lineage(&slug, &state.registry.get_all_workflows()))))
+}
+
+/// Current state of the project's MQTT / WebSocket devices
+/// 
+/// GET /api/projects/{slug}/devices?status=online
+/// Returns: { "devices": [{ "device_id": "sensors/boiler-2", "source": "mqtt", "workflow_id": "wf-boiler",
+///            "node_id": "mqtt", "status": "online", "last_payload": { "temperature": 71.5 },
+///            "first_seen": "...", "last_seen": "...", "message_count": 1520 }] }
+async fn get_project_devices(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Result<Json<Value>, StatusCode> {
+    if !Project::is_valid_slug(&slug) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let devices: Vec<_> = state.devices.list(&slug).into_iter()
+        .filter(|device| query.get("status").is_none_or(|status| device.status == *status))
+        .collect();
+    Ok(Json(json!({ "devices": devices })))
+}
+
+/// Node inputs parked by store-and-forward nodes while the network was down
+/// 
+/// GET /api/projects/{slug}/outbox?status=failed&limit=100
+/// Returns: { "pending": 3, "failed": 1, "entries": [{ "id": 7, "workflow_id": "wf-sync", "node_id": "upload",
+///            "status": "pending", "attempts": 2, "last_error": "HTTP request failed: ...",
+///            "created_at": "...", "next_attempt_at": "...", "items": [{ ... }] }] }
+async fn get_project_outbox(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Result<Json<Value>, StatusCode> {
+    if !Project::is_valid_slug(&slug) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let limit = query.get("limit").and_then(|l| l.parse().ok()).unwrap_or(100);
+
+    let listed = async {
+        let (pending, failed) = state.project_db_manager.outbox_counts(&slug).await?;
+        let entries = state.project_db_manager.outbox_list(&slug, query.get("status").map(String::as_str), limit).await?;
+        anyhow::Ok(json!({ "pending": pending, "failed": failed, "entries": entries }))
+    }.await;
+
+    match listed {
+        Ok(outbox) => Ok(Json(outbox)),
+        Err(e) => {
+            tracing::error!("Failed to list outbox of project {}: {}", slug, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Replay the due outbox entries now instead of waiting for the flusher
+/// 
+/// POST /api/projects/{slug}/outbox/flush
+/// Returns: { "project": "default", "delivered": 3, "rescheduled": 0, "failed": 0, "offline": false }
+async fn flush_project_outbox(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    if !Project::is_valid_slug(&slug) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match state.outbox.flush_project(&slug).await {
+        Ok(report) => Ok(Json(json!(report))),
+        Err(e) => {
+            tracing::error!("Outbox flush failed for project {}: {}", slug, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Make an outbox entry (e.g. a failed one) due for the next flush
+/// 
+/// POST /api/projects/{slug}/outbox/{id}/retry
+/// Returns: { "message": "Outbox entry 7 queued for retry" }
+async fn retry_outbox_entry(
+    State(state): State<AppState>,
+    Path((slug, id)): Path<(String, i64)>,
+) -> Result<Json<Value>, StatusCode> {
+    if !Project::is_valid_slug(&slug) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match state.project_db_manager.outbox_requeue(&slug, id, chrono::Utc::now().timestamp_millis()).await {
+        Ok(true) => Ok(Json(json!({ "message": format!("Outbox entry {} queued for retry", id) }))),
+        Ok(false) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to requeue outbox entry {} of project {}: {}", id, slug, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Discard an outbox entry without delivering it
+/// 
+/// DELETE /api/projects/{slug}/outbox/{id}
+/// Returns: { "message": "Outbox entry 7 deleted" }
+async fn delete_outbox_entry(
+    State(state): State<AppState>,
+    Path((slug, id)): Path<(String, i64)>,
+) -> Result<Json<Value>, StatusCode> {
+    if !Project::is_valid_slug(&slug) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match state.project_db_manager.outbox_delete(&slug, id).await {
+        Ok(true) => Ok(Json(json!({ "message": format!("Outbox entry {} deleted", id) }))),
+        Ok(false) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to delete outbox entry {} of project {}: {}", id, slug, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// List the project's feature flags
+/// 
+/// GET /api/projects/{slug}/flags
+/// Returns: { "flags": [{ "name": "new_checkout", "enabled": true, "percent": 20,
+///            "rules": [{ "attribute": "plan", "values": ["enterprise"] }], "updated_at": "..." }] }
+async fn list_feature_flags(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    if !Project::is_valid_slug(&slug) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match state.project_db_manager.flag_list(&slug).await {
+        Ok(flags) => Ok(Json(json!({ "flags": flags }))),
+        Err(e) => {
+            tracing::error!("Failed to list feature flags of project {}: {}", slug, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Get a feature flag
+/// 
+/// GET /api/projects/{slug}/flags/{name}
+/// Returns: { "enabled": true, "percent": 20, "rules": [...], "description": "..." }
+async fn get_feature_flag(
+    State(state): State<AppState>,
+    Path((slug, name)): Path<(String, String)>,
+) -> Result<Json<FeatureFlag>, StatusCode> {
+    if !Project::is_valid_slug(&slug) || !is_flag_name(&name) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match state.project_db_manager.flag_get(&slug, &name).await {
+        Ok(Some(flag)) => Ok(Json(flag)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to get feature flag {} of project {}: {}", name, slug, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Create or replace a feature flag (FeatureFlag nodes see it on their next run)
+/// 
+/// PUT /api/projects/{slug}/flags/{name}
+/// Body: { "enabled": true, "percent": 20, "rules": [{ "attribute": "plan", "values": ["enterprise"] }],
+///         "description": "New checkout flow" }
+async fn update_feature_flag(
+    State(state): State<AppState>,
+    Path((slug, name)): Path<(String, String)>,
+    Json(flag): Json<FeatureFlag>,
+) -> Result<Json<FeatureFlag>, StatusCode> {
+    if !Project::is_valid_slug(&slug) || !is_flag_name(&name) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if let Err(e) = flag.validate() {
+        tracing::warn!("Invalid feature flag {} for project {}: {}", name, slug, e);
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    if let Err(e) = state.project_db_manager.flag_set(&slug, &name, &flag).await {
+        tracing::error!("Failed to save feature flag {} of project {}: {}", name, slug, e);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    tracing::info!("🚩 Feature flag {} of project {} saved (enabled: {}, percent: {:?})", name, slug, flag.enabled, flag.percent);
+
+    Ok(Json(flag))
+}
+
+/// Delete a feature flag (FeatureFlag nodes fall back to their "default")
+/// 
+/// DELETE /api/projects/{slug}/flags/{name}
+/// Returns: { "message": "Feature flag new_checkout deleted" }
+async fn delete_feature_flag(
+    State(state): State<AppState>,
+    Path((slug, name)): Path<(String, String)>,
+) -> Result<Json<Value>, StatusCode> {
+    if !Project::is_valid_slug(&slug) || !is_flag_name(&name) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match state.project_db_manager.flag_delete(&slug, &name).await {
+        Ok(true) => Ok(Json(json!({ "message": format!("Feature flag {} deleted", name) }))),
+        Ok(false) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to delete feature flag {} of project {}: {}", name, slug, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Evaluate a feature flag for a set of attributes (what a FeatureFlag node would get)
+/// 
+/// POST /api/projects/{slug}/flags/{name}/evaluate
+/// Body: { "attributes": { "key": "user-42", "plan": "pro" } }
+/// Returns: { "enabled": true, "reason": "rollout" }
+async fn evaluate_feature_flag(
+    State(state): State<AppState>,
+    Path((slug, name)): Path<(String, String)>,
+    Json(body): Json<Value>,
+) -> Result<Json<Value>, StatusCode> {
+    if !Project::is_valid_slug(&slug) || !is_flag_name(&name) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let attributes = match body.get("attributes") {
+        None | Some(Value::Null) => serde_json::Map::new(),
+        Some(Value::Object(attributes)) => attributes.clone(),
+        Some(_) => return Err(StatusCode::UNPROCESSABLE_ENTITY),
+    };
+
+    match state.project_db_manager.flag_get(&slug, &name).await {
+        Ok(Some(flag)) => Ok(Json(json!(flag.evaluate(&name, &attributes)))),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to get feature flag {} of project {}: {}", name, slug, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// List the project's holiday calendars
+/// 
+/// GET /api/projects/{slug}/calendars
+/// Returns: { "calendars": [{ "name": "berlin", "dates": ["2026-04-03", "12-25"], "updated_at": "..." }] }
+async fn list_holiday_calendars(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    if !Project::is_valid_slug(&slug) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match state.project_db_manager.calendar_list(&slug).await {
+        Ok(calendars) => Ok(Json(json!({ "calendars": calendars }))),
+        Err(e) => {
+            tracing::error!("Failed to list holiday calendars of project {}: {}", slug, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Get a holiday calendar
+/// 
+/// GET /api/projects/{slug}/calendars/{name}
+/// Returns: { "dates": ["2026-04-03", "12-25"], "description": "..." }
+async fn get_holiday_calendar(
+    State(state): State<AppState>,
+    Path((slug, name)): Path<(String, String)>,
+) -> Result<Json<HolidayCalendar>, StatusCode> {
+    if !Project::is_valid_slug(&slug) || !is_calendar_name(&name) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match state.project_db_manager.calendar_get(&slug, &name).await {
+        Ok(Some(calendar)) => Ok(Json(calendar)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to get holiday calendar {} of project {}: {}", name, slug, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Create or replace a holiday calendar (scheduled triggers see it on their next tick)
+/// 
+/// PUT /api/projects/{slug}/calendars/{name}
+/// Body: { "dates": ["2026-04-03", "2026-04-06", "12-25", "12-26"], "description": "Public holidays Berlin" }
+async fn update_holiday_calendar(
+    State(state): State<AppState>,
+    Path((slug, name)): Path<(String, String)>,
+    Json(calendar): Json<HolidayCalendar>,
+) -> Result<Json<HolidayCalendar>, StatusCode> {
+    if !Project::is_valid_slug(&slug) || !is_calendar_name(&name) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if let Err(e) = calendar.validate() {
+        tracing::warn!("Invalid holiday calendar {} for project {}: {}", name, slug, e);
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    if let Err(e) = state.project_db_manager.calendar_set(&slug, &name, &calendar).await {
+        tracing::error!("Failed to save holiday calendar {} of project {}: {}", name, slug, e);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    tracing::info!("📅 Holiday calendar {} of project {} saved ({} dates)", name, slug, calendar.dates.len());
+
+    Ok(Json(calendar))
+}
+
+/// Delete a holiday calendar (triggers naming it run as if it had no dates)
+/// 
+/// DELETE /api/projects/{slug}/calendars/{name}
+/// Returns: { "message": "Holiday calendar berlin deleted" }
+async fn delete_holiday_calendar(
+    State(state): State<AppState>,
+    Path((slug, name)): Path<(String, String)>,
+) -> Result<Json<Value>, StatusCode> {
+    if !Project::is_valid_slug(&slug) || !is_calendar_name(&name) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match state.project_db_manager.calendar_delete(&slug, &name).await {
+        Ok(true) => Ok(Json(json!({ "message": format!("Holiday calendar {} deleted", name) }))),
+        Ok(false) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to delete holiday calendar {} of project {}: {}", name, slug, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
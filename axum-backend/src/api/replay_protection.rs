@@ -0,0 +1,127 @@
+//! Replay protection for webhook triggers
+//! 
+//! A Webhook node can require every request to carry a timestamp and a nonce:
+//! 
+//! { "path": "/payments", "method": "POST",
+//!   "replay_protection": { "timestamp_header": "x-webhook-timestamp",
+//!                          "nonce_header": "x-webhook-nonce", "max_skew_secs": 300 } }
+//! 
+//! Requests whose timestamp (unix seconds, milliseconds or RFC 3339) is more than
+//! `max_skew_secs` away from the server clock are rejected, and each nonce is
+//! accepted once per webhook node. Nonces are remembered in the project's KV
+//! store for twice the skew, long enough to outlive any timestamp that still
+//! passes the check. Senders should sign the timestamp and nonce together with
+//! the body so neither can be swapped on a captured request.
+
+use crate::project::ProjectDatabaseManager;
+use axum::http::HeaderMap;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+/// Longest nonce accepted (keeps KV keys bounded)
+const MAX_NONCE_LEN: usize = 128;
+
+fn default_timestamp_header() -> String {
+    "x-webhook-timestamp".to_string()
+}
+
+fn default_nonce_header() -> String {
+    "x-webhook-nonce".to_string()
+}
+
+fn default_max_skew_secs() -> u64 {
+    300
+}
+
+/// Webhook node params key "replay_protection"
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReplayProtection {
+    #[serde(default = "default_timestamp_header")]
+    pub timestamp_header: String,
+    #[serde(default = "default_nonce_header")]
+    pub nonce_header: String,
+    #[serde(default = "default_max_skew_secs")]
+    pub max_skew_secs: u64,
+}
+
+/// Why a request was rejected (reported to the caller)
+#[derive(Debug)]
+pub enum ReplayRejection {
+    MissingHeader(String),
+    InvalidTimestamp(String),
+    StaleTimestamp { skew_secs: i64, max_skew_secs: u64 },
+    InvalidNonce,
+    Replayed,
+}
+
+impl std::fmt::Display for ReplayRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplayRejection::MissingHeader(header) => write!(f, "missing {} header", header),
+            ReplayRejection::InvalidTimestamp(value) => write!(f, "invalid timestamp '{}'", value),
+            ReplayRejection::StaleTimestamp { skew_secs, max_skew_secs } =>
+                write!(f, "timestamp is {}s off the server clock (max {}s)", skew_secs, max_skew_secs),
+            ReplayRejection::InvalidNonce => write!(f, "nonce must be 1-{} visible ASCII characters", MAX_NONCE_LEN),
+            ReplayRejection::Replayed => write!(f, "nonce was already used"),
+        }
+    }
+}
+
+impl ReplayProtection {
+    /// Read the replay protection settings of a Webhook node (None if disabled)
+    pub fn from_node_params(params: &Value) -> anyhow::Result<Option<Self>> {
+        match params.get("replay_protection") {
+            None | Some(Value::Null) => Ok(None),
+            Some(settings) => serde_json::from_value(settings.clone())
+                .map(Some)
+                .map_err(|e| anyhow::anyhow!("Invalid 'replay_protection': {}", e)),
+        }
+    }
+
+    /// Check the request's timestamp and claim its nonce
+    /// 
+    /// The outer error means the nonce store failed; the inner one rejects the request.
+    pub async fn check(
+        &self,
+        headers: &HeaderMap,
+        project_db_manager: &ProjectDatabaseManager,
+        project_slug: &str,
+        scope: &str,
+    ) -> anyhow::Result<Result<(), ReplayRejection>> {
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(str::trim);
+
+        let Some(timestamp) = header(&self.timestamp_header) else {
+            return Ok(Err(ReplayRejection::MissingHeader(self.timestamp_header.clone())));
+        };
+        let Some(sent_at) = parse_timestamp(timestamp) else {
+            return Ok(Err(ReplayRejection::InvalidTimestamp(timestamp.chars().take(64).collect())));
+        };
+        let skew_secs = chrono::Utc::now().timestamp() - sent_at;
+        if skew_secs.unsigned_abs() > self.max_skew_secs {
+            return Ok(Err(ReplayRejection::StaleTimestamp { skew_secs, max_skew_secs: self.max_skew_secs }));
+        }
+
+        let Some(nonce) = header(&self.nonce_header) else {
+            return Ok(Err(ReplayRejection::MissingHeader(self.nonce_header.clone())));
+        };
+        if nonce.is_empty() || nonce.len() > MAX_NONCE_LEN || !nonce.chars().all(|c| c.is_ascii_graphic()) {
+            return Ok(Err(ReplayRejection::InvalidNonce));
+        }
+
+        let key = format!("webhook_nonce:{}:{}", scope, nonce);
+        let claimed = project_db_manager
+            .kv_claim(project_slug, &key, &json!(sent_at), self.max_skew_secs * 2)
+            .await?;
+        Ok(if claimed { Ok(()) } else { Err(ReplayRejection::Replayed) })
+    }
+}
+
+/// Parse unix seconds, unix milliseconds or RFC 3339 into unix seconds
+fn parse_timestamp(value: &str) -> Option<i64> {
+    match value.parse::<i64>() {
+        // Millisecond timestamps have 13 digits until the year 2286
+        Ok(ms) if ms > 100_000_000_000 => Some(ms / 1000),
+        Ok(secs) => Some(secs),
+        Err(_) => chrono::DateTime::parse_from_rfc3339(value).ok().map(|t| t.timestamp()),
+    }
+}
@@ -0,0 +1,100 @@
+//! Project secret endpoints
+//! 
+//! Manages the values behind `$secret.` pins per project. Values are write-only:
+//! they are sealed with MECHAWAY_MASTER_KEY and only listed by key.
+
+use crate::{
+    api::workflows::AppState,
+    project::{crypto::MasterKeyMissing, secrets::SecretError},
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+    routing::{get, put},
+    Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+/// Body of the put endpoint
+#[derive(Debug, Deserialize)]
+pub struct PutSecretRequest {
+    pub value: String,
+}
+
+/// Create project secret routes
+pub fn create_secret_routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/projects/{slug}/secrets", get(list_secrets))
+        .route("/api/projects/{slug}/secrets/{key}", put(put_secret).delete(delete_secret))
+}
+
+/// List a project's secrets (keys only)
+/// 
+/// GET /api/projects/{slug}/secrets
+/// Returns: { "secrets": [{ "key": "database_url", "created_at": "...", "updated_at": "..." }] }
+async fn list_secrets(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<Json<serde_json::Value>, Response> {
+    match state.project_db_manager.secret_list(&slug).await {
+        Ok(secrets) => Ok(Json(json!({ "secrets": secrets }))),
+        Err(e) => Err(secret_error_response(&slug, e)),
+    }
+}
+
+/// Create or replace a secret
+/// 
+/// PUT /api/projects/{slug}/secrets/{key}
+/// Body: { "value": "postgres://app:...@db:5432/app" }
+/// Returns: { "key": "database_url", "updated": true }
+async fn put_secret(
+    State(state): State<AppState>,
+    Path((slug, key)): Path<(String, String)>,
+    Json(request): Json<PutSecretRequest>,
+) -> Result<Json<serde_json::Value>, Response> {
+    match state.project_db_manager.secret_put(&slug, &key, &request.value).await {
+        Ok(()) => {
+            // The simpletable backend may connect with this secret
+            state.project_db_manager.invalidate_simpletable_backend(&slug).await;
+            tracing::info!("🔐 Secret '{}' saved for project {}", key, slug);
+            Ok(Json(json!({ "key": key, "updated": true })))
+        }
+        Err(e) => Err(secret_error_response(&slug, e)),
+    }
+}
+
+/// Delete a secret
+/// 
+/// DELETE /api/projects/{slug}/secrets/{key}
+/// Returns: { "key": "database_url", "deleted": true }
+async fn delete_secret(
+    State(state): State<AppState>,
+    Path((slug, key)): Path<(String, String)>,
+) -> Result<Json<serde_json::Value>, Response> {
+    match state.project_db_manager.secret_delete(&slug, &key).await {
+        Ok(true) => {
+            // The simpletable backend may connect with this secret
+            state.project_db_manager.invalidate_simpletable_backend(&slug).await;
+            tracing::info!("🔐 Secret '{}' deleted from project {}", key, slug);
+            Ok(Json(json!({ "key": key, "deleted": true })))
+        }
+        Ok(false) => Err(StatusCode::NOT_FOUND.into_response()),
+        Err(e) => Err(secret_error_response(&slug, e)),
+    }
+}
+
+/// Map secret failures to 404 / 422, and 503 when no master key is configured
+fn secret_error_response(slug: &str, error: anyhow::Error) -> Response {
+    let status = match error.downcast_ref::<SecretError>() {
+        Some(SecretError::NotFound { .. }) => StatusCode::NOT_FOUND,
+        Some(SecretError::Invalid(_)) => StatusCode::UNPROCESSABLE_ENTITY,
+        None if error.is::<MasterKeyMissing>() => StatusCode::SERVICE_UNAVAILABLE,
+        None => {
+            tracing::error!("Failed secret operation for project {}: {}", slug, error);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    (status, Json(json!({ "error": error.to_string() }))).into_response()
+}
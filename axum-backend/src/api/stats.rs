@@ -0,0 +1,43 @@
+//! Execution profiling endpoints
+//! 
+//! Exposes the in-memory timing statistics collected by the execution engine
+//! (per node type and per workflow) to find slow steps without external tooling.
+
+use crate::api::workflows::AppState;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::get,
+    Router,
+};
+use serde_json::{json, Value};
+
+/// Create profiling stats routes
+pub fn create_stats_routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/stats/nodes", get(get_node_stats))
+        .route("/api/stats/workflows/{id}", get(get_workflow_stats))
+}
+
+/// Timing statistics per node type
+/// 
+/// GET /api/stats/nodes
+/// Returns: { "node_types": { "HTTPClient": { "count": 120, "p50_ms": 84.2, "p95_ms": 310.5, "p99_ms": 702.0, "error_rate": 0.02, ... } } }
+async fn get_node_stats(State(state): State<AppState>) -> Json<Value> {
+    Json(json!({ "node_types": state.profiler.node_type_stats() }))
+}
+
+/// Timing statistics of one workflow and each of its nodes
+/// 
+/// GET /api/stats/workflows/{id}
+/// Returns: { "workflow_id": "wf-orders", "executions": {...}, "nodes": { "charge": {...} } }
+async fn get_workflow_stats(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    match state.profiler.workflow_stats(&id) {
+        Some(profile) => Ok(Json(json!(profile))),
+        None => Err(StatusCode::NOT_FOUND),
+    }
+}
@@ -0,0 +1,160 @@
+//! Stripe webhook triggers
+//! 
+//! A Webhook node with a "stripe" param receives Stripe events directly:
+//! 
+//! { "path": "/stripe", "method": "POST",
+//!   "stripe": { "signing_secret": "$secret.stripe_webhook_secret",
+//!               "events": ["checkout.session.completed", "invoice.*"],
+//!               "tolerance_secs": 300 } }
+//! 
+//! The Stripe-Signature header is verified against the raw body (HMAC-SHA256
+//! over "{t}.{body}", any v1 signature may match so secrets can be rolled) and
+//! timestamps older than the tolerance are rejected. Events outside the filter
+//! are acknowledged with 200 without running the workflow, so Stripe does not
+//! retry them. Accepted events reach the workflow as one typed item (StripeEvent).
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
+
+fn default_tolerance_secs() -> u64 {
+    300
+}
+
+/// Webhook node params key "stripe"
+#[derive(Debug, Clone, Deserialize)]
+pub struct StripeWebhook {
+    /// Endpoint signing secret (whsec_...), as a $secret. pin
+    pub signing_secret: String,
+    /// Event types to run the workflow for; "prefix.*" matches a family, empty = all
+    #[serde(default)]
+    pub events: Vec<String>,
+    /// Maximum age of the signature timestamp in seconds
+    #[serde(default = "default_tolerance_secs")]
+    pub tolerance_secs: u64,
+}
+
+/// Why a request was rejected (reported to the caller)
+#[derive(Debug)]
+pub enum StripeRejection {
+    MissingSignature,
+    MalformedSignature,
+    StaleTimestamp { age_secs: i64, tolerance_secs: u64 },
+    SignatureMismatch,
+    InvalidEvent(String),
+}
+
+impl std::fmt::Display for StripeRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StripeRejection::MissingSignature => write!(f, "missing Stripe-Signature header"),
+            StripeRejection::MalformedSignature => write!(f, "Stripe-Signature header has no timestamp or v1 signature"),
+            StripeRejection::StaleTimestamp { age_secs, tolerance_secs } =>
+                write!(f, "signature timestamp is {}s old (tolerance {}s)", age_secs, tolerance_secs),
+            StripeRejection::SignatureMismatch => write!(f, "no v1 signature matches the payload"),
+            StripeRejection::InvalidEvent(reason) => write!(f, "invalid Stripe event: {}", reason),
+        }
+    }
+}
+
+/// Stripe event as handed to the workflow
+#[derive(Debug, Clone, Serialize)]
+pub struct StripeEvent {
+    /// Event id (evt_...), stable across redeliveries - use it to dedupe
+    pub id: String,
+    /// Event type (e.g. "checkout.session.completed")
+    #[serde(rename = "type")]
+    pub event_type: String,
+    /// Type of the object the event is about (data.object.object, e.g. "checkout.session")
+    pub object_type: String,
+    /// Unix seconds
+    pub created: i64,
+    pub livemode: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_version: Option<String>,
+    /// The API object (data.object)
+    pub object: Value,
+    /// Changed fields for *.updated events (data.previous_attributes)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_attributes: Option<Value>,
+}
+
+impl StripeEvent {
+    /// Parse a Stripe event body
+    pub fn from_json(event: &Value) -> Result<Self, StripeRejection> {
+        let field = |name: &str| event.get(name).and_then(|v| v.as_str()).map(str::to_string)
+            .ok_or_else(|| StripeRejection::InvalidEvent(format!("missing '{}'", name)));
+        let object = event.pointer("/data/object").cloned()
+            .ok_or_else(|| StripeRejection::InvalidEvent("missing 'data.object'".to_string()))?;
+        Ok(Self {
+            id: field("id")?,
+            event_type: field("type")?,
+            object_type: object.get("object").and_then(|o| o.as_str()).unwrap_or_default().to_string(),
+            created: event.get("created").and_then(|c| c.as_i64()).unwrap_or_default(),
+            livemode: event.get("livemode").and_then(|l| l.as_bool()).unwrap_or(false),
+            api_version: event.get("api_version").and_then(|v| v.as_str()).map(str::to_string),
+            object,
+            previous_attributes: event.pointer("/data/previous_attributes").cloned(),
+        })
+    }
+}
+
+impl StripeWebhook {
+    /// Read the Stripe settings of a Webhook node (None if not a Stripe webhook)
+    pub fn from_node_params(params: &Value) -> anyhow::Result<Option<Self>> {
+        match params.get("stripe") {
+            None | Some(Value::Null) => Ok(None),
+            Some(settings) => {
+                let stripe: Self = serde_json::from_value(settings.clone())
+                    .map_err(|e| anyhow::anyhow!("Invalid 'stripe': {}", e))?;
+                if !stripe.signing_secret.starts_with("$secret.") {
+                    return Err(anyhow::anyhow!("'stripe.signing_secret' must be a $secret. pin"));
+                }
+                Ok(Some(stripe))
+            }
+        }
+    }
+
+    /// Verify the Stripe-Signature header against the raw request body
+    pub fn verify(&self, signature_header: Option<&str>, body: &[u8], secret: &str, now: i64) -> Result<(), StripeRejection> {
+        let signature_header = signature_header.ok_or(StripeRejection::MissingSignature)?;
+
+        let mut timestamp = None;
+        let mut signatures = Vec::new();
+        for part in signature_header.split(',') {
+            match part.trim().split_once('=') {
+                Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+                Some(("v1", value)) => signatures.extend(hex::decode(value).ok()),
+                _ => {}
+            }
+        }
+        let timestamp = timestamp.ok_or(StripeRejection::MalformedSignature)?;
+        if signatures.is_empty() {
+            return Err(StripeRejection::MalformedSignature);
+        }
+
+        let age_secs = now - timestamp;
+        if age_secs > self.tolerance_secs as i64 {
+            return Err(StripeRejection::StaleTimestamp { age_secs, tolerance_secs: self.tolerance_secs });
+        }
+
+        // Mac::verify_slice compares in constant time
+        let matches = signatures.iter().any(|signature| {
+            let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+            mac.update(timestamp.to_string().as_bytes());
+            mac.update(b".");
+            mac.update(body);
+            mac.verify_slice(signature).is_ok()
+        });
+        if matches { Ok(()) } else { Err(StripeRejection::SignatureMismatch) }
+    }
+
+    /// Whether the workflow should run for this event type
+    pub fn accepts(&self, event_type: &str) -> bool {
+        self.events.is_empty() || self.events.iter().any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => event_type.starts_with(prefix),
+            None => pattern == event_type,
+        })
+    }
+}
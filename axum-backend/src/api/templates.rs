@@ -0,0 +1,123 @@
+//! Workflow template endpoints
+//! 
+//! Lists the built-in templates and instantiates them into a project as
+//! inactive drafts. Activate a draft by saving it with "active": true
+//! (PUT /api/workflows/{id}).
+
+use crate::{
+    api::workflows::AppState,
+    project::Project,
+    workflow::{
+        params::validate_workflow_params,
+        templates::{builtin_templates, find_template, WorkflowTemplate},
+    },
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+    routing::{get, post},
+    Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+/// Query parameters of the instantiate endpoint
+#[derive(Debug, Deserialize)]
+pub struct InstantiateQuery {
+    pub project: String,
+}
+
+/// Optional body of the instantiate endpoint
+#[derive(Debug, Default, Deserialize)]
+pub struct InstantiateRequest {
+    /// Workflow ID (default "{template}-{random}")
+    #[serde(default)]
+    pub id: Option<String>,
+    /// Workflow name (default: the template name)
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Template parameter values; missing ones use the defaults
+    #[serde(default)]
+    pub params: Map<String, Value>,
+}
+
+/// Create template routes
+pub fn create_template_routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/templates", get(list_templates))
+        .route("/api/templates/{id}/instantiate", post(instantiate_template))
+}
+
+/// List built-in workflow templates
+/// 
+/// GET /api/templates
+/// Returns: { "templates": [{ "id": "cron-etl", "name": "...", "category": "etl", "params": [...], "workflow": {...} }] }
+async fn list_templates() -> Json<Value> {
+    let templates: Vec<WorkflowTemplate> = builtin_templates();
+    Json(json!({ "templates": templates }))
+}
+
+/// Create an inactive draft workflow from a template
+/// 
+/// POST /api/templates/{id}/instantiate?project=slug
+/// Body (optional): { "id": "orders-etl", "name": "Orders ETL", "params": { "table": "daily_orders" } }
+/// Returns: { "id": "orders-etl", "template": "cron-etl", "active": false, "workflow": {...} }
+async fn instantiate_template(
+    State(state): State<AppState>,
+    Path(template_id): Path<String>,
+    Query(query): Query<InstantiateQuery>,
+    body: Option<Json<InstantiateRequest>>,
+) -> Result<Json<Value>, Response> {
+    if !Project::is_valid_slug(&query.project) {
+        return Err(error_response(StatusCode::BAD_REQUEST, format!("Invalid project slug '{}'", query.project)));
+    }
+    let Some(template) = find_template(&template_id) else {
+        return Err(error_response(StatusCode::NOT_FOUND, format!("Template '{}' not found", template_id)));
+    };
+    let request = body.map(|Json(request)| request).unwrap_or_default();
+
+    let workflow_id = request.id.unwrap_or_else(|| {
+        format!("{}-{}", template.id, &uuid::Uuid::new_v4().simple().to_string()[..8])
+    });
+    if workflow_id.is_empty() {
+        return Err(error_response(StatusCode::BAD_REQUEST, "Workflow id must not be empty".to_string()));
+    }
+
+    let workflow = template
+        .instantiate(&query.project, &workflow_id, request.name.as_deref(), &request.params)
+        .map_err(|e| error_response(StatusCode::BAD_REQUEST, e.to_string()))?;
+    validate_workflow_params(&workflow).map_err(|e| error_response(StatusCode::BAD_REQUEST, e.to_string()))?;
+    state.registry.validate_workflow(&workflow).map_err(|e| error_response(StatusCode::BAD_REQUEST, e.to_string()))?;
+
+    match state.storage.get_workflow(&workflow.id).await {
+        Ok(Some(_)) => {
+            return Err(error_response(StatusCode::CONFLICT, format!("Workflow '{}' already exists", workflow.id)));
+        }
+        Ok(None) => {}
+        Err(e) => return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    }
+
+    if let Err(e) = state.storage.save_workflow(&workflow).await {
+        tracing::error!("Failed to save workflow: {}", e);
+        return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()));
+    }
+
+    // Drafts are compiled for validation only; nothing is registered
+    if let Err(e) = state.registry.reload_workflow(&workflow.id).await {
+        tracing::error!("Failed to compile workflow {} from template {}: {}", workflow.id, template.id, e);
+        return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()));
+    }
+
+    tracing::info!("📝 Created draft workflow {} from template {} in project {}", workflow.id, template.id, query.project);
+    Ok(Json(json!({
+        "id": workflow.id,
+        "template": template.id,
+        "active": false,
+        "workflow": workflow,
+    })))
+}
+
+fn error_response(status: StatusCode, message: String) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
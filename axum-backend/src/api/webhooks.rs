@@ -1,15 +1,27 @@
-/// Dynamic webhook execution endpoints
-/// 
-/// Handles webhook triggers that start workflow execution. Routes are registered
-/// dynamically based on active workflows with WebhookNode definitions.
+//! Dynamic webhook execution endpoints
+//! 
+//! Handles webhook triggers that start workflow execution. Routes are registered
+//! dynamically based on active workflows with WebhookNode definitions.
 
-use crate::api::workflows::AppState;
-use crate::runtime::engine::ExecutionEngine;
-use crate::workflow::types::ExecutionContext;
+use crate::api::{
+    error::ApiError,
+    git_webhooks::GitWebhook,
+    replay_protection::ReplayProtection,
+    stripe_webhook::{StripeEvent, StripeWebhook},
+    workflows::AppState,
+};
+use crate::runtime::{engine::ExecutionEngine, executor::Termination, logging::LogPolicy, queue::AdmissionError, trace::TRACE_METADATA_KEY};
+use crate::workflow::{
+    canary::WorkflowVersion,
+    environments::{ENVIRONMENT_HEADER, ENVIRONMENT_METADATA_KEY},
+    types::ExecutionContext,
+    webhook_index::WebhookLookup,
+};
 use axum::{
+    body::Bytes,
     extract::{Path, State, Query},
-    http::StatusCode,
-    response::Json,
+    http::{HeaderMap, HeaderName, HeaderValue, Method},
+    response::{IntoResponse, Json, Response},
     routing::{any, Router},
 };
 use std::collections::HashMap;
@@ -40,32 +52,51 @@ pub fn create_webhook_routes() -> Router<WebhookAppState> {
 /// 
 /// POST/GET/PUT/DELETE /webhook/{workflow_id}/{webhook_path}
 /// Supports JSON payloads (multipart support coming soon)
+/// BACKPRESSURE: Returns 429/503 with Retry-After when the execution queue is saturated
+/// Successful runs carry X-Mechaway-Run-Id and X-Mechaway-Run-Status ("completed" or
+/// "stopped_early"); runs a node ended early add X-Mechaway-Stop-Node and X-Mechaway-Stop-Reason
+/// X-Mechaway-Environment runs the variant of one of the workflow's environment profiles
+/// Snoozed workflows answer 503 workflow_snoozed with Retry-After until the snooze ends
+/// While a canary rolls out, the candidate serves its share of requests and successful
+/// runs carry X-Mechaway-Version ("stable" or "canary")
+/// Webhook nodes with "response": "envelope" return
+/// { "run_id", "status", "termination", "data", "branches": [...], "nodes": [...] } instead of the bare data
+/// Errors: { "error": "<code>", "message": "..." } (see `ApiError`), e.g. 422
+/// { "error": "node_execution_error", "node_id": "charge", "node_type": "FunLogic", "message": "...", "run_id": "..." }
 async fn execute_webhook(
     State(state): State<WebhookAppState>,
+    method: Method,
     Path((workflow_id, webhook_path)): Path<(String, String)>,
     Query(query_params): Query<HashMap<String, String>>,
-    body: String,
-) -> Result<Json<Value>, StatusCode> {
+    request_headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, ApiError> {
+    // Shed load before doing any parsing work
+    if let Err(shed) = state.app_state.queue.check_admission() {
+        tracing::warn!("🚦 Shedding webhook request {}/{}: {}", workflow_id, webhook_path, shed);
+        return Err(ApiError::Shed(shed));
+    }
+    
     tracing::info!("📥 Webhook request received: {}/{}", workflow_id, webhook_path);
     
-    // Parse JSON body manually to handle errors gracefully
-    let json_data: Value = match serde_json::from_str(&body) {
+    // Parse JSON body manually to handle errors gracefully (straight from bytes, no String copy)
+    let mut json_data: Value = match serde_json::from_slice(&body) {
         Ok(json) => {
             tracing::debug!("✅ JSON payload parsed successfully");
             json
         },
         Err(e) => {
             tracing::warn!("❌ Invalid JSON payload for webhook: {}/{} - Error: {}", workflow_id, webhook_path, e);
-            return Err(StatusCode::BAD_REQUEST);
+            return Err(ApiError::InvalidPayload(format!("Invalid JSON body: {}", e)));
         }
     };
     
     // Initialize execution context components (multipart support coming soon)
     let files = HashMap::new(); // TODO: Implement multipart support
-    let headers = HashMap::new(); // TODO: Extract from request headers
-    
-    tracing::debug!("📊 Parsed data - JSON: {:?}, Files: {}, Query: {:?}", 
-        json_data, files.len(), query_params);
+    // Header names are lowercase already; non-UTF-8 values are skipped
+    let headers: HashMap<String, String> = request_headers.iter()
+        .filter_map(|(name, value)| Some((name.as_str().to_string(), value.to_str().ok()?.to_string())))
+        .collect();
     
     // Get the compiled workflow from registry
     tracing::debug!("🔍 Looking up workflow in registry: {}", workflow_id);
@@ -76,39 +107,188 @@ async fn execute_webhook(
         },
         None => {
             tracing::warn!("❌ Webhook called for unknown workflow: {}", workflow_id);
-            return Err(StatusCode::NOT_FOUND);
+            return Err(ApiError::WorkflowNotFound(workflow_id));
         }
     };
 
-    // Find the webhook node that matches this path
-    let webhook_path_normalized = if webhook_path.starts_with('/') {
-        webhook_path
-    } else {
-        format!("/{}", webhook_path)
+    if let Some(snooze) = compiled_workflow.active_snooze() {
+        tracing::info!("💤 Webhook {}/{} rejected: workflow is snoozed", workflow_id, webhook_path);
+        return Err(ApiError::Shed(AdmissionError::Snoozed { retry_after_secs: snooze.retry_after_secs() }));
+    }
+
+    // CANARY: A share of the traffic runs the candidate version
+    let canary = compiled_workflow.canary.clone();
+    let version = canary.as_ref().map_or(WorkflowVersion::Stable, |canary| canary.pick());
+    let compiled_workflow = match &canary {
+        Some(canary) if version == WorkflowVersion::Canary => canary.workflow.clone(),
+        _ => compiled_workflow,
+    };
+    if canary.is_some() {
+        tracing::debug!("🐤 Webhook {}/{} served by the {} version", workflow_id, webhook_path, version.as_str());
+    }
+
+    // Resolve the webhook node via the precompiled route index
+    let (start_node_id, path_params) = match compiled_workflow.webhook_index.resolve(method.as_str(), &webhook_path) {
+        WebhookLookup::Found { node_id, path_params } => (node_id, path_params),
+        WebhookLookup::MethodNotAllowed => {
+            tracing::warn!("❌ Method {} not allowed for webhook {}/{}", method, workflow_id, webhook_path);
+            return Err(ApiError::MethodNotAllowed { method: method.to_string(), path: webhook_path });
+        }
+        WebhookLookup::NotFound => {
+            tracing::warn!("❌ No webhook node found for path '{}' in workflow '{}'", webhook_path, workflow_id);
+            return Err(ApiError::WebhookNotFound { workflow_id, path: webhook_path });
+        }
     };
-    
-    tracing::debug!("🔍 Searching for webhook node with path: {}", webhook_path_normalized);
-    let start_node_id = find_webhook_start_node(&compiled_workflow, &webhook_path_normalized)?;
     tracing::debug!("✅ Found start node: {}", start_node_id);
 
+    // REPLAY PROTECTION: Timestamp within the allowed skew and a nonce never seen before
+    let webhook_params = compiled_workflow.workflow.nodes.iter()
+        .find(|node| node.id == start_node_id)
+        .map(|node| &node.params);
+    let replay_protection = match webhook_params.map(ReplayProtection::from_node_params).transpose() {
+        Ok(settings) => settings.flatten(),
+        Err(e) => {
+            tracing::error!("❌ Webhook node '{}' of workflow '{}': {}", start_node_id, workflow_id, e);
+            return Err(ApiError::ValidationFailed(format!("Webhook node '{}': {}", start_node_id, e)));
+        }
+    };
+    if let Some(replay_protection) = replay_protection {
+        let scope = format!("{}:{}", workflow_id, start_node_id);
+        match replay_protection.check(&request_headers, &state.app_state.project_db_manager,
+            &compiled_workflow.workflow.project, &scope).await
+        {
+            Ok(Ok(())) => {}
+            Ok(Err(rejection)) => {
+                tracing::warn!("🛑 Rejected webhook request {}/{}: {}", workflow_id, webhook_path, rejection);
+                return Err(rejection.into());
+            }
+            Err(e) => {
+                tracing::error!("❌ Nonce store unavailable for webhook {}/{}: {}", workflow_id, webhook_path, e);
+                return Err(ApiError::Unavailable("Nonce store unavailable".to_string()));
+            }
+        }
+    }
+
+    // STRIPE: Verify the signature over the raw body, filter event types, type the payload
+    let stripe = match webhook_params.map(StripeWebhook::from_node_params).transpose() {
+        Ok(settings) => settings.flatten(),
+        Err(e) => {
+            tracing::error!("❌ Webhook node '{}' of workflow '{}': {}", start_node_id, workflow_id, e);
+            return Err(ApiError::ValidationFailed(format!("Webhook node '{}': {}", start_node_id, e)));
+        }
+    };
+    if let Some(stripe) = stripe {
+        let signing_secret = match state.engine.executor().resolve_secret(&compiled_workflow.workflow.project, &stripe.signing_secret) {
+            Ok(secret) => secret,
+            Err(e) => {
+                tracing::error!("❌ Stripe signing secret unavailable for webhook {}/{}: {}", workflow_id, webhook_path, e);
+                return Err(ApiError::Unavailable("Stripe signing secret unavailable".to_string()));
+            }
+        };
+        let signature = request_headers.get("stripe-signature").and_then(|v| v.to_str().ok());
+        let event = stripe.verify(signature, &body, &signing_secret, chrono::Utc::now().timestamp())
+            .and_then(|()| StripeEvent::from_json(&json_data));
+        let event = match event {
+            Ok(event) => event,
+            Err(rejection) => {
+                tracing::warn!("🛑 Rejected Stripe webhook {}/{}: {}", workflow_id, webhook_path, rejection);
+                return Err(rejection.into());
+            }
+        };
+        if !stripe.accepts(&event.event_type) {
+            tracing::debug!("⏭️ Ignoring Stripe event {} ({})", event.id, event.event_type);
+            return Ok(Json(serde_json::json!({ "received": true, "ignored": event.event_type })).into_response());
+        }
+        tracing::info!("💳 Stripe event {} ({})", event.id, event.event_type);
+        json_data = serde_json::to_value(event).map_err(|e| ApiError::internal("Failed to encode Stripe event", e))?;
+    }
+
+    // GITHUB / GITLAB: Verify signature or token, filter events, add the extracted "git" fields
+    let git_webhook = match webhook_params.map(GitWebhook::from_node_params).transpose() {
+        Ok(settings) => settings.flatten(),
+        Err(e) => {
+            tracing::error!("❌ Webhook node '{}' of workflow '{}': {}", start_node_id, workflow_id, e);
+            return Err(ApiError::ValidationFailed(format!("Webhook node '{}': {}", start_node_id, e)));
+        }
+    };
+    if let Some(git_webhook) = git_webhook {
+        let secret = match state.engine.executor().resolve_secret(&compiled_workflow.workflow.project, git_webhook.secret_pin()) {
+            Ok(secret) => secret,
+            Err(e) => {
+                tracing::error!("❌ Webhook secret unavailable for {}/{}: {}", workflow_id, webhook_path, e);
+                return Err(ApiError::Unavailable("Webhook secret unavailable".to_string()));
+            }
+        };
+        let event = match git_webhook.verify(&request_headers, &body, &json_data, &secret) {
+            Ok(event) => event,
+            Err(rejection) => {
+                tracing::warn!("🛑 Rejected webhook request {}/{}: {}", workflow_id, webhook_path, rejection);
+                return Err(rejection.into());
+            }
+        };
+        if !event.accepted {
+            tracing::debug!("⏭️ Ignoring {} event for webhook {}/{}", event.name, workflow_id, webhook_path);
+            return Ok(Json(serde_json::json!({ "received": true, "ignored": event.name })).into_response());
+        }
+        tracing::info!("🐙 {} event {}", event.git["provider"].as_str().unwrap_or_default(), event.name);
+        if let Some(payload) = json_data.as_object_mut() {
+            payload.insert("git".to_string(), event.git);
+        }
+    }
+
+    // Payload logging follows the workflow's log policy
+    let log_policy = LogPolicy::from_settings(&compiled_workflow.workflow.settings);
+    tracing::debug!("📊 Parsed data - JSON: {}, Files: {}, Query: {}", 
+        log_policy.preview_value(&json_data), files.len(), log_policy.preview_debug(&query_params));
+
     // Create execution context with unified data (JSON + files + query + headers)
     tracing::debug!("📋 Creating execution context with unified data");
-    let mut execution_context = ExecutionContext::from_webhook_data(workflow_id.clone(), json_data, "default".to_string());
+    let project_slug = compiled_workflow.workflow.project.clone();
+    let mut execution_context = ExecutionContext::from_webhook_data(workflow_id.clone(), json_data, project_slug);
     
     // Add files, query params, and headers to execution context
     execution_context.files = files;
     execution_context.query = query_params;
-    execution_context.headers = headers; // TODO: Extract from request headers
+    execution_context.headers = headers;
+    if !path_params.is_empty() {
+        execution_context.metadata.insert("path_params".to_string(), serde_json::json!(path_params));
+    }
+    
+    // Manual runs may pick one of the workflow's environment profiles
+    if let Some(environment) = execution_context.headers.get(ENVIRONMENT_HEADER) {
+        if !compiled_workflow.environments.contains_key(environment) {
+            return Err(ApiError::ValidationFailed(format!("Workflow '{}' has no environment '{}'", workflow_id, environment)));
+        }
+        execution_context.metadata.insert(ENVIRONMENT_METADATA_KEY.to_string(), serde_json::json!(environment));
+    }
+    
+    // Pre-assign the run ID so failed runs can report it
+    let run_id = uuid::Uuid::new_v4().to_string();
+    execution_context.metadata.insert("execution_id".to_string(), serde_json::json!(run_id));
     
     tracing::debug!("📊 Execution context created with {} metadata fields, {} files, {} query params", 
         execution_context.metadata.len(), execution_context.files.len(), execution_context.query.len());
 
+    // Webhook nodes with "response": "envelope" answer with the run outcome around the data
+    let envelope = webhook_params.and_then(|params| params.get("response")).and_then(|r| r.as_str()) == Some("envelope");
+    
     // Execute the workflow starting from the webhook node
     tracing::info!("🚀 Starting workflow execution for: {} from node: {}", workflow_id, start_node_id);
     let workflow_start_time = std::time::Instant::now();
     
-    match state.engine.execute_workflow(&compiled_workflow, &start_node_id, execution_context).await {
-        Ok(result) => {
+    let execution = match state.app_state.queue.execute(&compiled_workflow, &start_node_id, execution_context).await {
+        Ok(execution) => execution,
+        Err(shed) => {
+            tracing::warn!("🚦 Shedding webhook request {}/{}: {}", workflow_id, start_node_id, shed);
+            return Err(ApiError::Shed(shed));
+        }
+    };
+    if let Some(canary) = &canary {
+        canary.metrics.record(version, &Termination::of_run(&execution), workflow_start_time.elapsed());
+    }
+    
+    match execution {
+        Ok(mut result) => {
             let workflow_duration = workflow_start_time.elapsed();
             tracing::info!(
                 "🎉 Workflow execution completed successfully: {} -> {} in {:?}",
@@ -116,9 +296,25 @@ async fn execute_webhook(
                 start_node_id,
                 workflow_duration
             );
-            tracing::debug!("📤 Final result data: {}", 
-                serde_json::to_string(&result.data).unwrap_or_else(|_| "invalid_json".to_string()));
-            Ok(Json(serde_json::Value::Array(result.data)))
+            tracing::debug!("📤 Final result data: {}", log_policy.preview_data(&result.data));
+            let mut headers = termination_headers(&run_id, &result.termination);
+            if canary.is_some() {
+                headers.insert(HeaderName::from_static("x-mechaway-version"), HeaderValue::from_static(version.as_str()));
+            }
+            if !envelope {
+                return Ok((headers, Json(serde_json::Value::Array(result.data))).into_response());
+            }
+            
+            // ENVELOPE: Run outcome plus per-branch and per-node results
+            let mut trace = result.metadata.remove(TRACE_METADATA_KEY).unwrap_or_default();
+            Ok((headers, Json(serde_json::json!({
+                "run_id": run_id,
+                "status": result.termination.status(),
+                "termination": result.termination,
+                "data": result.data,
+                "branches": trace["branches"].take(),
+                "nodes": trace["nodes"].take(),
+            }))).into_response())
         }
         Err(e) => {
             let workflow_duration = workflow_start_time.elapsed();
@@ -141,49 +337,33 @@ async fn execute_webhook(
                 tracing::debug!("🔍 Error chain: {}", error_chain.join(" → "));
             }
             
-            // Use 422 (Unprocessable Entity) for execution failures
-            // vs 500 for system errors  
-            Err(StatusCode::UNPROCESSABLE_ENTITY)
+            // 422 for execution failures: failed node (with its ID) or Assert conditions
+            let detail = compiled_workflow.workflow.settings.error_detail.unwrap_or_default();
+            Err(ApiError::from_execution(e, Some(run_id), detail))
         }
     }
 }
 
-/// Find the webhook node that matches the requested path
+/// Run ID and termination headers of a successful webhook run
 /// 
-/// Searches through the workflow nodes to find a WebhookNode with a matching path parameter.
-/// Returns the node ID that should be used as the execution start point.
-fn find_webhook_start_node(
-    compiled_workflow: &crate::workflow::registry::CompiledWorkflow,
-    webhook_path: &str,
-) -> Result<String, StatusCode> {
-    tracing::debug!("🔍 Searching for webhook node with path: '{}'", webhook_path);
-    
-    let mut webhook_nodes_found = Vec::new();
-    
-    for node in &compiled_workflow.workflow.nodes {
-        if matches!(node.node_type, crate::workflow::NodeType::Webhook) {
-            if let Some(path) = node.params.get("path").and_then(|p| p.as_str()) {
-                webhook_nodes_found.push(format!("'{}' -> '{}'", node.id, path));
-                tracing::debug!("  🔍 Checking webhook node '{}' with path: '{}'", node.id, path);
-                if path == webhook_path {
-                    tracing::debug!("✅ Found matching webhook node: '{}'", node.id);
-                    return Ok(node.id.clone());
-                }
-            } else {
-                tracing::debug!("  ⚠️ Webhook node '{}' has no path parameter", node.id);
-                webhook_nodes_found.push(format!("'{}' -> [no path]", node.id));
-            }
+/// Stop reasons that aren't valid header values are left out.
+fn termination_headers(run_id: &str, termination: &Termination) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let mut insert = |name: &'static str, value: &str| {
+        if let Ok(value) = HeaderValue::from_str(value) {
+            headers.insert(HeaderName::from_static(name), value);
+        }
+    };
+    insert("x-mechaway-run-id", run_id);
+    match termination {
+        Termination::StoppedEarly { node_id, reason } => {
+            insert("x-mechaway-run-status", "stopped_early");
+            insert("x-mechaway-stop-node", node_id);
+            insert("x-mechaway-stop-reason", reason);
         }
+        _ => insert("x-mechaway-run-status", "completed"),
     }
-
-    tracing::warn!(
-        "❌ No webhook node found for path '{}' in workflow '{}'. Available webhook nodes: [{}]",
-        webhook_path,
-        compiled_workflow.workflow.id,
-        webhook_nodes_found.join(", ")
-    );
-    
-    Err(StatusCode::NOT_FOUND)
+    headers
 }
 
 /// Helper function to register webhook routes dynamically
@@ -0,0 +1,181 @@
+//! WebSocket endpoints for WebSocketTrigger nodes
+//! 
+//! A WebSocketTrigger node serves ws://host/ws/{workflow_id}{path}. Every text
+//! or binary message a client sends starts the workflow
+//! (or, with an "aggregate" param, every aggregated run - see stream_aggregate)
+//! and the result is sent back on the same connection:
+//! 
+//! { "path": "/robot/sensors", "aggregate": { "mode": "sample", "every": 10 } }
+//! 
+//! Item: the JSON object message (or { "value": message }) plus
+//! "websocket": { "connection_id", "path" }. Replies are text messages with
+//! { "data": [...] } or { "error": "..." }. Aggregation state is per connection.
+//! 
+//! Every message also updates the state of its device (see runtime::devices):
+//! connect with ws://host/ws/robot-v2/robot/sensors?device_id=robot-7, or set
+//! "device_field" on the node. Devices of a connection go offline when it closes.
+
+use crate::api::webhooks::WebhookAppState;
+use crate::runtime::{
+    devices::DeviceTrigger,
+    stream_aggregate::{message_item, Aggregated, Aggregator, StreamAggregate},
+};
+use crate::workflow::types::ExecutionContext;
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use tokio::time::Instant;
+
+/// Where a connection's messages go
+struct Connection {
+    workflow_id: String,
+    node_id: String,
+    project: String,
+    path: String,
+    connection_id: String,
+    /// Device ID of messages without a "device_field" value
+    default_device_id: String,
+    device: DeviceTrigger,
+}
+
+/// Create WebSocketTrigger routes
+pub fn create_websocket_routes() -> Router<WebhookAppState> {
+    Router::new().route("/ws/{workflow_id}/{*path}", get(websocket_upgrade))
+}
+
+/// Accept a WebSocket connection for the workflow's WebSocketTrigger serving the path
+/// 
+/// GET /ws/{workflow_id}/{path} (Upgrade: websocket)
+async fn websocket_upgrade(
+    State(state): State<WebhookAppState>,
+    Path((workflow_id, path)): Path<(String, String)>,
+    Query(query): Query<HashMap<String, String>>,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    let path = format!("/{}", path.trim_start_matches('/'));
+    let trigger = state.app_state.registry.get_workflow_websocket_trigger(&workflow_id, &path)
+        .and_then(|(workflow, node_id)| {
+            let node = workflow.nodes.iter().find(|node| node.id == node_id)?;
+            Some((DeviceTrigger::from_node("websocket", &workflow.id, node), workflow.project.clone(), node.params.clone()))
+        });
+    let Some((device, project, params)) = trigger else {
+        return (StatusCode::NOT_FOUND, format!("Workflow '{}' has no WebSocketTrigger serving '{}'", workflow_id, path)).into_response();
+    };
+    let aggregate = match StreamAggregate::from_node_params(&params) {
+        Ok(aggregate) => aggregate,
+        Err(e) => {
+            tracing::error!("❌ WebSocketTrigger '{}' has an invalid aggregate: {}", device.node_id, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "WebSocketTrigger is misconfigured").into_response();
+        }
+    };
+
+    let connection_id = uuid::Uuid::new_v4().to_string();
+    let connection = Connection {
+        workflow_id: device.workflow_id.clone(),
+        node_id: device.node_id.clone(),
+        project,
+        path,
+        default_device_id: query.get("device_id").filter(|id| !id.is_empty()).cloned().unwrap_or_else(|| connection_id.clone()),
+        connection_id,
+        device,
+    };
+    upgrade.on_upgrade(move |socket| serve_connection(state, socket, connection, aggregate))
+}
+
+/// Receive messages until the client disconnects
+async fn serve_connection(state: WebhookAppState, mut socket: WebSocket, connection: Connection, aggregate: Option<StreamAggregate>) {
+    let Connection { workflow_id, path, connection_id, .. } = &connection;
+    let devices = &state.app_state.devices;
+    let mut aggregator = Aggregator::new(aggregate);
+    // Devices that sent messages on this connection (marked offline when it closes)
+    let mut seen_devices = BTreeSet::new();
+    tracing::info!("🔌 WebSocket connection {} opened on '{}' ({})", connection_id, path, workflow_id);
+    if connection.default_device_id != *connection_id {
+        devices.record_connected(&connection.project, &connection.device, &connection.default_device_id);
+        seen_devices.insert(connection.default_device_id.clone());
+    }
+
+    loop {
+        let received = match aggregator.deadline() {
+            Some(deadline) => tokio::select! {
+                message = socket.recv() => Some(message),
+                _ = tokio::time::sleep_until(deadline) => None,
+            },
+            None => Some(socket.recv().await),
+        };
+
+        let run = match received {
+            // Due average window
+            None => aggregator.flush(),
+            Some(Some(Ok(message))) => {
+                let payload = match message {
+                    Message::Text(text) => text.as_bytes().to_vec(),
+                    Message::Binary(bytes) => bytes.to_vec(),
+                    Message::Close(_) => break,
+                    // Pings are answered by axum
+                    Message::Ping(_) | Message::Pong(_) => continue,
+                };
+                let item = message_item(&payload, "websocket", json!({ "connection_id": connection_id, "path": path }));
+                let device_id = connection.device.device_id(&item, &connection.default_device_id);
+                devices.record_message(&connection.project, &connection.device, &device_id, &item);
+                seen_devices.insert(device_id);
+                aggregator.push(item, Instant::now())
+            }
+            Some(Some(Err(e))) => {
+                tracing::warn!("⚠️ WebSocket connection {} failed: {}", connection_id, e);
+                break;
+            }
+            Some(None) => break,
+        };
+
+        if let Some(run) = run {
+            let reply = execute(&state, &connection, run).await;
+            if socket.send(Message::Text(reply.to_string().into())).await.is_err() {
+                break;
+            }
+        }
+    }
+
+    devices.set_offline(&connection.project, &seen_devices);
+    tracing::info!("🔌 WebSocket connection {} closed", connection_id);
+}
+
+/// Run the workflow for one (aggregated) message and build the reply
+async fn execute(state: &WebhookAppState, connection: &Connection, run: Aggregated) -> Value {
+    let Connection { workflow_id, node_id, connection_id, .. } = connection;
+    let device_id = connection.device.device_id(&run.item, &connection.default_device_id);
+    let Some(compiled_workflow) = state.app_state.registry.get_workflow(workflow_id) else {
+        return json!({ "error": "workflow no longer exists" });
+    };
+
+    let mut context = ExecutionContext::from_array_data(
+        workflow_id.clone(), vec![run.item], compiled_workflow.workflow.project.clone());
+    context.metadata.insert("trigger_type".to_string(), json!("websocket"));
+    context.metadata.insert("trigger_node_id".to_string(), json!(node_id));
+    context.metadata.insert("websocket_connection_id".to_string(), json!(connection_id));
+    context.metadata.insert("device_id".to_string(), json!(device_id));
+    if !run.info.is_null() {
+        context.metadata.insert("aggregate".to_string(), run.info);
+    }
+
+    match state.app_state.queue.execute(&compiled_workflow, node_id, context).await {
+        Ok(Ok(result)) => json!({ "data": result.data }),
+        Ok(Err(e)) => {
+            tracing::error!("❌ WebSocket-triggered workflow failed: {} - Error: {}", workflow_id, e);
+            json!({ "error": "workflow execution failed" })
+        }
+        Err(shed) => {
+            tracing::warn!("🚦 Shedding WebSocket message for {}: {}", workflow_id, shed);
+            json!({ "error": shed.to_string() })
+        }
+    }
+}
@@ -1,25 +1,32 @@
-/// Workflow management REST API endpoints
-/// 
-/// Provides CRUD operations for workflow definitions with hot-reload support.
-/// All changes trigger immediate registry updates for zero-downtime deployments.
+//! Workflow management REST API endpoints
+//! 
+//! Provides CRUD operations for workflow definitions with hot-reload support.
+//! All changes trigger immediate registry updates for zero-downtime deployments.
 
 use crate::{
+    api::error::ApiError,
     workflow::{
+        canary::WorkflowCanary,
+        dependencies::WorkflowDependencies,
+        params::validate_workflow_params,
         registry::WorkflowRegistry,
+        snooze::WorkflowSnooze,
         storage::WorkflowStorage,
+        test_cases::{TestRunReport, WorkflowTestCase},
         types::Workflow,
     },
-    runtime::scheduler::CronSchedulerService,
+    project::{Project, ProjectDatabaseManager},
+    runtime::{debugger::DebugSessionManager, devices::DeviceRegistry, janitor::RetentionJanitor, maintenance::DatabaseMaintenance, maintenance_mode::MaintenanceMode, outbox::OutboxFlusher, mqtt::MqttTriggerService, opcua_trigger::OpcUaTriggerService, serial_trigger::SerialTriggerService, ble::BleTriggerService, profiler::ExecutionProfiler, queue::ExecutionQueue, scheduler::CronSchedulerService, test_runner::WorkflowTestRunner},
 };
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
     response::Json,
     routing::{get, post, put, delete},
     Router,
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
+use serde_json::{json, Map, Value};
 use std::sync::Arc;
 
 /// Application state containing shared resources
@@ -31,6 +38,34 @@ pub struct AppState {
     pub registry: Arc<WorkflowRegistry>,
     /// Cron scheduler service for background job management
     pub scheduler: Arc<CronSchedulerService>,
+    /// MQTT subscriber service for MQTTTrigger nodes
+    pub mqtt: Arc<MqttTriggerService>,
+    /// OPC UA subscription service for OpcUaTrigger nodes
+    pub opcua: Arc<OpcUaTriggerService>,
+    /// Serial port reader service for SerialTrigger nodes
+    pub serial: Arc<SerialTriggerService>,
+    /// BLE trigger service for BLETrigger nodes
+    pub ble: Arc<BleTriggerService>,
+    /// Device states recorded by the MQTT / WebSocket triggers
+    pub devices: Arc<DeviceRegistry>,
+    /// Project database manager for project settings and isolated storage
+    pub project_db_manager: Arc<ProjectDatabaseManager>,
+    /// Bounded execution queue shared by all trigger ingress paths
+    pub queue: Arc<ExecutionQueue>,
+    /// Open step-through debugger sessions
+    pub debugger: Arc<DebugSessionManager>,
+    /// Node and workflow timing statistics
+    pub profiler: Arc<ExecutionProfiler>,
+    /// Retention janitor (on-demand runs)
+    pub janitor: Arc<RetentionJanitor>,
+    /// SQLite maintenance job (metrics and on-demand runs)
+    pub maintenance: Arc<DatabaseMaintenance>,
+    /// Server-wide maintenance mode (rejects new trigger ingress)
+    pub maintenance_mode: Arc<MaintenanceMode>,
+    /// Store-and-forward outbox flusher (on-demand flushes)
+    pub outbox: Arc<OutboxFlusher>,
+    /// Process start time (uptime reporting)
+    pub started_at: std::time::Instant,
 }
 
 /// Response for workflow creation/update operations
@@ -46,6 +81,47 @@ pub struct CreateWorkflowRequest {
     pub workflow: Workflow,
 }
 
+/// Request/response body for a workflow's test cases
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WorkflowTestsBody {
+    pub tests: Vec<WorkflowTestCase>,
+}
+
+/// Request/response body for a workflow's variables
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WorkflowVariablesBody {
+    pub variables: Map<String, Value>,
+}
+
+/// Request body for a workflow's canary version
+#[derive(Debug, Deserialize)]
+pub struct WorkflowCanaryRequest {
+    /// Candidate definition; left out to only change the percentage of the current canary
+    #[serde(default)]
+    pub workflow: Option<Workflow>,
+    /// Share of webhook traffic routed to the candidate (0-100)
+    pub percent: u8,
+}
+
+/// Response body for a workflow's canary version
+#[derive(Debug, Serialize)]
+pub struct WorkflowCanaryResponse {
+    #[serde(flatten)]
+    pub canary: WorkflowCanary,
+    /// Per-version run counts (null while the workflow is inactive or the canary doesn't compile)
+    pub metrics: Option<Value>,
+}
+
+/// Query parameters of a workflow snooze
+#[derive(Debug, Deserialize)]
+pub struct SnoozeQuery {
+    /// Triggers stay paused until this time (RFC 3339)
+    pub until: DateTime<Utc>,
+    /// Shown next to the snooze in list endpoints (e.g. the maintenance ticket)
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
 /// Create workflow management routes
 /// 
 /// Sets up the REST API endpoints for workflow CRUD operations.
@@ -57,6 +133,19 @@ pub fn create_workflow_routes() -> Router<AppState> {
         .route("/api/workflows/{id}", get(get_workflow))
         .route("/api/workflows/{id}", put(update_workflow))
         .route("/api/workflows/{id}", delete(delete_workflow))
+        .route("/api/workflows/{id}/dependencies", get(get_workflow_dependencies))
+        .route("/api/workflows/{id}/variables", get(get_workflow_variables))
+        .route("/api/workflows/{id}/variables", put(update_workflow_variables))
+        .route("/api/workflows/{id}/canary", get(get_workflow_canary))
+        .route("/api/workflows/{id}/canary", put(update_workflow_canary))
+        .route("/api/workflows/{id}/canary", delete(delete_workflow_canary))
+        .route("/api/workflows/{id}/canary/promote", post(promote_workflow_canary))
+        .route("/api/workflows/{id}/snooze", post(snooze_workflow))
+        .route("/api/workflows/{id}/snooze", delete(delete_workflow_snooze))
+        .route("/api/workflows/{id}/skip-next", post(skip_next_workflow_run))
+        .route("/api/workflows/{id}/tests", get(get_workflow_tests))
+        .route("/api/workflows/{id}/tests", put(update_workflow_tests))
+        .route("/api/workflows/{id}/tests/run", post(run_workflow_tests))
 }
 
 /// Create a new workflow
@@ -66,37 +155,73 @@ pub fn create_workflow_routes() -> Router<AppState> {
 async fn create_workflow(
     State(state): State<AppState>,
     Json(payload): Json<CreateWorkflowRequest>,
-) -> Result<Json<WorkflowResponse>, StatusCode> {
+) -> Result<Json<WorkflowResponse>, ApiError> {
     let workflow = payload.workflow;
 
     // Validate workflow structure
-    if workflow.id.is_empty() || workflow.name.is_empty() {
-        return Err(StatusCode::BAD_REQUEST);
+    if workflow.id.is_empty() || workflow.name.is_empty() || !Project::is_valid_slug(&workflow.project) {
+        return Err(ApiError::ValidationFailed("Workflow needs an id, a name and a valid project slug".to_string()));
     }
+    validate_workflow_params(&workflow).map_err(|e| ApiError::ValidationFailed(e.to_string()))?;
+    // Compile before saving, so a broken definition is never stored or hot-loaded
+    state.registry.validate_workflow(&workflow).map_err(ApiError::invalid_workflow)?;
 
     // Check if workflow already exists
     match state.storage.get_workflow(&workflow.id).await {
-        Ok(Some(_)) => return Err(StatusCode::CONFLICT), // Workflow already exists
+        Ok(Some(_)) => return Err(ApiError::WorkflowExists(workflow.id)), // Workflow already exists
         Ok(None) => {} // Good, doesn't exist
-        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+        Err(e) => return Err(ApiError::internal("Failed to load workflow", e)),
     }
 
     // Save to persistent storage
     if let Err(e) = state.storage.save_workflow(&workflow).await {
         tracing::error!("Failed to save workflow: {}", e);
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        return Err(ApiError::Internal("Failed to save workflow".to_string()));
     }
 
     // Hot-reload into registry
     if let Err(e) = state.registry.reload_workflow(&workflow.id).await {
         tracing::error!("Failed to reload workflow into registry: {}", e);
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        return Err(ApiError::ValidationFailed(e.to_string()));
+    }
+
+    // Drafts have no live triggers
+    if !workflow.active {
+        tracing::info!("📝 Created draft workflow: {} ({})", workflow.id, workflow.name);
+        return Ok(Json(WorkflowResponse {
+            id: workflow.id.clone(),
+            message: format!("Draft workflow '{}' created successfully", workflow.name),
+        }));
     }
 
     // HOT-RELOAD: Register cron triggers with zero-downtime (Scalable pattern)
     if let Err(e) = state.scheduler.add_or_update_workflow_cron_triggers(&workflow).await {
         tracing::error!("Failed to register cron triggers for workflow {}: {}", workflow.id, e);
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        return Err(ApiError::Internal(format!("Failed to register cron triggers: {}", e)));
+    }
+
+    // HOT-RELOAD: Subscribe MQTT triggers
+    if let Err(e) = state.mqtt.add_or_update_workflow_mqtt_triggers(&workflow).await {
+        tracing::error!("Failed to register MQTT triggers for workflow {}: {}", workflow.id, e);
+        return Err(ApiError::Internal(format!("Failed to register MQTT triggers: {}", e)));
+    }
+
+    // HOT-RELOAD: Subscribe OPC UA triggers
+    if let Err(e) = state.opcua.add_or_update_workflow_opcua_triggers(&workflow).await {
+        tracing::error!("Failed to register OPC UA triggers for workflow {}: {}", workflow.id, e);
+        return Err(ApiError::Internal(format!("Failed to register OPC UA triggers: {}", e)));
+    }
+
+    // HOT-RELOAD: Open serial triggers
+    if let Err(e) = state.serial.add_or_update_workflow_serial_triggers(&workflow).await {
+        tracing::error!("Failed to register serial triggers for workflow {}: {}", workflow.id, e);
+        return Err(ApiError::Internal(format!("Failed to register serial triggers: {}", e)));
+    }
+
+    // HOT-RELOAD: Start BLE triggers
+    if let Err(e) = state.ble.add_or_update_workflow_ble_triggers(&workflow).await {
+        tracing::error!("Failed to register BLE triggers for workflow {}: {}", workflow.id, e);
+        return Err(ApiError::Internal(format!("Failed to register BLE triggers: {}", e)));
     }
 
     tracing::info!("🔥 Created workflow: {} ({}) with cron triggers", workflow.id, workflow.name);
@@ -110,15 +235,15 @@ async fn create_workflow(
 /// List all workflows
 /// 
 /// GET /api/workflows
-/// Returns: [{ "id": "...", "name": "...", "created_at": "...", "updated_at": "..." }]
+/// Returns: [{ "id": "...", "name": "...", "active": true, "created_at": "...", "updated_at": "..." }]
 async fn list_workflows(
     State(state): State<AppState>,
-) -> Result<Json<Value>, StatusCode> {
+) -> Result<Json<Value>, ApiError> {
     match state.storage.list_workflows().await {
         Ok(workflows) => Ok(Json(json!({ "workflows": workflows }))),
         Err(e) => {
             tracing::error!("Failed to list workflows: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            Err(ApiError::Internal("Failed to list workflows".to_string()))
         }
     }
 }
@@ -130,13 +255,37 @@ async fn list_workflows(
 async fn get_workflow(
     State(state): State<AppState>,
     Path(id): Path<String>,
-) -> Result<Json<Workflow>, StatusCode> {
+) -> Result<Json<Workflow>, ApiError> {
     match state.storage.get_workflow(&id).await {
         Ok(Some(workflow)) => Ok(Json(workflow)),
-        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Ok(None) => Err(ApiError::WorkflowNotFound(id)),
         Err(e) => {
             tracing::error!("Failed to get workflow {}: {}", id, e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            Err(ApiError::Internal("Failed to load workflow".to_string()))
+        }
+    }
+}
+
+/// Secrets, sub-workflows, external hosts, topics and tables a workflow references
+/// 
+/// GET /api/workflows/:id/dependencies
+/// Returns: { "secrets": [...], "sub_workflows": [...], "external_hosts": [...], "dynamic_hosts": [...],
+///            "topics": [...], "tables": [...] }
+/// Registered workflows answer from their compiled form; others are analyzed from storage.
+async fn get_workflow_dependencies(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Arc<WorkflowDependencies>>, ApiError> {
+    if let Some(compiled) = state.registry.get_workflow(&id) {
+        return Ok(Json(Arc::clone(&compiled.dependencies)));
+    }
+
+    match state.storage.get_workflow(&id).await {
+        Ok(Some(workflow)) => Ok(Json(Arc::new(WorkflowDependencies::analyze(&workflow)))),
+        Ok(None) => Err(ApiError::WorkflowNotFound(id)),
+        Err(e) => {
+            tracing::error!("Failed to get workflow {}: {}", id, e);
+            Err(ApiError::Internal("Failed to load workflow".to_string()))
         }
     }
 }
@@ -149,42 +298,50 @@ async fn update_workflow(
     State(state): State<AppState>,
     Path(id): Path<String>,
     Json(payload): Json<CreateWorkflowRequest>,
-) -> Result<Json<WorkflowResponse>, StatusCode> {
+) -> Result<Json<WorkflowResponse>, ApiError> {
     let mut workflow = payload.workflow;
     
     // Ensure the workflow ID matches the URL parameter
     workflow.id = id.clone();
 
     // Validate workflow structure
-    if workflow.name.is_empty() {
-        return Err(StatusCode::BAD_REQUEST);
+    if workflow.name.is_empty() || !Project::is_valid_slug(&workflow.project) {
+        return Err(ApiError::ValidationFailed("Workflow needs a name and a valid project slug".to_string()));
     }
+    validate_workflow_params(&workflow).map_err(|e| ApiError::ValidationFailed(e.to_string()))?;
+    state.registry.validate_workflow(&workflow).map_err(ApiError::invalid_workflow)?;
 
     // Check if workflow exists
     match state.storage.get_workflow(&id).await {
         Ok(Some(_)) => {} // Good, exists
-        Ok(None) => return Err(StatusCode::NOT_FOUND),
-        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+        Ok(None) => return Err(ApiError::WorkflowNotFound(id)),
+        Err(e) => return Err(ApiError::internal("Failed to load workflow", e)),
     }
 
     // Save updated workflow to persistent storage
     if let Err(e) = state.storage.save_workflow(&workflow).await {
         tracing::error!("Failed to update workflow: {}", e);
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        return Err(ApiError::Internal("Failed to save workflow".to_string()));
     }
 
     // Hot-reload into registry
     if let Err(e) = state.registry.reload_workflow(&workflow.id).await {
         tracing::error!("Failed to reload updated workflow into registry: {}", e);
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        return Err(ApiError::ValidationFailed(e.to_string()));
     }
 
-    // HOT-RELOAD: Update cron triggers with zero-downtime (Scalable pattern)
-    if let Err(e) = state.scheduler.add_or_update_workflow_cron_triggers(&workflow).await {
-        tracing::error!("Failed to hot-reload cron triggers for workflow {}: {}", workflow.id, e);
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    // Deactivated (or still draft): stop its triggers
+    if !workflow.active {
+        remove_workflow_triggers(&state, &workflow.id).await;
+        tracing::info!("📝 Saved draft workflow: {} ({})", workflow.id, workflow.name);
+        return Ok(Json(WorkflowResponse {
+            id: workflow.id.clone(),
+            message: format!("Draft workflow '{}' updated successfully", workflow.name),
+        }));
     }
 
+    register_workflow_triggers(&state, &workflow).await?;
+
     tracing::info!("🔥 Hot-reloaded workflow: {} ({}) with cron triggers", workflow.id, workflow.name);
 
     Ok(Json(WorkflowResponse {
@@ -193,6 +350,50 @@ async fn update_workflow(
     }))
 }
 
+/// HOT-RELOAD: (Re)register the background triggers of an active workflow (cron, MQTT, OPC UA, serial, BLE)
+async fn register_workflow_triggers(state: &AppState, workflow: &Workflow) -> Result<(), ApiError> {
+    // HOT-RELOAD: Update cron triggers with zero-downtime (Scalable pattern)
+    if let Err(e) = state.scheduler.add_or_update_workflow_cron_triggers(workflow).await {
+        tracing::error!("Failed to hot-reload cron triggers for workflow {}: {}", workflow.id, e);
+        return Err(ApiError::Internal(format!("Failed to register cron triggers: {}", e)));
+    }
+
+    // HOT-RELOAD: Re-subscribe MQTT triggers
+    if let Err(e) = state.mqtt.add_or_update_workflow_mqtt_triggers(workflow).await {
+        tracing::error!("Failed to hot-reload MQTT triggers for workflow {}: {}", workflow.id, e);
+        return Err(ApiError::Internal(format!("Failed to register MQTT triggers: {}", e)));
+    }
+
+    // HOT-RELOAD: Re-subscribe OPC UA triggers
+    if let Err(e) = state.opcua.add_or_update_workflow_opcua_triggers(workflow).await {
+        tracing::error!("Failed to hot-reload OPC UA triggers for workflow {}: {}", workflow.id, e);
+        return Err(ApiError::Internal(format!("Failed to register OPC UA triggers: {}", e)));
+    }
+
+    // HOT-RELOAD: Reopen serial triggers
+    if let Err(e) = state.serial.add_or_update_workflow_serial_triggers(workflow).await {
+        tracing::error!("Failed to hot-reload serial triggers for workflow {}: {}", workflow.id, e);
+        return Err(ApiError::Internal(format!("Failed to register serial triggers: {}", e)));
+    }
+
+    // HOT-RELOAD: Restart BLE triggers
+    if let Err(e) = state.ble.add_or_update_workflow_ble_triggers(workflow).await {
+        tracing::error!("Failed to hot-reload BLE triggers for workflow {}: {}", workflow.id, e);
+        return Err(ApiError::Internal(format!("Failed to register BLE triggers: {}", e)));
+    }
+
+    Ok(())
+}
+
+/// HOT-RELOAD: Stop the background triggers of a workflow (cron, MQTT, OPC UA, serial, BLE)
+async fn remove_workflow_triggers(state: &AppState, workflow_id: &str) {
+    state.scheduler.remove_workflow_cron_triggers(workflow_id).await;
+    state.mqtt.remove_workflow_mqtt_triggers(workflow_id).await;
+    state.opcua.remove_workflow_opcua_triggers(workflow_id).await;
+    state.serial.remove_workflow_serial_triggers(workflow_id).await;
+    state.ble.remove_workflow_ble_triggers(workflow_id).await;
+}
+
 /// Delete a workflow
 /// 
 /// DELETE /api/workflows/:id
@@ -200,14 +401,14 @@ async fn update_workflow(
 async fn delete_workflow(
     State(state): State<AppState>,
     Path(id): Path<String>,
-) -> Result<Json<Value>, StatusCode> {
+) -> Result<Json<Value>, ApiError> {
     // HOT-RELOAD: Remove cron triggers first (Scalable pattern)
-    state.scheduler.remove_workflow_cron_triggers(&id).await;
+    remove_workflow_triggers(&state, &id).await;
 
     // Remove from registry
     if let Err(e) = state.registry.remove_workflow(&id).await {
         tracing::error!("Failed to remove workflow from registry: {}", e);
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        return Err(ApiError::Internal("Failed to remove workflow from registry".to_string()));
     }
 
     // Remove from persistent storage
@@ -221,10 +422,405 @@ async fn delete_workflow(
             
             Ok(Json(json!({ "message": "Workflow deleted successfully" })))
         }
-        Ok(false) => Err(StatusCode::NOT_FOUND),
+        Ok(false) => Err(ApiError::WorkflowNotFound(id)),
         Err(e) => {
             tracing::error!("Failed to delete workflow: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            Err(ApiError::Internal("Failed to delete workflow".to_string()))
+        }
+    }
+}
+
+/// Get a workflow's variables
+/// 
+/// GET /api/workflows/:id/variables
+/// Returns: { "variables": { "base_url": "https://api.example.com", "retries": 3 } }
+async fn get_workflow_variables(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<WorkflowVariablesBody>, ApiError> {
+    match state.storage.get_workflow(&id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(ApiError::WorkflowNotFound(id)),
+        Err(e) => return Err(ApiError::internal("Failed to load workflow", e)),
+    }
+
+    match state.storage.get_variables(&id).await {
+        Ok(variables) => Ok(Json(WorkflowVariablesBody { variables })),
+        Err(e) => {
+            tracing::error!("Failed to get variables for workflow {}: {}", id, e);
+            Err(ApiError::Internal("Failed to load variables".to_string()))
+        }
+    }
+}
+
+/// Replace a workflow's variables (hot-reloaded into running workflows)
+/// 
+/// PUT /api/workflows/:id/variables
+/// Body: { "variables": { "base_url": "https://api.example.com", "retries": 3 } }
+/// Variables survive workflow updates; nodes read them as `$vars.name` pins and Lua `vars.name`.
+async fn update_workflow_variables(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(payload): Json<WorkflowVariablesBody>,
+) -> Result<Json<WorkflowResponse>, ApiError> {
+    // Names are pin path segments, so they can't be empty or contain dots
+    if payload.variables.keys().any(|name| name.is_empty() || name.contains('.')) {
+        return Err(ApiError::ValidationFailed("Variable names must be non-empty and contain no dots".to_string()));
+    }
+
+    match state.storage.get_workflow(&id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(ApiError::WorkflowNotFound(id)),
+        Err(e) => return Err(ApiError::internal("Failed to load workflow", e)),
+    }
+
+    if let Err(e) = state.storage.save_variables(&id, &payload.variables).await {
+        tracing::error!("Failed to save variables for workflow {}: {}", id, e);
+        return Err(ApiError::Internal("Failed to save variables".to_string()));
+    }
+
+    if let Err(e) = state.registry.reload_workflow(&id).await {
+        tracing::error!("Failed to reload workflow into registry: {}", e);
+        return Err(ApiError::ValidationFailed(e.to_string()));
+    }
+
+    tracing::info!("📌 Saved {} variable(s) for workflow: {}", payload.variables.len(), id);
+
+    Ok(Json(WorkflowResponse {
+        id,
+        message: format!("{} variable(s) saved", payload.variables.len()),
+    }))
+}
+
+/// Get a workflow's canary version with per-version metrics
+/// 
+/// GET /api/workflows/:id/canary
+/// Returns: { "id": "...", "percent": 10, "workflow": {...}, "created_at": "...",
+///            "metrics": { "stable": { "runs", "completed", "stopped", "failed", "success_rate", "avg_duration_ms" }, "canary": {...} } }
+async fn get_workflow_canary(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<WorkflowCanaryResponse>, ApiError> {
+    let canary = match state.storage.get_canary(&id).await {
+        Ok(Some(canary)) => canary,
+        Ok(None) => return Err(ApiError::CanaryNotFound(id)),
+        Err(e) => return Err(ApiError::internal("Failed to load canary", e)),
+    };
+
+    let metrics = state.registry.get_workflow(&id)
+        .and_then(|compiled| compiled.canary)
+        .filter(|compiled| compiled.id == canary.id)
+        .map(|compiled| compiled.metrics.to_json());
+
+    Ok(Json(WorkflowCanaryResponse { canary, metrics }))
+}
+
+/// Start (or adjust) a canary rollout of a workflow
+/// 
+/// PUT /api/workflows/:id/canary
+/// Body: { "workflow": { ... }, "percent": 10 }
+/// The candidate serves `percent` of the webhook requests; cron, MQTT and other triggers keep
+/// running the stable version. A new "workflow" starts a new rollout (fresh metrics); leaving
+/// it out only changes the percentage of the current one.
+async fn update_workflow_canary(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(payload): Json<WorkflowCanaryRequest>,
+) -> Result<Json<WorkflowResponse>, ApiError> {
+    if payload.percent > 100 {
+        return Err(ApiError::ValidationFailed("Canary percent must be between 0 and 100".to_string()));
+    }
+
+    let stable = match state.storage.get_workflow(&id).await {
+        Ok(Some(workflow)) => workflow,
+        Ok(None) => return Err(ApiError::WorkflowNotFound(id)),
+        Err(e) => return Err(ApiError::internal("Failed to load workflow", e)),
+    };
+
+    let canary = match payload.workflow {
+        Some(mut workflow) => {
+            // Same workflow, so it keeps the stable version's ID, project and active flag
+            workflow.id = id.clone();
+            workflow.active = stable.active;
+            if workflow.name.is_empty() || workflow.project != stable.project {
+                return Err(ApiError::ValidationFailed(format!("Canary version needs a name and project '{}'", stable.project)));
+            }
+            if !workflow.nodes.iter().any(|node| matches!(node.node_type, crate::workflow::NodeType::Webhook)) {
+                return Err(ApiError::ValidationFailed("Canary version needs a Webhook node (only webhook traffic is split)".to_string()));
+            }
+            validate_workflow_params(&workflow).map_err(|e| ApiError::ValidationFailed(e.to_string()))?;
+            state.registry.validate_workflow(&workflow).map_err(ApiError::invalid_workflow)?;
+            WorkflowCanary::new(workflow, payload.percent)
+        }
+        None => match state.storage.get_canary(&id).await {
+            Ok(Some(canary)) => WorkflowCanary { percent: payload.percent, ..canary },
+            Ok(None) => return Err(ApiError::CanaryNotFound(id)),
+            Err(e) => return Err(ApiError::internal("Failed to load canary", e)),
+        },
+    };
+
+    if let Err(e) = state.storage.save_canary(&id, &canary).await {
+        tracing::error!("Failed to save canary for workflow {}: {}", id, e);
+        return Err(ApiError::Internal("Failed to save canary".to_string()));
+    }
+
+    if let Err(e) = state.registry.reload_workflow(&id).await {
+        tracing::error!("Failed to reload workflow into registry: {}", e);
+        return Err(ApiError::ValidationFailed(e.to_string()));
+    }
+
+    tracing::info!("🐤 Canary {} of workflow {} receives {}% of webhook traffic", canary.id, id, canary.percent);
+
+    Ok(Json(WorkflowResponse {
+        id,
+        message: format!("Canary {} receives {}% of webhook traffic", canary.id, canary.percent),
+    }))
+}
+
+/// Promote a workflow's canary version to stable
+/// 
+/// POST /api/workflows/:id/canary/promote
+/// The candidate replaces the workflow definition (all triggers run it) and the rollout ends.
+async fn promote_workflow_canary(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<WorkflowResponse>, ApiError> {
+    let stable = match state.storage.get_workflow(&id).await {
+        Ok(Some(workflow)) => workflow,
+        Ok(None) => return Err(ApiError::WorkflowNotFound(id)),
+        Err(e) => return Err(ApiError::internal("Failed to load workflow", e)),
+    };
+    let canary = match state.storage.get_canary(&id).await {
+        Ok(Some(canary)) => canary,
+        Ok(None) => return Err(ApiError::CanaryNotFound(id)),
+        Err(e) => return Err(ApiError::internal("Failed to load canary", e)),
+    };
+
+    // Activation stays with the stable version (it may have changed since the rollout started)
+    let mut workflow = canary.workflow;
+    workflow.active = stable.active;
+    state.registry.validate_workflow(&workflow).map_err(ApiError::invalid_workflow)?;
+
+    if let Err(e) = state.storage.save_workflow(&workflow).await {
+        tracing::error!("Failed to save promoted workflow: {}", e);
+        return Err(ApiError::Internal("Failed to save workflow".to_string()));
+    }
+    if let Err(e) = state.storage.delete_canary(&id).await {
+        return Err(ApiError::internal("Failed to delete canary", e));
+    }
+
+    if let Err(e) = state.registry.reload_workflow(&id).await {
+        tracing::error!("Failed to reload promoted workflow into registry: {}", e);
+        return Err(ApiError::ValidationFailed(e.to_string()));
+    }
+    if workflow.active {
+        register_workflow_triggers(&state, &workflow).await?;
+    }
+
+    tracing::info!("🚀 Promoted canary {} of workflow {} to stable", canary.id, id);
+
+    Ok(Json(WorkflowResponse {
+        id,
+        message: format!("Canary {} promoted to stable", canary.id),
+    }))
+}
+
+/// Roll back a canary: all traffic returns to the stable version
+/// 
+/// DELETE /api/workflows/:id/canary
+async fn delete_workflow_canary(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    match state.storage.delete_canary(&id).await {
+        Ok(true) => {}
+        Ok(false) => return Err(ApiError::CanaryNotFound(id)),
+        Err(e) => return Err(ApiError::internal("Failed to delete canary", e)),
+    }
+
+    if let Err(e) = state.registry.reload_workflow(&id).await {
+        tracing::error!("Failed to reload workflow into registry: {}", e);
+        return Err(ApiError::ValidationFailed(e.to_string()));
+    }
+
+    tracing::info!("↩️ Rolled back canary of workflow {}", id);
+
+    Ok(Json(json!({ "message": "Canary rolled back" })))
+}
+
+/// Snooze a workflow: pause all of its triggers until a given time (maintenance windows)
+/// 
+/// POST /api/workflows/:id/snooze?until=2026-10-17T06:00:00Z&reason=...
+/// Until then scheduled runs are skipped, webhooks answer 503 workflow_snoozed with
+/// Retry-After, queued runs are rejected and MQTT, OPC UA, serial, BLE and event
+/// triggers drop their input. Snoozing again moves the end; list endpoints show the snooze.
+async fn snooze_workflow(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<SnoozeQuery>,
+) -> Result<Json<Value>, ApiError> {
+    if query.until <= Utc::now() {
+        return Err(ApiError::ValidationFailed("Snooze 'until' must be in the future".to_string()));
+    }
+
+    let snooze = WorkflowSnooze {
+        until: Some(query.until),
+        reason: query.reason,
+        ..load_workflow_snooze(&state, &id).await?
+    };
+    save_workflow_snooze(&state, &id, &snooze).await?;
+
+    tracing::info!("💤 Workflow {} snoozed until {}", id, query.until);
+
+    Ok(Json(json!({ "id": id, "snooze": snooze })))
+}
+
+/// Skip the next scheduled (cron or polling) run of a workflow, once
+/// 
+/// POST /api/workflows/:id/skip-next
+/// Other triggers keep running; the flag clears when the skipped run would have started.
+async fn skip_next_workflow_run(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    let snooze = WorkflowSnooze {
+        skip_next: true,
+        ..load_workflow_snooze(&state, &id).await?
+    };
+    save_workflow_snooze(&state, &id, &snooze).await?;
+
+    tracing::info!("⏭️ Next scheduled run of workflow {} will be skipped", id);
+
+    Ok(Json(json!({ "id": id, "snooze": snooze })))
+}
+
+/// Wake a workflow: end its snooze and drop a pending one-time skip
+/// 
+/// DELETE /api/workflows/:id/snooze
+async fn delete_workflow_snooze(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    match state.storage.get_workflow(&id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(ApiError::WorkflowNotFound(id)),
+        Err(e) => return Err(ApiError::internal("Failed to load workflow", e)),
+    }
+    if let Err(e) = state.storage.delete_snooze(&id).await {
+        return Err(ApiError::internal("Failed to delete snooze", e));
+    }
+
+    if let Err(e) = state.registry.reload_workflow(&id).await {
+        tracing::error!("Failed to reload workflow into registry: {}", e);
+        return Err(ApiError::ValidationFailed(e.to_string()));
+    }
+
+    tracing::info!("⏰ Workflow {} woke up", id);
+
+    Ok(Json(json!({ "message": "Snooze cleared" })))
+}
+
+/// Current snooze state of an existing workflow (an expired snooze counts as none)
+async fn load_workflow_snooze(state: &AppState, id: &str) -> Result<WorkflowSnooze, ApiError> {
+    match state.storage.get_workflow(id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(ApiError::WorkflowNotFound(id.to_string())),
+        Err(e) => return Err(ApiError::internal("Failed to load workflow", e)),
+    }
+    match state.storage.get_snooze(id).await {
+        Ok(snooze) => Ok(snooze.filter(WorkflowSnooze::is_active).unwrap_or_default()),
+        Err(e) => Err(ApiError::internal("Failed to load snooze", e)),
+    }
+}
+
+/// Store a workflow's snooze state and hot-reload it into the registry
+async fn save_workflow_snooze(state: &AppState, id: &str, snooze: &WorkflowSnooze) -> Result<(), ApiError> {
+    if let Err(e) = state.storage.save_snooze(id, snooze).await {
+        return Err(ApiError::internal("Failed to save snooze", e));
+    }
+    if let Err(e) = state.registry.reload_workflow(id).await {
+        tracing::error!("Failed to reload workflow into registry: {}", e);
+        return Err(ApiError::ValidationFailed(e.to_string()));
+    }
+    Ok(())
+}
+
+/// Get the test cases attached to a workflow
+/// 
+/// GET /api/workflows/:id/tests
+/// Returns: { "tests": [{ "name": "...", "input": {...}, "mocks": {...}, "assertions": [...] }] }
+async fn get_workflow_tests(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<WorkflowTestsBody>, ApiError> {
+    match state.storage.get_workflow(&id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(ApiError::WorkflowNotFound(id)),
+        Err(e) => return Err(ApiError::internal("Failed to load workflow", e)),
+    }
+
+    match state.storage.get_tests(&id).await {
+        Ok(tests) => Ok(Json(WorkflowTestsBody { tests })),
+        Err(e) => {
+            tracing::error!("Failed to get tests for workflow {}: {}", id, e);
+            Err(ApiError::Internal("Failed to load tests".to_string()))
         }
     }
 }
+
+/// Replace the test cases attached to a workflow
+/// 
+/// PUT /api/workflows/:id/tests
+/// Body: { "tests": [{ "name": "...", "input": {...}, "mocks": {...}, "assertions": [...] }] }
+async fn update_workflow_tests(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(payload): Json<WorkflowTestsBody>,
+) -> Result<Json<WorkflowResponse>, ApiError> {
+    // Test names identify results, so they must be present and unique
+    let mut names = std::collections::HashSet::new();
+    if payload.tests.iter().any(|t| t.name.is_empty() || !names.insert(t.name.as_str())) {
+        return Err(ApiError::ValidationFailed("Test names must be non-empty and unique".to_string()));
+    }
+
+    match state.storage.get_workflow(&id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(ApiError::WorkflowNotFound(id)),
+        Err(e) => return Err(ApiError::internal("Failed to load workflow", e)),
+    }
+
+    if let Err(e) = state.storage.save_tests(&id, &payload.tests).await {
+        tracing::error!("Failed to save tests for workflow {}: {}", id, e);
+        return Err(ApiError::Internal("Failed to save tests".to_string()));
+    }
+
+    tracing::info!("🧪 Saved {} test case(s) for workflow: {}", payload.tests.len(), id);
+
+    Ok(Json(WorkflowResponse {
+        id,
+        message: format!("{} test case(s) saved", payload.tests.len()),
+    }))
+}
+
+/// Run a workflow's test cases in an isolated sandbox
+/// 
+/// POST /api/workflows/:id/tests/run
+/// Returns: { "workflow_id": "...", "passed": 2, "failed": 1, "results": [{ "name": "...", "passed": false, "assertions": [...] }] }
+async fn run_workflow_tests(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<TestRunReport>, ApiError> {
+    let workflow = state.registry.get_workflow(&id).ok_or_else(|| ApiError::WorkflowNotFound(id.clone()))?;
+
+    let tests = state.storage.get_tests(&id).await.map_err(|e| {
+        tracing::error!("Failed to load tests for workflow {}: {}", id, e);
+        ApiError::Internal("Failed to load tests".to_string())
+    })?;
+
+    let runner = WorkflowTestRunner::new(Arc::clone(&state.registry)).map_err(|e| {
+        tracing::error!("Failed to create test runner: {}", e);
+        ApiError::Internal("Failed to create test runner".to_string())
+    })?;
+
+    Ok(Json(runner.run(&workflow, &tests).await))
+}
@@ -0,0 +1,232 @@
+//! Built-in load harness for `mechaway bench` and the criterion benches
+//! 
+//! Boots the full application in-process against a throwaway data directory,
+//! installs a fixed set of benchmark workflows through the workflow API, and
+//! drives webhook requests straight into the Axum router (no network hop), so
+//! the numbers reflect engine cost rather than socket overhead.
+//! 
+//! Scenarios:
+//! - webhook:     Webhook -> trivial FunLogic (dispatch + engine overhead)
+//! - funlogic:    Webhook -> CPU-bound FunLogic script (Lua throughput)
+//! - simpletable: Webhook -> SimpleTableWriter (SQLite insert rate)
+
+use crate::testing::{temp_data_dir, test_config, TestApp, TestClient};
+use anyhow::Result;
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// All benchmark scenario names, in report order
+pub const SCENARIOS: &[&str] = &["webhook", "funlogic", "simpletable"];
+
+/// Load harness options
+#[derive(Debug, Clone)]
+pub struct BenchOptions {
+    /// Requests per scenario
+    pub requests: usize,
+    /// Concurrent in-flight requests
+    pub concurrency: usize,
+    /// Scenarios to run (see `SCENARIOS`)
+    pub scenarios: Vec<String>,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        Self {
+            requests: 2000,
+            concurrency: 32,
+            scenarios: SCENARIOS.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+/// Latency and throughput results for one scenario
+#[derive(Debug, Clone)]
+pub struct ScenarioReport {
+    pub name: String,
+    pub requests: usize,
+    pub errors: usize,
+    pub elapsed: Duration,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl ScenarioReport {
+    /// Completed requests per second
+    pub fn throughput(&self) -> f64 {
+        self.requests as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+impl std::fmt::Display for ScenarioReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:<12} {:>7} req {:>5} err {:>10.1} req/s   p50 {:>9.3?}  p95 {:>9.3?}  p99 {:>9.3?}  max {:>9.3?}",
+            self.name, self.requests, self.errors, self.throughput(), self.p50, self.p95, self.p99, self.max)
+    }
+}
+
+/// Cloneable handle that sends benchmark requests into the router
+#[derive(Clone)]
+pub struct BenchClient {
+    client: TestClient,
+}
+
+impl BenchClient {
+    /// Send a single request for a scenario, returning the response status
+    pub async fn request(&self, scenario: &str, seq: usize) -> Result<StatusCode> {
+        let (workflow_id, path, body) = match scenario {
+            "webhook" => ("bench-webhook", "/ping", json!({ "seq": seq })),
+            "funlogic" => ("bench-funlogic", "/compute", json!({ "seq": seq, "n": 200 })),
+            "simpletable" => ("bench-simpletable", "/insert",
+                json!({ "name": format!("event-{}", seq), "value": seq })),
+            other => return Err(anyhow::anyhow!("Unknown bench scenario: {}", other)),
+        };
+        Ok(self.client.webhook(workflow_id, path, body).await?.status)
+    }
+}
+
+/// In-process application with the benchmark workflows installed
+/// 
+/// Uses file-backed databases (unlike test fixtures) so insert rates are
+/// realistic. The data directory is removed when the harness is dropped.
+pub struct BenchHarness {
+    app: TestApp,
+}
+
+impl BenchHarness {
+    /// Boot the application in a fresh temporary data directory
+    pub async fn start() -> Result<Self> {
+        let mut config = test_config(&temp_data_dir("mechaway-bench"));
+        config.database.in_memory = false;
+
+        let app = TestApp::spawn_with(config).await?;
+        for workflow in bench_workflows() {
+            app.create_workflow(workflow).await?;
+        }
+
+        Ok(Self { app })
+    }
+
+    /// Request handle for driving scenarios (e.g. from criterion benches)
+    pub fn client(&self) -> BenchClient {
+        BenchClient { client: self.app.client() }
+    }
+
+    /// Run a scenario with the given request count and concurrency
+    pub async fn run_scenario(&self, scenario: &str, requests: usize, concurrency: usize) -> Result<ScenarioReport> {
+        let next = Arc::new(AtomicUsize::new(0));
+        let errors = Arc::new(AtomicUsize::new(0));
+        let started = Instant::now();
+
+        let mut workers = Vec::new();
+        for _ in 0..concurrency.max(1) {
+            let client = self.client();
+            let scenario = scenario.to_string();
+            let next = Arc::clone(&next);
+            let errors = Arc::clone(&errors);
+            workers.push(tokio::spawn(async move {
+                let mut latencies = Vec::new();
+                loop {
+                    let seq = next.fetch_add(1, Ordering::Relaxed);
+                    if seq >= requests {
+                        break;
+                    }
+                    let request_start = Instant::now();
+                    match client.request(&scenario, seq).await {
+                        Ok(status) if status.is_success() => {}
+                        _ => {
+                            errors.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                    latencies.push(request_start.elapsed());
+                }
+                latencies
+            }));
+        }
+
+        let mut latencies = Vec::with_capacity(requests);
+        for worker in workers {
+            latencies.extend(worker.await?);
+        }
+        let elapsed = started.elapsed();
+        latencies.sort_unstable();
+
+        Ok(ScenarioReport {
+            name: scenario.to_string(),
+            requests: latencies.len(),
+            errors: errors.load(Ordering::Relaxed),
+            elapsed,
+            p50: percentile(&latencies, 0.50),
+            p95: percentile(&latencies, 0.95),
+            p99: percentile(&latencies, 0.99),
+            max: latencies.last().copied().unwrap_or_default(),
+        })
+    }
+}
+
+/// Run the load harness and return one report per scenario
+pub async fn run(options: &BenchOptions) -> Result<Vec<ScenarioReport>> {
+    let harness = BenchHarness::start().await?;
+    let mut reports = Vec::new();
+
+    for scenario in &options.scenarios {
+        // Warm up pools, Lua sandboxes and table creation before measuring
+        harness.run_scenario(scenario, options.concurrency.max(1), options.concurrency).await?;
+        reports.push(harness.run_scenario(scenario, options.requests, options.concurrency).await?);
+    }
+
+    Ok(reports)
+}
+
+/// Benchmark workflow definitions installed by the harness
+pub fn bench_workflows() -> Vec<Value> {
+    vec![
+        json!({
+            "id": "bench-webhook",
+            "name": "Bench: webhook dispatch",
+            "nodes": [
+                { "id": "hook", "node_type": "Webhook", "params": { "path": "/ping" } },
+                { "id": "echo", "node_type": "FunLogic", "params": { "script": "return data[1]" } }
+            ],
+            "edges": [{ "from": "hook", "to": "echo" }]
+        }),
+        json!({
+            "id": "bench-funlogic",
+            "name": "Bench: FunLogic throughput",
+            "nodes": [
+                { "id": "hook", "node_type": "Webhook", "params": { "path": "/compute" } },
+                { "id": "compute", "node_type": "FunLogic", "params": { "script":
+                    "local acc = 0 for i = 1, data[1].n do acc = acc + (i * i) % 7 end return { seq = data[1].seq, acc = acc }" } }
+            ],
+            "edges": [{ "from": "hook", "to": "compute" }]
+        }),
+        json!({
+            "id": "bench-simpletable",
+            "name": "Bench: SimpleTableWriter inserts",
+            "nodes": [
+                { "id": "hook", "node_type": "Webhook", "params": { "path": "/insert" } },
+                { "id": "write", "node_type": "SimpleTableWriter",
+                  "params": { "table": "bench_events", "columns": ["name", "value"] },
+                  "inputs": ["$json.name", "$json.value"] }
+            ],
+            "edges": [{ "from": "hook", "to": "write" }]
+        }),
+    ]
+}
+
+/// Nearest-rank percentile over sorted latencies
+fn percentile(sorted: &[Duration], quantile: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = ((sorted.len() as f64 * quantile).ceil() as usize).clamp(1, sorted.len());
+    sorted[rank - 1]
+}
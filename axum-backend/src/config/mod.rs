@@ -1,7 +1,8 @@
-/// Configuration management for Mechaway engine
-/// 
-/// Handles server configuration, database connections, and runtime parameters.
+//! Configuration management for Mechaway engine
+//! 
+//! Handles server configuration, database connections, and runtime parameters.
 
+use crate::runtime::spill::DEFAULT_SPILL_THRESHOLD_BYTES;
 use serde::{Deserialize, Serialize};
 
 /// Main application configuration
@@ -11,6 +12,16 @@ pub struct Config {
     pub server: ServerConfig,
     /// Database configuration  
     pub database: DatabaseConfig,
+    /// Execution queue and load-shedding configuration
+    pub execution: ExecutionConfig,
+    /// Log output configuration
+    pub logging: LoggingConfig,
+    /// Failure alerting monitor configuration
+    pub alerts: AlertsConfig,
+    /// Background maintenance (retention janitor) configuration
+    pub maintenance: MaintenanceConfig,
+    /// Encryption of stored credentials
+    pub security: SecurityConfig,
 }
 
 /// HTTP server configuration
@@ -28,6 +39,123 @@ pub struct DatabaseConfig {
     /// Base directory for all project databases (default: "data")
     /// Creates: {project_slug}_project.db, {project_slug}_simpletable.db
     pub project_data_dir: String,
+    /// Keep all project databases in memory (`sqlite::memory:`) instead of files
+    /// Intended for tests; data is lost when the process exits
+    pub in_memory: bool,
+    /// Maximum external PostgreSQL pools (one per connection string)
+    pub external_pg_max_pools: usize,
+    /// Connections per external PostgreSQL pool
+    pub external_pg_pool_size: usize,
+    /// Seconds before an unused external PostgreSQL pool is closed
+    pub external_pg_idle_secs: u64,
+    /// Codec for stored workflow definitions and execution payloads: "zstd", "gzip" or "none"
+    pub storage_compression: String,
+    /// Follower mode: open the project databases of a primary sharing the data directory
+    /// read-only and serve read requests only (no triggers, schedulers or background writers)
+    pub follower: bool,
+    /// Seconds between workflow registry reloads of a follower (the primary's saves show up then)
+    pub follower_refresh_secs: u64,
+}
+
+/// Execution queue configuration for backpressure at trigger ingress
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionConfig {
+    /// Maximum workflow executions running at the same time
+    pub max_concurrent_executions: usize,
+    /// Queue depth at which new webhook requests get 429 Too Many Requests
+    pub throttle_queue_depth: usize,
+    /// Queue depth at which new webhook requests get 503 Service Unavailable
+    pub max_queue_depth: usize,
+    /// Retry-After hint (seconds) returned with shed requests
+    pub retry_after_secs: u64,
+    /// Node outputs larger than this (bytes of serialized JSON) are spilled
+    /// to disk between nodes; 0 disables spilling. Workflows may override it.
+    pub spill_threshold_bytes: usize,
+    /// Record every top-level run and its trigger payload (execution history, replay)
+    pub record_history: bool,
+    /// Seconds between profiling snapshots persisted to the default project; 0 disables
+    pub stats_persist_interval_secs: u64,
+    /// Seconds between outbox flushes (store-and-forward replays); 0 disables the flusher
+    pub outbox_flush_interval_secs: u64,
+    /// Window (seconds) scheduled runs are smeared over by a stable per-job offset; 0 disables
+    pub schedule_spread_secs: u64,
+    /// Execution slot weights per project ("shop=4,analytics=2"); unlisted projects weigh 1
+    pub project_weights: Option<String>,
+    /// Memory budget of a run (bytes of any node output, FunLogic VM allocations); 0 = unlimited
+    pub max_run_memory_bytes: usize,
+    /// CPU budget of a run (milliseconds its nodes spend computing); 0 = unlimited
+    pub max_run_cpu_ms: u64,
+}
+
+/// Log output configuration
+/// 
+/// The filter can be changed at runtime via PUT /api/admin/log-level.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggingConfig {
+    /// Output format: "pretty" (human readable) or "json" (one object per line)
+    pub format: String,
+    /// `RUST_LOG`-style filter (e.g. "info,mechaway::runtime=debug")
+    pub filter: String,
+    /// Write logs to rotating files in this directory instead of stdout
+    pub file_dir: Option<String>,
+    /// Log file name prefix (files are named {prefix}.{date})
+    pub file_prefix: String,
+    /// File rotation: "minutely", "hourly", "daily" or "never"
+    pub rotation: String,
+    /// Rotated log files to keep (oldest are deleted); None keeps all
+    pub max_files: Option<usize>,
+}
+
+/// Failure alerting monitor configuration
+/// 
+/// Alert rules themselves are per project (settings "alerting").
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertsConfig {
+    /// Seconds between rule evaluations; 0 disables the monitor
+    pub check_interval_secs: u64,
+    /// SMTP relay ("host:port") for email targets (plain SMTP, no auth)
+    pub smtp_addr: Option<String>,
+    /// Sender address for alert emails
+    pub smtp_from: String,
+}
+
+/// Background maintenance configuration
+/// 
+/// Retention limits themselves are per project (settings "retention"); SQLite
+/// maintenance (incremental vacuum, ANALYZE, WAL checkpoint) covers every project.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceConfig {
+    /// Seconds between retention janitor runs; 0 disables the janitor
+    pub janitor_interval_secs: u64,
+    /// Minimum seconds between SQLite maintenance passes; 0 disables them
+    pub db_interval_secs: u64,
+    /// UTC window for SQLite maintenance ("02:00-04:00"); None = any time
+    pub db_window: Option<String>,
+}
+
+/// Encryption of stored credentials
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SecurityConfig {
+    /// AES-256 master key (32 bytes as base64 or 64 hex chars) sealing stored
+    /// OAuth tokens; features storing credentials are disabled without it
+    #[serde(skip_serializing)]
+    pub master_key: Option<String>,
+}
+
+impl std::fmt::Debug for SecurityConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecurityConfig")
+            .field("master_key", &self.master_key.as_ref().map(|_| "REDACTED"))
+            .finish()
+    }
+}
+
+/// Read a numeric environment variable with a default fallback
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
 }
 
 impl Default for Config {
@@ -44,6 +172,51 @@ impl Default for Config {
             database: DatabaseConfig {
                 project_data_dir: std::env::var("MECHAWAY_DATA_DIR")
                     .unwrap_or_else(|_| "data".to_string()),
+                in_memory: env_or("MECHAWAY_IN_MEMORY_DB", false),
+                external_pg_max_pools: env_or("MECHAWAY_PG_MAX_POOLS", 32),
+                external_pg_pool_size: env_or("MECHAWAY_PG_POOL_SIZE", 8),
+                external_pg_idle_secs: env_or("MECHAWAY_PG_POOL_IDLE_SECS", 300),
+                storage_compression: std::env::var("MECHAWAY_STORAGE_COMPRESSION").unwrap_or_else(|_| "zstd".to_string()),
+                follower: env_or("MECHAWAY_FOLLOWER", false),
+                follower_refresh_secs: env_or("MECHAWAY_FOLLOWER_REFRESH_SECS", 30),
+            },
+            execution: ExecutionConfig {
+                max_concurrent_executions: env_or("MECHAWAY_MAX_CONCURRENT_EXECUTIONS", 64),
+                throttle_queue_depth: env_or("MECHAWAY_THROTTLE_QUEUE_DEPTH", 512),
+                max_queue_depth: env_or("MECHAWAY_MAX_QUEUE_DEPTH", 1024),
+                retry_after_secs: env_or("MECHAWAY_RETRY_AFTER_SECS", 5),
+                spill_threshold_bytes: env_or("MECHAWAY_SPILL_THRESHOLD_BYTES", DEFAULT_SPILL_THRESHOLD_BYTES),
+                record_history: env_or("MECHAWAY_RECORD_EXECUTIONS", true),
+                stats_persist_interval_secs: env_or("MECHAWAY_STATS_PERSIST_SECS", 60),
+                outbox_flush_interval_secs: env_or("MECHAWAY_OUTBOX_FLUSH_SECS", 15),
+                schedule_spread_secs: env_or("MECHAWAY_SCHEDULE_SPREAD_SECS", 0),
+                project_weights: std::env::var("MECHAWAY_PROJECT_WEIGHTS").ok().filter(|weights| !weights.is_empty()),
+                max_run_memory_bytes: env_or("MECHAWAY_MAX_RUN_MEMORY_BYTES", 0),
+                max_run_cpu_ms: env_or("MECHAWAY_MAX_RUN_CPU_MS", 0),
+            },
+            logging: LoggingConfig {
+                format: std::env::var("MECHAWAY_LOG_FORMAT").unwrap_or_else(|_| "pretty".to_string()),
+                filter: std::env::var("MECHAWAY_LOG")
+                    .or_else(|_| std::env::var("RUST_LOG"))
+                    .unwrap_or_else(|_| "info".to_string()),
+                file_dir: std::env::var("MECHAWAY_LOG_DIR").ok(),
+                file_prefix: std::env::var("MECHAWAY_LOG_FILE_PREFIX").unwrap_or_else(|_| "mechaway.log".to_string()),
+                rotation: std::env::var("MECHAWAY_LOG_ROTATION").unwrap_or_else(|_| "daily".to_string()),
+                max_files: std::env::var("MECHAWAY_LOG_MAX_FILES").ok().and_then(|v| v.parse().ok()),
+            },
+            alerts: AlertsConfig {
+                check_interval_secs: env_or("MECHAWAY_ALERT_CHECK_SECS", 30),
+                smtp_addr: std::env::var("MECHAWAY_SMTP_ADDR").ok(),
+                smtp_from: std::env::var("MECHAWAY_ALERT_FROM").unwrap_or_else(|_| "mechaway@localhost".to_string()),
+            },
+            maintenance: MaintenanceConfig {
+                janitor_interval_secs: env_or("MECHAWAY_JANITOR_INTERVAL_SECS", 3600),
+                db_interval_secs: env_or("MECHAWAY_DB_MAINTENANCE_INTERVAL_SECS", 86400),
+                db_window: Some(std::env::var("MECHAWAY_DB_MAINTENANCE_WINDOW").unwrap_or_else(|_| "02:00-05:00".to_string()))
+                    .filter(|window| !window.is_empty()),
+            },
+            security: SecurityConfig {
+                master_key: std::env::var("MECHAWAY_MASTER_KEY").ok().filter(|key| !key.is_empty()),
             },
         }
     }
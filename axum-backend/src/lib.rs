@@ -1,11 +1,14 @@
-/// Mechaway: Hyperminimalist intelligent systems automation engine
-/// 
-/// This library provides the core workflow automation engine with hot-reload capabilities,
-/// petgraph-based DAG execution, and extensible node system.
+//! Mechaway: Hyperminimalist intelligent systems automation engine
+//! 
+//! This library provides the core workflow automation engine with hot-reload capabilities,
+//! petgraph-based DAG execution, and extensible node system.
 
 // Core configuration and setup
 pub mod config;
 
+// Global tracing subscriber with a runtime-reloadable filter
+pub mod logging;
+
 // Project management layer - multi-tenant project isolation and database management
 pub mod project;
 
@@ -23,6 +26,12 @@ pub mod api;
 // Server setup and initialization
 pub mod server;
 
+// Integration test fixtures (in-process app, in-memory databases)
+pub mod testing;
+
+// In-process load harness (`mechaway bench`, criterion benches)
+pub mod bench;
+
 // Re-export commonly used types for external consumers
 pub use project::{Project, ProjectDatabaseManager};
 pub use workflow::{Workflow, Node, NodeType, Edge};
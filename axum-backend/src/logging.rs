@@ -0,0 +1,110 @@
+//! Logging setup with a runtime-reloadable filter
+//! 
+//! Builds the global tracing subscriber from `LoggingConfig`: pretty or JSON
+//! output, stdout or rotating files, and an `EnvFilter` wrapped in a reload
+//! layer so verbosity can be changed without restarting the server.
+
+use crate::{config::LoggingConfig, runtime::logging::workflow_level_filter};
+use anyhow::Result;
+use std::sync::{Mutex, OnceLock};
+use tracing_appender::{non_blocking::WorkerGuard, rolling::{RollingFileAppender, Rotation}};
+use tracing_subscriber::{
+    filter::filter_fn, fmt::writer::BoxMakeWriter, layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Layer,
+    Registry,
+};
+
+/// Process-wide handle, set once the global subscriber is installed
+static LOG_HANDLE: OnceLock<LogHandle> = OnceLock::new();
+
+/// Handle for reading and replacing the active log filter
+pub struct LogHandle {
+    reload: reload::Handle<EnvFilter, Registry>,
+    /// Filter directives currently in effect
+    filter: Mutex<String>,
+}
+
+impl LogHandle {
+    /// Filter directives currently in effect
+    pub fn filter(&self) -> String {
+        self.filter.lock().unwrap().clone()
+    }
+
+    /// Replace the active filter (`RUST_LOG` syntax)
+    pub fn set_filter(&self, directives: &str) -> Result<()> {
+        let filter = EnvFilter::try_new(directives)
+            .map_err(|e| anyhow::anyhow!("Invalid log filter '{}': {}", directives, e))?;
+        self.reload.reload(filter)?;
+        *self.filter.lock().unwrap() = directives.to_string();
+        Ok(())
+    }
+}
+
+/// Global log handle (None until `init_logging` ran, e.g. in tests)
+pub fn log_handle() -> Option<&'static LogHandle> {
+    LOG_HANDLE.get()
+}
+
+/// Install the global tracing subscriber
+/// 
+/// Returns the file writer guard when logging to files; keep it alive for the
+/// lifetime of the process so buffered lines are flushed on shutdown.
+pub fn init_logging(config: &LoggingConfig) -> Result<Option<WorkerGuard>> {
+    let filter = EnvFilter::try_new(&config.filter).unwrap_or_else(|e| {
+        eprintln!("Invalid log filter '{}' ({}), falling back to 'info'", config.filter, e);
+        EnvFilter::new("info")
+    });
+    let directives = filter.to_string();
+    let (filter_layer, reload_handle) = reload::Layer::new(filter);
+
+    let (writer, guard) = match &config.file_dir {
+        Some(dir) => {
+            let mut builder = RollingFileAppender::builder()
+                .rotation(parse_rotation(&config.rotation)?)
+                .filename_prefix(&config.file_prefix);
+            if let Some(max_files) = config.max_files {
+                builder = builder.max_log_files(max_files);
+            }
+            let appender = builder.build(dir)?;
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            (BoxMakeWriter::new(writer), Some(guard))
+        }
+        None => (BoxMakeWriter::new(std::io::stdout), None),
+    };
+    let to_file = config.file_dir.is_some();
+
+    let fmt_layer = match config.format.as_str() {
+        "json" => tracing_subscriber::fmt::layer()
+            .json()
+            .with_thread_ids(true)
+            .with_writer(writer)
+            .boxed(),
+        "pretty" => tracing_subscriber::fmt::layer()
+            .with_target(false)
+            .with_thread_ids(true)
+            .with_level(true)
+            .with_ansi(!to_file)
+            .with_writer(writer)
+            .boxed(),
+        other => return Err(anyhow::anyhow!("Unknown log format '{}' (expected pretty or json)", other)),
+    };
+
+    tracing_subscriber::registry()
+        .with(filter_layer)
+        .with(fmt_layer.with_filter(filter_fn(workflow_level_filter)))
+        .try_init()?;
+
+    let _ = LOG_HANDLE.set(LogHandle { reload: reload_handle, filter: Mutex::new(directives) });
+
+    Ok(guard)
+}
+
+/// Parse a rotation name from configuration
+fn parse_rotation(rotation: &str) -> Result<Rotation> {
+    match rotation {
+        "minutely" => Ok(Rotation::MINUTELY),
+        "hourly" => Ok(Rotation::HOURLY),
+        "daily" => Ok(Rotation::DAILY),
+        "never" => Ok(Rotation::NEVER),
+        other => Err(anyhow::anyhow!("Unknown log rotation '{}' (expected minutely, hourly, daily or never)", other)),
+    }
+}
@@ -1,9 +1,13 @@
-/// Mechaway: Hyperminimalist intelligent systems automation engine
-/// 
-/// Main entry point for the Mechaway server. Initializes configuration and starts
-/// the HTTP server with workflow management and execution capabilities.
+//! Mechaway: Hyperminimalist intelligent systems automation engine
+//! 
+//! Main entry point for the Mechaway server. Initializes configuration and starts
+//! the HTTP server with workflow management and execution capabilities.
 
-use mechaway::{config::Config, server::start_server};
+use mechaway::{
+    bench::{self, BenchOptions},
+    config::Config,
+    server::start_server,
+};
 
 /// Application entry point
 /// 
@@ -12,8 +16,16 @@ use mechaway::{config::Config, server::start_server};
 /// - Workflow management API at /api/workflows/*
 /// - Dynamic webhook execution at /webhook/{workflow_id}/*  
 /// - Health check at /healthz
+/// 
+/// `mechaway bench [--requests N] [--concurrency N] [--scenario NAME]...` runs the
+/// in-process load harness instead of starting the server.
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("bench") {
+        return run_bench(&args[1..]).await;
+    }
+    
     // Load configuration (defaults to localhost:3004 and SQLite databases)
     let config = Config::default();
     
@@ -21,4 +33,33 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     start_server(config).await?;
     
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Run the load harness and print one line per scenario
+async fn run_bench(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut options = BenchOptions::default();
+    let mut scenarios = Vec::new();
+    
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--requests" | "-n" => options.requests = value()?.parse()?,
+            "--concurrency" | "-c" => options.concurrency = value()?.parse()?,
+            "--scenario" | "-s" => scenarios.push(value()?.clone()),
+            other => return Err(format!(
+                "unknown bench option: {} (expected --requests, --concurrency, --scenario {})",
+                other, bench::SCENARIOS.join("|")).into()),
+        }
+    }
+    if !scenarios.is_empty() {
+        options.scenarios = scenarios;
+    }
+    
+    println!("mechaway bench: {} requests per scenario, concurrency {}", options.requests, options.concurrency);
+    for report in bench::run(&options).await? {
+        println!("{}", report);
+    }
+    
+    Ok(())
+}
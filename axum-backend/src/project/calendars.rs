@@ -0,0 +1,154 @@
+//! Project-scoped holiday calendars
+//!
+//! Backed by the holiday_calendars table in {slug}/project.db. CronTrigger and
+//! PollingTrigger nodes name one in their "holiday_calendar" param and skip
+//! runs on its dates (in the trigger's timezone):
+//!
+//! ```json
+//! { "dates": ["2026-04-03", "2026-04-06", "12-25", "12-26"],
+//!   "description": "Public holidays Berlin" }
+//! ```
+//!
+//! "YYYY-MM-DD" is a single day, "MM-DD" the same day every year.
+
+use crate::project::ProjectDatabaseManager;
+use anyhow::Result;
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+
+/// Stored calendar definition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HolidayCalendar {
+    pub dates: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Calendar with its name, as listed
+#[derive(Debug, Clone, Serialize)]
+pub struct NamedHolidayCalendar {
+    pub name: String,
+    #[serde(flatten)]
+    pub calendar: HolidayCalendar,
+    pub updated_at: String,
+}
+
+/// Calendar names: letters, digits, '_', '-' and '.'
+pub fn is_calendar_name(name: &str) -> bool {
+    !name.is_empty() && name.len() <= 128
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+/// One calendar entry
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Holiday {
+    Date(NaiveDate),
+    /// Month and day, every year
+    Annual(u32, u32),
+}
+
+impl Holiday {
+    fn parse(entry: &str) -> Option<Self> {
+        if let Ok(date) = NaiveDate::parse_from_str(entry, "%Y-%m-%d") {
+            return Some(Holiday::Date(date));
+        }
+        let (month, day) = entry.split_once('-')?;
+        let (month, day) = (month.parse().ok()?, day.parse().ok()?);
+        // Validated against a leap year so "02-29" is accepted
+        NaiveDate::from_ymd_opt(2024, month, day).map(|_| Holiday::Annual(month, day))
+    }
+}
+
+impl HolidayCalendar {
+    /// Reject entries that are neither "YYYY-MM-DD" nor "MM-DD"
+    pub fn validate(&self) -> Result<()> {
+        match self.dates.iter().find(|entry| Holiday::parse(entry).is_none()) {
+            Some(entry) => Err(anyhow::anyhow!("Invalid date '{}' (expected \"YYYY-MM-DD\" or \"MM-DD\")", entry)),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether a local date is a holiday
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.dates.iter()
+            .filter_map(|entry| Holiday::parse(entry))
+            .any(|holiday| match holiday {
+                Holiday::Date(day) => day == date,
+                Holiday::Annual(month, day) => date.month() == month && date.day() == day,
+            })
+    }
+}
+
+impl ProjectDatabaseManager {
+    /// Get a calendar by name
+    pub async fn calendar_get(&self, project_slug: &str, name: &str) -> Result<Option<HolidayCalendar>> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let row = sqlx::query("SELECT definition FROM holiday_calendars WHERE name = ?")
+            .bind(name)
+            .fetch_optional(&pool)
+            .await?;
+
+        match row {
+            Some(row) => {
+                let definition: String = row.get("definition");
+                Ok(Some(serde_json::from_str(&definition)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// All calendars of a project, sorted by name
+    pub async fn calendar_list(&self, project_slug: &str) -> Result<Vec<NamedHolidayCalendar>> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let rows = sqlx::query("SELECT name, definition, CAST(updated_at AS TEXT) AS updated_at FROM holiday_calendars ORDER BY name")
+            .fetch_all(&pool)
+            .await?;
+
+        rows.iter()
+            .map(|row| {
+                let definition: String = row.get("definition");
+                Ok(NamedHolidayCalendar {
+                    name: row.get("name"),
+                    calendar: serde_json::from_str(&definition)?,
+                    updated_at: row.get("updated_at"),
+                })
+            })
+            .collect()
+    }
+
+    /// Create or replace a calendar (UPSERT)
+    pub async fn calendar_set(&self, project_slug: &str, name: &str, calendar: &HolidayCalendar) -> Result<()> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO holiday_calendars (name, definition, updated_at)
+            VALUES (?, ?, CURRENT_TIMESTAMP)
+            ON CONFLICT(name) DO UPDATE SET
+                definition = excluded.definition,
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(name)
+        .bind(serde_json::to_string(calendar)?)
+        .execute(&pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Delete a calendar, returning whether it existed
+    pub async fn calendar_delete(&self, project_slug: &str, name: &str) -> Result<bool> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let result = sqlx::query("DELETE FROM holiday_calendars WHERE name = ?")
+            .bind(name)
+            .execute(&pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,112 @@
+//! Compression of large JSON blobs at rest
+//!
+//! Workflow definitions (workflows.definition) and execution trigger payloads
+//! (executions.payload) are written compressed once their JSON reaches
+//! MIN_COMPRESSED_BYTES, with MECHAWAY_STORAGE_COMPRESSION = "zstd" (default),
+//! "gzip" or "none". Compressed values are stored as BLOBs starting with the
+//! codec's magic bytes (zstd 28 B5 2F FD, gzip 1F 8B); anything else is plain
+//! JSON text, so rows written before (or below the size limit) read as they are
+//! and a database can switch codecs at any time. Rows are rewritten compressed
+//! the next time they are saved.
+
+use anyhow::Result;
+use sqlx::{
+    encode::IsNull,
+    error::BoxDynError,
+    sqlite::{SqliteArgumentValue, SqliteTypeInfo},
+    Encode, Sqlite, Type,
+};
+use std::io::{Read, Write};
+
+/// JSON shorter than this is stored as text (compression wouldn't pay off)
+const MIN_COMPRESSED_BYTES: usize = 1024;
+
+/// zstd level: fast, still well ahead of gzip's ratio on JSON
+const ZSTD_LEVEL: i32 = 3;
+
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xB5, 0x2F, 0xFD];
+const GZIP_MAGIC: &[u8] = &[0x1F, 0x8B];
+
+/// Codec for newly written blobs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StorageCompression {
+    None,
+    Gzip,
+    #[default]
+    Zstd,
+}
+
+impl StorageCompression {
+    /// Codec named by MECHAWAY_STORAGE_COMPRESSION (unknown names fall back to zstd with a warning)
+    pub fn from_config(name: &str) -> Self {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "none" | "off" => StorageCompression::None,
+            "gzip" => StorageCompression::Gzip,
+            "zstd" => StorageCompression::Zstd,
+            other => {
+                tracing::warn!("⚠️ Unknown storage compression '{}' (expected zstd, gzip or none), using zstd", other);
+                StorageCompression::Zstd
+            }
+        }
+    }
+
+    /// Encode JSON for storage, compressed when it is large enough and gets smaller
+    pub fn encode(&self, json: String) -> Result<StoredJson> {
+        if json.len() < MIN_COMPRESSED_BYTES {
+            return Ok(StoredJson::Text(json));
+        }
+        let compressed = match self {
+            StorageCompression::None => return Ok(StoredJson::Text(json)),
+            StorageCompression::Zstd => zstd::encode_all(json.as_bytes(), ZSTD_LEVEL)?,
+            StorageCompression::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(json.as_bytes())?;
+                encoder.finish()?
+            }
+        };
+        match compressed.len() < json.len() {
+            true => Ok(StoredJson::Compressed(compressed)),
+            false => Ok(StoredJson::Text(json)),
+        }
+    }
+}
+
+/// Read a stored value back into JSON text, whichever way it was written
+pub fn decode(stored: Vec<u8>) -> Result<String> {
+    let bytes = if stored.starts_with(ZSTD_MAGIC) {
+        zstd::decode_all(stored.as_slice())?
+    } else if stored.starts_with(GZIP_MAGIC) {
+        let mut bytes = Vec::new();
+        flate2::read::GzDecoder::new(stored.as_slice()).read_to_end(&mut bytes)?;
+        bytes
+    } else {
+        stored
+    };
+    Ok(String::from_utf8(bytes)?)
+}
+
+/// JSON value as written to SQLite: TEXT when plain, BLOB when compressed
+#[derive(Debug, Clone)]
+pub enum StoredJson {
+    Text(String),
+    Compressed(Vec<u8>),
+}
+
+impl Type<Sqlite> for StoredJson {
+    fn type_info() -> SqliteTypeInfo {
+        <String as Type<Sqlite>>::type_info()
+    }
+
+    fn compatible(ty: &SqliteTypeInfo) -> bool {
+        <Vec<u8> as Type<Sqlite>>::compatible(ty)
+    }
+}
+
+impl<'q> Encode<'q, Sqlite> for StoredJson {
+    fn encode_by_ref(&self, buf: &mut Vec<SqliteArgumentValue<'q>>) -> Result<IsNull, BoxDynError> {
+        match self {
+            StoredJson::Text(text) => <String as Encode<'q, Sqlite>>::encode_by_ref(text, buf),
+            StoredJson::Compressed(bytes) => <Vec<u8> as Encode<'q, Sqlite>>::encode_by_ref(bytes, buf),
+        }
+    }
+}
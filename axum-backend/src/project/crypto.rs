@@ -0,0 +1,83 @@
+//! Encryption at rest for credentials stored in project databases
+//! 
+//! Values are sealed with AES-256-GCM under the instance master key
+//! (MECHAWAY_MASTER_KEY: 32 bytes as base64 or 64 hex characters) and stored
+//! as "v1:" + base64(nonce || ciphertext). Without a master key, features that
+//! store credentials refuse to run instead of falling back to plaintext.
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Nonce,
+};
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+/// Format tag of sealed values (bumped if the scheme ever changes)
+const SEALED_PREFIX: &str = "v1:";
+
+/// AES-GCM nonce length in bytes
+const NONCE_LEN: usize = 12;
+
+/// Credential storage was used without MECHAWAY_MASTER_KEY
+#[derive(Debug)]
+pub struct MasterKeyMissing;
+
+impl std::fmt::Display for MasterKeyMissing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("MECHAWAY_MASTER_KEY is not configured")
+    }
+}
+
+impl std::error::Error for MasterKeyMissing {}
+
+/// Instance-wide key sealing stored credentials
+pub struct MasterKey {
+    cipher: Aes256Gcm,
+}
+
+impl std::fmt::Debug for MasterKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("MasterKey(REDACTED)")
+    }
+}
+
+impl MasterKey {
+    /// Parse a 256-bit key given as base64 or hex
+    pub fn parse(encoded: &str) -> Result<Self> {
+        let encoded = encoded.trim();
+        let bytes = if encoded.len() == 64 && encoded.chars().all(|c| c.is_ascii_hexdigit()) {
+            hex::decode(encoded)?
+        } else {
+            BASE64.decode(encoded)
+                .map_err(|_| anyhow::anyhow!("Master key must be 32 bytes encoded as base64 or 64 hex characters"))?
+        };
+        if bytes.len() != 32 {
+            return Err(anyhow::anyhow!("Master key must be 32 bytes, got {}", bytes.len()));
+        }
+        Ok(Self { cipher: Aes256Gcm::new_from_slice(&bytes)? })
+    }
+
+    /// Encrypt a value for storage
+    pub fn seal(&self, plaintext: &str) -> Result<String> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self.cipher.encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| anyhow::anyhow!("Encryption failed"))?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        Ok(format!("{}{}", SEALED_PREFIX, BASE64.encode(sealed)))
+    }
+
+    /// Decrypt a stored value (fails if it was sealed under another key or tampered with)
+    pub fn open(&self, sealed: &str) -> Result<String> {
+        let encoded = sealed.strip_prefix(SEALED_PREFIX)
+            .ok_or_else(|| anyhow::anyhow!("Unsupported sealed value format"))?;
+        let bytes = BASE64.decode(encoded)?;
+        if bytes.len() < NONCE_LEN {
+            return Err(anyhow::anyhow!("Sealed value is truncated"));
+        }
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+        let plaintext = self.cipher.decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow::anyhow!("Decryption failed (wrong master key or corrupted value)"))?;
+        Ok(String::from_utf8(plaintext)?)
+    }
+}
@@ -1,16 +1,18 @@
-/// Project database manager for isolated multi-tenant storage
-/// 
-/// Manages separate SQLite databases per project:
-/// - {slug}_project.db: workflows, secrets, project metadata
-/// - {slug}_simpletable.db: SimpleTable node data storage
-/// 
-/// INDUSTRIAL-GRADE: Connection pooling, lazy loading, zero cross-project data leaks
+//! Project database manager for isolated multi-tenant storage
+//! 
+//! Manages separate SQLite databases per project:
+//! - {slug}_project.db: workflows, secrets, project metadata
+//! - {slug}_simpletable.db: SimpleTable node data storage
+//! 
+//! INDUSTRIAL-GRADE: Connection pooling, lazy loading, zero cross-project data leaks
 
-use crate::project::types::Project;
+use crate::project::{compression::StorageCompression, crypto::{MasterKey, MasterKeyMissing}, pg_pool::ExternalPgPoolManager, simpletable_pg::SimpleTableBackend, types::Project};
 use anyhow::Result;
-use sqlx::{sqlite::{SqlitePool, SqliteConnectOptions}, SqliteConnection};
-use std::collections::HashMap;
-use std::path::Path;
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{sqlite::{SqliteAutoVacuum, SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions}, Row};
+use std::{collections::HashMap, str::FromStr, sync::Arc};
+use std::path::{Path, PathBuf};
 use tokio::sync::RwLock;
 
 /// Project database manager with isolated connection pools
@@ -25,6 +27,40 @@ pub struct ProjectDatabaseManager {
     simpletable_pools: RwLock<HashMap<String, SqlitePool>>,
     /// Base directory for database files
     data_dir: String,
+    /// Use `sqlite::memory:` databases instead of files (tests)
+    in_memory: bool,
+    /// Resolved SimpleTable storage per project (SQLite or a PostgreSQL schema)
+    pub(super) simpletable_backends: RwLock<HashMap<String, SimpleTableBackend>>,
+    /// Connection pools for external PostgreSQL databases (shared by PG nodes)
+    pg_pools: Arc<ExternalPgPoolManager>,
+    /// Key sealing stored credentials (None: credential storage disabled)
+    master_key: Option<Arc<MasterKey>>,
+    /// Serializes OAuth token refreshes so concurrent executions refresh once
+    pub(super) oauth_refresh: tokio::sync::Mutex<()>,
+    /// Sealed `$secret.` values per opened project (read synchronously by pins)
+    pub(super) secrets: std::sync::RwLock<HashMap<String, HashMap<String, String>>>,
+    /// Codec for execution payloads written to project.db
+    pub(super) compression: StorageCompression,
+    /// Open existing database files read-only, never creating or migrating them (follower instances)
+    read_only: bool,
+}
+
+/// Connection usage of one open pool
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolStats {
+    /// Open connections
+    pub size: u32,
+    /// Open connections not currently in use
+    pub idle: usize,
+}
+
+/// Open pools of one project
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProjectPoolStats {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_db: Option<PoolStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub simpletable_db: Option<PoolStats>,
 }
 
 impl ProjectDatabaseManager {
@@ -34,9 +70,76 @@ impl ProjectDatabaseManager {
             project_pools: RwLock::new(HashMap::new()),
             simpletable_pools: RwLock::new(HashMap::new()),
             data_dir,
+            in_memory: false,
+            simpletable_backends: RwLock::new(HashMap::new()),
+            pg_pools: Arc::new(ExternalPgPoolManager::default()),
+            master_key: None,
+            oauth_refresh: tokio::sync::Mutex::new(()),
+            secrets: std::sync::RwLock::new(HashMap::new()),
+            compression: StorageCompression::default(),
+            read_only: false,
+        }
+    }
+    
+    /// Use the given external PostgreSQL pool manager (limits from config)
+    pub fn with_pg_pools(mut self, pg_pools: Arc<ExternalPgPoolManager>) -> Self {
+        self.pg_pools = pg_pools;
+        self
+    }
+    
+    /// Seal stored credentials with the given master key
+    pub fn with_master_key(mut self, master_key: Option<Arc<MasterKey>>) -> Self {
+        self.master_key = master_key;
+        self
+    }
+    
+    /// Compress large execution payloads with the given codec (zstd by default)
+    pub fn with_compression(mut self, compression: StorageCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+    
+    /// Open the databases of a primary instance read-only (follower mode)
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+    
+    /// Whether databases are opened read-only (follower mode)
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+    
+    /// Master key for stored credentials (error if MECHAWAY_MASTER_KEY is not set)
+    pub fn master_key(&self) -> Result<&MasterKey> {
+        self.master_key.as_deref()
+            .ok_or_else(|| MasterKeyMissing.into())
+    }
+    
+    /// Connection pools for external PostgreSQL databases
+    pub fn pg_pools(&self) -> &Arc<ExternalPgPoolManager> {
+        &self.pg_pools
+    }
+    
+    /// Create project database manager backed by in-memory SQLite databases
+    /// 
+    /// Each project still gets its own isolated databases; the data directory
+    /// is only used for working files (uploads, spill files).
+    pub fn new_in_memory(data_dir: String) -> Self {
+        Self {
+            in_memory: true,
+            ..Self::new(data_dir)
         }
     }
     
+    /// Directory holding a project's databases and working files ({data_dir}/{slug})
+    pub fn project_dir(&self, project_slug: &str) -> Result<PathBuf> {
+        if !Project::is_valid_slug(project_slug) {
+            return Err(anyhow::anyhow!("Invalid project slug: {}", project_slug));
+        }
+        Ok(Path::new(&self.data_dir).join(project_slug))
+    }
+    
     /// Get or create project database pool ({slug}_project.db)
     /// 
     /// LAZY LOADING: Creates pool only when first accessed
@@ -58,22 +161,18 @@ impl ProjectDatabaseManager {
             return Ok(pool.clone());
         }
         
-        // Create project directory and database file path
-        let project_dir = Path::new(&self.data_dir).join(project_slug);
-        std::fs::create_dir_all(&project_dir)
-            .map_err(|e| anyhow::anyhow!("Failed to create project directory '{}': {}", project_dir.display(), e))?;
-        let db_path = project_dir.join("project.db");
-        
-        tracing::info!("🗄️ Creating project database pool: {}", db_path.display());
+        // Reject slugs that could escape the data directory
+        if !Project::is_valid_slug(project_slug) {
+            return Err(anyhow::anyhow!("Invalid project slug: {}", project_slug));
+        }
         
-        // Create connection pool with auto-create option
-        let options = SqliteConnectOptions::new()
-            .filename(&db_path)
-            .create_if_missing(true);
-        let pool = SqlitePool::connect_with(options).await?;
+        let pool = self.open_pool(project_slug, "project.db").await?;
         
-        // Initialize project database schema
-        self.init_project_schema(&pool).await?;
+        // Initialize project database schema (the primary owns it in follower mode)
+        if !self.read_only {
+            self.init_project_schema(&pool).await?;
+        }
+        self.cache_project_secrets(project_slug, &pool).await?;
         
         // Cache the pool
         pools.insert(project_slug.to_string(), pool.clone());
@@ -83,6 +182,21 @@ impl ProjectDatabaseManager {
         Ok(pool)
     }
     
+    /// Open connection pools per project (pools are created lazily on first use)
+    pub async fn pool_stats(&self) -> std::collections::BTreeMap<String, ProjectPoolStats> {
+        let stats_of = |pool: &SqlitePool| PoolStats { size: pool.size(), idle: pool.num_idle() };
+        let mut projects = std::collections::BTreeMap::<String, ProjectPoolStats>::new();
+        
+        for (slug, pool) in self.project_pools.read().await.iter() {
+            projects.entry(slug.clone()).or_default().project_db = Some(stats_of(pool));
+        }
+        for (slug, pool) in self.simpletable_pools.read().await.iter() {
+            projects.entry(slug.clone()).or_default().simpletable_db = Some(stats_of(pool));
+        }
+        
+        projects
+    }
+    
     /// Get or create simpletable database pool ({slug}_simpletable.db)
     /// 
     /// LAZY LOADING: Creates pool only when first accessed by SimpleTable nodes
@@ -103,19 +217,12 @@ impl ProjectDatabaseManager {
             return Ok(pool.clone());
         }
         
-        // Create project directory and database file path
-        let project_dir = Path::new(&self.data_dir).join(project_slug);
-        std::fs::create_dir_all(&project_dir)
-            .map_err(|e| anyhow::anyhow!("Failed to create project directory '{}': {}", project_dir.display(), e))?;
-        let db_path = project_dir.join("simpletable.db");
-        
-        tracing::info!("🗄️ Creating simpletable database pool: {}", db_path.display());
+        // Reject slugs that could escape the data directory
+        if !Project::is_valid_slug(project_slug) {
+            return Err(anyhow::anyhow!("Invalid project slug: {}", project_slug));
+        }
         
-        // Create connection pool with auto-create option
-        let options = SqliteConnectOptions::new()
-            .filename(&db_path)
-            .create_if_missing(true);
-        let pool = SqlitePool::connect_with(options).await?;
+        let pool = self.open_pool(project_slug, "simpletable.db").await?;
         
         // Cache the pool (no schema init needed - tables created dynamically)
         pools.insert(project_slug.to_string(), pool.clone());
@@ -125,9 +232,71 @@ impl ProjectDatabaseManager {
         Ok(pool)
     }
     
+    /// Simpletable pool of a project that already has simpletable data
+    /// 
+    /// Unlike `get_simpletable_pool`, never creates an empty database file.
+    pub async fn existing_simpletable_pool(&self, project_slug: &str) -> Result<Option<SqlitePool>> {
+        if let Some(pool) = self.simpletable_pools.read().await.get(project_slug) {
+            return Ok(Some(pool.clone()));
+        }
+        if self.in_memory || !self.project_dir(project_slug)?.join("simpletable.db").exists() {
+            return Ok(None);
+        }
+        Ok(Some(self.get_simpletable_pool(project_slug).await?))
+    }
+    
+    /// Open a connection pool for one of a project's database files
+    /// 
+    /// IN-MEMORY: Every connection to `sqlite::memory:` is a separate database, so
+    /// in-memory pools hold exactly one connection that is never recycled.
+    async fn open_pool(&self, project_slug: &str, file_name: &str) -> Result<SqlitePool> {
+        let project_dir = Path::new(&self.data_dir).join(project_slug);
+        if self.read_only && !self.in_memory {
+            let db_path = project_dir.join(file_name);
+            if !db_path.exists() {
+                return Err(anyhow::anyhow!("Database '{}' does not exist (follower instances only open the primary's databases)", db_path.display()));
+            }
+            tracing::info!("🗄️ Opening read-only database pool: {}", db_path.display());
+            let options = SqliteConnectOptions::new()
+                .filename(&db_path)
+                .read_only(true);
+            return Ok(SqlitePool::connect_with(options).await?);
+        }
+        
+        // Create project directory (also used for uploads and spill files)
+        std::fs::create_dir_all(&project_dir)
+            .map_err(|e| anyhow::anyhow!("Failed to create project directory '{}': {}", project_dir.display(), e))?;
+        
+        if self.in_memory {
+            tracing::info!("🗄️ Creating in-memory database pool: {}/{}", project_slug, file_name);
+            let options = SqliteConnectOptions::from_str("sqlite::memory:")?;
+            let pool = SqlitePoolOptions::new()
+                .max_connections(1)
+                .min_connections(1)
+                .idle_timeout(None)
+                .max_lifetime(None)
+                .connect_with(options)
+                .await?;
+            return Ok(pool);
+        }
+        
+        let db_path = project_dir.join(file_name);
+        tracing::info!("🗄️ Creating database pool: {}", db_path.display());
+        
+        // Create connection pool with auto-create option; incremental auto-vacuum
+        // lets database maintenance release free pages without a full VACUUM, and
+        // WAL lets readers (including follower instances) run while a write commits
+        let options = SqliteConnectOptions::new()
+            .filename(&db_path)
+            .create_if_missing(true)
+            .auto_vacuum(SqliteAutoVacuum::Incremental)
+            .journal_mode(SqliteJournalMode::Wal);
+        Ok(SqlitePool::connect_with(options).await?)
+    }
+    
     /// Initialize project database schema
     /// 
-    /// Creates tables for workflows, secrets, and project metadata
+    /// Creates tables for workflows, workflow tests and variables, executions, secrets, and project metadata
     async fn init_project_schema(&self, pool: &SqlitePool) -> Result<()> {
         // Workflows table (project-scoped)
         sqlx::query(
@@ -144,6 +313,58 @@ impl ProjectDatabaseManager {
         .execute(pool)
         .await?;
         
+        // Workflow test cases (stored alongside the workflow definition)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS workflow_tests (
+                workflow_id TEXT PRIMARY KEY,
+                cases JSON NOT NULL,
+                updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+        
+        // Workflow variables ($vars pins, Lua vars), kept across workflow updates
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS workflow_variables (
+                workflow_id TEXT PRIMARY KEY,
+                variables JSON NOT NULL,
+                updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+        
+        // Workflow canary versions (candidate definition and traffic percentage)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS workflow_canaries (
+                workflow_id TEXT PRIMARY KEY,
+                canary JSON NOT NULL,
+                updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+        
+        // Workflow snoozes (paused triggers, one-time skip of the next scheduled run)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS workflow_snoozes (
+                workflow_id TEXT PRIMARY KEY,
+                snooze JSON NOT NULL,
+                updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+        
         // Project secrets table (encrypted storage)
         sqlx::query(
             r#"
@@ -172,6 +393,231 @@ impl ProjectDatabaseManager {
         .execute(pool)
         .await?;
         
+        // Key-value store for cross-execution state (KVStore nodes)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS kv_store (
+                key TEXT PRIMARY KEY,
+                value JSON NOT NULL,
+                expires_at INTEGER,
+                updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+        
+        // Feature flags read by FeatureFlag nodes
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS feature_flags (
+                name TEXT PRIMARY KEY,
+                definition JSON NOT NULL,
+                updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+        
+        // Holiday calendars of CronTrigger / PollingTrigger schedule constraints
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS holiday_calendars (
+                name TEXT PRIMARY KEY,
+                definition JSON NOT NULL,
+                updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+        
+        // Execution history with the original trigger payload (replay)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS executions (
+                id TEXT PRIMARY KEY,
+                workflow_id TEXT NOT NULL,
+                start_node_id TEXT NOT NULL,
+                trigger_type TEXT NOT NULL,
+                status TEXT NOT NULL,
+                payload JSON NOT NULL,
+                error TEXT,
+                duration_ms INTEGER,
+                replay_of TEXT,
+                started_at TEXT NOT NULL,
+                finished_at TEXT
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+        
+        // Trigger payloads shared by identical executions (content-addressed by hash)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS payload_blobs (
+                hash TEXT PRIMARY KEY,
+                body BLOB NOT NULL,
+                size INTEGER NOT NULL,
+                created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+        
+        // Persisted execution profiling snapshots (used in the default project)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS profiling_stats (
+                scope TEXT NOT NULL,
+                key TEXT NOT NULL,
+                node_id TEXT NOT NULL DEFAULT '',
+                stats JSON NOT NULL,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (scope, key, node_id)
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+        
+        // OAuth2 authorization-code credentials (secrets and tokens sealed with the master key)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS oauth_credentials (
+                name TEXT PRIMARY KEY,
+                config JSON NOT NULL,
+                client_secret_enc TEXT NOT NULL,
+                access_token_enc TEXT,
+                refresh_token_enc TEXT,
+                expires_at INTEGER,
+                pending_state TEXT,
+                state_expires_at INTEGER,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        // Diff node snapshots (one row per item key as last seen by the node)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS diff_snapshots (
+                workflow_id TEXT NOT NULL,
+                node_id TEXT NOT NULL,
+                item_key TEXT NOT NULL,
+                hash TEXT NOT NULL,
+                item JSON NOT NULL,
+                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                PRIMARY KEY (workflow_id, node_id, item_key)
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        // Conversation memory (messages per session, plus the summary of compacted ones)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS memory_messages (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id TEXT NOT NULL,
+                role TEXT NOT NULL,
+                content JSON NOT NULL,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS memory_sessions (
+                session_id TEXT PRIMARY KEY,
+                summary TEXT,
+                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        // Window node buffers (items waiting for their count/time window to close)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS window_buffers (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                workflow_id TEXT NOT NULL,
+                node_id TEXT NOT NULL,
+                received_at INTEGER NOT NULL,
+                item JSON NOT NULL
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS devices (
+                device_id TEXT PRIMARY KEY,
+                source TEXT NOT NULL,
+                workflow_id TEXT NOT NULL,
+                node_id TEXT NOT NULL,
+                status TEXT NOT NULL,
+                last_payload JSON NOT NULL,
+                first_seen TEXT NOT NULL,
+                last_seen TEXT NOT NULL,
+                message_count INTEGER NOT NULL DEFAULT 0,
+                offline_after_secs INTEGER
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        // Store-and-forward outbox (node inputs parked while the network is down)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS outbox (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                workflow_id TEXT NOT NULL,
+                node_id TEXT NOT NULL,
+                context JSON NOT NULL,
+                status TEXT NOT NULL DEFAULT 'pending',
+                attempts INTEGER NOT NULL DEFAULT 0,
+                last_error TEXT,
+                created_at TEXT NOT NULL,
+                next_attempt_at INTEGER NOT NULL
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
+        // Trigger inbox (message keys claimed by exactly-once triggers)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS trigger_inbox (
+                workflow_id TEXT NOT NULL,
+                node_id TEXT NOT NULL,
+                message_key TEXT NOT NULL,
+                execution_id TEXT NOT NULL,
+                status TEXT NOT NULL,
+                received_at TEXT NOT NULL,
+                finished_at TEXT,
+                PRIMARY KEY (workflow_id, node_id, message_key)
+            )
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
         // Create indexes for performance
         sqlx::query("CREATE INDEX IF NOT EXISTS idx_workflows_name ON workflows(name)")
             .execute(pool)
@@ -181,6 +627,109 @@ impl ProjectDatabaseManager {
             .execute(pool)
             .await?;
         
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_executions_workflow ON executions(workflow_id, started_at)")
+            .execute(pool)
+            .await?;
+        
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_memory_messages_session ON memory_messages(session_id, id)")
+            .execute(pool)
+            .await?;
+        
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_window_buffers_node ON window_buffers(workflow_id, node_id, received_at)")
+            .execute(pool)
+            .await?;
+        
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_outbox_due ON outbox(status, next_attempt_at)")
+            .execute(pool)
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Get project settings (stored under the "settings" key of project_metadata)
+    /// 
+    /// Returns an empty object when the project has no settings yet
+    pub async fn get_project_settings(&self, project_slug: &str) -> Result<Value> {
+        let pool = self.get_project_pool(project_slug).await?;
+        
+        let row = sqlx::query("SELECT value FROM project_metadata WHERE key = 'settings'")
+            .fetch_optional(&pool)
+            .await?;
+        
+        match row {
+            Some(row) => {
+                let value_json: String = row.get("value");
+                Ok(serde_json::from_str(&value_json)?)
+            }
+            None => Ok(serde_json::json!({})),
+        }
+    }
+    
+    /// Replace project settings (UPSERT into project_metadata)
+    pub async fn save_project_settings(&self, project_slug: &str, settings: &Value) -> Result<()> {
+        let pool = self.get_project_pool(project_slug).await?;
+        
+        sqlx::query(
+            r#"
+            INSERT INTO project_metadata (key, value, updated_at)
+            VALUES ('settings', ?, CURRENT_TIMESTAMP)
+            ON CONFLICT(key) DO UPDATE SET
+                value = excluded.value,
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(serde_json::to_string(settings)?)
+        .execute(&pool)
+        .await?;
+        
+        // The simpletable backend may have changed
+        self.invalidate_simpletable_backend(project_slug).await;
+        
+        Ok(())
+    }
+    
+    /// Check whether a caller project may trigger workflows in the target project
+    /// 
+    /// TENANT ISOLATION: Same-project calls are always allowed. Cross-project calls
+    /// require the target's settings to list the caller explicitly:
+    /// { "allowed_callers": ["ecommerce", "analytics"] }
+    pub async fn is_caller_allowed(&self, target_slug: &str, caller_slug: &str) -> Result<bool> {
+        if target_slug == caller_slug {
+            return Ok(true);
+        }
+        
+        let settings = self.get_project_settings(target_slug).await?;
+        let allowed = settings.get("allowed_callers")
+            .and_then(|a| a.as_array())
+            .map(|callers| callers.iter().any(|c| c.as_str() == Some(caller_slug)))
+            .unwrap_or(false);
+        
+        Ok(allowed)
+    }
+    
+    /// Projects with databases on disk or open pools
+    /// 
+    /// Used by background maintenance, which must also reach projects whose
+    /// workflows were all deleted.
+    pub async fn known_projects(&self) -> Vec<String> {
+        let mut projects: std::collections::BTreeSet<String> = self.project_pools.read().await.keys().cloned().collect();
+        
+        if let Ok(entries) = std::fs::read_dir(&self.data_dir) {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if Project::is_valid_slug(&name) && entry.path().join("project.db").exists() {
+                    projects.insert(name);
+                }
+            }
+        }
+        
+        projects.into_iter().collect()
+    }
+    
+    /// Rebuild a project's database file to reclaim space freed by deletes
+    pub async fn vacuum_project_db(&self, project_slug: &str) -> Result<()> {
+        let pool = self.get_project_pool(project_slug).await?;
+        sqlx::query("VACUUM").execute(&pool).await?;
         Ok(())
     }
     
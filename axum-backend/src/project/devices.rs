@@ -0,0 +1,102 @@
+//! Device state persistence (digital twins)
+//! 
+//! Backed by the devices table in {slug}/project.db. The runtime device
+//! registry keeps the live state in memory and writes changed devices here
+//! periodically, so the last known state of every device survives restarts.
+
+use crate::project::ProjectDatabaseManager;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::Row;
+
+/// Last known state of a device feeding an MQTTTrigger or WebSocketTrigger
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceState {
+    pub device_id: String,
+    /// Trigger kind the device reports through ("mqtt" or "websocket")
+    pub source: String,
+    pub workflow_id: String,
+    pub node_id: String,
+    /// "online" or "offline"
+    pub status: String,
+    /// Last message, without the transport envelope
+    pub last_payload: Value,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub message_count: u64,
+    /// Silence after which an online device is reported offline
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offline_after_secs: Option<u64>,
+}
+
+impl ProjectDatabaseManager {
+    /// Insert or replace device states in one transaction
+    pub async fn device_save(&self, project_slug: &str, devices: &[DeviceState]) -> Result<()> {
+        let pool = self.get_project_pool(project_slug).await?;
+        let mut tx = pool.begin().await?;
+
+        for device in devices {
+            sqlx::query(
+                r#"
+                INSERT OR REPLACE INTO devices
+                    (device_id, source, workflow_id, node_id, status, last_payload, first_seen, last_seen, message_count, offline_after_secs)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(&device.device_id)
+            .bind(&device.source)
+            .bind(&device.workflow_id)
+            .bind(&device.node_id)
+            .bind(&device.status)
+            .bind(serde_json::to_string(&device.last_payload)?)
+            .bind(device.first_seen.to_rfc3339())
+            .bind(device.last_seen.to_rfc3339())
+            .bind(device.message_count as i64)
+            .bind(device.offline_after_secs.map(|secs| secs as i64))
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// All stored device states of a project, by device ID
+    pub async fn device_list(&self, project_slug: &str) -> Result<Vec<DeviceState>> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let rows = sqlx::query(
+            r#"
+            SELECT device_id, source, workflow_id, node_id, status, CAST(last_payload AS TEXT) AS last_payload,
+                   first_seen, last_seen, message_count, offline_after_secs
+            FROM devices ORDER BY device_id
+            "#,
+        )
+            .fetch_all(&pool)
+            .await?;
+
+        rows.iter()
+            .map(|row| {
+                let payload_json: String = row.get("last_payload");
+                let timestamp = |column: &str| -> Result<DateTime<Utc>> {
+                    let value: String = row.get(column);
+                    Ok(DateTime::parse_from_rfc3339(&value)?.with_timezone(&Utc))
+                };
+                Ok(DeviceState {
+                    device_id: row.get("device_id"),
+                    source: row.get("source"),
+                    workflow_id: row.get("workflow_id"),
+                    node_id: row.get("node_id"),
+                    status: row.get("status"),
+                    last_payload: serde_json::from_str(&payload_json)?,
+                    first_seen: timestamp("first_seen")?,
+                    last_seen: timestamp("last_seen")?,
+                    message_count: row.get::<i64, _>("message_count") as u64,
+                    offline_after_secs: row.get::<Option<i64>, _>("offline_after_secs").map(|secs| secs as u64),
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,362 @@
+//! Project-scoped execution history
+//! 
+//! Backed by the executions table in {slug}/project.db. Every top-level run
+//! stores its original trigger payload (data, query, headers, files, path
+//! params) so it can be replayed later against the current workflow version.
+//!
+//! Payloads of MIN_SHARED_PAYLOAD_BYTES or more are content-addressed: stored
+//! once in the payload_blobs table (compressed, see compression) under the
+//! SHA-256 of their canonical JSON, and referenced from executions.payload as
+//! "sha256:<hex>". Identical webhook traffic then costs one row per execution
+//! instead of one payload each. Blobs go when their last execution is pruned.
+
+use crate::project::{compression::{self, StoredJson}, ProjectDatabaseManager};
+use crate::runtime::executor::Termination;
+use crate::workflow::types::{ExecutionContext, FileInfo};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sqlx::Row;
+use std::collections::HashMap;
+
+/// Payloads (bytes of JSON) from this size on are stored once per distinct content
+const MIN_SHARED_PAYLOAD_BYTES: usize = 256;
+
+/// Prefix of payload references in executions.payload
+const PAYLOAD_REF_PREFIX: &str = "sha256:";
+
+/// Execution columns with the shared payload body joined in
+const EXECUTION_COLUMNS: &str = "SELECT e.*, b.body AS payload_body FROM executions e LEFT JOIN payload_blobs b ON b.hash = e.payload";
+
+/// Request headers never written to the history (credentials)
+const UNRECORDED_HEADERS: &[&str] = &["authorization", "proxy-authorization", "cookie"];
+
+/// Trigger input captured at the start of an execution
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TriggerPayload {
+    pub data: Vec<Value>,
+    #[serde(default)]
+    pub query: HashMap<String, String>,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub files: HashMap<String, FileInfo>,
+    /// Webhook path parameters (metadata "path_params")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path_params: Option<Value>,
+}
+
+impl TriggerPayload {
+    /// Capture the trigger parts of a fresh execution context
+    pub fn from_context(context: &ExecutionContext) -> Self {
+        Self {
+            data: context.data.clone(),
+            query: context.query.clone(),
+            headers: context.headers.iter()
+                .filter(|(name, _)| !UNRECORDED_HEADERS.contains(&name.as_str()))
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+            files: context.files.clone(),
+            path_params: context.metadata.get("path_params").cloned(),
+        }
+    }
+
+    /// Rebuild an execution context carrying exactly this payload
+    pub fn into_context(self, workflow_id: String, project_slug: String) -> ExecutionContext {
+        let mut context = ExecutionContext::from_array_data(workflow_id, self.data, project_slug);
+        context.query = self.query;
+        context.headers = self.headers;
+        context.files = self.files;
+        if let Some(path_params) = self.path_params {
+            context.metadata.insert("path_params".to_string(), path_params);
+        }
+        context
+    }
+}
+
+/// Stored execution record
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecutionRecord {
+    pub id: String,
+    pub workflow_id: String,
+    pub start_node_id: String,
+    /// webhook | cron | event | replay | resume
+    pub trigger_type: String,
+    /// running | success | stopped | failed | interrupted
+    pub status: String,
+    pub payload: TriggerPayload,
+    /// Content hash of a shared payload ("sha256:<hex>")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload_hash: Option<String>,
+    /// Error of failed runs, or where and why a stopped run ended
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<i64>,
+    /// Execution this one replayed, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replay_of: Option<String>,
+    pub started_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
+}
+
+/// Fields written when an execution starts
+#[derive(Debug, Clone)]
+pub struct NewExecution<'a> {
+    pub id: &'a str,
+    pub workflow_id: &'a str,
+    pub start_node_id: &'a str,
+    pub trigger_type: &'a str,
+    pub payload: &'a TriggerPayload,
+    pub replay_of: Option<&'a str>,
+}
+
+impl ProjectDatabaseManager {
+    /// Insert a running execution with its trigger payload
+    /// 
+    /// Large payloads are stored once in payload_blobs and referenced by hash. The
+    /// blob and the execution are written in one transaction, so a concurrent prune
+    /// can't delete the blob before the execution references it.
+    pub async fn record_execution_start(&self, project_slug: &str, execution: &NewExecution<'_>) -> Result<()> {
+        let pool = self.get_project_pool(project_slug).await?;
+        let mut tx = pool.begin().await?;
+        
+        // Canonical JSON (sorted keys): headers and query come from hash maps
+        let mut payload = serde_json::to_value(execution.payload)?;
+        payload.sort_all_objects();
+        let payload_json = serde_json::to_string(&payload)?;
+        let stored = if payload_json.len() >= MIN_SHARED_PAYLOAD_BYTES {
+            let hash = format!("{}{}", PAYLOAD_REF_PREFIX, hex::encode(Sha256::digest(payload_json.as_bytes())));
+            sqlx::query("INSERT INTO payload_blobs (hash, body, size) VALUES (?, ?, ?) ON CONFLICT(hash) DO NOTHING")
+                .bind(&hash)
+                .bind(self.compression.encode(payload_json.clone())?)
+                .bind(payload_json.len() as i64)
+                .execute(&mut *tx)
+                .await?;
+            StoredJson::Text(hash)
+        } else {
+            self.compression.encode(payload_json)?
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO executions (id, workflow_id, start_node_id, trigger_type, status, payload, replay_of, started_at)
+            VALUES (?, ?, ?, ?, 'running', ?, ?, ?)
+            "#,
+        )
+        .bind(execution.id)
+        .bind(execution.workflow_id)
+        .bind(execution.start_node_id)
+        .bind(execution.trigger_type)
+        .bind(stored)
+        .bind(execution.replay_of)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Mark an execution as finished (success, stopped or failed)
+    pub async fn record_execution_finish(
+        &self,
+        project_slug: &str,
+        execution_id: &str,
+        termination: &Termination,
+        duration_ms: u64,
+    ) -> Result<()> {
+        let pool = self.get_project_pool(project_slug).await?;
+        let error = match termination {
+            Termination::Completed => None,
+            Termination::StoppedEarly { node_id, reason } => Some(format!("Stopped at '{}': {}", node_id, reason)),
+            Termination::Failed { error } => Some(error.clone()),
+        };
+
+        sqlx::query(
+            r#"
+            UPDATE executions
+            SET status = ?, error = ?, duration_ms = ?, finished_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(termination.status())
+        .bind(error)
+        .bind(duration_ms as i64)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(execution_id)
+        .execute(&pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Mark executions left running by a previous process as interrupted
+    /// 
+    /// Called once at startup, before any trigger runs: every "running" record
+    /// then belongs to a process that died mid-run. Returns the updated records.
+    pub async fn mark_interrupted_executions(&self, project_slug: &str) -> Result<Vec<ExecutionRecord>> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let ids: Vec<String> = sqlx::query_scalar(
+            r#"
+            UPDATE executions
+            SET status = 'interrupted', error = 'Interrupted by a server restart', finished_at = ?
+            WHERE status = 'running'
+            RETURNING id
+            "#,
+        )
+        .bind(chrono::Utc::now().to_rfc3339())
+        .fetch_all(&pool)
+        .await?;
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let rows = sqlx::query(&format!("{} WHERE e.id IN (SELECT value FROM json_each(?))", EXECUTION_COLUMNS))
+            .bind(serde_json::to_string(&ids)?)
+            .fetch_all(&pool)
+            .await?;
+
+        Ok(readable_executions(&rows))
+    }
+
+    /// Get an execution record by ID
+    pub async fn get_execution(&self, project_slug: &str, execution_id: &str) -> Result<Option<ExecutionRecord>> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let row = sqlx::query(&format!("{} WHERE e.id = ?", EXECUTION_COLUMNS))
+            .bind(execution_id)
+            .fetch_optional(&pool)
+            .await?;
+
+        row.map(|row| execution_from_row(&row)).transpose()
+    }
+
+    /// List recent executions, newest first, optionally for one workflow
+    pub async fn list_executions(
+        &self,
+        project_slug: &str,
+        workflow_id: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<ExecutionRecord>> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let rows = sqlx::query(&format!(
+            "{} WHERE (?1 IS NULL OR e.workflow_id = ?1) ORDER BY e.started_at DESC LIMIT ?2",
+            EXECUTION_COLUMNS,
+        ))
+        .bind(workflow_id)
+        .bind(limit)
+        .fetch_all(&pool)
+        .await?;
+
+        Ok(readable_executions(&rows))
+    }
+
+    /// Delete executions started before a point in time, returning the number removed
+    /// 
+    /// Shared payloads no remaining execution references are deleted with them.
+    pub async fn prune_executions_before(
+        &self,
+        project_slug: &str,
+        cutoff: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let result = sqlx::query("DELETE FROM executions WHERE started_at < ?")
+            .bind(cutoff.to_rfc3339())
+            .execute(&pool)
+            .await?;
+
+        if result.rows_affected() > 0 {
+            sqlx::query(
+                r#"
+                DELETE FROM payload_blobs
+                WHERE hash NOT IN (SELECT payload FROM executions WHERE payload LIKE 'sha256:%')
+                "#,
+            )
+            .execute(&pool)
+            .await?;
+        }
+
+        Ok(result.rows_affected())
+    }
+
+    /// Failed executions per workflow since a point in time
+    /// 
+    /// Returns workflow_id -> (failure count, most recent error).
+    pub async fn count_failures_since(
+        &self,
+        project_slug: &str,
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> Result<HashMap<String, (u64, Option<String>)>> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        // started_at is RFC 3339 UTC, so string comparison follows time order
+        let rows = sqlx::query(
+            r#"
+            SELECT workflow_id, COUNT(*) AS failures,
+                   (SELECT error FROM executions latest
+                    WHERE latest.workflow_id = executions.workflow_id AND latest.status = 'failed'
+                    ORDER BY latest.started_at DESC LIMIT 1) AS last_error
+            FROM executions
+            WHERE status = 'failed' AND started_at >= ?
+            GROUP BY workflow_id
+            "#,
+        )
+        .bind(since.to_rfc3339())
+        .fetch_all(&pool)
+        .await?;
+
+        Ok(rows.iter()
+            .map(|row| {
+                let failures: i64 = row.get("failures");
+                (row.get("workflow_id"), (failures as u64, row.get("last_error")))
+            })
+            .collect())
+    }
+}
+
+/// Records of the rows that can be read, skipping (and logging) the others
+///
+/// One unreadable record (say, a shared payload gone missing) shouldn't hide
+/// the rest of the history.
+fn readable_executions(rows: &[sqlx::sqlite::SqliteRow]) -> Vec<ExecutionRecord> {
+    rows.iter()
+        .filter_map(|row| match execution_from_row(row) {
+            Ok(execution) => Some(execution),
+            Err(e) => {
+                tracing::warn!("⚠️ Skipped unreadable execution {}: {}", row.get::<String, _>("id"), e);
+                None
+            }
+        })
+        .collect()
+}
+
+fn execution_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<ExecutionRecord> {
+    let stored: Vec<u8> = row.get("payload");
+    let payload_hash = stored.starts_with(PAYLOAD_REF_PREFIX.as_bytes())
+        .then(|| String::from_utf8_lossy(&stored).into_owned());
+    let payload_json = match (&payload_hash, row.get::<Option<Vec<u8>>, _>("payload_body")) {
+        (Some(_), Some(body)) => compression::decode(body)?,
+        (Some(hash), None) => return Err(anyhow::anyhow!("Shared payload {} is missing", hash)),
+        (None, _) => compression::decode(stored)?,
+    };
+    Ok(ExecutionRecord {
+        id: row.get("id"),
+        workflow_id: row.get("workflow_id"),
+        start_node_id: row.get("start_node_id"),
+        trigger_type: row.get("trigger_type"),
+        status: row.get("status"),
+        payload: serde_json::from_str(&payload_json)?,
+        payload_hash,
+        error: row.get("error"),
+        duration_ms: row.get("duration_ms"),
+        replay_of: row.get("replay_of"),
+        started_at: row.get("started_at"),
+        finished_at: row.get("finished_at"),
+    })
+}
@@ -0,0 +1,194 @@
+//! Project-scoped feature flags
+//! 
+//! Backed by the feature_flags table in {slug}/project.db and read by
+//! FeatureFlag nodes, so behavior can be toggled without editing workflows:
+//! 
+//! ```json
+//! { "enabled": true,
+//!   "rules": [{ "attribute": "plan", "values": ["enterprise"] }],
+//!   "percent": 20 }
+//! ```
+//! 
+//! Evaluation against the attributes of a run:
+//! 1. `enabled: false` turns the flag off for everyone (kill switch)
+//! 2. a matching rule (the attribute's value is one of `values`) turns it on
+//! 3. otherwise `percent` rolls it out to that share of "key" attribute values
+//!    (sticky: the same key always lands in the same bucket; runs without a key
+//!    are bucketed at random)
+//! 4. without `percent` the flag is on unless it has rules (which then restrict it)
+
+use crate::project::ProjectDatabaseManager;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use sqlx::Row;
+
+/// Attribute identifying who a flag is evaluated for (percentage rollout bucket)
+pub const FLAG_KEY_ATTRIBUTE: &str = "key";
+
+/// Targeting rule: on when the attribute's value is one of `values`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlagRule {
+    pub attribute: String,
+    pub values: Vec<Value>,
+}
+
+/// Stored flag definition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeatureFlag {
+    pub enabled: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<FlagRule>,
+    /// Rollout percentage (0-100) for attributes no rule matches
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub percent: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Flag with its name, as listed
+#[derive(Debug, Clone, Serialize)]
+pub struct NamedFeatureFlag {
+    pub name: String,
+    #[serde(flatten)]
+    pub flag: FeatureFlag,
+    pub updated_at: String,
+}
+
+/// Result of evaluating a flag
+#[derive(Debug, Clone, Serialize)]
+pub struct FlagEvaluation {
+    pub enabled: bool,
+    /// "disabled", "rule", "rollout", "default" or "missing" (no such flag)
+    pub reason: &'static str,
+}
+
+impl FeatureFlag {
+    /// Reject out-of-range percentages and empty rules
+    pub fn validate(&self) -> Result<()> {
+        if self.percent.is_some_and(|percent| percent > 100) {
+            return Err(anyhow::anyhow!("'percent' must be between 0 and 100"));
+        }
+        if let Some(rule) = self.rules.iter().find(|rule| rule.attribute.is_empty() || rule.values.is_empty()) {
+            return Err(anyhow::anyhow!("rule on '{}' needs an attribute and at least one value", rule.attribute));
+        }
+        Ok(())
+    }
+
+    /// Evaluate the flag named `name` for a set of attributes
+    pub fn evaluate(&self, name: &str, attributes: &Map<String, Value>) -> FlagEvaluation {
+        let result = |enabled, reason| FlagEvaluation { enabled, reason };
+        if !self.enabled {
+            return result(false, "disabled");
+        }
+        let matches = |rule: &FlagRule| attributes.get(&rule.attribute)
+            .is_some_and(|value| rule.values.iter().any(|candidate| same_value(candidate, value)));
+        if self.rules.iter().any(matches) {
+            return result(true, "rule");
+        }
+        match self.percent {
+            Some(percent) => {
+                let bucket = match attributes.get(FLAG_KEY_ATTRIBUTE).filter(|key| !key.is_null()) {
+                    Some(key) => rollout_bucket(name, key),
+                    None => (uuid::Uuid::new_v4().as_u128() % 100) as u8,
+                };
+                result(bucket < percent, "rollout")
+            }
+            None => result(self.rules.is_empty(), "default"),
+        }
+    }
+}
+
+/// Strings compare with numbers by their text ("42" matches 42)
+fn same_value(candidate: &Value, value: &Value) -> bool {
+    match (candidate, value) {
+        (Value::String(a), Value::Number(b)) | (Value::Number(b), Value::String(a)) => *a == b.to_string(),
+        _ => candidate == value,
+    }
+}
+
+/// Stable 0-99 bucket of a key for a flag (each flag buckets keys independently)
+fn rollout_bucket(name: &str, key: &Value) -> u8 {
+    let key = match key {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    let digest = Sha256::digest(format!("{}:{}", name, key).as_bytes());
+    let mut prefix = [0u8; 8];
+    prefix.copy_from_slice(&digest[..8]);
+    (u64::from_be_bytes(prefix) % 100) as u8
+}
+
+impl ProjectDatabaseManager {
+    /// Get a flag by name
+    pub async fn flag_get(&self, project_slug: &str, name: &str) -> Result<Option<FeatureFlag>> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let row = sqlx::query("SELECT definition FROM feature_flags WHERE name = ?")
+            .bind(name)
+            .fetch_optional(&pool)
+            .await?;
+
+        match row {
+            Some(row) => {
+                let definition: String = row.get("definition");
+                Ok(Some(serde_json::from_str(&definition)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// All flags of a project, sorted by name
+    pub async fn flag_list(&self, project_slug: &str) -> Result<Vec<NamedFeatureFlag>> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let rows = sqlx::query("SELECT name, definition, CAST(updated_at AS TEXT) AS updated_at FROM feature_flags ORDER BY name")
+            .fetch_all(&pool)
+            .await?;
+
+        rows.iter()
+            .map(|row| {
+                let definition: String = row.get("definition");
+                Ok(NamedFeatureFlag {
+                    name: row.get("name"),
+                    flag: serde_json::from_str(&definition)?,
+                    updated_at: row.get("updated_at"),
+                })
+            })
+            .collect()
+    }
+
+    /// Create or replace a flag (UPSERT)
+    pub async fn flag_set(&self, project_slug: &str, name: &str, flag: &FeatureFlag) -> Result<()> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO feature_flags (name, definition, updated_at)
+            VALUES (?, ?, CURRENT_TIMESTAMP)
+            ON CONFLICT(name) DO UPDATE SET
+                definition = excluded.definition,
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(name)
+        .bind(serde_json::to_string(flag)?)
+        .execute(&pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Delete a flag, returning whether it existed
+    pub async fn flag_delete(&self, project_slug: &str, name: &str) -> Result<bool> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let result = sqlx::query("DELETE FROM feature_flags WHERE name = ?")
+            .bind(name)
+            .execute(&pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,91 @@
+//! Trigger inbox (exactly-once processing of broker messages)
+//!
+//! Backed by the trigger_inbox table in {slug}/project.db. A trigger with an
+//! exactly-once key claims each message key here before running the workflow
+//! and acknowledges the message to its broker only afterwards. A redelivery
+//! (broker retry, reconnect after a crash) finds the key already claimed and is
+//! acknowledged without running again. Claims are "running" until the run
+//! finishes, then carry its status (success, stopped or failed). A claim left
+//! "running" by a crash is taken over by the redelivery when its execution was
+//! interrupted (or never started), so the message still runs once.
+
+use crate::project::ProjectDatabaseManager;
+use anyhow::Result;
+use chrono::Utc;
+
+impl ProjectDatabaseManager {
+    /// Claim a message key for one execution; false when it was claimed before (a redelivery)
+    ///
+    /// With `reclaim_interrupted` a claim still "running" whose execution was
+    /// interrupted by a restart, or never recorded, is handed to `execution_id`.
+    /// Workflows resumed on restart pass false: recovery already runs them again.
+    pub async fn inbox_claim(
+        &self,
+        project_slug: &str,
+        workflow_id: &str,
+        node_id: &str,
+        message_key: &str,
+        execution_id: &str,
+        reclaim_interrupted: bool,
+    ) -> Result<bool> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO trigger_inbox (workflow_id, node_id, message_key, execution_id, status, received_at)
+            VALUES (?, ?, ?, ?, 'running', ?)
+            ON CONFLICT (workflow_id, node_id, message_key) DO UPDATE
+            SET execution_id = excluded.execution_id, received_at = excluded.received_at
+            WHERE ? AND trigger_inbox.status = 'running' AND NOT EXISTS (
+                SELECT 1 FROM executions
+                WHERE executions.id = trigger_inbox.execution_id AND executions.status != 'interrupted'
+            )
+            "#,
+        )
+        .bind(workflow_id)
+        .bind(node_id)
+        .bind(message_key)
+        .bind(execution_id)
+        .bind(Utc::now().to_rfc3339())
+        .bind(reclaim_interrupted)
+        .execute(&pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Record the outcome of a claimed message's run
+    pub async fn inbox_finish(&self, project_slug: &str, workflow_id: &str, node_id: &str, message_key: &str, status: &str) -> Result<()> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        sqlx::query(
+            r#"
+            UPDATE trigger_inbox SET status = ?, finished_at = ?
+            WHERE workflow_id = ? AND node_id = ? AND message_key = ?
+            "#,
+        )
+        .bind(status)
+        .bind(Utc::now().to_rfc3339())
+        .bind(workflow_id)
+        .bind(node_id)
+        .bind(message_key)
+        .execute(&pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Forget claims received before a point in time, returning the number removed
+    ///
+    /// Redeliveries older than the execution retention are processed again.
+    pub async fn prune_inbox_before(&self, project_slug: &str, cutoff: chrono::DateTime<Utc>) -> Result<u64> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let result = sqlx::query("DELETE FROM trigger_inbox WHERE received_at < ?")
+            .bind(cutoff.to_rfc3339())
+            .execute(&pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
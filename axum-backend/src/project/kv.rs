@@ -0,0 +1,175 @@
+//! Project-scoped key-value store for cross-execution state
+//! 
+//! Backed by the kv_store table in {slug}/project.db. Used by KVStore nodes
+//! for cursors, dedupe markers, and counters that must survive between runs.
+//! Expired keys are treated as missing and cleaned up lazily on access, or in
+//! bulk by the retention janitor.
+
+use crate::project::ProjectDatabaseManager;
+use anyhow::Result;
+use serde_json::Value;
+use sqlx::Row;
+
+impl ProjectDatabaseManager {
+    /// Delete all expired keys, returning the number removed
+    pub async fn kv_purge_expired(&self, project_slug: &str) -> Result<u64> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let result = sqlx::query("DELETE FROM kv_store WHERE expires_at IS NOT NULL AND expires_at <= ?")
+            .bind(chrono::Utc::now().timestamp())
+            .execute(&pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Get a value by key (None if missing or expired)
+    pub async fn kv_get(&self, project_slug: &str, key: &str) -> Result<Option<Value>> {
+        let pool = self.get_project_pool(project_slug).await?;
+        let now = chrono::Utc::now().timestamp();
+
+        // Numbers are stored with INTEGER/REAL affinity, so read them back as JSON text
+        let row = sqlx::query("SELECT CAST(value AS TEXT) AS value, expires_at FROM kv_store WHERE key = ?")
+            .bind(key)
+            .fetch_optional(&pool)
+            .await?;
+
+        match row {
+            Some(row) => {
+                let expires_at: Option<i64> = row.get("expires_at");
+                if matches!(expires_at, Some(ts) if ts <= now) {
+                    // Lazy expiry cleanup
+                    sqlx::query("DELETE FROM kv_store WHERE key = ? AND expires_at <= ?")
+                        .bind(key)
+                        .bind(now)
+                        .execute(&pool)
+                        .await?;
+                    return Ok(None);
+                }
+                let value_json: String = row.get("value");
+                Ok(Some(serde_json::from_str(&value_json)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Set a value with optional TTL in seconds (UPSERT)
+    pub async fn kv_set(&self, project_slug: &str, key: &str, value: &Value, ttl_seconds: Option<u64>) -> Result<()> {
+        let pool = self.get_project_pool(project_slug).await?;
+        let expires_at = ttl_seconds.map(|ttl| chrono::Utc::now().timestamp() + ttl as i64);
+
+        sqlx::query(
+            r#"
+            INSERT INTO kv_store (key, value, expires_at, updated_at)
+            VALUES (?, ?, ?, CURRENT_TIMESTAMP)
+            ON CONFLICT(key) DO UPDATE SET
+                value = excluded.value,
+                expires_at = excluded.expires_at,
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(key)
+        .bind(serde_json::to_string(value)?)
+        .bind(expires_at)
+        .execute(&pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Delete a key, returning whether it existed
+    pub async fn kv_delete(&self, project_slug: &str, key: &str) -> Result<bool> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let result = sqlx::query("DELETE FROM kv_store WHERE key = ?")
+            .bind(key)
+            .execute(&pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Store a key only if it is missing or expired, returning whether it was stored
+    /// 
+    /// ATOMIC: Single UPSERT statement, so of two concurrent claims exactly one wins.
+    pub async fn kv_claim(&self, project_slug: &str, key: &str, value: &Value, ttl_seconds: u64) -> Result<bool> {
+        let pool = self.get_project_pool(project_slug).await?;
+        let now = chrono::Utc::now().timestamp();
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO kv_store (key, value, expires_at, updated_at)
+            VALUES (?1, ?2, ?3, CURRENT_TIMESTAMP)
+            ON CONFLICT(key) DO UPDATE SET
+                value = excluded.value,
+                expires_at = excluded.expires_at,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE kv_store.expires_at IS NOT NULL AND kv_store.expires_at <= ?4
+            "#,
+        )
+        .bind(key)
+        .bind(serde_json::to_string(value)?)
+        .bind(now + ttl_seconds as i64)
+        .bind(now)
+        .execute(&pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Atomically increment a numeric value (missing or expired keys start at 0)
+    /// 
+    /// ATOMIC: Single UPSERT statement, safe under concurrent executions.
+    /// A TTL, when given, is only applied when the counter is (re)created.
+    pub async fn kv_increment(&self, project_slug: &str, key: &str, amount: f64, ttl_seconds: Option<u64>) -> Result<Value> {
+        let pool = self.get_project_pool(project_slug).await?;
+        let now = chrono::Utc::now().timestamp();
+        let expires_at = ttl_seconds.map(|ttl| now + ttl as i64);
+
+        // Integer amounts keep integer counters, fractional amounts switch to REAL
+        let row = if amount.fract() == 0.0 {
+            sqlx::query(
+                r#"
+                INSERT INTO kv_store (key, value, expires_at, updated_at)
+                VALUES (?1, ?2, ?3, CURRENT_TIMESTAMP)
+                ON CONFLICT(key) DO UPDATE SET
+                    value = CASE WHEN kv_store.expires_at IS NOT NULL AND kv_store.expires_at <= ?4
+                        THEN excluded.value ELSE CAST(kv_store.value AS INTEGER) + ?2 END,
+                    expires_at = CASE WHEN kv_store.expires_at IS NOT NULL AND kv_store.expires_at <= ?4
+                        THEN excluded.expires_at ELSE kv_store.expires_at END,
+                    updated_at = CURRENT_TIMESTAMP
+                RETURNING CAST(value AS TEXT) AS value
+                "#,
+            )
+            .bind(key)
+            .bind(amount as i64)
+            .bind(expires_at)
+            .bind(now)
+            .fetch_one(&pool)
+            .await?
+        } else {
+            sqlx::query(
+                r#"
+                INSERT INTO kv_store (key, value, expires_at, updated_at)
+                VALUES (?1, ?2, ?3, CURRENT_TIMESTAMP)
+                ON CONFLICT(key) DO UPDATE SET
+                    value = CASE WHEN kv_store.expires_at IS NOT NULL AND kv_store.expires_at <= ?4
+                        THEN excluded.value ELSE CAST(kv_store.value AS REAL) + ?2 END,
+                    expires_at = CASE WHEN kv_store.expires_at IS NOT NULL AND kv_store.expires_at <= ?4
+                        THEN excluded.expires_at ELSE kv_store.expires_at END,
+                    updated_at = CURRENT_TIMESTAMP
+                RETURNING CAST(value AS TEXT) AS value
+                "#,
+            )
+            .bind(key)
+            .bind(amount)
+            .bind(expires_at)
+            .bind(now)
+            .fetch_one(&pool)
+            .await?
+        };
+
+        let value_text: String = row.get("value");
+        Ok(serde_json::from_str(&value_text).unwrap_or(Value::String(value_text)))
+    }
+}
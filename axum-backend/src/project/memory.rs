@@ -0,0 +1,171 @@
+//! Conversation memory for chat workflows
+//! 
+//! Backed by the memory_messages and memory_sessions tables in {slug}/project.db.
+//! Memory nodes append messages per session ID and load the recent window (plus
+//! the running summary of older, compacted messages), so LLM-based workflows keep
+//! context across webhook calls. Sessions are project-scoped: every workflow of
+//! the project sees the same history for a session ID.
+
+use crate::project::ProjectDatabaseManager;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::Row;
+
+/// Stored chat message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryMessage {
+    pub role: String,
+    /// Text, or structured content (e.g. an array of content parts)
+    pub content: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+}
+
+/// Recent messages of a session
+#[derive(Debug, Clone, Default)]
+pub struct MemorySession {
+    /// Summary of messages compacted by a summarize operation
+    pub summary: Option<String>,
+    /// Most recent messages, oldest first
+    pub messages: Vec<MemoryMessage>,
+    /// Number of stored messages (including those outside the window)
+    pub stored: u64,
+}
+
+impl ProjectDatabaseManager {
+    /// Append messages to a session in one transaction
+    pub async fn memory_append(&self, project_slug: &str, session_id: &str, messages: &[MemoryMessage]) -> Result<()> {
+        let pool = self.get_project_pool(project_slug).await?;
+        let mut tx = pool.begin().await?;
+
+        for message in messages {
+            sqlx::query("INSERT INTO memory_messages (session_id, role, content) VALUES (?, ?, ?)")
+                .bind(session_id)
+                .bind(&message.role)
+                .bind(serde_json::to_string(&message.content)?)
+                .execute(&mut *tx)
+                .await?;
+        }
+        touch_session(&mut tx, session_id, None).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Load the summary and the last `window` messages of a session
+    pub async fn memory_load(&self, project_slug: &str, session_id: &str, window: usize) -> Result<MemorySession> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let summary: Option<String> = sqlx::query("SELECT summary FROM memory_sessions WHERE session_id = ?")
+            .bind(session_id)
+            .fetch_optional(&pool)
+            .await?
+            .and_then(|row| row.get("summary"));
+
+        let stored: i64 = sqlx::query("SELECT COUNT(*) AS stored FROM memory_messages WHERE session_id = ?")
+            .bind(session_id)
+            .fetch_one(&pool)
+            .await?
+            .get("stored");
+
+        let rows = sqlx::query(
+            "SELECT role, content, created_at FROM memory_messages WHERE session_id = ? ORDER BY id DESC LIMIT ?"
+        )
+            .bind(session_id)
+            .bind(window as i64)
+            .fetch_all(&pool)
+            .await?;
+
+        let mut messages = rows.iter()
+            .map(|row| {
+                let content_json: String = row.get("content");
+                Ok(MemoryMessage {
+                    role: row.get("role"),
+                    content: serde_json::from_str(&content_json)?,
+                    created_at: row.get("created_at"),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        messages.reverse();
+
+        Ok(MemorySession { summary, messages, stored: stored as u64 })
+    }
+
+    /// Delete all but the last `keep_last` messages, returning the number removed
+    pub async fn memory_trim(&self, project_slug: &str, session_id: &str, keep_last: usize) -> Result<u64> {
+        let pool = self.get_project_pool(project_slug).await?;
+        let mut tx = pool.begin().await?;
+        let removed = delete_older(&mut tx, session_id, keep_last).await?;
+        tx.commit().await?;
+        Ok(removed)
+    }
+
+    /// Replace the session summary and drop the messages it covers (all but the last `keep_last`)
+    pub async fn memory_summarize(&self, project_slug: &str, session_id: &str, summary: &str, keep_last: usize) -> Result<u64> {
+        let pool = self.get_project_pool(project_slug).await?;
+        let mut tx = pool.begin().await?;
+
+        let removed = delete_older(&mut tx, session_id, keep_last).await?;
+        touch_session(&mut tx, session_id, Some(summary)).await?;
+
+        tx.commit().await?;
+        Ok(removed)
+    }
+
+    /// Delete a session's messages and summary, returning whether it existed
+    pub async fn memory_clear(&self, project_slug: &str, session_id: &str) -> Result<bool> {
+        let pool = self.get_project_pool(project_slug).await?;
+        let mut tx = pool.begin().await?;
+
+        let messages = sqlx::query("DELETE FROM memory_messages WHERE session_id = ?")
+            .bind(session_id)
+            .execute(&mut *tx)
+            .await?;
+        let session = sqlx::query("DELETE FROM memory_sessions WHERE session_id = ?")
+            .bind(session_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(messages.rows_affected() > 0 || session.rows_affected() > 0)
+    }
+}
+
+/// Delete all but the newest `keep_last` messages of a session
+async fn delete_older(tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>, session_id: &str, keep_last: usize) -> Result<u64> {
+    let result = sqlx::query(
+        r#"
+        DELETE FROM memory_messages
+        WHERE session_id = ? AND id NOT IN (
+            SELECT id FROM memory_messages WHERE session_id = ? ORDER BY id DESC LIMIT ?
+        )
+        "#,
+    )
+    .bind(session_id)
+    .bind(session_id)
+    .bind(keep_last as i64)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Record session activity, replacing the summary when one is given
+async fn touch_session(tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>, session_id: &str, summary: Option<&str>) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO memory_sessions (session_id, summary, updated_at)
+        VALUES (?, ?, CURRENT_TIMESTAMP)
+        ON CONFLICT(session_id) DO UPDATE SET
+            summary = COALESCE(excluded.summary, memory_sessions.summary),
+            updated_at = CURRENT_TIMESTAMP
+        "#,
+    )
+    .bind(session_id)
+    .bind(summary)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
@@ -1,10 +1,30 @@
-/// Project management module
-/// 
-/// Handles project-level organization, database isolation, and multi-tenancy.
-/// Each project gets isolated databases: {slug}_project.db and {slug}_simpletable.db
+//! Project management module
+//! 
+//! Handles project-level organization, database isolation, and multi-tenancy.
+//! Each project gets isolated databases: {slug}_project.db and {slug}_simpletable.db
+//! (or, per project settings, a PostgreSQL schema for simpletable data)
 
+pub mod calendars;
+pub mod compression;
+pub mod crypto;
 pub mod database;
+pub mod devices;
+pub mod executions;
+pub mod flags;
+pub mod inbox;
+pub mod kv;
+pub mod memory;
+pub mod oauth;
+pub mod outbox;
+pub mod pg_dyn_table;
+pub mod pg_numeric;
+pub mod pg_pool;
+pub mod secrets;
+pub mod simpletable_pg;
+pub mod snapshots;
+pub mod transaction;
 pub mod types;
+pub mod windows;
 
 pub use database::ProjectDatabaseManager;
 pub use types::Project;
@@ -0,0 +1,421 @@
+//! Project-scoped OAuth2 authorization-code credentials
+//! 
+//! Backed by the oauth_credentials table in {slug}/project.db. An admin
+//! registers a credential (provider URLs, client id/secret), opens the
+//! authorization URL and the provider redirects back to
+//! /api/projects/{slug}/oauth/callback, where the code is exchanged for tokens.
+//! Client secrets and tokens are sealed with the instance master key; access
+//! tokens are refreshed shortly before they expire when a node asks for them.
+
+use crate::project::ProjectDatabaseManager;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::Row;
+use std::collections::HashMap;
+
+/// Seconds an authorization request (state) stays valid
+const STATE_TTL_SECS: i64 = 600;
+
+/// Access tokens expiring within this many seconds are refreshed before use
+const REFRESH_MARGIN_SECS: i64 = 60;
+
+/// Provider configuration of an OAuth2 credential
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthCredentialConfig {
+    /// Provider authorization endpoint
+    pub auth_url: String,
+    /// Provider token endpoint
+    pub token_url: String,
+    pub client_id: String,
+    /// Write-only; stored sealed and never returned (omit on update to keep it)
+    #[serde(default, skip_serializing)]
+    pub client_secret: Option<String>,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// Must point at /api/projects/{slug}/oauth/callback and be registered with the provider
+    pub redirect_uri: String,
+    /// Extra authorization URL parameters (e.g. { "access_type": "offline", "prompt": "consent" })
+    #[serde(default)]
+    pub extra_auth_params: HashMap<String, String>,
+}
+
+impl OAuthCredentialConfig {
+    /// Validate URLs and required fields
+    pub fn validate(&self) -> Result<()> {
+        for (field, url) in [("auth_url", &self.auth_url), ("token_url", &self.token_url)] {
+            let parsed = reqwest::Url::parse(url)
+                .map_err(|e| anyhow::anyhow!("Invalid {}: {}", field, e))?;
+            if !matches!(parsed.scheme(), "http" | "https") {
+                return Err(anyhow::anyhow!("{} must be an http(s) URL", field));
+            }
+        }
+        let redirect = reqwest::Url::parse(&self.redirect_uri)
+            .map_err(|e| anyhow::anyhow!("Invalid redirect_uri: {}", e))?;
+        if !redirect.path().ends_with("/oauth/callback") {
+            return Err(anyhow::anyhow!("redirect_uri must point at /api/projects/{{slug}}/oauth/callback"));
+        }
+        if self.client_id.is_empty() {
+            return Err(anyhow::anyhow!("client_id must not be empty"));
+        }
+        if matches!(&self.client_secret, Some(secret) if secret.is_empty()) {
+            return Err(anyhow::anyhow!("client_secret must not be empty"));
+        }
+        Ok(())
+    }
+}
+
+/// Credential as listed by the API (no secrets or tokens)
+#[derive(Debug, Clone, Serialize)]
+pub struct OAuthCredentialStatus {
+    pub name: String,
+    pub config: OAuthCredentialConfig,
+    /// Whether the authorization flow has been completed
+    pub authorized: bool,
+    pub has_refresh_token: bool,
+    /// Access token expiry (RFC 3339), if the provider reported one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
+    pub updated_at: String,
+}
+
+/// OAuth failure with a distinct API status
+#[derive(Debug)]
+pub enum OAuthError {
+    /// No credential with this name
+    NotFound(String),
+    /// Bad input: invalid config, unknown or expired state
+    Invalid(String),
+    /// The provider rejected the request or returned an unusable response
+    Provider(String),
+}
+
+impl std::fmt::Display for OAuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound(name) => write!(f, "OAuth credential '{}' not found", name),
+            Self::Invalid(message) => write!(f, "{}", message),
+            Self::Provider(message) => write!(f, "OAuth provider error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for OAuthError {}
+
+/// Token endpoint response (RFC 6749 section 5.1)
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    /// Seconds; some providers send it as a string
+    #[serde(default)]
+    expires_in: Option<Value>,
+}
+
+impl TokenResponse {
+    fn expires_at(&self, now: i64) -> Option<i64> {
+        let expires_in = match self.expires_in.as_ref()? {
+            Value::Number(n) => n.as_i64()?,
+            Value::String(s) => s.parse().ok()?,
+            _ => return None,
+        };
+        Some(now + expires_in)
+    }
+}
+
+impl ProjectDatabaseManager {
+    /// Create or update a credential (a missing client_secret keeps the stored one)
+    pub async fn oauth_upsert(&self, project_slug: &str, name: &str, config: &OAuthCredentialConfig) -> Result<()> {
+        let master_key = self.master_key()?;
+        if !is_valid_credential_name(name) {
+            return Err(OAuthError::Invalid(format!("Invalid credential name: {}", name)).into());
+        }
+        config.validate().map_err(|e| OAuthError::Invalid(e.to_string()))?;
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let sealed_secret = config.client_secret.as_deref().map(|secret| master_key.seal(secret)).transpose()?;
+        let result = sqlx::query(
+            r#"
+            INSERT INTO oauth_credentials (name, config, client_secret_enc, updated_at)
+            SELECT ?1, ?2, ?3, ?4 WHERE ?3 IS NOT NULL
+            ON CONFLICT(name) DO UPDATE SET
+                config = excluded.config,
+                client_secret_enc = excluded.client_secret_enc,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(name)
+        .bind(serde_json::to_string(config)?)
+        .bind(&sealed_secret)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(&pool)
+        .await?;
+        if result.rows_affected() > 0 {
+            return Ok(());
+        }
+
+        // No secret given: only updating an existing credential is possible
+        let result = sqlx::query("UPDATE oauth_credentials SET config = ?, updated_at = ? WHERE name = ?")
+            .bind(serde_json::to_string(config)?)
+            .bind(chrono::Utc::now().to_rfc3339())
+            .bind(name)
+            .execute(&pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(OAuthError::Invalid("client_secret is required for a new credential".to_string()).into());
+        }
+        Ok(())
+    }
+
+    /// List credentials with their authorization status
+    pub async fn oauth_list(&self, project_slug: &str) -> Result<Vec<OAuthCredentialStatus>> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let rows = sqlx::query(
+            r#"
+            SELECT name, config, access_token_enc IS NOT NULL AS authorized,
+                   refresh_token_enc IS NOT NULL AS has_refresh_token, expires_at, updated_at
+            FROM oauth_credentials ORDER BY name
+            "#,
+        )
+        .fetch_all(&pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                let config_json: String = row.get("config");
+                let expires_at: Option<i64> = row.get("expires_at");
+                Ok(OAuthCredentialStatus {
+                    name: row.get("name"),
+                    config: serde_json::from_str(&config_json)?,
+                    authorized: row.get("authorized"),
+                    has_refresh_token: row.get("has_refresh_token"),
+                    expires_at: expires_at
+                        .and_then(|ts| chrono::DateTime::from_timestamp(ts, 0))
+                        .map(|ts| ts.to_rfc3339()),
+                    updated_at: row.get("updated_at"),
+                })
+            })
+            .collect()
+    }
+
+    /// Delete a credential and its tokens, returning whether it existed
+    pub async fn oauth_delete(&self, project_slug: &str, name: &str) -> Result<bool> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let result = sqlx::query("DELETE FROM oauth_credentials WHERE name = ?")
+            .bind(name)
+            .execute(&pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Start the authorization flow, returning the provider URL to open
+    /// 
+    /// Issues a fresh single-use state valid for 10 minutes.
+    pub async fn oauth_begin(&self, project_slug: &str, name: &str) -> Result<String> {
+        self.master_key()?;
+        let pool = self.get_project_pool(project_slug).await?;
+        let config = self.oauth_config(&pool, name).await?;
+
+        let state = uuid::Uuid::new_v4().simple().to_string();
+        sqlx::query("UPDATE oauth_credentials SET pending_state = ?, state_expires_at = ? WHERE name = ?")
+            .bind(&state)
+            .bind(chrono::Utc::now().timestamp() + STATE_TTL_SECS)
+            .bind(name)
+            .execute(&pool)
+            .await?;
+
+        let mut url = reqwest::Url::parse(&config.auth_url)?;
+        {
+            let mut query = url.query_pairs_mut();
+            query.append_pair("response_type", "code")
+                .append_pair("client_id", &config.client_id)
+                .append_pair("redirect_uri", &config.redirect_uri)
+                .append_pair("state", &state);
+            if !config.scopes.is_empty() {
+                query.append_pair("scope", &config.scopes.join(" "));
+            }
+            for (key, value) in &config.extra_auth_params {
+                query.append_pair(key, value);
+            }
+        }
+        Ok(url.into())
+    }
+
+    /// Finish the authorization flow: check the state and exchange the code for tokens
+    /// 
+    /// Returns the name of the authorized credential.
+    pub async fn oauth_complete(&self, project_slug: &str, code: &str, state: &str) -> Result<String> {
+        let master_key = self.master_key()?;
+        let pool = self.get_project_pool(project_slug).await?;
+
+        // Consume the state atomically so a code can only be redeemed once
+        let name: Option<String> = sqlx::query_scalar(
+            r#"
+            UPDATE oauth_credentials SET pending_state = NULL, state_expires_at = NULL
+            WHERE pending_state = ? AND state_expires_at > ?
+            RETURNING name
+            "#,
+        )
+        .bind(state)
+        .bind(chrono::Utc::now().timestamp())
+        .fetch_optional(&pool)
+        .await?;
+        let name = name.ok_or_else(|| OAuthError::Invalid("Unknown or expired OAuth state".to_string()))?;
+
+        let config = self.oauth_config(&pool, &name).await?;
+        let client_secret = self.oauth_client_secret(&pool, &name).await?;
+        let tokens = request_tokens(&config.token_url, &[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &config.redirect_uri),
+            ("client_id", &config.client_id),
+            ("client_secret", &client_secret),
+        ]).await?;
+
+        let now = chrono::Utc::now().timestamp();
+        sqlx::query(
+            r#"
+            UPDATE oauth_credentials
+            SET access_token_enc = ?, refresh_token_enc = ?, expires_at = ?, updated_at = ?
+            WHERE name = ?
+            "#,
+        )
+        .bind(master_key.seal(&tokens.access_token)?)
+        .bind(tokens.refresh_token.as_deref().map(|token| master_key.seal(token)).transpose()?)
+        .bind(tokens.expires_at(now))
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(&name)
+        .execute(&pool)
+        .await?;
+
+        tracing::info!("🔑 OAuth credential '{}' authorized for project {}", name, project_slug);
+        Ok(name)
+    }
+
+    /// Current access token of a credential, refreshed first if it is about to expire
+    /// 
+    /// CONCURRENCY: Refreshes are serialized and re-checked after taking the lock,
+    /// so executions racing on an expired token trigger a single refresh.
+    pub async fn oauth_access_token(&self, project_slug: &str, name: &str) -> Result<String> {
+        let master_key = self.master_key()?;
+        let pool = self.get_project_pool(project_slug).await?;
+
+        if let Some(token) = self.oauth_fresh_token(&pool, name).await? {
+            return Ok(token);
+        }
+
+        let _refresh = self.oauth_refresh.lock().await;
+        if let Some(token) = self.oauth_fresh_token(&pool, name).await? {
+            return Ok(token);
+        }
+
+        let refresh_token: Option<String> = sqlx::query_scalar("SELECT refresh_token_enc FROM oauth_credentials WHERE name = ?")
+            .bind(name)
+            .fetch_optional(&pool)
+            .await?
+            .flatten();
+        let refresh_token = master_key.open(&refresh_token.ok_or_else(|| OAuthError::Invalid(format!(
+            "OAuth credential '{}' has expired and has no refresh token; authorize it again", name
+        )))?)?;
+
+        let config = self.oauth_config(&pool, name).await?;
+        let client_secret = self.oauth_client_secret(&pool, name).await?;
+        let tokens = request_tokens(&config.token_url, &[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", &refresh_token),
+            ("client_id", &config.client_id),
+            ("client_secret", &client_secret),
+        ]).await?;
+
+        // Providers that don't rotate refresh tokens omit them; keep the current one
+        let now = chrono::Utc::now().timestamp();
+        sqlx::query(
+            r#"
+            UPDATE oauth_credentials
+            SET access_token_enc = ?, refresh_token_enc = COALESCE(?, refresh_token_enc), expires_at = ?, updated_at = ?
+            WHERE name = ?
+            "#,
+        )
+        .bind(master_key.seal(&tokens.access_token)?)
+        .bind(tokens.refresh_token.as_deref().map(|token| master_key.seal(token)).transpose()?)
+        .bind(tokens.expires_at(now))
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(name)
+        .execute(&pool)
+        .await?;
+
+        tracing::info!("🔄 Refreshed OAuth access token '{}' for project {}", name, project_slug);
+        Ok(tokens.access_token)
+    }
+
+    /// Stored access token if it is not about to expire
+    async fn oauth_fresh_token(&self, pool: &sqlx::SqlitePool, name: &str) -> Result<Option<String>> {
+        let row = sqlx::query("SELECT access_token_enc, expires_at FROM oauth_credentials WHERE name = ?")
+            .bind(name)
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| OAuthError::NotFound(name.to_string()))?;
+
+        let access_token: Option<String> = row.get("access_token_enc");
+        let expires_at: Option<i64> = row.get("expires_at");
+        let Some(access_token) = access_token else {
+            return Err(OAuthError::Invalid(format!("OAuth credential '{}' has not been authorized yet", name)).into());
+        };
+        if matches!(expires_at, Some(ts) if ts - REFRESH_MARGIN_SECS <= chrono::Utc::now().timestamp()) {
+            return Ok(None);
+        }
+        Ok(Some(self.master_key()?.open(&access_token)?))
+    }
+
+    async fn oauth_config(&self, pool: &sqlx::SqlitePool, name: &str) -> Result<OAuthCredentialConfig> {
+        let config_json: String = sqlx::query_scalar("SELECT config FROM oauth_credentials WHERE name = ?")
+            .bind(name)
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| OAuthError::NotFound(name.to_string()))?;
+        Ok(serde_json::from_str(&config_json)?)
+    }
+
+    async fn oauth_client_secret(&self, pool: &sqlx::SqlitePool, name: &str) -> Result<String> {
+        let sealed: String = sqlx::query_scalar("SELECT client_secret_enc FROM oauth_credentials WHERE name = ?")
+            .bind(name)
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| OAuthError::NotFound(name.to_string()))?;
+        self.master_key()?.open(&sealed)
+    }
+}
+
+/// Credential names are used in states and URLs: letters, digits, '-' and '_'
+fn is_valid_credential_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name != "callback"
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// POST a form to the token endpoint (client credentials in the body)
+async fn request_tokens(token_url: &str, form: &[(&str, &str)]) -> Result<TokenResponse> {
+    let response = reqwest::Client::new()
+        .post(token_url)
+        .header("Accept", "application/json")
+        .form(form)
+        .send()
+        .await
+        .map_err(|e| OAuthError::Provider(format!("token request failed: {}", e)))?;
+
+    let status = response.status();
+    let body: Value = response.json().await
+        .map_err(|e| OAuthError::Provider(format!("token endpoint returned invalid JSON ({}): {}", status, e)))?;
+    if !status.is_success() || body.get("error").is_some() {
+        let error = body.get("error").and_then(|e| e.as_str()).unwrap_or("unknown_error");
+        let description = body.get("error_description").and_then(|d| d.as_str()).unwrap_or("");
+        return Err(OAuthError::Provider(format!("{} {} {}", status.as_u16(), error, description).trim_end().to_string()).into());
+    }
+    serde_json::from_value(body)
+        .map_err(|e| OAuthError::Provider(format!("token response without access_token: {}", e)).into())
+}
@@ -0,0 +1,206 @@
+//! Store-and-forward outbox
+//! 
+//! Backed by the outbox table in {slug}/project.db. Nodes with
+//! "store_and_forward" park their execution context here when a send fails
+//! for lack of network; the outbox flusher replays them later (see
+//! runtime::outbox). Entries are "pending" until delivered (then deleted)
+//! or "failed" when a replay fails for another reason.
+
+use crate::project::ProjectDatabaseManager;
+use anyhow::Result;
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{sqlite::SqliteRow, Row};
+
+/// Parked node input, as listed by the outbox API
+#[derive(Debug, Clone, Serialize)]
+pub struct OutboxEntry {
+    pub id: i64,
+    pub workflow_id: String,
+    pub node_id: String,
+    /// "pending" or "failed"
+    pub status: String,
+    /// Replays so far
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    pub created_at: String,
+    pub next_attempt_at: DateTime<Utc>,
+    /// Input items of the parked node
+    pub items: Value,
+}
+
+/// Pending entry due for a replay, with its full execution context
+#[derive(Debug, Clone)]
+pub struct DueOutboxEntry {
+    pub id: i64,
+    pub workflow_id: String,
+    pub node_id: String,
+    pub attempts: u32,
+    pub context: Value,
+}
+
+fn outbox_entry(row: &SqliteRow) -> Result<OutboxEntry> {
+    let items: Option<String> = row.get("items");
+    Ok(OutboxEntry {
+        id: row.get("id"),
+        workflow_id: row.get("workflow_id"),
+        node_id: row.get("node_id"),
+        status: row.get("status"),
+        attempts: row.get::<i64, _>("attempts") as u32,
+        last_error: row.get("last_error"),
+        created_at: row.get("created_at"),
+        next_attempt_at: Utc.timestamp_millis_opt(row.get("next_attempt_at")).single().unwrap_or_default(),
+        items: items.map(|items| serde_json::from_str(&items)).transpose()?.unwrap_or(Value::Null),
+    })
+}
+
+impl ProjectDatabaseManager {
+    /// Park a node's execution context, due for a replay at `next_attempt_at` (Unix milliseconds)
+    /// 
+    /// Returns the entry ID
+    pub async fn outbox_push(&self, project_slug: &str, workflow_id: &str, node_id: &str, context: &Value, error: &str, next_attempt_at: i64) -> Result<i64> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO outbox (workflow_id, node_id, context, status, attempts, last_error, created_at, next_attempt_at)
+            VALUES (?, ?, ?, 'pending', 0, ?, ?, ?)
+            "#,
+        )
+        .bind(workflow_id)
+        .bind(node_id)
+        .bind(serde_json::to_string(context)?)
+        .bind(error)
+        .bind(Utc::now().to_rfc3339())
+        .bind(next_attempt_at)
+        .execute(&pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    /// Pending entries due at `now` (Unix milliseconds), oldest first
+    pub async fn outbox_due(&self, project_slug: &str, now: i64, limit: usize) -> Result<Vec<DueOutboxEntry>> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let rows = sqlx::query(
+            r#"
+            SELECT id, workflow_id, node_id, attempts, CAST(context AS TEXT) AS context
+            FROM outbox WHERE status = 'pending' AND next_attempt_at <= ? ORDER BY id LIMIT ?
+            "#,
+        )
+            .bind(now)
+            .bind(limit as i64)
+            .fetch_all(&pool)
+            .await?;
+
+        rows.iter()
+            .map(|row| {
+                let context: String = row.get("context");
+                Ok(DueOutboxEntry {
+                    id: row.get("id"),
+                    workflow_id: row.get("workflow_id"),
+                    node_id: row.get("node_id"),
+                    attempts: row.get::<i64, _>("attempts") as u32,
+                    context: serde_json::from_str(&context)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Entries of a project, oldest first, optionally only those with `status`
+    pub async fn outbox_list(&self, project_slug: &str, status: Option<&str>, limit: usize) -> Result<Vec<OutboxEntry>> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let rows = sqlx::query(
+            r#"
+            SELECT id, workflow_id, node_id, status, attempts, last_error, created_at, next_attempt_at,
+                   json_extract(context, '$.data') AS items
+            FROM outbox WHERE (? IS NULL OR status = ?) ORDER BY id LIMIT ?
+            "#,
+        )
+            .bind(status)
+            .bind(status)
+            .bind(limit as i64)
+            .fetch_all(&pool)
+            .await?;
+
+        rows.iter().map(outbox_entry).collect()
+    }
+
+    /// Number of (pending, failed) entries
+    pub async fn outbox_counts(&self, project_slug: &str) -> Result<(u64, u64)> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let row = sqlx::query(
+            r#"
+            SELECT COALESCE(SUM(status = 'pending'), 0) AS pending, COALESCE(SUM(status = 'failed'), 0) AS failed FROM outbox
+            "#,
+        )
+            .fetch_one(&pool)
+            .await?;
+
+        Ok((row.get::<i64, _>("pending") as u64, row.get::<i64, _>("failed") as u64))
+    }
+
+    /// Record a failed replay; the entry stays pending until `next_attempt_at`, or None marks it failed
+    pub async fn outbox_record_attempt(&self, project_slug: &str, id: i64, attempts: u32, error: &str, next_attempt_at: Option<i64>) -> Result<()> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        sqlx::query(
+            r#"
+            UPDATE outbox SET attempts = ?, last_error = ?,
+                status = CASE WHEN ? IS NULL THEN 'failed' ELSE 'pending' END,
+                next_attempt_at = COALESCE(?, next_attempt_at)
+            WHERE id = ?
+            "#,
+        )
+        .bind(attempts as i64)
+        .bind(error)
+        .bind(next_attempt_at)
+        .bind(next_attempt_at)
+        .bind(id)
+        .execute(&pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Make an entry pending and due at `now` (Unix milliseconds); false if it doesn't exist
+    pub async fn outbox_requeue(&self, project_slug: &str, id: i64, now: i64) -> Result<bool> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let result = sqlx::query("UPDATE outbox SET status = 'pending', next_attempt_at = ? WHERE id = ?")
+            .bind(now)
+            .bind(id)
+            .execute(&pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Delete failed entries parked before a point in time, returning the number removed
+    pub async fn prune_failed_outbox_before(&self, project_slug: &str, cutoff: DateTime<Utc>) -> Result<u64> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let result = sqlx::query("DELETE FROM outbox WHERE status = 'failed' AND created_at < ?")
+            .bind(cutoff.to_rfc3339())
+            .execute(&pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Delete an entry (delivered or discarded); false if it doesn't exist
+    pub async fn outbox_delete(&self, project_slug: &str, id: i64) -> Result<bool> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let result = sqlx::query("DELETE FROM outbox WHERE id = ?")
+            .bind(id)
+            .execute(&pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,177 @@
+//! PostgreSQL target tables for PGDynTableWriter nodes
+//! 
+//! Tables live in the mway_dynamic_tables schema of the database behind the
+//! node's secret and are created from the node's "columns" on first write.
+//! Items that carry fields the table doesn't have yet (schema drift) are
+//! handled by the node's "schema_drift" params:
+//! 
+//! { "schema_drift": { "mode": "ignore|fail|add_columns", "allow": ["discount", "utm_*"], "types": "text|infer" } }
+//! 
+//! "ignore" (default) drops the new fields with a warning, "fail" fails the node
+//! and "add_columns" adds the allowlisted fields as nullable columns. Columns are
+//! TEXT unless "types" is "infer", which picks BIGINT, DOUBLE PRECISION, BOOLEAN
+//! or JSONB from the value. Any drift is also published as a "schema.drift" event
+//! in the workflow's project.
+
+use crate::project::simpletable_pg::{db_error, is_identifier, json_to_param};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use tokio_postgres::{types::ToSql, Client};
+
+/// Schema holding every PGDynTableWriter table
+pub const DYNAMIC_SCHEMA: &str = "mway_dynamic_tables";
+
+/// Event published on the project's event bus when drift is detected
+pub const DRIFT_EVENT: &str = "schema.drift";
+
+/// What to do with fields the target table doesn't have
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftMode {
+    #[default]
+    Ignore,
+    Fail,
+    AddColumns,
+}
+
+/// Column types for created tables and added columns
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnTypes {
+    #[default]
+    Text,
+    Infer,
+}
+
+impl ColumnTypes {
+    /// SQL type of a new column holding `value`
+    pub fn column_type(&self, value: &Value) -> &'static str {
+        match (self, value) {
+            (ColumnTypes::Text, _) | (ColumnTypes::Infer, Value::String(_) | Value::Null) => "TEXT",
+            (ColumnTypes::Infer, Value::Number(n)) if n.is_i64() || n.is_u64() => "BIGINT",
+            (ColumnTypes::Infer, Value::Number(_)) => "DOUBLE PRECISION",
+            (ColumnTypes::Infer, Value::Bool(_)) => "BOOLEAN",
+            (ColumnTypes::Infer, Value::Array(_) | Value::Object(_)) => "JSONB",
+        }
+    }
+}
+
+/// Schema drift handling of a PGDynTableWriter node (params key "schema_drift")
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SchemaDriftPolicy {
+    #[serde(default)]
+    pub mode: DriftMode,
+    /// Fields that may be added: exact names or "prefix_*" (empty allows every field)
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub types: ColumnTypes,
+}
+
+impl SchemaDriftPolicy {
+    /// Read the node's "schema_drift" params (defaults to ignoring new fields)
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        match params.get("schema_drift") {
+            Some(policy) => serde_json::from_value(policy.clone())
+                .map_err(|e| anyhow::anyhow!("Invalid 'schema_drift': {}", e)),
+            None => Ok(Self::default()),
+        }
+    }
+
+    fn allows(&self, field: &str) -> bool {
+        self.allow.is_empty() || self.allow.iter().any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => field.starts_with(prefix),
+            None => field == pattern,
+        })
+    }
+
+    /// Decide which new fields become columns (only in add_columns mode)
+    pub fn plan(&self, new_fields: &[(String, Value)]) -> DriftReport {
+        let mut report = DriftReport::default();
+        for (field, value) in new_fields {
+            if self.mode == DriftMode::AddColumns && is_identifier(field) && self.allows(field) {
+                report.added.push(AddedColumn { name: field.clone(), column_type: self.types.column_type(value) });
+            } else {
+                report.ignored.push(field.clone());
+            }
+        }
+        report
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AddedColumn {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub column_type: &'static str,
+}
+
+/// New fields seen by one write and what happened to them
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DriftReport {
+    pub added: Vec<AddedColumn>,
+    pub ignored: Vec<String>,
+}
+
+impl DriftReport {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.ignored.is_empty()
+    }
+}
+
+/// Create the schema and table (columns typed from their first values) if missing
+pub async fn ensure_table(client: &Client, table: &str, columns: &[(String, &'static str)]) -> Result<()> {
+    let column_defs = columns.iter()
+        .map(|(name, column_type)| {
+            if !is_identifier(name) {
+                return Err(anyhow::anyhow!("Invalid column name: {}", name));
+            }
+            Ok(format!("\"{}\" {}", name, column_type))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    client.batch_execute(&format!(
+        "CREATE SCHEMA IF NOT EXISTS {schema}; CREATE TABLE IF NOT EXISTS {schema}.\"{table}\" ({columns})",
+        schema = DYNAMIC_SCHEMA, table = table, columns = column_defs.join(", "),
+    )).await.map_err(db_error)?;
+    Ok(())
+}
+
+/// Column names of a table in the dynamic schema
+pub async fn table_columns(client: &Client, table: &str) -> Result<HashSet<String>> {
+    let rows = client.query(
+        "SELECT column_name::text FROM information_schema.columns WHERE table_schema = $1 AND table_name = $2",
+        &[&DYNAMIC_SCHEMA, &table],
+    ).await.map_err(db_error)?;
+    Ok(rows.iter().map(|row| row.get(0)).collect())
+}
+
+/// Add nullable columns for drifted fields
+pub async fn add_columns(client: &Client, table: &str, columns: &[AddedColumn]) -> Result<()> {
+    for column in columns {
+        client.batch_execute(&format!(
+            "ALTER TABLE {}.\"{}\" ADD COLUMN IF NOT EXISTS \"{}\" {}",
+            DYNAMIC_SCHEMA, table, column.name, column.column_type,
+        )).await.map_err(db_error)?;
+    }
+    Ok(())
+}
+
+/// Insert one row, binding values by the column types
+pub async fn insert_row(client: &Client, table: &str, row: &[(String, Value)]) -> Result<u64> {
+    let columns: Vec<String> = row.iter().map(|(name, _)| format!("\"{}\"", name)).collect();
+    let placeholders: Vec<String> = (1..=row.len()).map(|i| format!("${}", i)).collect();
+    let statement = client.prepare(&format!(
+        "INSERT INTO {}.\"{}\" ({}) VALUES ({})",
+        DYNAMIC_SCHEMA, table, columns.join(", "), placeholders.join(", "),
+    )).await.map_err(db_error)?;
+
+    let params = row.iter()
+        .zip(statement.params())
+        .map(|((_, value), ty)| json_to_param(value, ty))
+        .collect::<Result<Vec<_>>>()?;
+    let refs: Vec<&(dyn ToSql + Sync)> = params.iter().map(|p| p.as_ref() as &(dyn ToSql + Sync)).collect();
+    client.execute(&statement, &refs).await.map_err(db_error)
+}
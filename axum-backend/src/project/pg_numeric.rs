@@ -0,0 +1,186 @@
+//! PostgreSQL NUMERIC values
+//!
+//! tokio-postgres has no built-in mapping for NUMERIC, so PGQuery results and
+//! bind parameters go through this codec for the binary wire format:
+//! ndigits, weight, sign and display scale, then base-10000 digit groups.
+//! Values travel as decimal strings ("-1234.5600"), which keeps them exact.
+
+use bytes::{BufMut, BytesMut};
+use serde_json::{json, Value};
+use tokio_postgres::types::{to_sql_checked, FromSql, IsNull, ToSql, Type};
+
+const SIGN_POSITIVE: u16 = 0x0000;
+const SIGN_NEGATIVE: u16 = 0x4000;
+const SIGN_NAN: u16 = 0xC000;
+const SIGN_POSITIVE_INFINITY: u16 = 0xD000;
+const SIGN_NEGATIVE_INFINITY: u16 = 0xF000;
+
+/// Decimal digits in a JSON float before a value is returned as a string instead
+const MAX_FLOAT_DIGITS: usize = 15;
+
+/// NUMERIC value as its decimal string ("NaN", "Infinity" and "-Infinity" included)
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PgNumeric(pub String);
+
+impl PgNumeric {
+    /// Bind value from JSON: numbers, or strings holding a plain decimal ("12.50")
+    pub fn from_json(value: &Value) -> Option<Self> {
+        let text = match value {
+            Value::Number(number) => match number.as_f64() {
+                // f64 Display never uses exponent notation
+                Some(float) if number.is_f64() => float.to_string(),
+                _ => number.to_string(),
+            },
+            Value::String(s) => s.trim().to_string(),
+            _ => return None,
+        };
+        match text.as_str() {
+            "NaN" | "Infinity" | "-Infinity" => Some(Self(text)),
+            _ if split_decimal(&text).is_some() => Some(Self(text)),
+            _ => None,
+        }
+    }
+
+    /// JSON for a result column: a number when it converts without loss, otherwise the decimal string
+    pub fn to_json(&self) -> Value {
+        let Some((_, int_part, frac_part)) = split_decimal(&self.0) else {
+            return json!(self.0);
+        };
+        let frac_part = frac_part.trim_end_matches('0');
+        if frac_part.is_empty() {
+            if let Ok(int) = format!("{}{}", if self.0.starts_with('-') { "-" } else { "" }, int_part).parse::<i64>() {
+                return json!(int);
+            }
+        }
+        let digits = int_part.trim_start_matches('0').len() + frac_part.len();
+        match self.0.parse::<f64>() {
+            Ok(float) if digits <= MAX_FLOAT_DIGITS => json!(float),
+            _ => json!(self.0),
+        }
+    }
+}
+
+/// Split a plain decimal into (negative, integer digits, fraction digits)
+fn split_decimal(text: &str) -> Option<(bool, &str, &str)> {
+    let (negative, unsigned) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let (int_part, frac_part) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if int_part.is_empty() && frac_part.is_empty() || !all_digits(int_part) || !all_digits(frac_part) {
+        return None;
+    }
+    Some((negative, int_part, frac_part))
+}
+
+impl<'a> FromSql<'a> for PgNumeric {
+    fn from_sql(_ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn std::error::Error + Sync + Send>> {
+        let read_u16 = |offset: usize| -> Result<u16, Box<dyn std::error::Error + Sync + Send>> {
+            raw.get(offset..offset + 2)
+                .map(|b| u16::from_be_bytes([b[0], b[1]]))
+                .ok_or_else(|| "truncated NUMERIC value".into())
+        };
+        let ndigits = read_u16(0)? as usize;
+        let weight = read_u16(2)? as i16 as i32;
+        let sign = read_u16(4)?;
+        let dscale = read_u16(6)? as usize;
+        let digits = (0..ndigits)
+            .map(|i| read_u16(8 + i * 2))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match sign {
+            SIGN_NAN => return Ok(Self("NaN".to_string())),
+            SIGN_POSITIVE_INFINITY => return Ok(Self("Infinity".to_string())),
+            SIGN_NEGATIVE_INFINITY => return Ok(Self("-Infinity".to_string())),
+            SIGN_POSITIVE | SIGN_NEGATIVE => {}
+            other => return Err(format!("invalid NUMERIC sign 0x{:04X}", other).into()),
+        }
+        // Digit group i is worth 10000^(weight - i)
+        let group = |i: i32| if i >= 0 { digits.get(i as usize).copied().unwrap_or(0) } else { 0 };
+
+        let mut text = String::new();
+        if sign == SIGN_NEGATIVE {
+            text.push('-');
+        }
+        if weight < 0 {
+            text.push('0');
+        } else {
+            text.push_str(&group(0).to_string());
+            for i in 1..=weight {
+                text.push_str(&format!("{:04}", group(i)));
+            }
+        }
+        if dscale > 0 {
+            let mut fraction = String::new();
+            let mut i = weight + 1;
+            while fraction.len() < dscale {
+                fraction.push_str(&format!("{:04}", group(i)));
+                i += 1;
+            }
+            fraction.truncate(dscale);
+            text.push('.');
+            text.push_str(&fraction);
+        }
+        Ok(Self(text))
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        *ty == Type::NUMERIC
+    }
+}
+
+impl ToSql for PgNumeric {
+    fn to_sql(&self, _ty: &Type, out: &mut BytesMut) -> Result<IsNull, Box<dyn std::error::Error + Sync + Send>> {
+        let special = match self.0.as_str() {
+            "NaN" => Some(SIGN_NAN),
+            "Infinity" => Some(SIGN_POSITIVE_INFINITY),
+            "-Infinity" => Some(SIGN_NEGATIVE_INFINITY),
+            _ => None,
+        };
+        if let Some(sign) = special {
+            out.put_u16(0);
+            out.put_i16(0);
+            out.put_u16(sign);
+            out.put_u16(0);
+            return Ok(IsNull::No);
+        }
+
+        let (negative, int_part, frac_part) = split_decimal(&self.0)
+            .ok_or_else(|| format!("invalid NUMERIC value '{}'", self.0))?;
+        // Pad to whole base-10000 groups: the integer part on the left, the fraction on the right
+        let int_part = int_part.trim_start_matches('0');
+        let int_padded = format!("{}{}", "0".repeat((4 - int_part.len() % 4) % 4), int_part);
+        let frac_padded = format!("{}{}", frac_part, "0".repeat((4 - frac_part.len() % 4) % 4));
+        let mut groups: Vec<u16> = int_padded.as_bytes().chunks(4)
+            .chain(frac_padded.as_bytes().chunks(4))
+            .map(|chunk| std::str::from_utf8(chunk).unwrap().parse().unwrap())
+            .collect();
+        let mut weight = (int_padded.len() / 4) as i32 - 1;
+
+        let leading_zeros = groups.iter().take_while(|group| **group == 0).count();
+        groups.drain(..leading_zeros);
+        weight -= leading_zeros as i32;
+        while groups.last() == Some(&0) {
+            groups.pop();
+        }
+        if groups.is_empty() {
+            weight = 0;
+        }
+
+        out.put_u16(groups.len() as u16);
+        out.put_i16(weight as i16);
+        out.put_u16(if negative && !groups.is_empty() { SIGN_NEGATIVE } else { SIGN_POSITIVE });
+        out.put_u16(frac_part.len() as u16);
+        for group in groups {
+            out.put_u16(group);
+        }
+        Ok(IsNull::No)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        *ty == Type::NUMERIC
+    }
+
+    to_sql_checked!();
+}
@@ -0,0 +1,300 @@
+//! Shared connection pools for external PostgreSQL databases
+//! 
+//! Nodes talking to user databases (PGQuery, PGDynTableWriter, the postgres
+//! simpletable backend) check connections out of one pool per connection
+//! string instead of connecting on every execution. Pools are keyed by a hash
+//! of the connection string so credentials never appear in keys, logs or
+//! metrics. The number of pools is capped; pools idle for longer than the idle
+//! timeout are closed by a background sweep, and when the cap is reached the
+//! least recently used idle pool is evicted to make room.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    ops::Deref,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio_postgres::{Client, NoTls};
+
+/// Default pool limits (overridden from DatabaseConfig)
+const DEFAULT_MAX_POOLS: usize = 32;
+const DEFAULT_POOL_SIZE: usize = 8;
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// How long to wait for a free connection before failing the node
+const ACQUIRE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Connection pool for one connection string
+struct ExternalPgPool {
+    config: tokio_postgres::Config,
+    idle: Mutex<Vec<Client>>,
+    /// Bounds open connections (idle + checked out)
+    permits: Arc<Semaphore>,
+    size: usize,
+    last_used: Mutex<Instant>,
+    connections_opened: AtomicU64,
+    acquired_total: AtomicU64,
+}
+
+impl ExternalPgPool {
+    fn in_use(&self) -> usize {
+        self.size - self.permits.available_permits()
+    }
+}
+
+/// Connection checked out of a pool; returned to it on drop
+pub struct PgConnection {
+    client: Option<Client>,
+    pool: Arc<ExternalPgPool>,
+    permit: Option<OwnedSemaphorePermit>,
+    /// BEGIN issued without COMMIT/ROLLBACK yet
+    in_transaction: bool,
+}
+
+impl PgConnection {
+    pub async fn begin(&mut self) -> Result<()> {
+        self.batch_execute("BEGIN").await?;
+        self.in_transaction = true;
+        Ok(())
+    }
+
+    pub async fn commit(&mut self) -> Result<()> {
+        self.batch_execute("COMMIT").await?;
+        self.in_transaction = false;
+        Ok(())
+    }
+
+    pub async fn rollback(&mut self) -> Result<()> {
+        self.batch_execute("ROLLBACK").await?;
+        self.in_transaction = false;
+        Ok(())
+    }
+}
+
+impl Deref for PgConnection {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        self.client.as_ref().expect("client is present until drop")
+    }
+}
+
+impl Drop for PgConnection {
+    fn drop(&mut self) {
+        *self.pool.last_used.lock().unwrap() = Instant::now();
+        let Some(client) = self.client.take() else { return };
+        // Broken connections are discarded; the permit frees a slot for a new one
+        if client.is_closed() {
+            return;
+        }
+        if !self.in_transaction {
+            self.pool.idle.lock().unwrap().push(client);
+            return;
+        }
+
+        // Abandoned transaction: roll back before the connection is reused
+        let (pool, permit) = (Arc::clone(&self.pool), self.permit.take());
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(async move {
+                if client.batch_execute("ROLLBACK").await.is_ok() {
+                    pool.idle.lock().unwrap().push(client);
+                }
+                drop(permit);
+            });
+        }
+    }
+}
+
+/// Pool statistics for the metrics endpoint
+#[derive(Debug, Clone, Serialize)]
+pub struct PgPoolStats {
+    /// Hash of the connection string
+    pub key: String,
+    pub size: usize,
+    pub in_use: usize,
+    pub idle: usize,
+    pub connections_opened: u64,
+    pub acquired_total: u64,
+    pub idle_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PgPoolManagerStats {
+    pub pools: usize,
+    pub max_pools: usize,
+    pub evicted_total: u64,
+    pub by_pool: Vec<PgPoolStats>,
+}
+
+/// Pools for every external PostgreSQL connection string in use
+pub struct ExternalPgPoolManager {
+    pools: Mutex<HashMap<String, Arc<ExternalPgPool>>>,
+    max_pools: usize,
+    pool_size: usize,
+    idle_timeout: Duration,
+    evicted_total: AtomicU64,
+}
+
+impl std::fmt::Debug for ExternalPgPoolManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExternalPgPoolManager")
+            .field("pools", &self.pools.lock().unwrap().len())
+            .field("max_pools", &self.max_pools)
+            .field("pool_size", &self.pool_size)
+            .finish()
+    }
+}
+
+impl Default for ExternalPgPoolManager {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_POOLS, DEFAULT_POOL_SIZE, DEFAULT_IDLE_TIMEOUT)
+    }
+}
+
+impl ExternalPgPoolManager {
+    pub fn new(max_pools: usize, pool_size: usize, idle_timeout: Duration) -> Self {
+        Self {
+            pools: Mutex::new(HashMap::new()),
+            max_pools: max_pools.max(1),
+            pool_size: pool_size.max(1),
+            idle_timeout,
+            evicted_total: AtomicU64::new(0),
+        }
+    }
+
+    /// Check out a connection for `connection_string`
+    /// 
+    /// `search_path` is applied as a connection option, so it gets its own pool.
+    pub async fn acquire(&self, connection_string: &str, search_path: Option<&str>) -> Result<PgConnection> {
+        let pool = self.pool_for(connection_string, search_path)?;
+
+        let permit = tokio::time::timeout(ACQUIRE_TIMEOUT, Arc::clone(&pool.permits).acquire_owned()).await
+            .map_err(|_| anyhow::anyhow!("Timed out waiting for a PostgreSQL connection (pool size {})", pool.size))??;
+        pool.acquired_total.fetch_add(1, Ordering::Relaxed);
+        *pool.last_used.lock().unwrap() = Instant::now();
+
+        let idle = pool.idle.lock().unwrap().pop();
+        let client = match idle.filter(|client| !client.is_closed()) {
+            Some(client) => client,
+            None => {
+                let (client, connection) = pool.config.connect(NoTls).await
+                    .map_err(|e| anyhow::anyhow!("Failed to connect to PostgreSQL: {}", e))?;
+                tokio::spawn(async move {
+                    if let Err(e) = connection.await {
+                        tracing::warn!("⚠️ External PostgreSQL connection closed: {}", e);
+                    }
+                });
+                pool.connections_opened.fetch_add(1, Ordering::Relaxed);
+                client
+            }
+        };
+
+        Ok(PgConnection { client: Some(client), pool, permit: Some(permit), in_transaction: false })
+    }
+
+    fn pool_for(&self, connection_string: &str, search_path: Option<&str>) -> Result<Arc<ExternalPgPool>> {
+        let key = pool_key(connection_string, search_path);
+        let mut pools = self.pools.lock().unwrap();
+        if let Some(pool) = pools.get(&key) {
+            return Ok(Arc::clone(pool));
+        }
+
+        if pools.len() >= self.max_pools {
+            // Make room by closing the least recently used pool with nothing checked out
+            let victim = pools.iter()
+                .filter(|(_, pool)| pool.in_use() == 0)
+                .min_by_key(|(_, pool)| *pool.last_used.lock().unwrap())
+                .map(|(key, _)| key.clone())
+                .ok_or_else(|| anyhow::anyhow!("All {} external PostgreSQL pools are busy", self.max_pools))?;
+            pools.remove(&victim);
+            self.evicted_total.fetch_add(1, Ordering::Relaxed);
+            tracing::info!("🐘 Evicted external PostgreSQL pool {} (pool limit reached)", victim);
+        }
+
+        let mut config = tokio_postgres::Config::from_str(connection_string)
+            .map_err(|e| anyhow::anyhow!("Invalid PostgreSQL connection string: {}", e))?;
+        if let Some(search_path) = search_path {
+            config.options(format!("-c search_path={}", search_path));
+        }
+
+        let pool = Arc::new(ExternalPgPool {
+            config,
+            idle: Mutex::new(Vec::new()),
+            permits: Arc::new(Semaphore::new(self.pool_size)),
+            size: self.pool_size,
+            last_used: Mutex::new(Instant::now()),
+            connections_opened: AtomicU64::new(0),
+            acquired_total: AtomicU64::new(0),
+        });
+        pools.insert(key.clone(), Arc::clone(&pool));
+        tracing::info!("🐘 Created external PostgreSQL pool {} (size {})", key, self.pool_size);
+        Ok(pool)
+    }
+
+    /// Close pools idle for longer than the idle timeout
+    pub fn evict_idle(&self) -> usize {
+        let mut pools = self.pools.lock().unwrap();
+        let before = pools.len();
+        pools.retain(|key, pool| {
+            let keep = pool.in_use() > 0 || pool.last_used.lock().unwrap().elapsed() < self.idle_timeout;
+            if !keep {
+                tracing::info!("🐘 Closed idle external PostgreSQL pool {}", key);
+            }
+            keep
+        });
+        let evicted = before - pools.len();
+        self.evicted_total.fetch_add(evicted as u64, Ordering::Relaxed);
+        evicted
+    }
+
+    /// Sweep idle pools in the background
+    pub fn start_eviction(self: &Arc<Self>) {
+        let manager = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval((manager.idle_timeout / 4).max(Duration::from_secs(1)));
+            loop {
+                ticker.tick().await;
+                manager.evict_idle();
+            }
+        });
+    }
+
+    pub fn stats(&self) -> PgPoolManagerStats {
+        let pools = self.pools.lock().unwrap();
+        let mut by_pool: Vec<PgPoolStats> = pools.iter()
+            .map(|(key, pool)| PgPoolStats {
+                key: key.clone(),
+                size: pool.size,
+                in_use: pool.in_use(),
+                idle: pool.idle.lock().unwrap().len(),
+                connections_opened: pool.connections_opened.load(Ordering::Relaxed),
+                acquired_total: pool.acquired_total.load(Ordering::Relaxed),
+                idle_secs: pool.last_used.lock().unwrap().elapsed().as_secs(),
+            })
+            .collect();
+        by_pool.sort_by(|a, b| a.key.cmp(&b.key));
+
+        PgPoolManagerStats {
+            pools: pools.len(),
+            max_pools: self.max_pools,
+            evicted_total: self.evicted_total.load(Ordering::Relaxed),
+            by_pool,
+        }
+    }
+}
+
+/// Stable, non-reversible pool key
+fn pool_key(connection_string: &str, search_path: Option<&str>) -> String {
+    let mut hasher = DefaultHasher::new();
+    connection_string.hash(&mut hasher);
+    search_path.hash(&mut hasher);
+    format!("pg-{:016x}", hasher.finish())
+}
@@ -0,0 +1,155 @@
+//! Project secret vault behind `$secret.` pins
+//!
+//! Backed by the project_secrets table in {slug}/project.db. Values are sealed
+//! with the instance master key before they are stored and are never returned
+//! by the API. Nodes resolve `$secret.name` pins synchronously (also inside
+//! request signing and callbacks), so the sealed values of every opened project
+//! are kept in memory: loaded when the project database is opened (all projects
+//! at startup) and written through by `secret_put` / `secret_delete`. A value is
+//! only decrypted when a pin asks for it.
+
+use crate::project::ProjectDatabaseManager;
+use anyhow::Result;
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use std::collections::HashMap;
+
+/// Secret failure with a distinct API status
+#[derive(Debug)]
+pub enum SecretError {
+    /// No secret with this key in the project
+    NotFound { project: String, key: String },
+    /// Bad input: invalid key or empty value
+    Invalid(String),
+}
+
+impl std::fmt::Display for SecretError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound { project, key } => write!(f, "Secret '{}' not found in project '{}'", key, project),
+            Self::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for SecretError {}
+
+/// Secret as listed by the API (never its value)
+#[derive(Debug, Clone, Serialize)]
+pub struct SecretInfo {
+    pub key: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl ProjectDatabaseManager {
+    /// Create or replace a secret
+    pub async fn secret_put(&self, project_slug: &str, key: &str, value: &str) -> Result<()> {
+        let master_key = self.master_key()?;
+        if !is_valid_secret_key(key) {
+            return Err(SecretError::Invalid(format!("Invalid secret key: {}", key)).into());
+        }
+        if value.is_empty() {
+            return Err(SecretError::Invalid("Secret value must not be empty".to_string()).into());
+        }
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let sealed = master_key.seal(value)?;
+        let now = chrono::Utc::now().to_rfc3339();
+        sqlx::query(
+            r#"
+            INSERT INTO project_secrets (id, key, encrypted_value, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(key) DO UPDATE SET
+                encrypted_value = excluded.encrypted_value,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(key)
+        .bind(&sealed)
+        .bind(&now)
+        .bind(&now)
+        .execute(&pool)
+        .await?;
+
+        self.secrets.write().unwrap()
+            .entry(project_slug.to_string())
+            .or_default()
+            .insert(key.to_string(), sealed);
+        Ok(())
+    }
+
+    /// List a project's secrets (keys and timestamps only)
+    pub async fn secret_list(&self, project_slug: &str) -> Result<Vec<SecretInfo>> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let rows = sqlx::query("SELECT key, created_at, updated_at FROM project_secrets ORDER BY key")
+            .fetch_all(&pool)
+            .await?;
+
+        Ok(rows.iter()
+            .map(|row| SecretInfo {
+                key: row.get("key"),
+                created_at: row.get("created_at"),
+                updated_at: row.get("updated_at"),
+            })
+            .collect())
+    }
+
+    /// Delete a secret, returning whether it existed
+    pub async fn secret_delete(&self, project_slug: &str, key: &str) -> Result<bool> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let result = sqlx::query("DELETE FROM project_secrets WHERE key = ?")
+            .bind(key)
+            .execute(&pool)
+            .await?;
+
+        if let Some(secrets) = self.secrets.write().unwrap().get_mut(project_slug) {
+            secrets.remove(key);
+        }
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Decrypt a secret of a project (the key of a `$secret.key` pin)
+    pub fn resolve_secret(&self, project_slug: &str, key: &str) -> Result<String> {
+        let sealed = self.secrets.read().unwrap()
+            .get(project_slug)
+            .and_then(|secrets| secrets.get(key))
+            .cloned()
+            .ok_or_else(|| SecretError::NotFound { project: project_slug.to_string(), key: key.to_string() })?;
+        self.master_key()?.open(&sealed)
+            .map_err(|e| anyhow::anyhow!("Secret '{}' of project '{}' could not be decrypted: {}", key, project_slug, e))
+    }
+
+    /// Open the database of every known project, caching its secrets; returns the number of secrets
+    pub async fn load_secrets(&self) -> usize {
+        for project in self.known_projects().await {
+            if let Err(e) = self.get_project_pool(&project).await {
+                tracing::warn!("⚠️ Failed to load secrets of project {}: {}", project, e);
+            }
+        }
+        self.secrets.read().unwrap().values().map(HashMap::len).sum()
+    }
+
+    /// Cache the sealed secrets of a freshly opened project database
+    pub(super) async fn cache_project_secrets(&self, project_slug: &str, pool: &SqlitePool) -> Result<()> {
+        let rows = sqlx::query("SELECT key, encrypted_value FROM project_secrets")
+            .fetch_all(pool)
+            .await?;
+
+        let secrets = rows.iter()
+            .map(|row| (row.get("key"), row.get("encrypted_value")))
+            .collect();
+        self.secrets.write().unwrap().insert(project_slug.to_string(), secrets);
+        Ok(())
+    }
+}
+
+/// Secret keys as written in pins: `$secret.stripe_key`
+fn is_valid_secret_key(key: &str) -> bool {
+    !key.is_empty()
+        && key.len() <= 128
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
@@ -0,0 +1,408 @@
+//! PostgreSQL storage backend for SimpleTable nodes
+//! 
+//! A project can move its simpletable storage from {slug}/simpletable.db into
+//! a PostgreSQL schema through the project settings:
+//! 
+//! { "simpletable": { "backend": "postgres",
+//!                    "connection_string": "$secret.simpletable_dsn",
+//!                    "schema": "mway_shop" } }
+//! 
+//! The connection string is either a `$secret.<name>` reference resolved from
+//! the project's vault when the backend opens, or a plaintext DSN. The API never
+//! returns a plaintext DSN: it answers `"[redacted]"`, and a redacted value sent
+//! back keeps the stored one.
+//! 
+//! SimpleTableWriter/Reader/Query nodes keep the same workflow JSON: tables are
+//! created with the same layout (id + TEXT columns), `?` placeholders are
+//! rewritten to `$n`, and the connection's search_path points at the schema so
+//! unqualified table names resolve there. Unlike SQLite, PostgreSQL does not
+//! compare TEXT columns with numbers implicitly: filters such as `score > 50`
+//! need `CAST(score AS NUMERIC) > 50`.
+
+use crate::project::{pg_numeric::PgNumeric, pg_pool::{ExternalPgPoolManager, PgConnection}, ProjectDatabaseManager};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::sync::Arc;
+use tokio_postgres::{types::{ToSql, Type}, Client, Row};
+
+/// Per-project simpletable storage (project settings key "simpletable")
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SimpleTableSettings {
+    #[serde(default)]
+    pub backend: SimpleTableBackendKind,
+    /// PostgreSQL connection string or `$secret.<name>` reference (backend "postgres")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection_string: Option<String>,
+    /// Schema holding the tables (default: "mway_{slug}")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SimpleTableBackendKind {
+    #[default]
+    Sqlite,
+    Postgres,
+}
+
+/// Placeholder returned by the API instead of a plaintext connection string
+pub const REDACTED_CONNECTION_STRING: &str = "[redacted]";
+
+impl SimpleTableSettings {
+    /// Read the simpletable section of a project's settings (SQLite if absent or invalid)
+    pub fn from_project_settings(settings: &Value) -> Self {
+        settings.get("simpletable")
+            .and_then(|simpletable| serde_json::from_value(simpletable.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Check that a postgres backend can be connected to
+    pub fn validate(&self) -> Result<()> {
+        if self.backend == SimpleTableBackendKind::Postgres
+            && self.connection_string.as_deref().is_none_or(|c| c.trim().is_empty())
+        {
+            return Err(anyhow::anyhow!("The postgres simpletable backend needs a connection_string"));
+        }
+        if self.connection_string.as_deref() == Some(REDACTED_CONNECTION_STRING) {
+            return Err(anyhow::anyhow!("No connection_string is stored to keep; send a DSN or a $secret. reference"));
+        }
+        if let Some(schema) = &self.schema {
+            if !is_identifier(schema) {
+                return Err(anyhow::anyhow!("Invalid simpletable schema name: {}", schema));
+            }
+        }
+        Ok(())
+    }
+
+    /// Settings as returned by the API (plaintext connection strings hidden)
+    pub fn redacted(&self) -> Self {
+        let mut redacted = self.clone();
+        if let Some(connection_string) = &mut redacted.connection_string {
+            redact_connection_string(connection_string);
+        }
+        redacted
+    }
+
+    /// Keep the stored connection string when a client sends back the redacted one
+    pub fn restore_redacted(&mut self, stored: &Self) {
+        if self.connection_string.as_deref() == Some(REDACTED_CONNECTION_STRING) {
+            self.connection_string.clone_from(&stored.connection_string);
+        }
+    }
+
+    /// Hide the simpletable connection string inside full project settings
+    pub fn redact_project_settings(settings: &mut Value) {
+        if let Some(Value::String(connection_string)) = settings.pointer_mut("/simpletable/connection_string") {
+            redact_connection_string(connection_string);
+        }
+    }
+
+    /// Put the stored connection string back into project settings sent with the redacted one
+    pub fn restore_project_settings(settings: &mut Value, stored: &Value) {
+        let Some(connection_string) = settings.pointer_mut("/simpletable/connection_string") else {
+            return;
+        };
+        if connection_string.as_str() == Some(REDACTED_CONNECTION_STRING) {
+            *connection_string = stored.pointer("/simpletable/connection_string").cloned().unwrap_or(Value::Null);
+        }
+    }
+
+    fn schema_for(&self, project_slug: &str) -> String {
+        self.schema.clone().unwrap_or_else(|| format!("mway_{}", project_slug.replace('-', "_")))
+    }
+}
+
+/// Storage selected for a project's SimpleTable nodes
+#[derive(Clone)]
+pub enum SimpleTableBackend {
+    Sqlite,
+    Postgres(Arc<PgSimpleTable>),
+}
+
+impl std::fmt::Debug for SimpleTableBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sqlite => write!(f, "Sqlite"),
+            Self::Postgres(pg) => write!(f, "Postgres({})", pg.schema),
+        }
+    }
+}
+
+/// A project's simpletable schema, reached through the shared external PG pools
+/// 
+/// Statements run on a caller-provided client: a fresh pooled connection, or
+/// the connection of the execution's open transaction.
+pub struct PgSimpleTable {
+    pools: Arc<ExternalPgPoolManager>,
+    connection_string: String,
+    schema: String,
+}
+
+impl PgSimpleTable {
+    /// Create the schema if needed; connections use it as their search_path
+    pub async fn open(pools: Arc<ExternalPgPoolManager>, connection_string: &str, schema: &str) -> Result<Self> {
+        let table = Self { pools, connection_string: connection_string.to_string(), schema: schema.to_string() };
+        table.connection().await?
+            .batch_execute(&format!("CREATE SCHEMA IF NOT EXISTS {}", schema)).await
+            .map_err(db_error)?;
+        tracing::info!("🐘 Simpletable postgres backend ready (schema {})", schema);
+        Ok(table)
+    }
+
+    /// Check out a connection whose search_path is the schema
+    pub async fn connection(&self) -> Result<PgConnection> {
+        self.pools.acquire(&self.connection_string, Some(&self.schema)).await
+    }
+
+    pub fn schema(&self) -> &str {
+        &self.schema
+    }
+
+    /// Create a table with the SimpleTable layout (id + TEXT columns)
+    pub async fn ensure_table(&self, client: &Client, table_name: &str, columns: &[String]) -> Result<()> {
+        if !is_identifier(table_name) {
+            return Err(anyhow::anyhow!("Invalid table name: {}", table_name));
+        }
+        let column_defs = columns.iter()
+            .map(|col| {
+                if !is_identifier(col) {
+                    return Err(anyhow::anyhow!("Invalid column name: {}", col));
+                }
+                Ok(format!("{} TEXT", col))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        client.execute(
+            &format!("CREATE TABLE IF NOT EXISTS {} (id BIGSERIAL PRIMARY KEY, {})", table_name, column_defs.join(", ")),
+            &[],
+        ).await.map_err(db_error)?;
+        Ok(())
+    }
+
+    /// Add TEXT columns missing from an existing table
+    pub async fn add_columns(&self, client: &Client, table_name: &str, columns: &[String]) -> Result<()> {
+        for column in columns {
+            if !is_identifier(column) {
+                return Err(anyhow::anyhow!("Invalid column name: {}", column));
+            }
+            client.batch_execute(&format!("ALTER TABLE {} ADD COLUMN IF NOT EXISTS {} TEXT", table_name, column))
+                .await.map_err(db_error)?;
+        }
+        Ok(())
+    }
+
+    /// Insert one row, returning the new id
+    pub async fn insert(&self, client: &Client, table_name: &str, columns: &[String], values: &[Value]) -> Result<i64> {
+        let placeholders: Vec<String> = (1..=columns.len()).map(|i| format!("${}", i)).collect();
+        let query = format!(
+            "INSERT INTO {} ({}) VALUES ({}) RETURNING id",
+            table_name, columns.join(", "), placeholders.join(", ")
+        );
+
+        // Columns are TEXT, like the SQLite layout
+        let texts: Vec<Option<String>> = values.iter().map(value_as_text).collect();
+        let params: Vec<&(dyn ToSql + Sync)> = texts.iter().map(|t| t as &(dyn ToSql + Sync)).collect();
+        let row = client.query_one(&query, &params).await.map_err(db_error)?;
+        Ok(row.get(0))
+    }
+
+    /// Run a statement written for SQLite (`?` placeholders) and return the rows as JSON
+    pub async fn query(&self, client: &Client, query: &str, binds: &[Value]) -> Result<Vec<Value>> {
+        let statement = client.prepare(&rewrite_placeholders(query)).await.map_err(db_error)?;
+        let params = binds.iter()
+            .zip(statement.params())
+            .map(|(value, ty)| json_to_param(value, ty))
+            .collect::<Result<Vec<_>>>()?;
+        if params.len() != statement.params().len() {
+            return Err(anyhow::anyhow!("Query expects {} bind parameters, got {}", statement.params().len(), params.len()));
+        }
+        let refs: Vec<&(dyn ToSql + Sync)> = params.iter().map(|p| p.as_ref() as &(dyn ToSql + Sync)).collect();
+
+        let rows = client.query(&statement, &refs).await.map_err(db_error)?;
+        Ok(rows.iter().map(row_to_json).collect())
+    }
+}
+
+impl ProjectDatabaseManager {
+    /// Storage backend for a project's SimpleTable nodes (cached until settings change)
+    pub async fn simpletable_backend(&self, project_slug: &str) -> Result<SimpleTableBackend> {
+        if let Some(backend) = self.simpletable_backends.read().await.get(project_slug) {
+            return Ok(backend.clone());
+        }
+
+        let settings = SimpleTableSettings::from_project_settings(&self.get_project_settings(project_slug).await?);
+        let backend = match settings.backend {
+            SimpleTableBackendKind::Sqlite => SimpleTableBackend::Sqlite,
+            SimpleTableBackendKind::Postgres => {
+                let connection_string = settings.connection_string.as_deref()
+                    .ok_or_else(|| anyhow::anyhow!("Project '{}' simpletable backend has no connection_string", project_slug))?;
+                let connection_string = match connection_string.strip_prefix("$secret.") {
+                    Some(key) => self.resolve_secret(project_slug, key)?,
+                    None => connection_string.to_string(),
+                };
+                let schema = settings.schema_for(project_slug);
+                SimpleTableBackend::Postgres(Arc::new(PgSimpleTable::open(Arc::clone(self.pg_pools()), &connection_string, &schema).await?))
+            }
+        };
+
+        self.simpletable_backends.write().await.insert(project_slug.to_string(), backend.clone());
+        Ok(backend)
+    }
+
+    /// Forget the cached backend so the next node reconnects with new settings
+    pub async fn invalidate_simpletable_backend(&self, project_slug: &str) {
+        self.simpletable_backends.write().await.remove(project_slug);
+    }
+}
+
+/// Surface the server's message ("db error" alone is not actionable)
+pub(crate) fn db_error(e: tokio_postgres::Error) -> anyhow::Error {
+    match e.as_db_error() {
+        Some(db) => anyhow::anyhow!("{}: {}", db.code().code(), db.message()),
+        None => e.into(),
+    }
+}
+
+/// Replace a plaintext connection string with the placeholder (`$secret.` references are kept)
+fn redact_connection_string(connection_string: &mut String) {
+    if !connection_string.starts_with("$secret.") {
+        *connection_string = REDACTED_CONNECTION_STRING.to_string();
+    }
+}
+
+/// Identifier safe to splice into SQL unquoted
+pub(crate) fn is_identifier(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Store a JSON value in a TEXT column
+fn value_as_text(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+/// Rewrite SQLite `?` placeholders to `$1, $2, ...` (outside quotes)
+fn rewrite_placeholders(query: &str) -> String {
+    let mut rewritten = String::with_capacity(query.len() + 8);
+    let mut quote: Option<char> = None;
+    let mut index = 0;
+    for c in query.chars() {
+        match (quote, c) {
+            (None, '\'' | '"') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, '?') => {
+                index += 1;
+                rewritten.push_str(&format!("${}", index));
+                continue;
+            }
+            _ => {}
+        }
+        rewritten.push(c);
+    }
+    rewritten
+}
+
+/// Convert a bind value to the parameter type PostgreSQL inferred
+pub(crate) fn json_to_param(value: &Value, ty: &Type) -> Result<Box<dyn ToSql + Sync + Send>> {
+    let mismatch = || anyhow::anyhow!("Cannot bind {} as {}", value, ty);
+    if value.is_null() {
+        return Ok(match *ty {
+            Type::INT2 => Box::new(None::<i16>),
+            Type::INT4 => Box::new(None::<i32>),
+            Type::INT8 => Box::new(None::<i64>),
+            Type::FLOAT4 => Box::new(None::<f32>),
+            Type::FLOAT8 => Box::new(None::<f64>),
+            Type::BOOL => Box::new(None::<bool>),
+            Type::JSON | Type::JSONB => Box::new(None::<Value>),
+            Type::NUMERIC => Box::new(None::<PgNumeric>),
+            Type::TIMESTAMPTZ => Box::new(None::<chrono::DateTime<chrono::Utc>>),
+            Type::TIMESTAMP => Box::new(None::<chrono::NaiveDateTime>),
+            Type::DATE => Box::new(None::<chrono::NaiveDate>),
+            Type::TIME => Box::new(None::<chrono::NaiveTime>),
+            Type::UUID => Box::new(None::<uuid::Uuid>),
+            _ => Box::new(None::<String>),
+        });
+    }
+    // Numbers arriving as strings (e.g. query parameters) are accepted for numeric columns
+    let as_i64 = || value.as_i64().or_else(|| value.as_str().and_then(|s| s.parse().ok()));
+    let as_f64 = || value.as_f64().or_else(|| value.as_str().and_then(|s| s.parse().ok()));
+    Ok(match *ty {
+        Type::INT2 => Box::new(i16::try_from(as_i64().ok_or_else(mismatch)?)?),
+        Type::INT4 => Box::new(i32::try_from(as_i64().ok_or_else(mismatch)?)?),
+        Type::INT8 => Box::new(as_i64().ok_or_else(mismatch)?),
+        Type::FLOAT4 => Box::new(as_f64().ok_or_else(mismatch)? as f32),
+        Type::FLOAT8 => Box::new(as_f64().ok_or_else(mismatch)?),
+        Type::BOOL => Box::new(value.as_bool().ok_or_else(mismatch)?),
+        Type::JSON | Type::JSONB => Box::new(value.clone()),
+        Type::NUMERIC => Box::new(PgNumeric::from_json(value).ok_or_else(mismatch)?),
+        // Date/time values arrive as strings: RFC 3339 timestamps, "2024-05-01", "13:45:00"
+        Type::TIMESTAMPTZ => Box::new(chrono::DateTime::parse_from_rfc3339(value.as_str().ok_or_else(mismatch)?)
+            .map_err(|_| mismatch())?
+            .with_timezone(&chrono::Utc)),
+        Type::TIMESTAMP => Box::new(parse_naive_timestamp(value.as_str().ok_or_else(mismatch)?).ok_or_else(mismatch)?),
+        Type::DATE => Box::new(chrono::NaiveDate::parse_from_str(value.as_str().ok_or_else(mismatch)?, "%Y-%m-%d")
+            .map_err(|_| mismatch())?),
+        Type::TIME => Box::new(chrono::NaiveTime::parse_from_str(value.as_str().ok_or_else(mismatch)?, "%H:%M:%S%.f")
+            .map_err(|_| mismatch())?),
+        Type::UUID => Box::new(uuid::Uuid::parse_str(value.as_str().ok_or_else(mismatch)?).map_err(|_| mismatch())?),
+        _ => Box::new(value_as_text(value)),
+    })
+}
+
+/// TIMESTAMP bind value: "2024-05-01 13:45:00", "2024-05-01T13:45:00" or RFC 3339 (converted to UTC)
+fn parse_naive_timestamp(text: &str) -> Option<chrono::NaiveDateTime> {
+    chrono::NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f")
+        .or_else(|_| chrono::NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f"))
+        .ok()
+        .or_else(|| chrono::DateTime::parse_from_rfc3339(text).ok().map(|t| t.naive_utc()))
+}
+
+/// Convert a result row to a JSON object
+/// 
+/// TEXT values go through the same number/bool detection as the SQLite backend,
+/// so both backends return identical JSON for SimpleTable data. NUMERIC values
+/// become numbers when that loses nothing, else decimal strings.
+pub(crate) fn row_to_json(row: &Row) -> Value {
+    let mut record = Map::new();
+    for (i, column) in row.columns().iter().enumerate() {
+        let value = match *column.type_() {
+            Type::INT2 => row.try_get::<_, Option<i16>>(i).ok().flatten().map(|v| json!(v)),
+            Type::INT4 => row.try_get::<_, Option<i32>>(i).ok().flatten().map(|v| json!(v)),
+            Type::INT8 => row.try_get::<_, Option<i64>>(i).ok().flatten().map(|v| json!(v)),
+            Type::FLOAT4 => row.try_get::<_, Option<f32>>(i).ok().flatten().map(|v| json!(v)),
+            Type::FLOAT8 => row.try_get::<_, Option<f64>>(i).ok().flatten().map(|v| json!(v)),
+            Type::BOOL => row.try_get::<_, Option<bool>>(i).ok().flatten().map(|v| json!(v)),
+            Type::JSON | Type::JSONB => row.try_get::<_, Option<Value>>(i).ok().flatten(),
+            Type::TIMESTAMPTZ => row.try_get::<_, Option<chrono::DateTime<chrono::Utc>>>(i).ok().flatten()
+                .map(|v| json!(v.to_rfc3339())),
+            Type::TIMESTAMP => row.try_get::<_, Option<chrono::NaiveDateTime>>(i).ok().flatten()
+                .map(|v| json!(v.to_string())),
+            Type::DATE => row.try_get::<_, Option<chrono::NaiveDate>>(i).ok().flatten()
+                .map(|v| json!(v.to_string())),
+            Type::TIME => row.try_get::<_, Option<chrono::NaiveTime>>(i).ok().flatten()
+                .map(|v| json!(v.to_string())),
+            Type::NUMERIC => row.try_get::<_, Option<PgNumeric>>(i).ok().flatten().map(|v| v.to_json()),
+            Type::UUID => row.try_get::<_, Option<uuid::Uuid>>(i).ok().flatten().map(|v| json!(v.to_string())),
+            _ => row.try_get::<_, Option<String>>(i).ok().flatten().map(|v| text_to_json(&v)),
+        };
+        record.insert(column.name().to_string(), value.unwrap_or(Value::Null));
+    }
+    Value::Object(record)
+}
+
+fn text_to_json(v: &str) -> Value {
+    if let Ok(num) = v.parse::<i64>() {
+        json!(num)
+    } else if let Ok(num) = v.parse::<f64>() {
+        json!(num)
+    } else if v == "true" || v == "false" {
+        json!(v == "true")
+    } else {
+        json!(v)
+    }
+}
@@ -0,0 +1,116 @@
+//! Per-node data snapshots for change detection
+//! 
+//! Backed by the diff_snapshots table in {slug}/project.db. A Diff node keeps
+//! one row per item key with the hash of the item as last seen, so the next run
+//! can tell added, changed and removed items apart without re-reading the source.
+
+use crate::project::ProjectDatabaseManager;
+use anyhow::Result;
+use serde_json::Value;
+use sqlx::Row;
+use std::collections::HashMap;
+
+/// Keys per IN (...) lookup (SQLite limits bound parameters per statement)
+const LOOKUP_CHUNK: usize = 500;
+
+/// Item of a snapshot update
+pub struct SnapshotEntry<'a> {
+    pub key: &'a str,
+    pub hash: &'a str,
+    pub item: &'a Value,
+}
+
+impl ProjectDatabaseManager {
+    /// Item hashes of a node's snapshot, keyed by item key
+    pub async fn snapshot_hashes(&self, project_slug: &str, workflow_id: &str, node_id: &str) -> Result<HashMap<String, String>> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let rows = sqlx::query("SELECT item_key, hash FROM diff_snapshots WHERE workflow_id = ? AND node_id = ?")
+            .bind(workflow_id)
+            .bind(node_id)
+            .fetch_all(&pool)
+            .await?;
+
+        Ok(rows.iter().map(|row| (row.get("item_key"), row.get("hash"))).collect())
+    }
+
+    /// Stored items of a node's snapshot for the given keys
+    pub async fn snapshot_items(&self, project_slug: &str, workflow_id: &str, node_id: &str, keys: &[&str]) -> Result<HashMap<String, Value>> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let mut items = HashMap::with_capacity(keys.len());
+        for chunk in keys.chunks(LOOKUP_CHUNK) {
+            let placeholders = vec!["?"; chunk.len()].join(", ");
+            let sql = format!(
+                "SELECT item_key, item FROM diff_snapshots WHERE workflow_id = ? AND node_id = ? AND item_key IN ({})",
+                placeholders
+            );
+            let mut query = sqlx::query(&sql).bind(workflow_id).bind(node_id);
+            for key in chunk {
+                query = query.bind(*key);
+            }
+            for row in query.fetch_all(&pool).await? {
+                let item_json: String = row.get("item");
+                items.insert(row.get("item_key"), serde_json::from_str(&item_json)?);
+            }
+        }
+        Ok(items)
+    }
+
+    /// Upsert changed items and delete removed keys in one transaction
+    pub async fn snapshot_apply(
+        &self,
+        project_slug: &str,
+        workflow_id: &str,
+        node_id: &str,
+        upserts: &[SnapshotEntry<'_>],
+        removed: &[&str],
+    ) -> Result<()> {
+        let pool = self.get_project_pool(project_slug).await?;
+        let mut tx = pool.begin().await?;
+
+        for entry in upserts {
+            sqlx::query(
+                r#"
+                INSERT INTO diff_snapshots (workflow_id, node_id, item_key, hash, item, updated_at)
+                VALUES (?, ?, ?, ?, ?, CURRENT_TIMESTAMP)
+                ON CONFLICT(workflow_id, node_id, item_key) DO UPDATE SET
+                    hash = excluded.hash,
+                    item = excluded.item,
+                    updated_at = CURRENT_TIMESTAMP
+                "#,
+            )
+            .bind(workflow_id)
+            .bind(node_id)
+            .bind(entry.key)
+            .bind(entry.hash)
+            .bind(serde_json::to_string(entry.item)?)
+            .execute(&mut *tx)
+            .await?;
+        }
+        for key in removed {
+            sqlx::query("DELETE FROM diff_snapshots WHERE workflow_id = ? AND node_id = ? AND item_key = ?")
+                .bind(workflow_id)
+                .bind(node_id)
+                .bind(*key)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Forget a node's snapshot (its next run reports every item as added), returning the rows removed
+    pub async fn snapshot_clear(&self, project_slug: &str, workflow_id: &str, node_id: &str) -> Result<u64> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let result = sqlx::query("DELETE FROM diff_snapshots WHERE workflow_id = ? AND node_id = ?")
+            .bind(workflow_id)
+            .bind(node_id)
+            .execute(&pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
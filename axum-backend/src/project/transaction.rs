@@ -0,0 +1,157 @@
+//! Execution-scoped database transactions for SimpleTable nodes
+//! 
+//! A TransactionBegin node opens a transaction on the project's simpletable
+//! storage (SQLite or the postgres schema). It lives in the execution context,
+//! so every later SimpleTableWriter/Reader/Query node of the same run uses it
+//! until TransactionCommit or TransactionRollback. Whatever is still open when
+//! the run ends (failure, early stop, missing commit) is rolled back by the
+//! engine. Sub-workflows start with their own, empty slot.
+//! 
+//! Branches after a fork normally run concurrently, but the slot is shared by
+//! the whole execution: a walk that contains a TransactionBegin, or starts while
+//! a transaction is open, runs its nodes one at a time in plan order. Statements
+//! of different branches never interleave on the transaction's connection, and
+//! whether a node runs inside the transaction follows from the plan alone.
+
+use crate::project::{
+    pg_pool::PgConnection,
+    simpletable_pg::{PgSimpleTable, SimpleTableBackend},
+    ProjectDatabaseManager,
+};
+use anyhow::Result;
+use sqlx::{
+    query::Query,
+    sqlite::{SqliteArguments, SqliteQueryResult, SqliteRow},
+    Sqlite, SqlitePool,
+};
+use std::{ops::Deref, sync::Arc};
+use tokio::sync::{Mutex, MutexGuard};
+use tokio_postgres::Client;
+
+/// Open transaction on one of the simpletable backends
+pub enum ActiveTransaction {
+    Sqlite(sqlx::Transaction<'static, sqlx::Sqlite>),
+    /// Pooled connection with BEGIN issued (rolled back if dropped)
+    Postgres(PgConnection),
+}
+
+/// Shared transaction slot of one execution (cloned contexts share it)
+#[derive(Clone, Default)]
+pub struct TransactionSlot(Arc<Mutex<Option<ActiveTransaction>>>);
+
+impl std::fmt::Debug for TransactionSlot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = match self.0.try_lock() {
+            Ok(guard) if guard.is_some() => "open",
+            Ok(_) => "none",
+            Err(_) => "busy",
+        };
+        write!(f, "TransactionSlot({})", state)
+    }
+}
+
+impl TransactionSlot {
+    /// Begin a transaction on the project's simpletable backend
+    pub async fn begin(&self, project_db_manager: &ProjectDatabaseManager, project_slug: &str) -> Result<()> {
+        let mut slot = self.0.lock().await;
+        if slot.is_some() {
+            return Err(anyhow::anyhow!("A transaction is already open in this execution"));
+        }
+
+        *slot = Some(match project_db_manager.simpletable_backend(project_slug).await? {
+            SimpleTableBackend::Sqlite => {
+                let pool = project_db_manager.get_simpletable_pool(project_slug).await?;
+                ActiveTransaction::Sqlite(pool.begin().await?)
+            }
+            SimpleTableBackend::Postgres(pg) => {
+                let mut conn = pg.connection().await?;
+                conn.begin().await?;
+                ActiveTransaction::Postgres(conn)
+            }
+        });
+        Ok(())
+    }
+
+    /// Whether a transaction is open (a slot busy with a statement counts as open)
+    pub fn is_open(&self) -> bool {
+        self.0.try_lock().map_or(true, |slot| slot.is_some())
+    }
+
+    /// Commit the open transaction (error if none is open)
+    pub async fn commit(&self) -> Result<()> {
+        match self.0.lock().await.take() {
+            Some(ActiveTransaction::Sqlite(tx)) => tx.commit().await?,
+            Some(ActiveTransaction::Postgres(mut conn)) => conn.commit().await?,
+            None => return Err(anyhow::anyhow!("No open transaction to commit")),
+        }
+        Ok(())
+    }
+
+    /// Roll back the open transaction; returns false if none was open
+    pub async fn rollback(&self) -> Result<bool> {
+        match self.0.lock().await.take() {
+            Some(ActiveTransaction::Sqlite(tx)) => tx.rollback().await?,
+            Some(ActiveTransaction::Postgres(mut conn)) => conn.rollback().await?,
+            None => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Run a SQLite simpletable statement on the open transaction, or on the pool
+    pub async fn sqlite_execute<'q>(
+        &self,
+        pool: &SqlitePool,
+        query: Query<'q, Sqlite, SqliteArguments<'q>>,
+    ) -> Result<SqliteQueryResult> {
+        let mut slot = self.0.lock().await;
+        Ok(match slot.as_mut() {
+            Some(ActiveTransaction::Sqlite(tx)) => query.execute(&mut **tx).await?,
+            _ => query.execute(pool).await?,
+        })
+    }
+
+    /// Fetch SQLite simpletable rows through the open transaction, or from the pool
+    pub async fn sqlite_fetch_all<'q>(
+        &self,
+        pool: &SqlitePool,
+        query: Query<'q, Sqlite, SqliteArguments<'q>>,
+    ) -> Result<Vec<SqliteRow>> {
+        let mut slot = self.0.lock().await;
+        Ok(match slot.as_mut() {
+            Some(ActiveTransaction::Sqlite(tx)) => query.fetch_all(&mut **tx).await?,
+            _ => query.fetch_all(pool).await?,
+        })
+    }
+
+    /// Client for a postgres simpletable statement: the open transaction's
+    /// connection, or a fresh pooled one
+    pub async fn pg_client(&self, pg: &PgSimpleTable) -> Result<PgClient<'_>> {
+        let slot = self.0.lock().await;
+        if matches!(slot.as_ref(), Some(ActiveTransaction::Postgres(_))) {
+            return Ok(PgClient::Transaction(slot));
+        }
+        drop(slot);
+        Ok(PgClient::Pooled(pg.connection().await?))
+    }
+}
+
+/// Postgres client handed to SimpleTable nodes (see `TransactionSlot::pg_client`)
+pub enum PgClient<'a> {
+    /// Holds the slot locked while the statement runs
+    Transaction(MutexGuard<'a, Option<ActiveTransaction>>),
+    Pooled(PgConnection),
+}
+
+impl Deref for PgClient<'_> {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        match self {
+            PgClient::Transaction(slot) => match slot.as_ref() {
+                Some(ActiveTransaction::Postgres(conn)) => conn,
+                _ => unreachable!("PgClient::Transaction is only built for an open postgres transaction"),
+            },
+            PgClient::Pooled(conn) => conn,
+        }
+    }
+}
@@ -1,6 +1,6 @@
-/// Project type definitions for multi-tenant architecture
-/// 
-/// Defines project structure with slug-based database isolation like n8n.
+//! Project type definitions for multi-tenant architecture
+//! 
+//! Defines project structure with slug-based database isolation like n8n.
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -31,6 +31,15 @@ pub struct Project {
 }
 
 impl Project {
+    /// Check that a slug is safe to use as a directory name
+    /// 
+    /// SECURITY: Slugs become filesystem paths, so only [a-z0-9_-] are allowed
+    pub fn is_valid_slug(slug: &str) -> bool {
+        !slug.is_empty()
+            && slug.len() <= 64
+            && slug.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+    }
+
     /// Get the project directory path
     pub fn project_dir(&self) -> String {
         self.slug.clone()
@@ -45,9 +54,11 @@ impl Project {
     pub fn simpletable_db_path(&self) -> String {
         format!("{}/simpletable.db", self.slug)
     }
-    
+}
+
+impl Default for Project {
     /// Create default project for backwards compatibility
-    pub fn default() -> Self {
+    fn default() -> Self {
         Self {
             id: "default".to_string(),
             slug: "default".to_string(),
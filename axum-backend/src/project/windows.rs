@@ -0,0 +1,149 @@
+//! Buffered items of Window nodes
+//! 
+//! Backed by the window_buffers table in {slug}/project.db. A Window node
+//! appends the items of every execution (with their arrival time) and takes
+//! them out again when a count or time window closes, so batches survive
+//! restarts and span any number of executions.
+
+use crate::project::ProjectDatabaseManager;
+use anyhow::Result;
+use serde_json::Value;
+use sqlx::Row;
+
+/// Item waiting in a window buffer
+#[derive(Debug, Clone)]
+pub struct BufferedItem {
+    /// Arrival time (Unix milliseconds)
+    pub received_at: i64,
+    pub item: Value,
+}
+
+impl ProjectDatabaseManager {
+    /// Append items to a node's buffer, dropping the oldest beyond `max_items`
+    /// 
+    /// Returns the number of dropped items
+    pub async fn window_push(&self, project_slug: &str, workflow_id: &str, node_id: &str, items: &[Value], received_at: i64, max_items: usize) -> Result<u64> {
+        let pool = self.get_project_pool(project_slug).await?;
+        let mut tx = pool.begin().await?;
+
+        for item in items {
+            sqlx::query("INSERT INTO window_buffers (workflow_id, node_id, received_at, item) VALUES (?, ?, ?, ?)")
+                .bind(workflow_id)
+                .bind(node_id)
+                .bind(received_at)
+                .bind(serde_json::to_string(item)?)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        let dropped = sqlx::query(
+            r#"
+            DELETE FROM window_buffers
+            WHERE workflow_id = ? AND node_id = ? AND seq NOT IN (
+                SELECT seq FROM window_buffers WHERE workflow_id = ? AND node_id = ? ORDER BY seq DESC LIMIT ?
+            )
+            "#,
+        )
+        .bind(workflow_id)
+        .bind(node_id)
+        .bind(workflow_id)
+        .bind(node_id)
+        .bind(max_items as i64)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(dropped.rows_affected())
+    }
+
+    /// Number of buffered items of a node
+    pub async fn window_count(&self, project_slug: &str, workflow_id: &str, node_id: &str) -> Result<u64> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let count: i64 = sqlx::query("SELECT COUNT(*) AS buffered FROM window_buffers WHERE workflow_id = ? AND node_id = ?")
+            .bind(workflow_id)
+            .bind(node_id)
+            .fetch_one(&pool)
+            .await?
+            .get("buffered");
+
+        Ok(count as u64)
+    }
+
+    /// Oldest `limit` buffered items of a node, in arrival order
+    pub async fn window_oldest(&self, project_slug: &str, workflow_id: &str, node_id: &str, limit: usize) -> Result<Vec<BufferedItem>> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let rows = sqlx::query(
+            "SELECT received_at, item FROM window_buffers WHERE workflow_id = ? AND node_id = ? ORDER BY seq LIMIT ?"
+        )
+            .bind(workflow_id)
+            .bind(node_id)
+            .bind(limit as i64)
+            .fetch_all(&pool)
+            .await?;
+
+        rows.iter().map(buffered_item).collect()
+    }
+
+    /// Buffered items of a node that arrived before `before` (Unix milliseconds), in arrival order
+    pub async fn window_received_before(&self, project_slug: &str, workflow_id: &str, node_id: &str, before: i64) -> Result<Vec<BufferedItem>> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let rows = sqlx::query(
+            "SELECT received_at, item FROM window_buffers WHERE workflow_id = ? AND node_id = ? AND received_at < ? ORDER BY seq"
+        )
+            .bind(workflow_id)
+            .bind(node_id)
+            .bind(before)
+            .fetch_all(&pool)
+            .await?;
+
+        rows.iter().map(buffered_item).collect()
+    }
+
+    /// Delete the oldest `count` buffered items of a node
+    pub async fn window_drop_oldest(&self, project_slug: &str, workflow_id: &str, node_id: &str, count: usize) -> Result<u64> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let result = sqlx::query(
+            r#"
+            DELETE FROM window_buffers
+            WHERE workflow_id = ? AND node_id = ? AND seq IN (
+                SELECT seq FROM window_buffers WHERE workflow_id = ? AND node_id = ? ORDER BY seq LIMIT ?
+            )
+            "#,
+        )
+        .bind(workflow_id)
+        .bind(node_id)
+        .bind(workflow_id)
+        .bind(node_id)
+        .bind(count as i64)
+        .execute(&pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Delete buffered items of a node that arrived before `before` (Unix milliseconds)
+    pub async fn window_drop_before(&self, project_slug: &str, workflow_id: &str, node_id: &str, before: i64) -> Result<u64> {
+        let pool = self.get_project_pool(project_slug).await?;
+
+        let result = sqlx::query("DELETE FROM window_buffers WHERE workflow_id = ? AND node_id = ? AND received_at < ?")
+            .bind(workflow_id)
+            .bind(node_id)
+            .bind(before)
+            .execute(&pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+fn buffered_item(row: &sqlx::sqlite::SqliteRow) -> Result<BufferedItem> {
+    let item_json: String = row.get("item");
+    Ok(BufferedItem {
+        received_at: row.get("received_at"),
+        item: serde_json::from_str(&item_json)?,
+    })
+}
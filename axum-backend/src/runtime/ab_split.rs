@@ -0,0 +1,119 @@
+//! ABSplit node configuration
+//! 
+//! { "experiment": "checkout_button",
+//!   "variants": [{ "name": "control", "weight": 50 }, { "name": "blue", "weight": 50 }],
+//!   "field": "variant" }
+//! with inputs ["$json.user_id"]
+//! 
+//! Every item is assigned a variant from a hash of its key (the input pin,
+//! evaluated per item) and the experiment name, so the same key always lands in
+//! the same variant as long as the variants and weights don't change. Variant
+//! names are the node's output ports: each variant's items only flow down the
+//! edges with that "port" (the engine walks the rest of the plan once per variant).
+
+use anyhow::Result;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+/// One arm of an experiment
+#[derive(Debug, Clone)]
+pub struct SplitVariant {
+    /// Variant name, also the output port its items leave from
+    pub name: String,
+    /// Relative share of the keys (0 pauses the variant)
+    pub weight: u64,
+}
+
+/// ABSplit node params
+#[derive(Debug, Clone)]
+pub struct ABSplitParams {
+    /// Hash salt and metadata key; the node ID when not set
+    pub experiment: Option<String>,
+    pub variants: Vec<SplitVariant>,
+    /// Item field set to the assigned variant (objects only), for downstream writes
+    pub field: Option<String>,
+}
+
+impl ABSplitParams {
+    /// Parse node params
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        let experiment = match params.get("experiment") {
+            None | Some(Value::Null) => None,
+            Some(Value::String(name)) if !name.is_empty() => Some(name.clone()),
+            Some(_) => return Err(anyhow::anyhow!("'experiment' must be a non-empty string")),
+        };
+        let variants = params.get("variants")
+            .and_then(|v| v.as_array())
+            .filter(|v| v.len() >= 2)
+            .ok_or_else(|| anyhow::anyhow!("missing 'variants' parameter (at least two {{ \"name\", \"weight\" }} objects)"))?
+            .iter()
+            .map(|variant| {
+                let name = variant.get("name")
+                    .and_then(|n| n.as_str())
+                    .filter(|n| !n.is_empty())
+                    .ok_or_else(|| anyhow::anyhow!("every variant needs a 'name'"))?;
+                let weight = match variant.get("weight") {
+                    None | Some(Value::Null) => 1,
+                    Some(weight) => weight.as_u64()
+                        .ok_or_else(|| anyhow::anyhow!("weight of variant '{}' must be a non-negative integer", name))?,
+                };
+                Ok(SplitVariant { name: name.to_string(), weight })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if let Some(duplicate) = variants.iter().enumerate()
+            .find(|(i, variant)| variants[..*i].iter().any(|other| other.name == variant.name))
+        {
+            return Err(anyhow::anyhow!("variant '{}' is listed twice", duplicate.1.name));
+        }
+        if variants.iter().all(|variant| variant.weight == 0) {
+            return Err(anyhow::anyhow!("at least one variant needs a positive weight"));
+        }
+        let field = match params.get("field") {
+            None | Some(Value::Null) => None,
+            Some(Value::String(field)) if !field.is_empty() => Some(field.clone()),
+            Some(_) => return Err(anyhow::anyhow!("'field' must be a non-empty string")),
+        };
+        Ok(Self { experiment, variants, field })
+    }
+
+    /// Check that exactly one input pin provides the key
+    pub fn check_inputs(&self, inputs: Option<&[String]>) -> Result<()> {
+        match inputs {
+            Some([_]) => Ok(()),
+            _ => Err(anyhow::anyhow!("ABSplit needs exactly one input pin with the item key (e.g. [\"$json.user_id\"])")),
+        }
+    }
+
+    /// Output port names (the variant names)
+    pub fn ports(&self) -> Vec<String> {
+        self.variants.iter().map(|variant| variant.name.clone()).collect()
+    }
+
+    /// Index of the variant a key is assigned to; items without a key (null) get a random one
+    pub fn assign(&self, experiment: &str, key: &Value) -> usize {
+        let total: u64 = self.variants.iter().map(|variant| variant.weight).sum();
+        let mut point = match key {
+            Value::Null => (uuid::Uuid::new_v4().as_u128() % u128::from(total)) as u64,
+            key => key_hash(experiment, key) % total,
+        };
+        for (index, variant) in self.variants.iter().enumerate() {
+            if point < variant.weight {
+                return index;
+            }
+            point -= variant.weight;
+        }
+        self.variants.len() - 1
+    }
+}
+
+/// Stable hash of a key within an experiment (each experiment splits keys independently)
+fn key_hash(experiment: &str, key: &Value) -> u64 {
+    let key = match key {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    let digest = Sha256::digest(format!("{}:{}", experiment, key).as_bytes());
+    let mut prefix = [0u8; 8];
+    prefix.copy_from_slice(&digest[..8]);
+    u64::from_be_bytes(prefix)
+}
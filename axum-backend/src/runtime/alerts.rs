@@ -0,0 +1,301 @@
+//! Failure alerting monitor
+//! 
+//! Evaluates per-project alert rules against the execution history on a fixed
+//! interval and notifies webhook, Slack or email targets when a workflow fails
+//! `threshold` times within `window_secs`. Rules live in the project settings:
+//! 
+//! { "alerting": { "rules": [{ "name": "orders-broken", "workflow_id": "wf-orders",
+//!     "threshold": 5, "window_secs": 300,
+//!     "targets": [{ "type": "slack", "webhook_url": "https://hooks.slack.com/..." }] }] } }
+//! 
+//! Failures are read from the executions table, so history recording must be
+//! enabled (MECHAWAY_RECORD_EXECUTIONS).
+
+use crate::{config::AlertsConfig, project::ProjectDatabaseManager, workflow::registry::WorkflowRegistry};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+/// Per-project alerting configuration (project settings key "alerting")
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AlertingSettings {
+    #[serde(default)]
+    pub rules: Vec<AlertRule>,
+}
+
+impl AlertingSettings {
+    /// Read the alerting section of a project's settings (empty if absent or invalid)
+    pub fn from_project_settings(settings: &Value) -> Self {
+        settings.get("alerting")
+            .and_then(|alerting| serde_json::from_value(alerting.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Check rules for values the monitor cannot evaluate
+    pub fn validate(&self) -> Result<()> {
+        for rule in &self.rules {
+            if rule.name.trim().is_empty() {
+                return Err(anyhow::anyhow!("Alert rules need a name"));
+            }
+            if rule.threshold == 0 || rule.window_secs == 0 {
+                return Err(anyhow::anyhow!("Alert rule '{}' needs a positive threshold and window_secs", rule.name));
+            }
+            if rule.targets.is_empty() {
+                return Err(anyhow::anyhow!("Alert rule '{}' has no notification targets", rule.name));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Failure threshold for one workflow (or every workflow of the project)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub name: String,
+    /// Workflow to watch; omitted = each workflow of the project separately
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workflow_id: Option<String>,
+    /// Failures within the window that trigger the alert
+    pub threshold: u64,
+    /// Sliding window length in seconds
+    pub window_secs: u64,
+    /// Minimum seconds between two alerts for the same workflow (default: window_secs)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cooldown_secs: Option<u64>,
+    pub targets: Vec<AlertTarget>,
+}
+
+/// Where an alert is delivered
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum AlertTarget {
+    /// POST the alert JSON to a URL
+    Webhook { url: String },
+    /// Slack incoming webhook ({ "text": "..." })
+    Slack { webhook_url: String },
+    /// Email through the configured SMTP relay
+    Email { to: Vec<String> },
+}
+
+/// Alert raised by a rule
+#[derive(Debug, Clone, Serialize)]
+pub struct Alert {
+    pub project: String,
+    pub rule: String,
+    pub workflow_id: String,
+    pub failures: u64,
+    pub threshold: u64,
+    pub window_secs: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    pub fired_at: String,
+    /// Targets of the rule that raised the alert
+    #[serde(skip)]
+    pub targets: Vec<AlertTarget>,
+}
+
+impl Alert {
+    fn summary(&self) -> String {
+        format!(
+            "Mechaway alert '{}': workflow '{}' in project '{}' failed {} times in the last {}s (threshold {}){}",
+            self.rule, self.workflow_id, self.project, self.failures, self.window_secs, self.threshold,
+            self.last_error.as_ref().map(|e| format!(". Last error: {}", e)).unwrap_or_default(),
+        )
+    }
+}
+
+/// Background monitor evaluating alert rules
+pub struct AlertMonitor {
+    registry: Arc<WorkflowRegistry>,
+    project_db_manager: Arc<ProjectDatabaseManager>,
+    config: AlertsConfig,
+    http: reqwest::Client,
+    /// (project, rule, workflow_id) -> last time the alert fired
+    last_fired: Mutex<HashMap<(String, String, String), Instant>>,
+}
+
+impl AlertMonitor {
+    pub fn new(
+        registry: Arc<WorkflowRegistry>,
+        project_db_manager: Arc<ProjectDatabaseManager>,
+        config: AlertsConfig,
+    ) -> Self {
+        Self {
+            registry,
+            project_db_manager,
+            config,
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+            last_fired: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Evaluate rules every `check_interval_secs` in the background
+    pub fn start(self: Arc<Self>) {
+        if self.config.check_interval_secs == 0 {
+            tracing::info!("🔕 Alert monitor disabled");
+            return;
+        }
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(Duration::from_secs(self.config.check_interval_secs));
+            loop {
+                ticker.tick().await;
+                for alert in self.check().await {
+                    self.notify(&alert).await;
+                }
+            }
+        });
+    }
+
+    /// Evaluate every project's rules once, returning the alerts that fire now
+    pub async fn check(&self) -> Vec<Alert> {
+        // Only projects with active workflows can accumulate failures
+        let projects: BTreeSet<String> = self.registry.get_all_workflows()
+            .into_iter()
+            .map(|workflow| workflow.project)
+            .collect();
+
+        let mut alerts = Vec::new();
+        for project in projects {
+            match self.check_project(&project).await {
+                Ok(mut fired) => alerts.append(&mut fired),
+                Err(e) => tracing::warn!("⚠️ Failed to evaluate alert rules for project {}: {}", project, e),
+            }
+        }
+        alerts
+    }
+
+    async fn check_project(&self, project: &str) -> Result<Vec<Alert>> {
+        let settings = self.project_db_manager.get_project_settings(project).await?;
+        let alerting = AlertingSettings::from_project_settings(&settings);
+
+        let mut alerts = Vec::new();
+        for rule in &alerting.rules {
+            let since = chrono::Utc::now() - chrono::Duration::seconds(rule.window_secs as i64);
+            let failures = self.project_db_manager.count_failures_since(project, since).await?;
+            let cooldown = Duration::from_secs(rule.cooldown_secs.unwrap_or(rule.window_secs));
+
+            for (workflow_id, (count, last_error)) in failures {
+                if rule.workflow_id.as_ref().is_some_and(|id| *id != workflow_id) || count < rule.threshold {
+                    continue;
+                }
+
+                let key = (project.to_string(), rule.name.clone(), workflow_id.clone());
+                {
+                    let mut last_fired = self.last_fired.lock().unwrap();
+                    if last_fired.get(&key).is_some_and(|fired| fired.elapsed() < cooldown) {
+                        continue;
+                    }
+                    last_fired.insert(key, Instant::now());
+                }
+
+                tracing::warn!("🚨 Alert '{}' fired: workflow '{}' failed {} times in {}s",
+                    rule.name, workflow_id, count, rule.window_secs);
+                alerts.push(Alert {
+                    project: project.to_string(),
+                    rule: rule.name.clone(),
+                    workflow_id,
+                    failures: count,
+                    threshold: rule.threshold,
+                    window_secs: rule.window_secs,
+                    last_error,
+                    fired_at: chrono::Utc::now().to_rfc3339(),
+                    targets: rule.targets.clone(),
+                });
+            }
+        }
+
+        Ok(alerts)
+    }
+
+    /// Deliver an alert to all of its targets (failures are logged, not retried)
+    pub async fn notify(&self, alert: &Alert) {
+        for target in &alert.targets {
+            let delivered = match target {
+                AlertTarget::Webhook { url } => self.post_json(url, &json!({
+                    "type": "workflow_failures",
+                    "alert": alert,
+                    "message": alert.summary(),
+                })).await,
+                AlertTarget::Slack { webhook_url } => {
+                    self.post_json(webhook_url, &json!({ "text": format!("🚨 {}", alert.summary()) })).await
+                }
+                AlertTarget::Email { to } => match &self.config.smtp_addr {
+                    Some(addr) => {
+                        let subject = format!("[mechaway] {} failing in {}", alert.workflow_id, alert.project);
+                        send_smtp(addr, &self.config.smtp_from, to, &subject, &alert.summary()).await
+                    }
+                    None => Err(anyhow::anyhow!("no SMTP relay configured (MECHAWAY_SMTP_ADDR)")),
+                },
+            };
+            if let Err(e) = delivered {
+                tracing::warn!("⚠️ Failed to deliver alert '{}' for '{}': {}", alert.rule, alert.workflow_id, e);
+            }
+        }
+    }
+
+    async fn post_json(&self, url: &str, body: &Value) -> Result<()> {
+        self.http.post(url).json(body).send().await?.error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Send a plain-text email through an SMTP relay (no TLS/auth; meant for a local relay)
+async fn send_smtp(addr: &str, from: &str, to: &[String], subject: &str, body: &str) -> Result<()> {
+    let stream = tokio::time::timeout(Duration::from_secs(10), tokio::net::TcpStream::connect(addr)).await??;
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+
+    // Read a (possibly multi-line) reply and check its status class
+    async fn expect(reader: &mut BufReader<tokio::net::tcp::OwnedReadHalf>, code: char) -> Result<()> {
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).await? == 0 {
+                return Err(anyhow::anyhow!("SMTP connection closed"));
+            }
+            if !line.starts_with(code) {
+                return Err(anyhow::anyhow!("SMTP error: {}", line.trim_end()));
+            }
+            // "250-..." continues, "250 ..." ends the reply
+            if line.as_bytes().get(3) != Some(&b'-') {
+                return Ok(());
+            }
+        }
+    }
+
+    expect(&mut reader, '2').await?;
+    let mut commands = vec!["EHLO mechaway".to_string(), format!("MAIL FROM:<{}>", from)];
+    commands.extend(to.iter().map(|recipient| format!("RCPT TO:<{}>", recipient)));
+    for command in commands {
+        writer.write_all(format!("{}\r\n", command).as_bytes()).await?;
+        expect(&mut reader, '2').await?;
+    }
+
+    writer.write_all(b"DATA\r\n").await?;
+    expect(&mut reader, '3').await?;
+    // Dot-stuffing: lines starting with "." are escaped
+    let body = body.lines()
+        .map(|line| if line.starts_with('.') { format!(".{}", line) } else { line.to_string() })
+        .collect::<Vec<_>>()
+        .join("\r\n");
+    let message = format!(
+        "From: <{}>\r\nTo: {}\r\nSubject: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}\r\n.\r\n",
+        from,
+        to.iter().map(|recipient| format!("<{}>", recipient)).collect::<Vec<_>>().join(", "),
+        subject,
+        body,
+    );
+    writer.write_all(message.as_bytes()).await?;
+    expect(&mut reader, '2').await?;
+
+    writer.write_all(b"QUIT\r\n").await?;
+    Ok(())
+}
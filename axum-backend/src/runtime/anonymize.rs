@@ -0,0 +1,345 @@
+//! Anonymize node PII scrubbing strategies
+//! 
+//! Each configured field (dotted path, "*" matches every array element) gets
+//! one strategy:
+//! 
+//! { "fields": { "email": "hash", "card": { "strategy": "mask", "keep_last": 4 },
+//!               "notes": "redact", "customer.name": { "strategy": "fake", "kind": "name" },
+//!               "age": { "strategy": "bucket", "size": 10 }, "zip": { "strategy": "bucket", "keep_chars": 3 } },
+//!   "k": 5 }
+//! 
+//! Hashes and fakes are keyed with the node's secret (HMAC-SHA256), so the same
+//! input always maps to the same output within a project (joins keep working)
+//! while dictionary attacks need the key. Bucketed fields are quasi-identifiers:
+//! with "k", items whose bucket combination occurs fewer than k times in the
+//! data array have those fields suppressed ("*").
+
+use anyhow::Result;
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+const FIRST_NAMES: &[&str] = &[
+    "Alex", "Sam", "Jordan", "Taylor", "Morgan", "Casey", "Riley", "Jamie", "Avery", "Quinn",
+    "Robin", "Charlie", "Dana", "Drew", "Emery", "Finley", "Harper", "Kai", "Logan", "Noa",
+];
+const LAST_NAMES: &[&str] = &[
+    "Smith", "Garcia", "Müller", "Rossi", "Kim", "Nguyen", "Silva", "Novak", "Jensen", "Kowalski",
+    "Tanaka", "Dubois", "Okafor", "Haddad", "Larsen", "Moreau", "Santos", "Weber", "Ivanova", "Costa",
+];
+const COMPANIES: &[&str] = &[
+    "Acme", "Globex", "Initech", "Umbrella", "Hooli", "Vandelay", "Stark", "Wayne", "Tyrell", "Soylent",
+];
+const COMPANY_SUFFIXES: &[&str] = &["Ltd", "GmbH", "Inc", "Group", "Labs", "& Co"];
+const CITIES: &[&str] = &[
+    "Springfield", "Riverton", "Fairview", "Lakeside", "Greenville", "Kingston", "Milton", "Ashford",
+];
+const STREETS: &[&str] = &["Main St", "Oak Ave", "Park Rd", "Mill Lane", "High St", "Elm St", "Station Rd"];
+
+/// Fake value kinds for the "fake" strategy
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FakeKind {
+    Name,
+    FirstName,
+    LastName,
+    Email,
+    Phone,
+    Company,
+    City,
+    Address,
+    Ip,
+}
+
+impl FakeKind {
+    fn parse(name: &str) -> Result<Self> {
+        Ok(match name {
+            "name" => FakeKind::Name,
+            "first_name" => FakeKind::FirstName,
+            "last_name" => FakeKind::LastName,
+            "email" => FakeKind::Email,
+            "phone" => FakeKind::Phone,
+            "company" => FakeKind::Company,
+            "city" => FakeKind::City,
+            "address" => FakeKind::Address,
+            "ip" => FakeKind::Ip,
+            other => return Err(anyhow::anyhow!(
+                "Unknown fake kind '{}' (name, first_name, last_name, email, phone, company, city, address, ip)", other
+            )),
+        })
+    }
+}
+
+/// Generalization of a quasi-identifier
+#[derive(Debug, Clone, PartialEq)]
+pub enum Bucket {
+    /// Numbers into ranges of `size` ("30-39")
+    Range(f64),
+    /// Dates truncated to "year" / "month" / "day"
+    Date(String),
+    /// Strings truncated to their first characters ("123**")
+    Prefix(usize),
+}
+
+/// Per-field anonymization strategy
+#[derive(Debug, Clone, PartialEq)]
+pub enum Strategy {
+    Hash { length: Option<usize>, keep_domain: bool },
+    Mask { keep_first: usize, keep_last: usize, mask_char: char },
+    Redact { replacement: Value },
+    Remove,
+    Fake(FakeKind),
+    Bucket(Bucket),
+}
+
+impl Strategy {
+    fn parse(spec: &Value) -> Result<Self> {
+        let name = match spec {
+            Value::String(name) => name.as_str(),
+            _ => spec["strategy"].as_str().ok_or_else(|| anyhow::anyhow!("missing 'strategy'"))?,
+        };
+        let number = |key: &str| spec.get(key).and_then(|n| n.as_u64()).map(|n| n as usize);
+        Ok(match name {
+            "hash" => Strategy::Hash {
+                length: number("length").filter(|length| *length > 0),
+                keep_domain: spec.get("keep_domain").and_then(|k| k.as_bool()).unwrap_or(false),
+            },
+            "mask" => Strategy::Mask {
+                keep_first: number("keep_first").unwrap_or(0),
+                keep_last: number("keep_last").unwrap_or(4),
+                mask_char: spec.get("mask_char").and_then(|m| m.as_str()).and_then(|m| m.chars().next()).unwrap_or('*'),
+            },
+            "redact" => Strategy::Redact {
+                replacement: spec.get("replacement").cloned().unwrap_or_else(|| json!("[REDACTED]")),
+            },
+            "remove" => Strategy::Remove,
+            "fake" => Strategy::Fake(FakeKind::parse(spec.get("kind").and_then(|k| k.as_str()).unwrap_or("name"))?),
+            "bucket" => Strategy::Bucket(match spec.get("granularity").and_then(|g| g.as_str()) {
+                Some(granularity @ ("year" | "month" | "day")) => Bucket::Date(granularity.to_string()),
+                Some(other) => return Err(anyhow::anyhow!("Unknown granularity '{}' (year, month, day)", other)),
+                None => match spec.get("keep_chars") {
+                    Some(keep) => Bucket::Prefix(keep.as_u64().unwrap_or(3) as usize),
+                    None => Bucket::Range(spec.get("size").and_then(|s| s.as_f64()).filter(|s| *s > 0.0).unwrap_or(10.0)),
+                },
+            }),
+            other => return Err(anyhow::anyhow!("Unknown strategy '{}' (hash, mask, redact, remove, fake, bucket)", other)),
+        })
+    }
+}
+
+/// Parsed Anonymize node params
+#[derive(Debug, Clone)]
+pub struct AnonymizeParams {
+    /// (path segments, strategy), sorted by path
+    pub fields: Vec<(Vec<String>, Strategy)>,
+    /// Minimum equivalence class size over the bucketed fields
+    pub k: Option<usize>,
+}
+
+impl AnonymizeParams {
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        let fields = params.get("fields").and_then(|f| f.as_object())
+            .filter(|fields| !fields.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("Missing 'fields' object ({{ \"path\": strategy }})"))?
+            .iter()
+            .map(|(path, spec)| {
+                let strategy = Strategy::parse(spec).map_err(|e| anyhow::anyhow!("Field '{}': {}", path, e))?;
+                Ok((path.split('.').map(str::to_string).collect(), strategy))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let k = params.get("k").and_then(|k| k.as_u64()).map(|k| k as usize).filter(|k| *k > 1);
+        if k.is_some() && !fields.iter().any(|(_, strategy)| matches!(strategy, Strategy::Bucket(_))) {
+            return Err(anyhow::anyhow!("'k' needs at least one field with the bucket strategy"));
+        }
+        Ok(Self { fields, k })
+    }
+}
+
+/// Keyed hashing shared by the hash and fake strategies
+pub struct Anonymizer<'a> {
+    params: &'a AnonymizeParams,
+    key: Option<Vec<u8>>,
+}
+
+impl<'a> Anonymizer<'a> {
+    /// `key` is the node's secret (plain SHA-256 without one)
+    pub fn new(params: &'a AnonymizeParams, key: Option<&str>) -> Self {
+        Self { params, key: key.map(|key| key.as_bytes().to_vec()) }
+    }
+
+    /// Anonymize all items; returns the number of items with suppressed fields
+    pub fn apply(&self, items: &mut [Value]) -> usize {
+        // Quasi-identifier combination of every item (bucketed values in field order)
+        let mut classes: Vec<Vec<Value>> = Vec::with_capacity(items.len());
+        for item in items.iter_mut() {
+            let mut class = Vec::new();
+            for (path, strategy) in &self.params.fields {
+                let collected = &mut class;
+                visit(item, path, &mut |value: &mut Value| {
+                    let anonymized = self.anonymize(value, strategy);
+                    if let Strategy::Bucket(_) = strategy {
+                        collected.push(anonymized.clone().unwrap_or(Value::Null));
+                    }
+                    anonymized
+                });
+            }
+            classes.push(class);
+        }
+
+        let Some(k) = self.params.k else { return 0 };
+        let mut sizes: HashMap<String, usize> = HashMap::new();
+        let keys: Vec<String> = classes.into_iter().map(|class| Value::Array(class).to_string()).collect();
+        for key in &keys {
+            *sizes.entry(key.clone()).or_default() += 1;
+        }
+        let mut suppressed = 0;
+        for (item, key) in items.iter_mut().zip(&keys) {
+            if sizes[key] >= k {
+                continue;
+            }
+            suppressed += 1;
+            for (path, strategy) in &self.params.fields {
+                if let Strategy::Bucket(_) = strategy {
+                    visit(item, path, &mut |_| Some(json!("*")));
+                }
+            }
+        }
+        suppressed
+    }
+
+    /// New value for a field (None removes it)
+    fn anonymize(&self, value: &Value, strategy: &Strategy) -> Option<Value> {
+        if value.is_null() {
+            return Some(Value::Null);
+        }
+        let text = match value {
+            Value::String(text) => text.clone(),
+            other => other.to_string(),
+        };
+        Some(match strategy {
+            Strategy::Hash { length, keep_domain } => {
+                let (local, domain) = match text.rsplit_once('@') {
+                    Some((local, domain)) if *keep_domain => (local, Some(domain)),
+                    _ => (text.as_str(), None),
+                };
+                let mut digest = hex::encode(self.digest(local));
+                if let Some(length) = length {
+                    digest.truncate(*length);
+                }
+                match domain {
+                    Some(domain) => json!(format!("{}@{}", digest, domain)),
+                    None => json!(digest),
+                }
+            }
+            Strategy::Mask { keep_first, keep_last, mask_char } => {
+                let chars: Vec<char> = text.chars().collect();
+                // Short values are masked completely rather than revealed
+                let partial = keep_first + keep_last < chars.len();
+                json!(chars.iter().enumerate().map(|(index, c)| {
+                    let kept = index < *keep_first || index + keep_last >= chars.len();
+                    // Separators stay so masked values keep their shape ("****-****-****-1111")
+                    let separator = c.is_whitespace() || matches!(c, '-' | '@' | '.');
+                    if partial && (kept || separator) { *c } else { *mask_char }
+                }).collect::<String>())
+            }
+            Strategy::Redact { replacement } => replacement.clone(),
+            Strategy::Remove => return None,
+            Strategy::Fake(kind) => json!(self.fake(*kind, &text)),
+            Strategy::Bucket(bucket) => bucket_value(value, &text, bucket),
+        })
+    }
+
+    fn digest(&self, text: &str) -> [u8; 32] {
+        match &self.key {
+            Some(key) => {
+                let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+                mac.update(text.as_bytes());
+                mac.finalize().into_bytes().into()
+            }
+            None => Sha256::digest(text.as_bytes()).into(),
+        }
+    }
+
+    /// Deterministic fake value derived from the keyed hash of the input
+    fn fake(&self, kind: FakeKind, text: &str) -> String {
+        let digest = self.digest(text);
+        let pick = |list: &[&'static str], byte: usize| list[digest[byte] as usize % list.len()];
+        let number = |from: usize, digits: u32| {
+            let raw = u64::from_be_bytes(digest[from..from + 8].try_into().unwrap_or_default());
+            raw % 10u64.pow(digits)
+        };
+        match kind {
+            FakeKind::Name => format!("{} {}", pick(FIRST_NAMES, 0), pick(LAST_NAMES, 1)),
+            FakeKind::FirstName => pick(FIRST_NAMES, 0).to_string(),
+            FakeKind::LastName => pick(LAST_NAMES, 1).to_string(),
+            FakeKind::Email => format!("{}.{}{}@example.com", pick(FIRST_NAMES, 0).to_lowercase(),
+                pick(LAST_NAMES, 1).to_lowercase().replace('ü', "ue"), number(8, 4)),
+            // 555-01xx numbers are reserved for fiction
+            FakeKind::Phone => format!("+1-555-01{:02}", number(8, 2)),
+            FakeKind::Company => format!("{} {}", pick(COMPANIES, 2), pick(COMPANY_SUFFIXES, 3)),
+            FakeKind::City => pick(CITIES, 4).to_string(),
+            FakeKind::Address => format!("{} {}, {}", number(8, 3) + 1, pick(STREETS, 5), pick(CITIES, 4)),
+            // TEST-NET-3 documentation range
+            FakeKind::Ip => format!("203.0.113.{}", digest[6]),
+        }
+    }
+}
+
+/// Generalized value of a quasi-identifier
+fn bucket_value(value: &Value, text: &str, bucket: &Bucket) -> Value {
+    match bucket {
+        Bucket::Range(size) => match value.as_f64().or_else(|| text.trim().parse().ok()) {
+            Some(number) => {
+                let low = (number / size).floor() * size;
+                // Whole sizes give inclusive integer ranges ("30-39")
+                if size.fract() == 0.0 {
+                    json!(format!("{}-{}", low as i64, (low + size) as i64 - 1))
+                } else {
+                    json!(format!("{}-{}", low, low + size))
+                }
+            }
+            None => json!("*"),
+        },
+        Bucket::Date(granularity) => {
+            let length = match granularity.as_str() { "year" => 4, "month" => 7, _ => 10 };
+            let date = text.get(..length).filter(|date| {
+                date.bytes().enumerate().all(|(index, b)| if index == 4 || index == 7 { b == b'-' } else { b.is_ascii_digit() })
+            });
+            json!(date.unwrap_or("*"))
+        }
+        Bucket::Prefix(keep) => json!(text.chars().enumerate()
+            .map(|(index, c)| if index < *keep { c } else { '*' })
+            .collect::<String>()),
+    }
+}
+
+/// Apply `update` to every value at `path` (None removes the field)
+fn visit(value: &mut Value, path: &[String], update: &mut dyn FnMut(&mut Value) -> Option<Value>) {
+    let Some((segment, rest)) = path.split_first() else { return };
+    match value {
+        Value::Array(elements) if segment == "*" => {
+            for element in elements.iter_mut() {
+                if rest.is_empty() {
+                    if let Some(new) = update(element) {
+                        *element = new;
+                    }
+                } else {
+                    visit(element, rest, update);
+                }
+            }
+        }
+        Value::Object(object) => {
+            if rest.is_empty() {
+                if let Some(field) = object.get_mut(segment.as_str()) {
+                    match update(field) {
+                        Some(new) => *field = new,
+                        None => { object.remove(segment.as_str()); }
+                    }
+                }
+            } else if let Some(child) = object.get_mut(segment.as_str()) {
+                visit(child, rest, update);
+            }
+        }
+        _ => {}
+    }
+}
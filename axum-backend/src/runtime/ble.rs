@@ -0,0 +1,1047 @@
+//! Bluetooth Low Energy trigger service for BLETrigger nodes (cargo feature "ble")
+//! 
+//! Talks to BlueZ over the system D-Bus (Linux) and starts the workflow with
+//! decoded sensor payloads, either from advertisements picked up while
+//! scanning or from GATT characteristic notifications of a connected device:
+//! 
+//! { "mode": "advertisement", "service_uuids": ["fcd2"], "decoder": "bthome" }
+//! { "mode": "notify", "address": "A4:C1:38:12:34:56", "characteristic_uuids": ["2a6e"],
+//!   "fields": [{ "name": "temperature", "offset": 0, "type": "i16", "scale": 0.01 }] }
+//! 
+//! Advertisements are filtered by address, name prefix, manufacturer ID and
+//! service UUID, and throttled per device ("min_interval_ms", default 1000)
+//! because sensors advertise several times per second. Payloads are decoded
+//! with a field map (offsets into the bytes, little-endian by default) or as
+//! BTHome v2; the raw bytes are always in the envelope.
+//! 
+//! Item: the decoded fields plus "ble": { "address", "name", "rssi", "source",
+//! "uuid", "manufacturer_id", "data" }. Every payload also updates the state
+//! of its device (see devices); notify-mode devices go offline on disconnect
+//! and are reconnected with backoff.
+
+use crate::{
+    runtime::{
+        devices::{DeviceRegistry, DeviceTrigger},
+        engine::ExecutionEngine,
+    },
+    workflow::{
+        registry::WorkflowRegistry,
+        types::{ExecutionContext, Node, NodeType, Workflow},
+    },
+};
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::{sync::mpsc, sync::Mutex, task::JoinHandle, time::Instant};
+
+/// Error for builds without the "ble" feature
+const UNSUPPORTED: &str = "Bluetooth support is not compiled in (build with --features ble)";
+
+/// Decoded payloads buffered per trigger while a run is in progress
+const PAYLOAD_BUFFER: usize = 1024;
+
+/// Longest wait between reconnect attempts
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// Service data UUID of BTHome advertisements
+const BTHOME_UUID: &str = "0000fcd2-0000-1000-8000-00805f9b34fb";
+
+/// Full 128-bit form of a UUID ("2a6e" / "00002a6e" become Bluetooth base UUIDs)
+pub fn normalize_uuid(uuid: &str) -> String {
+    let uuid = uuid.trim().to_ascii_lowercase();
+    match uuid.len() {
+        4 => format!("0000{}-0000-1000-8000-00805f9b34fb", uuid),
+        8 => format!("{}-0000-1000-8000-00805f9b34fb", uuid),
+        _ => uuid,
+    }
+}
+
+/// What the trigger listens to
+#[derive(Debug, Clone, PartialEq)]
+pub enum BleMode {
+    /// Advertisements seen while scanning
+    Advertisement,
+    /// Notifications of these characteristics on one device
+    Notify { address: String, characteristic_uuids: Vec<String> },
+}
+
+/// Number format of a mapped field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldType {
+    U8,
+    I8,
+    U16,
+    I16,
+    U24,
+    I24,
+    U32,
+    I32,
+    F32,
+    Bool,
+}
+
+impl FieldType {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "u8" => FieldType::U8,
+            "i8" => FieldType::I8,
+            "u16" => FieldType::U16,
+            "i16" => FieldType::I16,
+            "u24" => FieldType::U24,
+            "i24" => FieldType::I24,
+            "u32" => FieldType::U32,
+            "i32" => FieldType::I32,
+            "f32" => FieldType::F32,
+            "bool" => FieldType::Bool,
+            _ => return None,
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            FieldType::U8 | FieldType::I8 | FieldType::Bool => 1,
+            FieldType::U16 | FieldType::I16 => 2,
+            FieldType::U24 | FieldType::I24 => 3,
+            FieldType::U32 | FieldType::I32 | FieldType::F32 => 4,
+        }
+    }
+}
+
+/// Named value at a byte offset of the payload
+#[derive(Debug, Clone)]
+pub struct PayloadField {
+    pub name: String,
+    pub offset: usize,
+    pub field_type: FieldType,
+    pub big_endian: bool,
+    pub scale: Option<f64>,
+}
+
+/// How payload bytes become item fields
+#[derive(Debug, Clone)]
+pub enum Decoder {
+    /// Raw bytes only (in the envelope)
+    Raw,
+    /// BTHome v2 service data
+    BtHome,
+    /// Field map
+    Fields(Vec<PayloadField>),
+}
+
+impl Decoder {
+    /// Decode a payload into item fields
+    pub fn decode(&self, data: &[u8]) -> Result<Map<String, Value>> {
+        match self {
+            Decoder::Raw => Ok(Map::new()),
+            Decoder::BtHome => decode_bthome(data),
+            Decoder::Fields(fields) => fields.iter()
+                .map(|field| Ok((field.name.clone(), decode_field(field, data)?)))
+                .collect(),
+        }
+    }
+}
+
+/// BLETrigger configuration
+#[derive(Debug, Clone)]
+pub struct BleParams {
+    /// BlueZ adapter ("hci0")
+    pub adapter: String,
+    pub mode: BleMode,
+    /// Advertisement filters (empty: any)
+    pub addresses: Vec<String>,
+    pub name_prefix: Option<String>,
+    pub manufacturer_id: Option<u16>,
+    /// Normalized service UUIDs (also passed to BlueZ as discovery filter)
+    pub service_uuids: Vec<String>,
+    pub decoder: Decoder,
+    /// Minimum time between payloads of one device
+    pub min_interval: Duration,
+}
+
+impl BleParams {
+    /// Parse node params
+    /// 
+    /// Expected params: { "mode": "advertisement" | "notify", "adapter": "hci0",
+    ///                    "addresses": ["A4:C1:38:12:34:56"], "name_prefix": "ATC_", "manufacturer_id": 1177,
+    ///                    "service_uuids": ["fcd2"], "address": "A4:C1:38:12:34:56", "characteristic_uuids": ["2a6e"],
+    ///                    "decoder": "raw" | "bthome", "fields": [...], "min_interval_ms": 1000 }
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        let text = |name: &str| params.get(name).and_then(|v| v.as_str()).filter(|v| !v.is_empty()).map(str::to_string);
+        let list = |name: &str| -> Result<Vec<String>> {
+            match params.get(name) {
+                None => Ok(Vec::new()),
+                Some(Value::Array(values)) => values.iter()
+                    .map(|value| value.as_str().filter(|v| !v.is_empty()).map(str::to_string)
+                        .ok_or_else(|| anyhow::anyhow!("'{}' must be an array of strings", name)))
+                    .collect(),
+                Some(_) => Err(anyhow::anyhow!("'{}' must be an array of strings", name)),
+            }
+        };
+
+        let mode = match text("mode").as_deref().unwrap_or("advertisement") {
+            "advertisement" => BleMode::Advertisement,
+            "notify" => {
+                let address = text("address")
+                    .ok_or_else(|| anyhow::anyhow!("notify mode REQUIRES 'address' (e.g. \"A4:C1:38:12:34:56\")"))?;
+                let characteristic_uuids: Vec<String> = list("characteristic_uuids")?.iter().map(|u| normalize_uuid(u)).collect();
+                if characteristic_uuids.is_empty() {
+                    return Err(anyhow::anyhow!("notify mode REQUIRES 'characteristic_uuids'"));
+                }
+                BleMode::Notify { address: parse_address(&address)?, characteristic_uuids }
+            }
+            other => return Err(anyhow::anyhow!("unsupported mode '{}' (expected advertisement or notify)", other)),
+        };
+
+        let decoder = match (text("decoder").as_deref(), params.get("fields")) {
+            (None | Some("fields"), Some(fields)) => Decoder::Fields(parse_fields(fields)?),
+            (None | Some("raw"), None) => Decoder::Raw,
+            (Some("bthome"), None) => Decoder::BtHome,
+            (Some("fields"), None) => return Err(anyhow::anyhow!("decoder 'fields' REQUIRES 'fields'")),
+            (Some(other @ ("raw" | "bthome")), Some(_)) => return Err(anyhow::anyhow!("'fields' can't be combined with decoder '{}'", other)),
+            (Some(other), _) => return Err(anyhow::anyhow!("unsupported decoder '{}' (expected raw, bthome or fields)", other)),
+        };
+        let mut service_uuids: Vec<String> = list("service_uuids")?.iter().map(|u| normalize_uuid(u)).collect();
+        if matches!(decoder, Decoder::BtHome) && matches!(mode, BleMode::Advertisement) && service_uuids.is_empty() {
+            service_uuids.push(BTHOME_UUID.to_string());
+        }
+        let manufacturer_id = match params.get("manufacturer_id") {
+            None => None,
+            Some(id) => Some(id.as_u64().filter(|id| *id <= u64::from(u16::MAX))
+                .ok_or_else(|| anyhow::anyhow!("'manufacturer_id' must be 0 to 65535"))? as u16),
+        };
+        let default_interval = if mode == BleMode::Advertisement { 1000 } else { 0 };
+        let min_interval_ms = params.get("min_interval_ms").map_or(Some(default_interval), |i| i.as_u64())
+            .ok_or_else(|| anyhow::anyhow!("'min_interval_ms' must be a non-negative integer"))?;
+
+        Ok(Self {
+            adapter: text("adapter").unwrap_or_else(|| "hci0".to_string()),
+            mode,
+            addresses: list("addresses")?.iter().map(|a| parse_address(a)).collect::<Result<_>>()?,
+            name_prefix: text("name_prefix"),
+            manufacturer_id,
+            service_uuids,
+            decoder,
+            min_interval: Duration::from_millis(min_interval_ms),
+        })
+    }
+
+    /// Payloads of an advertisement that pass the filters
+    fn matching_payloads<'a>(&self, advertisement: &'a Advertisement) -> Vec<Payload<'a>> {
+        if !self.addresses.is_empty() && !self.addresses.contains(&advertisement.address) {
+            return Vec::new();
+        }
+        if let Some(prefix) = &self.name_prefix {
+            if !advertisement.name.as_deref().is_some_and(|name| name.starts_with(prefix.as_str())) {
+                return Vec::new();
+            }
+        }
+
+        let mut payloads = Vec::new();
+        if self.service_uuids.is_empty() {
+            for (id, data) in &advertisement.manufacturer_data {
+                if self.manufacturer_id.is_none_or(|wanted| wanted == *id) {
+                    payloads.push(Payload { source: "manufacturer_data", uuid: None, manufacturer_id: Some(*id), data });
+                }
+            }
+        }
+        if self.manufacturer_id.is_none() {
+            for (uuid, data) in &advertisement.service_data {
+                if self.service_uuids.is_empty() || self.service_uuids.contains(uuid) {
+                    payloads.push(Payload { source: "service_data", uuid: Some(uuid), manufacturer_id: None, data });
+                }
+            }
+        }
+        payloads
+    }
+}
+
+/// Manufacturer or service data of an advertisement
+struct Payload<'a> {
+    source: &'static str,
+    uuid: Option<&'a str>,
+    manufacturer_id: Option<u16>,
+    data: &'a [u8],
+}
+
+/// "a4:c1:38:12:34:56" -> "A4:C1:38:12:34:56"
+fn parse_address(address: &str) -> Result<String> {
+    let address = address.trim().to_ascii_uppercase();
+    let valid = address.len() == 17 && address.split(':').all(|part| part.len() == 2 && part.chars().all(|c| c.is_ascii_hexdigit()));
+    if valid {
+        Ok(address)
+    } else {
+        Err(anyhow::anyhow!("invalid Bluetooth address '{}' (expected AA:BB:CC:DD:EE:FF)", address))
+    }
+}
+
+/// Parse the "fields" map
+fn parse_fields(fields: &Value) -> Result<Vec<PayloadField>> {
+    let fields = fields.as_array().filter(|f| !f.is_empty())
+        .ok_or_else(|| anyhow::anyhow!("'fields' must be a non-empty array"))?;
+    fields.iter().enumerate()
+        .map(|(index, field)| {
+            let name = field.get("name").and_then(|n| n.as_str()).filter(|n| !n.is_empty())
+                .ok_or_else(|| anyhow::anyhow!("fields[{}] REQUIRES 'name'", index))?;
+            let offset = field.get("offset").and_then(|o| o.as_u64())
+                .ok_or_else(|| anyhow::anyhow!("field '{}' REQUIRES 'offset'", name))?;
+            let type_name = field.get("type").and_then(|t| t.as_str()).unwrap_or("u8");
+            let field_type = FieldType::parse(type_name)
+                .ok_or_else(|| anyhow::anyhow!("field '{}' has unsupported type '{}' (u8, i8, u16, i16, u24, i24, u32, i32, f32 or bool)", name, type_name))?;
+            let big_endian = match field.get("endian").and_then(|e| e.as_str()).unwrap_or("little") {
+                "little" => false,
+                "big" => true,
+                other => return Err(anyhow::anyhow!("field '{}' has unsupported endian '{}' (little or big)", name, other)),
+            };
+            Ok(PayloadField {
+                name: name.to_string(),
+                offset: offset as usize,
+                field_type,
+                big_endian,
+                scale: field.get("scale").and_then(|s| s.as_f64()),
+            })
+        })
+        .collect()
+}
+
+/// Unsigned little- or big-endian integer of 1 to 4 bytes
+fn read_uint(bytes: &[u8], big_endian: bool) -> u64 {
+    let fold = |value: u64, byte: &u8| (value << 8) | u64::from(*byte);
+    if big_endian {
+        bytes.iter().fold(0, fold)
+    } else {
+        bytes.iter().rev().fold(0, fold)
+    }
+}
+
+/// Sign-extend an integer of `size` bytes
+fn to_signed(value: u64, size: usize) -> i64 {
+    let shift = 64 - size as u32 * 8;
+    ((value << shift) as i64) >> shift
+}
+
+/// Scale an integer reading, keeping exact decimals for factors like 0.01
+fn scaled(raw: f64, factor: f64) -> Value {
+    let inverse = 1.0 / factor;
+    let value = if factor < 1.0 && (inverse - inverse.round()).abs() < 1e-9 {
+        raw / inverse.round()
+    } else {
+        raw * factor
+    };
+    json!(value)
+}
+
+fn decode_field(field: &PayloadField, data: &[u8]) -> Result<Value> {
+    let size = field.field_type.size();
+    let bytes = data.get(field.offset..field.offset + size)
+        .ok_or_else(|| anyhow::anyhow!("field '{}' needs {} byte(s) at offset {}, payload has {}", field.name, size, field.offset, data.len()))?;
+    let raw = read_uint(bytes, field.big_endian);
+    let number = match field.field_type {
+        FieldType::Bool => return Ok(json!(raw != 0)),
+        FieldType::F32 => f64::from(f32::from_bits(raw as u32)),
+        FieldType::I8 | FieldType::I16 | FieldType::I24 | FieldType::I32 => to_signed(raw, size) as f64,
+        _ => raw as f64,
+    };
+    Ok(match field.scale {
+        Some(scale) => scaled(number, scale),
+        None if field.field_type == FieldType::F32 => json!(number),
+        None if number < 0.0 => json!(number as i64),
+        None => json!(raw),
+    })
+}
+
+/// BTHome v2 object: (name, size, signed, factor); binary sensors have factor 0
+const BTHOME_OBJECTS: &[(u8, &str, usize, bool, f64)] = &[
+    (0x00, "packet_id", 1, false, 1.0),
+    (0x01, "battery", 1, false, 1.0),
+    (0x02, "temperature", 2, true, 0.01),
+    (0x03, "humidity", 2, false, 0.01),
+    (0x04, "pressure", 3, false, 0.01),
+    (0x05, "illuminance", 3, false, 0.01),
+    (0x06, "mass_kg", 2, false, 0.01),
+    (0x07, "mass_lb", 2, false, 0.01),
+    (0x08, "dewpoint", 2, true, 0.01),
+    (0x09, "count", 1, false, 1.0),
+    (0x0A, "energy", 3, false, 0.001),
+    (0x0B, "power", 3, false, 0.01),
+    (0x0C, "voltage", 2, false, 0.001),
+    (0x0D, "pm2_5", 2, false, 1.0),
+    (0x0E, "pm10", 2, false, 1.0),
+    (0x0F, "generic_boolean", 1, false, 0.0),
+    (0x10, "power_on", 1, false, 0.0),
+    (0x11, "opening", 1, false, 0.0),
+    (0x12, "co2", 2, false, 1.0),
+    (0x13, "tvoc", 2, false, 1.0),
+    (0x14, "moisture", 2, false, 0.01),
+    (0x15, "battery_low", 1, false, 0.0),
+    (0x16, "battery_charging", 1, false, 0.0),
+    (0x17, "carbon_monoxide", 1, false, 0.0),
+    (0x18, "cold", 1, false, 0.0),
+    (0x19, "connectivity", 1, false, 0.0),
+    (0x1A, "door", 1, false, 0.0),
+    (0x1B, "garage_door", 1, false, 0.0),
+    (0x1C, "gas_detected", 1, false, 0.0),
+    (0x1D, "heat", 1, false, 0.0),
+    (0x1E, "light", 1, false, 0.0),
+    (0x1F, "lock", 1, false, 0.0),
+    (0x20, "moisture_detected", 1, false, 0.0),
+    (0x21, "motion", 1, false, 0.0),
+    (0x22, "moving", 1, false, 0.0),
+    (0x23, "occupancy", 1, false, 0.0),
+    (0x24, "plug", 1, false, 0.0),
+    (0x25, "presence", 1, false, 0.0),
+    (0x26, "problem", 1, false, 0.0),
+    (0x27, "running", 1, false, 0.0),
+    (0x28, "safety", 1, false, 0.0),
+    (0x29, "smoke", 1, false, 0.0),
+    (0x2A, "sound", 1, false, 0.0),
+    (0x2B, "tamper", 1, false, 0.0),
+    (0x2C, "vibration", 1, false, 0.0),
+    (0x2D, "window", 1, false, 0.0),
+    (0x2E, "humidity", 1, false, 1.0),
+    (0x2F, "moisture", 1, false, 1.0),
+    (0x3A, "button", 1, false, 1.0),
+    (0x3D, "count", 2, false, 1.0),
+    (0x3E, "count", 4, false, 1.0),
+    (0x3F, "rotation", 2, true, 0.1),
+    (0x40, "distance_mm", 2, false, 1.0),
+    (0x41, "distance_m", 2, false, 0.1),
+    (0x42, "duration", 3, false, 0.001),
+    (0x43, "current", 2, false, 0.001),
+    (0x44, "speed", 2, false, 0.01),
+    (0x45, "temperature", 2, true, 0.1),
+    (0x46, "uv_index", 1, false, 0.1),
+    (0x47, "volume_l", 2, false, 0.1),
+    (0x48, "volume_ml", 2, false, 1.0),
+    (0x49, "volume_flow_rate", 2, false, 0.001),
+    (0x4A, "voltage", 2, false, 0.1),
+    (0x4B, "gas", 3, false, 0.001),
+    (0x4C, "gas", 4, false, 0.001),
+    (0x4D, "energy", 4, false, 0.001),
+    (0x4E, "volume_l", 4, false, 0.001),
+    (0x4F, "water", 4, false, 0.001),
+    (0x50, "timestamp", 4, false, 1.0),
+    (0x51, "acceleration", 2, false, 0.001),
+    (0x52, "gyroscope", 2, false, 0.001),
+    (0x57, "temperature", 1, true, 1.0),
+    (0x58, "temperature", 1, true, 0.35),
+    (0x59, "count", 1, true, 1.0),
+    (0x5A, "count", 2, true, 1.0),
+    (0x5B, "count", 4, true, 1.0),
+    (0x5C, "power", 4, true, 0.01),
+    (0x5D, "current", 2, true, 0.001),
+    (0x5E, "direction", 2, false, 0.01),
+    (0x5F, "precipitation", 2, false, 0.1),
+    (0x60, "channel", 1, false, 1.0),
+    (0x61, "rotational_speed", 2, false, 1.0),
+];
+
+/// Decode BTHome v2 service data (unencrypted)
+/// 
+/// Repeated objects get "_2", "_3" suffixes; decoding stops at the first
+/// unknown object ID, leaving the rest under "bthome_undecoded".
+fn decode_bthome(data: &[u8]) -> Result<Map<String, Value>> {
+    let (&info, mut rest) = data.split_first().ok_or_else(|| anyhow::anyhow!("empty BTHome payload"))?;
+    if info >> 5 != 2 {
+        return Err(anyhow::anyhow!("unsupported BTHome version {}", info >> 5));
+    }
+    if info & 0x01 != 0 {
+        return Err(anyhow::anyhow!("encrypted BTHome payloads are not supported"));
+    }
+
+    let mut fields = Map::new();
+    while let Some((&id, after_id)) = rest.split_first() {
+        let Some(&(_, name, size, signed, factor)) = BTHOME_OBJECTS.iter().find(|object| object.0 == id) else {
+            fields.insert("bthome_undecoded".to_string(), json!(hex::encode(rest)));
+            break;
+        };
+        let Some(bytes) = after_id.get(..size) else {
+            return Err(anyhow::anyhow!("truncated BTHome object 0x{:02x}", id));
+        };
+        let raw = read_uint(bytes, false);
+        let value = if factor == 0.0 {
+            json!(raw != 0)
+        } else if signed {
+            scaled(to_signed(raw, size) as f64, factor)
+        } else if factor == 1.0 {
+            json!(raw)
+        } else {
+            scaled(raw as f64, factor)
+        };
+        let key = (1..).map(|n| if n == 1 { name.to_string() } else { format!("{}_{}", name, n) })
+            .find(|key| !fields.contains_key(key))
+            .unwrap_or_default();
+        fields.insert(key, value);
+        rest = &after_id[size..];
+    }
+    Ok(fields)
+}
+
+/// Advertisement data of a device as last reported by BlueZ
+#[derive(Debug, Clone, Default)]
+pub struct Advertisement {
+    pub address: String,
+    pub name: Option<String>,
+    pub rssi: Option<i16>,
+    pub manufacturer_data: Vec<(u16, Vec<u8>)>,
+    /// (normalized service UUID, data)
+    pub service_data: Vec<(String, Vec<u8>)>,
+}
+
+/// Events reported by an adapter
+#[derive(Debug, Clone)]
+pub enum BleEvent {
+    Advertisement(Advertisement),
+    Notification { address: String, name: Option<String>, uuid: String, value: Vec<u8> },
+    Disconnected { address: String },
+}
+
+/// BlueZ adapter on the system bus
+#[cfg(feature = "ble")]
+pub struct Adapter {
+    connection: zbus::Connection,
+    /// Object path ("/org/bluez/hci0")
+    path: String,
+}
+
+/// BlueZ adapter (uninhabited: this build has no Bluetooth support)
+#[cfg(not(feature = "ble"))]
+pub enum Adapter {}
+
+#[cfg(not(feature = "ble"))]
+impl Adapter {
+    /// Connect to the system bus and check the adapter exists
+    pub async fn open(_name: &str) -> Result<Self> {
+        Err(anyhow::anyhow!(UNSUPPORTED))
+    }
+
+    /// Report advertisements until the connection fails
+    pub async fn scan(&self, _service_uuids: &[String], _events: &mpsc::Sender<BleEvent>) -> Result<()> {
+        match *self {}
+    }
+
+    /// Connect to a device and report notifications until it disconnects
+    pub async fn notify(&self, _address: &str, _characteristic_uuids: &[String], _events: &mpsc::Sender<BleEvent>) -> Result<()> {
+        match *self {}
+    }
+}
+
+#[cfg(feature = "ble")]
+mod bluez {
+    use super::{normalize_uuid, Adapter, Advertisement, BleEvent};
+    use anyhow::Result;
+    use futures_util::StreamExt;
+    use std::{collections::HashMap, time::Duration};
+    use tokio::sync::mpsc;
+    use zbus::{message::Type, zvariant::{OwnedObjectPath, OwnedValue, Value}, Connection, MatchRule, Message, MessageStream, Proxy};
+
+    type Properties = HashMap<String, OwnedValue>;
+    type Interfaces = HashMap<String, Properties>;
+
+    const DEVICE: &str = "org.bluez.Device1";
+    const CHARACTERISTIC: &str = "org.bluez.GattCharacteristic1";
+
+    /// How long a notify-mode device may take to show up, connect and resolve services
+    const SETUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+    fn string(value: &Value) -> Option<String> {
+        match value {
+            Value::Str(text) => Some(text.to_string()),
+            Value::Value(inner) => string(inner),
+            _ => None,
+        }
+    }
+
+    fn boolean(value: &Value) -> Option<bool> {
+        match value {
+            Value::Bool(flag) => Some(*flag),
+            Value::Value(inner) => boolean(inner),
+            _ => None,
+        }
+    }
+
+    fn rssi(value: &Value) -> Option<i16> {
+        match value {
+            Value::I16(rssi) => Some(*rssi),
+            Value::Value(inner) => rssi(inner),
+            _ => None,
+        }
+    }
+
+    fn bytes(value: &Value) -> Option<Vec<u8>> {
+        match value {
+            Value::Array(array) => array.iter().map(|byte| match byte {
+                Value::U8(byte) => Some(*byte),
+                _ => None,
+            }).collect(),
+            Value::Value(inner) => bytes(inner),
+            _ => None,
+        }
+    }
+
+    /// a{qv} ManufacturerData
+    fn manufacturer_data(value: &Value) -> Vec<(u16, Vec<u8>)> {
+        match value {
+            Value::Dict(dict) => dict.iter()
+                .filter_map(|(key, data)| match key {
+                    Value::U16(id) => Some((*id, bytes(data)?)),
+                    _ => None,
+                })
+                .collect(),
+            Value::Value(inner) => manufacturer_data(inner),
+            _ => Vec::new(),
+        }
+    }
+
+    /// a{sv} ServiceData
+    fn service_data(value: &Value) -> Vec<(String, Vec<u8>)> {
+        match value {
+            Value::Dict(dict) => dict.iter()
+                .filter_map(|(key, data)| Some((normalize_uuid(&string(key)?), bytes(data)?)))
+                .collect(),
+            Value::Value(inner) => service_data(inner),
+            _ => Vec::new(),
+        }
+    }
+
+    /// "/org/bluez/hci0/dev_A4_C1_38_12_34_56" -> "A4:C1:38:12:34:56"
+    fn device_address(path: &str) -> Option<String> {
+        let (_, device) = path.rsplit_once("/dev_")?;
+        (device.len() == 17 && !device.contains('/')).then(|| device.replace('_', ":"))
+    }
+
+    fn signal_rule(interface: &'static str, member: &'static str, path_namespace: &str) -> Result<MatchRule<'static>> {
+        Ok(MatchRule::builder()
+            .msg_type(Type::Signal)
+            .sender("org.bluez")?
+            .interface(interface)?
+            .member(member)?
+            .path_namespace(path_namespace.to_string())?
+            .build())
+    }
+
+    /// (path, interface, changed properties) of a PropertiesChanged signal
+    fn properties_changed(message: &Message) -> Option<(String, String, Properties)> {
+        let path = message.header().path()?.to_string();
+        let (interface, changed, _invalidated): (String, Properties, Vec<String>) = message.body().deserialize().ok()?;
+        Some((path, interface, changed))
+    }
+
+    /// (path, interfaces) of an InterfacesAdded signal
+    fn interfaces_added(message: &Message) -> Option<(String, Interfaces)> {
+        let (path, interfaces): (OwnedObjectPath, Interfaces) = message.body().deserialize().ok()?;
+        Some((path.to_string(), interfaces))
+    }
+
+    /// Name and RSSI of known devices, to complete advertisement updates
+    #[derive(Default)]
+    struct DeviceCache(HashMap<String, Advertisement>);
+
+    impl DeviceCache {
+        /// Merge device properties, returning the advertisement when payload data changed
+        fn update(&mut self, path: &str, properties: &Properties) -> Option<Advertisement> {
+            let address = properties.get("Address").and_then(|a| string(a)).or_else(|| device_address(path))?;
+            let entry = self.0.entry(path.to_string()).or_insert_with(|| Advertisement { address, ..Default::default() });
+            if let Some(name) = properties.get("Name").or(properties.get("Alias")).and_then(|n| string(n)) {
+                entry.name = Some(name);
+            }
+            if let Some(rssi) = properties.get("RSSI").and_then(|r| rssi(r)) {
+                entry.rssi = Some(rssi);
+            }
+            let manufacturer = properties.get("ManufacturerData").map(|m| manufacturer_data(m));
+            let service = properties.get("ServiceData").map(|s| service_data(s));
+            if manufacturer.is_none() && service.is_none() {
+                return None;
+            }
+            // Only the payloads of this update, so unchanged data doesn't repeat
+            Some(Advertisement {
+                manufacturer_data: manufacturer.unwrap_or_default(),
+                service_data: service.unwrap_or_default(),
+                ..entry.clone()
+            })
+        }
+    }
+
+    impl Adapter {
+        /// Connect to the system bus and check the adapter exists
+        pub async fn open(name: &str) -> Result<Self> {
+            let connection = Connection::system().await
+                .map_err(|e| anyhow::anyhow!("failed to connect to the system D-Bus: {}", e))?;
+            let adapter = Self { connection, path: format!("/org/bluez/{}", name) };
+            let objects = adapter.managed_objects().await?;
+            if !objects.get(adapter.path.as_str()).is_some_and(|interfaces| interfaces.contains_key("org.bluez.Adapter1")) {
+                return Err(anyhow::anyhow!("Bluetooth adapter '{}' not found", name));
+            }
+            Ok(adapter)
+        }
+
+        async fn managed_objects(&self) -> Result<HashMap<String, Interfaces>> {
+            let reply = self.connection.call_method(Some("org.bluez"), "/", Some("org.freedesktop.DBus.ObjectManager"), "GetManagedObjects", &()).await
+                .map_err(|e| anyhow::anyhow!("BlueZ is not available: {}", e))?;
+            let objects: HashMap<OwnedObjectPath, Interfaces> = reply.body().deserialize()?;
+            Ok(objects.into_iter().map(|(path, interfaces)| (path.to_string(), interfaces)).collect())
+        }
+
+        async fn proxy(&self, path: &str, interface: &'static str) -> Result<Proxy<'static>> {
+            Ok(Proxy::new(&self.connection, "org.bluez", path.to_string(), interface).await?)
+        }
+
+        async fn start_discovery(&self, service_uuids: &[String]) -> Result<()> {
+            let adapter = self.proxy(&self.path, "org.bluez.Adapter1").await?;
+            let mut filter: HashMap<&str, Value> = HashMap::new();
+            filter.insert("Transport", Value::from("le"));
+            filter.insert("DuplicateData", Value::from(true));
+            if !service_uuids.is_empty() {
+                filter.insert("UUIDs", Value::from(service_uuids.to_vec()));
+            }
+            adapter.call::<_, _, ()>("SetDiscoveryFilter", &(filter,)).await
+                .map_err(|e| anyhow::anyhow!("SetDiscoveryFilter failed: {}", e))?;
+            adapter.call::<_, _, ()>("StartDiscovery", &()).await
+                .map_err(|e| anyhow::anyhow!("StartDiscovery failed: {}", e))
+        }
+
+        /// Report advertisements until the connection fails
+        pub async fn scan(&self, service_uuids: &[String], events: &mpsc::Sender<BleEvent>) -> Result<()> {
+            let mut changes = MessageStream::for_match_rule(
+                signal_rule("org.freedesktop.DBus.Properties", "PropertiesChanged", &self.path)?, &self.connection, None).await?;
+            let mut added = MessageStream::for_match_rule(
+                signal_rule("org.freedesktop.DBus.ObjectManager", "InterfacesAdded", "/")?, &self.connection, None).await?;
+
+            let mut devices = DeviceCache::default();
+            for (path, interfaces) in self.managed_objects().await? {
+                if let Some(properties) = interfaces.get(DEVICE).filter(|_| path.starts_with(&self.path)) {
+                    devices.update(&path, properties);
+                }
+            }
+            // Discovery stops by itself when this connection goes away
+            self.start_discovery(service_uuids).await?;
+
+            loop {
+                let update = tokio::select! {
+                    message = changes.next() => match message {
+                        Some(Ok(message)) => properties_changed(&message)
+                            .filter(|(_, interface, _)| interface == DEVICE)
+                            .and_then(|(path, _, changed)| devices.update(&path, &changed)),
+                        _ => return Err(anyhow::anyhow!("D-Bus connection closed")),
+                    },
+                    message = added.next() => match message {
+                        Some(Ok(message)) => interfaces_added(&message)
+                            .filter(|(path, _)| path.starts_with(&self.path))
+                            .and_then(|(path, interfaces)| devices.update(&path, interfaces.get(DEVICE)?)),
+                        _ => return Err(anyhow::anyhow!("D-Bus connection closed")),
+                    },
+                };
+                if let Some(advertisement) = update {
+                    if events.send(BleEvent::Advertisement(advertisement)).await.is_err() {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+
+        /// Connect to a device and report notifications until it disconnects
+        pub async fn notify(&self, address: &str, characteristic_uuids: &[String], events: &mpsc::Sender<BleEvent>) -> Result<()> {
+            let device_path = format!("{}/dev_{}", self.path, address.replace(':', "_"));
+            let mut changes = MessageStream::for_match_rule(
+                signal_rule("org.freedesktop.DBus.Properties", "PropertiesChanged", &device_path)?, &self.connection, None).await?;
+
+            // Unknown devices have to be seen in a scan before BlueZ can connect
+            if !self.managed_objects().await?.contains_key(&device_path) {
+                let mut added = MessageStream::for_match_rule(
+                    signal_rule("org.freedesktop.DBus.ObjectManager", "InterfacesAdded", "/")?, &self.connection, None).await?;
+                self.start_discovery(&[]).await?;
+                tokio::time::timeout(SETUP_TIMEOUT, async {
+                    while let Some(Ok(message)) = added.next().await {
+                        if interfaces_added(&message).is_some_and(|(path, _)| path == device_path) {
+                            return;
+                        }
+                    }
+                }).await.map_err(|_| anyhow::anyhow!("device {} not found within {:?}", address, SETUP_TIMEOUT))?;
+                let adapter = self.proxy(&self.path, "org.bluez.Adapter1").await?;
+                let _ = adapter.call::<_, _, ()>("StopDiscovery", &()).await;
+            }
+
+            let device = self.proxy(&device_path, DEVICE).await?;
+            tokio::time::timeout(SETUP_TIMEOUT, device.call::<_, _, ()>("Connect", &())).await
+                .map_err(|_| anyhow::anyhow!("connecting to {} timed out", address))?
+                .map_err(|e| anyhow::anyhow!("failed to connect to {}: {}", address, e))?;
+            let resolved: bool = device.get_property("ServicesResolved").await.unwrap_or(false);
+            if !resolved {
+                tokio::time::timeout(SETUP_TIMEOUT, async {
+                    while let Some(Ok(message)) = changes.next().await {
+                        let done = properties_changed(&message).is_some_and(|(path, interface, changed)| path == device_path
+                            && interface == DEVICE
+                            && changed.get("ServicesResolved").and_then(|r| boolean(r)) == Some(true));
+                        if done {
+                            return;
+                        }
+                    }
+                }).await.map_err(|_| anyhow::anyhow!("services of {} not resolved within {:?}", address, SETUP_TIMEOUT))?;
+            }
+            let name: Option<String> = device.get_property("Name").await.ok();
+
+            // Characteristic object path -> UUID
+            let mut characteristics: HashMap<String, String> = HashMap::new();
+            for (path, interfaces) in self.managed_objects().await? {
+                let uuid = interfaces.get(CHARACTERISTIC)
+                    .filter(|_| path.starts_with(&format!("{}/", device_path)))
+                    .and_then(|properties| properties.get("UUID"))
+                    .and_then(|uuid| string(uuid))
+                    .map(|uuid| normalize_uuid(&uuid));
+                if let Some(uuid) = uuid.filter(|uuid| characteristic_uuids.contains(uuid)) {
+                    characteristics.insert(path, uuid);
+                }
+            }
+            if let Some(missing) = characteristic_uuids.iter().find(|uuid| !characteristics.values().any(|found| found == *uuid)) {
+                return Err(anyhow::anyhow!("characteristic {} not found on {}", missing, address));
+            }
+            for path in characteristics.keys() {
+                self.proxy(path, CHARACTERISTIC).await?
+                    .call::<_, _, ()>("StartNotify", &()).await
+                    .map_err(|e| anyhow::anyhow!("StartNotify on {} failed: {}", characteristics[path], e))?;
+            }
+            tracing::info!("🔵 Subscribed to {} characteristic(s) of {}", characteristics.len(), address);
+
+            while let Some(message) = changes.next().await {
+                let Some((path, interface, changed)) = message.ok().as_ref().and_then(properties_changed) else { continue };
+                if interface == CHARACTERISTIC {
+                    let (Some(uuid), Some(value)) = (characteristics.get(&path), changed.get("Value").and_then(|v| bytes(v))) else { continue };
+                    let event = BleEvent::Notification { address: address.to_string(), name: name.clone(), uuid: uuid.clone(), value };
+                    if events.send(event).await.is_err() {
+                        return Ok(());
+                    }
+                } else if path == device_path && interface == DEVICE && changed.get("Connected").and_then(|c| boolean(c)) == Some(false) {
+                    let _ = events.send(BleEvent::Disconnected { address: address.to_string() }).await;
+                    return Ok(());
+                }
+            }
+            Err(anyhow::anyhow!("D-Bus connection closed"))
+        }
+    }
+}
+
+/// Fails when the binary was built without Bluetooth support
+pub fn ensure_supported() -> Result<()> {
+    if cfg!(feature = "ble") {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(UNSUPPORTED))
+    }
+}
+
+/// HOT-RELOAD BLE trigger service
+pub struct BleTriggerService {
+    registry: Arc<WorkflowRegistry>,
+    engine: Arc<ExecutionEngine>,
+    /// Device states updated by every payload
+    devices: Arc<DeviceRegistry>,
+    /// workflow_id -> one listener task per BLETrigger node
+    listeners: Mutex<HashMap<String, Vec<JoinHandle<()>>>>,
+}
+
+impl BleTriggerService {
+    /// Create new BLE trigger service
+    pub fn new(registry: Arc<WorkflowRegistry>, engine: Arc<ExecutionEngine>, devices: Arc<DeviceRegistry>) -> Self {
+        Self { registry, engine, devices, listeners: Mutex::new(HashMap::new()) }
+    }
+
+    /// Start the BLETrigger listeners of all registered workflows
+    pub async fn start(&self) {
+        tracing::info!("🔵 Starting BLE trigger service");
+        for workflow in self.registry.get_all_workflows() {
+            if let Err(e) = self.add_or_update_workflow_ble_triggers(&workflow).await {
+                tracing::error!("❌ Failed to start BLE triggers of workflow {}: {}", workflow.id, e);
+            }
+        }
+    }
+
+    /// Number of running BLE listeners
+    pub async fn listener_count(&self) -> usize {
+        self.listeners.lock().await.values().map(Vec::len).sum()
+    }
+
+    /// HOT-RELOAD: Replace the listeners of a workflow with its current BLETrigger nodes
+    pub async fn add_or_update_workflow_ble_triggers(&self, workflow: &Workflow) -> Result<()> {
+        let triggers: Vec<(&Node, BleParams)> = workflow.nodes.iter()
+            .filter(|node| matches!(node.node_type, NodeType::BLETrigger))
+            .map(|node| BleParams::from_node_params(&node.params)
+                .and_then(|params| ensure_supported().map(|_| params))
+                .map(|params| (node, params))
+                .map_err(|e| anyhow::anyhow!("BLETrigger '{}': {}", node.id, e)))
+            .collect::<Result<_>>()?;
+
+        self.remove_workflow_ble_triggers(&workflow.id).await;
+        if triggers.is_empty() {
+            return Ok(());
+        }
+
+        let handles: Vec<JoinHandle<()>> = triggers.into_iter()
+            .map(|(node, params)| {
+                tracing::info!("🔵 Starting BLETrigger {}:{} on {} ({:?})", workflow.id, node.id, params.adapter, params.mode);
+                let listener = Listener {
+                    registry: Arc::clone(&self.registry),
+                    engine: Arc::clone(&self.engine),
+                    devices: Arc::clone(&self.devices),
+                    project: workflow.project.clone(),
+                    device: DeviceTrigger::from_node("ble", &workflow.id, node),
+                    params,
+                };
+                tokio::spawn(listener.run())
+            })
+            .collect();
+
+        tracing::info!("🔥 Hot-reloaded {} BLE triggers for workflow: {}", handles.len(), workflow.id);
+        self.listeners.lock().await.insert(workflow.id.clone(), handles);
+        Ok(())
+    }
+
+    /// HOT-RELOAD: Stop all BLE triggers of a workflow
+    pub async fn remove_workflow_ble_triggers(&self, workflow_id: &str) {
+        if let Some(handles) = self.listeners.lock().await.remove(workflow_id) {
+            tracing::info!("🗑️ Removing {} BLE triggers for workflow: {}", handles.len(), workflow_id);
+            for handle in handles {
+                handle.abort();
+            }
+        }
+    }
+}
+
+/// One running BLETrigger
+struct Listener {
+    registry: Arc<WorkflowRegistry>,
+    engine: Arc<ExecutionEngine>,
+    devices: Arc<DeviceRegistry>,
+    project: String,
+    device: DeviceTrigger,
+    params: BleParams,
+}
+
+impl Listener {
+    /// Adapter connection, payload decoding and worker of the node (runs until aborted)
+    async fn run(self) {
+        let (event_sender, mut event_receiver) = mpsc::channel::<BleEvent>(256);
+        let (sender, mut receiver) = mpsc::channel::<(String, Value)>(PAYLOAD_BUFFER);
+        let trigger = format!("{}:{}", self.device.workflow_id, self.device.node_id);
+
+        let connection = async {
+            let mut delay = Duration::from_secs(1);
+            loop {
+                let result = match Adapter::open(&self.params.adapter).await {
+                    Ok(adapter) => {
+                        delay = Duration::from_secs(1);
+                        match &self.params.mode {
+                            BleMode::Advertisement => adapter.scan(&self.params.service_uuids, &event_sender).await,
+                            BleMode::Notify { address, characteristic_uuids } => adapter.notify(address, characteristic_uuids, &event_sender).await,
+                        }
+                    }
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok(()) => tracing::warn!("⚠️ BLETrigger {} disconnected (reconnecting in {:?})", trigger, delay),
+                    Err(e) => tracing::warn!("⚠️ BLETrigger {}: {} (retrying in {:?})", trigger, e, delay),
+                }
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+            }
+        };
+
+        let decoder = async {
+            let mut last_payload: HashMap<String, Instant> = HashMap::new();
+            while let Some(event) = event_receiver.recv().await {
+                let payloads: Vec<(String, Value)> = match &event {
+                    BleEvent::Advertisement(advertisement) => {
+                        let now = Instant::now();
+                        let throttled = last_payload.get(&advertisement.address)
+                            .is_some_and(|last| now.duration_since(*last) < self.params.min_interval);
+                        let matching = self.params.matching_payloads(advertisement);
+                        if throttled || matching.is_empty() {
+                            continue;
+                        }
+                        last_payload.insert(advertisement.address.clone(), now);
+                        matching.into_iter()
+                            .filter_map(|payload| self.item(&trigger, &advertisement.address, json!({
+                                "address": advertisement.address,
+                                "name": advertisement.name,
+                                "rssi": advertisement.rssi,
+                                "source": payload.source,
+                                "uuid": payload.uuid,
+                                "manufacturer_id": payload.manufacturer_id,
+                                "data": hex::encode(payload.data),
+                            }), payload.data))
+                            .collect()
+                    }
+                    BleEvent::Notification { address, name, uuid, value } => self.item(&trigger, address, json!({
+                        "address": address,
+                        "name": name,
+                        "rssi": Value::Null,
+                        "source": "notification",
+                        "uuid": uuid,
+                        "manufacturer_id": Value::Null,
+                        "data": hex::encode(value),
+                    }), value).into_iter().collect(),
+                    BleEvent::Disconnected { address } => {
+                        let device_id = self.device.device_id(&Value::Null, address);
+                        self.devices.set_offline(&self.project, [&device_id]);
+                        continue;
+                    }
+                };
+                for (device_id, item) in payloads {
+                    // Device state follows every payload
+                    self.devices.record_message(&self.project, &self.device, &device_id, &item);
+                    if sender.try_send((device_id, item)).is_err() {
+                        tracing::warn!("⚠️ BLETrigger {} is falling behind, payload dropped", trigger);
+                    }
+                }
+            }
+        };
+
+        let worker = async {
+            while let Some((device_id, item)) = receiver.recv().await {
+                self.execute(&device_id, item).await;
+            }
+        };
+
+        tokio::join!(connection, decoder, worker);
+    }
+
+    /// Decoded item and device ID of one payload (None when decoding fails)
+    fn item(&self, trigger: &str, address: &str, envelope: Value, data: &[u8]) -> Option<(String, Value)> {
+        let mut item = match self.params.decoder.decode(data) {
+            Ok(fields) => fields,
+            Err(e) => {
+                tracing::debug!("BLETrigger {} skipped a payload from {}: {}", trigger, address, e);
+                return None;
+            }
+        };
+        item.insert("ble".to_string(), envelope);
+        let item = Value::Object(item);
+        Some((self.device.device_id(&item, address), item))
+    }
+
+    /// Run the workflow for one payload
+    async fn execute(&self, device_id: &str, item: Value) {
+        let (workflow_id, node_id) = (&self.device.workflow_id, &self.device.node_id);
+        let Some(compiled) = self.registry.get_workflow(workflow_id) else {
+            tracing::warn!("⚠️ BLETrigger {}:{} received a payload for an unknown workflow", workflow_id, node_id);
+            return;
+        };
+        if self.engine.maintenance_mode().is_enabled() {
+            tracing::debug!("🚧 BLETrigger {}:{} dropped a payload: maintenance mode", workflow_id, node_id);
+            return;
+        }
+        if compiled.active_snooze().is_some() {
+            tracing::debug!("💤 BLETrigger {}:{} dropped a payload: workflow is snoozed", workflow_id, node_id);
+            return;
+        }
+
+        let mut context = ExecutionContext::from_array_data(
+            workflow_id.clone(), vec![item], compiled.workflow.project.clone());
+        context.metadata.insert("trigger_type".to_string(), json!("ble"));
+        context.metadata.insert("trigger_node_id".to_string(), json!(node_id));
+        context.metadata.insert("device_id".to_string(), json!(device_id));
+
+        tracing::info!("🚀 Executing BLE-triggered workflow: {} ({}, device '{}')", workflow_id, node_id, device_id);
+        if let Err(e) = self.engine.execute_workflow(&compiled, node_id, context).await {
+            tracing::error!("❌ BLE-triggered workflow failed: {} - Error: {}", workflow_id, e);
+        }
+    }
+}
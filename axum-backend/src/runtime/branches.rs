@@ -0,0 +1,614 @@
+//! Branch-aware routing of node outputs within a run
+//!
+//! Every node gets the output of the nodes that feed it, not whatever ran last:
+//! its input is the items arriving on its incoming edges, concatenated in plan
+//! order, so a join waits until every node feeding it has finished. A node is
+//! ready as soon as that happens, which lets the engine run the branches after
+//! a fork concurrently.
+//!
+//! Edges from a node that picked an output port (FeatureFlag) only carry its
+//! output on that port and on port-less edges; a split output (ABSplit) sends
+//! each port's edges that port's items and port-less edges all of them. A node
+//! nothing arrives at is "not taken" when an edge into it was a port the run
+//! didn't take, else "not reached" (its branch stopped early). Outputs still
+//! waiting for a node that isn't ready yet can be spilled to disk.
+
+use crate::runtime::executor::{ExecutionResult, Termination};
+use crate::runtime::spill::{exceeds_threshold, SpilledData};
+use crate::workflow::graph::{ExecutionPlan, PortSplit, OUTPUT_PORT_METADATA_KEY, OUTPUT_SPLIT_METADATA_KEY};
+use anyhow::Result;
+use petgraph::graph::NodeIndex;
+use serde_json::Value;
+use std::{
+    collections::{BTreeSet, HashMap},
+    ops::Range,
+    path::Path,
+    sync::Arc,
+};
+
+/// What a ready node gets from the nodes feeding it
+pub enum NodeInput {
+    /// Run with the arriving items (and the feeding nodes' metadata)
+    Run(ExecutionResult),
+    /// Only fed through ports the run didn't take
+    NotTaken,
+    /// Its branch stopped before it
+    NotReached,
+}
+
+/// Output data of a finished node, in memory or spilled while it waits
+enum HeldData {
+    Memory(Vec<Value>),
+    Spilled(SpilledData),
+}
+
+/// Output of a finished node, held until the nodes it feeds have taken their share
+struct HeldOutput {
+    data: HeldData,
+    metadata: HashMap<String, Value>,
+    termination: Termination,
+    /// Output port the node picked
+    port: Option<String>,
+    /// Items per port of a split output
+    split: Option<Vec<PortSplit>>,
+    /// Edges to nodes of the walk that haven't taken their share yet
+    waiting: usize,
+}
+
+enum Finished {
+    Output(HeldOutput),
+    NotTaken,
+    NotReached,
+}
+
+/// Share of an output that one edge carries
+enum Share {
+    /// All items, or the range of a split port
+    Items(Option<Range<usize>>),
+    NotTaken,
+    NotReached,
+}
+
+impl HeldOutput {
+    fn share(&self, edge_port: Option<&str>) -> Share {
+        if self.termination != Termination::Completed {
+            return Share::NotReached;
+        }
+        if let Some(split) = &self.split {
+            let Some(edge_port) = edge_port else {
+                return Share::Items(None);
+            };
+            let mut start = 0;
+            for PortSplit { port, items } in split {
+                if port == edge_port {
+                    return match items {
+                        0 => Share::NotTaken,
+                        items => Share::Items(Some(start..start + items)),
+                    };
+                }
+                start += items;
+            }
+            return Share::NotTaken;
+        }
+        match (&self.port, edge_port) {
+            (Some(picked), Some(edge_port)) if picked != edge_port => Share::NotTaken,
+            _ => Share::Items(None),
+        }
+    }
+}
+
+/// Routing state of one walk over a plan's steps
+pub struct BranchRouter {
+    plan: Arc<ExecutionPlan>,
+    /// Position of each step in plan order
+    positions: HashMap<NodeIndex, usize>,
+    /// Edges from steps that haven't finished yet, per step
+    unfinished_inputs: HashMap<NodeIndex, usize>,
+    /// Positions of the steps whose inputs are complete
+    ready: BTreeSet<usize>,
+    steps: Vec<NodeIndex>,
+    /// Node whose result started the walk (the trigger, or a streamed node)
+    seed: Option<NodeIndex>,
+    finished: HashMap<NodeIndex, Finished>,
+    /// Input of the steps nothing in the walk feeds (walks without a seed)
+    root_input: Option<ExecutionResult>,
+}
+
+impl BranchRouter {
+    /// Route `steps` (in plan order) starting from `seed`'s result, or with
+    /// `input` going to the steps nothing feeds when there is no seed node
+    pub fn new(plan: Arc<ExecutionPlan>, steps: &[NodeIndex], seed: Option<NodeIndex>, input: ExecutionResult) -> Self {
+        let positions: HashMap<NodeIndex, usize> = steps.iter()
+            .enumerate()
+            .map(|(position, &index)| (index, position))
+            .collect();
+        let unfinished_inputs: HashMap<NodeIndex, usize> = steps.iter()
+            .map(|&index| (index, plan.incoming(index).filter(|(source, _)| positions.contains_key(source)).count()))
+            .collect();
+        let ready = steps.iter()
+            .enumerate()
+            .filter(|(_, index)| unfinished_inputs[index] == 0)
+            .map(|(position, _)| position)
+            .collect();
+        let mut router = Self {
+            plan,
+            positions,
+            unfinished_inputs,
+            ready,
+            steps: steps.to_vec(),
+            seed,
+            finished: HashMap::new(),
+            root_input: None,
+        };
+        match seed {
+            Some(seed) => {
+                let output = router.hold(seed, input);
+                router.finished.insert(seed, Finished::Output(output));
+            }
+            None => router.root_input = Some(input),
+        }
+        router
+    }
+
+    /// Next step whose inputs are complete (in plan order)
+    pub fn next_ready(&mut self) -> Option<NodeIndex> {
+        while let Some(position) = self.ready.pop_first() {
+            let index = self.steps[position];
+            if !self.finished.contains_key(&index) {
+                return Some(index);
+            }
+        }
+        None
+    }
+
+    /// Take a ready node's input from the nodes feeding it
+    ///
+    /// A node that gets nothing is finished as not taken / not reached.
+    pub async fn take_input(&mut self, index: NodeIndex) -> Result<NodeInput> {
+        let edges: Vec<(NodeIndex, Option<String>)> = self.plan.incoming(index)
+            .filter(|(source, _)| self.positions.contains_key(source) || Some(*source) == self.seed)
+            .map(|(source, port)| (source, port.map(str::to_string)))
+            .collect();
+        if edges.is_empty() {
+            // Nodes nothing in the walk feeds get the walk's input (only the start node in practice)
+            return Ok(match self.root_input.take() {
+                Some(input) => NodeInput::Run(input),
+                None => self.skip(index, Finished::NotReached),
+            });
+        }
+
+        let mut edges = edges;
+        edges.sort_by_key(|(source, _)| self.order(*source));
+        let mut input: Option<ExecutionResult> = None;
+        let mut not_taken = false;
+        for (source, port) in edges {
+            let Some(Finished::Output(output)) = self.finished.get_mut(&source) else {
+                not_taken |= matches!(self.finished.get(&source), Some(Finished::NotTaken));
+                continue;
+            };
+            output.waiting = output.waiting.saturating_sub(1);
+            let range = match output.share(port.as_deref()) {
+                Share::Items(range) => range,
+                Share::NotTaken => {
+                    not_taken = true;
+                    continue;
+                }
+                Share::NotReached => continue,
+            };
+            if let HeldData::Spilled(_) = output.data {
+                let HeldData::Spilled(spill) = std::mem::replace(&mut output.data, HeldData::Memory(Vec::new())) else {
+                    unreachable!()
+                };
+                output.data = HeldData::Memory(spill.rehydrate().await?);
+            }
+            let HeldData::Memory(data) = &mut output.data else {
+                unreachable!()
+            };
+            // ZERO-COPY: The last node to take a whole output takes it by move
+            let (items, metadata) = match range {
+                Some(range) => (data[range].to_vec(), output.metadata.clone()),
+                None if output.waiting == 0 => (std::mem::take(data), std::mem::take(&mut output.metadata)),
+                None => (data.clone(), output.metadata.clone()),
+            };
+            match &mut input {
+                Some(input) => {
+                    input.data.extend(items);
+                    input.metadata.extend(metadata);
+                }
+                None => input = Some(ExecutionResult { data: items, metadata, termination: Termination::Completed }),
+            }
+        }
+        Ok(match input {
+            Some(input) => NodeInput::Run(input),
+            None if not_taken => self.skip(index, Finished::NotTaken),
+            None => self.skip(index, Finished::NotReached),
+        })
+    }
+
+    /// Finish a node with its result (or a disabled node with its input)
+    pub fn finish(&mut self, index: NodeIndex, result: ExecutionResult) {
+        let output = self.hold(index, result);
+        self.resolve(index, Finished::Output(output));
+    }
+
+    /// Finish nodes without running them (the steps a streamed node runs per batch)
+    pub fn claim(&mut self, steps: &[NodeIndex]) {
+        for &index in steps {
+            self.finished.insert(index, Finished::NotReached);
+        }
+    }
+
+    /// Whether a finished node's output still waits for nodes that aren't ready yet
+    pub fn is_waiting(&self, index: NodeIndex) -> bool {
+        matches!(self.finished.get(&index), Some(Finished::Output(output)) if output.waiting > 0)
+    }
+
+    /// Spill a waiting output to disk when it serializes to more than `threshold_bytes`
+    pub async fn spill_if_oversized(&mut self, index: NodeIndex, threshold_bytes: usize, project_dir: &Path) -> Result<()> {
+        let Some(Finished::Output(output)) = self.finished.get_mut(&index) else {
+            return Ok(());
+        };
+        let HeldData::Memory(data) = &mut output.data else {
+            return Ok(());
+        };
+        if output.waiting > 0 && exceeds_threshold(data, threshold_bytes) {
+            let spill = SpilledData::write(project_dir, std::mem::take(data)).await?;
+            output.data = HeldData::Spilled(spill);
+        }
+        Ok(())
+    }
+
+    /// Result of the walk: the output of the last node that ran (in plan order)
+    ///
+    /// Without one, it's the walk's own input. When that node completed but
+    /// another branch stopped early, the walk reports the first stop.
+    pub async fn into_result(mut self) -> Result<ExecutionResult> {
+        let last = self.finished.iter()
+            .filter(|(_, finished)| matches!(finished, Finished::Output(_)))
+            .map(|(&index, _)| index)
+            .max_by_key(|&index| self.order(index));
+        let Some(last) = last else {
+            return Ok(self.root_input.take().unwrap_or_else(|| ExecutionResult {
+                data: Vec::new(),
+                metadata: HashMap::new(),
+                termination: Termination::Completed,
+            }));
+        };
+        let first_stop = self.finished.iter()
+            .filter_map(|(&index, finished)| match finished {
+                Finished::Output(output) if output.termination != Termination::Completed => Some((self.order(index), &output.termination)),
+                _ => None,
+            })
+            .min_by_key(|(order, _)| *order)
+            .map(|(_, termination)| termination.clone());
+
+        let Some(Finished::Output(output)) = self.finished.remove(&last) else {
+            unreachable!()
+        };
+        let data = match output.data {
+            HeldData::Memory(data) => data,
+            HeldData::Spilled(spill) => spill.rehydrate().await?,
+        };
+        let termination = match (output.termination, first_stop) {
+            (Termination::Completed, Some(stop)) => stop,
+            (termination, _) => termination,
+        };
+        Ok(ExecutionResult { data, metadata: output.metadata, termination })
+    }
+
+    /// Plan order of a node (the seed comes first)
+    fn order(&self, index: NodeIndex) -> usize {
+        self.positions.get(&index).map_or(0, |position| position + 1)
+    }
+
+    fn hold(&self, index: NodeIndex, mut result: ExecutionResult) -> HeldOutput {
+        let port = result.metadata.remove(OUTPUT_PORT_METADATA_KEY)
+            .and_then(|port| port.as_str().map(str::to_string));
+        let split = result.metadata.remove(OUTPUT_SPLIT_METADATA_KEY)
+            .and_then(|split| serde_json::from_value::<Vec<PortSplit>>(split).ok());
+        let waiting = self.plan.graph.neighbors(index)
+            .filter(|target| self.positions.contains_key(target) && !self.finished.contains_key(target))
+            .count();
+        HeldOutput {
+            data: HeldData::Memory(result.data),
+            metadata: result.metadata,
+            termination: result.termination,
+            port,
+            split,
+            waiting,
+        }
+    }
+
+    fn skip(&mut self, index: NodeIndex, finished: Finished) -> NodeInput {
+        let input = match finished {
+            Finished::NotTaken => NodeInput::NotTaken,
+            _ => NodeInput::NotReached,
+        };
+        self.resolve(index, finished);
+        input
+    }
+
+    /// Record a finished node and mark the nodes it completes the inputs of as ready
+    fn resolve(&mut self, index: NodeIndex, finished: Finished) {
+        self.finished.insert(index, finished);
+        let targets: Vec<NodeIndex> = self.plan.graph.neighbors(index).collect();
+        for target in targets {
+            let Some(unfinished) = self.unfinished_inputs.get_mut(&target) else {
+                continue;
+            };
+            *unfinished = unfinished.saturating_sub(1);
+            if *unfinished == 0 {
+                self.ready.insert(self.positions[&target]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workflow::types::Workflow;
+    use serde_json::json;
+
+    /// Plan of a workflow whose "hook" Webhook feeds the given nodes
+    fn plan(nodes: Value, edges: Value) -> Arc<ExecutionPlan> {
+        let mut all_nodes = vec![json!({ "id": "hook", "node_type": "Webhook", "params": { "path": "/test" } })];
+        all_nodes.extend(nodes.as_array().unwrap().iter().cloned());
+        let workflow: Workflow = serde_json::from_value(json!({
+            "id": "wf-branches",
+            "name": "Branches",
+            "nodes": all_nodes,
+            "edges": edges,
+        })).unwrap();
+        Arc::new(ExecutionPlan::build(&workflow).unwrap())
+    }
+
+    fn logic(id: &str) -> Value {
+        json!({ "id": id, "node_type": "FunLogic", "params": { "script": "return data" } })
+    }
+
+    fn index(plan: &ExecutionPlan, id: &str) -> NodeIndex {
+        plan.node_id_to_index[id]
+    }
+
+    /// Router over the steps after "hook", seeded with its output
+    fn router(plan: &Arc<ExecutionPlan>) -> BranchRouter {
+        let steps = plan.execution_order("hook").unwrap().into_owned();
+        BranchRouter::new(Arc::clone(plan), &steps, Some(index(plan, "hook")), output(json!([{ "n": 0 }])))
+    }
+
+    fn output(data: Value) -> ExecutionResult {
+        ExecutionResult {
+            data: data.as_array().unwrap().clone(),
+            metadata: HashMap::new(),
+            termination: Termination::Completed,
+        }
+    }
+
+    fn with_metadata(mut result: ExecutionResult, key: &str, value: Value) -> ExecutionResult {
+        result.metadata.insert(key.to_string(), value);
+        result
+    }
+
+    fn stopped(data: Value, node_id: &str) -> ExecutionResult {
+        ExecutionResult { termination: Termination::stopped(node_id, "test stop"), ..output(data) }
+    }
+
+    fn ready(router: &mut BranchRouter, plan: &ExecutionPlan) -> Vec<String> {
+        std::iter::from_fn(|| router.next_ready()).map(|index| plan.graph[index].id.clone()).collect()
+    }
+
+    async fn input(router: &mut BranchRouter, plan: &ExecutionPlan, id: &str) -> Value {
+        match router.take_input(index(plan, id)).await.unwrap() {
+            NodeInput::Run(input) => Value::Array(input.data),
+            NodeInput::NotTaken => json!("not_taken"),
+            NodeInput::NotReached => json!("not_reached"),
+        }
+    }
+
+    fn diamond() -> Arc<ExecutionPlan> {
+        plan(
+            json!([logic("a"), logic("b"), logic("join")]),
+            json!([
+                { "from": "hook", "to": "a" }, { "from": "hook", "to": "b" },
+                { "from": "a", "to": "join" }, { "from": "b", "to": "join" }
+            ]),
+        )
+    }
+
+    #[tokio::test]
+    async fn fork_branches_get_the_same_input() {
+        let plan = diamond();
+        let mut router = router(&plan);
+        let mut forked = ready(&mut router, &plan);
+        forked.sort();
+        assert_eq!(forked, ["a", "b"]);
+        assert_eq!(input(&mut router, &plan, "a").await, json!([{ "n": 0 }]));
+        assert_eq!(input(&mut router, &plan, "b").await, json!([{ "n": 0 }]));
+    }
+
+    #[tokio::test]
+    async fn join_waits_for_every_branch_and_takes_inputs_in_plan_order() {
+        let plan = diamond();
+        let mut router = router(&plan);
+        let branches = ready(&mut router, &plan);
+        for id in &branches {
+            input(&mut router, &plan, id).await;
+        }
+
+        // Finish the branches in reverse plan order
+        let (first, second) = (&branches[0], &branches[1]);
+        router.finish(index(&plan, second), output(json!([{ "from": second }])));
+        assert!(ready(&mut router, &plan).is_empty());
+        router.finish(index(&plan, first), output(json!([{ "from": first }])));
+        assert_eq!(ready(&mut router, &plan), ["join"]);
+
+        assert_eq!(input(&mut router, &plan, "join").await, json!([{ "from": first }, { "from": second }]));
+    }
+
+    #[tokio::test]
+    async fn picked_port_only_feeds_its_edges_and_portless_ones() {
+        let plan = plan(
+            json!([
+                { "id": "flag", "node_type": "FeatureFlag", "params": { "flag": "beta" } },
+                logic("on"), logic("off"), logic("always")
+            ]),
+            json!([
+                { "from": "hook", "to": "flag" },
+                { "from": "flag", "to": "on", "port": "true" },
+                { "from": "flag", "to": "off", "port": "false" },
+                { "from": "flag", "to": "always" }
+            ]),
+        );
+        let mut router = router(&plan);
+        assert_eq!(ready(&mut router, &plan), ["flag"]);
+        input(&mut router, &plan, "flag").await;
+        router.finish(index(&plan, "flag"), with_metadata(output(json!([1])), OUTPUT_PORT_METADATA_KEY, json!("true")));
+
+        assert_eq!(input(&mut router, &plan, "on").await, json!([1]));
+        assert_eq!(input(&mut router, &plan, "off").await, json!("not_taken"));
+        assert_eq!(input(&mut router, &plan, "always").await, json!([1]));
+    }
+
+    #[tokio::test]
+    async fn split_output_sends_each_port_its_items() {
+        let plan = plan(
+            json!([
+                { "id": "split", "node_type": "ABSplit", "params": { "variants": [
+                    { "name": "control", "weight": 50 }, { "name": "blue", "weight": 50 }, { "name": "red", "weight": 0 }
+                ] } },
+                logic("control"), logic("blue"), logic("red"), logic("all")
+            ]),
+            json!([
+                { "from": "hook", "to": "split" },
+                { "from": "split", "to": "control", "port": "control" },
+                { "from": "split", "to": "blue", "port": "blue" },
+                { "from": "split", "to": "red", "port": "red" },
+                { "from": "split", "to": "all" }
+            ]),
+        );
+        let mut router = router(&plan);
+        ready(&mut router, &plan);
+        input(&mut router, &plan, "split").await;
+        let split = json!([{ "port": "control", "items": 2 }, { "port": "blue", "items": 1 }, { "port": "red", "items": 0 }]);
+        router.finish(index(&plan, "split"), with_metadata(output(json!(["c1", "c2", "b1"])), OUTPUT_SPLIT_METADATA_KEY, split));
+
+        assert_eq!(input(&mut router, &plan, "control").await, json!(["c1", "c2"]));
+        assert_eq!(input(&mut router, &plan, "blue").await, json!(["b1"]));
+        assert_eq!(input(&mut router, &plan, "red").await, json!("not_taken"));
+        assert_eq!(input(&mut router, &plan, "all").await, json!(["c1", "c2", "b1"]));
+    }
+
+    #[tokio::test]
+    async fn not_taken_and_not_reached_propagate_downstream() {
+        let plan = plan(
+            json!([
+                { "id": "flag", "node_type": "FeatureFlag", "params": { "flag": "beta" } },
+                logic("off"), logic("after_off"), logic("stopper"), logic("after_stop")
+            ]),
+            json!([
+                { "from": "hook", "to": "flag" },
+                { "from": "flag", "to": "off", "port": "false" },
+                { "from": "off", "to": "after_off" },
+                { "from": "hook", "to": "stopper" },
+                { "from": "stopper", "to": "after_stop" }
+            ]),
+        );
+        let mut router = router(&plan);
+        ready(&mut router, &plan);
+        input(&mut router, &plan, "flag").await;
+        input(&mut router, &plan, "stopper").await;
+        router.finish(index(&plan, "flag"), with_metadata(output(json!([1])), OUTPUT_PORT_METADATA_KEY, json!("true")));
+        router.finish(index(&plan, "stopper"), stopped(json!([]), "stopper"));
+
+        assert_eq!(input(&mut router, &plan, "off").await, json!("not_taken"));
+        assert_eq!(input(&mut router, &plan, "after_off").await, json!("not_taken"));
+        assert_eq!(input(&mut router, &plan, "after_stop").await, json!("not_reached"));
+    }
+
+    #[tokio::test]
+    async fn result_is_the_last_node_with_the_first_stop() {
+        let plan = plan(
+            json!([logic("a"), logic("b"), logic("c")]),
+            json!([{ "from": "hook", "to": "a" }, { "from": "hook", "to": "b" }, { "from": "hook", "to": "c" }]),
+        );
+        let steps = plan.execution_order("hook").unwrap().into_owned();
+        let ids: Vec<&str> = steps.iter().map(|&index| plan.graph[index].id.as_str()).collect();
+        let mut router = router(&plan);
+        ready(&mut router, &plan);
+        for id in &ids {
+            input(&mut router, &plan, id).await;
+        }
+
+        // The two earlier branches stop, the last one in plan order completes
+        router.finish(steps[1], stopped(json!([]), ids[1]));
+        router.finish(steps[0], stopped(json!([]), ids[0]));
+        router.finish(steps[2], output(json!([{ "last": true }])));
+
+        let result = router.into_result().await.unwrap();
+        assert_eq!(result.data, vec![json!({ "last": true })]);
+        assert_eq!(result.termination, Termination::stopped(ids[0], "test stop"));
+    }
+
+    #[tokio::test]
+    async fn result_without_any_node_is_the_walk_input() {
+        let plan = plan(json!([logic("a")]), json!([{ "from": "hook", "to": "a" }]));
+        let steps = plan.execution_order("hook").unwrap().into_owned();
+        let router = BranchRouter::new(Arc::clone(&plan), &steps, None, output(json!([{ "n": 1 }])));
+        assert_eq!(router.into_result().await.unwrap().data, vec![json!({ "n": 1 })]);
+    }
+
+    #[tokio::test]
+    async fn claimed_steps_are_not_started() {
+        let plan = plan(
+            json!([logic("stream"), logic("per_batch"), logic("after")]),
+            json!([{ "from": "hook", "to": "stream" }, { "from": "stream", "to": "per_batch" }, { "from": "per_batch", "to": "after" }]),
+        );
+        let mut router = router(&plan);
+        assert_eq!(ready(&mut router, &plan), ["stream"]);
+        input(&mut router, &plan, "stream").await;
+        router.claim(&[index(&plan, "per_batch"), index(&plan, "after")]);
+        router.finish(index(&plan, "stream"), output(json!([{ "batch": 3 }])));
+
+        assert!(ready(&mut router, &plan).is_empty());
+        assert_eq!(router.into_result().await.unwrap().data, vec![json!({ "batch": 3 })]);
+    }
+
+    #[tokio::test]
+    async fn waiting_output_is_spilled_and_rehydrated() {
+        let plan = diamond();
+        let project_dir = std::env::temp_dir().join(format!("mechaway-branches-{}", uuid::Uuid::new_v4()));
+        let mut router = router(&plan);
+        let branches = ready(&mut router, &plan);
+        for id in &branches {
+            input(&mut router, &plan, id).await;
+        }
+        let (first, second) = (index(&plan, &branches[0]), index(&plan, &branches[1]));
+
+        let big = json!([{ "payload": "x".repeat(1024) }]);
+        router.finish(first, output(big.clone()));
+        assert!(router.is_waiting(first));
+        router.spill_if_oversized(first, 16, &project_dir).await.unwrap();
+        assert_eq!(std::fs::read_dir(project_dir.join("spill")).unwrap().count(), 1);
+
+        router.finish(second, output(json!([])));
+        assert_eq!(ready(&mut router, &plan), ["join"]);
+        assert_eq!(input(&mut router, &plan, "join").await, big);
+        assert!(!router.is_waiting(first));
+        assert_eq!(std::fs::read_dir(project_dir.join("spill")).unwrap().count(), 0);
+        std::fs::remove_dir_all(&project_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn small_outputs_stay_in_memory() {
+        let plan = diamond();
+        let project_dir = std::env::temp_dir().join(format!("mechaway-branches-{}", uuid::Uuid::new_v4()));
+        let mut router = router(&plan);
+        let branches = ready(&mut router, &plan);
+        input(&mut router, &plan, &branches[0]).await;
+        let first = index(&plan, &branches[0]);
+        router.finish(first, output(json!([1])));
+        router.spill_if_oversized(first, 1024, &project_dir).await.unwrap();
+        assert!(!project_dir.exists());
+    }
+}
@@ -0,0 +1,197 @@
+//! Memory and CPU budgets of workflow runs
+//!
+//! Soft limits that keep one pathological workflow from hogging a shared
+//! instance. A run that exceeds one fails with a `ResourceExceeded` error
+//! naming the node it happened in:
+//!
+//! - memory: the serialized size of any node's output, and the allocations of
+//!   FunLogic Lua VMs (capped at the same size)
+//! - CPU: cumulative time the run's nodes spend being polled on runtime threads
+//!   (waiting on I/O doesn't count); FunLogic scripts are also checked while they run
+//!
+//! Server-wide defaults come from MECHAWAY_MAX_RUN_MEMORY_BYTES and
+//! MECHAWAY_MAX_RUN_CPU_MS (0 = unlimited); a workflow can tighten them with
+//! `"settings": { "max_memory_bytes": 67108864, "max_cpu_ms": 5000 }`.
+//! Sub-workflows draw from the calling run's budget.
+
+use crate::runtime::spill::exceeds_threshold;
+use crate::workflow::types::WorkflowSettings;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::Poll,
+    time::{Duration, Instant},
+};
+
+/// Lua instructions between CPU checks of a running FunLogic script
+const LUA_CHECK_INSTRUCTIONS: u32 = 10_000;
+
+/// Server-wide or per-workflow limits (0 = unlimited)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    pub max_memory_bytes: usize,
+    pub max_cpu_ms: u64,
+}
+
+impl ResourceLimits {
+    /// Limits of a workflow: its settings can lower the server's limits, never raise them
+    pub fn for_workflow(&self, settings: &WorkflowSettings) -> Self {
+        fn tighter<T: Copy + Ord + Default>(server: T, workflow: Option<T>) -> T {
+            match workflow.filter(|limit| *limit != T::default()) {
+                Some(limit) if server == T::default() => limit,
+                Some(limit) => limit.min(server),
+                None => server,
+            }
+        }
+        Self {
+            max_memory_bytes: tighter(self.max_memory_bytes, settings.max_memory_bytes),
+            max_cpu_ms: tighter(self.max_cpu_ms, settings.max_cpu_ms),
+        }
+    }
+
+    fn is_unlimited(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Budgeted resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Resource {
+    Memory,
+    Cpu,
+}
+
+/// Error of a run that exceeded its budget
+#[derive(Debug, Clone)]
+pub struct ResourceExceeded {
+    pub node_id: String,
+    pub resource: Resource,
+    /// Bytes (memory) or milliseconds (CPU)
+    pub limit: u64,
+}
+
+impl ResourceExceeded {
+    /// JSON body for API responses
+    pub fn to_json(&self) -> Value {
+        json!({
+            "error": "resource_exceeded",
+            "message": self.to_string(),
+            "node_id": self.node_id,
+            "resource": self.resource,
+            "limit": self.limit,
+        })
+    }
+}
+
+impl std::fmt::Display for ResourceExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.resource {
+            Resource::Memory => write!(f, "ResourceExceeded: node '{}' exceeded the run's memory budget of {} bytes", self.node_id, self.limit),
+            Resource::Cpu => write!(f, "ResourceExceeded: node '{}' exceeded the run's CPU budget of {} ms", self.node_id, self.limit),
+        }
+    }
+}
+
+impl std::error::Error for ResourceExceeded {}
+
+#[derive(Debug)]
+struct BudgetState {
+    limits: ResourceLimits,
+    cpu_used_us: AtomicU64,
+}
+
+/// Budget of one run, shared by its nodes and sub-workflows (no-op when unset)
+#[derive(Debug, Clone, Default)]
+pub struct RunBudget(Option<Arc<BudgetState>>);
+
+impl RunBudget {
+    /// Budget with the given limits (unset when both are unlimited)
+    pub fn new(limits: ResourceLimits) -> Self {
+        match limits.is_unlimited() {
+            true => Self(None),
+            false => Self(Some(Arc::new(BudgetState { limits, cpu_used_us: AtomicU64::new(0) }))),
+        }
+    }
+
+    /// Whether a budget applies (set once per top-level run)
+    pub fn is_set(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// CPU time charged so far
+    pub fn cpu_used(&self) -> Duration {
+        self.0.as_ref().map_or(Duration::ZERO, |state| Duration::from_micros(state.cpu_used_us.load(Ordering::Relaxed)))
+    }
+
+    /// Run a node's future, charging the time spent polling it; fails once the CPU budget is spent
+    pub async fn metered<T>(&self, node_id: &str, future: impl Future<Output = anyhow::Result<T>>) -> anyhow::Result<T> {
+        let Some(state) = self.0.as_ref().filter(|state| state.limits.max_cpu_ms > 0) else {
+            return future.await;
+        };
+        let mut future = std::pin::pin!(future);
+        std::future::poll_fn(|cx| {
+            let started = Instant::now();
+            let poll = future.as_mut().poll(cx);
+            let polled_us = started.elapsed().as_micros() as u64;
+            let used_us = state.cpu_used_us.fetch_add(polled_us, Ordering::Relaxed) + polled_us;
+            // Dropping the future aborts the node at its next await point
+            if used_us > state.limits.max_cpu_ms * 1000 {
+                return Poll::Ready(Err(anyhow::Error::new(exceeded(node_id, Resource::Cpu, state.limits.max_cpu_ms))));
+            }
+            poll
+        }).await
+    }
+
+    /// Fail when a node's output serializes to more than the memory budget
+    pub fn check_output(&self, node_id: &str, data: &[Value]) -> Result<(), ResourceExceeded> {
+        match self.0.as_ref().map(|state| state.limits.max_memory_bytes) {
+            Some(limit) if exceeds_threshold(data, limit) => Err(exceeded(node_id, Resource::Memory, limit as u64)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Cap a FunLogic VM's allocations at the memory budget and stop it when the CPU budget runs out
+    pub fn limit_lua(&self, lua: &mlua::Lua, node_id: &str) -> anyhow::Result<()> {
+        let Some(state) = &self.0 else {
+            return Ok(());
+        };
+        if state.limits.max_memory_bytes > 0 {
+            lua.set_memory_limit(state.limits.max_memory_bytes)
+                .map_err(|e| anyhow::anyhow!("Failed to limit Lua memory: {}", e))?;
+        }
+        if state.limits.max_cpu_ms > 0 {
+            let limit_ms = state.limits.max_cpu_ms;
+            let remaining = Duration::from_millis(limit_ms).saturating_sub(self.cpu_used());
+            let deadline = Instant::now() + remaining;
+            let node_id = node_id.to_string();
+            lua.set_hook(mlua::HookTriggers::new().every_nth_instruction(LUA_CHECK_INSTRUCTIONS), move |_, _| {
+                match Instant::now() > deadline {
+                    true => Err(mlua::Error::external(exceeded(&node_id, Resource::Cpu, limit_ms))),
+                    false => Ok(mlua::VmState::Continue),
+                }
+            }).map_err(|e| anyhow::anyhow!("Failed to limit Lua CPU time: {}", e))?;
+        }
+        Ok(())
+    }
+
+    /// Budget error behind a failed Lua call, if the budget stopped it
+    pub fn lua_error(&self, node_id: &str, error: &mlua::Error) -> Option<ResourceExceeded> {
+        let state = self.0.as_ref()?;
+        if let Some(exceeded) = error.chain().find_map(|cause| cause.downcast_ref::<ResourceExceeded>()) {
+            return Some(exceeded.clone());
+        }
+        error.chain()
+            .any(|cause| matches!(cause.downcast_ref::<mlua::Error>(), Some(mlua::Error::MemoryError(_))))
+            .then(|| exceeded(node_id, Resource::Memory, state.limits.max_memory_bytes as u64))
+    }
+}
+
+fn exceeded(node_id: &str, resource: Resource, limit: u64) -> ResourceExceeded {
+    ResourceExceeded { node_id: node_id.to_string(), resource, limit }
+}
@@ -0,0 +1,615 @@
+//! CalendarEvent node (iCalendar / .ics)
+//! 
+//! Creates invites from input items or parses incoming calendar data:
+//! 
+//! { "operation": "create", "method": "REQUEST", "summary": "Onboarding call with {{$json.name}}",
+//!   "start": "$json.slot_start", "duration_minutes": 30, "timezone": "Europe/Berlin",
+//!   "organizer": { "email": "sales@example.com", "name": "Sales" }, "attendees": ["$json.email"] }
+//! { "operation": "parse", "ics": "$json.attachment" }
+//! 
+//! Create params render per input item like Mock templates and output one item
+//! with the .ics text, file name and content type, ready to attach to an email.
+//! Times are RFC 3339, Unix seconds, dates ("2025-03-01" = all day) or local
+//! times read in `timezone`; everything except dates and floating times is
+//! written as UTC. Parse outputs one item per VEVENT with times normalized the
+//! same way (TZID times are converted when the zone is a known IANA name).
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde_json::{json, Map, Value};
+
+/// PRODID written into generated calendars
+const PRODID: &str = "-//mechaway//CalendarEvent//EN";
+
+/// Lines are folded at 75 octets (RFC 5545 3.1)
+const MAX_LINE_OCTETS: usize = 75;
+
+/// CalendarEvent operation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CalendarOperation {
+    Create,
+    Parse,
+}
+
+/// Parsed CalendarEvent node params (templates still unrendered)
+#[derive(Debug, Clone)]
+pub struct CalendarParams {
+    pub operation: CalendarOperation,
+    /// iTIP method of created calendars (REQUEST, PUBLISH, CANCEL, REPLY)
+    pub method: String,
+    /// Zone for local (offset-less) times of created events
+    pub timezone: Option<Tz>,
+    /// Create: event fields; parse: { "ics": ... }. Rendered per input item
+    pub templates: Value,
+}
+
+impl CalendarParams {
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        let operation = match params.get("operation").and_then(|o| o.as_str()) {
+            Some("create") => CalendarOperation::Create,
+            Some("parse") => CalendarOperation::Parse,
+            Some(other) => return Err(anyhow::anyhow!("Unknown calendar operation '{}' (create, parse)", other)),
+            None => return Err(anyhow::anyhow!("Missing 'operation' parameter")),
+        };
+
+        let method = params.get("method").and_then(|m| m.as_str()).unwrap_or("REQUEST").to_ascii_uppercase();
+        if !["REQUEST", "PUBLISH", "CANCEL", "REPLY"].contains(&method.as_str()) {
+            return Err(anyhow::anyhow!("'method' must be REQUEST, PUBLISH, CANCEL or REPLY"));
+        }
+        let timezone = params.get("timezone").and_then(|t| t.as_str())
+            .map(|name| name.parse::<Tz>().map_err(|_| anyhow::anyhow!("Unknown timezone '{}'", name)))
+            .transpose()?;
+
+        let fields: &[&str] = match operation {
+            CalendarOperation::Create => &[
+                "uid", "summary", "description", "location", "url", "start", "end", "duration_minutes",
+                "organizer", "attendees", "status", "sequence", "rrule", "alarm_minutes", "filename",
+            ],
+            CalendarOperation::Parse => &["ics"],
+        };
+        let required = match operation {
+            CalendarOperation::Create => ["summary", "start"].as_slice(),
+            CalendarOperation::Parse => ["ics"].as_slice(),
+        };
+        if let Some(missing) = required.iter().find(|key| params.get(**key).is_none_or(|v| v.is_null())) {
+            return Err(anyhow::anyhow!("Missing '{}' parameter", missing));
+        }
+
+        let mut templates = Map::new();
+        for key in fields {
+            if let Some(value) = params.get(*key).filter(|v| !v.is_null()) {
+                templates.insert(key.to_string(), value.clone());
+            }
+        }
+
+        Ok(Self { operation, method, timezone, templates: Value::Object(templates) })
+    }
+
+    /// Build a calendar from the rendered event fields
+    pub fn create(&self, event: &Value) -> Result<Value> {
+        let text = |key: &str| event.get(key).and_then(|v| match v {
+            Value::String(s) if !s.is_empty() => Some(s.clone()),
+            Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        });
+        let number = |key: &str| event.get(key).and_then(|v| v.as_i64().or_else(|| v.as_str()?.trim().parse().ok()));
+
+        let summary = text("summary").ok_or_else(|| anyhow::anyhow!("'summary' rendered empty"))?;
+        let start = IcsTime::from_value(event.get("start").unwrap_or(&Value::Null), self.timezone)
+            .map_err(|e| anyhow::anyhow!("'start': {}", e))?;
+        let end = match event.get("end").filter(|v| !v.is_null() && v.as_str() != Some("")) {
+            Some(end) => IcsTime::from_value(end, self.timezone).map_err(|e| anyhow::anyhow!("'end': {}", e))?,
+            None => start.default_end(number("duration_minutes").unwrap_or(60)),
+        };
+        if !start.is_before(&end) {
+            return Err(anyhow::anyhow!("'end' must be after 'start' and of the same kind (date or time)"));
+        }
+        let uid = text("uid").unwrap_or_else(|| format!("{}@mechaway", uuid::Uuid::new_v4()));
+        let status = text("status").map(|s| s.to_ascii_uppercase())
+            .or_else(|| (self.method == "CANCEL").then(|| "CANCELLED".to_string()));
+        if let Some(status) = &status {
+            if !["TENTATIVE", "CONFIRMED", "CANCELLED"].contains(&status.as_str()) {
+                return Err(anyhow::anyhow!("'status' must be TENTATIVE, CONFIRMED or CANCELLED"));
+            }
+        }
+        let organizer = event.get("organizer").map(Person::from_value).transpose()
+            .map_err(|e| anyhow::anyhow!("'organizer': {}", e))?.flatten();
+        let attendees = Person::list(event.get("attendees").unwrap_or(&Value::Null))
+            .map_err(|e| anyhow::anyhow!("'attendees': {}", e))?;
+        if self.method == "REQUEST" && organizer.is_none() && !attendees.is_empty() {
+            return Err(anyhow::anyhow!("Invitations with attendees need an 'organizer'"));
+        }
+
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_string(),
+            "VERSION:2.0".to_string(),
+            format!("PRODID:{}", PRODID),
+            "CALSCALE:GREGORIAN".to_string(),
+            format!("METHOD:{}", self.method),
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{}", escape_text(&uid)),
+            format!("DTSTAMP:{}", Utc::now().format("%Y%m%dT%H%M%SZ")),
+            format!("DTSTART{}", start.to_ics()),
+            format!("DTEND{}", end.to_ics()),
+            format!("SUMMARY:{}", escape_text(&summary)),
+        ];
+        for (property, key) in [("DESCRIPTION", "description"), ("LOCATION", "location")] {
+            if let Some(value) = text(key) {
+                lines.push(format!("{}:{}", property, escape_text(&value)));
+            }
+        }
+        if let Some(url) = text("url") {
+            lines.push(format!("URL:{}", strip_controls(&url)));
+        }
+        if let Some(status) = &status {
+            lines.push(format!("STATUS:{}", status));
+        }
+        lines.push(format!("SEQUENCE:{}", number("sequence").unwrap_or(0).max(0)));
+        if let Some(rrule) = text("rrule") {
+            let rrule = rrule.strip_prefix("RRULE:").unwrap_or(&rrule);
+            if !rrule.starts_with("FREQ=") || rrule.chars().any(|c| c.is_control() || c == ':') {
+                return Err(anyhow::anyhow!("'rrule' must be an RRULE value like FREQ=WEEKLY;BYDAY=MO"));
+            }
+            lines.push(format!("RRULE:{}", rrule));
+        }
+        if let Some(organizer) = &organizer {
+            lines.push(format!("ORGANIZER{}", organizer.to_ics(false)));
+        }
+        for attendee in &attendees {
+            lines.push(format!("ATTENDEE{}", attendee.to_ics(true)));
+        }
+        if let Some(minutes) = number("alarm_minutes").filter(|m| *m >= 0) {
+            lines.extend([
+                "BEGIN:VALARM".to_string(),
+                "ACTION:DISPLAY".to_string(),
+                format!("DESCRIPTION:{}", escape_text(&summary)),
+                format!("TRIGGER:-PT{}M", minutes),
+                "END:VALARM".to_string(),
+            ]);
+        }
+        lines.extend(["END:VEVENT".to_string(), "END:VCALENDAR".to_string()]);
+
+        let ics: String = lines.iter().map(|line| fold_line(line)).collect();
+        Ok(json!({
+            "uid": uid,
+            "method": self.method,
+            "summary": summary,
+            "start": start.to_json()["time"],
+            "end": end.to_json()["time"],
+            "attendees": attendees.iter().map(|a| a.email.as_str()).collect::<Vec<_>>(),
+            "ics": ics,
+            "filename": text("filename").unwrap_or_else(|| "invite.ics".to_string()),
+            "content_type": format!("text/calendar; charset=utf-8; method={}", self.method),
+        }))
+    }
+}
+
+/// Parse calendar text into one object per VEVENT
+pub fn parse_ics(ics: &str) -> Result<Vec<Value>> {
+    let lines = unfold(ics);
+    if !lines.first().is_some_and(|line| line.eq_ignore_ascii_case("BEGIN:VCALENDAR")) {
+        return Err(anyhow::anyhow!("Input is not iCalendar data (no BEGIN:VCALENDAR)"));
+    }
+
+    let mut method = None;
+    let mut components: Vec<String> = Vec::new();
+    let mut events = Vec::new();
+    let mut current: Option<Vec<Property>> = None;
+    for line in &lines {
+        let Some(property) = Property::parse(line) else { continue };
+        match property.name.as_str() {
+            "BEGIN" => {
+                let component = property.value.to_ascii_uppercase();
+                if component == "VEVENT" {
+                    current = Some(Vec::new());
+                }
+                components.push(component);
+            }
+            "END" => {
+                let closed = components.pop();
+                if closed.as_deref() == Some("VEVENT") {
+                    events.extend(current.take());
+                }
+            }
+            // Only direct VEVENT properties (not those of nested VALARMs)
+            _ if components.last().map(String::as_str) == Some("VEVENT") => {
+                if let Some(properties) = current.as_mut() {
+                    properties.push(property);
+                }
+            }
+            "METHOD" if components.last().map(String::as_str) == Some("VCALENDAR") => {
+                method = Some(property.value.to_ascii_uppercase());
+            }
+            _ => {}
+        }
+    }
+
+    events.iter().map(|properties| event_json(properties, method.as_deref())).collect()
+}
+
+fn event_json(properties: &[Property], method: Option<&str>) -> Result<Value> {
+    let find = |name: &str| properties.iter().find(|p| p.name == name);
+    let text = |name: &str| find(name).map(|p| unescape_text(&p.value));
+
+    let start = find("DTSTART").map(parse_ics_time).transpose()?;
+    let end = match (find("DTEND").or_else(|| find("DUE")), &start) {
+        (Some(end), _) => Some(parse_ics_time(end)?),
+        (None, Some(start)) => match find("DURATION").and_then(|d| parse_duration(&d.value)) {
+            Some(duration) => Some(start.add(duration)),
+            // RFC 5545: a date-only event without end lasts one day
+            None => Some(start.default_end(0)),
+        },
+        (None, None) => None,
+    };
+    let (start_json, end_json) = (start.as_ref().map(IcsTime::to_json), end.as_ref().map(IcsTime::to_json));
+
+    Ok(json!({
+        "method": method,
+        "uid": text("UID"),
+        "summary": text("SUMMARY"),
+        "description": text("DESCRIPTION"),
+        "location": text("LOCATION"),
+        "url": find("URL").map(|p| p.value.clone()),
+        "status": find("STATUS").map(|p| p.value.to_ascii_uppercase()),
+        "sequence": find("SEQUENCE").and_then(|p| p.value.trim().parse::<i64>().ok()).unwrap_or(0),
+        "start": start_json.as_ref().map(|t| t["time"].clone()),
+        "end": end_json.as_ref().map(|t| t["time"].clone()),
+        "all_day": matches!(start, Some(IcsTime::Date(_))),
+        "timezone": start_json.as_ref().and_then(|t| t.get("timezone").cloned()),
+        "rrule": find("RRULE").map(|p| p.value.clone()),
+        "recurrence_id": find("RECURRENCE-ID").map(parse_ics_time).transpose()?.map(|t| t.to_json()["time"].clone()),
+        "organizer": find("ORGANIZER").map(Person::from_property),
+        "attendees": properties.iter().filter(|p| p.name == "ATTENDEE").map(Person::from_property).collect::<Vec<_>>(),
+    }))
+}
+
+/// A point in time as iCalendar knows it
+#[derive(Debug, Clone, PartialEq)]
+enum IcsTime {
+    /// All-day (VALUE=DATE)
+    Date(NaiveDate),
+    Utc(DateTime<Utc>),
+    /// Floating local time (no zone)
+    Floating(NaiveDateTime),
+    /// Local time in a zone we can't resolve (e.g. Windows zone names)
+    Zoned(NaiveDateTime, String),
+}
+
+impl IcsTime {
+    /// Read a rendered node param (RFC 3339, Unix seconds, date, local time)
+    fn from_value(value: &Value, timezone: Option<Tz>) -> Result<Self> {
+        let text = match value {
+            Value::Number(n) => {
+                let seconds = n.as_i64().ok_or_else(|| anyhow::anyhow!("invalid Unix timestamp {}", n))?;
+                return Utc.timestamp_opt(seconds, 0).single().map(IcsTime::Utc)
+                    .ok_or_else(|| anyhow::anyhow!("invalid Unix timestamp {}", seconds));
+            }
+            Value::String(s) if !s.trim().is_empty() => s.trim(),
+            _ => return Err(anyhow::anyhow!("expected a date or time")),
+        };
+        if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+            return Ok(IcsTime::Utc(time.with_timezone(&Utc)));
+        }
+        if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+            return Ok(IcsTime::Date(date));
+        }
+        let local = ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"].iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+            .ok_or_else(|| anyhow::anyhow!("'{}' is not an RFC 3339 time, date or local time", text))?;
+        match timezone {
+            Some(tz) => local_to_utc(local, tz)
+                .ok_or_else(|| anyhow::anyhow!("'{}' does not exist in {}", text, tz)),
+            None => Ok(IcsTime::Floating(local)),
+        }
+    }
+
+    /// End of an event without explicit end (dates: next day)
+    fn default_end(&self, minutes: i64) -> Self {
+        match self {
+            IcsTime::Date(date) => IcsTime::Date(*date + Duration::days(1)),
+            _ => self.add(Duration::minutes(minutes)),
+        }
+    }
+
+    fn add(&self, duration: Duration) -> Self {
+        match self {
+            IcsTime::Date(date) => IcsTime::Date(*date + Duration::days(duration.num_days())),
+            IcsTime::Utc(time) => IcsTime::Utc(*time + duration),
+            IcsTime::Floating(time) => IcsTime::Floating(*time + duration),
+            IcsTime::Zoned(time, zone) => IcsTime::Zoned(*time + duration, zone.clone()),
+        }
+    }
+
+    fn is_before(&self, other: &Self) -> bool {
+        match (self, other) {
+            (IcsTime::Date(a), IcsTime::Date(b)) => a < b,
+            (IcsTime::Utc(a), IcsTime::Utc(b)) => a < b,
+            (IcsTime::Floating(a), IcsTime::Floating(b)) => a < b,
+            _ => false,
+        }
+    }
+
+    /// Property parameters and value, e.g. ";VALUE=DATE:20250301"
+    fn to_ics(&self) -> String {
+        match self {
+            IcsTime::Date(date) => format!(";VALUE=DATE:{}", date.format("%Y%m%d")),
+            IcsTime::Utc(time) => format!(":{}", time.format("%Y%m%dT%H%M%SZ")),
+            IcsTime::Floating(time) => format!(":{}", time.format("%Y%m%dT%H%M%S")),
+            IcsTime::Zoned(time, zone) => format!(";TZID={}:{}", zone, time.format("%Y%m%dT%H%M%S")),
+        }
+    }
+
+    /// { "time": ..., "timezone": ... } for node output
+    fn to_json(&self) -> Value {
+        match self {
+            IcsTime::Date(date) => json!({ "time": date.format("%Y-%m-%d").to_string() }),
+            IcsTime::Utc(time) => json!({ "time": time.to_rfc3339_opts(chrono::SecondsFormat::Secs, true) }),
+            IcsTime::Floating(time) => json!({ "time": time.format("%Y-%m-%dT%H:%M:%S").to_string() }),
+            IcsTime::Zoned(time, zone) => json!({ "time": time.format("%Y-%m-%dT%H:%M:%S").to_string(), "timezone": zone }),
+        }
+    }
+}
+
+/// Resolve a local time in a zone (ambiguous times take the earlier instant)
+fn local_to_utc(local: NaiveDateTime, tz: Tz) -> Option<IcsTime> {
+    tz.from_local_datetime(&local).earliest().map(|time| IcsTime::Utc(time.with_timezone(&Utc)))
+}
+
+/// DTSTART / DTEND / RECURRENCE-ID value
+fn parse_ics_time(property: &Property) -> Result<IcsTime> {
+    let value = property.value.trim();
+    let invalid = || anyhow::anyhow!("Invalid {} value '{}'", property.name, value);
+    if property.param("VALUE").is_some_and(|v| v.eq_ignore_ascii_case("DATE")) || value.len() == 8 {
+        return NaiveDate::parse_from_str(value, "%Y%m%d").map(IcsTime::Date).map_err(|_| invalid());
+    }
+    if let Some(utc) = value.strip_suffix('Z') {
+        return NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")
+            .map(|time| IcsTime::Utc(Utc.from_utc_datetime(&time)))
+            .map_err(|_| invalid());
+    }
+    let local = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").map_err(|_| invalid())?;
+    match property.param("TZID") {
+        // Some producers prefix global zone ids with "/"
+        Some(zone) => Ok(zone.trim_start_matches('/').parse::<Tz>().ok()
+            .and_then(|tz| local_to_utc(local, tz))
+            .unwrap_or_else(|| IcsTime::Zoned(local, zone.to_string()))),
+        None => Ok(IcsTime::Floating(local)),
+    }
+}
+
+/// ISO 8601 duration as used by iCalendar ("PT1H30M", "P1D", "P2W")
+fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let mut rest = value.strip_prefix('P')?;
+    let mut total = Duration::zero();
+    let mut in_time = false;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('T') {
+            in_time = true;
+            rest = after;
+            continue;
+        }
+        let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+        let amount: i64 = rest[..digits].parse().ok()?;
+        total += match (rest.as_bytes()[digits], in_time) {
+            (b'W', false) => Duration::weeks(amount),
+            (b'D', false) => Duration::days(amount),
+            (b'H', true) => Duration::hours(amount),
+            (b'M', true) => Duration::minutes(amount),
+            (b'S', true) => Duration::seconds(amount),
+            _ => return None,
+        };
+        rest = &rest[digits + 1..];
+    }
+    Some(if negative { -total } else { total })
+}
+
+/// Organizer / attendee
+#[derive(Debug, Clone)]
+struct Person {
+    email: String,
+    name: Option<String>,
+    role: Option<String>,
+    partstat: Option<String>,
+    rsvp: Option<bool>,
+}
+
+impl Person {
+    /// "jane@example.com", "Jane <jane@example.com>" or { email, name, role, partstat, rsvp }
+    fn from_value(value: &Value) -> Result<Option<Self>> {
+        let field = |key: &str| value.get(key).and_then(|v| v.as_str()).filter(|s| !s.is_empty()).map(str::to_string);
+        let (email, name) = match value {
+            Value::Null => return Ok(None),
+            Value::String(s) if s.trim().is_empty() => return Ok(None),
+            Value::String(s) => match s.trim().split_once('<') {
+                Some((name, email)) => (email.trim_end_matches('>').trim().to_string(),
+                    Some(name.trim().trim_matches('"').to_string()).filter(|n| !n.is_empty())),
+                None => (s.trim().to_string(), None),
+            },
+            Value::Object(_) => (field("email").ok_or_else(|| anyhow::anyhow!("entry without 'email'"))?, field("name")),
+            other => return Err(anyhow::anyhow!("expected an email or object, got {}", other)),
+        };
+        let email = email.strip_prefix("mailto:").unwrap_or(&email).to_string();
+        if !email.contains('@') || email.chars().any(|c| c.is_whitespace() || c.is_control() || "<>\";:,".contains(c)) {
+            return Err(anyhow::anyhow!("'{}' is not an email address", email));
+        }
+        Ok(Some(Self {
+            email,
+            name,
+            role: field("role").map(|r| r.to_ascii_uppercase()),
+            partstat: field("partstat").map(|p| p.to_ascii_uppercase()),
+            rsvp: value.get("rsvp").and_then(|r| r.as_bool()),
+        }))
+    }
+
+    /// Array of entries or a comma-separated string
+    fn list(value: &Value) -> Result<Vec<Self>> {
+        let entries: Vec<Value> = match value {
+            Value::Null => Vec::new(),
+            Value::Array(entries) => entries.iter().flat_map(|entry| match entry {
+                // A pin inside the array may resolve to a list itself
+                Value::Array(inner) => inner.clone(),
+                other => vec![other.clone()],
+            }).collect(),
+            Value::String(s) => s.split(',').map(|part| Value::String(part.to_string())).collect(),
+            other => vec![other.clone()],
+        };
+        Ok(entries.iter().map(Self::from_value).collect::<Result<Vec<_>>>()?.into_iter().flatten().collect())
+    }
+
+    fn to_ics(&self, attendee: bool) -> String {
+        let mut params = String::new();
+        if let Some(name) = &self.name {
+            params.push_str(&format!(";CN={}", param_value(name)));
+        }
+        if attendee {
+            params.push_str(&format!(";ROLE={}", param_value(self.role.as_deref().unwrap_or("REQ-PARTICIPANT"))));
+            params.push_str(&format!(";PARTSTAT={}", param_value(self.partstat.as_deref().unwrap_or("NEEDS-ACTION"))));
+            params.push_str(&format!(";RSVP={}", if self.rsvp.unwrap_or(true) { "TRUE" } else { "FALSE" }));
+        }
+        format!("{}:mailto:{}", params, self.email)
+    }
+
+    fn from_property(property: &Property) -> Value {
+        let value = property.value.trim();
+        let email = value.get(..7).filter(|scheme| scheme.eq_ignore_ascii_case("mailto:"))
+            .map(|_| &value[7..])
+            .unwrap_or(value);
+        json!({
+            "email": email,
+            "name": property.param("CN"),
+            "role": property.param("ROLE"),
+            "partstat": property.param("PARTSTAT"),
+            "rsvp": property.param("RSVP").map(|r| r.eq_ignore_ascii_case("TRUE")),
+        })
+    }
+}
+
+/// Content line: NAME;PARAM=value:value
+#[derive(Debug, Clone)]
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn parse(line: &str) -> Option<Self> {
+        // The value starts at the first ':' outside quoted parameter values
+        let mut quoted = false;
+        let colon = line.char_indices().find(|(_, c)| {
+            if *c == '"' {
+                quoted = !quoted;
+            }
+            *c == ':' && !quoted
+        })?.0;
+        let (head, value) = (&line[..colon], &line[colon + 1..]);
+
+        let mut parts = Vec::new();
+        let (mut quoted, mut begin) = (false, 0);
+        for (index, c) in head.char_indices() {
+            match c {
+                '"' => quoted = !quoted,
+                ';' if !quoted => {
+                    parts.push(&head[begin..index]);
+                    begin = index + 1;
+                }
+                _ => {}
+            }
+        }
+        parts.push(&head[begin..]);
+
+        let name = parts[0].trim().to_ascii_uppercase();
+        let params = parts[1..].iter()
+            .filter_map(|param| param.split_once('='))
+            .map(|(key, value)| (key.trim().to_ascii_uppercase(), value.trim().trim_matches('"').to_string()))
+            .collect();
+        Some(Self { name, params, value: value.to_string() })
+    }
+
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+}
+
+/// Split into content lines, joining folded continuation lines
+fn unfold(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for raw in ics.split('\n') {
+        let line = raw.strip_suffix('\r').unwrap_or(raw);
+        match (line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')), lines.last_mut()) {
+            (Some(continuation), Some(previous)) => previous.push_str(continuation),
+            _ if line.trim().is_empty() => {}
+            _ => lines.push(line.trim_start_matches('\u{feff}').to_string()),
+        }
+    }
+    lines
+}
+
+/// Fold a content line at 75 octets without splitting UTF-8 characters, CRLF-terminated
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 8);
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            // The leading space counts towards the next line
+            octets = 1;
+        }
+        folded.push(c);
+        octets += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+/// TEXT value escaping (RFC 5545 3.3.11)
+fn escape_text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape_text(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') | Some('N') => unescaped.push('\n'),
+                Some(other) => unescaped.push(other),
+                None => {}
+            },
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Parameter value, quoted when it contains separators
+fn param_value(value: &str) -> String {
+    let cleaned: String = value.chars().filter(|c| !c.is_control() && *c != '"').collect();
+    if cleaned.contains([':', ';', ',']) {
+        format!("\"{}\"", cleaned)
+    } else {
+        cleaned
+    }
+}
+
+fn strip_controls(value: &str) -> String {
+    value.chars().filter(|c| !c.is_control()).collect()
+}
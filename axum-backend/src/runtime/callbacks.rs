@@ -0,0 +1,144 @@
+//! Run result callbacks
+//! 
+//! Workflows with "callbacks" settings get a summary of every top-level run
+//! POSTed to `on_complete` (success) or `on_failure` (error), so external
+//! orchestrators don't have to poll the execution history:
+//! 
+//! { "settings": { "callbacks": { "on_complete": "https://orchestrator.example.com/runs",
+//!                                "on_failure": "https://orchestrator.example.com/runs",
+//!                                "secret": "$secret.callback_key", "include_output": false } } }
+//! 
+//! With a secret, requests carry X-Mechaway-Timestamp and X-Mechaway-Signature
+//! ("sha256=" + hex HMAC-SHA256 of "{timestamp}.{body}", as HTTPClient "sign").
+//! Delivery happens in the background and is retried on connection errors
+//! and 5xx responses; runs never wait for or fail because of a callback.
+
+use crate::{
+    runtime::{executor::Termination, request_signing::{HmacAlgorithm, SignConfig, SignatureEncoding}},
+    workflow::types::CallbackSettings,
+};
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+use std::time::Duration;
+
+/// Attempts per callback (first try included)
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Summary of a finished top-level run
+#[derive(Debug, Clone, Serialize)]
+pub struct RunSummary {
+    /// "execution.completed" or "execution.failed"
+    pub event: &'static str,
+    pub execution_id: String,
+    pub workflow_id: String,
+    pub workflow_name: String,
+    pub project: String,
+    pub start_node_id: String,
+    pub trigger_type: String,
+    /// "success", "stopped" (a node ended the run early) or "failed"
+    pub status: &'static str,
+    pub error: Option<String>,
+    /// How the run ended, with the stopping node and reason of stopped runs
+    pub termination: Termination,
+    pub started_at: String,
+    pub finished_at: String,
+    pub duration_ms: u64,
+    /// Items output by the last node (0 for failed runs)
+    pub output_items: usize,
+    /// The output items themselves (with "include_output")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<Vec<Value>>,
+}
+
+/// Background delivery of run callbacks
+#[derive(Debug, Clone)]
+pub struct CallbackSender {
+    http: reqwest::Client,
+}
+
+impl Default for CallbackSender {
+    fn default() -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl CallbackSender {
+    /// Deliver a run summary to the URL matching its outcome (no-op without one)
+    /// 
+    /// The signing key is resolved through `resolve` before the background task starts.
+    pub fn send(&self, settings: &CallbackSettings, summary: RunSummary, resolve: impl Fn(&str) -> Result<String>) {
+        let url = if summary.error.is_none() { &settings.on_complete } else { &settings.on_failure };
+        let Some(url) = url.clone() else { return };
+
+        let key = match settings.secret.as_deref().map(&resolve).transpose() {
+            Ok(key) => key,
+            Err(e) => {
+                tracing::warn!("⚠️ Callback for execution {} not sent, signing key unavailable: {}", summary.execution_id, e);
+                return;
+            }
+        };
+        let body = match serde_json::to_vec(&summary) {
+            Ok(body) => body,
+            Err(e) => {
+                tracing::warn!("⚠️ Callback for execution {} not sent: {}", summary.execution_id, e);
+                return;
+            }
+        };
+
+        let http = self.http.clone();
+        tokio::spawn(async move {
+            let mut delay = Duration::from_secs(1);
+            for attempt in 1..=MAX_ATTEMPTS {
+                match post(&http, &url, &body, key.as_deref()).await {
+                    Ok(()) => {
+                        tracing::debug!("📨 Callback for execution {} delivered to {}", summary.execution_id, url);
+                        return;
+                    }
+                    Err((e, retry)) if retry && attempt < MAX_ATTEMPTS => {
+                        tracing::debug!("📨 Callback for execution {} failed (attempt {}): {}", summary.execution_id, attempt, e);
+                        tokio::time::sleep(delay).await;
+                        delay *= 5;
+                    }
+                    Err((e, _)) => {
+                        tracing::warn!("⚠️ Callback for execution {} to {} failed: {}", summary.execution_id, url, e);
+                        return;
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// POST one (signed) callback; errors say whether a retry may help
+async fn post(http: &reqwest::Client, url: &str, body: &[u8], key: Option<&str>) -> Result<(), (anyhow::Error, bool)> {
+    let mut request = http.post(url)
+        .header("Content-Type", "application/json")
+        .body(body.to_vec())
+        .build()
+        .map_err(|e| (e.into(), false))?;
+    if let Some(key) = key {
+        let sign = SignConfig::Hmac {
+            secret: String::new(),
+            header: "X-Mechaway-Signature".to_string(),
+            algorithm: HmacAlgorithm::Sha256,
+            encoding: SignatureEncoding::Hex,
+            prefix: "sha256=".to_string(),
+            timestamp_header: Some("X-Mechaway-Timestamp".to_string()),
+        };
+        sign.sign(&mut request, chrono::Utc::now(), |_| Ok(key.to_string())).map_err(|e| (e, false))?;
+    }
+
+    let response = http.execute(request).await.map_err(|e| (e.into(), true))?;
+    let status = response.status();
+    if status.is_success() {
+        Ok(())
+    } else {
+        Err((anyhow::anyhow!("HTTP {}", status), status.is_server_error()))
+    }
+}
@@ -0,0 +1,304 @@
+//! CurrencyConvert node exchange rates
+//! 
+//! Rate tables are fetched from a configurable provider and cached per project
+//! in the KV store ("currency:rates:{provider}") for `ttl_seconds`:
+//! 
+//! { "provider": "ecb", "amount": "$json.total", "from": "$json.currency", "to": "EUR" }
+//! { "provider": "openexchangerates", "amount": "$json.total", "from": "USD", "to": "$json.customer_currency",
+//!   "ttl_seconds": 3600 }   + secrets ["$secret.oxr_app_id"]
+//! 
+//! Providers: "ecb" (European Central Bank daily reference rates, no key),
+//! "openexchangerates", "exchangerate_api" and "fixer" (API key from the
+//! node's secrets). Any pair is converted through the table's base currency.
+//! If the provider can't be reached, an expired table is used (marked stale)
+//! rather than failing the workflow.
+
+use crate::project::ProjectDatabaseManager;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Default cache lifetime of a rate table (ECB publishes once per working day)
+const DEFAULT_TTL_SECS: u64 = 3600;
+
+/// Currencies without minor units
+const ZERO_DECIMAL_CURRENCIES: &[&str] = &[
+    "BIF", "CLP", "DJF", "GNF", "ISK", "JPY", "KMF", "KRW", "PYG", "RWF", "UGX", "UYI", "VND", "VUV", "XAF", "XOF", "XPF",
+];
+
+/// Currencies with three decimal places
+const THREE_DECIMAL_CURRENCIES: &[&str] = &["BHD", "IQD", "JOD", "KWD", "LYD", "OMR", "TND"];
+
+/// Exchange rate source
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RateProvider {
+    Ecb,
+    OpenExchangeRates,
+    ExchangeRateApi,
+    Fixer,
+}
+
+impl RateProvider {
+    fn parse(name: &str) -> Result<Self> {
+        match name {
+            "ecb" => Ok(RateProvider::Ecb),
+            "openexchangerates" => Ok(RateProvider::OpenExchangeRates),
+            "exchangerate_api" => Ok(RateProvider::ExchangeRateApi),
+            "fixer" => Ok(RateProvider::Fixer),
+            other => Err(anyhow::anyhow!(
+                "Unknown rate provider '{}' (ecb, openexchangerates, exchangerate_api, fixer)", other
+            )),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RateProvider::Ecb => "ecb",
+            RateProvider::OpenExchangeRates => "openexchangerates",
+            RateProvider::ExchangeRateApi => "exchangerate_api",
+            RateProvider::Fixer => "fixer",
+        }
+    }
+
+    pub fn needs_api_key(&self) -> bool {
+        *self != RateProvider::Ecb
+    }
+
+    fn default_api_base(&self) -> &'static str {
+        match self {
+            RateProvider::Ecb => "https://www.ecb.europa.eu",
+            RateProvider::OpenExchangeRates => "https://openexchangerates.org",
+            RateProvider::ExchangeRateApi => "https://v6.exchangerate-api.com",
+            RateProvider::Fixer => "https://data.fixer.io",
+        }
+    }
+}
+
+/// Rates relative to one base currency
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateTable {
+    pub base: String,
+    /// Units of each currency per one unit of base
+    pub rates: HashMap<String, f64>,
+    /// Publication date / time reported by the provider
+    pub as_of: String,
+    /// Unix seconds when the table was fetched
+    pub fetched_at: i64,
+}
+
+impl RateTable {
+    /// Rate to multiply an amount in `from` with to get `to`
+    pub fn rate(&self, from: &str, to: &str) -> Result<f64> {
+        let units_per_base = |code: &str| -> Result<f64> {
+            if code == self.base {
+                return Ok(1.0);
+            }
+            self.rates.get(code).copied().filter(|rate| *rate > 0.0)
+                .ok_or_else(|| anyhow::anyhow!("No exchange rate for '{}'", code))
+        };
+        Ok(units_per_base(to)? / units_per_base(from)?)
+    }
+}
+
+/// Parsed CurrencyConvert node params (templates still unrendered)
+#[derive(Debug, Clone)]
+pub struct CurrencyParams {
+    pub provider: RateProvider,
+    /// { amount, from, to } rendered per input item
+    pub templates: Value,
+    pub ttl_secs: u64,
+    /// Decimal places of the result (default: minor units of the target currency)
+    pub decimals: Option<u32>,
+    pub output_field: String,
+    pub api_base: String,
+}
+
+impl CurrencyParams {
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        let provider = RateProvider::parse(params.get("provider").and_then(|p| p.as_str()).unwrap_or("ecb"))?;
+        let mut templates = serde_json::Map::new();
+        for key in ["amount", "from", "to"] {
+            let template = params.get(key).filter(|v| !v.is_null())
+                .ok_or_else(|| anyhow::anyhow!("Missing '{}' parameter", key))?;
+            templates.insert(key.to_string(), template.clone());
+        }
+        Ok(Self {
+            provider,
+            templates: Value::Object(templates),
+            ttl_secs: params.get("ttl_seconds").and_then(|t| t.as_u64()).unwrap_or(DEFAULT_TTL_SECS),
+            decimals: params.get("decimals").and_then(|d| d.as_u64()).map(|d| d.min(10) as u32),
+            output_field: params.get("output_field").and_then(|f| f.as_str()).unwrap_or("conversion").to_string(),
+            api_base: params.get("api_base").and_then(|b| b.as_str())
+                .unwrap_or(provider.default_api_base()).trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// Convert one rendered { amount, from, to }
+    pub fn convert(&self, table: &RateTable, stale: bool, rendered: &Value) -> Result<Value> {
+        let amount = match &rendered["amount"] {
+            Value::Number(n) => n.as_f64(),
+            Value::String(s) => s.trim().parse::<f64>().ok(),
+            _ => None,
+        }.filter(|amount| amount.is_finite())
+            .ok_or_else(|| anyhow::anyhow!("'amount' is not a number: {}", rendered["amount"]))?;
+        let code = |key: &str| -> Result<String> {
+            let code = rendered[key].as_str().unwrap_or_default().trim().to_ascii_uppercase();
+            if code.len() != 3 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+                return Err(anyhow::anyhow!("'{}' is not an ISO 4217 currency code: {}", key, rendered[key]));
+            }
+            Ok(code)
+        };
+        let (from, to) = (code("from")?, code("to")?);
+
+        let rate = table.rate(&from, &to)?;
+        let decimals = self.decimals.unwrap_or_else(|| minor_units(&to));
+        let factor = 10f64.powi(decimals as i32);
+        let value = (amount * rate * factor).round() / factor;
+        Ok(json!({
+            "amount": amount,
+            "from": from,
+            "to": to,
+            "rate": rate,
+            // Currencies without minor units come out as integers
+            "value": if decimals == 0 && value.abs() < 9e15 { json!(value as i64) } else { json!(value) },
+            "provider": self.provider.name(),
+            "as_of": table.as_of,
+            "stale": stale,
+        }))
+    }
+}
+
+/// Decimal places of a currency
+pub fn minor_units(code: &str) -> u32 {
+    if ZERO_DECIMAL_CURRENCIES.contains(&code) {
+        0
+    } else if THREE_DECIMAL_CURRENCIES.contains(&code) {
+        3
+    } else {
+        2
+    }
+}
+
+/// Rate table from the project cache, refreshed when older than the TTL
+/// 
+/// Returns the table and whether it is stale (refresh failed, cached copy used).
+pub async fn cached_rates(
+    project_db_manager: &ProjectDatabaseManager,
+    project_slug: &str,
+    params: &CurrencyParams,
+    api_key: Option<&str>,
+) -> Result<(RateTable, bool)> {
+    let key = format!("currency:rates:{}", params.provider.name());
+    let cached: Option<RateTable> = project_db_manager.kv_get(project_slug, &key).await?
+        .and_then(|value| serde_json::from_value(value).ok());
+    let now = chrono::Utc::now().timestamp();
+    if let Some(table) = &cached {
+        if now - table.fetched_at < params.ttl_secs as i64 {
+            return Ok((table.clone(), false));
+        }
+    }
+
+    match fetch_rates(params, api_key).await {
+        Ok(table) => {
+            tracing::info!("💱 Fetched {} {} exchange rates (as of {})", table.rates.len(), params.provider.name(), table.as_of);
+            // Stored without expiry so an old table can stand in when the provider is down
+            project_db_manager.kv_set(project_slug, &key, &serde_json::to_value(&table)?, None).await?;
+            Ok((table, false))
+        }
+        Err(e) => match cached {
+            Some(table) => {
+                tracing::warn!("⚠️ Exchange rate refresh from {} failed, using rates fetched {}s ago: {}",
+                    params.provider.name(), now - table.fetched_at, e);
+                Ok((table, true))
+            }
+            None => Err(e),
+        },
+    }
+}
+
+/// Fetch the provider's latest rate table
+async fn fetch_rates(params: &CurrencyParams, api_key: Option<&str>) -> Result<RateTable> {
+    let base = &params.api_base;
+    let key = || api_key.ok_or_else(|| anyhow::anyhow!("Provider '{}' needs an API key secret", params.provider.name()));
+    let request = match params.provider {
+        RateProvider::Ecb => reqwest::Client::new().get(format!("{}/stats/eurofxref/eurofxref-daily.xml", base)),
+        RateProvider::OpenExchangeRates => reqwest::Client::new()
+            .get(format!("{}/api/latest.json", base)).query(&[("app_id", key()?)]),
+        RateProvider::ExchangeRateApi => reqwest::Client::new()
+            .get(format!("{}/v6/{}/latest/USD", base, key()?)),
+        RateProvider::Fixer => reqwest::Client::new()
+            .get(format!("{}/api/latest", base)).query(&[("access_key", key()?)]),
+    };
+    let response = request.timeout(std::time::Duration::from_secs(15)).send().await
+        .map_err(|e| anyhow::anyhow!("Exchange rate request failed: {}", e.without_url()))?;
+    let status = response.status();
+    let body = response.text().await?;
+    if !status.is_success() {
+        return Err(anyhow::anyhow!("Exchange rate provider returned {}: {}", status.as_u16(),
+            body.chars().take(200).collect::<String>()));
+    }
+
+    let fetched_at = chrono::Utc::now().timestamp();
+    if params.provider == RateProvider::Ecb {
+        return parse_ecb(&body, fetched_at);
+    }
+
+    let json: Value = serde_json::from_str(&body)
+        .map_err(|e| anyhow::anyhow!("Exchange rate provider returned invalid JSON: {}", e))?;
+    // Errors arrive with status 200 as { "success": false } / { "result": "error" } / { "error": true }
+    if json["success"] == json!(false) || json["result"] == json!("error") || json["error"] == json!(true) {
+        let reason = ["/error/info", "/error-type", "/description", "/message"].iter()
+            .find_map(|pointer| json.pointer(pointer).and_then(|v| v.as_str()))
+            .unwrap_or("unknown error");
+        return Err(anyhow::anyhow!("Exchange rate provider error: {}", reason));
+    }
+    let (base_key, rates_key, time_key) = match params.provider {
+        RateProvider::ExchangeRateApi => ("base_code", "conversion_rates", "time_last_update_unix"),
+        _ => ("base", "rates", "timestamp"),
+    };
+    let rates: HashMap<String, f64> = json[rates_key].as_object()
+        .ok_or_else(|| anyhow::anyhow!("Exchange rate response has no '{}'", rates_key))?
+        .iter()
+        .filter_map(|(code, rate)| rate.as_f64().map(|rate| (code.to_ascii_uppercase(), rate)))
+        .collect();
+    let as_of = json[time_key].as_i64()
+        .and_then(|seconds| chrono::DateTime::from_timestamp(seconds, 0))
+        .map(|time| time.to_rfc3339())
+        .or_else(|| json["date"].as_str().map(str::to_string))
+        .unwrap_or_default();
+    Ok(RateTable {
+        base: json[base_key].as_str().unwrap_or("USD").to_ascii_uppercase(),
+        rates,
+        as_of,
+        fetched_at,
+    })
+}
+
+/// ECB reference rates: <Cube time='2025-03-03'> <Cube currency='USD' rate='1.0465'/> ...
+fn parse_ecb(xml: &str, fetched_at: i64) -> Result<RateTable> {
+    let attribute = |tag: &str, name: &str| -> Option<String> {
+        let start = tag.find(&format!("{}=", name))? + name.len() + 1;
+        let quote = tag[start..].chars().next().filter(|q| *q == '\'' || *q == '"')?;
+        let value = &tag[start + 1..];
+        Some(value[..value.find(quote)?].to_string())
+    };
+
+    let mut rates = HashMap::new();
+    let mut as_of = String::new();
+    for tag in xml.split("<Cube").skip(1) {
+        let tag = tag.split('>').next().unwrap_or_default();
+        if let Some(time) = attribute(tag, "time") {
+            as_of = time;
+        }
+        if let (Some(currency), Some(rate)) = (attribute(tag, "currency"), attribute(tag, "rate")) {
+            if let Ok(rate) = rate.parse::<f64>() {
+                rates.insert(currency.to_ascii_uppercase(), rate);
+            }
+        }
+    }
+    if rates.is_empty() {
+        return Err(anyhow::anyhow!("ECB response contains no rates"));
+    }
+    Ok(RateTable { base: "EUR".to_string(), rates, as_of, fetched_at })
+}
@@ -0,0 +1,363 @@
+//! Step-through debugger sessions
+//! 
+//! A debug session routes node outputs like the engine (runtime::branches),
+//! but runs one node per request, in plan order: each step runs a single node
+//! through `ExecutionEngine::execute_step` with the output of the nodes feeding
+//! it and records its input and output. Sessions
+//! pause on breakpoints (node IDs) when continued, and are dropped after
+//! `SESSION_IDLE_TIMEOUT` without activity.
+
+use crate::runtime::{
+    branches::{BranchRouter, NodeInput},
+    engine::ExecutionEngine,
+    executor::{ExecutionResult, Termination},
+    logging::LogPolicy,
+};
+use crate::workflow::{registry::CompiledWorkflow, types::{DisabledMode, ExecutionContext}};
+use anyhow::Result;
+use petgraph::graph::NodeIndex;
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::{Mutex, RwLock};
+
+/// Maximum concurrently open debug sessions
+const MAX_DEBUG_SESSIONS: usize = 32;
+
+/// Idle sessions are removed after this long
+const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Debug session lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DebugStatus {
+    /// Waiting for the next step
+    Paused,
+    /// Every node has executed
+    Completed,
+    /// Every node has executed, but a branch stopped early (Termination::StoppedEarly)
+    Stopped,
+    /// A node failed; the error is in the last step record
+    Failed,
+}
+
+/// Input and output of one executed node
+#[derive(Debug, Clone, Serialize)]
+pub struct StepRecord {
+    pub step: usize,
+    pub node_id: String,
+    pub node_type: String,
+    pub input: Vec<Value>,
+    pub output: Vec<Value>,
+    pub duration_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Externally visible session state
+#[derive(Debug, Clone, Serialize)]
+pub struct DebugSessionView {
+    pub session_id: String,
+    pub workflow_id: String,
+    pub status: DebugStatus,
+    /// Steps executed so far
+    pub position: usize,
+    pub total_steps: usize,
+    /// Node that the next step will execute
+    pub next_node: Option<String>,
+    pub breakpoints: BTreeSet<String>,
+    /// Data that the next node will receive
+    pub current_data: Vec<Value>,
+}
+
+/// A paused workflow execution
+pub struct DebugSession {
+    id: String,
+    workflow: CompiledWorkflow,
+    /// Enabled processing nodes (entry points excluded)
+    total_steps: usize,
+    position: usize,
+    /// Routes node outputs to the nodes they feed
+    router: BranchRouter,
+    /// Node the next step executes
+    next: Option<NodeIndex>,
+    /// Shared request parts (files, query, headers, project)
+    context: ExecutionContext,
+    /// Input of the next node (the output of the nodes feeding it)
+    data: Vec<Value>,
+    metadata: HashMap<String, Value>,
+    /// Whether a branch stopped early
+    stopped: bool,
+    breakpoints: BTreeSet<String>,
+    status: DebugStatus,
+    history: Vec<StepRecord>,
+    last_activity: Instant,
+}
+
+impl DebugSession {
+    /// Current session state
+    pub fn view(&self) -> DebugSessionView {
+        DebugSessionView {
+            session_id: self.id.clone(),
+            workflow_id: self.workflow.workflow.id.clone(),
+            status: self.status,
+            position: self.position,
+            total_steps: self.total_steps,
+            next_node: self.next_node_id().map(|id| id.to_string()),
+            breakpoints: self.breakpoints.clone(),
+            current_data: self.data.clone(),
+        }
+    }
+
+    /// Executed steps, oldest first
+    pub fn history(&self) -> &[StepRecord] {
+        &self.history
+    }
+
+    /// Replace the breakpoint set
+    pub fn set_breakpoints(&mut self, breakpoints: impl IntoIterator<Item = String>) {
+        self.breakpoints = breakpoints.into_iter().collect();
+        self.last_activity = Instant::now();
+    }
+
+    fn next_node_id(&self) -> Option<&str> {
+        if self.status != DebugStatus::Paused {
+            return None;
+        }
+        self.next.map(|index| self.workflow.plan.graph[index].id.as_str())
+    }
+
+    /// Move to the next node that runs, taking its input
+    /// 
+    /// Disabled nodes pass their input through (or end their branch in "block" mode).
+    async fn advance(&mut self) -> Result<()> {
+        let plan = Arc::clone(&self.workflow.plan);
+        self.next = None;
+        while let Some(index) = self.router.next_ready() {
+            let node = &plan.graph[index];
+            // Nodes behind output ports not taken (or a stopped branch) leave the session
+            let NodeInput::Run(mut input) = self.router.take_input(index).await? else {
+                if !node.disabled {
+                    self.total_steps -= 1;
+                }
+                continue;
+            };
+            if node.disabled {
+                if node.disabled_mode == DisabledMode::Block {
+                    input.termination = Termination::stopped(&node.id, "Disabled node blocks the run");
+                    self.stopped = true;
+                }
+                self.router.finish(index, input);
+                continue;
+            }
+            self.next = Some(index);
+            self.data = input.data;
+            self.metadata = input.metadata;
+            break;
+        }
+        Ok(())
+    }
+
+    /// Execute the next node and record its input/output
+    /// 
+    /// Fails only when the session has already finished; node failures are
+    /// recorded in the step and move the session to `Failed`.
+    pub async fn step(&mut self, engine: &ExecutionEngine) -> Result<StepRecord> {
+        self.last_activity = Instant::now();
+        if self.status != DebugStatus::Paused {
+            return Err(anyhow::anyhow!("Debug session {} is {:?}", self.id, self.status));
+        }
+
+        let plan = Arc::clone(&self.workflow.plan);
+        let index = self.next.ok_or_else(|| anyhow::anyhow!("Debug session {} has no next node", self.id))?;
+        let node = &plan.graph[index];
+        let input = self.data.clone();
+
+        let mut node_context = self.context.clone();
+        node_context.data = std::mem::take(&mut self.data);
+        node_context.metadata = self.metadata.clone();
+
+        tracing::info!("🐞 Debug session {}: step {}/{} executing '{}'",
+            self.id, self.position + 1, self.total_steps, node.id);
+
+        let started = Instant::now();
+        let outcome = LogPolicy::from_settings(&self.workflow.workflow.settings)
+            .scope(engine.execute_step(node, node_context))
+            .await;
+        let duration_ms = started.elapsed().as_millis() as u64;
+        self.position += 1;
+
+        let record = match outcome {
+            Ok(result) => {
+                self.stopped |= !result.should_continue();
+                if plan.is_output_referenced(index) {
+                    self.context.node_outputs.insert(node.id.clone(), Arc::new(result.data.clone()));
+                }
+                let output = result.data.clone();
+                self.router.finish(index, result);
+                self.advance().await?;
+                self.status = if self.next.is_some() {
+                    DebugStatus::Paused
+                } else if self.stopped {
+                    DebugStatus::Stopped
+                } else {
+                    DebugStatus::Completed
+                };
+                StepRecord {
+                    step: self.position,
+                    node_id: node.id.clone(),
+                    node_type: format!("{:?}", node.node_type),
+                    input,
+                    output,
+                    duration_ms,
+                    error: None,
+                }
+            }
+            Err(e) => {
+                // Keep the input so the failing node's data can still be inspected
+                self.data = input.clone();
+                self.status = DebugStatus::Failed;
+                StepRecord {
+                    step: self.position,
+                    node_id: node.id.clone(),
+                    node_type: format!("{:?}", node.node_type),
+                    input,
+                    output: Vec::new(),
+                    duration_ms,
+                    error: Some(e.to_string()),
+                }
+            }
+        };
+
+        self.history.push(record.clone());
+        Ok(record)
+    }
+
+    /// Execute steps until the next node has a breakpoint or the session finishes
+    /// 
+    /// Always executes at least one step, so continuing from a breakpoint moves past it.
+    pub async fn continue_run(&mut self, engine: &ExecutionEngine) -> Result<Vec<StepRecord>> {
+        let mut records = vec![self.step(engine).await?];
+        while self.status == DebugStatus::Paused {
+            if self.next_node_id().is_some_and(|id| self.breakpoints.contains(id)) {
+                tracing::info!("🔴 Debug session {} hit breakpoint before '{}'", self.id, self.next_node_id().unwrap_or_default());
+                break;
+            }
+            records.push(self.step(engine).await?);
+        }
+        Ok(records)
+    }
+}
+
+/// Registry of open debug sessions
+pub struct DebugSessionManager {
+    engine: Arc<ExecutionEngine>,
+    sessions: RwLock<HashMap<String, Arc<Mutex<DebugSession>>>>,
+}
+
+impl DebugSessionManager {
+    /// Create a session manager that steps nodes through the given engine
+    pub fn new(engine: Arc<ExecutionEngine>) -> Self {
+        Self {
+            engine,
+            sessions: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Engine used to execute debug steps
+    pub fn engine(&self) -> &ExecutionEngine {
+        &self.engine
+    }
+
+    /// Open a paused session positioned before the first processing node
+    pub async fn start(
+        &self,
+        workflow: CompiledWorkflow,
+        start_node_id: &str,
+        mut context: ExecutionContext,
+        breakpoints: impl IntoIterator<Item = String>,
+    ) -> Result<DebugSessionView> {
+        let steps = workflow.plan.execution_order(start_node_id)?.into_owned();
+        let total_steps = steps.iter().filter(|&&index| !workflow.plan.graph[index].disabled).count();
+        if total_steps == 0 {
+            return Err(anyhow::anyhow!("Start node '{}' has no connected processing nodes", start_node_id));
+        }
+        let start_index = *workflow.plan.node_id_to_index.get(start_node_id)
+            .ok_or_else(|| anyhow::anyhow!("Start node not found: {}", start_node_id))?;
+
+        let mut sessions = self.sessions.write().await;
+        Self::prune_idle(&mut sessions);
+        if sessions.len() >= MAX_DEBUG_SESSIONS {
+            return Err(anyhow::anyhow!("Too many open debug sessions (max {})", MAX_DEBUG_SESSIONS));
+        }
+
+        let id = uuid::Uuid::new_v4().to_string();
+        context.metadata.insert("debug_session".to_string(), Value::String(id.clone()));
+        context.vars = Arc::clone(&workflow.variables);
+        if workflow.plan.is_output_referenced(start_index) {
+            context.node_outputs.insert(start_node_id.to_string(), Arc::new(context.data.clone()));
+        }
+        let input = ExecutionResult {
+            data: std::mem::take(&mut context.data),
+            metadata: context.metadata.clone(),
+            termination: Termination::Completed,
+        };
+        let seed = (!steps.contains(&start_index)).then_some(start_index);
+        let mut session = DebugSession {
+            id: id.clone(),
+            router: BranchRouter::new(Arc::clone(&workflow.plan), &steps, seed, input),
+            workflow,
+            total_steps,
+            position: 0,
+            next: None,
+            data: Vec::new(),
+            metadata: HashMap::new(),
+            stopped: false,
+            context,
+            breakpoints: breakpoints.into_iter().collect(),
+            status: DebugStatus::Paused,
+            history: Vec::new(),
+            last_activity: Instant::now(),
+        };
+        session.advance().await?;
+        if session.next.is_none() {
+            session.status = DebugStatus::Completed;
+        }
+        let view = session.view();
+        sessions.insert(id.clone(), Arc::new(Mutex::new(session)));
+
+        tracing::info!("🐞 Debug session {} started for workflow '{}' ({} steps)",
+            id, view.workflow_id, view.total_steps);
+        Ok(view)
+    }
+
+    /// Look up an open session
+    pub async fn get(&self, session_id: &str) -> Option<Arc<Mutex<DebugSession>>> {
+        self.sessions.read().await.get(session_id).cloned()
+    }
+
+    /// Close a session, returning whether it existed
+    pub async fn remove(&self, session_id: &str) -> bool {
+        self.sessions.write().await.remove(session_id).is_some()
+    }
+
+    /// Drop sessions idle for longer than SESSION_IDLE_TIMEOUT (busy sessions are kept)
+    fn prune_idle(sessions: &mut HashMap<String, Arc<Mutex<DebugSession>>>) {
+        let mut expired = Vec::new();
+        for (id, session) in sessions.iter() {
+            if let Ok(session) = session.try_lock() {
+                if session.last_activity.elapsed() > SESSION_IDLE_TIMEOUT {
+                    expired.push(id.clone());
+                }
+            }
+        }
+        for id in expired {
+            tracing::info!("🧹 Removing idle debug session {}", id);
+            sessions.remove(&id);
+        }
+    }
+}
@@ -0,0 +1,247 @@
+//! Device state registry (digital twins)
+//! 
+//! The MQTT and WebSocket trigger services record every message they receive
+//! (before stream aggregation) as the current state of the device that sent
+//! it, so workflows can read it with `$device.*` pins instead of re-deriving
+//! it from tables:
+//! 
+//! $device.status                          device of the current run (online / offline)
+//! $device.last_payload.temperature        its latest reading, even when the run is aggregated
+//! $device[boiler-2].last_payload.pressure any device of the same project
+//! 
+//! Device IDs come from the trigger's "device_field" (a dotted path in the
+//! message), else the MQTT topic or the WebSocket `?device_id=` query
+//! parameter (else the connection ID). WebSocket devices go offline when their
+//! connection closes; with "offline_after_secs" a device is also reported
+//! offline after that much silence. State lives in memory and changed devices
+//! are written to the project DB every second.
+
+use crate::{
+    project::{devices::DeviceState, ProjectDatabaseManager},
+    workflow::types::Node,
+};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::{Arc, Mutex, RwLock},
+    time::Duration,
+};
+
+/// How often changed devices are written to the project DBs
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Device settings of a MQTTTrigger / WebSocketTrigger node
+#[derive(Debug, Clone)]
+pub struct DeviceTrigger {
+    /// "mqtt" or "websocket"
+    pub source: &'static str,
+    pub workflow_id: String,
+    pub node_id: String,
+    /// Dotted path of the device ID in messages
+    pub device_field: Option<String>,
+    pub offline_after_secs: Option<u64>,
+}
+
+impl DeviceTrigger {
+    /// Read the device settings of a trigger node ("device_field", "offline_after_secs")
+    pub fn from_node(source: &'static str, workflow_id: &str, node: &Node) -> Self {
+        Self {
+            source,
+            workflow_id: workflow_id.to_string(),
+            node_id: node.id.clone(),
+            device_field: node.params.get("device_field").and_then(|f| f.as_str()).filter(|f| !f.is_empty()).map(str::to_string),
+            offline_after_secs: node.params.get("offline_after_secs").and_then(|s| s.as_u64()).filter(|s| *s > 0),
+        }
+    }
+
+    /// Device ID of a message: the "device_field" value, else `fallback`
+    pub fn device_id(&self, item: &Value, fallback: &str) -> String {
+        let value = self.device_field.as_deref()
+            .and_then(|path| path.split('.').try_fold(item, |value, segment| value.get(segment)));
+        match value {
+            Some(Value::String(id)) if !id.is_empty() => id.clone(),
+            Some(id @ Value::Number(_)) => id.to_string(),
+            _ => fallback.to_string(),
+        }
+    }
+}
+
+/// In-memory device states per project, persisted in the background
+#[derive(Debug)]
+pub struct DeviceRegistry {
+    project_db_manager: Arc<ProjectDatabaseManager>,
+    /// project_slug -> device_id -> state
+    devices: RwLock<HashMap<String, BTreeMap<String, DeviceState>>>,
+    /// (project_slug, device_id) changed since the last flush
+    dirty: Mutex<HashSet<(String, String)>>,
+}
+
+impl DeviceRegistry {
+    /// Create an empty device registry
+    pub fn new(project_db_manager: Arc<ProjectDatabaseManager>) -> Self {
+        Self {
+            project_db_manager,
+            devices: RwLock::new(HashMap::new()),
+            dirty: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Load the stored devices of all projects, returning how many were loaded
+    /// 
+    /// WebSocket connections don't survive a restart, so their devices come back offline.
+    pub async fn load(&self) -> usize {
+        let mut loaded = 0;
+        for project in self.project_db_manager.known_projects().await {
+            match self.project_db_manager.device_list(&project).await {
+                Ok(devices) => {
+                    loaded += devices.len();
+                    let states = devices.into_iter()
+                        .map(|mut device| {
+                            if device.source == "websocket" {
+                                device.status = "offline".to_string();
+                            }
+                            (device.device_id.clone(), device)
+                        })
+                        .collect();
+                    self.devices.write().unwrap().insert(project, states);
+                }
+                Err(e) => tracing::warn!("⚠️ Failed to load devices of project {}: {}", project, e),
+            }
+        }
+        loaded
+    }
+
+    /// Write changed devices to the project DBs every second
+    pub fn start(self: Arc<Self>) {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(FLUSH_INTERVAL);
+            loop {
+                ticker.tick().await;
+                self.flush().await;
+            }
+        });
+    }
+
+    /// Write the devices changed since the last flush
+    pub async fn flush(&self) {
+        let changed: Vec<(String, String)> = self.dirty.lock().unwrap().drain().collect();
+        if changed.is_empty() {
+            return;
+        }
+
+        let mut by_project: HashMap<String, Vec<DeviceState>> = HashMap::new();
+        {
+            let devices = self.devices.read().unwrap();
+            for (project, device_id) in changed {
+                if let Some(state) = devices.get(&project).and_then(|states| states.get(&device_id)) {
+                    by_project.entry(project).or_default().push(state.clone());
+                }
+            }
+        }
+        for (project, states) in by_project {
+            if let Err(e) = self.project_db_manager.device_save(&project, &states).await {
+                tracing::warn!("⚠️ Failed to save {} device states of project {}: {}", states.len(), project, e);
+                // Retry on the next flush
+                self.dirty.lock().unwrap().extend(states.into_iter().map(|state| (project.clone(), state.device_id)));
+            }
+        }
+    }
+
+    /// Record a message as the device's current state (marks it online)
+    pub fn record_message(&self, project: &str, trigger: &DeviceTrigger, device_id: &str, item: &Value) {
+        let mut payload = item.clone();
+        if let Some(object) = payload.as_object_mut() {
+            object.remove(trigger.source);
+        }
+        self.update(project, trigger, device_id, Some(payload));
+    }
+
+    /// Mark a device online before its first message (e.g. a WebSocket connection with ?device_id=)
+    pub fn record_connected(&self, project: &str, trigger: &DeviceTrigger, device_id: &str) {
+        self.update(project, trigger, device_id, None);
+    }
+
+    fn update(&self, project: &str, trigger: &DeviceTrigger, device_id: &str, payload: Option<Value>) {
+        let now = chrono::Utc::now();
+        let messages = u64::from(payload.is_some());
+
+        let mut devices = self.devices.write().unwrap();
+        let states = devices.entry(project.to_string()).or_default();
+        match states.get_mut(device_id) {
+            Some(state) => {
+                state.source = trigger.source.to_string();
+                state.workflow_id = trigger.workflow_id.clone();
+                state.node_id = trigger.node_id.clone();
+                state.status = "online".to_string();
+                if let Some(payload) = payload {
+                    state.last_payload = payload;
+                }
+                state.last_seen = now;
+                state.message_count += messages;
+                state.offline_after_secs = trigger.offline_after_secs;
+            }
+            None => {
+                tracing::info!("📟 New device '{}' in project {} ({})", device_id, project, trigger.source);
+                states.insert(device_id.to_string(), DeviceState {
+                    device_id: device_id.to_string(),
+                    source: trigger.source.to_string(),
+                    workflow_id: trigger.workflow_id.clone(),
+                    node_id: trigger.node_id.clone(),
+                    status: "online".to_string(),
+                    last_payload: payload.unwrap_or(Value::Null),
+                    first_seen: now,
+                    last_seen: now,
+                    message_count: messages,
+                    offline_after_secs: trigger.offline_after_secs,
+                });
+            }
+        }
+        drop(devices);
+        self.dirty.lock().unwrap().insert((project.to_string(), device_id.to_string()));
+    }
+
+    /// Mark devices offline (e.g. when their WebSocket connection closes)
+    pub fn set_offline<'a>(&self, project: &str, device_ids: impl IntoIterator<Item = &'a String>) {
+        let mut devices = self.devices.write().unwrap();
+        let Some(states) = devices.get_mut(project) else { return };
+        let mut dirty = self.dirty.lock().unwrap();
+        for device_id in device_ids {
+            if let Some(state) = states.get_mut(device_id) {
+                state.status = "offline".to_string();
+                dirty.insert((project.to_string(), device_id.clone()));
+            }
+        }
+    }
+
+    /// Current state of a device
+    pub fn get(&self, project: &str, device_id: &str) -> Option<DeviceState> {
+        self.devices.read().unwrap()
+            .get(project)
+            .and_then(|states| states.get(device_id))
+            .map(current_state)
+    }
+
+    /// Current states of a project's devices, by device ID
+    pub fn list(&self, project: &str) -> Vec<DeviceState> {
+        self.devices.read().unwrap()
+            .get(project)
+            .map(|states| states.values().map(current_state).collect())
+            .unwrap_or_default()
+    }
+
+    /// `$device.*` pin value: the state of a device as JSON (null when unknown)
+    pub fn pin_value(&self, project: &str, device_id: &str) -> Value {
+        self.get(project, device_id).map(|state| json!(state)).unwrap_or(Value::Null)
+    }
+}
+
+/// State with "offline_after_secs" applied
+fn current_state(state: &DeviceState) -> DeviceState {
+    let mut state = state.clone();
+    if let Some(secs) = state.offline_after_secs {
+        if state.status == "online" && chrono::Utc::now() - state.last_seen > chrono::Duration::seconds(secs as i64) {
+            state.status = "offline".to_string();
+        }
+    }
+    state
+}
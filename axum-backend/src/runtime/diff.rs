@@ -0,0 +1,283 @@
+//! Diff node change detection
+//! 
+//! Compares the incoming data array against the node's snapshot from the
+//! previous run (project DB, one row per item key) and emits one item per
+//! change, so downstream nodes only sync what actually changed:
+//! 
+//! { "key": "id", "ignore": ["updated_at"], "emit": ["added", "changed", "removed"] }
+//! 
+//! Output items: { "change": "added" | "changed" | "removed" | "unchanged",
+//!                 "key": 42, "item": {...}, "previous": {...}, "changed_fields": ["price"] }
+//! 
+//! "key" is a dotted field path or an array of them (composite key). Items are
+//! fingerprinted by their SHA-256 over key-sorted JSON, leaving out "ignore"d
+//! fields, or over only the "compare" fields when given. In "full" mode (the
+//! default) keys missing from the data array are reported as removed; use
+//! "incremental" when the source only sends new or modified records.
+
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+
+/// How the data array relates to the tracked set
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiffMode {
+    /// Data array is the complete set: absent keys were removed
+    Full,
+    /// Data array only holds new/modified items: nothing is ever removed
+    Incremental,
+}
+
+/// Kind of change of one item
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Change {
+    Added,
+    Changed,
+    Removed,
+    Unchanged,
+}
+
+impl Change {
+    fn parse(name: &str) -> Result<Self> {
+        match name {
+            "added" => Ok(Change::Added),
+            "changed" => Ok(Change::Changed),
+            "removed" => Ok(Change::Removed),
+            "unchanged" => Ok(Change::Unchanged),
+            other => Err(anyhow::anyhow!("unknown change kind '{}' (expected added, changed, removed or unchanged)", other)),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Change::Added => "added",
+            Change::Changed => "changed",
+            Change::Removed => "removed",
+            Change::Unchanged => "unchanged",
+        }
+    }
+}
+
+/// Diff node configuration
+#[derive(Debug, Clone)]
+pub struct DiffParams {
+    /// Key field paths (one for a simple key, several for a composite key)
+    pub key: Vec<Vec<String>>,
+    /// Field paths left out of the fingerprint
+    pub ignore: Vec<Vec<String>>,
+    /// Only these field paths make up the fingerprint (when set)
+    pub compare: Option<Vec<Vec<String>>>,
+    /// Change kinds emitted downstream
+    pub emit: Vec<Change>,
+    pub mode: DiffMode,
+}
+
+impl DiffParams {
+    /// Parse node params
+    /// 
+    /// Expected params: { "key": "id" | ["tenant", "sku"], "ignore": ["synced_at"],
+    ///                    "compare": ["price", "stock"], "emit": ["added", "changed"], "mode": "full" | "incremental" }
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        let key = match params.get("key") {
+            Some(Value::String(path)) => vec![parse_path(path)?],
+            Some(Value::Array(paths)) if !paths.is_empty() => paths_param(paths, "key")?,
+            _ => return Err(anyhow::anyhow!("missing 'key' parameter (a field path or an array of field paths)")),
+        };
+        let ignore = match params.get("ignore") {
+            None | Some(Value::Null) => Vec::new(),
+            Some(Value::Array(paths)) => paths_param(paths, "ignore")?,
+            Some(_) => return Err(anyhow::anyhow!("'ignore' must be an array of field paths")),
+        };
+        let compare = match params.get("compare") {
+            None | Some(Value::Null) => None,
+            Some(Value::Array(paths)) if !paths.is_empty() => Some(paths_param(paths, "compare")?),
+            Some(_) => return Err(anyhow::anyhow!("'compare' must be a non-empty array of field paths")),
+        };
+        let emit = match params.get("emit") {
+            None | Some(Value::Null) => vec![Change::Added, Change::Changed, Change::Removed],
+            Some(Value::Array(kinds)) => kinds.iter()
+                .map(|kind| kind.as_str()
+                    .ok_or_else(|| anyhow::anyhow!("'emit' entries must be strings, got {}", kind))
+                    .and_then(Change::parse))
+                .collect::<Result<_>>()?,
+            Some(_) => return Err(anyhow::anyhow!("'emit' must be an array of change kinds")),
+        };
+        let mode = match params.get("mode").and_then(|m| m.as_str()).unwrap_or("full") {
+            "full" => DiffMode::Full,
+            "incremental" => DiffMode::Incremental,
+            other => return Err(anyhow::anyhow!("unknown mode '{}' (expected full or incremental)", other)),
+        };
+
+        Ok(Self { key, ignore, compare, emit, mode })
+    }
+
+    /// Whether items of this change kind go downstream
+    pub fn emits(&self, change: Change) -> bool {
+        self.emit.contains(&change)
+    }
+
+    /// Key of an item: its snapshot key string and the key value reported downstream
+    /// 
+    /// A simple string key is stored as is, anything else as its JSON text.
+    pub fn item_key(&self, item: &Value) -> Result<(String, Value)> {
+        let mut parts = Vec::with_capacity(self.key.len());
+        for path in &self.key {
+            match lookup(item, path) {
+                None | Some(Value::Null) => return Err(anyhow::anyhow!("missing key field '{}'", path.join("."))),
+                Some(value) => parts.push(value.clone()),
+            }
+        }
+        let key = if parts.len() == 1 { parts.remove(0) } else { Value::Array(parts) };
+        let text = match &key {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        Ok((text, key))
+    }
+
+    /// Hex SHA-256 of the item's compared content
+    pub fn fingerprint(&self, item: &Value) -> String {
+        let mut canonical = String::new();
+        write_canonical(&self.compared(item), &mut canonical);
+        let hash = Sha256::digest(canonical.as_bytes());
+        hash.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// Dotted paths of the compared fields that differ between two versions of an item
+    pub fn changed_fields(&self, previous: &Value, current: &Value) -> Vec<String> {
+        let mut fields = Vec::new();
+        collect_changes(&self.compared(previous), &self.compared(current), &mut Vec::new(), &mut fields);
+        fields
+    }
+
+    /// The part of an item that takes part in change detection
+    fn compared(&self, item: &Value) -> Value {
+        match &self.compare {
+            Some(paths) => {
+                let mut projected = Value::Object(Map::new());
+                for path in paths {
+                    if let Some(value) = lookup(item, path) {
+                        insert(&mut projected, path, value.clone());
+                    }
+                }
+                projected
+            }
+            None => {
+                let mut item = item.clone();
+                for path in &self.ignore {
+                    remove(&mut item, path);
+                }
+                item
+            }
+        }
+    }
+}
+
+/// One output item
+pub fn change_item(change: Change, key: &Value, item: Option<&Value>, previous: Option<&Value>, changed_fields: Option<Vec<String>>) -> Value {
+    let mut output = json!({
+        "change": change.as_str(),
+        "key": key,
+        "item": item.cloned().unwrap_or(Value::Null),
+        "previous": previous.cloned().unwrap_or(Value::Null),
+    });
+    if let Some(fields) = changed_fields {
+        output["changed_fields"] = json!(fields);
+    }
+    output
+}
+
+fn parse_path(path: &str) -> Result<Vec<String>> {
+    let segments: Vec<String> = path.split('.').map(|s| s.to_string()).collect();
+    if segments.iter().any(|s| s.is_empty()) {
+        return Err(anyhow::anyhow!("invalid field path '{}'", path));
+    }
+    Ok(segments)
+}
+
+fn paths_param(paths: &[Value], name: &str) -> Result<Vec<Vec<String>>> {
+    paths.iter()
+        .map(|path| path.as_str()
+            .ok_or_else(|| anyhow::anyhow!("'{}' entries must be field path strings, got {}", name, path))
+            .and_then(parse_path))
+        .collect()
+}
+
+fn lookup<'a>(value: &'a Value, path: &[String]) -> Option<&'a Value> {
+    path.iter().try_fold(value, |current, segment| current.get(segment.as_str()))
+}
+
+fn insert(target: &mut Value, path: &[String], value: Value) {
+    let Some((last, parents)) = path.split_last() else { return };
+    let mut current = target;
+    for segment in parents {
+        let Some(object) = current.as_object_mut() else { return };
+        current = object.entry(segment.clone()).or_insert_with(|| Value::Object(Map::new()));
+    }
+    if let Some(object) = current.as_object_mut() {
+        object.insert(last.clone(), value);
+    }
+}
+
+fn remove(target: &mut Value, path: &[String]) {
+    let Some((last, parents)) = path.split_last() else { return };
+    let mut current = target;
+    for segment in parents {
+        match current.get_mut(segment.as_str()) {
+            Some(next) => current = next,
+            None => return,
+        }
+    }
+    if let Some(object) = current.as_object_mut() {
+        object.remove(last);
+    }
+}
+
+/// JSON text with object keys sorted at every level, so field order never changes a fingerprint
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            out.push('{');
+            for (index, (key, value)) in entries.into_iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(value, out);
+            }
+            out.push('}');
+        }
+        Value::Array(values) => {
+            out.push('[');
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_canonical(value, out);
+            }
+            out.push(']');
+        }
+        scalar => out.push_str(&scalar.to_string()),
+    }
+}
+
+/// Walk two values in parallel, recording the paths of differing leaves (objects are descended, arrays compared whole)
+fn collect_changes(previous: &Value, current: &Value, path: &mut Vec<String>, fields: &mut Vec<String>) {
+    match (previous, current) {
+        (Value::Object(before), Value::Object(after)) => {
+            let mut keys: Vec<&String> = before.keys().chain(after.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                path.push(key.clone());
+                collect_changes(before.get(key).unwrap_or(&Value::Null), after.get(key).unwrap_or(&Value::Null), path, fields);
+                path.pop();
+            }
+        }
+        (before, after) if before != after => fields.push(path.join(".")),
+        _ => {}
+    }
+}
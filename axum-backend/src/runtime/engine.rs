@@ -1,228 +1,769 @@
-/// Petgraph-based DAG execution engine
-/// 
-/// Converts workflows into directed acyclic graphs (DAGs) and executes them
-/// using topological sorting for deterministic, parallel execution.
+//! Petgraph-based DAG execution engine
+//! 
+//! Executes workflows along the precompiled DAG execution plan built by the
+//! registry. Every node gets the output of the nodes feeding it, and the
+//! branches after a fork run concurrently (runtime::branches).
 
-use crate::runtime::executor::{ExecutionResult, NodeExecutor};
-use crate::workflow::registry::CompiledWorkflow;
-use crate::workflow::types::{ExecutionContext, Node};
+use crate::project::executions::{NewExecution, TriggerPayload};
+use crate::runtime::branches::{BranchRouter, NodeInput};
+use crate::runtime::budget::{ResourceExceeded, ResourceLimits, RunBudget};
+use crate::runtime::callbacks::{CallbackSender, RunSummary};
+use crate::runtime::executor::{ExecutionResult, NodeExecutor, Termination};
+use crate::runtime::logging::LogPolicy;
+use crate::runtime::maintenance_mode::MaintenanceMode;
+use crate::runtime::ndjson::{ndjson_batch_size, NdjsonReader};
+use crate::runtime::pg_cursor::{cursor_batch_size, PgCursor};
+use crate::runtime::profiler::ExecutionProfiler;
+use crate::runtime::trace::{NodeStatus, RunTrace, TRACE_METADATA_KEY};
+use crate::workflow::assertions::AssertionError;
+use crate::workflow::environments::{project_environment, ENVIRONMENT_METADATA_KEY};
+use crate::workflow::registry::{CompiledWorkflow, WorkflowRegistry};
+use crate::workflow::types::{DisabledMode, ExecutionContext, Node, NodeType};
 use anyhow::Result;
-use petgraph::algo::toposort;
-use petgraph::graph::{DiGraph, NodeIndex};
-use std::{collections::HashMap, sync::Arc};
+use futures_util::{future::BoxFuture, stream::FuturesUnordered, StreamExt};
+use petgraph::graph::NodeIndex;
+use serde_json::{json, Value};
+use std::{
+    borrow::Cow,
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+/// Maximum nesting depth for ExecuteWorkflow chains (guards against A -> B -> A loops)
+const MAX_SUB_WORKFLOW_DEPTH: u64 = 8;
+
+/// Error of a node that failed during a run
+/// 
+/// Keeps the failing node's ID so API responses can point at it; nested
+/// ExecuteWorkflow failures report the calling node and name the inner one in the message.
+#[derive(Debug)]
+pub struct NodeExecutionError {
+    pub node_id: String,
+    /// Node type name (e.g. "FunLogic")
+    pub node_type: String,
+    pub message: String,
+}
+
+impl NodeExecutionError {
+    fn wrap(node: &Node, error: anyhow::Error) -> anyhow::Error {
+        anyhow::Error::new(Self {
+            node_id: node.id.clone(),
+            node_type: format!("{:?}", node.node_type),
+            message: error.to_string(),
+        })
+    }
+}
+
+impl std::fmt::Display for NodeExecutionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Node execution failed for '{}': {}", self.node_id, self.message)
+    }
+}
+
+impl std::error::Error for NodeExecutionError {}
 
 /// DAG execution engine using petgraph for workflow orchestration
 /// 
-/// Builds a directed graph from workflow definitions and executes nodes
-/// in topological order, respecting dependencies and data flow.
+/// Walks the compiled workflow's execution plan and executes each node once
+/// the nodes feeding it have finished, respecting dependencies and data flow.
 #[derive(Debug)]
 pub struct ExecutionEngine {
     /// Node executor for handling individual node execution
     executor: Arc<NodeExecutor>,
-}
-
-/// Internal representation of a workflow as a petgraph DAG
-#[derive(Debug)]
-struct WorkflowGraph {
-    /// The petgraph DiGraph structure
-    graph: DiGraph<Node, ()>,
-    /// Mapping from node ID to graph node index
-    node_id_to_index: HashMap<String, NodeIndex>,
-    /// Mapping from graph node index to node ID
-    index_to_node_id: HashMap<NodeIndex, String>,
+    /// Workflow registry for resolving ExecuteWorkflow targets
+    registry: Arc<WorkflowRegistry>,
+    /// Default spill threshold for intermediate node outputs (0 = disabled)
+    spill_threshold_bytes: usize,
+    /// Record top-level runs (with trigger payloads) in the project's execution history
+    record_history: bool,
+    /// Node and workflow timing statistics (None = not profiled, e.g. test runs)
+    profiler: Option<Arc<ExecutionProfiler>>,
+    /// Run result callbacks of workflows with "callbacks" settings (None = not sent, e.g. test runs)
+    callbacks: Option<CallbackSender>,
+    /// Server-wide maintenance mode checked by the trigger services
+    maintenance_mode: Arc<MaintenanceMode>,
+    /// Server-wide memory and CPU budget of a run (unlimited by default)
+    resource_limits: ResourceLimits,
 }
 
 impl ExecutionEngine {
-    /// Create new execution engine with node executor
-    pub fn new(executor: Arc<NodeExecutor>) -> Self {
-        Self { executor }
+    /// Create new execution engine with node executor, workflow registry and spill threshold
+    pub fn new(executor: Arc<NodeExecutor>, registry: Arc<WorkflowRegistry>, spill_threshold_bytes: usize) -> Self {
+        Self {
+            executor,
+            registry,
+            spill_threshold_bytes,
+            record_history: false,
+            profiler: None,
+            callbacks: None,
+            maintenance_mode: Arc::new(MaintenanceMode::new(0)),
+            resource_limits: ResourceLimits::default(),
+        }
     }
     
-    /// Find all nodes reachable from the starting node using DFS
-    fn find_reachable_nodes(&self, graph: &petgraph::Graph<Node, ()>, start_index: petgraph::graph::NodeIndex) -> std::collections::HashSet<petgraph::graph::NodeIndex> {
-        use std::collections::{HashSet, VecDeque};
-        
-        let mut reachable = HashSet::new();
-        let mut queue = VecDeque::new();
-        
-        queue.push_back(start_index);
-        reachable.insert(start_index);
-        
-        while let Some(current) = queue.pop_front() {
-            // Add all neighbors (nodes this one points to)
-            let mut neighbors = graph.neighbors(current).detach();
-            while let Some(target) = neighbors.next_node(&graph) {
-                if !reachable.contains(&target) {
-                    reachable.insert(target);
-                    queue.push_back(target);
-                }
-            }
-        }
-        
-        reachable
+    /// Enable or disable execution history recording (disabled by default)
+    pub fn with_history(mut self, enabled: bool) -> Self {
+        self.record_history = enabled;
+        self
     }
-
-    /// Execute a workflow starting from a webhook trigger
+    
+    /// Feed node and workflow timings into a profiler
+    pub fn with_profiler(mut self, profiler: Arc<ExecutionProfiler>) -> Self {
+        self.profiler = Some(profiler);
+        self
+    }
+    
+    /// Send run result callbacks for workflows that configure them
+    pub fn with_callbacks(mut self) -> Self {
+        self.callbacks = Some(CallbackSender::default());
+        self
+    }
+    
+    /// Share the server's maintenance mode toggle (off and private by default, e.g. test runs)
+    pub fn with_maintenance_mode(mut self, maintenance_mode: Arc<MaintenanceMode>) -> Self {
+        self.maintenance_mode = maintenance_mode;
+        self
+    }
+    
+    /// Fail runs exceeding a memory or CPU budget (workflows may set lower ones)
+    pub fn with_resource_limits(mut self, resource_limits: ResourceLimits) -> Self {
+        self.resource_limits = resource_limits;
+        self
+    }
+    
+    /// Maintenance mode toggle (trigger services drop new runs while it is on)
+    pub fn maintenance_mode(&self) -> &MaintenanceMode {
+        &self.maintenance_mode
+    }
+    
+    /// Node executor (secret resolution for trigger ingress)
+    pub fn executor(&self) -> &Arc<NodeExecutor> {
+        &self.executor
+    }
+    
+    /// Execute a workflow starting from a trigger node
     /// 
     /// Takes the compiled workflow and initial execution context,
     /// builds a DAG, and executes nodes in topological order.
     /// Returns the final execution result after all nodes complete.
+    /// Every run gets an ID (metadata "execution_id"); with history enabled
+    /// the run and its trigger payload are recorded under it. Workflows with
+    /// "callbacks" settings get the run summary POSTed when it finishes.
+    /// Runs use the workflow's variant of the selected environment (see `select_environment`).
     pub async fn execute_workflow(
         &self,
         workflow: &CompiledWorkflow,
         start_node_id: &str,
         mut context: ExecutionContext,
+    ) -> Result<ExecutionResult> {
+        let workflow = self.select_environment(workflow, &mut context).await?;
+        
+        // Callers may pre-assign the ID (e.g. replays report it even when the run fails)
+        let execution_id = match context.metadata.get("execution_id").and_then(|v| v.as_str()) {
+            Some(id) => id.to_string(),
+            None => uuid::Uuid::new_v4().to_string(),
+        };
+        context.metadata.insert("execution_id".to_string(), json!(execution_id));
+        
+        let callbacks = self.callbacks.as_ref()
+            .and_then(|sender| Some((sender, workflow.workflow.settings.callbacks.as_ref()?)));
+        if !self.record_history && callbacks.is_none() {
+            return self.run_workflow(workflow, start_node_id, context).await;
+        }
+        
+        let project_slug = context.project_slug.clone();
+        let metadata_str = |key: &str| context.metadata.get(key).and_then(|v| v.as_str()).map(|v| v.to_string());
+        let trigger_type = metadata_str("trigger_type").unwrap_or_else(|| "webhook".to_string());
+        let replay_of = metadata_str("replay_of");
+        
+        // History is best-effort: a failed insert must not fail the execution itself
+        let history = self.executor.project_db_manager();
+        let recorded = match self.record_history {
+            true => Some(history.record_execution_start(&project_slug, &NewExecution {
+                id: &execution_id,
+                workflow_id: &workflow.workflow.id,
+                start_node_id,
+                trigger_type: &trigger_type,
+                payload: &TriggerPayload::from_context(&context),
+                replay_of: replay_of.as_deref(),
+            }).await),
+            false => None,
+        };
+        if let Some(Err(e)) = &recorded {
+            tracing::warn!("⚠️ Failed to record execution {} for '{}': {}", execution_id, workflow.workflow.id, e);
+        }
+        
+        let started_at = chrono::Utc::now();
+        let started = std::time::Instant::now();
+        let result = self.run_workflow(workflow, start_node_id, context).await;
+        let duration_ms = started.elapsed().as_millis() as u64;
+        let termination = Termination::of_run(&result);
+        
+        if let Some(Ok(())) = recorded {
+            if let Err(e) = history.record_execution_finish(&project_slug, &execution_id, &termination, duration_ms).await {
+                tracing::warn!("⚠️ Failed to finish execution record {}: {}", execution_id, e);
+            }
+        }
+        
+        if let Some((sender, settings)) = callbacks {
+            let output = result.as_ref().ok().map(|result| &result.data);
+            let error = match &termination {
+                Termination::Failed { error } => Some(error.clone()),
+                _ => None,
+            };
+            sender.send(settings, RunSummary {
+                event: if error.is_none() { "execution.completed" } else { "execution.failed" },
+                execution_id,
+                workflow_id: workflow.workflow.id.clone(),
+                workflow_name: workflow.workflow.name.clone(),
+                project: project_slug,
+                start_node_id: start_node_id.to_string(),
+                trigger_type,
+                status: termination.status(),
+                error,
+                termination,
+                started_at: started_at.to_rfc3339(),
+                finished_at: chrono::Utc::now().to_rfc3339(),
+                duration_ms,
+                output_items: output.map_or(0, Vec::len),
+                output: output.filter(|_| settings.include_output).cloned(),
+            }, |pin| self.executor.resolve_secret(&workflow.workflow.project, pin));
+        }
+        
+        result
+    }
+    
+    /// Pick the environment variant of a workflow for a run
+    /// 
+    /// Metadata "environment" (set from the X-Mechaway-Environment header of manual
+    /// runs) must name a profile of the workflow. Otherwise the project's default
+    /// environment applies when the workflow has a profile of that name, and the
+    /// plain definition when it hasn't. The chosen environment is kept in metadata.
+    pub async fn select_environment<'a>(
+        &self,
+        workflow: &'a CompiledWorkflow,
+        context: &mut ExecutionContext,
+    ) -> Result<&'a CompiledWorkflow> {
+        if let Some(name) = context.metadata.get(ENVIRONMENT_METADATA_KEY).and_then(|e| e.as_str()) {
+            return workflow.environments.get(name)
+                .ok_or_else(|| anyhow::anyhow!("Workflow '{}' has no environment '{}'", workflow.workflow.id, name));
+        }
+        if workflow.environments.is_empty() {
+            return Ok(workflow);
+        }
+        
+        let settings = self.executor.project_db_manager().get_project_settings(&context.project_slug).await?;
+        match project_environment(&settings).and_then(|name| Some((workflow.environments.get(&name)?, name))) {
+            Some((variant, name)) => {
+                tracing::debug!("🌐 Running workflow '{}' in environment '{}'", workflow.workflow.id, name);
+                context.metadata.insert(ENVIRONMENT_METADATA_KEY.to_string(), json!(name));
+                Ok(variant)
+            }
+            None => Ok(workflow),
+        }
+    }
+    
+    /// Run a workflow under its log policy (shared by top-level runs and sub-workflows)
+    async fn run_workflow(
+        &self,
+        workflow: &CompiledWorkflow,
+        start_node_id: &str,
+        mut context: ExecutionContext,
+    ) -> Result<ExecutionResult> {
+        let started = std::time::Instant::now();
+        context.vars = Arc::clone(&workflow.variables);
+        // Sub-workflows arrive with the calling run's budget
+        if !context.budget.is_set() {
+            context.budget = RunBudget::new(self.resource_limits.for_workflow(&workflow.workflow.settings));
+        }
+        let transaction = context.transaction.clone();
+        let result = LogPolicy::from_settings(&workflow.workflow.settings)
+            .scope(self.walk_plan(workflow, start_node_id, context))
+            .await;
+        
+        // ATOMICITY: A transaction still open at the end (failed run or missing commit) is discarded
+        match transaction.rollback().await {
+            Ok(true) => tracing::warn!("↩️ Rolled back uncommitted transaction of workflow '{}'", workflow.workflow.id),
+            Ok(false) => {}
+            Err(e) => tracing::warn!("⚠️ Failed to roll back transaction of workflow '{}': {}", workflow.workflow.id, e),
+        }
+        if let Some(profiler) = &self.profiler {
+            profiler.record_workflow(&workflow.workflow.id, started.elapsed(), result.is_err());
+        }
+        result
+    }
+    
+    /// Walk the execution plan
+    async fn walk_plan(
+        &self,
+        workflow: &CompiledWorkflow,
+        start_node_id: &str,
+        mut context: ExecutionContext,
     ) -> Result<ExecutionResult> {
         let workflow_start_time = std::time::Instant::now();
         
         tracing::info!("🚀 Starting workflow execution: {} from node: {}", 
             workflow.workflow.id, start_node_id);
         
-        // Build petgraph DAG from workflow definition
-        tracing::debug!("📊 Building workflow DAG with {} nodes and {} edges", 
-            workflow.workflow.nodes.len(), workflow.workflow.edges.len());
-        let graph = self.build_workflow_graph(&workflow.workflow)?;
-        
-        // Find the start node index
-        let start_index = graph.node_id_to_index.get(start_node_id)
+        // PRECOMPILED: The DAG, toposort and reachability were computed at registry compile time
+        let plan = &workflow.plan;
+        let start_index = plan.node_id_to_index.get(start_node_id)
             .ok_or_else(|| anyhow::anyhow!("Start node not found: {}", start_node_id))?;
-
-        // Get topological order for execution
-        tracing::debug!("🔄 Computing topological execution order");
-        let topo_order = toposort(&graph.graph, None)
-            .map_err(|_| anyhow::anyhow!("Workflow contains cycles - must be a DAG"))?;
-        
-        let unknown_name = "unknown".to_string();
-        let node_order: Vec<String> = topo_order.iter()
-            .map(|&idx| graph.index_to_node_id.get(&idx).unwrap_or(&unknown_name).clone())
-            .collect();
-        tracing::debug!("📋 Execution order: {:?}", node_order);
-
-        // Find position of start node in topological order  
-        let start_pos = topo_order.iter().position(|&idx| idx == *start_index)
-            .ok_or_else(|| anyhow::anyhow!("Start node not found in topological order"))?;
-        
-        // If the start node is a WebhookNode or CronTrigger, we need to start from the next position
-        // because these are just entry points and don't process data
-        let execution_start_pos = if matches!(graph.graph[*start_index].node_type, 
-                crate::workflow::NodeType::Webhook | crate::workflow::NodeType::CronTrigger) {
-            tracing::debug!("🎯 Start node is WebhookNode/CronTrigger, beginning execution from next connected node");
-            let next_pos = start_pos + 1;
-            if next_pos >= topo_order.len() {
-                return Err(anyhow::anyhow!("Start node has no connected processing nodes"));
-            }
-            next_pos
-        } else {
-            start_pos
-        };
+        let nodes_to_execute = plan.execution_order(start_node_id)?;
         
-        tracing::debug!("🎯 Starting execution from position {} in topology", execution_start_pos);
+        if plan.graph[*start_index].disabled {
+            return Err(anyhow::anyhow!("Start node '{}' is disabled", start_node_id));
+        }
 
-        // Find nodes reachable from the start node (not all nodes!)
-        let reachable_nodes = self.find_reachable_nodes(&graph.graph, *start_index);
-        
-        // Filter topological order to only include reachable nodes (excluding webhooks and cron triggers)
-        let nodes_to_execute: Vec<petgraph::graph::NodeIndex> = topo_order.iter()
-            .filter(|&&idx| reachable_nodes.contains(&idx) && 
-                   !matches!(graph.graph[idx].node_type, 
-                           crate::workflow::NodeType::Webhook | crate::workflow::NodeType::CronTrigger))
-            .cloned()
-            .collect();
+        // Entry points (Webhook, CronTrigger, PollingTrigger, FormTrigger, ChatTrigger, EventTrigger, MQTTTrigger, WebSocketTrigger, OpcUaTrigger, SerialTrigger, BLETrigger, MCPTrigger) don't process data themselves
+        if plan.graph[*start_index].node_type.is_entry_point() && nodes_to_execute.is_empty() {
+            return Err(anyhow::anyhow!("Start node has no connected processing nodes"));
+        }
             
         tracing::info!("🔄 Executing {} nodes reachable from {}", nodes_to_execute.len(), start_node_id);
         
+        // The start node's output is the run's input
+        if plan.is_output_referenced(*start_index) {
+            context.node_outputs.insert(start_node_id.to_string(), Arc::new(context.data.clone()));
+        }
+
         // Execute the filtered nodes
-        let mut current_result = ExecutionResult {
-            data: context.data.clone(),
+        // ZERO-COPY: Data moves from node to node; `context` keeps only the shared
+        // request parts (files, query, headers) so cloning it per node stays cheap
+        let input = ExecutionResult {
+            data: std::mem::take(&mut context.data),
             metadata: context.metadata.clone(),
-            should_continue: true,
+            termination: Termination::Completed,
         };
+        let seed = (!nodes_to_execute.contains(start_index)).then_some(*start_index);
+        let trace = Mutex::new(RunTrace::new(plan.leaves(&nodes_to_execute)));
+        let mut current_result = self.walk_steps(workflow, &nodes_to_execute, seed, input, &context, &trace).await?;
+        let trace = trace.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner());
+        current_result.metadata.insert(TRACE_METADATA_KEY.to_string(), trace.into_json());
 
-        for (step_num, &node_index) in nodes_to_execute.iter().enumerate() {
-            if !current_result.should_continue {
-                tracing::warn!("⏸️ Workflow execution stopped at step {} - should_continue = false", step_num);
-                break;
+        let workflow_duration = workflow_start_time.elapsed();
+        tracing::info!("🎉 Workflow '{}' execution completed successfully in {:?}", 
+            workflow.workflow.id, workflow_duration);
+
+        Ok(current_result)
+    }
+
+    /// Execute the plan's steps, each node with the output of the nodes that feed it
+    /// 
+    /// `seed` is the node whose result `input` is (the trigger, or a streamed
+    /// node's batch); without one, `input` goes to the steps nothing feeds. A node
+    /// starts as soon as the nodes feeding it have finished, so the branches after
+    /// a fork run concurrently (one at a time when a transaction may be open); the
+    /// first node to fail fails the walk once the nodes already running finished. Output ports,
+    /// split outputs and joins are routed by runtime::branches. A node stopping
+    /// early ends its own branch. A cursor-mode PGQuery or batched NdjsonParse
+    /// streams its rows: the steps downstream of it run once per batch. The walk's
+    /// result is the output of the last node that ran, in plan order.
+    fn walk_steps<'a>(
+        &'a self,
+        workflow: &'a CompiledWorkflow,
+        steps: &'a [NodeIndex],
+        seed: Option<NodeIndex>,
+        input: ExecutionResult,
+        context: &'a ExecutionContext,
+        trace: &'a Mutex<RunTrace>,
+    ) -> BoxFuture<'a, Result<ExecutionResult>> {
+        Box::pin(async move {
+            let plan = &workflow.plan;
+        
+            // SPILL: Oversized outputs wait on disk until the nodes they feed are ready
+            let spill_threshold = workflow.workflow.settings.spill_threshold_bytes
+                .unwrap_or(self.spill_threshold_bytes);
+            let mut router = BranchRouter::new(Arc::clone(plan), steps, seed, input);
+            // OUTPUTS: Nodes read by `$node("id")` pins leave their output to the later steps
+            let mut context = Cow::Borrowed(context);
+            let mut running: FuturesUnordered<BoxFuture<'_, (NodeIndex, StepOutcome)>> = FuturesUnordered::new();
+            let mut last_finished = None;
+
+            // TRANSACTIONS: Nodes share the execution's open transaction, so a walk that
+            // may run inside one starts its nodes one at a time, in plan order
+            let serial = context.transaction.is_open()
+                || steps.iter().any(|&index| matches!(plan.graph[index].node_type, NodeType::TransactionBegin));
+            // FAILURE: The first error stops new nodes from starting; the nodes already
+            // running finish (so their side effects complete) before the walk fails
+            let mut failure: Option<anyhow::Error> = None;
+
+            loop {
+                if failure.is_none() {
+                    let started: Result<()> = async {
+                        // A shared borrow of the set held across an await would make the walk !Send
+                        let running = &mut running;
+                        // Start every node whose inputs are complete
+                        while running.is_empty() || !serial {
+                            let Some(node_index) = router.next_ready() else {
+                                break;
+                            };
+                            let node = &plan.graph[node_index];
+                            let node_type = format!("{:?}", node.node_type);
+                            let mut input = match router.take_input(node_index).await? {
+                                NodeInput::Run(input) => input,
+                                // PORTS: Nodes only fed through ports the run didn't take are skipped
+                                NodeInput::NotTaken => {
+                                    tracing::info!("⤵️ Skipping node '{}' (behind a port not taken)", node.id);
+                                    lock(trace).push(&node.id, &node_type, NodeStatus::NotTaken, 0, Duration::ZERO);
+                                    continue;
+                                }
+                                NodeInput::NotReached => continue,
+                            };
+
+                            // Disabled nodes pass their input through unchanged or end their branch
+                            if node.disabled {
+                                match node.disabled_mode {
+                                    DisabledMode::Passthrough => {
+                                        tracing::info!("⏭️ Skipping disabled node '{}'", node.id);
+                                        lock(trace).push(&node.id, &node_type, NodeStatus::Skipped, input.data.len(), Duration::ZERO);
+                                    }
+                                    DisabledMode::Block => {
+                                        tracing::info!("⛔ Disabled node '{}' blocks its branch", node.id);
+                                        input.termination = Termination::stopped(&node.id, "Disabled node blocks the run");
+                                        lock(trace).push(&node.id, &node_type, NodeStatus::Blocked, 0, Duration::ZERO);
+                                    }
+                                }
+                                router.finish(node_index, input);
+                                continue;
+                            }
+
+                            let mut node_context = context.as_ref().clone();
+                            node_context.data = input.data;
+                            node_context.metadata = input.metadata;
+
+                            // STREAMING: A cursor-mode PGQuery or batched NdjsonParse runs its downstream steps per batch
+                            let mocked = node_context.mocks.as_ref().is_some_and(|mocks| mocks.contains_key(&node.id));
+                            let batch_size = match node.node_type {
+                                NodeType::PGQuery => cursor_batch_size(&node.params),
+                                NodeType::NdjsonParse => ndjson_batch_size(&node.params),
+                                _ => Ok(None),
+                            }.map_err(|e| NodeExecutionError::wrap(node, e))?;
+                            if let (Some(batch_size), false) = (batch_size, mocked) {
+                                let downstream = plan.downstream(node_index, steps);
+                                router.claim(&downstream);
+                                running.push(Box::pin(async move {
+                                    let result = self.stream_batches(workflow, node_index, &downstream, batch_size, node_context, trace).await;
+                                    (node_index, StepOutcome::Streamed(result))
+                                }));
+                                continue;
+                            }
+
+                            tracing::info!("📍 Executing node '{}' (type: {:?})", node.id, node.node_type);
+                            running.push(Box::pin(async move {
+                                let node_start_time = std::time::Instant::now();
+                                let result = self.execute_step(node, node_context).await;
+                                (node_index, StepOutcome::Executed(result, node_start_time.elapsed()))
+                            }));
+                        }
+
+                        // An output still waiting for nodes that aren't ready yet goes to disk when oversized
+                        if let Some(node_index) = last_finished.take().filter(|&index| router.is_waiting(index)) {
+                            let project_dir = self.executor.project_db_manager().project_dir(&context.project_slug)?;
+                            router.spill_if_oversized(node_index, spill_threshold, &project_dir).await
+                                .map_err(|e| anyhow::anyhow!("Failed to spill output of '{}': {}", plan.graph[node_index].id, e))?;
+                        }
+                        Ok(())
+                    }.await;
+                    failure = started.err();
+                }
+
+                let Some((node_index, outcome)) = running.next().await else {
+                    break;
+                };
+                let node = &plan.graph[node_index];
+                let result = match outcome {
+                    StepOutcome::Executed(result, node_duration) => {
+                        if let Some(profiler) = &self.profiler {
+                            profiler.record_node(&workflow.workflow.id, &node.id, &format!("{:?}", node.node_type),
+                                node_duration, result.is_err());
+                        }
+                        result.and_then(|result| {
+                            context.budget.check_output(&node.id, &result.data)?;
+                            lock(trace).record(&node.id, &format!("{:?}", node.node_type), &result, node_duration);
+                            tracing::info!("✅ Node '{}' completed in {:?}", node.id, node_duration);
+                            Ok(result)
+                        })
+                    }
+                    // The streamed node's downstream steps already ran: its result is the last batch's
+                    StepOutcome::Streamed(result) => result,
+                };
+                match (result, &failure) {
+                    (Ok(result), None) => {
+                        if plan.is_output_referenced(node_index) {
+                            context.to_mut().node_outputs.insert(node.id.clone(), Arc::new(result.data.clone()));
+                        }
+                        router.finish(node_index, result);
+                        last_finished = Some(node_index);
+                    }
+                    // A sibling of a failed node finished; nothing downstream of it starts
+                    (Ok(_), Some(_)) => {}
+                    (Err(e), None) => {
+                        if !running.is_empty() {
+                            tracing::warn!("⏳ Node '{}' failed, waiting for {} running node(s) before failing the run", node.id, running.len());
+                        }
+                        failure = Some(e);
+                    }
+                    (Err(e), Some(_)) => tracing::warn!("❌ Node '{}' failed after the run had already failed: {}", node.id, e),
+                }
             }
 
-            let node = &graph.graph[node_index];
-            let unknown_name = "unknown".to_string();
-            let node_name = graph.index_to_node_id.get(&node_index).unwrap_or(&unknown_name);
-            
-            tracing::info!("📍 Step {}/{}: Executing node '{}' (type: {:?})", 
-                step_num + 1, nodes_to_execute.len(), node_name, node.node_type);
-            
-            // Update execution context with current result
-            context.data = current_result.data.clone();
-            context.metadata = current_result.metadata.clone();
-            
-            // Skip any remaining webhook nodes during execution (they shouldn't be in processing flow)
-            if matches!(node.node_type, crate::workflow::NodeType::Webhook) {
-                tracing::debug!("⏭️ Skipping webhook node '{}' during execution", node_name);
-                continue;
+            if let Some(e) = failure {
+                return Err(e);
             }
+            router.into_result().await
+        })
+    }
 
-            // Execute the current node
-            let node_start_time = std::time::Instant::now();
-            
-            current_result = self.executor.execute_node(node, context.clone()).await
-                .map_err(|e| anyhow::anyhow!("Node execution failed for '{}': {}", node.id, e))?;
+    /// Stream a node's rows through the steps downstream of it, one batch at a time
+    /// 
+    /// Only one batch is in memory at once. Each batch's metadata carries "stream"
+    /// ({ id, node_id, batch }) so writers can append across batches. The result is
+    /// the last batch's output; metadata "pg_cursor" / "ndjson" reports the batch
+    /// and row counts.
+    async fn stream_batches(
+        &self,
+        workflow: &CompiledWorkflow,
+        node_index: NodeIndex,
+        downstream: &[NodeIndex],
+        batch_size: usize,
+        mut node_context: ExecutionContext,
+        trace: &Mutex<RunTrace>,
+    ) -> Result<ExecutionResult> {
+        let node = &workflow.plan.graph[node_index];
+        let fail = |e: anyhow::Error| NodeExecutionError::wrap(node, e);
+        let node_type = format!("{:?}", node.node_type);
+        let mut fetch_time = std::time::Duration::ZERO;
+        
+        let started = std::time::Instant::now();
+        let opened = match node.node_type {
+            NodeType::NdjsonParse => self.executor.open_ndjson_reader(node, &node_context, batch_size).await
+                .map(BatchStream::Ndjson),
+            _ => self.executor.open_pgquery_cursor(node, &node_context, batch_size).await
+                .map(BatchStream::Cursor),
+        };
+        fetch_time += started.elapsed();
+        if let (Err(_), Some(profiler)) = (&opened, &self.profiler) {
+            profiler.record_node(&workflow.workflow.id, &node.id, &node_type, fetch_time, true);
+        }
+        let mut stream = opened.map_err(fail)?;
+        // Downstream steps only see the batches, not the node's own input
+        node_context.data = Vec::new();
+        let referenced = workflow.plan.is_output_referenced(node_index);
+        let stream_id = uuid::Uuid::new_v4().to_string();
+        
+        let mut last_result = ExecutionResult {
+            data: Vec::new(),
+            metadata: node_context.metadata.clone(),
+            termination: Termination::Completed,
+        };
+        loop {
+            let started = std::time::Instant::now();
+            let batch = stream.next_batch().await.map_err(fail)?;
+            fetch_time += started.elapsed();
+            let Some(batch) = batch else { break };
             
-            let node_duration = node_start_time.elapsed();
-            tracing::info!("✅ Node '{}' completed in {:?}", node_name, node_duration);
+            let (batches, rows) = stream.counts();
+            tracing::info!("📦 {} '{}' batch {} ({} rows, {} total)", node_type, node.id, batches, batch.len(), rows);
+            let mut metadata = node_context.metadata.clone();
+            metadata.insert("stream".to_string(), json!({
+                "id": stream_id,
+                "node_id": node.id,
+                "batch": batches,
+            }));
+            // `$node("id")` pins read the node's current batch
+            if referenced {
+                node_context.node_outputs.insert(node.id.clone(), Arc::new(batch.clone()));
+            }
+            let batch_result = ExecutionResult {
+                data: batch,
+                metadata,
+                termination: Termination::Completed,
+            };
+            last_result = self.walk_steps(workflow, downstream, Some(node_index), batch_result, &node_context, trace).await?;
+            if !last_result.should_continue() {
+                tracing::warn!("⏸️ Stopping stream of '{}' after batch {}", node.id, batches);
+                break;
+            }
         }
         
-        let workflow_duration = workflow_start_time.elapsed();
-        tracing::info!("🎉 Workflow '{}' execution completed successfully in {:?}", 
-            workflow.workflow.id, workflow_duration);
+        let (batches, rows) = stream.counts();
+        let (report_key, mut report) = match &stream {
+            BatchStream::Cursor(_) => ("pg_cursor", json!({})),
+            BatchStream::Ndjson(reader) => ("ndjson", json!({ "lines": reader.lines, "skipped": reader.skipped })),
+        };
+        stream.close().await.map_err(fail)?;
+        if let Some(profiler) = &self.profiler {
+            profiler.record_node(&workflow.workflow.id, &node.id, &node_type, fetch_time, false);
+        }
+        lock(trace).push(&node.id, &node_type, NodeStatus::Success, rows as usize, fetch_time);
+        tracing::info!("✅ Node '{}' streamed {} rows in {} batches", node.id, rows, batches);
+        
+        last_result.metadata.remove("stream");
+        report["node_id"] = json!(node.id);
+        report["batches"] = json!(batches);
+        report["rows"] = json!(rows);
+        last_result.metadata.insert(report_key.to_string(), report);
+        Ok(last_result)
+    }
 
-        Ok(current_result)
+    /// Execute a single node with the given input context
+    /// 
+    /// Dispatches mocked nodes (test runs), ExecuteWorkflow orchestration and
+    /// regular nodes. Used by the main execution loop and the step debugger.
+    pub async fn execute_step(&self, node: &Node, node_context: ExecutionContext) -> Result<ExecutionResult> {
+        // TEST RUNS: Mocked nodes return their canned result instead of executing
+        let mock = node_context.mocks.as_ref()
+            .and_then(|mocks| mocks.get(&node.id))
+            .cloned();
+        
+        // ExecuteWorkflow needs the registry and engine, so it's orchestrated here
+        let node_result = if let Some(mock) = mock {
+            tracing::info!("🎭 Node '{}' is mocked", node.id);
+            match &mock.error {
+                Some(error) => Err(anyhow::anyhow!("{}", error)),
+                None => Ok(ExecutionResult {
+                    data: mock.items(),
+                    metadata: node_context.metadata,
+                    termination: Termination::Completed,
+                }),
+            }
+        } else if matches!(node.node_type, NodeType::ExecuteWorkflow) {
+            self.execute_sub_workflow(node, node_context).await
+        } else {
+            let budget = node_context.budget.clone();
+            budget.metered(&node.id, self.executor.execute_node(node, node_context)).await
+        };
+        
+        node_result.map_err(|e| {
+            // Assertion and budget errors already name their node and stay structured for the API layer
+            if e.is::<AssertionError>() || e.is::<ResourceExceeded>() {
+                e
+            } else {
+                NodeExecutionError::wrap(node, e)
+            }
+        })
     }
 
-    /// Build a petgraph DiGraph from workflow definition
+    /// Execute an ExecuteWorkflow node by running the target workflow
     /// 
-    /// Creates nodes and edges in the graph while maintaining bidirectional
-    /// mappings between node IDs and graph indices for efficient lookups.
-    fn build_workflow_graph(&self, workflow: &crate::workflow::Workflow) -> Result<WorkflowGraph> {
-        tracing::debug!("🏗️ Building workflow graph for '{}'", workflow.id);
-        
-        let mut graph = DiGraph::new();
-        let mut node_id_to_index = HashMap::new();
-        let mut index_to_node_id = HashMap::new();
-
-        // Add all nodes to the graph
-        tracing::debug!("📦 Adding {} nodes to graph", workflow.nodes.len());
-        for node in &workflow.nodes {
-            let node_index = graph.add_node(node.clone());
-            node_id_to_index.insert(node.id.clone(), node_index);
-            index_to_node_id.insert(node_index, node.id.clone());
-            tracing::debug!("  ➕ Added node: '{}' (type: {:?})", node.id, node.node_type);
-        }
-
-        // Add all edges to the graph
-        tracing::debug!("🔗 Adding {} edges to graph", workflow.edges.len());
-        for edge in &workflow.edges {
-            let from_index = node_id_to_index.get(&edge.from)
-                .ok_or_else(|| anyhow::anyhow!("Edge references unknown node: {}", edge.from))?;
-            let to_index = node_id_to_index.get(&edge.to)
-                .ok_or_else(|| anyhow::anyhow!("Edge references unknown node: {}", edge.to))?;
-            
-            graph.add_edge(*from_index, *to_index, ());
-            tracing::debug!("  🔗 Added edge: '{}' → '{}'", edge.from, edge.to);
+    /// Expected params: { "workflow_id": "wf-geocode", "start_node": "entry" }
+    /// TENANT ISOLATION: Cross-project targets must allowlist the caller project
+    /// in their settings ("allowed_callers"), otherwise the call is rejected.
+    async fn execute_sub_workflow(&self, node: &Node, context: ExecutionContext) -> Result<ExecutionResult> {
+        let target_id = node.params.get("workflow_id")
+            .and_then(|w| w.as_str())
+            .ok_or_else(|| anyhow::anyhow!("ExecuteWorkflow node '{}' missing 'workflow_id' parameter", node.id))?;
+        
+        let depth = context.metadata.get("call_depth")
+            .and_then(|d| d.as_u64())
+            .unwrap_or(0);
+        if depth >= MAX_SUB_WORKFLOW_DEPTH {
+            return Err(anyhow::anyhow!("ExecuteWorkflow nesting exceeds maximum depth of {}", MAX_SUB_WORKFLOW_DEPTH));
         }
-
-        // Validate that the graph is a DAG (no cycles)
-        tracing::debug!("🔍 Validating DAG structure (checking for cycles)");
-        if let Err(_) = toposort(&graph, None) {
-            tracing::error!("❌ Workflow contains cycles - must be a DAG");
-            return Err(anyhow::anyhow!("Workflow contains cycles - must be a DAG"));
+        
+        let target = self.registry.get_workflow(target_id)
+            .ok_or_else(|| anyhow::anyhow!("ExecuteWorkflow target not found: {}", target_id))?;
+        let target_project = target.workflow.project.clone();
+        
+        // Enforce the target project's caller allowlist
+        let allowed = self.executor.project_db_manager()
+            .is_caller_allowed(&target_project, &context.project_slug).await?;
+        if !allowed {
+            tracing::warn!("🚫 Project '{}' is not allowed to trigger workflows in project '{}'", 
+                context.project_slug, target_project);
+            return Err(anyhow::anyhow!("Project '{}' is not in the allowed_callers of project '{}'", 
+                context.project_slug, target_project));
         }
         
-        tracing::debug!("✅ DAG validation successful - no cycles detected");
-
-        Ok(WorkflowGraph {
-            graph,
-            node_id_to_index,
-            index_to_node_id,
+        let start_node_id = match node.params.get("start_node").and_then(|s| s.as_str()) {
+            Some(start) => start.to_string(),
+            None => target.start_node_ids.first()
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("ExecuteWorkflow target '{}' has no start node", target_id))?,
+        };
+        
+        // Input pins (if any) build the sub-workflow data array, otherwise forward data as-is
+        let data: Vec<Value> = match &node.inputs {
+            Some(_) => self.executor.resolve_input_pins(node, &context)?,
+            None => context.data.clone(),
+        };
+        
+        let mut sub_context = ExecutionContext::from_array_data(target_id.to_string(), data, target_project.clone());
+        sub_context.metadata.insert("parent_workflow_id".to_string(), 
+            context.metadata.get("workflow_id").cloned().unwrap_or(Value::Null));
+        sub_context.metadata.insert("caller_project".to_string(), json!(context.project_slug));
+        sub_context.metadata.insert("call_depth".to_string(), json!(depth + 1));
+        // Sub-workflows are part of the calling run (audit columns, logs)
+        if let Some(execution_id) = context.metadata.get("execution_id") {
+            sub_context.metadata.insert("execution_id".to_string(), execution_id.clone());
+        }
+        // Binary references in the forwarded items must stay resolvable
+        sub_context.binaries = context.binaries.clone();
+        sub_context.budget = context.budget.clone();
+        
+        tracing::info!("🔀 Executing sub-workflow '{}' (project: {}) from node '{}'", 
+            target_id, target_project, node.id);
+        
+        // Sub-workflows follow the calling run's environment when they have a profile of that name
+        let target = match context.metadata.get(ENVIRONMENT_METADATA_KEY) {
+            Some(environment) => {
+                sub_context.metadata.insert(ENVIRONMENT_METADATA_KEY.to_string(), environment.clone());
+                environment.as_str().and_then(|name| target.environments.get(name)).cloned().unwrap_or(target)
+            }
+            None => self.select_environment(&target, &mut sub_context).await?.clone(),
+        };
+        
+        let sub_result = Box::pin(self.run_workflow(&target, &start_node_id, sub_context)).await?;
+        
+        // Sub-workflow output becomes this node's output; caller metadata is preserved
+        Ok(ExecutionResult {
+            data: sub_result.data,
+            metadata: context.metadata,
+            termination: sub_result.termination,
         })
     }
 }
+
+/// Source of a streamed node's batches
+enum BatchStream {
+    Cursor(PgCursor),
+    Ndjson(NdjsonReader),
+}
+
+impl BatchStream {
+    async fn next_batch(&mut self) -> Result<Option<Vec<Value>>> {
+        match self {
+            BatchStream::Cursor(cursor) => cursor.next_batch().await,
+            BatchStream::Ndjson(reader) => reader.next_batch().await,
+        }
+    }
+
+    /// Batches and rows returned so far
+    fn counts(&self) -> (u64, u64) {
+        match self {
+            BatchStream::Cursor(cursor) => (cursor.batches, cursor.rows),
+            BatchStream::Ndjson(reader) => (reader.batches, reader.rows),
+        }
+    }
+
+    async fn close(self) -> Result<()> {
+        match self {
+            BatchStream::Cursor(cursor) => cursor.close().await,
+            BatchStream::Ndjson(_) => Ok(()),
+        }
+    }
+}
+
+/// How a started node finished
+enum StepOutcome {
+    /// Executed once (with its run time)
+    Executed(Result<ExecutionResult>, Duration),
+    /// Streamed its rows through its downstream steps
+    Streamed(Result<ExecutionResult>),
+}
+
+/// Lock the run trace (a panic elsewhere doesn't lose it)
+fn lock(trace: &Mutex<RunTrace>) -> MutexGuard<'_, RunTrace> {
+    trace.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
@@ -0,0 +1,163 @@
+//! In-process event bus for EmitEvent / EventTrigger nodes
+//! 
+//! Lets workflows communicate without exposing HTTP webhooks to themselves.
+//! Events are scoped to a project: an EmitEvent in project "ecommerce" only
+//! starts EventTrigger workflows that also belong to "ecommerce".
+
+use crate::{
+    runtime::engine::ExecutionEngine,
+    workflow::{
+        registry::WorkflowRegistry,
+        types::{ExecutionContext, NodeType},
+    },
+};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// Buffered events per subscriber before slow consumers start lagging
+const EVENT_BUS_CAPACITY: usize = 1024;
+
+/// Maximum emit -> trigger -> emit chain length (guards against event loops)
+pub const MAX_EVENT_DEPTH: u64 = 8;
+
+/// A single event published on the bus
+#[derive(Debug, Clone)]
+pub struct InternalEvent {
+    /// Project the event belongs to (subscribers must be in the same project)
+    pub project_slug: String,
+    /// Event name (e.g., "order.created")
+    pub name: String,
+    /// Event payload items (n8n-style array)
+    pub data: Vec<Value>,
+    /// Workflow that emitted the event
+    pub source_workflow_id: Option<String>,
+    /// Number of event hops that led to this event
+    pub depth: u64,
+}
+
+/// Project-scoped publish/subscribe bus backed by a tokio broadcast channel
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<InternalEvent>,
+}
+
+impl EventBus {
+    /// Create a new in-process event bus
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUS_CAPACITY);
+        Self { sender }
+    }
+
+    /// Publish an event, returning the number of active bus listeners
+    pub fn emit(&self, event: InternalEvent) -> usize {
+        tracing::debug!("📣 Emitting event '{}' in project '{}'", event.name, event.project_slug);
+        // No listeners is not an error - events are fire-and-forget
+        self.sender.send(event).unwrap_or(0)
+    }
+
+    /// Subscribe to all events on the bus
+    pub fn subscribe(&self) -> broadcast::Receiver<InternalEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Background service that starts EventTrigger workflows for emitted events
+pub struct EventTriggerService {
+    bus: Arc<EventBus>,
+    registry: Arc<WorkflowRegistry>,
+    engine: Arc<ExecutionEngine>,
+}
+
+impl EventTriggerService {
+    /// Create new event trigger service
+    pub fn new(bus: Arc<EventBus>, registry: Arc<WorkflowRegistry>, engine: Arc<ExecutionEngine>) -> Self {
+        Self { bus, registry, engine }
+    }
+
+    /// Start dispatching events in the background
+    /// 
+    /// HOT-RELOAD: Subscribers are resolved from the registry per event, so new or
+    /// updated EventTrigger nodes take effect without re-registration.
+    pub fn start(&self) {
+        let mut receiver = self.bus.subscribe();
+        let registry = Arc::clone(&self.registry);
+        let engine = Arc::clone(&self.engine);
+
+        tokio::spawn(async move {
+            tracing::info!("📣 Event trigger service started");
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => Self::dispatch(&registry, &engine, event),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!("⚠️ Event trigger service lagged, {} events dropped", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+            tracing::info!("⏹️ Event trigger service stopped");
+        });
+    }
+
+    /// Start one execution per matching EventTrigger node
+    fn dispatch(registry: &Arc<WorkflowRegistry>, engine: &Arc<ExecutionEngine>, event: InternalEvent) {
+        if engine.maintenance_mode().is_enabled() {
+            tracing::debug!("🚧 Event '{}' not delivered: maintenance mode", event.name);
+            return;
+        }
+        if event.depth > MAX_EVENT_DEPTH {
+            tracing::warn!("🔁 Dropping event '{}' - chain depth {} exceeds {}",
+                event.name, event.depth, MAX_EVENT_DEPTH);
+            return;
+        }
+
+        for workflow in registry.get_all_workflows() {
+            if workflow.project != event.project_slug {
+                continue;
+            }
+
+            for node in &workflow.nodes {
+                if !matches!(node.node_type, NodeType::EventTrigger) {
+                    continue;
+                }
+                let subscribed = node.params.get("event").and_then(|e| e.as_str());
+                if !matches!(subscribed, Some(name) if name == event.name || name == "*") {
+                    continue;
+                }
+
+                let Some(compiled) = registry.get_workflow(&workflow.id) else { continue };
+                if compiled.active_snooze().is_some() {
+                    tracing::debug!("💤 Event '{}' not delivered to snoozed workflow {}", event.name, workflow.id);
+                    continue;
+                }
+                let trigger_node_id = node.id.clone();
+                let engine = Arc::clone(engine);
+
+                let mut context = ExecutionContext::from_array_data(
+                    workflow.id.clone(), event.data.clone(), event.project_slug.clone());
+                context.metadata.insert("trigger_type".to_string(), json!("event"));
+                context.metadata.insert("trigger_node_id".to_string(), json!(trigger_node_id));
+                context.metadata.insert("event_name".to_string(), json!(event.name));
+                context.metadata.insert("event_depth".to_string(), json!(event.depth));
+                if let Some(source) = &event.source_workflow_id {
+                    context.metadata.insert("event_source_workflow_id".to_string(), json!(source));
+                }
+
+                tokio::spawn(async move {
+                    tracing::info!("🚀 Executing event-triggered workflow: {} ({})",
+                        compiled.workflow.id, trigger_node_id);
+                    if let Err(e) = engine.execute_workflow(&compiled, &trigger_node_id, context).await {
+                        tracing::error!("❌ Event-triggered workflow failed: {} - Error: {}",
+                            compiled.workflow.id, e);
+                    }
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,318 @@
+//! Excel node workbook reading and writing
+//! 
+//! Read turns one sheet of an .xlsx/.xls/.ods file (uploaded file or binary
+//! item) into items, keyed by the header row:
+//! 
+//! { "operation": "read", "sheet": "Orders", "skip_rows": 2 }   + inputs ["$file.upload"]
+//! 
+//! Write turns the data array into a formatted workbook (bold, frozen header
+//! row with autofilter, autofitted columns) stored in the project's uploads
+//! directory:
+//! 
+//! { "operation": "write", "sheet": "Report", "filename": "report-%Y-%m-%d.xlsx",
+//!   "columns": [{ "key": "total", "header": "Total", "num_format": "#,##0.00" }] }
+//! 
+//! Dates come out of read as "YYYY-MM-DD" / "YYYY-MM-DDTHH:MM:SS" strings, and
+//! strings in those forms (or RFC 3339, converted to UTC) are written as Excel
+//! dates. Strings are always written as text, never as formulas.
+
+use anyhow::Result;
+use bytes::Bytes;
+use calamine::{open_workbook_auto_from_rs, Data, Reader};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Timelike};
+use rust_xlsxwriter::{ExcelDateTime, Format, Workbook, Worksheet};
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashSet};
+
+/// MIME type of written workbooks
+pub const XLSX_CONTENT_TYPE: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+
+/// Excel's limit on the text in one cell
+const MAX_CELL_CHARS: usize = 32_767;
+
+/// Excel's row limit per sheet (including the header row)
+const MAX_ROWS: usize = 1_048_576;
+
+/// Excel node operation
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExcelOperation {
+    Read,
+    Write,
+}
+
+impl ExcelOperation {
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        match params.get("operation").and_then(|o| o.as_str()).unwrap_or("read") {
+            "read" => Ok(ExcelOperation::Read),
+            "write" => Ok(ExcelOperation::Write),
+            other => Err(anyhow::anyhow!("Unknown operation '{}' (read, write)", other)),
+        }
+    }
+}
+
+/// Parsed params of a read operation
+#[derive(Debug, Clone)]
+pub struct ExcelReadParams {
+    /// Sheet name (default: first sheet)
+    pub sheet: Option<String>,
+    /// First row holds column names (otherwise keys are column letters)
+    pub header: bool,
+    /// Rows to skip before the header / data (titles above a table)
+    pub skip_rows: usize,
+}
+
+impl ExcelReadParams {
+    pub fn from_node_params(params: &Value) -> Self {
+        Self {
+            sheet: params.get("sheet").and_then(|s| s.as_str()).map(str::to_string),
+            header: params.get("header").and_then(|h| h.as_bool()).unwrap_or(true),
+            skip_rows: params.get("skip_rows").and_then(|s| s.as_u64()).unwrap_or(0) as usize,
+        }
+    }
+}
+
+/// Read one sheet into items, skipping empty rows
+pub fn read_workbook(content: Bytes, params: &ExcelReadParams) -> Result<Vec<Value>> {
+    let mut workbook = open_workbook_auto_from_rs(std::io::Cursor::new(content))
+        .map_err(|e| anyhow::anyhow!("Not a readable workbook: {}", e))?;
+    let sheet = match &params.sheet {
+        Some(sheet) => sheet.clone(),
+        None => workbook.sheet_names().first().cloned()
+            .ok_or_else(|| anyhow::anyhow!("Workbook has no sheets"))?,
+    };
+    let range = workbook.worksheet_range(&sheet).map_err(|e| match workbook.sheet_names() {
+        names if !names.contains(&sheet) => anyhow::anyhow!("No sheet '{}' (sheets: {})", sheet, names.join(", ")),
+        _ => anyhow::anyhow!("Can't read sheet '{}': {}", sheet, e),
+    })?;
+    // The range starts at the first used cell, not necessarily A1
+    let (_, first_column) = range.start().unwrap_or((0, 0));
+
+    let mut rows = range.rows().skip(params.skip_rows);
+    let keys: Vec<String> = match params.header {
+        true => match rows.next() {
+            Some(header) => header_keys(header),
+            None => return Ok(Vec::new()),
+        },
+        false => (0..range.width()).map(|offset| column_letters(first_column as usize + offset)).collect(),
+    };
+
+    Ok(rows
+        .filter(|row| row.iter().any(|cell| *cell != Data::Empty))
+        .map(|row| Value::Object(keys.iter().cloned().zip(row.iter().map(cell_to_json)).collect()))
+        .collect())
+}
+
+/// Unique keys from a header row ("column_3" for blank cells, "_2" suffixes for repeats)
+fn header_keys(row: &[Data]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    row.iter().enumerate().map(|(index, cell)| {
+        let name = match cell_to_json(cell) {
+            Value::String(text) if !text.trim().is_empty() => text.trim().to_string(),
+            Value::Null => format!("column_{}", index + 1),
+            other => other.to_string(),
+        };
+        let mut key = name.clone();
+        let mut suffix = 2;
+        while !seen.insert(key.clone()) {
+            key = format!("{}_{}", name, suffix);
+            suffix += 1;
+        }
+        key
+    }).collect()
+}
+
+/// Spreadsheet column letters of a zero-based index (0 -> "A", 27 -> "AB")
+fn column_letters(mut index: usize) -> String {
+    let mut letters = Vec::new();
+    loop {
+        letters.push(b'A' + (index % 26) as u8);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    letters.reverse();
+    String::from_utf8(letters).unwrap_or_default()
+}
+
+fn cell_to_json(cell: &Data) -> Value {
+    match cell {
+        Data::Empty | Data::Error(_) => Value::Null,
+        Data::Int(n) => json!(n),
+        // Excel stores every number as a float
+        Data::Float(f) if f.fract() == 0.0 && f.abs() < 9e15 => json!(*f as i64),
+        Data::Float(f) => json!(f),
+        Data::String(text) => json!(text),
+        Data::Bool(flag) => json!(flag),
+        Data::DateTime(datetime) if datetime.is_duration() => {
+            json!(datetime.as_duration().map(|d| d.num_milliseconds() as f64 / 1000.0))
+        }
+        Data::DateTime(datetime) => match datetime.as_datetime() {
+            Some(datetime) if datetime.num_seconds_from_midnight() == 0 && datetime.nanosecond() == 0 => {
+                json!(datetime.format("%Y-%m-%d").to_string())
+            }
+            Some(datetime) => json!(datetime.format("%Y-%m-%dT%H:%M:%S").to_string()),
+            None => json!(datetime.as_f64()),
+        },
+        Data::DateTimeIso(text) | Data::DurationIso(text) => json!(text),
+    }
+}
+
+/// One written column
+#[derive(Debug, Clone)]
+pub struct ExcelColumn {
+    /// Item key
+    pub key: String,
+    /// Header text (default: the key)
+    pub header: String,
+    /// Excel number format for numbers and dates (e.g. "#,##0.00", "dd.mm.yyyy")
+    pub num_format: Option<String>,
+    /// Column width in characters (default: autofit)
+    pub width: Option<f64>,
+}
+
+/// Parsed params of a write operation
+#[derive(Debug, Clone)]
+pub struct ExcelWriteParams {
+    pub sheet: String,
+    /// Declared columns (None: every key of the items, sorted)
+    pub columns: Option<Vec<ExcelColumn>>,
+    /// Filename template, strftime specifiers expanded with the current UTC time
+    pub filename: Value,
+}
+
+impl ExcelWriteParams {
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        let columns = match params.get("columns") {
+            None | Some(Value::Null) => None,
+            Some(Value::Array(specs)) if !specs.is_empty() => Some(specs.iter().map(|spec| {
+                let key = match spec {
+                    Value::String(key) => key.as_str(),
+                    _ => spec["key"].as_str().ok_or_else(|| anyhow::anyhow!("Every column needs a 'key'"))?,
+                };
+                Ok(ExcelColumn {
+                    key: key.to_string(),
+                    header: spec["header"].as_str().unwrap_or(key).to_string(),
+                    num_format: spec["num_format"].as_str().map(str::to_string),
+                    width: spec["width"].as_f64(),
+                })
+            }).collect::<Result<Vec<_>>>()?),
+            Some(_) => return Err(anyhow::anyhow!("'columns' must be a non-empty array of keys or {{ key, header, num_format, width }}")),
+        };
+        Ok(Self {
+            sheet: params.get("sheet").and_then(|s| s.as_str()).unwrap_or("Sheet1").to_string(),
+            columns,
+            filename: params.get("filename").cloned().unwrap_or_else(|| json!("export-%Y%m%dT%H%M%SZ.xlsx")),
+        })
+    }
+}
+
+/// Build the workbook, returning its bytes and the number of columns
+pub fn write_workbook(items: &[Value], params: &ExcelWriteParams) -> Result<(Vec<u8>, usize)> {
+    if items.len() >= MAX_ROWS {
+        return Err(anyhow::anyhow!("{} rows don't fit in one sheet (max {})", items.len(), MAX_ROWS - 1));
+    }
+    let columns = match &params.columns {
+        Some(columns) => columns.clone(),
+        None => {
+            let mut keys = BTreeSet::new();
+            for (index, item) in items.iter().enumerate() {
+                let object = item.as_object().ok_or_else(|| anyhow::anyhow!("Item {} is not an object", index))?;
+                keys.extend(object.keys().cloned());
+            }
+            keys.into_iter()
+                .map(|key| ExcelColumn { header: key.clone(), key, num_format: None, width: None })
+                .collect()
+        }
+    };
+
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name(&params.sheet)
+        .map_err(|e| anyhow::anyhow!("Invalid sheet name '{}': {}", params.sheet, e))?;
+
+    let header_format = Format::new().set_bold();
+    let date_format = Format::new().set_num_format("yyyy-mm-dd");
+    let datetime_format = Format::new().set_num_format("yyyy-mm-dd hh:mm:ss");
+    for (col, column) in columns.iter().enumerate() {
+        worksheet.write_string_with_format(0, col as u16, &column.header, &header_format)?;
+    }
+    for (row, item) in items.iter().enumerate() {
+        for (col, column) in columns.iter().enumerate() {
+            let format = column.num_format.as_ref().map(|f| Format::new().set_num_format(f));
+            write_cell(worksheet, row as u32 + 1, col as u16, &item[&column.key], format.as_ref(), &date_format, &datetime_format)
+                .map_err(|e| anyhow::anyhow!("Row {}, column '{}': {}", row, column.key, e))?;
+        }
+    }
+
+    if !columns.is_empty() {
+        worksheet.set_freeze_panes(1, 0)?;
+        worksheet.autofilter(0, 0, items.len() as u32, columns.len() as u16 - 1)?;
+        worksheet.autofit();
+        for (col, column) in columns.iter().enumerate() {
+            if let Some(width) = column.width {
+                worksheet.set_column_width(col as u16, width)?;
+            }
+        }
+    }
+    Ok((workbook.save_to_buffer()?, columns.len()))
+}
+
+fn write_cell(
+    worksheet: &mut Worksheet,
+    row: u32,
+    col: u16,
+    value: &Value,
+    format: Option<&Format>,
+    date_format: &Format,
+    datetime_format: &Format,
+) -> Result<()> {
+    match value {
+        Value::Null => {}
+        Value::Bool(flag) => { worksheet.write_boolean(row, col, *flag)?; }
+        Value::Number(n) => {
+            let number = n.as_f64().unwrap_or_default();
+            match format {
+                Some(format) => worksheet.write_number_with_format(row, col, number, format)?,
+                None => worksheet.write_number(row, col, number)?,
+            };
+        }
+        Value::String(text) => match parse_datetime(text) {
+            Some((datetime, date_only)) => {
+                let default_format = if date_only { date_format } else { datetime_format };
+                worksheet.write_datetime_with_format(row, col, &datetime, format.unwrap_or(default_format))?;
+            }
+            None if text.chars().count() > MAX_CELL_CHARS => {
+                return Err(anyhow::anyhow!("text longer than {} characters", MAX_CELL_CHARS));
+            }
+            None => { worksheet.write_string(row, col, text)?; }
+        },
+        // Nested values are written as JSON text
+        other => {
+            let text = other.to_string();
+            if text.chars().count() > MAX_CELL_CHARS {
+                return Err(anyhow::anyhow!("value longer than {} characters", MAX_CELL_CHARS));
+            }
+            worksheet.write_string(row, col, &text)?;
+        }
+    }
+    Ok(())
+}
+
+/// Excel date for "YYYY-MM-DD", "YYYY-MM-DD[T ]HH:MM:SS" and RFC 3339 strings (and whether it's a plain date)
+fn parse_datetime(text: &str) -> Option<(ExcelDateTime, bool)> {
+    // Cheap check first, most strings aren't dates
+    let bytes = text.as_bytes();
+    if bytes.len() < 10 || bytes[4] != b'-' || bytes[7] != b'-' {
+        return None;
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        let timestamp = date.and_hms_opt(0, 0, 0)?.and_utc().timestamp();
+        return ExcelDateTime::from_timestamp(timestamp).ok().map(|datetime| (datetime, true));
+    }
+    let datetime = DateTime::parse_from_rfc3339(text).map(|t| t.naive_utc())
+        .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f"))
+        .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f"))
+        .ok()?;
+    ExcelDateTime::from_timestamp(datetime.and_utc().timestamp()).ok().map(|datetime| (datetime, false))
+}
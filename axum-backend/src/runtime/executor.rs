@@ -1,18 +1,86 @@
-/// Node execution handlers for the 3 POC node types
-/// 
-/// This module contains the actual execution logic for each node type:
-/// - WebhookNode: Entry point (handled by API layer)
-/// - FunLogicNode: Lua script execution using mlua
-/// - SimpleTableWriterNode: SQLite data storage
+//! Node execution handlers for the 3 POC node types
+//! 
+//! This module contains the actual execution logic for each node type:
+//! - WebhookNode: Entry point (handled by API layer)
+//! - FunLogicNode: Lua script execution using mlua
+//! - SimpleTableWriterNode: SQLite data storage
 
 use crate::{
-    workflow::types::{ExecutionContext, Node, NodeType},
-    project::ProjectDatabaseManager,
+    workflow::{
+        assertions::{Assertion, AssertionError},
+        binary::BinaryRef,
+        graph::{PortSplit, OUTPUT_PORT_METADATA_KEY, OUTPUT_SPLIT_METADATA_KEY},
+        params::{BodyMode, FunLogicParams, HttpClientParams, HttpMethod, ResponseFormat, SimpleTableWriterParams},
+        pins::PinExpr,
+        types::{ExecutionContext, FileInfo, Node, NodeType},
+    },
+    project::{
+        flags::FlagEvaluation,
+        pg_dyn_table::{self, AddedColumn, DriftMode, SchemaDriftPolicy},
+        simpletable_pg::{db_error, is_identifier, json_to_param, row_to_json, SimpleTableBackend},
+        snapshots::SnapshotEntry,
+        ProjectDatabaseManager,
+    },
+    runtime::{
+        ab_split::ABSplitParams,
+        events::{EventBus, InternalEvent},
+        devices::DeviceRegistry,
+        logging::{preview_data, preview_debug, preview_value},
+        anonymize::{AnonymizeParams, Anonymizer},
+        diff::{self, Change, DiffMode, DiffParams},
+        feature_flag::FeatureFlagParams,
+        memory::{MemoryOperation, MemoryParams},
+        modbus::{self, ModbusClient, ModbusParams, RegisterType},
+        opcua::{self, OpcUaClient, OpcUaOperation, OpcUaParams, ATTRIBUTE_VALUE},
+        outbox,
+        serial::{self, SerialParams, SerialPorts},
+        window::{self, ClosedWindow, WindowKind, WindowLocks, WindowOutput, WindowParams},
+        calendar::{parse_ics, CalendarOperation, CalendarParams},
+        excel::{self, ExcelOperation, ExcelReadParams, ExcelWriteParams, XLSX_CONTENT_TYPE},
+        currency::{self, CurrencyParams},
+        geoip::{self, GeoIpReaders, GeoIpSettings},
+        math::MathProgram,
+        ndjson::{self, ndjson_batch_size, NdjsonReader, NDJSON_CONTENT_TYPE},
+        parquet_write::{self, ParquetParams, PARQUET_CONTENT_TYPE},
+        pg_cursor::{cursor_batch_size, PgCursor},
+        push::{send_fcm, send_webpush, FcmAccount, PushParams, PushProvider, VapidKey},
+        request_signing::SignConfig,
+        github_api::{GitHubApiParams, GitHubTarget},
+        safe_lua,
+        stripe_api::StripeApiParams,
+        twilio::{TwilioMessage, TwilioParams},
+    },
 };
 use anyhow::Result;
+use serde::Serialize;
 use serde_json::{json, Value};
-use sqlx::{sqlite::SqlitePool, Column, Row};
-use std::{collections::HashMap, sync::Arc};
+use sqlx::{Column, Row};
+use std::{collections::{HashMap, HashSet}, sync::Arc};
+use tokio_postgres::types::ToSql;
+
+/// Lua function wrapping workflow variables in a read-only (nested) proxy table
+const READONLY_VARS: &str = r#"(function(tbl)
+    local function readonly(t)
+        return setmetatable({}, {
+            __index = function(_, key)
+                local value = t[key]
+                if type(value) == "table" then return readonly(value) end
+                return value
+            end,
+            __newindex = function(_, key) error("vars is read-only (vars." .. tostring(key) .. ")", 2) end,
+            __len = function() return #t end,
+            __pairs = function()
+                return function(_, key)
+                    local next_key, value = next(t, key)
+                    if type(value) == "table" then value = readonly(value) end
+                    return next_key, value
+                end, nil, nil
+            end,
+            __metatable = false,
+        })
+    end
+    return readonly(tbl)
+end)"#;
 
 /// Result of executing a single node
 /// 
@@ -27,8 +95,62 @@ pub struct ExecutionResult {
     pub data: Vec<Value>,
     /// Updated execution metadata
     pub metadata: HashMap<String, Value>,
+    /// Whether execution continues to the next nodes; a run's final result
+    /// tells how the run ended
+    pub termination: Termination,
+}
+
+impl ExecutionResult {
     /// Whether execution should continue to next nodes
-    pub should_continue: bool,
+    pub fn should_continue(&self) -> bool {
+        matches!(self.termination, Termination::Completed)
+    }
+}
+
+/// How a node's output (or a whole run) ends execution
+/// 
+/// Serialized as { "status": "completed" }, { "status": "stopped_early", "node_id", "reason" }
+/// or { "status": "failed", "error" }.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Termination {
+    /// Continue with the next node; for a run: every node ran
+    Completed,
+    /// A node ended the run on purpose (cache hit, open window, Assert "stop", ...)
+    StoppedEarly { node_id: String, reason: String },
+    /// The run failed (for run summaries; node errors travel as `Err`)
+    Failed { error: String },
+}
+
+impl Termination {
+    pub fn stopped(node_id: &str, reason: impl Into<String>) -> Self {
+        Termination::StoppedEarly { node_id: node_id.to_string(), reason: reason.into() }
+    }
+
+    /// Outcome of a finished run
+    pub fn of_run(result: &Result<ExecutionResult>) -> Self {
+        match result {
+            Ok(result) => result.termination.clone(),
+            Err(e) => Termination::Failed { error: e.to_string() },
+        }
+    }
+
+    /// "success", "stopped" or "failed" (execution history status)
+    pub fn status(&self) -> &'static str {
+        match self {
+            Termination::Completed => "success",
+            Termination::StoppedEarly { .. } => "stopped",
+            Termination::Failed { .. } => "failed",
+        }
+    }
+}
+
+/// HTTP responses outside 2xx stop the run after the HTTP node
+fn http_termination(node: &Node, status: reqwest::StatusCode) -> Termination {
+    match status.is_success() {
+        true => Termination::Completed,
+        false => Termination::stopped(&node.id, format!("HTTP {}", status)),
+    }
 }
 
 /// Node executor that handles execution of different node types
@@ -41,12 +163,51 @@ pub struct ExecutionResult {
 pub struct NodeExecutor {
     /// Project database manager for isolated multi-tenant storage
     project_db_manager: Arc<ProjectDatabaseManager>,
+    /// In-process event bus for EmitEvent nodes
+    event_bus: Arc<EventBus>,
+    /// MaxMind readers for GeoIP nodes, loaded on first use
+    geoip: GeoIpReaders,
+    /// Per-node locks of Window node buffers
+    window_locks: WindowLocks,
+    /// Device states read by `$device.*` pins
+    devices: Arc<DeviceRegistry>,
+    /// Serial ports held open by SerialTriggers (Serial nodes write through them)
+    serial_ports: SerialPorts,
 }
 
 impl NodeExecutor {
-    /// Create new node executor with project database manager
-    pub fn new(project_db_manager: Arc<ProjectDatabaseManager>) -> Result<Self> {
-        Ok(Self { project_db_manager })
+    /// Create new node executor with project database manager and event bus
+    pub fn new(project_db_manager: Arc<ProjectDatabaseManager>, event_bus: Arc<EventBus>) -> Result<Self> {
+        let devices = Arc::new(DeviceRegistry::new(Arc::clone(&project_db_manager)));
+        Ok(Self { project_db_manager, event_bus, geoip: GeoIpReaders::default(), window_locks: WindowLocks::default(), devices, serial_ports: SerialPorts::default() })
+    }
+
+    /// Read `$device.*` pins from a shared device registry (the trigger services' one)
+    pub fn with_devices(mut self, devices: Arc<DeviceRegistry>) -> Self {
+        self.devices = devices;
+        self
+    }
+
+    /// Serial ports shared with the serial trigger service
+    pub fn serial_ports(&self) -> &SerialPorts {
+        &self.serial_ports
+    }
+
+    /// Project database manager shared with the execution engine
+    pub fn project_db_manager(&self) -> &Arc<ProjectDatabaseManager> {
+        &self.project_db_manager
+    }
+
+    /// Resolve a single `$secret.` pin of a project
+    pub fn resolve_secret(&self, project_slug: &str, pin: &str) -> Result<String> {
+        let key = pin.strip_prefix("$secret.")
+            .ok_or_else(|| anyhow::anyhow!("Invalid secret pin expression: {}. Must start with '$secret.'", pin))?;
+        self.project_db_manager.resolve_secret(project_slug, key)
+    }
+
+    /// Resolve a node's input pins for callers outside the executor
+    pub fn resolve_input_pins(&self, node: &Node, context: &ExecutionContext) -> Result<Vec<Value>> {
+        self.evaluate_input_pins(node, context)
     }
 
     /// Execute a single node with the given execution context
@@ -55,7 +216,7 @@ impl NodeExecutor {
     /// Returns the execution result for flowing to downstream nodes.
     pub async fn execute_node(&self, node: &Node, mut context: ExecutionContext) -> Result<ExecutionResult> {
         tracing::info!("🚀 Starting node execution: {} (type: {:?})", node.id, node.node_type);
-        tracing::debug!("📥 Input data: {}", serde_json::to_string(&context.data).unwrap_or_else(|_| "invalid_json".to_string()));
+        tracing::debug!("📥 Input data: {}", preview_data(&context.data));
         
         let start_time = std::time::Instant::now();
         
@@ -64,6 +225,9 @@ impl NodeExecutor {
         context.metadata.insert("current_node_type".to_string(), json!(format!("{:?}", node.node_type)));
         context.metadata.insert("execution_start".to_string(), json!(chrono::Utc::now().to_rfc3339()));
         
+        // STORE-AND-FORWARD: Keep the input to park in the outbox if the network is down
+        let parked_context = outbox::parks_on_connectivity_error(node, &context).then(|| context.clone());
+        
         let result = match node.node_type {
             NodeType::Webhook => {
                 // WebhookNode is handled by the API layer as entry point
@@ -89,6 +253,21 @@ impl NodeExecutor {
                 tracing::error!("❌ CronTrigger should not be executed directly: {}", node.id);
                 Err(anyhow::anyhow!("CronTrigger should not be executed directly"))
             }
+            NodeType::PollingTrigger => {
+                // PollingTrigger is fetched and run by the scheduler service as background trigger
+                tracing::error!("❌ PollingTrigger should not be executed directly: {}", node.id);
+                Err(anyhow::anyhow!("PollingTrigger should not be executed directly"))
+            }
+            NodeType::FormTrigger => {
+                // FormTrigger is handled by the API layer as entry point
+                tracing::error!("❌ FormTrigger should not be executed directly: {}", node.id);
+                Err(anyhow::anyhow!("FormTrigger should not be executed directly"))
+            }
+            NodeType::ChatTrigger => {
+                // ChatTrigger is handled by the API layer as entry point
+                tracing::error!("❌ ChatTrigger should not be executed directly: {}", node.id);
+                Err(anyhow::anyhow!("ChatTrigger should not be executed directly"))
+            }
             NodeType::HTTPClient => {
                 self.execute_http_client_node(node, context).await
             }
@@ -98,6 +277,60 @@ impl NodeExecutor {
             NodeType::PGDynTableWriter => {
                 self.execute_pgdyn_table_writer_node(node, context).await
             }
+            NodeType::StripeAPI => {
+                self.execute_stripe_api_node(node, context).await
+            }
+            NodeType::GitHubAPI => {
+                self.execute_github_api_node(node, context).await
+            }
+            NodeType::TwilioSend => {
+                self.execute_twilio_send_node(node, context).await
+            }
+            NodeType::PushNotification => {
+                self.execute_push_notification_node(node, context).await
+            }
+            NodeType::CalendarEvent => {
+                self.execute_calendar_event_node(node, context).await
+            }
+            NodeType::GeoIP => {
+                self.execute_geoip_node(node, context).await
+            }
+            NodeType::Math => {
+                self.execute_math_node(node, context).await
+            }
+            NodeType::CurrencyConvert => {
+                self.execute_currency_convert_node(node, context).await
+            }
+            NodeType::ParquetWrite => {
+                self.execute_parquet_write_node(node, context).await
+            }
+            NodeType::NdjsonParse => {
+                self.execute_ndjson_parse_node(node, context).await
+            }
+            NodeType::NdjsonWrite => {
+                self.execute_ndjson_write_node(node, context).await
+            }
+            NodeType::Excel => {
+                self.execute_excel_node(node, context).await
+            }
+            NodeType::Anonymize => {
+                self.execute_anonymize_node(node, context).await
+            }
+            NodeType::Diff => {
+                self.execute_diff_node(node, context).await
+            }
+            NodeType::Window => {
+                self.execute_window_node(node, context).await
+            }
+            NodeType::Modbus => {
+                self.execute_modbus_node(node, context).await
+            }
+            NodeType::OpcUa => {
+                self.execute_opcua_node(node, context).await
+            }
+            NodeType::Serial => {
+                self.execute_serial_node(node, context).await
+            }
             NodeType::MCPTrigger => {
                 // MCPTrigger is handled by the API layer as entry point
                 // This should not be called during execution
@@ -105,17 +338,78 @@ impl NodeExecutor {
                 Err(anyhow::anyhow!("MCPTrigger should not be executed directly"))
             }
             NodeType::WebSocketTrigger => {
-                // WebSocketTrigger is handled by the API layer as entry point
+                // WebSocketTrigger is handled by the WebSocket API layer as entry point
                 // This should not be called during execution
                 tracing::error!("❌ WebSocketTrigger should not be executed directly: {}", node.id);
                 Err(anyhow::anyhow!("WebSocketTrigger should not be executed directly"))
             }
             NodeType::MQTTTrigger => {
-                // MQTTTrigger is handled by the API layer as entry point
+                // MQTTTrigger is handled by the MQTT trigger service as entry point
                 // This should not be called during execution
                 tracing::error!("❌ MQTTTrigger should not be executed directly: {}", node.id);
                 Err(anyhow::anyhow!("MQTTTrigger should not be executed directly"))
             }
+            NodeType::OpcUaTrigger => {
+                // OpcUaTrigger is handled by the OPC UA trigger service as entry point
+                // This should not be called during execution
+                tracing::error!("❌ OpcUaTrigger should not be executed directly: {}", node.id);
+                Err(anyhow::anyhow!("OpcUaTrigger should not be executed directly"))
+            }
+            NodeType::SerialTrigger => {
+                // SerialTrigger is handled by the serial trigger service as entry point
+                // This should not be called during execution
+                tracing::error!("❌ SerialTrigger should not be executed directly: {}", node.id);
+                Err(anyhow::anyhow!("SerialTrigger should not be executed directly"))
+            }
+            NodeType::BLETrigger => {
+                // BLETrigger is handled by the BLE trigger service as entry point
+                // This should not be called during execution
+                tracing::error!("❌ BLETrigger should not be executed directly: {}", node.id);
+                Err(anyhow::anyhow!("BLETrigger should not be executed directly"))
+            }
+            NodeType::ExecuteWorkflow => {
+                // ExecuteWorkflow is orchestrated by the execution engine (needs registry access)
+                tracing::error!("❌ ExecuteWorkflow should be dispatched by the engine: {}", node.id);
+                Err(anyhow::anyhow!("ExecuteWorkflow should be dispatched by the execution engine"))
+            }
+            NodeType::EmitEvent => {
+                self.execute_emit_event_node(node, context).await
+            }
+            NodeType::KVStore => {
+                self.execute_kv_store_node(node, context).await
+            }
+            NodeType::Memory => {
+                self.execute_memory_node(node, context).await
+            }
+            NodeType::Cache => {
+                self.execute_cache_node(node, context).await
+            }
+            NodeType::Mock => {
+                self.execute_mock_node(node, context).await
+            }
+            NodeType::Assert => {
+                self.execute_assert_node(node, context).await
+            }
+            NodeType::TransactionBegin | NodeType::TransactionCommit | NodeType::TransactionRollback => {
+                self.execute_transaction_node(node, context).await
+            }
+            NodeType::FeatureFlag => {
+                self.execute_feature_flag_node(node, context).await
+            }
+            NodeType::ABSplit => {
+                self.execute_ab_split_node(node, context).await
+            }
+            NodeType::EventTrigger => {
+                // EventTrigger is handled by the event trigger service as entry point
+                // This should not be called during execution
+                tracing::error!("❌ EventTrigger should not be executed directly: {}", node.id);
+                Err(anyhow::anyhow!("EventTrigger should not be executed directly"))
+            }
+        };
+        
+        let result = match (result, parked_context) {
+            (Err(e), Some(context)) if outbox::is_connectivity_error(&e) => self.park_in_outbox(node, context, e).await,
+            (result, _) => result,
         };
         
         let duration = start_time.elapsed();
@@ -123,8 +417,8 @@ impl NodeExecutor {
         match &result {
             Ok(exec_result) => {
                 tracing::info!("✅ Node execution completed: {} in {:?}", node.id, duration);
-                tracing::debug!("📤 Output data: {}", serde_json::to_string(&exec_result.data).unwrap_or_else(|_| "invalid_json".to_string()));
-                tracing::debug!("📊 Should continue: {}", exec_result.should_continue);
+                tracing::debug!("📤 Output data: {}", preview_data(&exec_result.data));
+                tracing::debug!("📊 Termination: {:?}", exec_result.termination);
             }
             Err(e) => {
                 tracing::error!("❌ Node execution failed: {} in {:?} - Error: {}", node.id, duration, e);
@@ -134,77 +428,139 @@ impl NodeExecutor {
         result
     }
 
-    /// Evaluate input pin expressions against context data
-    /// Returns array of values for bind parameters
-    fn evaluate_input_pins(&self, pins: &[String], context: &ExecutionContext) -> Result<Vec<Value>> {
-        let mut values = Vec::new();
-        
-        for pin_expr in pins {
-            tracing::debug!("🔌 Evaluating input pin: {}", pin_expr);
-            
-            // INDUSTRIAL-GRADE: Handle different pin expression types for millions of traffic
-            let value = if pin_expr.starts_with("$json.") {
-                let field_path = &pin_expr[6..]; // Remove "$json."
-                self.extract_json_field(&context.data, field_path)?
-            } else if pin_expr == "$json" {
-                // Return first item from array
-                context.data.get(0).cloned().unwrap_or(Value::Null)
-            } else if pin_expr.starts_with("$file.") {
-                let field_name = &pin_expr[6..]; // Remove "$file."
-                self.extract_file_field(&context.files, field_name)?
-            } else if pin_expr.starts_with("$query.") {
-                let param_name = &pin_expr[7..]; // Remove "$query."
-                self.extract_query_param(&context.query, param_name)?
-            } else if pin_expr.starts_with("$headers.") {
-                let header_name = &pin_expr[9..]; // Remove "$headers."
-                self.extract_header_value(&context.headers, header_name)?
-            } else if pin_expr.starts_with("$websocket.") {
-                let field_name = &pin_expr[11..]; // Remove "$websocket."
-                self.extract_websocket_field(&context.data, field_name)?
-            } else if pin_expr.starts_with("$mqtt.") {
-                let field_name = &pin_expr[6..]; // Remove "$mqtt."
-                self.extract_mqtt_field(&context.data, field_name)?
-            } else if pin_expr.starts_with("$mcp.") {
-                let field_name = &pin_expr[5..]; // Remove "$mcp."
-                self.extract_mcp_field(&context.data, field_name)?
-            } else if self.is_safe_lua_expression(pin_expr) {
-                // SAFE LUA EXECUTION: Single-line expressions with security limits
-                self.execute_safe_lua_expression(pin_expr, context)?
-            } else {
-                // Fallback: literal value
-                serde_json::from_str(pin_expr).unwrap_or_else(|_| Value::String(pin_expr.to_string()))
-            };
-            
-            tracing::debug!("🎯 Pin '{}' evaluated to: {:?}", pin_expr, value);
-            values.push(value);
+    /// Park a node's input in the project outbox after a connectivity error
+    /// 
+    /// The node outputs a "queued" item and stops the branch; the outbox flusher
+    /// replays the node later. If the input can't be stored, the original error stands.
+    async fn park_in_outbox(&self, node: &Node, context: ExecutionContext, error: anyhow::Error) -> Result<ExecutionResult> {
+        let workflow_id = context.metadata.get("workflow_id").and_then(|id| id.as_str()).unwrap_or_default().to_string();
+        let stored = match serde_json::to_value(&context) {
+            Ok(stored) => stored,
+            Err(e) => {
+                tracing::error!("❌ Failed to park node '{}' in the outbox: {}", node.id, e);
+                return Err(error);
+            }
+        };
+        let next_attempt_at = outbox::due_in(outbox::retry_delay(0));
+        let outbox_id = match self.project_db_manager.outbox_push(&context.project_slug, &workflow_id, &node.id, &stored, &error.to_string(), next_attempt_at).await {
+            Ok(id) => id,
+            Err(e) => {
+                tracing::error!("❌ Failed to park node '{}' in the outbox: {}", node.id, e);
+                return Err(error);
+            }
+        };
+
+        tracing::warn!("📮 Node '{}' is offline, parked its input as outbox entry {}: {}", node.id, outbox_id, error);
+        Ok(ExecutionResult {
+            data: vec![json!({
+                "success": false,
+                "queued": true,
+                "outbox_id": outbox_id,
+                "error": error.to_string()
+            })],
+            metadata: context.metadata,
+            termination: Termination::stopped(&node.id, format!("Node is offline, input parked as outbox entry {}", outbox_id)),
+        })
+    }
+
+    /// Evaluate a node's input pins against context data
+    /// Returns array of values for bind parameters (empty if the node has no pins)
+    fn evaluate_input_pins(&self, node: &Node, context: &ExecutionContext) -> Result<Vec<Value>> {
+        match node.input_pins() {
+            Some(pins) => self.evaluate_pins(&pins, context),
+            None => Ok(Vec::new()),
         }
+    }
+
+    /// Evaluate precompiled pin expressions against context data
+    fn evaluate_pins(&self, pins: &[PinExpr], context: &ExecutionContext) -> Result<Vec<Value>> {
+        pins.iter().map(|pin| self.evaluate_pin(pin, context)).collect()
+    }
+
+    /// Evaluate a single precompiled pin expression
+    fn evaluate_pin(&self, pin: &PinExpr, context: &ExecutionContext) -> Result<Value> {
+        // INDUSTRIAL-GRADE: Pins were classified at compile time, only extraction runs per request
+        let value = match pin {
+            PinExpr::JsonPath(field_path) => self.extract_json_field(&context.data, field_path)?,
+            // Return first item from array
+            PinExpr::JsonItem => context.data.first().cloned().unwrap_or(Value::Null),
+            PinExpr::File(field_name) => self.extract_file_field(&context.files, field_name)?,
+            PinExpr::Query(param_name) => self.extract_query_param(&context.query, param_name)?,
+            PinExpr::Header(header_name) => self.extract_header_value(&context.headers, header_name)?,
+            PinExpr::PathParam(param_name) => context.metadata.get("path_params")
+                .and_then(|params| params.get(param_name))
+                .cloned()
+                .unwrap_or(Value::Null),
+            PinExpr::WebSocket(field_name) => self.extract_websocket_field(&context.data, field_name)?,
+            PinExpr::Mqtt(field_name) => self.extract_mqtt_field(&context.data, field_name)?,
+            PinExpr::Mcp(field_name) => self.extract_mcp_field(&context.data, field_name)?,
+            PinExpr::Memory(field_path) => match context.metadata.get("memory") {
+                Some(memory) => self.extract_json_field(std::slice::from_ref(memory), field_path)?,
+                None => {
+                    tracing::warn!("⚠️ Pin $memory.{} used before a Memory node loaded a session", field_path);
+                    Value::Null
+                }
+            },
+            PinExpr::Var(var_path) => self.extract_json_field(std::slice::from_ref(&*context.vars), var_path)?,
+            PinExpr::Device { device_id, path } => {
+                let device_id = device_id.as_deref()
+                    .or_else(|| context.metadata.get("device_id").and_then(|id| id.as_str()));
+                match device_id {
+                    Some(device_id) => {
+                        let state = self.devices.pin_value(&context.project_slug, device_id);
+                        if path.is_empty() { state } else { self.extract_json_field(std::slice::from_ref(&state), path)? }
+                    }
+                    None => {
+                        tracing::warn!("⚠️ Pin $device.{} used in a run not started by a device", path);
+                        Value::Null
+                    }
+                }
+            }
+            PinExpr::NodeOutput { node_id, path } => match context.node_outputs.get(node_id) {
+                Some(items) if path.is_empty() => items.first().cloned().unwrap_or(Value::Null),
+                Some(items) => self.extract_json_field(items, path)?,
+                None => {
+                    tracing::warn!("⚠️ Pin $node(\"{}\") used before node '{}' ran in this run", node_id, node_id);
+                    Value::Null
+                }
+            },
+            // SAFE LUA EXECUTION: Single-line expressions with security limits
+            PinExpr::Lua(expr) => self.execute_safe_lua_expression(expr)?,
+            PinExpr::Literal(value) => value.clone(),
+        };
         
-        Ok(values)
+        tracing::debug!("🎯 Pin {:?} evaluated to: {}", pin, preview_value(&value));
+        Ok(value)
     }
     
+    /// Evaluate a pin against one item: `$json` pins read the item, other pins the run
+    fn evaluate_pin_for_item(&self, pin: &PinExpr, item: &Value, context: &ExecutionContext) -> Result<Value> {
+        match pin {
+            PinExpr::JsonPath(field_path) => self.extract_json_field(std::slice::from_ref(item), field_path),
+            PinExpr::JsonItem => Ok(item.clone()),
+            _ => self.evaluate_pin(pin, context),
+        }
+    }
+
+    /// Evaluate an input pin exactly as a node would (expression previews)
+    pub fn preview_pin(&self, pin: &PinExpr, context: &ExecutionContext) -> Result<Value> {
+        self.evaluate_pin(pin, context)
+    }
+
+    /// Render a param template exactly as a node would (expression previews)
+    pub fn preview_template(&self, template: &Value, context: &ExecutionContext) -> Result<Value> {
+        self.render_template(template, context)
+    }
+
     /// Evaluate secret pin expressions to get credentials (n8n-style)
     /// Returns array of secret values for database connections, API keys, etc.
-    fn evaluate_secret_pins(&self, pins: &[String]) -> Result<Vec<String>> {
-        let mut secrets = Vec::new();
-        
-        for pin_expr in pins {
-            tracing::debug!("🔐 Evaluating secret pin: {}", pin_expr);
-            
-            if pin_expr.starts_with("$secret.") {
-                let secret_key = &pin_expr[8..]; // Remove "$secret."
-                
-                // TODO: Implement secret vault lookup
-                // For now, return placeholder to prevent compilation errors
-                let secret_value = format!("PLACEHOLDER_SECRET_{}", secret_key);
-                tracing::warn!("🚨 Secret vault not implemented yet, using placeholder for: {}", secret_key);
-                
-                secrets.push(secret_value);
-            } else {
-                return Err(anyhow::anyhow!("Invalid secret pin expression: {}. Must start with '$secret.'", pin_expr));
-            }
-        }
-        
-        Ok(secrets)
+    fn evaluate_secret_pins(&self, project_slug: &str, pins: &[String]) -> Result<Vec<String>> {
+        pins.iter()
+            .map(|pin_expr| {
+                tracing::debug!("🔐 Evaluating secret pin: {}", pin_expr);
+                self.resolve_secret(project_slug, pin_expr)
+            })
+            .collect()
     }
     
     /// Extract file information from uploaded files
@@ -251,7 +607,7 @@ impl NodeExecutor {
     /// Extract WebSocket data field
     fn extract_websocket_field(&self, data: &[Value], field_name: &str) -> Result<Value> {
         // WebSocket data is stored in the first data item with websocket prefix
-        let first_item = data.get(0).unwrap_or(&Value::Null);
+        let first_item = data.first().unwrap_or(&Value::Null);
         if let Some(websocket_data) = first_item.get("websocket") {
             match websocket_data.get(field_name) {
                 Some(value) => Ok(value.clone()),
@@ -269,7 +625,7 @@ impl NodeExecutor {
     /// Extract MQTT data field
     fn extract_mqtt_field(&self, data: &[Value], field_name: &str) -> Result<Value> {
         // MQTT data is stored in the first data item with mqtt prefix
-        let first_item = data.get(0).unwrap_or(&Value::Null);
+        let first_item = data.first().unwrap_or(&Value::Null);
         if let Some(mqtt_data) = first_item.get("mqtt") {
             match mqtt_data.get(field_name) {
                 Some(value) => Ok(value.clone()),
@@ -287,7 +643,7 @@ impl NodeExecutor {
     /// Extract MCP data field
     fn extract_mcp_field(&self, data: &[Value], field_name: &str) -> Result<Value> {
         // MCP data is stored in the first data item with mcp prefix
-        let first_item = data.get(0).unwrap_or(&Value::Null);
+        let first_item = data.first().unwrap_or(&Value::Null);
         if let Some(mcp_data) = first_item.get("mcp") {
             match mcp_data.get(field_name) {
                 Some(value) => Ok(value.clone()),
@@ -305,7 +661,7 @@ impl NodeExecutor {
     /// Extract field from JSON data using simple dot notation
     fn extract_json_field(&self, data_array: &[Value], field_path: &str) -> Result<Value> {
         // Get first item from array (like n8n's $json behavior)
-        let first_item = data_array.get(0).unwrap_or(&Value::Null);
+        let first_item = data_array.first().unwrap_or(&Value::Null);
         
         // Split field path by dots: "user.name" -> ["user", "name"]
         let path_parts: Vec<&str> = field_path.split('.').collect();
@@ -323,78 +679,9 @@ impl NodeExecutor {
         Ok(current.clone())
     }
 
-    /// SECURITY: Check if expression is safe for Lua execution (millions of traffic)
-    fn is_safe_lua_expression(&self, expr: &str) -> bool {
-        // Whitelist approach for maximum security
-        let safe_patterns = [
-            "date(", "time()", "now()",
-            "math.", "string.", 
-            "uuid()", "hash(",
-        ];
-        
-        // Block dangerous patterns
-        let dangerous_patterns = [
-            "os.", "io.", "debug.", "package.", "require", "load", "dofile", 
-            "loadfile", "loadstring", "rawget", "rawset", "getmetatable", 
-            "setmetatable", "_G", "_ENV", "coroutine", "collectgarbage"
-        ];
-        
-        // Check for dangerous patterns first
-        for pattern in &dangerous_patterns {
-            if expr.contains(pattern) {
-                tracing::warn!("🚨 Blocked dangerous Lua expression: {}", expr);
-                return false;
-            }
-        }
-        
-        // Check for safe patterns or simple expressions
-        for pattern in &safe_patterns {
-            if expr.contains(pattern) {
-                return true;
-            }
-        }
-        
-        // Allow simple expressions (numbers, strings, basic operations)
-        expr.len() < 200 && expr.chars().all(|c| c.is_alphanumeric() || " +-*/()[]{}.,\"'_%".contains(c))
-    }
-
-    /// PERFORMANCE: Execute safe Lua expression with limits (millions of traffic)
-    fn execute_safe_lua_expression(&self, expr: &str, _context: &ExecutionContext) -> Result<Value> {
-        // Create sandboxed Lua instance
-        let lua = mlua::Lua::new();
-        
-        // Provide safe API functions
-        let globals = lua.globals();
-        
-        // Safe time functions (replace os.date, os.time)
-        if let Err(e) = globals.set("date", lua.create_function(|_, format: String| {
-            let now = chrono::Utc::now();
-            Ok(now.format(&format).to_string())
-        }).map_err(|e| anyhow::anyhow!("Failed to create date function: {}", e))?) {
-            return Err(anyhow::anyhow!("Failed to set date function: {}", e));
-        }
-        
-        if let Err(e) = globals.set("time", lua.create_function(|_, ()| {
-            Ok(chrono::Utc::now().timestamp())
-        }).map_err(|e| anyhow::anyhow!("Failed to create time function: {}", e))?) {
-            return Err(anyhow::anyhow!("Failed to set time function: {}", e));
-        }
-        
-        if let Err(e) = globals.set("now", lua.create_function(|_, ()| {
-            Ok(chrono::Utc::now().to_rfc3339())
-        }).map_err(|e| anyhow::anyhow!("Failed to create now function: {}", e))?) {
-            return Err(anyhow::anyhow!("Failed to set now function: {}", e));
-        }
-        
-        // Remove dangerous globals (ignore errors)
-        let _ = globals.set("os", mlua::Nil);
-        let _ = globals.set("io", mlua::Nil);
-        let _ = globals.set("debug", mlua::Nil);
-        let _ = globals.set("package", mlua::Nil);
-        
-        // Execute expression with error handling
-        let result = lua.load(expr).eval::<mlua::Value>()
-            .map_err(|e| anyhow::anyhow!("Safe Lua execution failed: {}", e))?;
+    /// PERFORMANCE: Execute safe Lua expression in the cached per-thread sandbox
+    fn execute_safe_lua_expression(&self, expr: &str) -> Result<Value> {
+        let result = safe_lua::eval_expression(expr)?;
         
         // Convert result back to JSON
         self.lua_to_json(result)
@@ -502,35 +789,44 @@ impl NodeExecutor {
     async fn execute_fun_logic_node(&self, node: &Node, context: ExecutionContext) -> Result<ExecutionResult> {
         tracing::debug!("🧠 Executing FunLogicNode: {}", node.id);
         
-        let script = node.params.get("script")
-            .and_then(|s| s.as_str())
-            .ok_or_else(|| anyhow::anyhow!("FunLogicNode missing 'script' parameter"))?;
+        let FunLogicParams { script } = FunLogicParams::from_node_params(&node.params)
+            .map_err(|e| anyhow::anyhow!("FunLogicNode '{}': {}", node.id, e))?;
         
         tracing::debug!("📝 Lua script: {}", script);
 
         // Create new Lua instance for thread safety
         let lua = mlua::Lua::new();
+        context.budget.limit_lua(&lua, &node.id)?;
         
         // Convert array data to proper Lua table syntax
         let mut lua_items = Vec::new();
-        for (i, item) in context.data.iter().enumerate() {
-            let item_lua = self.json_to_lua_string(item)?;
-            tracing::debug!("📋 Item {}: {}", i+1, item_lua);
-            lua_items.push(item_lua);
+        for item in context.data.iter() {
+            lua_items.push(self.json_to_lua_string(item)?);
         }
         
         // Build Lua array: data = {item1, item2, ...}
-        let setup_script = format!("data = {{{}}}", lua_items.join(", "));
+        // Workflow variables are a read-only `vars` table
+        let vars = match context.vars.as_ref() {
+            Value::Object(_) => self.json_to_lua_string(&context.vars)?,
+            _ => "{}".to_string(),
+        };
+        let setup_script = format!("data = {{{}}}\nvars = {}({})", lua_items.join(", "), READONLY_VARS, vars);
         
         tracing::debug!("⚙️ Setting up Lua data context");
-        tracing::debug!("🔧 Lua setup script: {}", setup_script);
+        tracing::debug!("🔧 Lua setup script: {} items, {} bytes", lua_items.len(), setup_script.len());
         lua.load(&setup_script).exec()
-            .map_err(|e| anyhow::anyhow!("Failed to setup Lua data: {}", e))?;
+            .map_err(|e| match context.budget.lua_error(&node.id, &e) {
+                Some(exceeded) => anyhow::Error::new(exceeded),
+                None => anyhow::anyhow!("Failed to setup Lua data: {}", e),
+            })?;
 
         // Execute the user script directly (it should return a value)
         tracing::debug!("🏃 Executing user Lua script");
-        let lua_result: mlua::Value = lua.load(script).eval()
-            .map_err(|e| anyhow::anyhow!("Lua script execution failed: {}", e))?;
+        let lua_result: mlua::Value = lua.load(&script).eval()
+            .map_err(|e| match context.budget.lua_error(&node.id, &e) {
+                Some(exceeded) => anyhow::Error::new(exceeded),
+                None => anyhow::anyhow!("Lua script execution failed: {}", e),
+            })?;
 
         // Convert Lua value to JSON using manual conversion
         tracing::debug!("🔄 Converting Lua result back to JSON");
@@ -547,39 +843,86 @@ impl NodeExecutor {
         Ok(ExecutionResult {
             data: result_array,
             metadata: context.metadata,
-            should_continue: true,
+            termination: Termination::Completed,
         })
     }
 
     /// Execute SimpleTableWriterNode to store data in SQLite
     /// 
     /// Expected params: { "table": "grades", "columns": ["id", "score", "result"] }
+    /// Optional params: { "audit_columns": true } (see `audit_values`)
     /// Writes the current data to the specified table and columns.
     async fn execute_simple_table_writer_node(&self, node: &Node, context: ExecutionContext) -> Result<ExecutionResult> {
         tracing::debug!("💾 Executing SimpleTableWriterNode: {}", node.id);
         
-        let table_name = node.params.get("table")
-            .and_then(|t| t.as_str())
-            .ok_or_else(|| anyhow::anyhow!("SimpleTableWriterNode missing 'table' parameter"))?;
-
-        let columns: Vec<String> = node.params.get("columns")
-            .and_then(|c| c.as_array())
-            .ok_or_else(|| anyhow::anyhow!("SimpleTableWriterNode missing 'columns' parameter"))?
-            .iter()
-            .filter_map(|c| c.as_str().map(|s| s.to_string()))
-            .collect();
+        let params = SimpleTableWriterParams::from_node_params(&node.params)
+            .map_err(|e| anyhow::anyhow!("SimpleTableWriterNode '{}': {}", node.id, e))?;
+        params.check_inputs(node.inputs.as_deref())?;
+        let SimpleTableWriterParams { table, mut columns, .. } = params;
+        let table_name = table.as_str();
         
         tracing::debug!("📋 Target table: {} with columns: {:?}", table_name, columns);
 
-        if columns.is_empty() {
-            tracing::error!("❌ No columns specified for table: {}", table_name);
-            return Err(anyhow::anyhow!("SimpleTableWriterNode 'columns' cannot be empty"));
+        // Extract values using input pins if provided, otherwise use column names directly
+        let mut values_to_insert = if let Some(inputs) = &node.inputs {
+            // Use input pins to extract values (BLAZING FAST!)
+            tracing::debug!("🔌 Using {} input pins for data extraction", inputs.len());
+            
+            self.evaluate_input_pins(node, &context)?
+        } else {
+            // Backwards compatible: extract values by column names
+            tracing::debug!("📋 Using column names for data extraction (backwards compatible)");
+            let first_item = context.data.first().unwrap_or(&Value::Null);
+            
+            let mut values = Vec::new();
+            for column in &columns {
+                let value = first_item.get(column).unwrap_or(&Value::Null);
+                values.push(value.clone());
+            }
+            values
+        };
+        
+        // Run lineage columns, maintained by the executor
+        let audit = audit_values(node, &context);
+        for (column, value) in audit.iter().cloned() {
+            columns.push(column);
+            values_to_insert.push(value);
         }
-
+        
+        // PostgreSQL-backed projects insert into their schema instead
+        if let SimpleTableBackend::Postgres(pg) = self.project_db_manager.simpletable_backend(&context.project_slug).await? {
+            let client = context.transaction.pg_client(&pg).await?;
+            pg.ensure_table(&client, table_name, &columns).await?;
+            if !audit.is_empty() {
+                let audit_columns: Vec<String> = audit.into_iter().map(|(column, _)| column).collect();
+                pg.add_columns(&client, table_name, &audit_columns).await?;
+            }
+            let inserted_id = pg.insert(&client, table_name, &columns, &values_to_insert).await?;
+            drop(client);
+            tracing::info!("✅ Database insert successful (postgres {}): id {}", pg.schema(), inserted_id);
+            return Ok(ExecutionResult {
+                data: vec![json!({
+                    "inserted_data": {
+                        "table": table_name,
+                        "columns": columns,
+                        "values": values_to_insert
+                    },
+                    "_inserted_id": inserted_id,
+                    "_rows_affected": 1,
+                    "_success": true
+                })],
+                metadata: context.metadata,
+                termination: Termination::Completed,
+            });
+        }
+        
         // Ensure table exists with the specified columns
         tracing::debug!("🔧 Ensuring table exists: {}", table_name);
-        self.ensure_table_exists(table_name, &columns, &context.project_slug).await?;
-
+        self.ensure_table_exists(table_name, &columns, &context).await?;
+        if !audit.is_empty() {
+            self.ensure_audit_columns(table_name, &audit, &context).await?;
+        }
+        
         // Build INSERT query dynamically
         let column_list = columns.join(", ");
         let placeholders: Vec<String> = (0..columns.len()).map(|_| "?".to_string()).collect();
@@ -591,34 +934,10 @@ impl NodeExecutor {
         );
         
         tracing::debug!("📝 SQL Query: {}", query);
-
-        // Extract values using input pins if provided, otherwise use column names directly
+        
         let mut query_builder = sqlx::query(&query);
         let mut bound_values = Vec::new();
         
-        let values_to_insert = if let Some(inputs) = &node.inputs {
-            // Use input pins to extract values (BLAZING FAST!)
-            tracing::debug!("🔌 Using {} input pins for data extraction", inputs.len());
-            
-            if inputs.len() != columns.len() {
-                return Err(anyhow::anyhow!("Input pins count ({}) must match columns count ({})", 
-                    inputs.len(), columns.len()));
-            }
-            
-            self.evaluate_input_pins(inputs, &context)?
-        } else {
-            // Backwards compatible: extract values by column names
-            tracing::debug!("📋 Using column names for data extraction (backwards compatible)");
-            let first_item = context.data.get(0).unwrap_or(&Value::Null);
-            
-            let mut values = Vec::new();
-            for column in &columns {
-                let value = first_item.get(column).unwrap_or(&Value::Null);
-                values.push(value.clone());
-            }
-            values
-        };
-        
         // Bind the extracted values to the SQL query
         for (i, value) in values_to_insert.iter().enumerate() {
             let column_name = &columns[i];
@@ -646,9 +965,9 @@ impl NodeExecutor {
         // Get project-scoped simpletable database
         let simpletable_pool = self.project_db_manager.get_simpletable_pool(&context.project_slug).await?;
         
-        // Execute the insert
+        // Execute the insert (inside the execution's transaction, if one is open)
         tracing::debug!("💽 Executing database insert");
-        let result = query_builder.execute(&simpletable_pool).await?;
+        let result = context.transaction.sqlite_execute(&simpletable_pool, query_builder).await?;
         
         tracing::info!("✅ Database insert successful: {} rows affected, last_insert_id: {}", 
             result.rows_affected(), result.last_insert_rowid());
@@ -668,7 +987,7 @@ impl NodeExecutor {
         Ok(ExecutionResult {
             data: vec![response_data], // Wrap in array for consistency
             metadata: context.metadata,
-            should_continue: true,
+            termination: Termination::Completed,
         })
     }
 
@@ -719,13 +1038,40 @@ impl NodeExecutor {
         
         tracing::debug!("📝 SQL Query: {}", query);
 
+        let results = match self.project_db_manager.simpletable_backend(&context.project_slug).await? {
+            SimpleTableBackend::Postgres(pg) => pg.query(&*context.transaction.pg_client(&pg).await?, &query, &[]).await
+                .map_err(|e| anyhow::anyhow!("Database query failed: {}", e))?,
+            SimpleTableBackend::Sqlite => self.read_simpletable_rows(sqlx::query(&query), &context).await?,
+        };
+
+        tracing::info!("✅ Database query successful: {} rows returned", results.len());
+
+        // Return results as JSON array
+        let response_data = json!({
+            "results": results,
+            "count": results.len(),
+            "table": table_name
+        });
+
+        Ok(ExecutionResult {
+            data: vec![response_data], // Wrap query results in array for consistency
+            metadata: context.metadata,
+            termination: Termination::Completed,
+        })
+    }
+
+    /// Run a query against the project's SQLite simpletable database and convert rows to JSON
+    async fn read_simpletable_rows<'q>(
+        &self,
+        query_builder: sqlx::query::Query<'q, sqlx::Sqlite, sqlx::sqlite::SqliteArguments<'q>>,
+        context: &ExecutionContext,
+    ) -> Result<Vec<Value>> {
         // Get project-scoped simpletable database
         let simpletable_pool = self.project_db_manager.get_simpletable_pool(&context.project_slug).await?;
         
-        // Execute the query
+        // Execute the query (inside the execution's transaction, if one is open)
         tracing::debug!("📊 Executing database query");
-        let rows = sqlx::query(&query)
-            .fetch_all(&simpletable_pool)
+        let rows = context.transaction.sqlite_fetch_all(&simpletable_pool, query_builder)
             .await
             .map_err(|e| anyhow::anyhow!("Database query failed: {}", e))?;
 
@@ -762,20 +1108,7 @@ impl NodeExecutor {
             results.push(Value::Object(record));
         }
 
-        tracing::info!("✅ Database query successful: {} rows returned", results.len());
-
-        // Return results as JSON array
-        let response_data = json!({
-            "results": results,
-            "count": results.len(),
-            "table": table_name
-        });
-
-        Ok(ExecutionResult {
-            data: vec![response_data], // Wrap query results in array for consistency
-            metadata: context.metadata,
-            should_continue: true,
-        })
+        Ok(results)
     }
 
     /// Execute SimpleTableQuery with input pins and bind parameters
@@ -800,79 +1133,15 @@ impl NodeExecutor {
         // Evaluate input pins to get bind parameter values
         let bind_values = if let Some(inputs) = &node.inputs {
             tracing::debug!("🔌 Found {} input pins", inputs.len());
-            self.evaluate_input_pins(inputs, &context)?
+            self.evaluate_input_pins(node, &context)?
         } else {
             tracing::debug!("🔌 No input pins defined");
             Vec::new()
         };
 
-        // Build query with bind parameters for security
-        let mut query_builder = sqlx::query(query);
-        
-        tracing::debug!("🔗 Binding {} parameters", bind_values.len());
-        for (i, value) in bind_values.iter().enumerate() {
-            tracing::debug!("🔗 Bind param {}: {:?}", i+1, value);
-            
-            // Bind parameter based on JSON value type
-            query_builder = match value {
-                Value::String(s) => query_builder.bind(s),
-                Value::Number(n) => {
-                    if let Some(i) = n.as_i64() {
-                        query_builder.bind(i)
-                    } else if let Some(f) = n.as_f64() {
-                        query_builder.bind(f)
-                    } else {
-                        query_builder.bind(n.to_string())
-                    }
-                }
-                Value::Bool(b) => query_builder.bind(*b),
-                Value::Null => query_builder.bind(None::<String>),
-                _ => query_builder.bind(value.to_string()),
-            };
-        }
+        let results = self.query_simpletable(query, &bind_values, &context).await?;
 
-        // Get project-scoped simpletable database
-        let simpletable_pool = self.project_db_manager.get_simpletable_pool(&context.project_slug).await?;
-        
-        // Execute the bound query
-        tracing::debug!("📊 Executing bound query");
-        let rows = query_builder.fetch_all(&simpletable_pool).await
-            .map_err(|e| anyhow::anyhow!("Database query failed: {}", e))?;
-
-        // Convert rows to JSON array
-        let mut results = Vec::new();
-        for row in rows {
-            let mut record = serde_json::Map::new();
-            
-            // Dynamically get all columns from the row
-            for (i, column) in row.columns().iter().enumerate() {
-                let column_name = column.name();
-                let value: Option<String> = row.try_get(i).unwrap_or(None);
-                
-                // Convert SQL value to JSON value
-                let json_value = match value {
-                    Some(v) => {
-                        // Try to parse as number first, then fall back to string
-                        if let Ok(num) = v.parse::<i64>() {
-                            json!(num)
-                        } else if let Ok(num) = v.parse::<f64>() {
-                            json!(num)
-                        } else if v == "true" || v == "false" {
-                            json!(v == "true")
-                        } else {
-                            json!(v)
-                        }
-                    }
-                    None => Value::Null,
-                };
-                
-                record.insert(column_name.to_string(), json_value);
-            }
-            
-            results.push(Value::Object(record));
-        }
-
-        tracing::info!("✅ Query successful: {} rows returned from {}", results.len(), table_name);
+        tracing::info!("✅ Query successful: {} rows returned from {}", results.len(), table_name);
 
         // Return results as JSON
         let response_data = if results.len() == 1 {
@@ -890,15 +1159,53 @@ impl NodeExecutor {
         Ok(ExecutionResult {
             data: vec![response_data], // Wrap in array for consistency
             metadata: context.metadata,
-            should_continue: true,
+            termination: Termination::Completed,
         })
     }
 
+    /// Run a SQL query with bind parameters against the project's SimpleTable backend
+    pub async fn query_simpletable(&self, query: &str, bind_values: &[Value], context: &ExecutionContext) -> Result<Vec<Value>> {
+        let rows = match self.project_db_manager.simpletable_backend(&context.project_slug).await? {
+            SimpleTableBackend::Postgres(pg) => pg.query(&*context.transaction.pg_client(&pg).await?, query, bind_values).await
+                .map_err(|e| anyhow::anyhow!("Database query failed: {}", e))?,
+            SimpleTableBackend::Sqlite => {
+                // Build query with bind parameters for security
+                let mut query_builder = sqlx::query(query);
+                
+                tracing::debug!("🔗 Binding {} parameters", bind_values.len());
+                for (i, value) in bind_values.iter().enumerate() {
+                    tracing::debug!("🔗 Bind param {}: {}", i+1, preview_value(value));
+                    
+                    // Bind parameter based on JSON value type
+                    query_builder = match value {
+                        Value::String(s) => query_builder.bind(s),
+                        Value::Number(n) => {
+                            if let Some(i) = n.as_i64() {
+                                query_builder.bind(i)
+                            } else if let Some(f) = n.as_f64() {
+                                query_builder.bind(f)
+                            } else {
+                                query_builder.bind(n.to_string())
+                            }
+                        }
+                        Value::Bool(b) => query_builder.bind(*b),
+                        Value::Null => query_builder.bind(None::<String>),
+                        _ => query_builder.bind(value.to_string()),
+                    };
+                }
+                
+                self.read_simpletable_rows(query_builder, context).await?
+            }
+        };
+
+        Ok(rows)
+    }
+
     /// Ensure a table exists with the specified columns
     /// 
     /// Creates the table if it doesn't exist. Uses TEXT type for simplicity
     /// since we're handling JSON data conversion manually.
-    async fn ensure_table_exists(&self, table_name: &str, columns: &[String], project_slug: &str) -> Result<()> {
+    async fn ensure_table_exists(&self, table_name: &str, columns: &[String], context: &ExecutionContext) -> Result<()> {
         // Validate table name to prevent SQL injection
         if !table_name.chars().all(|c| c.is_alphanumeric() || c == '_') {
             return Err(anyhow::anyhow!("Invalid table name: {}", table_name));
@@ -922,13 +1229,30 @@ impl NodeExecutor {
         );
 
         // Get project-scoped simpletable database
-        let simpletable_pool = self.project_db_manager.get_simpletable_pool(project_slug).await?;
+        let simpletable_pool = self.project_db_manager.get_simpletable_pool(&context.project_slug).await?;
         
-        sqlx::query(&create_sql).execute(&simpletable_pool).await?;
+        context.transaction.sqlite_execute(&simpletable_pool, sqlx::query(&create_sql)).await?;
         
         Ok(())
     }
 
+    /// Add audit columns missing from a table created before "audit_columns" was enabled
+    async fn ensure_audit_columns(&self, table_name: &str, audit: &[(String, Value)], context: &ExecutionContext) -> Result<()> {
+        let simpletable_pool = self.project_db_manager.get_simpletable_pool(&context.project_slug).await?;
+        let existing: HashSet<String> = context.transaction
+            .sqlite_fetch_all(&simpletable_pool, sqlx::query(&format!("PRAGMA table_info({})", table_name)))
+            .await?
+            .iter()
+            .map(|row| row.get("name"))
+            .collect();
+        
+        for (column, _) in audit.iter().filter(|(column, _)| !existing.contains(column)) {
+            let alter_sql = format!("ALTER TABLE {} ADD COLUMN {} TEXT", table_name, column);
+            context.transaction.sqlite_execute(&simpletable_pool, sqlx::query(&alter_sql)).await?;
+        }
+        Ok(())
+    }
+
     /// Execute HTTPClient node to make external HTTP requests
     /// 
     /// Supports GET, POST, PUT, DELETE methods with optional input pins for request body.
@@ -936,33 +1260,27 @@ impl NodeExecutor {
     async fn execute_http_client_node(&self, node: &Node, context: ExecutionContext) -> Result<ExecutionResult> {
         tracing::debug!("🌐 Executing HTTPClientNode: {}", node.id);
         
-        let url = node.params.get("url")
-            .and_then(|u| u.as_str())
-            .ok_or_else(|| anyhow::anyhow!("HTTPClient missing 'url' parameter"))?;
+        let HttpClientParams { url, method, headers, oauth, body_mode, download_to_file, response_format } =
+            HttpClientParams::from_node_params(&node.params)
+                .map_err(|e| anyhow::anyhow!("HTTPClient node '{}': {}", node.id, e))?;
+        let url = url.as_str();
         
-        let method = node.params.get("method")
-            .and_then(|m| m.as_str())
-            .unwrap_or("GET");
-        
-        let headers = node.params.get("headers")
-            .and_then(|h| h.as_object())
-            .cloned()
-            .unwrap_or_default();
+        let sign_config = SignConfig::from_node_params(&node.params)
+            .map_err(|e| anyhow::anyhow!("HTTPClient node '{}': {}", node.id, e))?;
         
         tracing::debug!("🌍 HTTP Request: {} {}", method, url);
-        tracing::debug!("📋 Headers: {:?}", headers);
+        tracing::debug!("📋 Headers: {}", preview_debug(&headers));
 
         // Create HTTP client
         let client = reqwest::Client::new();
         
         // Start building the request
-        let mut request_builder = match method.to_uppercase().as_str() {
-            "GET" => client.get(url),
-            "POST" => client.post(url),
-            "PUT" => client.put(url),
-            "DELETE" => client.delete(url),
-            "PATCH" => client.patch(url),
-            _ => return Err(anyhow::anyhow!("Unsupported HTTP method: {}", method)),
+        let mut request_builder = match method {
+            HttpMethod::Get => client.get(url),
+            HttpMethod::Post => client.post(url),
+            HttpMethod::Put => client.put(url),
+            HttpMethod::Delete => client.delete(url),
+            HttpMethod::Patch => client.patch(url),
         };
 
         // Add headers
@@ -972,18 +1290,57 @@ impl NodeExecutor {
             }
         }
 
+        // OAUTH: Bearer token of a project OAuth credential, refreshed when about to expire
+        if let Some(credential) = oauth.as_deref() {
+            let access_token = self.project_db_manager.oauth_access_token(&context.project_slug, credential).await
+                .map_err(|e| anyhow::anyhow!("HTTPClient node '{}': {}", node.id, e))?;
+            tracing::debug!("🔑 Using OAuth credential '{}'", credential);
+            request_builder = request_builder.bearer_auth(access_token);
+        }
+
         // Handle request body from input pins
         if let Some(inputs) = &node.inputs {
             tracing::debug!("🔌 Processing {} input pins", inputs.len());
-            let input_values = self.evaluate_input_pins(inputs, &context)?;
+            let input_values = self.evaluate_input_pins(node, &context)?;
             
-            // Use the first input pin as request body (if method supports it)
-            if !input_values.is_empty() && matches!(method.to_uppercase().as_str(), "POST" | "PUT" | "PATCH") {
+            if body_mode == BodyMode::Multipart {
+                // MULTIPART: Every input pin becomes a form part named after its last path segment
+                let mut form = reqwest::multipart::Form::new();
+                for (pin_expr, value) in inputs.iter().zip(&input_values) {
+                    let part_name = pin_expr.rsplit('.').next().unwrap_or(pin_expr).to_string();
+                    if let Some(binary) = BinaryRef::from_value(value) {
+                        let content = context.binaries.read(&binary).await?;
+                        let mut part = reqwest::multipart::Part::bytes(content.to_vec())
+                            .mime_str(&binary.content_type)
+                            .map_err(|e| anyhow::anyhow!("Invalid content type for part '{}': {}", part_name, e))?;
+                        if let Some(filename) = binary.filename {
+                            part = part.file_name(filename);
+                        }
+                        tracing::debug!("📎 Multipart file part '{}': {} bytes", part_name, content.len());
+                        form = form.part(part_name, part);
+                    } else {
+                        let text = match value {
+                            Value::String(text) => text.clone(),
+                            Value::Null => continue,
+                            other => other.to_string(),
+                        };
+                        form = form.text(part_name, text);
+                    }
+                }
+                request_builder = request_builder.multipart(form);
+            } else if !input_values.is_empty() && method.has_body() {
+                // Use the first input pin as request body (if method supports it)
                 let body_data = &input_values[0];
-                tracing::debug!("📦 Request body: {}", body_data);
+                tracing::debug!("📦 Request body: {}", preview_value(body_data));
                 
                 // Set content-type and body based on data type
-                if body_data.is_object() || body_data.is_array() {
+                if let Some(binary) = BinaryRef::from_value(body_data) {
+                    let content = context.binaries.read(&binary).await?;
+                    tracing::debug!("📎 Sending binary body: {} bytes ({})", content.len(), binary.content_type);
+                    request_builder = request_builder
+                        .header("Content-Type", binary.content_type.as_str())
+                        .body(content);
+                } else if body_data.is_object() || body_data.is_array() {
                     request_builder = request_builder
                         .header("Content-Type", "application/json")
                         .json(body_data);
@@ -995,10 +1352,18 @@ impl NodeExecutor {
             }
         }
 
-        // Make the HTTP request
+        // Make the HTTP request (signed over the final body when "sign" is set)
         tracing::debug!("🚀 Sending HTTP request");
-        let response = request_builder.send().await
-            .map_err(|e| anyhow::anyhow!("HTTP request failed: {}", e))?;
+        let response = match &sign_config {
+            Some(sign_config) => {
+                let mut request = request_builder.build()
+                    .map_err(|e| anyhow::anyhow!("HTTP request failed: {}", e))?;
+                sign_config.sign(&mut request, chrono::Utc::now(), |pin| self.resolve_secret(&context.project_slug, pin)).map_err(|e| anyhow::anyhow!("HTTPClient node '{}' failed to sign request: {}", node.id, e))?;
+                tracing::debug!("🔏 Request signed");
+                client.execute(request).await
+            }
+            None => request_builder.send().await,
+        }.map_err(|e| outbox::request_error("HTTP request failed", e))?;
 
         let status = response.status();
         let headers_map: HashMap<String, String> = response.headers()
@@ -1010,6 +1375,51 @@ impl NodeExecutor {
 
         tracing::debug!("📡 Response status: {}", status);
 
+        // DOWNLOAD: Stream the body straight to {project}/uploads/ instead of buffering it
+        if download_to_file {
+            let file_info = self.download_response_to_file(response, &headers_map, &context.project_slug).await?;
+
+            tracing::info!("✅ HTTP request completed: {} {} (status: {}, downloaded: {} bytes to {})",
+                method, url, status, file_info.size, file_info.path);
+
+            return Ok(ExecutionResult {
+                data: vec![json!({
+                    "status": status.as_u16(),
+                    "headers": headers_map,
+                    "data": file_info,
+                    "success": status.is_success()
+                })],
+                metadata: context.metadata,
+                termination: http_termination(node, status),
+            });
+        }
+
+        // Response format: "auto" (default) detects binary content types, "binary" forces a binary item
+        let content_type = headers_map.get("content-type")
+            .cloned()
+            .unwrap_or_else(|| "application/octet-stream".to_string());
+
+        if response_format == ResponseFormat::Binary || (response_format == ResponseFormat::Auto && !is_textual_content_type(&content_type)) {
+            let filename = response_filename(&headers_map, response.url());
+            let content = response.bytes().await
+                .map_err(|e| anyhow::anyhow!("Failed to read response body: {}", e))?;
+            let binary = context.binaries.put(content, filename, content_type);
+
+            tracing::info!("✅ HTTP request completed: {} {} (status: {}, binary: {} bytes)",
+                method, url, status, binary.size);
+
+            return Ok(ExecutionResult {
+                data: vec![json!({
+                    "status": status.as_u16(),
+                    "headers": headers_map,
+                    "data": binary.to_item(),
+                    "success": status.is_success()
+                })],
+                metadata: context.metadata,
+                termination: http_termination(node, status),
+            });
+        }
+
         // Parse response body as JSON if possible, otherwise as text
         let response_text = response.text().await
             .map_err(|e| anyhow::anyhow!("Failed to read response body: {}", e))?;
@@ -1035,7 +1445,55 @@ impl NodeExecutor {
         Ok(ExecutionResult {
             data: vec![response_data], // Wrap in array for consistency
             metadata: context.metadata,
-            should_continue: status.is_success(),
+            termination: http_termination(node, status),
+        })
+    }
+
+    /// Stream an HTTP response body into the project's uploads directory
+    /// 
+    /// Chunks are written as they arrive, so large downloads never sit in memory.
+    /// A partially written file is removed if the transfer fails.
+    async fn download_response_to_file(
+        &self,
+        mut response: reqwest::Response,
+        headers: &HashMap<String, String>,
+        project_slug: &str,
+    ) -> Result<FileInfo> {
+        use tokio::io::AsyncWriteExt;
+
+        let uploads_dir = self.project_db_manager.project_dir(project_slug)?.join("uploads");
+        tokio::fs::create_dir_all(&uploads_dir).await?;
+
+        let filename = response_filename(headers, response.url())
+            .map(|name| sanitize_filename(&name))
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| "download".to_string());
+        let content_type = headers.get("content-type")
+            .cloned()
+            .unwrap_or_else(|| "application/octet-stream".to_string());
+        let path = uploads_dir.join(format!("{}-{}", uuid::Uuid::new_v4(), filename));
+
+        let mut file = tokio::fs::File::create(&path).await?;
+        let mut size: u64 = 0;
+        let transfer: Result<()> = async {
+            while let Some(chunk) = response.chunk().await? {
+                file.write_all(&chunk).await?;
+                size += chunk.len() as u64;
+            }
+            file.flush().await?;
+            Ok(())
+        }.await;
+
+        if let Err(e) = transfer {
+            let _ = tokio::fs::remove_file(&path).await;
+            return Err(anyhow::anyhow!("Failed to download response body: {}", e));
+        }
+
+        Ok(FileInfo {
+            filename,
+            content_type,
+            size,
+            path: path.to_string_lossy().into_owned(),
         })
     }
 
@@ -1045,6 +1503,70 @@ impl NodeExecutor {
     async fn execute_pgquery_node(&self, node: &Node, context: ExecutionContext) -> Result<ExecutionResult> {
         tracing::debug!("🐘 Executing PGQuery node: {}", node.id);
         
+        // Cursor mode is streamed batch by batch by the engine; run on its own
+        // (e.g. in the step debugger) the node returns the first batch
+        if let Some(batch_size) = cursor_batch_size(&node.params)
+            .map_err(|e| anyhow::anyhow!("PGQuery node '{}': {}", node.id, e))?
+        {
+            let mut cursor = self.open_pgquery_cursor(node, &context, batch_size).await?;
+            let data = cursor.next_batch().await?.unwrap_or_default();
+            cursor.close().await?;
+            return Ok(ExecutionResult {
+                data,
+                metadata: context.metadata,
+                termination: Termination::Completed,
+            });
+        }
+        
+        let (connection_string, query, bind_params) = self.pgquery_request(node, &context)?;
+        
+        // STEP 5: Execute on a connection from the pool kept per connection string
+        let connection = self.project_db_manager.pg_pools().acquire(&connection_string, None).await
+            .map_err(|e| anyhow::anyhow!("PGQuery node '{}' failed to connect: {}", node.id, e))?;
+        let statement = connection.prepare(query).await
+            .map_err(|e| anyhow::anyhow!("PGQuery node '{}' failed to prepare query: {}", node.id, db_error(e)))?;
+        if bind_params.len() != statement.params().len() {
+            return Err(anyhow::anyhow!("PGQuery node '{}': query expects {} bind parameters, got {}",
+                node.id, statement.params().len(), bind_params.len()));
+        }
+        let params = bind_params.iter()
+            .zip(statement.params())
+            .map(|(value, ty)| json_to_param(value, ty))
+            .collect::<Result<Vec<_>>>()
+            .map_err(|e| anyhow::anyhow!("PGQuery node '{}': {}", node.id, e))?;
+        let refs: Vec<&(dyn ToSql + Sync)> = params.iter().map(|p| p.as_ref() as &(dyn ToSql + Sync)).collect();
+        
+        // STEP 6: Rows become the data array; statements without a result set report the rows they affected
+        let data = if statement.columns().is_empty() {
+            let rows_affected = connection.execute(&statement, &refs).await
+                .map_err(|e| anyhow::anyhow!("PGQuery node '{}' failed: {}", node.id, db_error(e)))?;
+            vec![json!({ "rows_affected": rows_affected })]
+        } else {
+            let rows = connection.query(&statement, &refs).await
+                .map_err(|e| anyhow::anyhow!("PGQuery node '{}' failed: {}", node.id, db_error(e)))?;
+            rows.iter().map(row_to_json).collect()
+        };
+        
+        tracing::info!("✅ PGQuery node '{}' completed: {} item(s)", node.id, data.len());
+        
+        Ok(ExecutionResult {
+            data,
+            metadata: context.metadata,
+            termination: Termination::Completed,
+        })
+    }
+    
+    /// Open a server-side cursor for a cursor-mode PGQuery node
+    pub async fn open_pgquery_cursor(&self, node: &Node, context: &ExecutionContext, batch_size: usize) -> Result<PgCursor> {
+        let (connection_string, query, bind_params) = self.pgquery_request(node, context)?;
+        let connection = self.project_db_manager.pg_pools().acquire(&connection_string, None).await?;
+        tracing::info!("🐘 PGQuery node '{}' streaming in batches of {}", node.id, batch_size);
+        PgCursor::open(connection, query, &bind_params, batch_size).await
+            .map_err(|e| anyhow::anyhow!("PGQuery node '{}' failed to open cursor: {}", node.id, e))
+    }
+    
+    /// Resolve a PGQuery node's connection string, SQL and bind parameters
+    fn pgquery_request<'n>(&self, node: &'n Node, context: &ExecutionContext) -> Result<(String, &'n str, Vec<Value>)> {
         // STEP 1: MANDATORY secret validation (no fallbacks!)
         let secrets = node.secrets.as_ref()
             .ok_or_else(|| anyhow::anyhow!("PGQuery node '{}' REQUIRES secrets field - no fallbacks allowed!", node.id))?;
@@ -1054,8 +1576,9 @@ impl NodeExecutor {
         }
         
         // STEP 2: Resolve secrets (database connection strings)
-        let resolved_secrets = self.evaluate_secret_pins(secrets)?;
-        let connection_string = resolved_secrets.get(0)
+        let connection_string = self.evaluate_secret_pins(&context.project_slug, secrets)?
+            .into_iter()
+            .next()
             .ok_or_else(|| anyhow::anyhow!("PGQuery node '{}' failed to resolve database connection secret", node.id))?;
         
         tracing::debug!("🔐 Using database connection for node: {}", node.id);
@@ -1068,40 +1591,574 @@ impl NodeExecutor {
         tracing::debug!("📝 SQL Query: {}", query);
         
         // STEP 4: Resolve input pins for bind parameters
-        let bind_params = if let Some(inputs) = &node.inputs {
-            self.evaluate_input_pins(inputs, &context)?
+        let bind_params = if node.inputs.is_some() {
+            self.evaluate_input_pins(node, context)?
         } else {
             Vec::new()
         };
         
-        tracing::debug!("🔗 Bind parameters: {:?}", bind_params);
+        tracing::debug!("🔗 Bind parameters: {}", preview_debug(&bind_params));
+        
+        Ok((connection_string, query, bind_params))
+    }
+    
+    /// Execute PGDynTableWriter node for ETL operations
+    /// 
+    /// INDUSTRIAL-GRADE: Auto-creates mway_dynamic_tables schema and table
+    /// ETL-FOCUSED: Designed for data pipeline operations to user's business databases
+    /// Execute StripeAPI node (key from the node's mandatory secret)
+    async fn execute_stripe_api_node(&self, node: &Node, context: ExecutionContext) -> Result<ExecutionResult> {
+        tracing::debug!("💳 Executing StripeAPI node: {}", node.id);
+        
+        let secrets = node.secrets.as_ref().filter(|s| !s.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("StripeAPI node '{}' REQUIRES secrets field with the API key", node.id))?;
+        let api_key = self.resolve_secret(&context.project_slug, &secrets[0])?;
+        
+        let stripe = StripeApiParams::from_node_params(&node.params)
+            .map_err(|e| anyhow::anyhow!("StripeAPI node '{}': {}", node.id, e))?;
+        let input_values = self.evaluate_input_pins(node, &context)?;
+        let form = stripe.form(&input_values);
+        let idempotency_key = match &stripe.idempotency_key {
+            Some(expr) => match self.evaluate_pin(&PinExpr::parse(expr), &context)? {
+                Value::Null => None,
+                Value::String(key) => Some(key),
+                other => Some(other.to_string()),
+            },
+            None => None,
+        };
+        
+        tracing::debug!("💳 Stripe {:?} with {} form fields", stripe.operation, form.len());
+        let object = stripe.send(&api_key, &form, idempotency_key).await
+            .map_err(|e| anyhow::anyhow!("StripeAPI node '{}': {}", node.id, e))?;
+        tracing::info!("✅ Stripe {:?} succeeded: {}", stripe.operation,
+            object.get("id").and_then(|id| id.as_str()).unwrap_or("-"));
+        
+        Ok(ExecutionResult {
+            data: vec![object],
+            metadata: context.metadata,
+            termination: Termination::Completed,
+        })
+    }
+
+    /// Execute GitHubAPI node (token from the node's mandatory secret)
+    async fn execute_github_api_node(&self, node: &Node, context: ExecutionContext) -> Result<ExecutionResult> {
+        tracing::debug!("🐙 Executing GitHubAPI node: {}", node.id);
+        
+        let secrets = node.secrets.as_ref().filter(|s| !s.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("GitHubAPI node '{}' REQUIRES secrets field with the token", node.id))?;
+        let token = self.resolve_secret(&context.project_slug, &secrets[0])?;
+        
+        let github = GitHubApiParams::from_node_params(&node.params)
+            .map_err(|e| anyhow::anyhow!("GitHubAPI node '{}': {}", node.id, e))?;
+        let pin_text = |expr: &str| -> Result<String> {
+            Ok(match self.evaluate_pin(&PinExpr::parse(expr), &context)? {
+                Value::String(text) => text,
+                other => other.to_string(),
+            })
+        };
+        let target = GitHubTarget {
+            repo: pin_text(&github.repo)?,
+            issue_number: github.issue_number.as_deref().map(pin_text).transpose()?,
+            sha: github.sha.as_deref().map(pin_text).transpose()?,
+        };
+        let input_values = self.evaluate_input_pins(node, &context)?;
+        let body = github.body(&input_values);
         
-        // STEP 5: Execute PostgreSQL query (placeholder implementation)
-        // TODO: Implement actual tokio-postgres connection and query execution
-        tracing::warn!("🚨 PGQuery execution not fully implemented yet - returning placeholder");
+        tracing::debug!("🐙 GitHub {:?} on {}", github.operation, target.repo);
+        let response = github.send(&token, &target, &body).await
+            .map_err(|e| anyhow::anyhow!("GitHubAPI node '{}': {}", node.id, e))?;
+        tracing::info!("✅ GitHub {:?} succeeded on {}", github.operation, target.repo);
         
-        let placeholder_result = json!({
-            "query": query,
-            "connection": "REDACTED",
-            "bind_params": bind_params,
-            "rows": [],
-            "row_count": 0,
-            "executed_at": chrono::Utc::now().to_rfc3339()
-        });
+        Ok(ExecutionResult {
+            data: vec![response],
+            metadata: context.metadata,
+            termination: Termination::Completed,
+        })
+    }
+
+    /// Execute TwilioSend node: one SMS / WhatsApp message per input item
+    async fn execute_twilio_send_node(&self, node: &Node, context: ExecutionContext) -> Result<ExecutionResult> {
+        tracing::debug!("📱 Executing TwilioSend node: {}", node.id);
+        
+        let secrets = node.secrets.as_ref().filter(|s| s.len() >= 2)
+            .ok_or_else(|| anyhow::anyhow!("TwilioSend node '{}' REQUIRES secrets [account_sid, auth_token]", node.id))?;
+        let account_sid = self.resolve_secret(&context.project_slug, &secrets[0])?;
+        let auth_token = self.resolve_secret(&context.project_slug, &secrets[1])?;
+        
+        let twilio = TwilioParams::from_node_params(&node.params)
+            .map_err(|e| anyhow::anyhow!("TwilioSend node '{}': {}", node.id, e))?;
+        let render_text = |template: &Value, item_context: &ExecutionContext| -> Result<String> {
+            Ok(match self.render_template(template, item_context)? {
+                Value::String(text) => text,
+                Value::Null => String::new(),
+                other => other.to_string(),
+            })
+        };
+        
+        // One message per input item, rendered like Mock templates
+        let mut item_context = context.clone();
+        let mut results = Vec::with_capacity(context.data.len());
+        for (index, item) in context.data.iter().enumerate() {
+            item_context.data = vec![item.clone()];
+            let message = TwilioMessage {
+                to: render_text(&twilio.to, &item_context)?,
+                from: render_text(&twilio.from, &item_context)?,
+                body: render_text(&twilio.body, &item_context)?,
+                media_url: twilio.media_url.as_ref()
+                    .map(|template| render_text(template, &item_context))
+                    .transpose()?
+                    .filter(|url| !url.is_empty()),
+            };
+            let result = twilio.send(&account_sid, &auth_token, &message).await
+                .map_err(|e| anyhow::anyhow!("TwilioSend node '{}' (item {}): {}", node.id, index, e))?;
+            tracing::debug!("📱 Message {} to item {}: {}", result["sid"], index, result["status"]);
+            results.push(result);
+        }
+        
+        tracing::info!("✅ TwilioSend node '{}' sent {} message(s)", node.id, results.len());
+        
+        Ok(ExecutionResult {
+            data: results,
+            metadata: context.metadata,
+            termination: Termination::Completed,
+        })
+    }
+
+    /// Execute PushNotification node: one FCM / Web Push message per input item
+    async fn execute_push_notification_node(&self, node: &Node, context: ExecutionContext) -> Result<ExecutionResult> {
+        tracing::debug!("🔔 Executing PushNotification node: {}", node.id);
+        
+        let secrets = node.secrets.as_ref().filter(|s| !s.is_empty())
+            .ok_or_else(|| anyhow::anyhow!(
+                "PushNotification node '{}' REQUIRES secrets [service_account_json] (fcm) or [vapid_private_key] (webpush)", node.id
+            ))?;
+        let credential = self.resolve_secret(&context.project_slug, &secrets[0])?;
+        let push = PushParams::from_node_params(&node.params)
+            .map_err(|e| anyhow::anyhow!("PushNotification node '{}': {}", node.id, e))?;
+        
+        // CREDENTIALS: One FCM access token / VAPID key for the whole batch
+        let client = reqwest::Client::new();
+        let (fcm, vapid) = match push.provider {
+            PushProvider::Fcm => {
+                let account = FcmAccount::from_json(&credential)
+                    .map_err(|e| anyhow::anyhow!("PushNotification node '{}': {}", node.id, e))?;
+                let token = account.access_token(&client).await
+                    .map_err(|e| anyhow::anyhow!("PushNotification node '{}': {}", node.id, e))?;
+                (Some((account, token)), None)
+            }
+            PushProvider::WebPush => (None, Some(VapidKey::from_base64(&credential)
+                .map_err(|e| anyhow::anyhow!("PushNotification node '{}': {}", node.id, e))?)),
+        };
+        
+        let mut item_context = context.clone();
+        let mut results = Vec::with_capacity(context.data.len());
+        for (index, item) in context.data.iter().enumerate() {
+            item_context.data = vec![item.clone()];
+            let rendered = self.render_template(&push.templates, &item_context)?;
+            let result = match (&fcm, &vapid) {
+                (Some((account, token)), _) => send_fcm(&client, &push, account, token, &rendered).await,
+                (_, Some(vapid)) => send_webpush(&client, &push, vapid, &rendered).await,
+                _ => unreachable!("credentials match the provider"),
+            }.map_err(|e| anyhow::anyhow!("PushNotification node '{}' (item {}): {}", node.id, index, e))?;
+            if result["invalid_target"] == json!(true) {
+                tracing::warn!("🔔 PushNotification node '{}' item {}: target no longer valid", node.id, index);
+            }
+            results.push(result);
+        }
+        
+        tracing::info!("✅ PushNotification node '{}' sent {} notification(s)", node.id, results.len());
+        
+        Ok(ExecutionResult {
+            data: results,
+            metadata: context.metadata,
+            termination: Termination::Completed,
+        })
+    }
+
+    /// Execute CalendarEvent node: build an .ics per input item, or parse .ics input into events
+    async fn execute_calendar_event_node(&self, node: &Node, context: ExecutionContext) -> Result<ExecutionResult> {
+        tracing::debug!("📅 Executing CalendarEvent node: {}", node.id);
+        
+        let calendar = CalendarParams::from_node_params(&node.params)
+            .map_err(|e| anyhow::anyhow!("CalendarEvent node '{}': {}", node.id, e))?;
+        
+        let mut item_context = context.clone();
+        let mut results = Vec::with_capacity(context.data.len());
+        for (index, item) in context.data.iter().enumerate() {
+            item_context.data = vec![item.clone()];
+            let rendered = self.render_template(&calendar.templates, &item_context)?;
+            let item_error = |e: anyhow::Error| anyhow::anyhow!("CalendarEvent node '{}' (item {}): {}", node.id, index, e);
+            match calendar.operation {
+                CalendarOperation::Create => results.push(calendar.create(&rendered).map_err(item_error)?),
+                CalendarOperation::Parse => {
+                    let ics = rendered["ics"].as_str().filter(|ics| !ics.trim().is_empty())
+                        .ok_or_else(|| item_error(anyhow::anyhow!("'ics' rendered empty or not text")))?;
+                    results.extend(parse_ics(ics).map_err(item_error)?);
+                }
+            }
+        }
+        
+        tracing::info!("✅ CalendarEvent node '{}' produced {} event(s)", node.id, results.len());
+        
+        Ok(ExecutionResult {
+            data: results,
+            metadata: context.metadata,
+            termination: Termination::Completed,
+        })
+    }
+
+    /// Execute GeoIP node: add country / city / ASN of an IP pin to each input item
+    async fn execute_geoip_node(&self, node: &Node, context: ExecutionContext) -> Result<ExecutionResult> {
+        tracing::debug!("🌍 Executing GeoIP node: {}", node.id);
+        
+        let ip_template = node.params.get("ip").filter(|v| !v.is_null())
+            .ok_or_else(|| anyhow::anyhow!("GeoIP node '{}' REQUIRES 'ip' parameter", node.id))?;
+        let output_field = node.params.get("output_field").and_then(|f| f.as_str()).unwrap_or("geo");
+        let language = node.params.get("language").and_then(|l| l.as_str()).unwrap_or("en");
+        
+        // DATABASES: Configured per project, readers cached across executions
+        let settings = GeoIpSettings::from_project_settings(
+            &self.project_db_manager.get_project_settings(&context.project_slug).await?
+        );
+        if !settings.is_configured() {
+            return Err(anyhow::anyhow!(
+                "GeoIP node '{}': project '{}' has no GeoIP database (PUT /api/projects/{}/geoip)",
+                node.id, context.project_slug, context.project_slug
+            ));
+        }
+        let project_dir = self.project_db_manager.project_dir(&context.project_slug)?;
+        let city_db = match &settings.city_db {
+            Some(path) => Some(self.geoip.get(&geoip::resolve_path(&project_dir, path)).await?),
+            None => None,
+        };
+        let asn_db = match &settings.asn_db {
+            Some(path) => Some(self.geoip.get(&geoip::resolve_path(&project_dir, path)).await?),
+            None => None,
+        };
+        
+        let mut item_context = context.clone();
+        let mut results = Vec::with_capacity(context.data.len());
+        for (index, item) in context.data.iter().enumerate() {
+            item_context.data = vec![item.clone()];
+            let ip = match self.render_template(ip_template, &item_context)? {
+                Value::String(text) => geoip::parse_ip(&text),
+                _ => None,
+            };
+            let geo = match ip {
+                Some(ip) => geoip::lookup(ip, city_db.as_deref(), asn_db.as_deref(), language)
+                    .map_err(|e| anyhow::anyhow!("GeoIP node '{}' (item {}): {}", node.id, index, e))?,
+                None => Value::Null,
+            };
+            
+            let mut enriched = item.clone();
+            match enriched.as_object_mut() {
+                Some(object) => { object.insert(output_field.to_string(), geo); }
+                None => enriched = json!({ "value": item, output_field: geo }),
+            }
+            results.push(enriched);
+        }
+        
+        tracing::info!("✅ GeoIP node '{}' enriched {} item(s)", node.id, results.len());
+        
+        Ok(ExecutionResult {
+            data: results,
+            metadata: context.metadata,
+            termination: Termination::Completed,
+        })
+    }
+
+    /// Execute Math node: evaluate numeric expressions over the input pins of each item
+    async fn execute_math_node(&self, node: &Node, context: ExecutionContext) -> Result<ExecutionResult> {
+        tracing::debug!("🧮 Executing Math node: {}", node.id);
+        
+        let program = MathProgram::from_node_params(&node.params)
+            .map_err(|e| anyhow::anyhow!("Math node '{}': {}", node.id, e))?;
+        let pin_count = node.input_pins().map(|pins| pins.len()).unwrap_or(0);
+        if pin_count != program.variables().len() {
+            return Err(anyhow::anyhow!("Math node '{}' has {} input pin(s) for {} variable(s)",
+                node.id, pin_count, program.variables().len()));
+        }
+        let output_field = node.params.get("output_field").and_then(|f| f.as_str());
+        let null_on_error = node.params.get("on_error").and_then(|o| o.as_str()) == Some("null");
+        
+        let mut item_context = context.clone();
+        let mut results = Vec::with_capacity(context.data.len());
+        for (index, item) in context.data.iter().enumerate() {
+            item_context.data = vec![item.clone()];
+            let inputs = self.evaluate_input_pins(node, &item_context)?;
+            let outputs = match program.evaluate(&inputs) {
+                Ok(outputs) => outputs,
+                Err(e) if null_on_error => {
+                    tracing::debug!("🧮 Math node '{}' item {}: {}", node.id, index, e);
+                    node.params["expressions"].as_object().into_iter().flatten()
+                        .map(|(name, _)| (name.clone(), Value::Null))
+                        .collect()
+                }
+                Err(e) => return Err(anyhow::anyhow!("Math node '{}' (item {}): {}", node.id, index, e)),
+            };
+            
+            let mut enriched = item.clone();
+            match (output_field, enriched.as_object_mut()) {
+                (Some(field), Some(object)) => { object.insert(field.to_string(), Value::Object(outputs)); }
+                (None, Some(object)) => object.extend(outputs),
+                (_, None) => enriched = Value::Object(outputs),
+            }
+            results.push(enriched);
+        }
+        
+        tracing::info!("✅ Math node '{}' evaluated {} item(s)", node.id, results.len());
+        
+        Ok(ExecutionResult {
+            data: results,
+            metadata: context.metadata,
+            termination: Termination::Completed,
+        })
+    }
+
+    /// Execute CurrencyConvert node: convert amounts with cached provider exchange rates
+    async fn execute_currency_convert_node(&self, node: &Node, context: ExecutionContext) -> Result<ExecutionResult> {
+        tracing::debug!("💱 Executing CurrencyConvert node: {}", node.id);
+        
+        let params = CurrencyParams::from_node_params(&node.params)
+            .map_err(|e| anyhow::anyhow!("CurrencyConvert node '{}': {}", node.id, e))?;
+        let api_key = match node.secrets.as_ref().and_then(|secrets| secrets.first()) {
+            Some(secret) => Some(self.resolve_secret(&context.project_slug, secret)?),
+            None if params.provider.needs_api_key() => {
+                return Err(anyhow::anyhow!("CurrencyConvert node '{}' requires an API key secret for provider '{}'",
+                    node.id, params.provider.name()));
+            }
+            None => None,
+        };
+        let (table, stale) = currency::cached_rates(&self.project_db_manager, &context.project_slug, &params, api_key.as_deref()).await
+            .map_err(|e| anyhow::anyhow!("CurrencyConvert node '{}': {}", node.id, e))?;
+        
+        let mut item_context = context.clone();
+        let mut results = Vec::with_capacity(context.data.len());
+        for (index, item) in context.data.iter().enumerate() {
+            item_context.data = vec![item.clone()];
+            let rendered = self.render_template(&params.templates, &item_context)?;
+            let conversion = params.convert(&table, stale, &rendered)
+                .map_err(|e| anyhow::anyhow!("CurrencyConvert node '{}' (item {}): {}", node.id, index, e))?;
+            
+            let mut enriched = item.clone();
+            match enriched.as_object_mut() {
+                Some(object) => { object.insert(params.output_field.clone(), conversion); }
+                None => enriched = json!({ params.output_field.clone(): conversion }),
+            }
+            results.push(enriched);
+        }
+        
+        tracing::info!("✅ CurrencyConvert node '{}' converted {} item(s) ({} rates as of {})",
+            node.id, results.len(), params.provider.name(), table.as_of);
+        
+        Ok(ExecutionResult {
+            data: results,
+            metadata: context.metadata,
+            termination: Termination::Completed,
+        })
+    }
+
+    /// Execute ParquetWrite node: encode the data array as one Parquet file (binary item)
+    async fn execute_parquet_write_node(&self, node: &Node, context: ExecutionContext) -> Result<ExecutionResult> {
+        tracing::debug!("🧱 Executing ParquetWrite node: {}", node.id);
+        
+        let params = ParquetParams::from_node_params(&node.params)
+            .map_err(|e| anyhow::anyhow!("ParquetWrite node '{}': {}", node.id, e))?;
+        let filename = self.render_filename(&params.filename, &context)
+            .map_err(|e| anyhow::anyhow!("ParquetWrite node '{}': {}", node.id, e))?;
+        
+        // A single array item (e.g. a webhook JSON array body) holds the rows itself
+        let items = match context.data.as_slice() {
+            [Value::Array(rows)] => rows.clone(),
+            items => items.to_vec(),
+        };
+        let row_count = items.len();
+        
+        // Encoding is CPU-bound, keep it off the async workers
+        let (columns, content) = tokio::task::spawn_blocking(move || -> Result<_> {
+            let columns = match params.columns.clone() {
+                Some(columns) => columns,
+                None => parquet_write::infer_columns(&items)?,
+            };
+            let content = parquet_write::write_parquet(&items, &columns, &params)?;
+            Ok((columns, content))
+        }).await?
+            .map_err(|e| anyhow::anyhow!("ParquetWrite node '{}': {}", node.id, e))?;
+        
+        let binary = context.binaries.put(content, Some(filename), PARQUET_CONTENT_TYPE.to_string());
+        tracing::info!("✅ ParquetWrite node '{}' wrote {} row(s) x {} column(s) ({} bytes)",
+            node.id, row_count, columns.len(), binary.size);
+        
+        let mut item = binary.to_item();
+        item["rows"] = json!(row_count);
+        item["columns"] = Value::Array(columns.iter().map(|column| column.to_json()).collect());
+        
+        Ok(ExecutionResult {
+            data: vec![item],
+            metadata: context.metadata,
+            termination: Termination::Completed,
+        })
+    }
+
+    /// Execute NdjsonParse node: parse JSON Lines from the first input pin into items
+    /// 
+    /// With "batch_size" the engine streams the batches through the downstream nodes;
+    /// run on its own (e.g. in the step debugger) the node returns the first batch.
+    async fn execute_ndjson_parse_node(&self, node: &Node, context: ExecutionContext) -> Result<ExecutionResult> {
+        tracing::debug!("📜 Executing NdjsonParse node: {}", node.id);
+        
+        let batch_size = ndjson_batch_size(&node.params)
+            .map_err(|e| anyhow::anyhow!("NdjsonParse node '{}': {}", node.id, e))?;
+        // Without a batch size the first batch is the whole input
+        let mut reader = self.open_ndjson_reader(node, &context, batch_size.unwrap_or(usize::MAX)).await?;
+        let data = reader.next_batch().await
+            .map_err(|e| anyhow::anyhow!("NdjsonParse node '{}': {}", node.id, e))?
+            .unwrap_or_default();
         
-        tracing::info!("✅ PGQuery placeholder completed: {}", node.id);
+        tracing::info!("✅ NdjsonParse node '{}' parsed {} item(s) from {} line(s) ({} skipped)",
+            node.id, data.len(), reader.lines, reader.skipped);
         
         Ok(ExecutionResult {
-            data: vec![placeholder_result],
+            data,
             metadata: context.metadata,
-            should_continue: true,
+            termination: Termination::Completed,
         })
     }
     
-    /// Execute PGDynTableWriter node for ETL operations
+    /// Open a line reader over an NdjsonParse node's input (text, uploaded file or binary item)
+    pub async fn open_ndjson_reader(&self, node: &Node, context: &ExecutionContext, batch_size: usize) -> Result<NdjsonReader> {
+        let fail = |e: anyhow::Error| anyhow::anyhow!("NdjsonParse node '{}': {}", node.id, e);
+        let source = self.evaluate_input_pins(node, context)?.into_iter().next()
+            .ok_or_else(|| anyhow::anyhow!("NdjsonParse node '{}' needs an input pin with the NDJSON text or file", node.id))?;
+        let skip_invalid = match node.params.get("on_error").and_then(|o| o.as_str()).unwrap_or("fail") {
+            "fail" => false,
+            "skip" => true,
+            other => return Err(fail(anyhow::anyhow!("Unknown on_error '{}' (fail, skip)", other))),
+        };
+        
+        if let Some(binary) = BinaryRef::from_value(&source) {
+            // File-backed content is streamed from disk, never loaded whole
+            return match &binary.path {
+                Some(path) => NdjsonReader::open_file(std::path::Path::new(path), batch_size, skip_invalid).await.map_err(fail),
+                None => Ok(NdjsonReader::from_bytes(context.binaries.read(&binary).await?, batch_size, skip_invalid)),
+            };
+        }
+        match source {
+            Value::String(text) => Ok(NdjsonReader::from_bytes(bytes::Bytes::from(text), batch_size, skip_invalid)),
+            other => Err(fail(anyhow::anyhow!("Input must be NDJSON text, a file or a binary item, got {}", preview_value(&other)))),
+        }
+    }
+    
+    /// Execute NdjsonWrite node: serialize the data array as JSON Lines into an uploads file
     /// 
-    /// INDUSTRIAL-GRADE: Auto-creates mway_dynamic_tables schema and table
-    /// ETL-FOCUSED: Designed for data pipeline operations to user's business databases
+    /// The batches of one stream (cursor-mode PGQuery, batched NdjsonParse) append
+    /// to the same file; the output references the file written so far.
+    async fn execute_ndjson_write_node(&self, node: &Node, context: ExecutionContext) -> Result<ExecutionResult> {
+        tracing::debug!("📜 Executing NdjsonWrite node: {}", node.id);
+        
+        let template = node.params.get("filename").cloned().unwrap_or_else(|| json!("data-%Y%m%dT%H%M%SZ.ndjson"));
+        let filename = self.render_filename(&template, &context)
+            .map(|filename| sanitize_filename(&filename))
+            .map_err(|e| anyhow::anyhow!("NdjsonWrite node '{}': {}", node.id, e))?;
+        if filename.is_empty() {
+            return Err(anyhow::anyhow!("NdjsonWrite node '{}': filename is empty", node.id));
+        }
+        
+        // One file per stream: the stream id names it, later batches append
+        let stream = context.metadata.get("stream");
+        let append = stream.and_then(|s| s["batch"].as_u64()).is_some_and(|batch| batch > 1);
+        let prefix = match stream.and_then(|s| s["id"].as_str()) {
+            Some(stream_id) => format!("{}-{}", stream_id, node.id),
+            None => uuid::Uuid::new_v4().to_string(),
+        };
+        let uploads_dir = self.project_db_manager.project_dir(&context.project_slug)?.join("uploads");
+        tokio::fs::create_dir_all(&uploads_dir).await?;
+        let path = uploads_dir.join(format!("{}-{}", sanitize_filename(&prefix), filename));
+        
+        let size = ndjson::write_lines(&path, &context.data, append).await
+            .map_err(|e| anyhow::anyhow!("NdjsonWrite node '{}': {}", node.id, e))?;
+        tracing::info!("✅ NdjsonWrite node '{}' {} {} line(s) to {} ({} bytes)", node.id,
+            if append { "appended" } else { "wrote" }, context.data.len(), path.display(), size);
+        
+        let binary = BinaryRef::from_file(path.to_string_lossy().into_owned(), Some(filename),
+            NDJSON_CONTENT_TYPE.to_string(), size);
+        let mut item = binary.to_item();
+        item["rows"] = json!(context.data.len());
+        
+        Ok(ExecutionResult {
+            data: vec![item],
+            metadata: context.metadata,
+            termination: Termination::Completed,
+        })
+    }
+
+    /// Execute Excel node: read a sheet into items or write the data array as a workbook
+    async fn execute_excel_node(&self, node: &Node, context: ExecutionContext) -> Result<ExecutionResult> {
+        tracing::debug!("📗 Executing Excel node: {}", node.id);
+        
+        let fail = |e: anyhow::Error| anyhow::anyhow!("Excel node '{}': {}", node.id, e);
+        match ExcelOperation::from_node_params(&node.params).map_err(fail)? {
+            ExcelOperation::Read => {
+                let params = ExcelReadParams::from_node_params(&node.params);
+                let source = self.evaluate_input_pins(node, &context)?.into_iter().next()
+                    .ok_or_else(|| anyhow::anyhow!("Excel node '{}' needs an input pin with the workbook file", node.id))?;
+                let binary = BinaryRef::from_value(&source)
+                    .ok_or_else(|| fail(anyhow::anyhow!("Input is not a file or binary item: {}", preview_value(&source))))?;
+                let content = context.binaries.read(&binary).await?;
+                
+                // Parsing is CPU-bound, keep it off the async workers
+                let data = tokio::task::spawn_blocking(move || excel::read_workbook(content, &params)).await?
+                    .map_err(fail)?;
+                tracing::info!("✅ Excel node '{}' read {} row(s) from {}", node.id, data.len(),
+                    binary.filename.as_deref().unwrap_or("workbook"));
+                
+                Ok(ExecutionResult {
+                    data,
+                    metadata: context.metadata,
+                    termination: Termination::Completed,
+                })
+            }
+            ExcelOperation::Write => {
+                let params = ExcelWriteParams::from_node_params(&node.params).map_err(fail)?;
+                let filename = self.render_filename(&params.filename, &context)
+                    .map(|filename| sanitize_filename(&filename))
+                    .map_err(fail)?;
+                if filename.is_empty() {
+                    return Err(fail(anyhow::anyhow!("filename is empty")));
+                }
+                
+                // A single array item (e.g. a webhook JSON array body) holds the rows itself
+                let items = match context.data.as_slice() {
+                    [Value::Array(rows)] => rows.clone(),
+                    items => items.to_vec(),
+                };
+                let row_count = items.len();
+                let sheet = params.sheet.clone();
+                let (content, column_count) = tokio::task::spawn_blocking(move || excel::write_workbook(&items, &params)).await?
+                    .map_err(fail)?;
+                
+                let uploads_dir = self.project_db_manager.project_dir(&context.project_slug)?.join("uploads");
+                tokio::fs::create_dir_all(&uploads_dir).await?;
+                let path = uploads_dir.join(format!("{}-{}", uuid::Uuid::new_v4(), filename));
+                tokio::fs::write(&path, &content).await?;
+                tracing::info!("✅ Excel node '{}' wrote {} row(s) x {} column(s) to {} ({} bytes)",
+                    node.id, row_count, column_count, path.display(), content.len());
+                
+                let binary = BinaryRef::from_file(path.to_string_lossy().into_owned(), Some(filename),
+                    XLSX_CONTENT_TYPE.to_string(), content.len() as u64);
+                let mut item = binary.to_item();
+                item["rows"] = json!(row_count);
+                item["sheet"] = json!(sheet);
+                
+                Ok(ExecutionResult {
+                    data: vec![item],
+                    metadata: context.metadata,
+                    termination: Termination::Completed,
+                })
+            }
+        }
+    }
+
     async fn execute_pgdyn_table_writer_node(&self, node: &Node, context: ExecutionContext) -> Result<ExecutionResult> {
         tracing::debug!("🐘📝 Executing PGDynTableWriter node: {}", node.id);
         
@@ -1114,8 +2171,8 @@ impl NodeExecutor {
         }
         
         // STEP 2: Resolve secrets (database connection strings)
-        let resolved_secrets = self.evaluate_secret_pins(secrets)?;
-        let connection_string = resolved_secrets.get(0)
+        let resolved_secrets = self.evaluate_secret_pins(&context.project_slug, secrets)?;
+        let connection_string = resolved_secrets.first()
             .ok_or_else(|| anyhow::anyhow!("PGDynTableWriter node '{}' failed to resolve database connection secret", node.id))?;
         
         tracing::debug!("🔐 Using database connection for ETL node: {}", node.id);
@@ -1145,34 +2202,1131 @@ impl NodeExecutor {
                 return Err(anyhow::anyhow!("Input pins count ({}) must match columns count ({})", 
                     inputs.len(), columns.len()));
             }
-            self.evaluate_input_pins(inputs, &context)?
+            self.evaluate_input_pins(node, &context)?
         } else {
             return Err(anyhow::anyhow!("PGDynTableWriter node '{}' requires input pins for data values", node.id));
         };
         
-        tracing::debug!("🔗 Data values: {:?}", data_values);
+        tracing::debug!("🔗 Data values: {}", preview_debug(&data_values));
         
-        // STEP 5: Execute PostgreSQL ETL operation (placeholder implementation)
-        // TODO: Implement actual tokio-postgres connection, schema creation, and table insertion
-        tracing::warn!("🚨 PGDynTableWriter execution not fully implemented yet - returning placeholder");
-        
-        let placeholder_result = json!({
-            "operation": "pgdyn_table_write",
-            "schema": "mway_dynamic_tables",
-            "table": table_name,
-            "columns": columns,
-            "data_values": data_values,
-            "connection": "REDACTED",
-            "rows_affected": 1,
-            "executed_at": chrono::Utc::now().to_rfc3339()
-        });
-        
-        tracing::info!("✅ PGDynTableWriter placeholder completed: {}", node.id);
+        // STEP 5: Create the target table (typed from the first values) if missing
+        if !is_identifier(table_name) {
+            return Err(anyhow::anyhow!("PGDynTableWriter node '{}' has invalid table name: {}", node.id, table_name));
+        }
+        let drift_policy = SchemaDriftPolicy::from_node_params(&node.params)
+            .map_err(|e| anyhow::anyhow!("PGDynTableWriter node '{}': {}", node.id, e))?;
+        let client = self.project_db_manager.pg_pools().acquire(connection_string, None).await?;
+        let audit = audit_values(node, &context);
+        let column_types: Vec<(String, &'static str)> = columns.iter()
+            .zip(&data_values)
+            .map(|(column, value)| (column.clone(), drift_policy.types.column_type(value)))
+            .chain(audit.iter().map(|(column, _)| (column.clone(), "TEXT")))
+            .collect();
+        pg_dyn_table::ensure_table(&client, table_name, &column_types).await?;
+        
+        // STEP 6: Schema drift - item fields that neither map to a column nor feed a pin
+        let mut row: Vec<(String, Value)> = columns.iter().cloned().zip(data_values).collect();
+        let pins = node.input_pins().unwrap_or_default();
+        let pin_fields: HashSet<&str> = pins.iter()
+            .filter_map(|pin| match pin {
+                PinExpr::JsonPath(path) => path.split(['.', '[']).next(),
+                _ => None,
+            })
+            .collect();
+        let table_columns = pg_dyn_table::table_columns(&client, table_name).await?;
+        let mut new_fields = Vec::new();
+        if let Some(Value::Object(item)) = context.data.first() {
+            for (field, value) in item {
+                if columns.contains(field) || pin_fields.contains(field.as_str())
+                    || audit.iter().any(|(column, _)| column == field)
+                {
+                    continue;
+                }
+                if table_columns.contains(field) {
+                    // Added by an earlier drift (or by hand): keep filling it
+                    row.push((field.clone(), value.clone()));
+                } else {
+                    new_fields.push((field.clone(), value.clone()));
+                }
+            }
+        }
         
-        Ok(ExecutionResult {
-            data: vec![placeholder_result],
+        let drift = drift_policy.plan(&new_fields);
+        if !new_fields.is_empty() {
+            let fields: Vec<&str> = new_fields.iter().map(|(field, _)| field.as_str()).collect();
+            tracing::warn!("⚠️ Schema drift in {}.{} (node '{}'): new fields {:?}", 
+                pg_dyn_table::DYNAMIC_SCHEMA, table_name, node.id, fields);
+            self.publish_event(&context, pg_dyn_table::DRIFT_EVENT, vec![json!({
+                "node_id": node.id,
+                "schema": pg_dyn_table::DYNAMIC_SCHEMA,
+                "table": table_name,
+                "mode": node.params.pointer("/schema_drift/mode").cloned().unwrap_or(json!("ignore")),
+                "fields": fields,
+                "added": drift.added,
+                "ignored": drift.ignored,
+            })]);
+            if drift_policy.mode == DriftMode::Fail {
+                return Err(anyhow::anyhow!("Schema drift in table '{}': new fields {:?} (schema_drift mode is \"fail\")", 
+                    table_name, fields));
+            }
+        }
+        if !drift.added.is_empty() {
+            pg_dyn_table::add_columns(&client, table_name, &drift.added).await?;
+            for column in &drift.added {
+                let value = new_fields.iter().find(|(field, _)| *field == column.name).map(|(_, v)| v.clone());
+                row.push((column.name.clone(), value.unwrap_or(Value::Null)));
+            }
+            tracing::info!("🧬 Added {} column(s) to {}.{}", drift.added.len(), pg_dyn_table::DYNAMIC_SCHEMA, table_name);
+        }
+        
+        // STEP 7: Audit columns (tables created before "audit_columns" was enabled get them added)
+        if !audit.is_empty() {
+            let missing: Vec<AddedColumn> = audit.iter()
+                .filter(|(column, _)| !table_columns.contains(column))
+                .map(|(column, _)| AddedColumn { name: column.clone(), column_type: "TEXT" })
+                .collect();
+            pg_dyn_table::add_columns(&client, table_name, &missing).await?;
+            row.extend(audit);
+        }
+        
+        // STEP 8: Insert the row
+        let rows_affected = pg_dyn_table::insert_row(&client, table_name, &row).await?;
+        
+        tracing::info!("✅ PGDynTableWriter wrote {} row(s) to {}.{}", rows_affected, pg_dyn_table::DYNAMIC_SCHEMA, table_name);
+        
+        let result = json!({
+            "operation": "pgdyn_table_write",
+            "schema": pg_dyn_table::DYNAMIC_SCHEMA,
+            "table": table_name,
+            "columns": row.iter().map(|(column, _)| column).collect::<Vec<_>>(),
+            "rows_affected": rows_affected,
+            "schema_drift": if drift.is_empty() { Value::Null } else { json!(drift) },
+            "executed_at": chrono::Utc::now().to_rfc3339()
+        });
+        
+        Ok(ExecutionResult {
+            data: vec![result],
+            metadata: context.metadata,
+            termination: Termination::Completed,
+        })
+    }
+
+    /// Execute Anonymize node: scrub PII fields of every item with per-field strategies
+    async fn execute_anonymize_node(&self, node: &Node, mut context: ExecutionContext) -> Result<ExecutionResult> {
+        tracing::debug!("🕶️ Executing Anonymize node: {}", node.id);
+        
+        let params = AnonymizeParams::from_node_params(&node.params)
+            .map_err(|e| anyhow::anyhow!("Anonymize node '{}': {}", node.id, e))?;
+        let key = match node.secrets.as_ref().and_then(|secrets| secrets.first()) {
+            Some(secret) => Some(self.resolve_secret(&context.project_slug, secret)?),
+            None => None,
+        };
+        
+        let mut data = std::mem::take(&mut context.data);
+        // A single array item (e.g. a webhook JSON array body) is scrubbed element by element
+        let items = match data.as_mut_slice() {
+            [Value::Array(rows)] => rows.as_mut_slice(),
+            items => items,
+        };
+        let suppressed = Anonymizer::new(&params, key.as_deref()).apply(items);
+        
+        tracing::info!("✅ Anonymize node '{}' scrubbed {} field(s) in {} item(s){}", node.id, params.fields.len(), items.len(),
+            params.k.map(|k| format!(", {} item(s) below k={} suppressed", suppressed, k)).unwrap_or_default());
+        
+        Ok(ExecutionResult {
+            data,
+            metadata: context.metadata,
+            termination: Termination::Completed,
+        })
+    }
+
+    /// Execute Diff node: compare the data array with the node's previous snapshot and emit the changes
+    async fn execute_diff_node(&self, node: &Node, mut context: ExecutionContext) -> Result<ExecutionResult> {
+        tracing::debug!("🔍 Executing Diff node: {}", node.id);
+
+        let params = DiffParams::from_node_params(&node.params)
+            .map_err(|e| anyhow::anyhow!("Diff node '{}': {}", node.id, e))?;
+        let workflow_id = context.metadata.get("workflow_id")
+            .and_then(|id| id.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Diff node '{}': execution has no workflow_id", node.id))?
+            .to_string();
+
+        // A single array item (e.g. a webhook JSON array body) is diffed element by element
+        let items = match context.data.as_slice() {
+            [Value::Array(rows)] => rows.clone(),
+            items => items.to_vec(),
+        };
+
+        // Key and fingerprint every item; a repeated key replaces the earlier item in place
+        let mut current: Vec<(String, Value, String, Value)> = Vec::with_capacity(items.len());
+        let mut positions: HashMap<String, usize> = HashMap::with_capacity(items.len());
+        let mut duplicates = 0;
+        for (index, item) in items.into_iter().enumerate() {
+            let (key_text, key) = params.item_key(&item)
+                .map_err(|e| anyhow::anyhow!("Diff node '{}' (item {}): {}", node.id, index, e))?;
+            let hash = params.fingerprint(&item);
+            match positions.get(&key_text) {
+                Some(&position) => {
+                    duplicates += 1;
+                    current[position] = (key_text, key, hash, item);
+                }
+                None => {
+                    positions.insert(key_text.clone(), current.len());
+                    current.push((key_text, key, hash, item));
+                }
+            }
+        }
+        if duplicates > 0 {
+            tracing::warn!("⚠️ Diff node '{}': {} duplicate key(s), the last item of each key was kept", node.id, duplicates);
+        }
+
+        let slug = &context.project_slug;
+        let snapshot = self.project_db_manager.snapshot_hashes(slug, &workflow_id, &node.id).await?;
+
+        let changes: Vec<Change> = current.iter()
+            .map(|(key_text, _, hash, _)| match snapshot.get(key_text) {
+                None => Change::Added,
+                Some(previous) if previous != hash => Change::Changed,
+                Some(_) => Change::Unchanged,
+            })
+            .collect();
+
+        // Each streamed batch only holds part of the set, so absent keys don't mean removed
+        let streamed = context.metadata.contains_key("stream");
+        let removed: Vec<&str> = if params.mode == DiffMode::Full && !streamed {
+            let mut removed: Vec<&str> = snapshot.keys()
+                .filter(|key| !positions.contains_key(*key))
+                .map(|key| key.as_str())
+                .collect();
+            removed.sort_unstable();
+            removed
+        } else {
+            Vec::new()
+        };
+
+        // Stored versions are only loaded for the changes that are emitted with them
+        let mut wanted: Vec<&str> = Vec::new();
+        if params.emits(Change::Changed) {
+            wanted.extend(current.iter().zip(&changes)
+                .filter(|(_, change)| **change == Change::Changed)
+                .map(|((key_text, ..), _)| key_text.as_str()));
+        }
+        if params.emits(Change::Removed) {
+            wanted.extend(&removed);
+        }
+        let previous = if wanted.is_empty() {
+            HashMap::new()
+        } else {
+            self.project_db_manager.snapshot_items(slug, &workflow_id, &node.id, &wanted).await?
+        };
+
+        let mut data = Vec::new();
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for ((key_text, key, _, item), change) in current.iter().zip(&changes) {
+            *counts.entry(change.as_str()).or_default() += 1;
+            if !params.emits(*change) {
+                continue;
+            }
+            data.push(match change {
+                Change::Changed => {
+                    let before = previous.get(key_text);
+                    let fields = before.map(|before| params.changed_fields(before, item)).unwrap_or_default();
+                    diff::change_item(*change, key, Some(item), before, Some(fields))
+                }
+                _ => diff::change_item(*change, key, Some(item), None, None),
+            });
+        }
+        counts.insert(Change::Removed.as_str(), removed.len());
+        if params.emits(Change::Removed) {
+            for key_text in &removed {
+                let before = previous.get(*key_text);
+                let key = before.and_then(|before| params.item_key(before).ok())
+                    .map(|(_, key)| key)
+                    .unwrap_or_else(|| json!(key_text));
+                data.push(diff::change_item(Change::Removed, &key, None, before, None));
+            }
+        }
+
+        let upserts: Vec<SnapshotEntry> = current.iter().zip(&changes)
+            .filter(|(_, change)| **change != Change::Unchanged)
+            .map(|((key_text, _, hash, item), _)| SnapshotEntry { key: key_text, hash, item })
+            .collect();
+        self.project_db_manager.snapshot_apply(slug, &workflow_id, &node.id, &upserts, &removed).await?;
+
+        let count = |change: Change| counts.get(change.as_str()).copied().unwrap_or(0);
+        tracing::info!("✅ Diff node '{}': {} added, {} changed, {} removed, {} unchanged",
+            node.id, count(Change::Added), count(Change::Changed), count(Change::Removed), count(Change::Unchanged));
+
+        context.metadata.insert("diff".to_string(), json!({
+            "added": count(Change::Added),
+            "changed": count(Change::Changed),
+            "removed": count(Change::Removed),
+            "unchanged": count(Change::Unchanged),
+            "duplicates": duplicates,
+        }));
+
+        Ok(ExecutionResult {
+            data,
             metadata: context.metadata,
-            should_continue: true,
+            termination: Termination::Completed,
         })
     }
+
+    /// Execute Window node: buffer the data array and continue only with the windows that closed
+    async fn execute_window_node(&self, node: &Node, mut context: ExecutionContext) -> Result<ExecutionResult> {
+        tracing::debug!("🪟 Executing Window node: {}", node.id);
+
+        let params = WindowParams::from_node_params(&node.params)
+            .map_err(|e| anyhow::anyhow!("Window node '{}': {}", node.id, e))?;
+        let workflow_id = context.metadata.get("workflow_id")
+            .and_then(|id| id.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Window node '{}': execution has no workflow_id", node.id))?
+            .to_string();
+        let slug = context.project_slug.clone();
+        let _guard = self.window_locks.lock(format!("{}:{}:{}", slug, workflow_id, node.id)).await;
+
+        // Cron ticks only close due windows; everything else is buffered
+        let is_cron_tick = context.metadata.get("trigger_type").and_then(|t| t.as_str()) == Some("cron");
+        let now = chrono::Utc::now().timestamp_millis();
+        if !is_cron_tick {
+            let items = match context.data.as_slice() {
+                [Value::Array(rows)] => rows.clone(),
+                items => items.to_vec(),
+            };
+            let dropped = self.project_db_manager.window_push(&slug, &workflow_id, &node.id, &items, now, params.max_items).await?;
+            if dropped > 0 {
+                tracing::warn!("⚠️ Window node '{}' buffer is full ({} items): dropped {} oldest", node.id, params.max_items, dropped);
+            }
+        }
+
+        let mut closed: Vec<ClosedWindow> = Vec::new();
+        match params.kind {
+            WindowKind::Count { size, slide } => {
+                let mut buffered = self.project_db_manager.window_count(&slug, &workflow_id, &node.id).await? as usize;
+                while buffered >= size {
+                    let items = self.project_db_manager.window_oldest(&slug, &workflow_id, &node.id, size).await?;
+                    closed.push(ClosedWindow { bounds: None, items: items.into_iter().map(|buffered| buffered.item).collect() });
+                    self.project_db_manager.window_drop_oldest(&slug, &workflow_id, &node.id, slide).await?;
+                    buffered -= slide;
+                }
+            }
+            WindowKind::Time { duration_ms, slide_ms } => {
+                let state_key = format!("window:closed:{}:{}", workflow_id, node.id);
+                let previously_closed = self.project_db_manager.kv_get(&slug, &state_key).await?
+                    .and_then(|closed| closed.as_i64())
+                    .unwrap_or(i64::MIN);
+                let closed_until = window::closed_until(now, duration_ms, slide_ms);
+                if closed_until > previously_closed {
+                    let items = self.project_db_manager.window_received_before(&slug, &workflow_id, &node.id, closed_until).await?;
+                    closed = window::close_time_windows(&items, duration_ms, slide_ms, previously_closed, closed_until);
+                    let cutoff = window::retention_cutoff(now, duration_ms, slide_ms);
+                    self.project_db_manager.window_drop_before(&slug, &workflow_id, &node.id, cutoff).await?;
+                    self.project_db_manager.kv_set(&slug, &state_key, &json!(closed_until), None).await?;
+                }
+            }
+        }
+        let buffered = self.project_db_manager.window_count(&slug, &workflow_id, &node.id).await?;
+
+        context.metadata.insert("window".to_string(), json!({
+            "closed": closed.len(),
+            "buffered": buffered,
+            "windows": closed.iter().map(|window| json!({
+                "start": window.bounds.map(|(start, _)| start),
+                "end": window.bounds.map(|(_, end)| end),
+                "count": window.items.len(),
+            })).collect::<Vec<_>>(),
+        }));
+
+        if closed.is_empty() {
+            tracing::info!("✅ Window node '{}': window open ({} buffered)", node.id, buffered);
+            return Ok(ExecutionResult {
+                data: vec![json!({ "window": "open", "buffered": buffered })],
+                metadata: context.metadata,
+                termination: Termination::stopped(&node.id, "Window still open"),
+            });
+        }
+
+        tracing::info!("✅ Window node '{}': {} window(s) closed ({} still buffered)", node.id, closed.len(), buffered);
+        let data = match params.output {
+            WindowOutput::Items => closed.into_iter().flat_map(|window| window.items).collect(),
+            WindowOutput::Windows => closed.iter().map(ClosedWindow::to_item).collect(),
+        };
+
+        Ok(ExecutionResult {
+            data,
+            metadata: context.metadata,
+            termination: Termination::Completed,
+        })
+    }
+
+    /// Execute Modbus node: read a register map into one item, or write coils / registers per input item
+    async fn execute_modbus_node(&self, node: &Node, context: ExecutionContext) -> Result<ExecutionResult> {
+        tracing::debug!("🏭 Executing Modbus node: {}", node.id);
+
+        let secrets = node.secrets.as_ref().filter(|s| !s.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("Modbus node '{}' REQUIRES secrets field with the endpoint (host:port)", node.id))?;
+        let endpoint = modbus::parse_endpoint(&self.resolve_secret(&context.project_slug, &secrets[0])?)
+            .map_err(|e| anyhow::anyhow!("Modbus node '{}': {}", node.id, e))?;
+        let params = ModbusParams::from_node_params(&node.params)
+            .map_err(|e| anyhow::anyhow!("Modbus node '{}': {}", node.id, e))?;
+        let mut client = ModbusClient::connect(&endpoint, params.unit_id, params.timeout).await
+            .map_err(|e| anyhow::anyhow!("Modbus node '{}': {}", node.id, e))?;
+
+        if params.operation.is_read() {
+            let mut words = Vec::new();
+            for (address, count) in params.read_ranges() {
+                let values = client.read(params.operation, address, count).await
+                    .map_err(|e| anyhow::anyhow!("Modbus node '{}' ({} x{} at {}): {}", node.id, params.operation.name(), count, address, e))?;
+                words.push((address, values));
+            }
+            tracing::info!("✅ Modbus node '{}' read {} request(s) from unit {} at {}", node.id, words.len(), params.unit_id, endpoint);
+
+            return Ok(ExecutionResult {
+                data: vec![params.decode(&words)],
+                metadata: context.metadata,
+                termination: Termination::Completed,
+            });
+        }
+
+        // WRITES: mapped fields of each item, or the first input pin at "address"
+        let mut item_context = context.clone();
+        let mut writes = 0;
+        for (index, item) in context.data.iter().enumerate() {
+            let item_writes = if params.registers.is_empty() {
+                item_context.data = vec![item.clone()];
+                let value = self.evaluate_input_pins(node, &item_context)?.into_iter().next()
+                    .ok_or_else(|| anyhow::anyhow!("Modbus node '{}' REQUIRES 'registers' or an input pin with the value to write", node.id))?;
+                let values = match value {
+                    Value::Array(values) => values,
+                    value => vec![value],
+                };
+                let words = values.iter()
+                    .map(|value| if params.operation.is_bits() {
+                        modbus::as_bool(value).map(|bit| vec![u16::from(bit)])
+                    } else {
+                        value.as_f64().and_then(|number| modbus::encode_number(number, RegisterType::U16, false))
+                    })
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| anyhow::anyhow!("Modbus node '{}' (item {}): values must be {}", node.id, index,
+                        if params.operation.is_bits() { "booleans" } else { "integers from 0 to 65535" }))?;
+                vec![(params.address, words.concat())]
+            } else {
+                params.encode(item).map_err(|e| anyhow::anyhow!("Modbus node '{}' (item {}): {}", node.id, index, e))?
+            };
+
+            for (address, words) in item_writes {
+                let written = if params.operation.is_bits() {
+                    client.write_coils(address, &words.iter().map(|bit| *bit != 0).collect::<Vec<_>>()).await
+                } else {
+                    client.write_registers(address, &words).await
+                };
+                written.map_err(|e| anyhow::anyhow!("Modbus node '{}' (item {}, write at {}): {}", node.id, index, address, e))?;
+                writes += 1;
+            }
+        }
+
+        tracing::info!("✅ Modbus node '{}' made {} write(s) to unit {} at {}", node.id, writes, params.unit_id, endpoint);
+
+        Ok(ExecutionResult {
+            data: context.data,
+            metadata: context.metadata,
+            termination: Termination::Completed,
+        })
+    }
+
+    /// Execute OpcUa node: read nodes into one item, write item fields to nodes, or browse the address space
+    async fn execute_opcua_node(&self, node: &Node, context: ExecutionContext) -> Result<ExecutionResult> {
+        tracing::debug!("🏭 Executing OpcUa node: {}", node.id);
+
+        let secrets = node.secrets.as_ref().filter(|s| !s.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("OpcUa node '{}' REQUIRES secrets field with the endpoint (opc.tcp://host:port)", node.id))?;
+        let resolved = secrets.iter().map(|pin| self.resolve_secret(&context.project_slug, pin)).collect::<Result<Vec<_>>>()?;
+        let (endpoint, credentials) = opcua::connection_from_secrets(&resolved);
+        let params = OpcUaParams::from_node_params(&node.params)
+            .map_err(|e| anyhow::anyhow!("OpcUa node '{}': {}", node.id, e))?;
+        let mut client = OpcUaClient::connect(&endpoint, &credentials, params.timeout).await
+            .map_err(|e| anyhow::anyhow!("OpcUa node '{}': {}", node.id, e))?;
+
+        let outcome: Result<Vec<Value>> = async {
+            match params.operation {
+                OpcUaOperation::Read => {
+                    let node_ids: Vec<_> = params.nodes.iter().map(|n| n.node_id.clone()).collect();
+                    let values = client.read(&node_ids, ATTRIBUTE_VALUE).await?;
+                    Ok(params.read_output(&values))
+                }
+                OpcUaOperation::Browse => opcua::browse_tree(&mut client, &params).await,
+                OpcUaOperation::Write => {
+                    // TYPES: nodes without a "type" are written as the type of their current value
+                    let untyped: Vec<_> = params.nodes.iter().filter(|n| n.type_id.is_none()).map(|n| n.node_id.clone()).collect();
+                    let current = if untyped.is_empty() { Vec::new() } else { client.read(&untyped, ATTRIBUTE_VALUE).await? };
+                    let mut current = current.into_iter();
+                    let types = params.nodes.iter()
+                        .map(|n| match n.type_id {
+                            Some(type_id) => Ok(type_id),
+                            None => current.next().map(|value| value.type_id).filter(|type_id| *type_id != 0)
+                                .ok_or_else(|| anyhow::anyhow!("{} has no value to take its type from, set \"type\"", n.node_id)),
+                        })
+                        .collect::<Result<Vec<u8>>>()?;
+
+                    for (index, item) in context.data.iter().enumerate() {
+                        let values: Vec<_> = params.nodes.iter().zip(&types)
+                            .filter_map(|(n, type_id)| {
+                                let value = item.get(&n.name).filter(|v| !v.is_null())?;
+                                Some((n.node_id.clone(), *type_id, value.clone()))
+                            })
+                            .collect();
+                        if values.is_empty() {
+                            continue;
+                        }
+                        let statuses = client.write(&values).await
+                            .map_err(|e| anyhow::anyhow!("item {}: {}", index, e))?;
+                        if let Some(((node_id, _, _), status)) = values.iter().zip(&statuses).find(|(_, status)| !opcua::status_is_usable(**status)) {
+                            return Err(anyhow::anyhow!("item {}: write to {} failed: {}", index, node_id, opcua::status_name(*status)));
+                        }
+                    }
+                    Ok(context.data.clone())
+                }
+            }
+        }.await;
+        client.close().await;
+        let data = outcome.map_err(|e| anyhow::anyhow!("OpcUa node '{}': {}", node.id, e))?;
+
+        tracing::info!("✅ OpcUa node '{}' {:?} finished with {} item(s)", node.id, params.operation, data.len());
+
+        Ok(ExecutionResult {
+            data,
+            metadata: context.metadata,
+            termination: Termination::Completed,
+        })
+    }
+
+    /// Execute Serial node: write each item's command to a serial port, optionally reading one reply per command
+    async fn execute_serial_node(&self, node: &Node, context: ExecutionContext) -> Result<ExecutionResult> {
+        tracing::debug!("🔌 Executing Serial node: {}", node.id);
+
+        let params = SerialParams::from_node_params(&node.params)
+            .map_err(|e| anyhow::anyhow!("Serial node '{}': {}", node.id, e))?;
+
+        // COMMANDS: the first input pin of each item
+        let mut item_context = context.clone();
+        let mut commands = Vec::with_capacity(context.data.len());
+        for (index, item) in context.data.iter().enumerate() {
+            item_context.data = vec![item.clone()];
+            let command = self.evaluate_input_pins(node, &item_context)?.into_iter().next()
+                .ok_or_else(|| anyhow::anyhow!("Serial node '{}' REQUIRES an input pin with the command to write", node.id))?;
+            let mut bytes = params.encoding.encode(&command)
+                .map_err(|e| anyhow::anyhow!("Serial node '{}' (item {}): {}", node.id, index, e))?;
+            bytes.extend_from_slice(&params.line_ending);
+            commands.push(bytes);
+        }
+
+        // A SerialTrigger holding the port writes for us (its workflow gets the replies)
+        if let Some(writer) = self.serial_ports.writer(&params.settings.port) {
+            if params.reply.is_some() {
+                return Err(anyhow::anyhow!("Serial node '{}': {} is held by a SerialTrigger, which receives the replies (remove 'read_response')",
+                    node.id, params.settings.port));
+            }
+            for command in commands.iter() {
+                writer.send(command.clone()).await
+                    .map_err(|_| anyhow::anyhow!("Serial node '{}': the SerialTrigger on {} closed the port", node.id, params.settings.port))?;
+            }
+            tracing::info!("✅ Serial node '{}' queued {} command(s) on the SerialTrigger port {}", node.id, commands.len(), params.settings.port);
+            return Ok(ExecutionResult {
+                data: context.data,
+                metadata: context.metadata,
+                termination: Termination::Completed,
+            });
+        }
+
+        let replies = serial::exchange(&self.serial_ports, &params, &commands).await
+            .map_err(|e| anyhow::anyhow!("Serial node '{}': {}", node.id, e))?;
+        tracing::info!("✅ Serial node '{}' wrote {} command(s) to {} ({} replies)", node.id, commands.len(), params.settings.port, replies.len());
+
+        Ok(ExecutionResult {
+            data: if params.reply.is_some() { replies } else { context.data },
+            metadata: context.metadata,
+            termination: Termination::Completed,
+        })
+    }
+
+    /// Execute EmitEvent node to publish an internal event
+    /// 
+    /// Expected params: { "event": "order.created" }
+    /// Input pins (optional) build the event payload, otherwise the current data is sent.
+    /// Data passes through unchanged so the emitting workflow can keep going.
+    async fn execute_emit_event_node(&self, node: &Node, context: ExecutionContext) -> Result<ExecutionResult> {
+        tracing::debug!("📣 Executing EmitEvent node: {}", node.id);
+        
+        let event_name = node.params.get("event")
+            .and_then(|e| e.as_str())
+            .filter(|e| !e.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("EmitEvent node '{}' missing 'event' parameter", node.id))?;
+        
+        let payload = match &node.inputs {
+            Some(_) => self.evaluate_input_pins(node, &context)?,
+            None => context.data.clone(),
+        };
+        
+        let listeners = self.publish_event(&context, event_name, payload);
+        
+        tracing::info!("✅ Event '{}' emitted in project '{}' ({} bus listeners)", 
+            event_name, context.project_slug, listeners);
+        
+        Ok(ExecutionResult {
+            data: context.data,
+            metadata: context.metadata,
+            termination: Termination::Completed,
+        })
+    }
+
+    /// Publish an event in the run's project, returning the number of bus listeners
+    fn publish_event(&self, context: &ExecutionContext, name: &str, data: Vec<Value>) -> usize {
+        // Events emitted from event-triggered runs carry the chain depth forward
+        let depth = context.metadata.get("event_depth")
+            .and_then(|d| d.as_u64())
+            .unwrap_or(0) + 1;
+        
+        self.event_bus.emit(InternalEvent {
+            project_slug: context.project_slug.clone(),
+            name: name.to_string(),
+            data,
+            source_workflow_id: context.metadata.get("workflow_id")
+                .and_then(|w| w.as_str())
+                .map(|w| w.to_string()),
+            depth,
+        })
+    }
+
+    /// Execute Mock node with static or templated responses
+    /// 
+    /// Expected params: { "response": [...] } or { "template": {...} }, plus optional
+    /// { "latency_ms": 150, "jitter_ms": 50, "failure_rate": 0.1, "error": "upstream timeout" }
+    async fn execute_mock_node(&self, node: &Node, context: ExecutionContext) -> Result<ExecutionResult> {
+        tracing::debug!("🎭 Executing Mock node: {}", node.id);
+        
+        // Artificial latency (base + uniform jitter)
+        let latency_ms = node.params.get("latency_ms").and_then(|l| l.as_u64()).unwrap_or(0);
+        let jitter_ms = node.params.get("jitter_ms").and_then(|j| j.as_u64()).unwrap_or(0);
+        let delay_ms = latency_ms + (random_unit() * jitter_ms as f64) as u64;
+        if delay_ms > 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(delay_ms)).await;
+        }
+        
+        // Simulated failures
+        let failure_rate = node.params.get("failure_rate").and_then(|f| f.as_f64()).unwrap_or(0.0);
+        if failure_rate > 0.0 && random_unit() < failure_rate {
+            let message = node.params.get("error")
+                .and_then(|e| e.as_str())
+                .unwrap_or("Mock node simulated failure");
+            tracing::warn!("🎭 Mock node '{}' simulated failure (rate {})", node.id, failure_rate);
+            return Err(anyhow::anyhow!("{}", message));
+        }
+        
+        let data = if let Some(template) = node.params.get("template") {
+            // One rendered response per input item
+            let mut item_context = context.clone();
+            let mut rendered = Vec::with_capacity(context.data.len());
+            for item in &context.data {
+                item_context.data = vec![item.clone()];
+                rendered.push(self.render_template(template, &item_context)?);
+            }
+            rendered
+        } else {
+            match node.params.get("response") {
+                Some(Value::Array(items)) => items.clone(),
+                Some(value) => vec![value.clone()],
+                None => context.data.clone(),
+            }
+        };
+        
+        tracing::info!("✅ Mock node '{}' returned {} item(s) after {}ms", node.id, data.len(), delay_ms);
+        
+        Ok(ExecutionResult {
+            data,
+            metadata: context.metadata,
+            termination: Termination::Completed,
+        })
+    }
+    
+    /// Execute Assert node (pipeline guardrail)
+    /// 
+    /// Expected params: { "conditions": [{ "path": "$[0].total", "greater_than": 0 }], "message": "...", "on_failure": "error|stop" }
+    async fn execute_assert_node(&self, node: &Node, mut context: ExecutionContext) -> Result<ExecutionResult> {
+        tracing::debug!("🛡️ Executing Assert node: {}", node.id);
+        
+        let conditions: Vec<Assertion> = match node.params.get("conditions") {
+            Some(conditions) => serde_json::from_value(conditions.clone())
+                .map_err(|e| anyhow::anyhow!("Assert node '{}' has invalid 'conditions': {}", node.id, e))?,
+            None => return Err(anyhow::anyhow!("Assert node '{}' missing 'conditions' parameter", node.id)),
+        };
+        
+        // JSONPath needs a single document, so the items move into an array and back
+        let data = Value::Array(std::mem::take(&mut context.data));
+        let failures: Vec<_> = conditions.iter()
+            .map(|condition| condition.evaluate(&data))
+            .filter(|result| !result.passed)
+            .collect();
+        if let Value::Array(items) = data {
+            context.data = items;
+        }
+        
+        if failures.is_empty() {
+            tracing::info!("✅ Assert node '{}': {} condition(s) passed", node.id, conditions.len());
+            return Ok(ExecutionResult {
+                data: context.data,
+                metadata: context.metadata,
+                termination: Termination::Completed,
+            });
+        }
+        
+        let error = AssertionError {
+            node_id: node.id.clone(),
+            message: node.params.get("message")
+                .and_then(|m| m.as_str())
+                .unwrap_or("Assertion failed")
+                .to_string(),
+            failures,
+        };
+        
+        match node.params.get("on_failure").and_then(|o| o.as_str()).unwrap_or("error") {
+            "stop" => {
+                tracing::warn!("🛡️ {} - stopping execution", error);
+                context.metadata.insert("assertion_failed".to_string(), error.to_json());
+                Ok(ExecutionResult {
+                    data: context.data,
+                    metadata: context.metadata,
+                    termination: Termination::stopped(&node.id, error.message.clone()),
+                })
+            }
+            _ => Err(error.into()),
+        }
+    }
+    
+    /// Execute TransactionBegin / TransactionCommit / TransactionRollback nodes
+    /// 
+    /// The transaction lives in the execution context, so every SimpleTable node
+    /// between Begin and Commit shares one database connection.
+    async fn execute_transaction_node(&self, node: &Node, context: ExecutionContext) -> Result<ExecutionResult> {
+        match node.node_type {
+            NodeType::TransactionBegin => {
+                context.transaction.begin(&self.project_db_manager, &context.project_slug).await?;
+                tracing::info!("🔐 Transaction opened by node '{}'", node.id);
+            }
+            NodeType::TransactionCommit => {
+                context.transaction.commit().await?;
+                tracing::info!("💾 Transaction committed by node '{}'", node.id);
+            }
+            _ => {
+                if context.transaction.rollback().await? {
+                    tracing::info!("↩️ Transaction rolled back by node '{}'", node.id);
+                } else {
+                    tracing::debug!("↩️ Node '{}': no open transaction to roll back", node.id);
+                }
+            }
+        }
+        
+        Ok(ExecutionResult {
+            data: context.data,
+            metadata: context.metadata,
+            termination: Termination::Completed,
+        })
+    }
+    
+    /// Render a JSON template against the context
+    /// 
+    /// Strings that are a single `$` pin are replaced by the pin value (keeping its
+    /// JSON type); `{{pin}}` placeholders inside longer strings are interpolated as text.
+    fn render_template(&self, template: &Value, context: &ExecutionContext) -> Result<Value> {
+        Ok(match template {
+            Value::String(s) if s.starts_with('$') && !s.contains("{{") => {
+                self.evaluate_pin(&PinExpr::parse(s), context)?
+            }
+            Value::String(s) if s.contains("{{") => {
+                let mut output = String::with_capacity(s.len());
+                let mut rest = s.as_str();
+                while let Some(start) = rest.find("{{") {
+                    let Some(end) = rest[start..].find("}}") else { break };
+                    output.push_str(&rest[..start]);
+                    let expr = rest[start + 2..start + end].trim();
+                    match self.evaluate_pin(&PinExpr::parse(expr), context)? {
+                        Value::String(text) => output.push_str(&text),
+                        Value::Null => {}
+                        other => output.push_str(&other.to_string()),
+                    }
+                    rest = &rest[start + end + 2..];
+                }
+                output.push_str(rest);
+                Value::String(output)
+            }
+            Value::Array(items) => Value::Array(items.iter()
+                .map(|item| self.render_template(item, context))
+                .collect::<Result<_>>()?),
+            Value::Object(map) => Value::Object(map.iter()
+                .map(|(k, v)| Ok((k.clone(), self.render_template(v, context)?)))
+                .collect::<Result<_>>()?),
+            other => other.clone(),
+        })
+    }
+
+    /// Render an output filename template, expanding strftime specifiers
+    /// ("orders-%Y-%m-%d.parquet") with the current UTC time
+    fn render_filename(&self, template: &Value, context: &ExecutionContext) -> Result<String> {
+        let filename = match self.render_template(template, context)? {
+            Value::String(filename) if !filename.is_empty() => filename,
+            other => return Err(anyhow::anyhow!("filename rendered to {}", other)),
+        };
+        let items: Vec<_> = chrono::format::StrftimeItems::new(&filename).collect();
+        if items.contains(&chrono::format::Item::Error) {
+            return Err(anyhow::anyhow!("Invalid date format in filename '{}'", filename));
+        }
+        Ok(chrono::Utc::now().format_with_items(items.into_iter()).to_string())
+    }
+
+    /// Execute KVStore node for cross-execution state
+    /// 
+    /// Expected params: { "operation": "get|set|delete|increment", "key": "cursor", "ttl_seconds": 3600 }
+    /// Persisted in the project's kv_store table so values survive restarts.
+    async fn execute_kv_store_node(&self, node: &Node, context: ExecutionContext) -> Result<ExecutionResult> {
+        tracing::debug!("🗝️ Executing KVStore node: {}", node.id);
+        
+        let operation = node.params.get("operation")
+            .and_then(|o| o.as_str())
+            .unwrap_or("get");
+        
+        let key_expr = node.params.get("key")
+            .and_then(|k| k.as_str())
+            .ok_or_else(|| anyhow::anyhow!("KVStore node '{}' missing 'key' parameter", node.id))?;
+        
+        // Keys starting with '$' are pin expressions (e.g. "$json.order_id")
+        let key = if key_expr.starts_with('$') {
+            match self.evaluate_pin(&PinExpr::parse(key_expr), &context)? {
+                Value::String(s) => s,
+                Value::Null => return Err(anyhow::anyhow!("KVStore node '{}' key '{}' resolved to null", node.id, key_expr)),
+                other => other.to_string(),
+            }
+        } else {
+            key_expr.to_string()
+        };
+        
+        let ttl_seconds = node.params.get("ttl_seconds").and_then(|t| t.as_u64());
+        
+        let first_input = match node.input_pins() {
+            Some(pins) if !pins.is_empty() => Some(self.evaluate_pin(&pins[0], &context)?),
+            _ => None,
+        };
+        
+        tracing::debug!("🗝️ KV {} '{}' in project '{}'", operation, key, context.project_slug);
+        
+        let slug = &context.project_slug;
+        let response_data = match operation {
+            "get" => {
+                let value = self.project_db_manager.kv_get(slug, &key).await?;
+                json!({ "key": key, "found": value.is_some(), "value": value.unwrap_or(Value::Null) })
+            }
+            "set" => {
+                let value = first_input.ok_or_else(|| anyhow::anyhow!("KVStore node '{}' set requires an input pin for the value", node.id))?;
+                self.project_db_manager.kv_set(slug, &key, &value, ttl_seconds).await?;
+                json!({ "key": key, "value": value, "stored": true })
+            }
+            "delete" => {
+                let deleted = self.project_db_manager.kv_delete(slug, &key).await?;
+                json!({ "key": key, "deleted": deleted })
+            }
+            "increment" => {
+                let amount = match first_input {
+                    Some(value) => value.as_f64()
+                        .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+                        .ok_or_else(|| anyhow::anyhow!("KVStore node '{}' increment amount must be numeric", node.id))?,
+                    None => 1.0,
+                };
+                let value = self.project_db_manager.kv_increment(slug, &key, amount, ttl_seconds).await?;
+                json!({ "key": key, "value": value })
+            }
+            other => return Err(anyhow::anyhow!("KVStore node '{}' has unsupported operation: {}", node.id, other)),
+        };
+        
+        tracing::info!("✅ KVStore {} completed: {}", operation, key);
+        
+        Ok(ExecutionResult {
+            data: vec![response_data],
+            metadata: context.metadata,
+            termination: Termination::Completed,
+        })
+    }
+
+    /// Execute Memory node: load, append, summarize or clear a conversation session
+    /// 
+    /// Passes the data array through; metadata "memory" holds the session window afterwards
+    async fn execute_memory_node(&self, node: &Node, mut context: ExecutionContext) -> Result<ExecutionResult> {
+        tracing::debug!("🧠 Executing Memory node: {}", node.id);
+
+        let params = MemoryParams::from_node_params(&node.params)
+            .map_err(|e| anyhow::anyhow!("Memory node '{}': {}", node.id, e))?;
+
+        // Session IDs starting with '$' are pin expressions (e.g. "$json.user")
+        let session_id = if params.session_id.starts_with('$') {
+            match self.evaluate_pin(&PinExpr::parse(&params.session_id), &context)? {
+                Value::String(s) if !s.is_empty() => s,
+                Value::String(_) | Value::Null => return Err(anyhow::anyhow!("Memory node '{}': session_id '{}' resolved to nothing", node.id, params.session_id)),
+                other => other.to_string(),
+            }
+        } else {
+            params.session_id.clone()
+        };
+
+        let slug = &context.project_slug;
+        match params.operation {
+            MemoryOperation::Load => {}
+            MemoryOperation::Append => {
+                let inputs = self.evaluate_input_pins(node, &context)?;
+                let messages = params.messages_from_inputs(&inputs)
+                    .map_err(|e| anyhow::anyhow!("Memory node '{}': {}", node.id, e))?;
+                if messages.is_empty() {
+                    tracing::warn!("⚠️ Memory node '{}' has no messages to append to session '{}'", node.id, session_id);
+                } else {
+                    self.project_db_manager.memory_append(slug, &session_id, &messages).await?;
+                }
+                if let Some(max_messages) = params.max_messages {
+                    let pruned = self.project_db_manager.memory_trim(slug, &session_id, max_messages).await?;
+                    if pruned > 0 {
+                        tracing::debug!("✂️ Pruned {} messages from session '{}'", pruned, session_id);
+                    }
+                }
+            }
+            MemoryOperation::Summarize => {
+                let summary = match self.evaluate_input_pins(node, &context)?.into_iter().next() {
+                    Some(Value::String(summary)) if !summary.trim().is_empty() => summary,
+                    _ => return Err(anyhow::anyhow!("Memory node '{}': summarize requires an input pin with the summary text", node.id)),
+                };
+                let compacted = self.project_db_manager.memory_summarize(slug, &session_id, &summary, params.keep_last).await?;
+                tracing::debug!("🗜️ Summarized {} messages of session '{}'", compacted, session_id);
+            }
+            MemoryOperation::Clear => {
+                let existed = self.project_db_manager.memory_clear(slug, &session_id).await?;
+                tracing::debug!("🧹 Cleared session '{}' (existed: {})", session_id, existed);
+            }
+        }
+
+        let session = self.project_db_manager.memory_load(slug, &session_id, params.window).await?;
+        let stored = session.stored;
+        context.metadata.insert("memory".to_string(), params.memory_value(&session_id, session));
+
+        tracing::info!("✅ Memory {} completed: session '{}' ({} stored messages)", params.operation.as_str(), session_id, stored);
+
+        Ok(ExecutionResult {
+            data: context.data,
+            metadata: context.metadata,
+            termination: Termination::Completed,
+        })
+    }
+
+    /// Execute FeatureFlag node: evaluate a project flag and pick the "true" or "false" port
+    /// 
+    /// Passes the data array through; metadata "feature_flags" collects { flag: { enabled, reason } }
+    async fn execute_feature_flag_node(&self, node: &Node, mut context: ExecutionContext) -> Result<ExecutionResult> {
+        tracing::debug!("🚩 Executing FeatureFlag node: {}", node.id);
+
+        let params = FeatureFlagParams::from_node_params(&node.params)
+            .map_err(|e| anyhow::anyhow!("FeatureFlag node '{}': {}", node.id, e))?;
+        let attributes = params.attributes(self.evaluate_input_pins(node, &context)?);
+
+        let evaluation = match self.project_db_manager.flag_get(&context.project_slug, &params.flag).await? {
+            Some(flag) => flag.evaluate(&params.flag, &attributes),
+            None => FlagEvaluation { enabled: params.default, reason: "missing" },
+        };
+
+        let port = if evaluation.enabled { "true" } else { "false" };
+        tracing::info!("✅ FeatureFlag '{}' is {} ({})", params.flag, port, evaluation.reason);
+        let flags = context.metadata.entry("feature_flags".to_string()).or_insert_with(|| json!({}));
+        flags[params.flag.as_str()] = json!(evaluation);
+        context.metadata.insert(OUTPUT_PORT_METADATA_KEY.to_string(), json!(port));
+
+        Ok(ExecutionResult {
+            data: context.data,
+            metadata: context.metadata,
+            termination: Termination::Completed,
+        })
+    }
+
+    /// Execute ABSplit node: assign every item to a variant and split the output by variant port
+    /// 
+    /// Items keep their order within a variant; metadata "ab_split" collects
+    /// { experiment: { node_id, assignments: { variant: items } } }
+    async fn execute_ab_split_node(&self, node: &Node, mut context: ExecutionContext) -> Result<ExecutionResult> {
+        tracing::debug!("🧪 Executing ABSplit node: {}", node.id);
+
+        let params = ABSplitParams::from_node_params(&node.params)
+            .map_err(|e| anyhow::anyhow!("ABSplit node '{}': {}", node.id, e))?;
+        let experiment = params.experiment.as_deref().unwrap_or(&node.id);
+        let pins = node.input_pins().unwrap_or_default();
+        let [key_pin] = pins.as_ref() else {
+            return Err(anyhow::anyhow!("ABSplit node '{}' needs exactly one input pin with the item key", node.id));
+        };
+
+        let mut groups: Vec<Vec<Value>> = vec![Vec::new(); params.variants.len()];
+        for mut item in std::mem::take(&mut context.data) {
+            let key = self.evaluate_pin_for_item(key_pin, &item, &context)?;
+            let variant = params.assign(experiment, &key);
+            if let (Some(field), Value::Object(fields)) = (&params.field, &mut item) {
+                fields.insert(field.clone(), json!(params.variants[variant].name));
+            }
+            groups[variant].push(item);
+        }
+
+        let assignments: serde_json::Map<String, Value> = params.variants.iter().zip(&groups)
+            .map(|(variant, items)| (variant.name.clone(), json!(items.len())))
+            .collect();
+        tracing::info!("✅ ABSplit '{}' assigned {}", experiment, json!(assignments));
+        let splits = context.metadata.entry("ab_split".to_string()).or_insert_with(|| json!({}));
+        splits[experiment] = json!({ "node_id": node.id, "assignments": assignments });
+
+        let split: Vec<PortSplit> = params.variants.iter().zip(&groups)
+            .map(|(variant, items)| PortSplit { port: variant.name.clone(), items: items.len() })
+            .collect();
+        context.metadata.insert(OUTPUT_SPLIT_METADATA_KEY.to_string(), json!(split));
+
+        Ok(ExecutionResult {
+            data: groups.into_iter().flatten().collect(),
+            metadata: context.metadata,
+            termination: Termination::Completed,
+        })
+    }
+
+    /// Execute Cache node (cache-aside pattern on top of the project KV store)
+    /// 
+    /// Expected params: { "mode": "lookup|store", "namespace": "geocode", "ttl_seconds": 300 }
+    /// - lookup hit: returns the cached items with should_continue = false (unless
+    ///   "stop_on_hit": false), so the expensive downstream fetch is skipped
+    /// - lookup miss: passes data through and stores the key in metadata ("cache_miss_key")
+    /// - store: caches the current data under the key from pins or the recorded miss key
+    async fn execute_cache_node(&self, node: &Node, mut context: ExecutionContext) -> Result<ExecutionResult> {
+        tracing::debug!("🧊 Executing Cache node: {}", node.id);
+        
+        let mode = node.params.get("mode")
+            .and_then(|m| m.as_str())
+            .unwrap_or("lookup");
+        let namespace = node.params.get("namespace")
+            .and_then(|n| n.as_str())
+            .unwrap_or("default");
+        let ttl_seconds = node.params.get("ttl_seconds")
+            .and_then(|t| t.as_u64())
+            .unwrap_or(300);
+        
+        // Key parts from input pins, e.g. ["$json.address"] -> cache:geocode:["Main St 1"]
+        let pin_key = match &node.inputs {
+            Some(inputs) if !inputs.is_empty() => {
+                let parts = self.evaluate_input_pins(node, &context)?;
+                Some(format!("cache:{}:{}", namespace, Value::Array(parts)))
+            }
+            _ => None,
+        };
+        
+        match mode {
+            "lookup" => {
+                let key = pin_key.ok_or_else(|| anyhow::anyhow!("Cache node '{}' lookup requires input pins for the key", node.id))?;
+                
+                if let Some(Value::Array(items)) = self.project_db_manager.kv_get(&context.project_slug, &key).await? {
+                    let stop_on_hit = node.params.get("stop_on_hit")
+                        .and_then(|s| s.as_bool())
+                        .unwrap_or(true);
+                    tracing::info!("✅ Cache hit: {}", key);
+                    context.metadata.insert("cache_hit".to_string(), json!(true));
+                    return Ok(ExecutionResult {
+                        data: items,
+                        metadata: context.metadata,
+                        termination: if stop_on_hit { Termination::stopped(&node.id, "Cache hit") } else { Termination::Completed },
+                    });
+                }
+                
+                tracing::info!("✅ Cache miss: {}", key);
+                context.metadata.insert("cache_hit".to_string(), json!(false));
+                context.metadata.insert("cache_miss_key".to_string(), json!(key));
+                Ok(ExecutionResult {
+                    data: context.data,
+                    metadata: context.metadata,
+                    termination: Termination::Completed,
+                })
+            }
+            "store" => {
+                let key = match pin_key {
+                    Some(key) => key,
+                    None => context.metadata.get("cache_miss_key")
+                        .and_then(|k| k.as_str())
+                        .map(|k| k.to_string())
+                        .ok_or_else(|| anyhow::anyhow!("Cache node '{}' store needs input pins or a preceding cache miss", node.id))?,
+                };
+                
+                self.project_db_manager
+                    .kv_set(&context.project_slug, &key, &Value::Array(context.data.clone()), Some(ttl_seconds))
+                    .await?;
+                tracing::info!("✅ Cached {} items: {} (ttl: {}s)", context.data.len(), key, ttl_seconds);
+                
+                Ok(ExecutionResult {
+                    data: context.data,
+                    metadata: context.metadata,
+                    termination: Termination::Completed,
+                })
+            }
+            other => Err(anyhow::anyhow!("Cache node '{}' has unsupported mode: {}", node.id, other)),
+        }
+    }
+}
+
+/// Whether an HTTP response content type should be read as text/JSON
+fn is_textual_content_type(content_type: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+    mime.starts_with("text/")
+        || mime.ends_with("json")
+        || mime.ends_with("+json")
+        || mime.ends_with("xml")
+        || mime == "application/javascript"
+        || mime == "application/x-www-form-urlencoded"
+}
+
+/// Filename for a downloaded response (Content-Disposition, else last URL segment)
+fn response_filename(headers: &HashMap<String, String>, url: &reqwest::Url) -> Option<String> {
+    let from_disposition = headers.get("content-disposition").and_then(|disposition| {
+        disposition.split(';')
+            .map(|part| part.trim())
+            .find_map(|part| part.strip_prefix("filename="))
+            .map(|name| name.trim_matches('"').to_string())
+    });
+
+    from_disposition.or_else(|| {
+        url.path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| segment.to_string())
+    })
+}
+
+/// Strip path separators and unusual characters from a downloaded filename
+fn sanitize_filename(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+        .collect::<String>()
+        .trim_start_matches('.')
+        .to_string()
+}
+
+/// Uniform random number in [0, 1) for Mock latency jitter and failure rates
+fn random_unit() -> f64 {
+    // The low 53 bits of a v4 UUID are fully random
+    let bits = uuid::Uuid::new_v4().as_u128() & ((1u128 << 53) - 1);
+    bits as f64 / (1u64 << 53) as f64
+}
+
+/// Audit columns of a SimpleTableWriter/PGDynTableWriter row ("audit_columns": true)
+/// 
+/// `_run_id` (metadata "execution_id", shared by sub-workflows), `_workflow_id`
+/// and `_executed_at` (RFC 3339), stored as TEXT; empty when the option is off.
+fn audit_values(node: &Node, context: &ExecutionContext) -> Vec<(String, Value)> {
+    if !node.params.get("audit_columns").and_then(|a| a.as_bool()).unwrap_or(false) {
+        return Vec::new();
+    }
+    vec![
+        ("_run_id".to_string(), context.metadata.get("execution_id").cloned().unwrap_or(Value::Null)),
+        ("_workflow_id".to_string(), context.metadata.get("workflow_id").cloned().unwrap_or(Value::Null)),
+        ("_executed_at".to_string(), json!(chrono::Utc::now().to_rfc3339())),
+    ]
 }
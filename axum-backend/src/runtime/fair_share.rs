@@ -0,0 +1,203 @@
+//! Weighted fair sharing of execution slots across projects
+//!
+//! Executions waiting for a slot queue per project. A freed slot goes to the
+//! project at the front of a round-robin ring, which keeps the turn for up to
+//! its weight grants in a row before the next waiting project is served, so a
+//! burst in one project can't starve the others:
+//!
+//! MECHAWAY_PROJECT_WEIGHTS="shop=4,analytics=2"   (every other project: 1)
+//!
+//! Within a project executions start in arrival order. With a single busy
+//! project (or free slots) it behaves like a plain FIFO semaphore.
+
+use serde::Serialize;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+use tokio::sync::oneshot;
+
+/// Weight of projects MECHAWAY_PROJECT_WEIGHTS doesn't list
+const DEFAULT_WEIGHT: u32 = 1;
+
+/// Slot usage of one project for the metrics endpoint
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectShare {
+    pub project: String,
+    pub weight: u32,
+    pub running: usize,
+    pub queued: usize,
+}
+
+/// Execution slot held by a running execution; freed (and handed on) on drop
+pub struct SlotPermit {
+    slots: Option<Arc<FairSlots>>,
+    project: String,
+}
+
+impl Drop for SlotPermit {
+    fn drop(&mut self) {
+        if let Some(slots) = self.slots.take() {
+            slots.release(&self.project);
+        }
+    }
+}
+
+#[derive(Default)]
+struct ProjectSlots {
+    waiters: VecDeque<oneshot::Sender<SlotPermit>>,
+    running: usize,
+}
+
+#[derive(Default)]
+struct FairState {
+    available: usize,
+    /// Projects with waiters in round-robin order; the front one has the turn
+    ring: VecDeque<String>,
+    /// Slots the front project got in its current turn
+    turn_grants: u32,
+    projects: HashMap<String, ProjectSlots>,
+}
+
+/// Concurrency slots shared by weighted round-robin across projects
+pub struct FairSlots {
+    weights: HashMap<String, u32>,
+    state: Mutex<FairState>,
+}
+
+impl std::fmt::Debug for FairSlots {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FairSlots").field("weights", &self.weights).finish()
+    }
+}
+
+impl FairSlots {
+    /// Create `capacity` slots with per-project weights
+    pub fn new(capacity: usize, weights: HashMap<String, u32>) -> Self {
+        Self {
+            weights,
+            state: Mutex::new(FairState { available: capacity, ..FairState::default() }),
+        }
+    }
+
+    /// Weight of a project
+    pub fn weight(&self, project: &str) -> u32 {
+        self.weights.get(project).copied().unwrap_or(DEFAULT_WEIGHT)
+    }
+
+    /// Wait for a slot for an execution of `project` (None if the slots are gone)
+    pub async fn acquire(self: &Arc<Self>, project: &str) -> Option<SlotPermit> {
+        let (sender, receiver) = oneshot::channel();
+        {
+            let mut state = self.state.lock().unwrap();
+            let slots = state.projects.entry(project.to_string()).or_default();
+            slots.waiters.push_back(sender);
+            if !state.ring.iter().any(|waiting| waiting == project) {
+                state.ring.push_back(project.to_string());
+            }
+            self.dispatch(&mut state);
+        }
+        // A waiter cancelled after its grant drops the permit with the channel, freeing the slot
+        receiver.await.ok()
+    }
+
+    /// Usage of every project with running or waiting executions, sorted by project
+    pub fn stats(&self) -> Vec<ProjectShare> {
+        let state = self.state.lock().unwrap();
+        let mut shares: Vec<ProjectShare> = state.projects.iter()
+            .map(|(project, slots)| ProjectShare {
+                project: project.clone(),
+                weight: self.weight(project),
+                running: slots.running,
+                queued: slots.waiters.iter().filter(|waiter| !waiter.is_closed()).count(),
+            })
+            .filter(|share| share.running > 0 || share.queued > 0)
+            .collect();
+        shares.sort_by(|a, b| a.project.cmp(&b.project));
+        shares
+    }
+
+    fn release(self: &Arc<Self>, project: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.available += 1;
+        if let Some(slots) = state.projects.get_mut(project) {
+            slots.running = slots.running.saturating_sub(1);
+        }
+        self.dispatch(&mut state);
+        forget_idle(&mut state, project);
+    }
+
+    /// Hand free slots to waiters, taking turns across projects by weight
+    fn dispatch(self: &Arc<Self>, state: &mut FairState) {
+        while state.available > 0 {
+            let Some((project, waiter)) = self.next_waiter(state) else {
+                return;
+            };
+            let permit = SlotPermit { slots: Some(Arc::clone(self)), project: project.clone() };
+            match waiter.send(permit) {
+                Ok(()) => {
+                    state.available -= 1;
+                    state.projects.entry(project).or_default().running += 1;
+                }
+                // The waiter was cancelled meanwhile: disarm the permit (the slot stays free)
+                Err(mut permit) => {
+                    permit.slots = None;
+                    forget_idle(state, &project);
+                }
+            }
+        }
+    }
+
+    /// Next live waiter in round-robin order, advancing the turn
+    fn next_waiter(&self, state: &mut FairState) -> Option<(String, oneshot::Sender<SlotPermit>)> {
+        loop {
+            let project = state.ring.front()?.clone();
+            let slots = state.projects.entry(project.clone()).or_default();
+            // Waiters cancelled before their turn are dropped without using it
+            while slots.waiters.front().is_some_and(|waiter| waiter.is_closed()) {
+                slots.waiters.pop_front();
+            }
+            let Some(waiter) = slots.waiters.pop_front() else {
+                state.ring.pop_front();
+                state.turn_grants = 0;
+                forget_idle(state, &project);
+                continue;
+            };
+
+            let more_waiting = !slots.waiters.is_empty();
+            state.turn_grants += 1;
+            if !more_waiting || state.turn_grants >= self.weight(&project) {
+                state.ring.pop_front();
+                state.turn_grants = 0;
+                if more_waiting {
+                    state.ring.push_back(project.clone());
+                }
+            }
+            return Some((project, waiter));
+        }
+    }
+}
+
+/// Drop the bookkeeping of a project with nothing running or waiting
+fn forget_idle(state: &mut FairState, project: &str) {
+    if state.projects.get(project).is_some_and(|slots| slots.running == 0 && slots.waiters.is_empty()) {
+        state.projects.remove(project);
+    }
+}
+
+/// Parse MECHAWAY_PROJECT_WEIGHTS ("shop=4,analytics=2"), skipping invalid entries
+pub fn parse_project_weights(spec: &str) -> HashMap<String, u32> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| {
+            let parsed = entry.split_once('=')
+                .and_then(|(project, weight)| Some((project.trim().to_string(), weight.trim().parse::<u32>().ok()?)))
+                .filter(|(project, weight)| !project.is_empty() && *weight > 0);
+            if parsed.is_none() {
+                tracing::warn!("⚠️ Ignoring invalid project weight '{}' (expected project=weight, weight >= 1)", entry);
+            }
+            parsed
+        })
+        .collect()
+}
@@ -0,0 +1,67 @@
+//! FeatureFlag node configuration
+//! 
+//! { "flag": "new_checkout", "attributes": ["key", "country"], "default": false }
+//! with inputs ["$json.user_id", "$json.country"]
+//! 
+//! The node evaluates a project flag (see project::flags) once per run against
+//! the attributes filled from its input pins, passes the data through and picks
+//! its "true" or "false" output port; edges leaving the other port don't run.
+
+use anyhow::Result;
+use serde_json::{Map, Value};
+
+/// FeatureFlag node params
+#[derive(Debug, Clone)]
+pub struct FeatureFlagParams {
+    /// Flag name in the project
+    pub flag: String,
+    /// Attribute names filled from the input pins in order
+    pub attributes: Vec<String>,
+    /// Value when the project has no such flag
+    pub default: bool,
+}
+
+impl FeatureFlagParams {
+    /// Parse node params
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        let flag = params.get("flag")
+            .and_then(|f| f.as_str())
+            .filter(|f| is_flag_name(f))
+            .ok_or_else(|| anyhow::anyhow!("missing or invalid 'flag' parameter (letters, digits, '_', '-' and '.')"))?
+            .to_string();
+        let attributes = match params.get("attributes") {
+            None | Some(Value::Null) => Vec::new(),
+            Some(Value::Array(names)) => names.iter()
+                .map(|name| name.as_str().filter(|name| !name.is_empty()).map(str::to_string))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| anyhow::anyhow!("'attributes' must be a list of attribute names"))?,
+            Some(_) => return Err(anyhow::anyhow!("'attributes' must be a list of attribute names")),
+        };
+        let default = match params.get("default") {
+            None | Some(Value::Null) => false,
+            Some(Value::Bool(default)) => *default,
+            Some(_) => return Err(anyhow::anyhow!("'default' must be true or false")),
+        };
+        Ok(Self { flag, attributes, default })
+    }
+
+    /// Check that the input pins fill the attributes one to one
+    pub fn check_inputs(&self, inputs: Option<&[String]>) -> Result<()> {
+        let pins = inputs.map_or(0, <[String]>::len);
+        if pins != self.attributes.len() {
+            return Err(anyhow::anyhow!("{} attribute(s) but {} input pin(s)", self.attributes.len(), pins));
+        }
+        Ok(())
+    }
+
+    /// Attributes of a run from the evaluated input pins
+    pub fn attributes(&self, values: Vec<Value>) -> Map<String, Value> {
+        self.attributes.iter().cloned().zip(values).collect()
+    }
+}
+
+/// Flag names: letters, digits, '_', '-' and '.'
+pub fn is_flag_name(name: &str) -> bool {
+    !name.is_empty() && name.len() <= 128
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
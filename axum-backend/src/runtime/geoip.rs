@@ -0,0 +1,193 @@
+//! GeoIP node lookups against local MaxMind databases
+//! 
+//! Databases are configured per project (project settings key "geoip"):
+//! 
+//! { "city_db": "/var/lib/geoip/GeoLite2-City.mmdb", "asn_db": "GeoLite2-ASN.mmdb" }
+//! 
+//! Relative paths are resolved inside the project directory. `city_db` may also
+//! be a Country database (city fields then stay null). Readers are loaded once
+//! and reloaded when the file's modification time changes, so databases can be
+//! updated in place (e.g. by geoipupdate) without a restart.
+
+use anyhow::Result;
+use maxminddb::{geoip2, MaxMindDBError, Reader};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, HashMap},
+    net::{IpAddr, SocketAddr},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+/// Per-project GeoIP databases (project settings key "geoip")
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GeoIpSettings {
+    /// GeoIP2 / GeoLite2 City or Country database
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub city_db: Option<String>,
+    /// GeoIP2 / GeoLite2 ASN database
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub asn_db: Option<String>,
+}
+
+impl GeoIpSettings {
+    /// Read the geoip section of a project's settings (nothing configured if absent or invalid)
+    pub fn from_project_settings(settings: &Value) -> Self {
+        settings.get("geoip")
+            .and_then(|geoip| serde_json::from_value(geoip.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn is_configured(&self) -> bool {
+        self.city_db.is_some() || self.asn_db.is_some()
+    }
+
+    /// Check that the configured files are MaxMind databases of the right kind
+    pub fn validate(&self, project_dir: &Path) -> Result<()> {
+        for (path, kinds) in [(&self.city_db, ["City", "Country"].as_slice()), (&self.asn_db, ["ASN"].as_slice())] {
+            let Some(path) = path else { continue };
+            let resolved = resolve_path(project_dir, path);
+            let reader = Reader::open_readfile(&resolved)
+                .map_err(|e| anyhow::anyhow!("Can't open '{}': {}", resolved.display(), e))?;
+            let database_type = &reader.metadata.database_type;
+            if !kinds.iter().any(|kind| database_type.contains(kind)) {
+                return Err(anyhow::anyhow!("'{}' is a {} database, expected {}", path, database_type, kinds.join(" or ")));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Database path, relative paths inside the project directory
+pub fn resolve_path(project_dir: &Path, path: &str) -> PathBuf {
+    let path = Path::new(path);
+    if path.is_absolute() { path.to_path_buf() } else { project_dir.join(path) }
+}
+
+/// Database file contents as loaded by maxminddb
+pub type GeoIpReader = Reader<Vec<u8>>;
+
+/// Reader with the file modification time it was loaded at
+struct LoadedReader {
+    modified: Option<SystemTime>,
+    reader: Arc<GeoIpReader>,
+}
+
+/// Loaded readers, shared by all projects using the same file
+#[derive(Default)]
+pub struct GeoIpReaders {
+    readers: Mutex<HashMap<PathBuf, LoadedReader>>,
+}
+
+impl std::fmt::Debug for GeoIpReaders {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let loaded: Vec<PathBuf> = self.readers.lock().unwrap().keys().cloned().collect();
+        f.debug_struct("GeoIpReaders").field("loaded", &loaded).finish()
+    }
+}
+
+impl GeoIpReaders {
+    /// Reader for a database file, (re)loading it when new or modified
+    pub async fn get(&self, path: &Path) -> Result<Arc<GeoIpReader>> {
+        let modified = tokio::fs::metadata(path).await
+            .map_err(|e| anyhow::anyhow!("GeoIP database '{}' is not readable: {}", path.display(), e))?
+            .modified().ok();
+        if let Some(loaded) = self.readers.lock().unwrap().get(path) {
+            if loaded.modified == modified {
+                return Ok(loaded.reader.clone());
+            }
+        }
+
+        let owned_path = path.to_path_buf();
+        let reader = tokio::task::spawn_blocking(move || Reader::open_readfile(owned_path)).await?
+            .map_err(|e| anyhow::anyhow!("Can't load GeoIP database '{}': {}", path.display(), e))?;
+        tracing::info!("🌍 Loaded GeoIP database {} ({}, built {})", path.display(),
+            reader.metadata.database_type, reader.metadata.build_epoch);
+        let reader = Arc::new(reader);
+        self.readers.lock().unwrap().insert(path.to_path_buf(), LoadedReader { modified, reader: reader.clone() });
+        Ok(reader)
+    }
+}
+
+/// Parse a client address: plain IPs, "ip:port", "[v6]:port" and X-Forwarded-For lists (first entry)
+pub fn parse_ip(text: &str) -> Option<IpAddr> {
+    let first = text.split(',').next()?.trim();
+    first.parse::<IpAddr>().ok()
+        .or_else(|| first.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+        .or_else(|| first.trim_start_matches('[').trim_end_matches(']').parse().ok())
+        // IPv4-mapped IPv6 (::ffff:1.2.3.4) is looked up as IPv4
+        .map(|ip| ip.to_canonical())
+}
+
+/// Look an address up in the configured databases
+/// 
+/// Unknown and private addresses give `found: false` with null fields.
+pub fn lookup(
+    ip: IpAddr,
+    city_db: Option<&GeoIpReader>,
+    asn_db: Option<&GeoIpReader>,
+    language: &str,
+) -> Result<Value> {
+    let name = |names: &Option<BTreeMap<&str, &str>>| names.as_ref()
+        .and_then(|names| names.get(language).or_else(|| names.get("en")).map(|n| n.to_string()));
+
+    let mut result = json!({
+        "ip": ip.to_string(),
+        "found": false,
+        "country_code": null,
+        "country": null,
+        "in_eu": null,
+        "continent_code": null,
+        "subdivision_code": null,
+        "subdivision": null,
+        "city": null,
+        "postal_code": null,
+        "latitude": null,
+        "longitude": null,
+        "accuracy_radius_km": null,
+        "time_zone": null,
+        "asn": null,
+        "asn_org": null,
+    });
+
+    if let Some(reader) = city_db {
+        if let Some(city) = not_found_as_none(reader.lookup::<geoip2::City>(ip))? {
+            result["found"] = json!(true);
+            // Anycast / satellite ranges only have a registered country
+            let country = city.country.as_ref().or(city.registered_country.as_ref());
+            result["country_code"] = json!(country.and_then(|c| c.iso_code));
+            result["country"] = json!(country.and_then(|c| name(&c.names)));
+            result["in_eu"] = json!(country.map(|c| c.is_in_european_union.unwrap_or(false)));
+            result["continent_code"] = json!(city.continent.as_ref().and_then(|c| c.code));
+            let subdivision = city.subdivisions.as_ref().and_then(|s| s.first());
+            result["subdivision_code"] = json!(subdivision.and_then(|s| s.iso_code));
+            result["subdivision"] = json!(subdivision.and_then(|s| name(&s.names)));
+            result["city"] = json!(city.city.as_ref().and_then(|c| name(&c.names)));
+            result["postal_code"] = json!(city.postal.as_ref().and_then(|p| p.code));
+            if let Some(location) = &city.location {
+                result["latitude"] = json!(location.latitude);
+                result["longitude"] = json!(location.longitude);
+                result["accuracy_radius_km"] = json!(location.accuracy_radius);
+                result["time_zone"] = json!(location.time_zone);
+            }
+        }
+    }
+    if let Some(reader) = asn_db {
+        if let Some(asn) = not_found_as_none(reader.lookup::<geoip2::Asn>(ip))? {
+            result["found"] = json!(true);
+            result["asn"] = json!(asn.autonomous_system_number);
+            result["asn_org"] = json!(asn.autonomous_system_organization);
+        }
+    }
+    Ok(result)
+}
+
+fn not_found_as_none<T>(lookup: Result<T, MaxMindDBError>) -> Result<Option<T>> {
+    match lookup {
+        Ok(record) => Ok(Some(record)),
+        Err(MaxMindDBError::AddressNotFoundError(_)) => Ok(None),
+        Err(e) => Err(anyhow::anyhow!("GeoIP lookup failed: {}", e)),
+    }
+}
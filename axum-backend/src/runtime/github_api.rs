@@ -0,0 +1,163 @@
+//! GitHubAPI node requests
+//! 
+//! Creates issues, comments and commit statuses with a token from the node's
+//! secrets (`["$secret.github_token"]`):
+//! 
+//! { "operation": "create_issue", "repo": "acme/api", "fields": ["title", "body"] }
+//! { "operation": "create_comment", "repo": "$json.git.repository", "issue_number": "$json.number", "fields": ["body"] }
+//! { "operation": "create_status", "repo": "$json.git.repository", "sha": "$json.git.sha",
+//!   "params": { "state": "success", "context": "mechaway/checks" } }
+//! 
+//! `repo`, `issue_number` and `sha` are pin expressions or literals. Input pins
+//! fill `fields` (JSON body keys) in order on top of the static `params` body.
+
+use crate::runtime::outbox;
+use anyhow::Result;
+use serde_json::{Map, Value};
+
+/// Public GitHub API (overridable with "api_base" for GitHub Enterprise)
+const DEFAULT_API_BASE: &str = "https://api.github.com";
+
+/// Supported GitHubAPI operations
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GitHubOperation {
+    CreateIssue,
+    CreateComment,
+    CreateStatus,
+}
+
+/// Parsed GitHubAPI node params
+#[derive(Debug, Clone)]
+pub struct GitHubApiParams {
+    pub operation: GitHubOperation,
+    /// "owner/name" or a pin expression
+    pub repo: String,
+    /// Pin expression or literal (create_comment)
+    pub issue_number: Option<String>,
+    /// Pin expression or literal (create_status)
+    pub sha: Option<String>,
+    /// JSON body keys filled by the input pins, in order
+    pub fields: Vec<String>,
+    /// Static JSON body
+    pub params: Map<String, Value>,
+    pub api_base: String,
+}
+
+/// Path values resolved from the `repo` / `issue_number` / `sha` pins
+#[derive(Debug, Clone)]
+pub struct GitHubTarget {
+    pub repo: String,
+    pub issue_number: Option<String>,
+    pub sha: Option<String>,
+}
+
+impl GitHubApiParams {
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        let str_param = |name: &str| params.get(name).and_then(|v| match v {
+            Value::String(s) => Some(s.clone()),
+            Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        });
+
+        let operation = match str_param("operation").as_deref() {
+            Some("create_issue") => GitHubOperation::CreateIssue,
+            Some("create_comment") => GitHubOperation::CreateComment,
+            Some("create_status") => GitHubOperation::CreateStatus,
+            Some(other) => return Err(anyhow::anyhow!(
+                "Unknown GitHub operation '{}' (create_issue, create_comment, create_status)", other
+            )),
+            None => return Err(anyhow::anyhow!("Missing 'operation' parameter")),
+        };
+        let repo = str_param("repo").ok_or_else(|| anyhow::anyhow!("Missing 'repo' parameter"))?;
+        let issue_number = str_param("issue_number");
+        let sha = str_param("sha");
+        match operation {
+            GitHubOperation::CreateComment if issue_number.is_none() =>
+                return Err(anyhow::anyhow!("'create_comment' needs 'issue_number'")),
+            GitHubOperation::CreateStatus if sha.is_none() =>
+                return Err(anyhow::anyhow!("'create_status' needs 'sha'")),
+            _ => {}
+        }
+
+        let fields = match params.get("fields") {
+            None | Some(Value::Null) => Vec::new(),
+            Some(Value::Array(fields)) => fields.iter()
+                .map(|f| f.as_str().map(str::to_string).ok_or_else(|| anyhow::anyhow!("'fields' must be strings")))
+                .collect::<Result<_>>()?,
+            Some(_) => return Err(anyhow::anyhow!("'fields' must be an array of body keys")),
+        };
+        let static_params = match params.get("params") {
+            None | Some(Value::Null) => Map::new(),
+            Some(Value::Object(map)) => map.clone(),
+            Some(_) => return Err(anyhow::anyhow!("'params' must be an object")),
+        };
+
+        Ok(Self {
+            operation,
+            repo,
+            issue_number,
+            sha,
+            fields,
+            params: static_params,
+            api_base: str_param("api_base").unwrap_or_else(|| DEFAULT_API_BASE.to_string()),
+        })
+    }
+
+    /// JSON body from the static params and the evaluated input pins
+    pub fn body(&self, input_values: &[Value]) -> Value {
+        let mut body = self.params.clone();
+        for (field, value) in self.fields.iter().zip(input_values) {
+            body.insert(field.clone(), value.clone());
+        }
+        Value::Object(body)
+    }
+
+    /// Send the request, returning GitHub's response object
+    pub async fn send(&self, token: &str, target: &GitHubTarget, body: &Value) -> Result<Value> {
+        let valid_part = |part: &str| !part.is_empty() && part != "." && part != ".."
+            && part.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if target.repo.split('/').count() != 2 || !target.repo.split('/').all(valid_part) {
+            return Err(anyhow::anyhow!("'repo' must be 'owner/name', got '{}'", target.repo));
+        }
+        // Pin values end up in the URL path: only accept what GitHub itself would
+        if let Some(number) = &target.issue_number {
+            if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+                return Err(anyhow::anyhow!("'issue_number' must be a number, got '{}'", number));
+            }
+        }
+        if let Some(sha) = &target.sha {
+            if sha.is_empty() || !sha.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(anyhow::anyhow!("'sha' must be a commit SHA, got '{}'", sha));
+            }
+        }
+        let path = match self.operation {
+            GitHubOperation::CreateIssue => format!("/repos/{}/issues", target.repo),
+            GitHubOperation::CreateComment => format!("/repos/{}/issues/{}/comments", target.repo,
+                target.issue_number.as_deref().unwrap_or_default()),
+            GitHubOperation::CreateStatus => format!("/repos/{}/statuses/{}", target.repo,
+                target.sha.as_deref().unwrap_or_default()),
+        };
+        let url = format!("{}{}", self.api_base.trim_end_matches('/'), path);
+
+        let response = reqwest::Client::new()
+            .post(&url)
+            .bearer_auth(token)
+            .header("Accept", "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28")
+            // GitHub rejects requests without a User-Agent
+            .header("User-Agent", "mechaway")
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| outbox::request_error("GitHub request failed", e))?;
+
+        let status = response.status();
+        let body: Value = response.json().await
+            .map_err(|e| anyhow::anyhow!("GitHub returned invalid JSON ({}): {}", status, e))?;
+        if !status.is_success() {
+            let message = body.get("message").and_then(|m| m.as_str()).unwrap_or("unknown error");
+            return Err(anyhow::anyhow!("GitHub API error {}: {}", status.as_u16(), message));
+        }
+        Ok(body)
+    }
+}
@@ -0,0 +1,176 @@
+//! Retention janitor
+//! 
+//! Applies per-project retention settings on a fixed interval: prunes old
+//! execution history (and exactly-once inbox claims), purges expired KV entries, trims the uploads directory
+//! by age and total size, drops old dead letters (failed outbox entries), and
+//! vacuums the project database when rows were removed. Settings live in the
+//! project settings:
+//! 
+//! { "retention": { "execution_days": 30, "upload_days": 7, "upload_max_bytes": 1073741824,
+//!                  "dead_letter_days": 14 } }
+//! 
+//! Projects without a "retention" section keep everything except expired KV keys.
+
+use crate::{config::MaintenanceConfig, project::ProjectDatabaseManager};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{path::Path, sync::Arc, time::{Duration, SystemTime}};
+
+/// Subdirectory of the project directory holding downloaded/uploaded files
+const UPLOADS_DIR_NAME: &str = "uploads";
+
+/// Per-project retention limits (project settings key "retention")
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetentionSettings {
+    /// Keep execution history for this many days
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub execution_days: Option<u64>,
+    /// Delete files in uploads/ older than this many days
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upload_days: Option<u64>,
+    /// Keep uploads/ under this many bytes (oldest files are deleted first)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upload_max_bytes: Option<u64>,
+    /// Delete failed outbox entries parked more than this many days ago
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dead_letter_days: Option<u64>,
+}
+
+impl RetentionSettings {
+    /// Read the retention section of a project's settings (empty if absent or invalid)
+    pub fn from_project_settings(settings: &Value) -> Self {
+        settings.get("retention")
+            .and_then(|retention| serde_json::from_value(retention.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Reject zero limits, which would delete everything on the next pass
+    pub fn validate(&self) -> Result<()> {
+        if self.execution_days == Some(0) || self.upload_days == Some(0) || self.dead_letter_days == Some(0) {
+            return Err(anyhow::anyhow!("Retention days must be positive (omit the field to keep everything)"));
+        }
+        Ok(())
+    }
+}
+
+/// What one janitor pass removed from a project
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RetentionReport {
+    pub project: String,
+    pub executions_deleted: u64,
+    pub kv_expired_deleted: u64,
+    pub uploads_deleted: u64,
+    pub upload_bytes_freed: u64,
+    pub dead_letters_deleted: u64,
+    /// Whether the project database was vacuumed after deleting rows
+    pub vacuumed: bool,
+}
+
+/// Background task applying retention settings
+pub struct RetentionJanitor {
+    project_db_manager: Arc<ProjectDatabaseManager>,
+    config: MaintenanceConfig,
+}
+
+impl RetentionJanitor {
+    pub fn new(project_db_manager: Arc<ProjectDatabaseManager>, config: MaintenanceConfig) -> Self {
+        Self { project_db_manager, config }
+    }
+
+    /// Run every `janitor_interval_secs` in the background
+    pub fn start(self: Arc<Self>) {
+        if self.config.janitor_interval_secs == 0 {
+            tracing::info!("🧹 Retention janitor disabled");
+            return;
+        }
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(Duration::from_secs(self.config.janitor_interval_secs));
+            loop {
+                ticker.tick().await;
+                for project in self.project_db_manager.known_projects().await {
+                    match self.run_project(&project).await {
+                        Ok(report) => tracing::debug!("🧹 Retention pass for {}: {:?}", project, report),
+                        Err(e) => tracing::warn!("⚠️ Retention pass failed for project {}: {}", project, e),
+                    }
+                }
+            }
+        });
+    }
+
+    /// Apply a project's retention settings once
+    pub async fn run_project(&self, project_slug: &str) -> Result<RetentionReport> {
+        let settings = self.project_db_manager.get_project_settings(project_slug).await?;
+        let retention = RetentionSettings::from_project_settings(&settings);
+        let mut report = RetentionReport { project: project_slug.to_string(), ..Default::default() };
+
+        if let Some(days) = retention.execution_days {
+            let cutoff = chrono::Utc::now() - chrono::Duration::days(days as i64);
+            report.executions_deleted = self.project_db_manager.prune_executions_before(project_slug, cutoff).await?;
+            // Exactly-once claims live as long as the executions they started
+            self.project_db_manager.prune_inbox_before(project_slug, cutoff).await?;
+        }
+        report.kv_expired_deleted = self.project_db_manager.kv_purge_expired(project_slug).await?;
+        if let Some(days) = retention.dead_letter_days {
+            let cutoff = chrono::Utc::now() - chrono::Duration::days(days as i64);
+            report.dead_letters_deleted = self.project_db_manager.prune_failed_outbox_before(project_slug, cutoff).await?;
+        }
+
+        if retention.upload_days.is_some() || retention.upload_max_bytes.is_some() {
+            let uploads_dir = self.project_db_manager.project_dir(project_slug)?.join(UPLOADS_DIR_NAME);
+            let (deleted, freed) = trim_uploads(&uploads_dir, &retention).await?;
+            report.uploads_deleted = deleted;
+            report.upload_bytes_freed = freed;
+        }
+
+        if report.executions_deleted + report.kv_expired_deleted + report.dead_letters_deleted > 0 {
+            self.project_db_manager.vacuum_project_db(project_slug).await?;
+            report.vacuumed = true;
+        }
+
+        if report.executions_deleted + report.kv_expired_deleted + report.uploads_deleted + report.dead_letters_deleted > 0 {
+            tracing::info!("🧹 Retention for {}: {} executions, {} expired keys, {} uploads ({} bytes), {} dead letters removed",
+                project_slug, report.executions_deleted, report.kv_expired_deleted,
+                report.uploads_deleted, report.upload_bytes_freed, report.dead_letters_deleted);
+        }
+
+        Ok(report)
+    }
+}
+
+/// Delete uploads past their age limit, then the oldest until under the size cap
+async fn trim_uploads(uploads_dir: &Path, retention: &RetentionSettings) -> Result<(u64, u64)> {
+    let mut files = Vec::new();
+    let mut entries = match tokio::fs::read_dir(uploads_dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((0, 0)),
+        Err(e) => return Err(e.into()),
+    };
+    while let Some(entry) = entries.next_entry().await? {
+        let metadata = entry.metadata().await?;
+        if metadata.is_file() {
+            files.push((entry.path(), metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH), metadata.len()));
+        }
+    }
+    // Oldest first
+    files.sort_by_key(|(_, modified, _)| *modified);
+
+    let max_age = retention.upload_days.map(|days| Duration::from_secs(days * 24 * 60 * 60));
+    let mut total: u64 = files.iter().map(|(_, _, size)| size).sum();
+    let (mut deleted, mut freed) = (0, 0);
+
+    for (path, modified, size) in files {
+        let expired = max_age.is_some_and(|max_age| modified.elapsed().unwrap_or_default() > max_age);
+        let over_quota = retention.upload_max_bytes.is_some_and(|max| total > max);
+        if !expired && !over_quota {
+            // Remaining files are newer and the total is within the cap
+            break;
+        }
+        tokio::fs::remove_file(&path).await?;
+        total -= size;
+        deleted += 1;
+        freed += size;
+    }
+
+    Ok((deleted, freed))
+}
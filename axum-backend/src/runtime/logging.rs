@@ -0,0 +1,166 @@
+//! Bounded payload previews for log lines
+//! 
+//! Debug logs used to serialize entire data arrays, which collapses with
+//! multi-MB payloads. These helpers stop serializing once the preview limit
+//! is reached, so logging cost stays constant regardless of payload size.
+//! 
+//! Previews respect the running workflow's log policy (`log_level` and
+//! `log_data` settings), which the engine scopes around each execution.
+
+use crate::workflow::types::{LogDataPolicy, LogLevel, WorkflowSettings};
+use serde_json::Value;
+use std::{future::Future, io::Write};
+use tracing::{Level, Metadata};
+
+/// Maximum number of bytes of serialized JSON shown in a log line
+pub const LOG_PREVIEW_LIMIT: usize = 2048;
+
+tokio::task_local! {
+    /// Log policy of the workflow executing on the current task
+    static WORKFLOW_LOG_POLICY: LogPolicy;
+}
+
+/// Per-workflow logging policy
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LogPolicy {
+    /// Most verbose level allowed (None = global filter only)
+    pub level: Option<LogLevel>,
+    /// Payload logging policy
+    pub data: LogDataPolicy,
+}
+
+impl LogPolicy {
+    /// Policy configured in a workflow's settings
+    pub fn from_settings(settings: &WorkflowSettings) -> Self {
+        Self {
+            level: settings.log_level,
+            data: settings.log_data.unwrap_or_default(),
+        }
+    }
+
+    /// Policy of the workflow executing on the current task (default outside executions)
+    pub fn current() -> Self {
+        WORKFLOW_LOG_POLICY.try_with(|policy| *policy).unwrap_or_default()
+    }
+
+    /// Run a future with this policy applied to its log events
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        WORKFLOW_LOG_POLICY.scope(self, future).await
+    }
+
+    /// Whether an event at `level` may be logged under this policy
+    pub fn allows(&self, level: &Level) -> bool {
+        let Some(max) = self.level else { return true };
+        let max = match max {
+            LogLevel::Error => Level::ERROR,
+            LogLevel::Warn => Level::WARN,
+            LogLevel::Info => Level::INFO,
+            LogLevel::Debug => Level::DEBUG,
+            LogLevel::Trace => Level::TRACE,
+        };
+        // tracing orders levels by verbosity: ERROR < WARN < ... < TRACE
+        *level <= max
+    }
+
+    /// Preview of a data array under this policy
+    pub fn preview_data(&self, data: &[Value]) -> String {
+        match self.data {
+            LogDataPolicy::None => format!("[{} item(s) redacted]", data.len()),
+            LogDataPolicy::Truncated => preview(data, LOG_PREVIEW_LIMIT),
+            LogDataPolicy::Full => serde_json::to_string(data).unwrap_or_else(|_| "invalid_json".to_string()),
+        }
+    }
+
+    /// Preview of a single JSON value under this policy
+    pub fn preview_value(&self, value: &Value) -> String {
+        match self.data {
+            LogDataPolicy::None => "[redacted]".to_string(),
+            LogDataPolicy::Truncated => preview(value, LOG_PREVIEW_LIMIT),
+            LogDataPolicy::Full => value.to_string(),
+        }
+    }
+
+    /// Debug-formatted payload (headers, bind parameters) under this policy
+    pub fn preview_debug<T: std::fmt::Debug + ?Sized>(&self, value: &T) -> String {
+        match self.data {
+            LogDataPolicy::None => "[redacted]".to_string(),
+            LogDataPolicy::Truncated => {
+                let mut text = format!("{:?}", value);
+                if text.len() > LOG_PREVIEW_LIMIT {
+                    let mut end = LOG_PREVIEW_LIMIT;
+                    while !text.is_char_boundary(end) {
+                        end -= 1;
+                    }
+                    text.truncate(end);
+                    text.push_str("…(truncated)");
+                }
+                text
+            }
+            LogDataPolicy::Full => format!("{:?}", value),
+        }
+    }
+}
+
+/// Subscriber filter applying the current workflow's `log_level` cap
+/// 
+/// Installed next to the global filter; events outside workflow executions
+/// are unaffected.
+pub fn workflow_level_filter(metadata: &Metadata<'_>) -> bool {
+    !metadata.is_event() || LogPolicy::current().allows(metadata.level())
+}
+
+/// Writer that keeps the first `limit` bytes and then aborts serialization
+struct BoundedWriter {
+    buffer: Vec<u8>,
+    limit: usize,
+    truncated: bool,
+}
+
+impl Write for BoundedWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let remaining = self.limit.saturating_sub(self.buffer.len());
+        if buf.len() > remaining {
+            self.buffer.extend_from_slice(&buf[..remaining]);
+            self.truncated = true;
+            // Abort serialization early - the rest of the payload is never visited
+            return Err(std::io::Error::other("preview limit reached"));
+        }
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Serialize at most `limit` bytes of a value for logging
+fn preview<T: serde::Serialize + ?Sized>(value: &T, limit: usize) -> String {
+    let mut writer = BoundedWriter { buffer: Vec::new(), limit, truncated: false };
+    let result = serde_json::to_writer(&mut writer, value);
+
+    if result.is_err() && !writer.truncated {
+        return "invalid_json".to_string();
+    }
+
+    let mut text = String::from_utf8_lossy(&writer.buffer).into_owned();
+    if writer.truncated {
+        text.push_str("…(truncated)");
+    }
+    text
+}
+
+/// Preview of a data array for log lines (current workflow's policy)
+pub fn preview_data(data: &[Value]) -> String {
+    LogPolicy::current().preview_data(data)
+}
+
+/// Preview of a single JSON value for log lines (current workflow's policy)
+pub fn preview_value(value: &Value) -> String {
+    LogPolicy::current().preview_value(value)
+}
+
+/// Debug-formatted payload for log lines (current workflow's policy)
+pub fn preview_debug<T: std::fmt::Debug + ?Sized>(value: &T) -> String {
+    LogPolicy::current().preview_debug(value)
+}
@@ -0,0 +1,240 @@
+//! SQLite maintenance job
+//! 
+//! Long-lived project databases (especially simpletable.db) accumulate free
+//! pages, stale planner statistics and large WAL files. Inside a configurable
+//! UTC window this job walks every project database and runs incremental
+//! vacuum, ANALYZE and a truncating WAL checkpoint, recording how much disk
+//! space each pass reclaimed.
+//! 
+//! Databases created before incremental auto-vacuum was enabled are converted
+//! once with a full VACUUM on their first pass.
+
+use crate::{config::MaintenanceConfig, project::ProjectDatabaseManager};
+use anyhow::Result;
+use chrono::NaiveTime;
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// How often the job checks whether a pass is due
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// `PRAGMA auto_vacuum` value for incremental mode
+const AUTO_VACUUM_INCREMENTAL: i64 = 2;
+
+/// Daily UTC time window ("02:00-04:00"); may wrap past midnight
+#[derive(Debug, Clone, Copy)]
+pub struct MaintenanceWindow {
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl MaintenanceWindow {
+    pub fn parse(window: &str) -> Result<Self> {
+        let (start, end) = window.split_once('-')
+            .ok_or_else(|| anyhow::anyhow!("Maintenance window must look like \"02:00-04:00\", got '{}'", window))?;
+        let time = |value: &str| NaiveTime::parse_from_str(value.trim(), "%H:%M")
+            .map_err(|e| anyhow::anyhow!("Invalid maintenance window time '{}': {}", value.trim(), e));
+        Ok(Self { start: time(start)?, end: time(end)? })
+    }
+
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// Result of maintaining one database file
+#[derive(Debug, Clone, Serialize)]
+pub struct DatabaseMaintenanceReport {
+    pub project: String,
+    pub database: String,
+    /// Database + WAL size before and after the pass
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+    pub bytes_reclaimed: u64,
+    /// Free pages left after the incremental vacuum
+    pub freelist_pages: i64,
+    /// Whether the database was converted to incremental auto-vacuum (full VACUUM)
+    pub converted: bool,
+    pub duration_ms: u64,
+}
+
+/// Cumulative maintenance metrics
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MaintenanceStats {
+    pub runs_total: u64,
+    pub databases_maintained_total: u64,
+    pub failures_total: u64,
+    pub bytes_reclaimed_total: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_run_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_run_duration_ms: Option<u64>,
+    /// "{project}/{database}" -> report of its latest pass
+    pub databases: BTreeMap<String, DatabaseMaintenanceReport>,
+}
+
+/// Background job maintaining every project database
+pub struct DatabaseMaintenance {
+    project_db_manager: Arc<ProjectDatabaseManager>,
+    config: MaintenanceConfig,
+    stats: Mutex<MaintenanceStats>,
+    last_run: Mutex<Option<Instant>>,
+}
+
+impl DatabaseMaintenance {
+    pub fn new(project_db_manager: Arc<ProjectDatabaseManager>, config: MaintenanceConfig) -> Self {
+        Self {
+            project_db_manager,
+            config,
+            stats: Mutex::new(MaintenanceStats::default()),
+            last_run: Mutex::new(None),
+        }
+    }
+
+    /// Run a pass every `db_interval_secs`, only inside `db_window` if one is set
+    pub fn start(self: Arc<Self>) {
+        if self.config.db_interval_secs == 0 {
+            tracing::info!("🧽 Database maintenance disabled");
+            return;
+        }
+        let window = match self.config.db_window.as_deref().map(MaintenanceWindow::parse).transpose() {
+            Ok(window) => window,
+            Err(e) => {
+                tracing::error!("❌ Database maintenance disabled: {}", e);
+                return;
+            }
+        };
+        let interval = Duration::from_secs(self.config.db_interval_secs);
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+            loop {
+                ticker.tick().await;
+                if window.is_some_and(|window| !window.contains(chrono::Utc::now().time())) {
+                    continue;
+                }
+                let due = self.last_run.lock().unwrap().is_none_or(|last| last.elapsed() >= interval);
+                if due {
+                    self.run().await;
+                }
+            }
+        });
+    }
+
+    /// Maintain every known project database once
+    pub async fn run(&self) -> Vec<DatabaseMaintenanceReport> {
+        let started = Instant::now();
+        *self.last_run.lock().unwrap() = Some(started);
+        tracing::info!("🧽 Starting database maintenance pass");
+
+        let mut reports = Vec::new();
+        let mut failures = 0;
+        for project in self.project_db_manager.known_projects().await {
+            match self.run_project(&project).await {
+                Ok(mut project_reports) => reports.append(&mut project_reports),
+                Err(e) => {
+                    failures += 1;
+                    tracing::warn!("⚠️ Database maintenance failed for project {}: {}", project, e);
+                }
+            }
+        }
+
+        let reclaimed: u64 = reports.iter().map(|report| report.bytes_reclaimed).sum();
+        {
+            let mut stats = self.stats.lock().unwrap();
+            stats.runs_total += 1;
+            stats.databases_maintained_total += reports.len() as u64;
+            stats.failures_total += failures;
+            stats.bytes_reclaimed_total += reclaimed;
+            stats.last_run_at = Some(chrono::Utc::now().to_rfc3339());
+            stats.last_run_duration_ms = Some(started.elapsed().as_millis() as u64);
+            for report in &reports {
+                stats.databases.insert(format!("{}/{}", report.project, report.database), report.clone());
+            }
+        }
+
+        tracing::info!("🧽 Database maintenance finished: {} databases, {} bytes reclaimed in {:?}",
+            reports.len(), reclaimed, started.elapsed());
+        reports
+    }
+
+    /// Maintain a project's project.db and, if present, simpletable.db
+    async fn run_project(&self, project_slug: &str) -> Result<Vec<DatabaseMaintenanceReport>> {
+        let project_dir = self.project_db_manager.project_dir(project_slug)?;
+        let mut reports = Vec::new();
+
+        let pool = self.project_db_manager.get_project_pool(project_slug).await?;
+        reports.push(maintain_database(project_slug, "project.db", &pool, project_dir.join("project.db")).await?);
+
+        if let Some(pool) = self.project_db_manager.existing_simpletable_pool(project_slug).await? {
+            reports.push(maintain_database(project_slug, "simpletable.db", &pool, project_dir.join("simpletable.db")).await?);
+        }
+
+        Ok(reports)
+    }
+
+    /// Snapshot of the cumulative metrics
+    pub fn stats(&self) -> MaintenanceStats {
+        self.stats.lock().unwrap().clone()
+    }
+}
+
+/// Incremental vacuum, ANALYZE and WAL checkpoint on one database
+async fn maintain_database(project: &str, database: &str, pool: &SqlitePool, path: PathBuf) -> Result<DatabaseMaintenanceReport> {
+    let started = Instant::now();
+    let wal_path = PathBuf::from(format!("{}-wal", path.display()));
+    let bytes_before = database_bytes(pool).await? + file_bytes(&wal_path);
+
+    // VACUUM and the pending auto_vacuum mode must share one connection
+    let mut conn = pool.acquire().await?;
+    let auto_vacuum: i64 = sqlx::query("PRAGMA auto_vacuum").fetch_one(&mut *conn).await?.get(0);
+    let converted = auto_vacuum != AUTO_VACUUM_INCREMENTAL;
+    if converted {
+        sqlx::query("PRAGMA auto_vacuum = INCREMENTAL").execute(&mut *conn).await?;
+        sqlx::query("VACUUM").execute(&mut *conn).await?;
+    } else {
+        sqlx::query("PRAGMA incremental_vacuum").execute(&mut *conn).await?;
+    }
+    sqlx::query("ANALYZE").execute(&mut *conn).await?;
+    sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)").execute(&mut *conn).await?;
+    let freelist_pages: i64 = sqlx::query("PRAGMA freelist_count").fetch_one(&mut *conn).await?.get(0);
+    drop(conn);
+
+    let bytes_after = database_bytes(pool).await? + file_bytes(&wal_path);
+    let report = DatabaseMaintenanceReport {
+        project: project.to_string(),
+        database: database.to_string(),
+        bytes_before,
+        bytes_after,
+        bytes_reclaimed: bytes_before.saturating_sub(bytes_after),
+        freelist_pages,
+        converted,
+        duration_ms: started.elapsed().as_millis() as u64,
+    };
+    tracing::debug!("🧽 Maintained {}/{}: {} -> {} bytes{}", project, database, bytes_before, bytes_after,
+        if converted { " (converted to incremental auto-vacuum)" } else { "" });
+    Ok(report)
+}
+
+/// Size of the main database file (page_count * page_size)
+async fn database_bytes(pool: &SqlitePool) -> Result<u64> {
+    let row = sqlx::query("SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()")
+        .fetch_one(pool)
+        .await?;
+    Ok(row.get::<i64, _>(0).max(0) as u64)
+}
+
+/// Size of a file on disk (0 if missing, e.g. in-memory databases)
+fn file_bytes(path: &Path) -> u64 {
+    std::fs::metadata(path).map(|metadata| metadata.len()).unwrap_or(0)
+}
@@ -0,0 +1,71 @@
+//! Server-wide maintenance mode
+//!
+//! While maintenance mode is on, new trigger ingress is rejected: webhook, form,
+//! chat and WebSocket requests get 503 with the operator's JSON body and a
+//! Retry-After header, the execution queue admits nothing, and schedules, MQTT,
+//! OPC UA, serial, BLE and event triggers drop their runs. Executions already in
+//! flight keep running (including their sub-workflows) so the instance drains,
+//! and the management API keeps working. The mode lives in memory only: a
+//! restart always comes up serving traffic.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::sync::RwLock;
+
+/// Active maintenance window, as reported by the admin API
+#[derive(Debug, Clone, Serialize)]
+pub struct MaintenanceNotice {
+    /// When maintenance mode was switched on
+    pub since: DateTime<Utc>,
+    /// Response body of rejected requests
+    pub body: Value,
+    /// Retry-After hint (seconds) of rejected requests
+    pub retry_after_secs: u64,
+}
+
+/// Maintenance mode toggle shared by the API and the trigger services
+#[derive(Debug)]
+pub struct MaintenanceMode {
+    notice: RwLock<Option<MaintenanceNotice>>,
+    /// Retry-After used when the toggle doesn't set one
+    default_retry_after_secs: u64,
+}
+
+impl MaintenanceMode {
+    /// Create the toggle (off)
+    pub fn new(default_retry_after_secs: u64) -> Self {
+        Self { notice: RwLock::new(None), default_retry_after_secs }
+    }
+
+    /// Switch maintenance mode on (or replace the body / Retry-After of the current window)
+    pub fn enable(&self, body: Option<Value>, retry_after_secs: Option<u64>) -> MaintenanceNotice {
+        let mut notice = self.notice.write().unwrap();
+        let since = notice.as_ref().map_or_else(Utc::now, |current| current.since);
+        let enabled = MaintenanceNotice {
+            since,
+            body: body.unwrap_or_else(|| json!({
+                "error": "maintenance",
+                "message": "Server is under maintenance, try again later",
+            })),
+            retry_after_secs: retry_after_secs.unwrap_or(self.default_retry_after_secs),
+        };
+        *notice = Some(enabled.clone());
+        enabled
+    }
+
+    /// Switch maintenance mode off; true when it was on
+    pub fn disable(&self) -> bool {
+        self.notice.write().unwrap().take().is_some()
+    }
+
+    /// The current maintenance window, while maintenance mode is on
+    pub fn current(&self) -> Option<MaintenanceNotice> {
+        self.notice.read().unwrap().clone()
+    }
+
+    /// Whether new trigger ingress is rejected
+    pub fn is_enabled(&self) -> bool {
+        self.notice.read().unwrap().is_some()
+    }
+}
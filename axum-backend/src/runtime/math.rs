@@ -0,0 +1,566 @@
+//! Math node expressions
+//! 
+//! A small numeric expression language, parsed once per execution and
+//! evaluated per input item without the Lua sandbox:
+//! 
+//! { "variables": ["temp_f", "price"],
+//!   "expressions": { "temp_c": "round(convert(temp_f, 'F', 'C'), 1)",
+//!                    "gross": "round(price * 1.19, 2)", "alert": "temp_c > 30" } }
+//! 
+//! Input pins fill `variables` in order. Expressions may use other outputs
+//! (evaluated in dependency order), the operators + - * / % ^, comparisons,
+//! && || !, the constants pi and e, and the functions listed in FUNCTIONS.
+//! Comparisons and logic give 1 or 0. `convert(value, 'from', 'to')` converts
+//! between units of the same kind (see UNITS). Every result must be finite.
+
+use anyhow::Result;
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+
+/// Longest accepted expression
+const MAX_EXPRESSION_LEN: usize = 1000;
+
+/// Deepest accepted nesting (parentheses, unary operators, calls)
+const MAX_DEPTH: usize = 64;
+
+/// Functions: name, minimum and maximum argument count
+const FUNCTIONS: &[(&str, usize, usize)] = &[
+    ("abs", 1, 1), ("sign", 1, 1), ("sqrt", 1, 1), ("cbrt", 1, 1), ("exp", 1, 1),
+    ("ln", 1, 1), ("log10", 1, 1), ("log", 1, 2), ("pow", 2, 2),
+    ("floor", 1, 1), ("ceil", 1, 1), ("trunc", 1, 1), ("round", 1, 2),
+    ("min", 1, 16), ("max", 1, 16), ("clamp", 3, 3), ("hypot", 2, 2),
+    ("sin", 1, 1), ("cos", 1, 1), ("tan", 1, 1), ("asin", 1, 1), ("acos", 1, 1), ("atan", 1, 1), ("atan2", 2, 2),
+    ("deg", 1, 1), ("rad", 1, 1), ("if", 3, 3), ("convert", 3, 3),
+];
+
+/// Units: name, kind, factor to the kind's base unit (temperatures are converted separately)
+const UNITS: &[(&str, &str, f64)] = &[
+    // Length (m)
+    ("mm", "length", 0.001), ("cm", "length", 0.01), ("m", "length", 1.0), ("km", "length", 1000.0),
+    ("in", "length", 0.0254), ("ft", "length", 0.3048), ("yd", "length", 0.9144),
+    ("mi", "length", 1609.344), ("nmi", "length", 1852.0),
+    // Mass (kg)
+    ("mg", "mass", 1e-6), ("g", "mass", 0.001), ("kg", "mass", 1.0), ("t", "mass", 1000.0),
+    ("oz", "mass", 0.028349523125), ("lb", "mass", 0.45359237),
+    // Time (s)
+    ("ms", "time", 0.001), ("s", "time", 1.0), ("min", "time", 60.0), ("h", "time", 3600.0),
+    ("d", "time", 86400.0), ("wk", "time", 604800.0),
+    // Volume (l)
+    ("ml", "volume", 0.001), ("l", "volume", 1.0), ("m3", "volume", 1000.0),
+    ("gal", "volume", 3.785411784), ("qt", "volume", 0.946352946), ("pt", "volume", 0.473176473),
+    ("cup", "volume", 0.2365882365), ("floz", "volume", 0.0295735295625),
+    // Area (m2)
+    ("cm2", "area", 1e-4), ("m2", "area", 1.0), ("ha", "area", 1e4), ("km2", "area", 1e6),
+    ("ft2", "area", 0.09290304), ("acre", "area", 4046.8564224),
+    // Speed (m/s)
+    ("m/s", "speed", 1.0), ("km/h", "speed", 1.0 / 3.6), ("mph", "speed", 0.44704),
+    ("kn", "speed", 1852.0 / 3600.0), ("ft/s", "speed", 0.3048),
+    // Pressure (Pa)
+    ("Pa", "pressure", 1.0), ("hPa", "pressure", 100.0), ("kPa", "pressure", 1000.0),
+    ("mbar", "pressure", 100.0), ("bar", "pressure", 1e5), ("psi", "pressure", 6894.757293168),
+    ("atm", "pressure", 101325.0), ("mmHg", "pressure", 133.322387415),
+    // Energy (J)
+    ("J", "energy", 1.0), ("kJ", "energy", 1000.0), ("Wh", "energy", 3600.0), ("kWh", "energy", 3.6e6),
+    ("cal", "energy", 4.184), ("kcal", "energy", 4184.0), ("BTU", "energy", 1055.05585262),
+    // Power (W)
+    ("W", "power", 1.0), ("kW", "power", 1000.0), ("MW", "power", 1e6), ("hp", "power", 745.6998715822702),
+    // Data (B)
+    ("bit", "data", 0.125), ("B", "data", 1.0), ("KB", "data", 1e3), ("MB", "data", 1e6), ("GB", "data", 1e9),
+    ("TB", "data", 1e12), ("KiB", "data", 1024.0), ("MiB", "data", 1048576.0), ("GiB", "data", 1073741824.0),
+    ("TiB", "data", 1099511627776.0),
+    // Temperature (offsets handled in convert())
+    ("C", "temperature", 1.0), ("F", "temperature", 1.0), ("K", "temperature", 1.0),
+];
+
+/// Parsed expression
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(f64),
+    Variable(String),
+    Unary(char, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+    /// convert(value, 'from', 'to') with the units resolved at parse time
+    Convert(Box<Expr>, &'static str, &'static str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BinaryOp {
+    Add, Sub, Mul, Div, Rem, Pow,
+    Lt, Le, Gt, Ge, Eq, Ne,
+    And, Or,
+}
+
+impl BinaryOp {
+    /// Binding power (left, right); ^ is right-associative
+    fn binding_power(self) -> (u8, u8) {
+        match self {
+            BinaryOp::Or => (1, 2),
+            BinaryOp::And => (3, 4),
+            BinaryOp::Eq | BinaryOp::Ne => (5, 6),
+            BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => (7, 8),
+            BinaryOp::Add | BinaryOp::Sub => (9, 10),
+            BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => (11, 12),
+            BinaryOp::Pow => (16, 15),
+        }
+    }
+}
+
+/// Binding power of unary - and ! (below ^ so -2^2 = -4)
+const UNARY_POWER: u8 = 13;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Str(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+    Comma,
+}
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>> {
+    const OPERATORS: &[&str] = &["<=", ">=", "==", "!=", "&&", "||", "+", "-", "*", "/", "%", "^", "<", ">", "!"];
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let c = bytes[pos] as char;
+        let start = pos;
+        if c.is_ascii_whitespace() {
+            pos += 1;
+            continue;
+        }
+        let token = if c.is_ascii_digit() || (c == '.' && bytes.get(pos + 1).is_some_and(u8::is_ascii_digit)) {
+            while pos < bytes.len() && (bytes[pos].is_ascii_digit() || bytes[pos] == b'.') {
+                pos += 1;
+            }
+            // Exponent: 1e3, 2.5E-4
+            if pos < bytes.len() && (bytes[pos] == b'e' || bytes[pos] == b'E') {
+                let mut end = pos + 1;
+                if end < bytes.len() && (bytes[end] == b'+' || bytes[end] == b'-') {
+                    end += 1;
+                }
+                if end < bytes.len() && bytes[end].is_ascii_digit() {
+                    pos = end;
+                    while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+                        pos += 1;
+                    }
+                }
+            }
+            let text = &source[start..pos];
+            Token::Number(text.parse().map_err(|_| anyhow::anyhow!("Invalid number '{}' at {}", text, start))?)
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            while pos < bytes.len() && (bytes[pos].is_ascii_alphanumeric() || bytes[pos] == b'_') {
+                pos += 1;
+            }
+            Token::Ident(source[start..pos].to_string())
+        } else if c == '\'' || c == '"' {
+            let end = source[pos + 1..].find(c)
+                .ok_or_else(|| anyhow::anyhow!("Unterminated string at {}", start))?;
+            pos += end + 2;
+            Token::Str(source[start + 1..pos - 1].to_string())
+        } else if c == '(' {
+            pos += 1;
+            Token::LParen
+        } else if c == ')' {
+            pos += 1;
+            Token::RParen
+        } else if c == ',' {
+            pos += 1;
+            Token::Comma
+        } else {
+            let op = OPERATORS.iter().find(|op| source[pos..].starts_with(**op))
+                .ok_or_else(|| anyhow::anyhow!("Unexpected '{}' at {}", source[pos..].chars().next().unwrap_or(c), start))?;
+            pos += op.len();
+            Token::Op(op)
+        };
+        tokens.push((token, start));
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    end: usize,
+    /// Names that may be referenced (variables and outputs)
+    names: &'a HashSet<String>,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn offset(&self) -> usize {
+        self.tokens.get(self.pos).map(|(_, offset)| *offset).unwrap_or(self.end)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|(token, _)| token.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token, what: &str) -> Result<()> {
+        let offset = self.offset();
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            _ => Err(anyhow::anyhow!("Expected {} at {}", what, offset)),
+        }
+    }
+
+    fn expression(&mut self, min_power: u8) -> Result<Expr> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(anyhow::anyhow!("Expression is nested too deeply"));
+        }
+        let mut left = self.operand()?;
+        while let Some(Token::Op(op)) = self.peek() {
+            let op = match *op {
+                "+" => BinaryOp::Add, "-" => BinaryOp::Sub, "*" => BinaryOp::Mul, "/" => BinaryOp::Div,
+                "%" => BinaryOp::Rem, "^" => BinaryOp::Pow,
+                "<" => BinaryOp::Lt, "<=" => BinaryOp::Le, ">" => BinaryOp::Gt, ">=" => BinaryOp::Ge,
+                "==" => BinaryOp::Eq, "!=" => BinaryOp::Ne, "&&" => BinaryOp::And, "||" => BinaryOp::Or,
+                other => return Err(anyhow::anyhow!("Unexpected '{}' at {}", other, self.offset())),
+            };
+            let (left_power, right_power) = op.binding_power();
+            if left_power < min_power {
+                break;
+            }
+            self.pos += 1;
+            let right = self.expression(right_power)?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        self.depth -= 1;
+        Ok(left)
+    }
+
+    fn operand(&mut self) -> Result<Expr> {
+        let offset = self.offset();
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Expr::Number(n)),
+            Some(Token::Op("-")) => Ok(Expr::Unary('-', Box::new(self.expression(UNARY_POWER)?))),
+            Some(Token::Op("+")) => self.expression(UNARY_POWER),
+            Some(Token::Op("!")) => Ok(Expr::Unary('!', Box::new(self.expression(UNARY_POWER)?))),
+            Some(Token::LParen) => {
+                let inner = self.expression(0)?;
+                self.expect(Token::RParen, "')'")?;
+                Ok(inner)
+            }
+            Some(Token::Ident(name)) if self.peek() == Some(&Token::LParen) => self.call(name, offset),
+            Some(Token::Ident(name)) => match name.as_str() {
+                _ if self.names.contains(&name) => Ok(Expr::Variable(name)),
+                "pi" => Ok(Expr::Number(std::f64::consts::PI)),
+                "e" => Ok(Expr::Number(std::f64::consts::E)),
+                "true" => Ok(Expr::Number(1.0)),
+                "false" => Ok(Expr::Number(0.0)),
+                _ => Err(anyhow::anyhow!("Unknown variable '{}' at {}", name, offset)),
+            },
+            Some(Token::Str(_)) => Err(anyhow::anyhow!("Strings are only allowed as convert() units (at {})", offset)),
+            _ => Err(anyhow::anyhow!("Expected a number, variable or '(' at {}", offset)),
+        }
+    }
+
+    fn call(&mut self, name: String, offset: usize) -> Result<Expr> {
+        let &(_, min_args, max_args) = FUNCTIONS.iter().find(|(function, _, _)| *function == name)
+            .ok_or_else(|| anyhow::anyhow!("Unknown function '{}' at {}", name, offset))?;
+        self.expect(Token::LParen, "'('")?;
+
+        if name == "convert" {
+            let value = self.expression(0)?;
+            let mut units = Vec::with_capacity(2);
+            for _ in 0..2 {
+                self.expect(Token::Comma, "','")?;
+                let unit_offset = self.offset();
+                match self.next() {
+                    Some(Token::Str(unit)) => units.push(find_unit(&unit)
+                        .ok_or_else(|| anyhow::anyhow!("Unknown unit '{}' at {}", unit, unit_offset))?),
+                    _ => return Err(anyhow::anyhow!("convert() units must be quoted strings (at {})", unit_offset)),
+                }
+            }
+            self.expect(Token::RParen, "')'")?;
+            let (from, to) = (units[0], units[1]);
+            if from.1 != to.1 {
+                return Err(anyhow::anyhow!("Can't convert {} ({}) to {} ({})", from.0, from.1, to.0, to.1));
+            }
+            return Ok(Expr::Convert(Box::new(value), from.0, to.0));
+        }
+
+        let mut args = Vec::new();
+        if self.peek() != Some(&Token::RParen) {
+            loop {
+                args.push(self.expression(0)?);
+                if self.peek() == Some(&Token::Comma) {
+                    self.pos += 1;
+                } else {
+                    break;
+                }
+            }
+        }
+        self.expect(Token::RParen, "')'")?;
+        if args.len() < min_args || args.len() > max_args {
+            return Err(anyhow::anyhow!("{}() takes {} argument(s), got {} (at {})", name,
+                if min_args == max_args { min_args.to_string() } else { format!("{}-{}", min_args, max_args) },
+                args.len(), offset));
+        }
+        Ok(Expr::Call(name, args))
+    }
+}
+
+fn find_unit(name: &str) -> Option<(&'static str, &'static str, f64)> {
+    let name = match name {
+        "celsius" | "degC" | "°C" => "C",
+        "fahrenheit" | "degF" | "°F" => "F",
+        "kelvin" => "K",
+        other => other,
+    };
+    UNITS.iter().find(|(unit, _, _)| *unit == name).copied()
+}
+
+fn convert(value: f64, from: &str, to: &str) -> f64 {
+    let (Some((_, kind, from_factor)), Some((_, _, to_factor))) = (find_unit(from), find_unit(to)) else {
+        return f64::NAN;
+    };
+    if kind != "temperature" {
+        return value * from_factor / to_factor;
+    }
+    let kelvin = match from {
+        "C" => value + 273.15,
+        "F" => (value - 32.0) * 5.0 / 9.0 + 273.15,
+        _ => value,
+    };
+    match to {
+        "C" => kelvin - 273.15,
+        "F" => (kelvin - 273.15) * 9.0 / 5.0 + 32.0,
+        _ => kelvin,
+    }
+}
+
+fn collect_variables(expr: &Expr, names: &mut HashSet<String>) {
+    match expr {
+        Expr::Number(_) => {}
+        Expr::Variable(name) => { names.insert(name.clone()); }
+        Expr::Unary(_, inner) | Expr::Convert(inner, _, _) => collect_variables(inner, names),
+        Expr::Binary(_, left, right) => {
+            collect_variables(left, names);
+            collect_variables(right, names);
+        }
+        Expr::Call(_, args) => args.iter().for_each(|arg| collect_variables(arg, names)),
+    }
+}
+
+fn evaluate(expr: &Expr, values: &HashMap<String, f64>) -> Result<f64> {
+    let truth = |value: f64| value != 0.0;
+    let flag = |value: bool| if value { 1.0 } else { 0.0 };
+    Ok(match expr {
+        Expr::Number(n) => *n,
+        Expr::Variable(name) => *values.get(name).ok_or_else(|| anyhow::anyhow!("'{}' has no value", name))?,
+        Expr::Unary('-', inner) => -evaluate(inner, values)?,
+        Expr::Unary(_, inner) => flag(!truth(evaluate(inner, values)?)),
+        Expr::Binary(BinaryOp::And, left, right) => flag(truth(evaluate(left, values)?) && truth(evaluate(right, values)?)),
+        Expr::Binary(BinaryOp::Or, left, right) => flag(truth(evaluate(left, values)?) || truth(evaluate(right, values)?)),
+        Expr::Binary(op, left, right) => {
+            let (a, b) = (evaluate(left, values)?, evaluate(right, values)?);
+            match op {
+                BinaryOp::Add => a + b,
+                BinaryOp::Sub => a - b,
+                BinaryOp::Mul => a * b,
+                BinaryOp::Div if b == 0.0 => return Err(anyhow::anyhow!("Division by zero")),
+                BinaryOp::Div => a / b,
+                BinaryOp::Rem if b == 0.0 => return Err(anyhow::anyhow!("Division by zero")),
+                BinaryOp::Rem => a % b,
+                BinaryOp::Pow => a.powf(b),
+                BinaryOp::Lt => flag(a < b),
+                BinaryOp::Le => flag(a <= b),
+                BinaryOp::Gt => flag(a > b),
+                BinaryOp::Ge => flag(a >= b),
+                BinaryOp::Eq => flag(a == b),
+                BinaryOp::Ne => flag(a != b),
+                BinaryOp::And | BinaryOp::Or => unreachable!("short-circuit operators are handled above"),
+            }
+        }
+        // Only the chosen branch is evaluated
+        Expr::Call(name, args) if name == "if" => {
+            if truth(evaluate(&args[0], values)?) { evaluate(&args[1], values)? } else { evaluate(&args[2], values)? }
+        }
+        Expr::Call(name, args) => {
+            let args = args.iter().map(|arg| evaluate(arg, values)).collect::<Result<Vec<f64>>>()?;
+            let x = args[0];
+            match name.as_str() {
+                "abs" => x.abs(),
+                "sign" => if x == 0.0 { 0.0 } else { x.signum() },
+                "sqrt" => x.sqrt(),
+                "cbrt" => x.cbrt(),
+                "exp" => x.exp(),
+                "ln" => x.ln(),
+                "log10" => x.log10(),
+                "log" => match args.get(1) { Some(base) => x.log(*base), None => x.ln() },
+                "pow" => x.powf(args[1]),
+                "floor" => x.floor(),
+                "ceil" => x.ceil(),
+                "trunc" => x.trunc(),
+                "round" => {
+                    let factor = 10f64.powi(args.get(1).copied().unwrap_or(0.0).clamp(-15.0, 15.0) as i32);
+                    (x * factor).round() / factor
+                }
+                "min" => args.iter().copied().fold(f64::INFINITY, f64::min),
+                "max" => args.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+                "clamp" if args[1] > args[2] => return Err(anyhow::anyhow!("clamp() bounds are reversed")),
+                "clamp" => x.clamp(args[1], args[2]),
+                "hypot" => x.hypot(args[1]),
+                "sin" => x.sin(),
+                "cos" => x.cos(),
+                "tan" => x.tan(),
+                "asin" => x.asin(),
+                "acos" => x.acos(),
+                "atan" => x.atan(),
+                "atan2" => x.atan2(args[1]),
+                "deg" => x.to_degrees(),
+                "rad" => x.to_radians(),
+                other => return Err(anyhow::anyhow!("Unknown function '{}'", other)),
+            }
+        }
+        Expr::Convert(inner, from, to) => convert(evaluate(inner, values)?, from, to),
+    })
+}
+
+/// Compiled Math node: outputs in dependency order
+#[derive(Debug, Clone)]
+pub struct MathProgram {
+    variables: Vec<String>,
+    outputs: Vec<(String, Expr)>,
+}
+
+impl MathProgram {
+    /// Parse the node's "variables" and "expressions" params
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        let variables: Vec<String> = match params.get("variables") {
+            None | Some(Value::Null) => Vec::new(),
+            Some(Value::Array(names)) => names.iter()
+                .map(|name| name.as_str().map(str::to_string).ok_or_else(|| anyhow::anyhow!("'variables' must be strings")))
+                .collect::<Result<_>>()?,
+            Some(_) => return Err(anyhow::anyhow!("'variables' must be an array of names")),
+        };
+        let expressions = match params.get("expressions") {
+            Some(Value::Object(expressions)) if !expressions.is_empty() => expressions,
+            _ => return Err(anyhow::anyhow!("'expressions' must be an object of output name -> expression")),
+        };
+
+        let valid_name = |name: &str| name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && !["pi", "e", "true", "false"].contains(&name);
+        let mut names = HashSet::new();
+        for name in variables.iter().chain(expressions.keys()) {
+            if !valid_name(name) {
+                return Err(anyhow::anyhow!("'{}' is not a valid variable name", name));
+            }
+            if !names.insert(name.clone()) {
+                return Err(anyhow::anyhow!("'{}' is defined twice", name));
+            }
+        }
+
+        let mut parsed = HashMap::new();
+        for (name, source) in expressions {
+            let source = source.as_str()
+                .ok_or_else(|| anyhow::anyhow!("Expression '{}' must be a string", name))?;
+            if source.len() > MAX_EXPRESSION_LEN {
+                return Err(anyhow::anyhow!("Expression '{}' is longer than {} characters", name, MAX_EXPRESSION_LEN));
+            }
+            let mut parser = Parser { tokens: tokenize(source)?, pos: 0, end: source.len(), names: &names, depth: 0 };
+            let expr = parser.expression(0)
+                .and_then(|expr| match parser.peek() {
+                    None => Ok(expr),
+                    Some(_) => Err(anyhow::anyhow!("Unexpected input at {}", parser.offset())),
+                })
+                .map_err(|e| anyhow::anyhow!("Expression '{}': {}", name, e))?;
+            parsed.insert(name.clone(), expr);
+        }
+
+        let outputs = order_outputs(expressions, parsed)?;
+        Ok(Self { variables, outputs })
+    }
+
+    pub fn variables(&self) -> &[String] {
+        &self.variables
+    }
+
+    /// Evaluate all outputs for one item's variable values
+    pub fn evaluate(&self, inputs: &[Value]) -> Result<Map<String, Value>> {
+        let mut values = HashMap::with_capacity(self.variables.len() + self.outputs.len());
+        for (name, value) in self.variables.iter().zip(inputs.iter().chain(std::iter::repeat(&Value::Null))) {
+            let number = match value {
+                Value::Number(n) => n.as_f64(),
+                Value::String(s) => s.trim().parse::<f64>().ok(),
+                Value::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+                _ => None,
+            };
+            match number {
+                Some(number) => { values.insert(name.clone(), number); }
+                None => return Err(anyhow::anyhow!("Variable '{}' is not a number: {}", name, value)),
+            }
+        }
+
+        let mut results = Map::new();
+        for (name, expr) in &self.outputs {
+            let result = evaluate(expr, &values).map_err(|e| anyhow::anyhow!("'{}': {}", name, e))?;
+            if !result.is_finite() {
+                return Err(anyhow::anyhow!("'{}' is not a finite number", name));
+            }
+            values.insert(name.clone(), result);
+            results.insert(name.clone(), number_value(result));
+        }
+        Ok(results)
+    }
+}
+
+/// Whole numbers as JSON integers, everything else as floats
+fn number_value(value: f64) -> Value {
+    if value.fract() == 0.0 && value.abs() < 9.0e15 {
+        Value::from(value as i64)
+    } else {
+        Value::from(value)
+    }
+}
+
+/// Topological order of the outputs (outputs may reference each other)
+fn order_outputs(expressions: &Map<String, Value>, mut parsed: HashMap<String, Expr>) -> Result<Vec<(String, Expr)>> {
+    let dependencies: HashMap<String, Vec<String>> = parsed.iter().map(|(name, expr)| {
+        let mut used = HashSet::new();
+        collect_variables(expr, &mut used);
+        (name.clone(), used.into_iter().filter(|used| expressions.contains_key(used)).collect())
+    }).collect();
+
+    fn visit(
+        name: &str,
+        dependencies: &HashMap<String, Vec<String>>,
+        visiting: &mut Vec<String>,
+        done: &mut Vec<String>,
+    ) -> Result<()> {
+        if done.iter().any(|d| d == name) {
+            return Ok(());
+        }
+        if visiting.iter().any(|v| v == name) {
+            return Err(anyhow::anyhow!("Expressions reference each other in a cycle: {} -> {}", visiting.join(" -> "), name));
+        }
+        visiting.push(name.to_string());
+        for dependency in &dependencies[name] {
+            visit(dependency, dependencies, visiting, done)?;
+        }
+        visiting.pop();
+        done.push(name.to_string());
+        Ok(())
+    }
+
+    let mut done = Vec::new();
+    for name in expressions.keys() {
+        visit(name, &dependencies, &mut Vec::new(), &mut done)?;
+    }
+    Ok(done.into_iter().map(|name| {
+        let expr = parsed.remove(&name).expect("every output was parsed");
+        (name, expr)
+    }).collect())
+}
@@ -0,0 +1,207 @@
+//! Memory node conversation history
+//! 
+//! Keeps per-session chat history in the project DB so LLM-based workflows
+//! keep context across webhook calls:
+//! 
+//! { "operation": "load", "session_id": "$json.user", "window": 20, "max_chars": 8000 }
+//! { "operation": "append", "session_id": "$json.user", "roles": ["user", "assistant"] }
+//!   with inputs ["$json.last_message", "$json.reply"]
+//! { "operation": "summarize", "session_id": "$json.user", "keep_last": 4 } with inputs ["$json.summary"]
+//! 
+//! Every operation passes the data array through and puts the session's
+//! current window in metadata "memory" (read with `$memory.*` pins):
+//! { "session_id", "summary", "messages": [{ "role", "content", "created_at" }],
+//!   "transcript": "user: ...\nassistant: ...", "stored": 12, "needs_summary": false }
+//! 
+//! Windowing: "window" caps loaded messages, "max_chars" drops the oldest ones
+//! until the window's text fits, "max_messages" prunes stored history on append.
+//! Summarization: "summarize_after" flags sessions with more stored messages
+//! (needs_summary), and "summarize" replaces all but the last "keep_last"
+//! messages with the summary text produced upstream (e.g. by an LLM call).
+
+use crate::project::memory::{MemoryMessage, MemorySession};
+use anyhow::Result;
+use serde_json::{json, Value};
+
+fn default_window() -> usize {
+    20
+}
+
+fn default_keep_last() -> usize {
+    4
+}
+
+/// What a Memory node does with its session
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MemoryOperation {
+    Load,
+    Append,
+    Summarize,
+    Clear,
+}
+
+impl MemoryOperation {
+    fn parse(name: &str) -> Result<Self> {
+        match name {
+            "load" => Ok(MemoryOperation::Load),
+            "append" => Ok(MemoryOperation::Append),
+            "summarize" => Ok(MemoryOperation::Summarize),
+            "clear" => Ok(MemoryOperation::Clear),
+            other => Err(anyhow::anyhow!("unsupported operation '{}' (expected load, append, summarize or clear)", other)),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MemoryOperation::Load => "load",
+            MemoryOperation::Append => "append",
+            MemoryOperation::Summarize => "summarize",
+            MemoryOperation::Clear => "clear",
+        }
+    }
+}
+
+/// Memory node configuration
+#[derive(Debug, Clone)]
+pub struct MemoryParams {
+    pub operation: MemoryOperation,
+    /// Session ID, literal or pin expression (e.g. "$json.user")
+    pub session_id: String,
+    /// Most recent messages loaded into metadata "memory"
+    pub window: usize,
+    /// Text budget of the loaded window (oldest messages dropped first)
+    pub max_chars: Option<usize>,
+    /// Stored messages kept per session after an append
+    pub max_messages: Option<usize>,
+    /// Stored message count above which "needs_summary" is set
+    pub summarize_after: Option<usize>,
+    /// Messages left uncompacted by "summarize"
+    pub keep_last: usize,
+    /// Roles of appended text inputs, by input position (the last one repeats)
+    pub roles: Vec<String>,
+}
+
+impl MemoryParams {
+    /// Parse node params
+    /// 
+    /// Expected params: { "operation": "load|append|summarize|clear", "session_id": "$json.user", "window": 20,
+    ///                    "max_chars": 8000, "max_messages": 200, "summarize_after": 40, "keep_last": 4,
+    ///                    "role": "user" | "roles": ["user", "assistant"] }
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        let operation = MemoryOperation::parse(params.get("operation").and_then(|o| o.as_str()).unwrap_or("load"))?;
+        let session_id = params.get("session_id")
+            .and_then(|s| s.as_str())
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("missing 'session_id' parameter (a literal or a pin such as \"$json.user\")"))?
+            .to_string();
+        let count = |name: &str| -> Result<Option<usize>> {
+            match params.get(name) {
+                None | Some(Value::Null) => Ok(None),
+                Some(value) => value.as_u64()
+                    .map(|n| Some(n as usize))
+                    .ok_or_else(|| anyhow::anyhow!("'{}' must be a non-negative integer", name)),
+            }
+        };
+        let roles = match (params.get("roles"), params.get("role")) {
+            (Some(Value::Array(roles)), _) if !roles.is_empty() => roles.iter()
+                .map(|role| role.as_str().map(str::to_string).ok_or_else(|| anyhow::anyhow!("'roles' must be an array of strings")))
+                .collect::<Result<Vec<_>>>()?,
+            (Some(_), _) => return Err(anyhow::anyhow!("'roles' must be a non-empty array of strings")),
+            (None, Some(Value::String(role))) => vec![role.clone()],
+            (None, Some(_)) => return Err(anyhow::anyhow!("'role' must be a string")),
+            (None, None) => vec!["user".to_string()],
+        };
+
+        let window = count("window")?.unwrap_or_else(default_window);
+        if window == 0 {
+            return Err(anyhow::anyhow!("'window' must be at least 1"));
+        }
+
+        Ok(Self {
+            operation,
+            session_id,
+            window,
+            max_chars: count("max_chars")?,
+            max_messages: count("max_messages")?,
+            summarize_after: count("summarize_after")?,
+            keep_last: count("keep_last")?.unwrap_or_else(default_keep_last),
+            roles,
+        })
+    }
+
+    /// Messages to append from the evaluated input pins
+    /// 
+    /// Text inputs get the role of their position; { "role", "content" } objects
+    /// keep their own, and arrays append every element. Null inputs are skipped.
+    pub fn messages_from_inputs(&self, inputs: &[Value]) -> Result<Vec<MemoryMessage>> {
+        let mut messages = Vec::new();
+        for (index, input) in inputs.iter().enumerate() {
+            let role = self.roles.get(index).or(self.roles.last()).map(String::as_str).unwrap_or("user");
+            push_messages(&mut messages, input, role)?;
+        }
+        Ok(messages)
+    }
+
+    /// Metadata "memory" for a loaded session, after applying the text budget
+    pub fn memory_value(&self, session_id: &str, mut session: MemorySession) -> Value {
+        if let Some(max_chars) = self.max_chars {
+            let mut total: usize = session.messages.iter().map(|m| content_text(&m.content).chars().count()).sum();
+            // The newest message is always kept
+            while total > max_chars && session.messages.len() > 1 {
+                let dropped = session.messages.remove(0);
+                total -= content_text(&dropped.content).chars().count();
+            }
+        }
+
+        let mut transcript: Vec<String> = Vec::with_capacity(session.messages.len() + 1);
+        if let Some(summary) = &session.summary {
+            transcript.push(format!("summary: {}", summary));
+        }
+        transcript.extend(session.messages.iter().map(|m| format!("{}: {}", m.role, content_text(&m.content))));
+
+        json!({
+            "session_id": session_id,
+            "summary": session.summary,
+            "messages": session.messages,
+            "transcript": transcript.join("\n"),
+            "stored": session.stored,
+            "needs_summary": self.summarize_after.is_some_and(|limit| session.stored as usize > limit),
+        })
+    }
+}
+
+fn push_messages(messages: &mut Vec<MemoryMessage>, input: &Value, role: &str) -> Result<()> {
+    match input {
+        Value::Null => {}
+        Value::Array(items) => {
+            for item in items {
+                push_messages(messages, item, role)?;
+            }
+        }
+        Value::Object(message) if message.contains_key("content") => {
+            let role = message.get("role").and_then(|r| r.as_str()).unwrap_or(role);
+            messages.push(MemoryMessage {
+                role: role.to_string(),
+                content: message["content"].clone(),
+                created_at: None,
+            });
+        }
+        Value::Object(_) => return Err(anyhow::anyhow!("message objects need a 'content' field")),
+        Value::String(text) => messages.push(MemoryMessage { role: role.to_string(), content: json!(text), created_at: None }),
+        other => messages.push(MemoryMessage { role: role.to_string(), content: json!(other.to_string()), created_at: None }),
+    }
+    Ok(())
+}
+
+/// Plain text of message content (text parts of structured content are joined)
+pub fn content_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts.iter()
+            .filter_map(|part| part.get("text").and_then(|t| t.as_str()).or_else(|| part.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
@@ -1,11 +1,11 @@
-/// Runtime Execution Engine
-/// 
-/// This module provides the petgraph-based DAG execution engine for workflows.
-/// It handles:
-/// - Converting workflows to petgraph DAGs
-/// - Topological execution of nodes
-/// - Async task orchestration with tokio
-/// - Data flow between connected nodes
+//! Runtime Execution Engine
+//! 
+//! This module provides the petgraph-based DAG execution engine for workflows.
+//! It handles:
+//! - Converting workflows to petgraph DAGs
+//! - Topological execution of nodes
+//! - Async task orchestration with tokio
+//! - Data flow between connected nodes
 
 // Core execution engine using petgraph for DAG processing
 pub mod engine;
@@ -13,10 +13,166 @@ pub mod engine;
 // Individual node execution handlers
 pub mod executor;
 
-// Background cron scheduler service for CronTrigger nodes
+// Run result callbacks (on_complete / on_failure) of top-level runs
+pub mod callbacks;
+
+// Background cron scheduler service for CronTrigger and PollingTrigger nodes
 pub mod scheduler;
 
+// Spread, jitter and dispatch latency of scheduled runs
+pub mod schedule_dispatch;
+
+// Business days, holidays and blackout windows of scheduled triggers
+pub mod schedule_calendar;
+
+// PollingTrigger fetches and persisted cursors
+pub mod polling;
+
+// Stream aggregation (sample, average, threshold) for MQTT / WebSocket triggers
+pub mod stream_aggregate;
+
+// Background MQTT subscriber service for MQTTTrigger nodes
+pub mod mqtt;
+
+// OPC UA client (binary protocol, security mode None) for OpcUa nodes
+pub mod opcua;
+
+// Background OPC UA subscription service for OpcUaTrigger nodes
+pub mod opcua_trigger;
+
+// Serial port framing and I/O for Serial / SerialTrigger nodes (cargo feature "serial")
+pub mod serial;
+
+// Background serial port reader service for SerialTrigger nodes
+pub mod serial_trigger;
+
+// Store-and-forward outbox flusher for nodes sending to remote services
+pub mod outbox;
+
+// Bluetooth Low Energy trigger service for BLETrigger nodes (cargo feature "ble")
+pub mod ble;
+
+// Device state registry (digital twins) for MQTT / WebSocket devices and $device pins
+pub mod devices;
+
+// In-process event bus for EmitEvent / EventTrigger nodes
+pub mod events;
+
+// Bounded execution queue with admission control (load shedding)
+pub mod queue;
+
+// Weighted round-robin of execution slots across projects
+pub mod fair_share;
+
+// Per-run memory and CPU budgets (ResourceExceeded)
+pub mod budget;
+
+// Bounded payload previews for log lines
+pub mod logging;
+
+// Disk spilling for oversized intermediate node outputs
+pub mod spill;
+
+// Per-node and per-branch outcomes of a run
+pub mod trace;
+
+// Routing of node outputs along the edges of a run (forks, joins, ports)
+pub mod branches;
+
+// Cached sandbox for safe Lua pin expressions
+pub mod safe_lua;
+
+// Isolated sandbox for workflow test cases
+pub mod test_runner;
+
+// Step-through debugger sessions
+pub mod debugger;
+
+// In-memory node and workflow timing statistics
+pub mod profiler;
+
+// Background monitor for per-project failure alert rules
+pub mod alerts;
+
+// Background janitor applying per-project retention settings
+pub mod janitor;
+
+// HMAC / AWS SigV4 signing of HTTPClient requests
+pub mod request_signing;
+
+// StripeAPI node requests (form encoding, errors)
+pub mod stripe_api;
+
+// GitHubAPI node requests (issues, comments, commit statuses)
+pub mod github_api;
+
+// TwilioSend node requests (SMS / WhatsApp, delivery status)
+pub mod twilio;
+
+// PushNotification node delivery (FCM, Web Push with VAPID)
+pub mod push;
+
+// CalendarEvent node iCalendar generation and parsing
+pub mod calendar;
+
+// GeoIP node lookups (per-project MaxMind databases, cached readers)
+pub mod geoip;
+
+// Math node expression parser, evaluator and unit conversion
+pub mod math;
+
+// CurrencyConvert node exchange rate providers and per-project rate cache
+pub mod currency;
+
+// ParquetWrite node schema inference and Arrow/Parquet encoding
+pub mod parquet_write;
+
+// NdjsonParse line reader (batched streaming) and NdjsonWrite serialization
+pub mod ndjson;
+
+// Excel node workbook reading (calamine) and writing (rust_xlsxwriter)
+pub mod excel;
+
+// Anonymize node strategies (hash, mask, redact, fake, bucket) and k-anonymity
+pub mod anonymize;
+
+// Diff node item keys, fingerprints and field-level change detection
+pub mod diff;
+
+// Memory node session windowing, appended messages and $memory values
+pub mod memory;
+
+// Window node count / time window parameters, closing and per-node locks
+pub mod window;
+
+// Modbus node TCP client (MBAP framing) and register maps
+pub mod modbus;
+
+// Server-side cursors streaming PGQuery results in batches
+pub mod pg_cursor;
+
+// Scheduled SQLite vacuum / ANALYZE / WAL checkpoint per project database
+pub mod maintenance;
+
+// Server-wide maintenance mode (rejects new trigger ingress while runs drain)
+pub mod maintenance_mode;
+
+// Startup recovery of executions interrupted by a crash or restart
+pub mod recovery;
+
+// FeatureFlag node params (flag name, attributes from input pins)
+pub mod feature_flag;
+
+// ABSplit node params and deterministic variant assignment
+pub mod ab_split;
+
 // Re-export main types
 pub use engine::ExecutionEngine;
 pub use executor::ExecutionResult;
 pub use scheduler::CronSchedulerService;
+pub use events::{EventBus, EventTriggerService};
+pub use mqtt::MqttTriggerService;
+pub use opcua_trigger::OpcUaTriggerService;
+pub use serial_trigger::SerialTriggerService;
+pub use ble::BleTriggerService;
+pub use queue::ExecutionQueue;
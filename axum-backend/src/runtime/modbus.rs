@@ -0,0 +1,508 @@
+//! Modbus TCP node client and register maps
+//! 
+//! Talks Modbus TCP to PLCs and meters directly, with the endpoint
+//! ("plc.local:502" or "modbus://plc.local:502") from the node's secrets:
+//! 
+//! { "operation": "read_holding", "unit_id": 1, "registers": [
+//!     { "name": "temperature", "address": 100, "type": "i16", "scale": 0.1 },
+//!     { "name": "energy_kwh", "address": 200, "type": "u32", "word_order": "little" } ] }
+//!   -> one item { "temperature": 21.5, "energy_kwh": 10231 }
+//! 
+//! { "operation": "write_coils", "registers": [{ "name": "pump", "address": 10 }] }
+//!   -> writes the "pump" field of every input item to coil 10
+//! 
+//! Without a register map, reads return { "address", "values" } for "address"
+//! and "count", and writes take their values from the first input pin. Mapped
+//! reads are merged into as few requests as the protocol limits allow.
+
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+use std::time::Duration;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+/// Most registers per read request (protocol limit)
+const MAX_READ_REGISTERS: u16 = 125;
+
+/// Most coils / discrete inputs per read request (protocol limit)
+const MAX_READ_BITS: u16 = 2000;
+
+/// Most registers per write request (protocol limit)
+const MAX_WRITE_REGISTERS: usize = 123;
+
+/// Most coils per write request (protocol limit)
+const MAX_WRITE_COILS: usize = 1968;
+
+/// What a Modbus node does
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ModbusOperation {
+    ReadHolding,
+    ReadInput,
+    ReadCoils,
+    ReadDiscreteInputs,
+    WriteCoils,
+    WriteRegisters,
+}
+
+impl ModbusOperation {
+    fn parse(name: &str) -> Result<Self> {
+        match name {
+            "read_holding" => Ok(ModbusOperation::ReadHolding),
+            "read_input" => Ok(ModbusOperation::ReadInput),
+            "read_coils" => Ok(ModbusOperation::ReadCoils),
+            "read_discrete_inputs" => Ok(ModbusOperation::ReadDiscreteInputs),
+            "write_coils" => Ok(ModbusOperation::WriteCoils),
+            "write_registers" => Ok(ModbusOperation::WriteRegisters),
+            other => Err(anyhow::anyhow!(
+                "unsupported operation '{}' (expected read_holding, read_input, read_coils, read_discrete_inputs, write_coils or write_registers)",
+                other
+            )),
+        }
+    }
+
+    /// Param name of the operation
+    pub fn name(&self) -> &'static str {
+        match self {
+            ModbusOperation::ReadHolding => "read_holding",
+            ModbusOperation::ReadInput => "read_input",
+            ModbusOperation::ReadCoils => "read_coils",
+            ModbusOperation::ReadDiscreteInputs => "read_discrete_inputs",
+            ModbusOperation::WriteCoils => "write_coils",
+            ModbusOperation::WriteRegisters => "write_registers",
+        }
+    }
+
+    pub fn is_read(&self) -> bool {
+        !matches!(self, ModbusOperation::WriteCoils | ModbusOperation::WriteRegisters)
+    }
+
+    /// Whether the operation addresses single bits (coils / discrete inputs)
+    pub fn is_bits(&self) -> bool {
+        matches!(self, ModbusOperation::ReadCoils | ModbusOperation::ReadDiscreteInputs | ModbusOperation::WriteCoils)
+    }
+
+    /// Function code of a read
+    fn read_function(&self) -> u8 {
+        match self {
+            ModbusOperation::ReadCoils => 0x01,
+            ModbusOperation::ReadDiscreteInputs => 0x02,
+            ModbusOperation::ReadHolding => 0x03,
+            _ => 0x04,
+        }
+    }
+}
+
+/// How a mapped value is stored in registers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RegisterType {
+    Bool,
+    U16,
+    I16,
+    U32,
+    I32,
+    F32,
+}
+
+impl RegisterType {
+    fn parse(name: &str) -> Result<Self> {
+        match name {
+            "bool" => Ok(RegisterType::Bool),
+            "u16" => Ok(RegisterType::U16),
+            "i16" => Ok(RegisterType::I16),
+            "u32" => Ok(RegisterType::U32),
+            "i32" => Ok(RegisterType::I32),
+            "f32" => Ok(RegisterType::F32),
+            other => Err(anyhow::anyhow!("unsupported register type '{}' (expected bool, u16, i16, u32, i32 or f32)", other)),
+        }
+    }
+
+    /// Registers (or bits) the value occupies
+    fn width(&self) -> u16 {
+        match self {
+            RegisterType::U32 | RegisterType::I32 | RegisterType::F32 => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// Named value of a register map
+#[derive(Debug, Clone)]
+pub struct RegisterField {
+    pub name: String,
+    pub address: u16,
+    pub kind: RegisterType,
+    /// Multiplier applied after reading (divisor before writing)
+    pub scale: f64,
+    /// 32-bit values: low word first ("little") instead of high word first
+    pub low_word_first: bool,
+}
+
+/// Modbus node configuration
+#[derive(Debug, Clone)]
+pub struct ModbusParams {
+    pub operation: ModbusOperation,
+    pub unit_id: u8,
+    /// Named values; empty for raw access at "address"
+    pub registers: Vec<RegisterField>,
+    /// Start address of raw reads / writes
+    pub address: u16,
+    /// Number of registers / bits of raw reads
+    pub count: u16,
+    pub timeout: Duration,
+}
+
+impl ModbusParams {
+    /// Parse node params
+    /// 
+    /// Expected params: { "operation": "read_holding|read_input|read_coils|read_discrete_inputs|write_coils|write_registers",
+    ///                    "unit_id": 1, "registers": [{ "name", "address", "type", "scale", "word_order" }]
+    ///                    | "address": 0, "count": 10, "timeout_secs": 5 }
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        let operation = ModbusOperation::parse(params.get("operation").and_then(|o| o.as_str()).unwrap_or("read_holding"))?;
+        let unit_id = match params.get("unit_id") {
+            None => 1,
+            Some(unit) => unit.as_u64().filter(|u| *u <= 255)
+                .ok_or_else(|| anyhow::anyhow!("'unit_id' must be an integer from 0 to 255"))? as u8,
+        };
+        let address = |value: Option<&Value>, name: &str| -> Result<u16> {
+            value.and_then(|a| a.as_u64()).filter(|a| *a <= u16::MAX as u64).map(|a| a as u16)
+                .ok_or_else(|| anyhow::anyhow!("'{}' must be a register address from 0 to 65535", name))
+        };
+
+        let default_word_order = params.get("word_order").and_then(|w| w.as_str()).unwrap_or("big");
+        let registers = match params.get("registers") {
+            None | Some(Value::Null) => Vec::new(),
+            Some(Value::Array(fields)) if !fields.is_empty() => fields.iter()
+                .map(|field| {
+                    let name = field.get("name").and_then(|n| n.as_str()).filter(|n| !n.is_empty())
+                        .ok_or_else(|| anyhow::anyhow!("every register needs a 'name'"))?;
+                    let default_kind = if operation.is_bits() { "bool" } else { "u16" };
+                    let kind = RegisterType::parse(field.get("type").and_then(|t| t.as_str()).unwrap_or(default_kind))?;
+                    if operation.is_bits() != (kind == RegisterType::Bool) {
+                        return Err(anyhow::anyhow!("register '{}': coils and discrete inputs are 'bool', registers are numeric", name));
+                    }
+                    let low_word_first = match field.get("word_order").and_then(|w| w.as_str()).unwrap_or(default_word_order) {
+                        "big" => false,
+                        "little" => true,
+                        other => return Err(anyhow::anyhow!("register '{}': unsupported word_order '{}' (expected big or little)", name, other)),
+                    };
+                    let field_address = address(field.get("address"), &format!("{}.address", name))?;
+                    if field_address as u32 + kind.width() as u32 > 65536 {
+                        return Err(anyhow::anyhow!("register '{}' runs past address 65535", name));
+                    }
+                    Ok(RegisterField {
+                        name: name.to_string(),
+                        address: field_address,
+                        kind,
+                        scale: field.get("scale").and_then(|s| s.as_f64()).filter(|s| *s != 0.0).unwrap_or(1.0),
+                        low_word_first,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?,
+            Some(_) => return Err(anyhow::anyhow!("'registers' must be a non-empty array")),
+        };
+
+        let count = match params.get("count") {
+            None => 1,
+            Some(count) => count.as_u64().filter(|c| *c >= 1).map(|c| c.min(u16::MAX as u64) as u16)
+                .ok_or_else(|| anyhow::anyhow!("'count' must be a positive integer"))?,
+        };
+        let limit = if operation.is_bits() { MAX_READ_BITS } else { MAX_READ_REGISTERS };
+        if operation.is_read() && registers.is_empty() && count > limit {
+            return Err(anyhow::anyhow!("'count' can be at most {} for {}", limit, operation.name()));
+        }
+
+        Ok(Self {
+            operation,
+            unit_id,
+            address: if params.get("address").is_some() { address(params.get("address"), "address")? } else { 0 },
+            registers,
+            count,
+            timeout: Duration::from_secs_f64(params.get("timeout_secs").and_then(|t| t.as_f64()).filter(|t| *t > 0.0).unwrap_or(5.0)),
+        })
+    }
+
+    /// Read requests (start, count) covering the register map, each within protocol limits
+    pub fn read_ranges(&self) -> Vec<(u16, u16)> {
+        if self.registers.is_empty() {
+            return vec![(self.address, self.count)];
+        }
+        let limit = if self.operation.is_bits() { MAX_READ_BITS } else { MAX_READ_REGISTERS } as u32;
+        let mut fields: Vec<&RegisterField> = self.registers.iter().collect();
+        fields.sort_by_key(|field| field.address);
+
+        let mut ranges: Vec<(u16, u16)> = Vec::new();
+        for field in fields {
+            let end = field.address as u32 + field.kind.width() as u32;
+            match ranges.last_mut() {
+                Some((start, count)) if end - *start as u32 <= limit => {
+                    *count = (*count as u32).max(end - *start as u32) as u16;
+                }
+                _ => ranges.push((field.address, field.kind.width())),
+            }
+        }
+        ranges
+    }
+
+    /// Output item of a read: named values, or { "address", "values" } without a register map
+    pub fn decode(&self, words: &[(u16, Vec<u16>)]) -> Value {
+        if self.registers.is_empty() {
+            let values: Vec<Value> = words.iter().flat_map(|(_, values)| values.iter())
+                .map(|&value| if self.operation.is_bits() { json!(value != 0) } else { json!(value) })
+                .collect();
+            return json!({ "address": self.address, "values": values });
+        }
+
+        let word = |address: u16| -> Option<u16> {
+            words.iter().find_map(|(start, values)| {
+                address.checked_sub(*start).and_then(|offset| values.get(offset as usize).copied())
+            })
+        };
+        let mut item = Map::new();
+        for field in &self.registers {
+            let value = match field.kind {
+                RegisterType::Bool => word(field.address).map(|bit| json!(bit != 0)),
+                RegisterType::U16 => word(field.address).map(|w| scaled(w as f64, field.scale)),
+                RegisterType::I16 => word(field.address).map(|w| scaled(w as i16 as f64, field.scale)),
+                RegisterType::U32 | RegisterType::I32 | RegisterType::F32 => {
+                    word(field.address).zip(word(field.address + 1)).map(|(first, second)| {
+                        let (high, low) = if field.low_word_first { (second, first) } else { (first, second) };
+                        let bits = ((high as u32) << 16) | low as u32;
+                        match field.kind {
+                            RegisterType::U32 => scaled(bits as f64, field.scale),
+                            RegisterType::I32 => scaled(bits as i32 as f64, field.scale),
+                            _ => scaled(f32::from_bits(bits) as f64, field.scale),
+                        }
+                    })
+                }
+            };
+            item.insert(field.name.clone(), value.unwrap_or(Value::Null));
+        }
+        Value::Object(item)
+    }
+
+    /// Register / coil writes (start, words) for the mapped fields of an item
+    /// 
+    /// Fields missing from the item are left alone; coil words are 0 or 1.
+    pub fn encode(&self, item: &Value) -> Result<Vec<(u16, Vec<u16>)>> {
+        let mut writes = Vec::new();
+        for field in &self.registers {
+            let Some(value) = item.get(&field.name).filter(|v| !v.is_null()) else { continue };
+            let words = match field.kind {
+                RegisterType::Bool => vec![u16::from(as_bool(value).ok_or_else(|| anyhow::anyhow!("'{}' must be a boolean", field.name))?)],
+                kind => {
+                    let number = value.as_f64().ok_or_else(|| anyhow::anyhow!("'{}' must be a number", field.name))? / field.scale;
+                    encode_number(number, kind, field.low_word_first)
+                        .ok_or_else(|| anyhow::anyhow!("'{}' value {} doesn't fit a {:?} register", field.name, value, kind))?
+                }
+            };
+            writes.push((field.address, words));
+        }
+        Ok(writes)
+    }
+}
+
+fn scaled(value: f64, scale: f64) -> Value {
+    if scale == 1.0 && value.fract() == 0.0 && value.abs() < 9.0e15 {
+        json!(value as i64)
+    } else {
+        json!(value * scale)
+    }
+}
+
+/// Coil value of a JSON value (booleans, 0/1, "on"/"off")
+pub fn as_bool(value: &Value) -> Option<bool> {
+    match value {
+        Value::Bool(flag) => Some(*flag),
+        Value::Number(n) => n.as_f64().map(|n| n != 0.0),
+        Value::String(text) => match text.as_str() {
+            "true" | "on" | "1" => Some(true),
+            "false" | "off" | "0" => Some(false),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Register words of a number (None when it doesn't fit the type)
+pub fn encode_number(number: f64, kind: RegisterType, low_word_first: bool) -> Option<Vec<u16>> {
+    let rounded = number.round();
+    let bits: u32 = match kind {
+        RegisterType::U16 if (0.0..=u16::MAX as f64).contains(&rounded) => return Some(vec![rounded as u16]),
+        RegisterType::I16 if (i16::MIN as f64..=i16::MAX as f64).contains(&rounded) => return Some(vec![rounded as i16 as u16]),
+        RegisterType::U32 if (0.0..=u32::MAX as f64).contains(&rounded) => rounded as u32,
+        RegisterType::I32 if (i32::MIN as f64..=i32::MAX as f64).contains(&rounded) => rounded as i32 as u32,
+        RegisterType::F32 if number.is_finite() => (number as f32).to_bits(),
+        _ => return None,
+    };
+    let (high, low) = ((bits >> 16) as u16, bits as u16);
+    Some(if low_word_first { vec![low, high] } else { vec![high, low] })
+}
+
+/// Host and port of a Modbus endpoint ("host", "host:port", "modbus://host:port", "tcp://host:port")
+pub fn parse_endpoint(endpoint: &str) -> Result<String> {
+    let address = endpoint.trim();
+    let address = address.strip_prefix("modbus://")
+        .or_else(|| address.strip_prefix("modbus+tcp://"))
+        .or_else(|| address.strip_prefix("tcp://"))
+        .unwrap_or(address)
+        .trim_end_matches('/');
+    if address.is_empty() || address.contains("://") {
+        return Err(anyhow::anyhow!("invalid Modbus endpoint (expected host:port or modbus://host:port)"));
+    }
+    // Bare IPv6 addresses need brackets to carry a port
+    let has_port = match address.rsplit_once(':') {
+        Some((host, port)) => !host.is_empty() && port.parse::<u16>().is_ok() && (!host.contains(':') || host.ends_with(']')),
+        None => false,
+    };
+    Ok(if has_port { address.to_string() } else { format!("{}:502", address) })
+}
+
+/// Modbus TCP connection to one unit
+pub struct ModbusClient {
+    stream: TcpStream,
+    unit_id: u8,
+    transaction_id: u16,
+    timeout: Duration,
+}
+
+impl ModbusClient {
+    pub async fn connect(endpoint: &str, unit_id: u8, timeout: Duration) -> Result<Self> {
+        let stream = tokio::time::timeout(timeout, TcpStream::connect(endpoint)).await
+            .map_err(|_| anyhow::anyhow!("connecting to {} timed out", endpoint))?
+            .map_err(|e| anyhow::anyhow!("failed to connect to {}: {}", endpoint, e))?;
+        stream.set_nodelay(true)?;
+        Ok(Self { stream, unit_id, transaction_id: 0, timeout })
+    }
+
+    /// Read registers (one word each) or bits (0 / 1 each)
+    pub async fn read(&mut self, operation: ModbusOperation, address: u16, count: u16) -> Result<Vec<u16>> {
+        let mut pdu = vec![operation.read_function()];
+        pdu.extend_from_slice(&address.to_be_bytes());
+        pdu.extend_from_slice(&count.to_be_bytes());
+        let response = self.request(&pdu).await?;
+
+        let data = response.get(2..).filter(|data| data.len() == response[1] as usize)
+            .ok_or_else(|| anyhow::anyhow!("malformed read response"))?;
+        if operation.is_bits() {
+            if data.len() * 8 < count as usize {
+                return Err(anyhow::anyhow!("read response has {} bits, expected {}", data.len() * 8, count));
+            }
+            Ok((0..count as usize).map(|bit| u16::from(data[bit / 8] >> (bit % 8) & 1)).collect())
+        } else {
+            if data.len() != count as usize * 2 {
+                return Err(anyhow::anyhow!("read response has {} bytes, expected {}", data.len(), count as usize * 2));
+            }
+            Ok(data.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect())
+        }
+    }
+
+    /// Write coils starting at `address`
+    pub async fn write_coils(&mut self, address: u16, values: &[bool]) -> Result<()> {
+        for (index, chunk) in values.chunks(MAX_WRITE_COILS).enumerate() {
+            let start = address_at(address, index * MAX_WRITE_COILS)?;
+            let pdu = match chunk {
+                [single] => {
+                    let mut pdu = vec![0x05];
+                    pdu.extend_from_slice(&start.to_be_bytes());
+                    pdu.extend_from_slice(if *single { &[0xFF, 0x00] } else { &[0x00, 0x00] });
+                    pdu
+                }
+                _ => {
+                    let mut bytes = vec![0u8; chunk.len().div_ceil(8)];
+                    for (bit, _) in chunk.iter().enumerate().filter(|(_, on)| **on) {
+                        bytes[bit / 8] |= 1 << (bit % 8);
+                    }
+                    let mut pdu = vec![0x0F];
+                    pdu.extend_from_slice(&start.to_be_bytes());
+                    pdu.extend_from_slice(&(chunk.len() as u16).to_be_bytes());
+                    pdu.push(bytes.len() as u8);
+                    pdu.extend_from_slice(&bytes);
+                    pdu
+                }
+            };
+            self.request(&pdu).await?;
+        }
+        Ok(())
+    }
+
+    /// Write holding registers starting at `address`
+    pub async fn write_registers(&mut self, address: u16, values: &[u16]) -> Result<()> {
+        for (index, chunk) in values.chunks(MAX_WRITE_REGISTERS).enumerate() {
+            let start = address_at(address, index * MAX_WRITE_REGISTERS)?;
+            let mut pdu = match chunk {
+                [_] => vec![0x06],
+                _ => vec![0x10],
+            };
+            pdu.extend_from_slice(&start.to_be_bytes());
+            if chunk.len() > 1 {
+                pdu.extend_from_slice(&(chunk.len() as u16).to_be_bytes());
+                pdu.push((chunk.len() * 2) as u8);
+            }
+            for value in chunk {
+                pdu.extend_from_slice(&value.to_be_bytes());
+            }
+            self.request(&pdu).await?;
+        }
+        Ok(())
+    }
+
+    /// Send one request PDU and return the response PDU (exceptions become errors)
+    async fn request(&mut self, pdu: &[u8]) -> Result<Vec<u8>> {
+        self.transaction_id = self.transaction_id.wrapping_add(1);
+        let mut frame = Vec::with_capacity(7 + pdu.len());
+        frame.extend_from_slice(&self.transaction_id.to_be_bytes());
+        frame.extend_from_slice(&[0, 0]);
+        frame.extend_from_slice(&(pdu.len() as u16 + 1).to_be_bytes());
+        frame.push(self.unit_id);
+        frame.extend_from_slice(pdu);
+
+        let exchange = async {
+            self.stream.write_all(&frame).await?;
+            loop {
+                let mut header = [0u8; 7];
+                self.stream.read_exact(&mut header).await?;
+                let length = u16::from_be_bytes([header[4], header[5]]) as usize;
+                if length < 2 {
+                    return Err(anyhow::anyhow!("malformed response header"));
+                }
+                let mut response = vec![0u8; length - 1];
+                self.stream.read_exact(&mut response).await?;
+                // Late answers to earlier (timed out) requests are skipped
+                if u16::from_be_bytes([header[0], header[1]]) == self.transaction_id {
+                    return Ok(response);
+                }
+            }
+        };
+        let response = tokio::time::timeout(self.timeout, exchange).await
+            .map_err(|_| anyhow::anyhow!("no response from unit {} within {:?}", self.unit_id, self.timeout))??;
+
+        match response.first() {
+            Some(&function) if function == pdu[0] => Ok(response),
+            Some(&function) if function == pdu[0] | 0x80 => {
+                Err(anyhow::anyhow!("unit {} answered with exception: {}", self.unit_id, exception_name(response.get(1).copied().unwrap_or(0))))
+            }
+            _ => Err(anyhow::anyhow!("unexpected response function code")),
+        }
+    }
+}
+
+fn address_at(start: u16, offset: usize) -> Result<u16> {
+    u16::try_from(start as usize + offset).map_err(|_| anyhow::anyhow!("write runs past address 65535"))
+}
+
+fn exception_name(code: u8) -> String {
+    match code {
+        0x01 => "illegal function (01)".to_string(),
+        0x02 => "illegal data address (02)".to_string(),
+        0x03 => "illegal data value (03)".to_string(),
+        0x04 => "server device failure (04)".to_string(),
+        0x05 => "acknowledge (05)".to_string(),
+        0x06 => "server device busy (06)".to_string(),
+        0x0A => "gateway path unavailable (0A)".to_string(),
+        0x0B => "gateway target device failed to respond (0B)".to_string(),
+        other => format!("code {:02X}", other),
+    }
+}
@@ -0,0 +1,447 @@
+//! Background MQTT subscriber service for MQTTTrigger nodes
+//! 
+//! Keeps one broker connection per MQTTTrigger node and starts the workflow
+//! for every received message (or every aggregated run, see stream_aggregate):
+//! 
+//! { "broker": "mqtt://broker.local:1883", "topic": "sensors/+/data", "qos": 1,
+//!   "username": "mechaway", "password": "$secret.MQTT_PASSWORD",
+//!   "aggregate": { "mode": "average", "window_secs": 5 } }
+//! 
+//! Runs of one trigger are executed one after another, in message order. When
+//! the workflow can't keep up, messages beyond a bounded buffer are dropped
+//! (logged) instead of piling up in memory. Connections are re-established
+//! with backoff and the topic is re-subscribed after every reconnect.
+//! 
+//! Item: the JSON object payload (or { "value": payload }) plus
+//! "mqtt": { "topic", "qos", "retain" }. Every message also updates the state
+//! of its device (see devices), which is the aggregation stream as well.
+//! 
+//! With "exactly_once_key": "payment_id" (QoS 1 or 2, no aggregation) messages
+//! are acknowledged only after their run, in a persistent broker session, and
+//! each value of that payload field runs the workflow once (see project::inbox):
+//! a redelivery of a finished run is acknowledged without running again, one
+//! whose run was interrupted by a crash runs again.
+
+use crate::{
+    runtime::{
+        devices::{DeviceRegistry, DeviceTrigger},
+        engine::ExecutionEngine,
+        executor::{NodeExecutor, Termination},
+        stream_aggregate::{message_item, Aggregated, Aggregator, StreamAggregate},
+    },
+    workflow::{
+        registry::{CompiledWorkflow, WorkflowRegistry},
+        types::{ExecutionContext, Node, NodeType, Workflow},
+    },
+};
+use anyhow::Result;
+use rumqttc::{AsyncClient, Event, MqttOptions, Packet, Publish, QoS, Transport};
+use serde_json::{json, Value};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::{sync::mpsc, sync::Mutex, task::JoinHandle, time::Instant};
+
+/// Received messages buffered per trigger while a run is in progress
+const MESSAGE_BUFFER: usize = 1024;
+
+/// Longest wait between reconnect attempts
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// MQTTTrigger configuration
+#[derive(Debug, Clone)]
+pub struct MqttParams {
+    pub host: String,
+    pub port: u16,
+    pub tls: bool,
+    /// Topic filter (wildcards + and # allowed)
+    pub topic: String,
+    pub qos: QoS,
+    pub client_id: Option<String>,
+    pub username: Option<String>,
+    /// Literal password or `$secret.` pin
+    pub password: Option<String>,
+    pub keep_alive: Duration,
+    pub aggregate: Option<StreamAggregate>,
+    /// Payload field identifying a message for exactly-once processing
+    pub exactly_once_key: Option<String>,
+}
+
+impl MqttParams {
+    /// Parse node params
+    /// 
+    /// Expected params: { "broker": "mqtt://host:1883" | "mqtts://host:8883" | "host:port", "topic": "sensors/#",
+    ///                    "qos": 0-2, "client_id": "...", "username": "...", "password": "$secret.NAME",
+    ///                    "keep_alive_secs": 30, "aggregate": {...}, "exactly_once_key": "payment_id" }
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        let broker = params.get("broker")
+            .and_then(|b| b.as_str())
+            .filter(|b| !b.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("missing 'broker' parameter (e.g. \"mqtt://broker.local:1883\")"))?;
+        let (tls, address) = match broker.split_once("://") {
+            Some(("mqtt" | "tcp", address)) => (false, address),
+            Some(("mqtts" | "ssl", address)) => (true, address),
+            Some((scheme, _)) => return Err(anyhow::anyhow!("unsupported broker scheme '{}' (expected mqtt or mqtts)", scheme)),
+            None => (false, broker),
+        };
+        let address = address.trim_end_matches('/');
+        let (host, port) = match address.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse::<u16>()
+                .map_err(|_| anyhow::anyhow!("invalid broker port '{}'", port))?),
+            None => (address, if tls { 8883 } else { 1883 }),
+        };
+        if host.is_empty() {
+            return Err(anyhow::anyhow!("broker '{}' has no host", broker));
+        }
+
+        let topic = params.get("topic")
+            .and_then(|t| t.as_str())
+            .filter(|t| !t.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("missing 'topic' parameter"))?
+            .to_string();
+        let qos = match params.get("qos").map(|q| q.as_u64()) {
+            None | Some(Some(0)) => QoS::AtMostOnce,
+            Some(Some(1)) => QoS::AtLeastOnce,
+            Some(Some(2)) => QoS::ExactlyOnce,
+            Some(_) => return Err(anyhow::anyhow!("'qos' must be 0, 1 or 2")),
+        };
+        let keep_alive_secs = match params.get("keep_alive_secs") {
+            None => 30,
+            Some(secs) => secs.as_u64().filter(|s| *s >= 5)
+                .ok_or_else(|| anyhow::anyhow!("'keep_alive_secs' must be an integer of at least 5"))?,
+        };
+        let text = |name: &str| params.get(name).and_then(|v| v.as_str()).filter(|v| !v.is_empty()).map(str::to_string);
+        let aggregate = StreamAggregate::from_node_params(params)?;
+        let exactly_once_key = text("exactly_once_key");
+        if exactly_once_key.is_some() {
+            if qos == QoS::AtMostOnce {
+                return Err(anyhow::anyhow!("'exactly_once_key' needs 'qos' 1 or 2 (QoS 0 messages are never redelivered)"));
+            }
+            if aggregate.is_some() {
+                return Err(anyhow::anyhow!("'exactly_once_key' can't be combined with 'aggregate'"));
+            }
+        }
+
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            tls,
+            topic,
+            qos,
+            client_id: text("client_id"),
+            username: text("username"),
+            password: text("password"),
+            keep_alive: Duration::from_secs(keep_alive_secs),
+            aggregate,
+            exactly_once_key,
+        })
+    }
+}
+
+/// HOT-RELOAD MQTT subscriber service
+pub struct MqttTriggerService {
+    registry: Arc<WorkflowRegistry>,
+    executor: Arc<NodeExecutor>,
+    engine: Arc<ExecutionEngine>,
+    /// Device states updated by every message
+    devices: Arc<DeviceRegistry>,
+    /// workflow_id -> one subscription task per MQTTTrigger node
+    subscriptions: Mutex<HashMap<String, Vec<JoinHandle<()>>>>,
+}
+
+impl MqttTriggerService {
+    /// Create new MQTT trigger service
+    pub fn new(registry: Arc<WorkflowRegistry>, executor: Arc<NodeExecutor>, engine: Arc<ExecutionEngine>, devices: Arc<DeviceRegistry>) -> Self {
+        Self { registry, executor, engine, devices, subscriptions: Mutex::new(HashMap::new()) }
+    }
+
+    /// Subscribe the MQTTTrigger nodes of all registered workflows
+    pub async fn start(&self) {
+        tracing::info!("📡 Starting MQTT trigger service");
+        for workflow in self.registry.get_all_workflows() {
+            if let Err(e) = self.add_or_update_workflow_mqtt_triggers(&workflow).await {
+                tracing::error!("❌ Failed to subscribe MQTT triggers of workflow {}: {}", workflow.id, e);
+            }
+        }
+    }
+
+    /// Number of active MQTT subscriptions
+    pub async fn subscription_count(&self) -> usize {
+        self.subscriptions.lock().await.values().map(Vec::len).sum()
+    }
+
+    /// HOT-RELOAD: Replace the subscriptions of a workflow with its current MQTTTrigger nodes
+    pub async fn add_or_update_workflow_mqtt_triggers(&self, workflow: &Workflow) -> Result<()> {
+        let triggers: Vec<(&Node, MqttParams)> = workflow.nodes.iter()
+            .filter(|node| matches!(node.node_type, NodeType::MQTTTrigger))
+            .map(|node| MqttParams::from_node_params(&node.params)
+                .map(|params| (node, params))
+                .map_err(|e| anyhow::anyhow!("MQTTTrigger '{}': {}", node.id, e)))
+            .collect::<Result<_>>()?;
+
+        // Resolve credentials before dropping the running subscriptions
+        let mut options = Vec::with_capacity(triggers.len());
+        for (node, params) in &triggers {
+            options.push(self.mqtt_options(workflow, node, params)?);
+        }
+
+        self.remove_workflow_mqtt_triggers(&workflow.id).await;
+        if triggers.is_empty() {
+            return Ok(());
+        }
+
+        let handles: Vec<JoinHandle<()>> = triggers.into_iter().zip(options)
+            .map(|((node, params), options)| {
+                tracing::info!("📡 Subscribing MQTTTrigger {}:{} to '{}' on {}:{}",
+                    workflow.id, node.id, params.topic, params.host, params.port);
+                let subscription = Subscription {
+                    registry: Arc::clone(&self.registry),
+                    engine: Arc::clone(&self.engine),
+                    devices: Arc::clone(&self.devices),
+                    project: workflow.project.clone(),
+                    device: DeviceTrigger::from_node("mqtt", &workflow.id, node),
+                    params,
+                };
+                tokio::spawn(subscription.run(options))
+            })
+            .collect();
+
+        tracing::info!("🔥 Hot-reloaded {} MQTT triggers for workflow: {}", handles.len(), workflow.id);
+        self.subscriptions.lock().await.insert(workflow.id.clone(), handles);
+        Ok(())
+    }
+
+    /// HOT-RELOAD: Disconnect all MQTT triggers of a workflow
+    pub async fn remove_workflow_mqtt_triggers(&self, workflow_id: &str) {
+        if let Some(handles) = self.subscriptions.lock().await.remove(workflow_id) {
+            tracing::info!("🗑️ Removing {} MQTT triggers for workflow: {}", handles.len(), workflow_id);
+            for handle in handles {
+                handle.abort();
+            }
+        }
+    }
+
+    fn mqtt_options(&self, workflow: &Workflow, node: &Node, params: &MqttParams) -> Result<MqttOptions> {
+        let client_id = params.client_id.clone()
+            .unwrap_or_else(|| format!("mechaway-{}-{}", workflow.id, node.id));
+        let mut options = MqttOptions::new(client_id, params.host.clone(), params.port);
+        options.set_keep_alive(params.keep_alive);
+        if params.tls {
+            options.set_transport(Transport::tls_with_default_config());
+        }
+        if params.exactly_once_key.is_some() {
+            // Unacknowledged messages survive a crash in the broker's session
+            options.set_manual_acks(true);
+            options.set_clean_session(false);
+        }
+        if let Some(username) = &params.username {
+            let password = match &params.password {
+                Some(pin) if pin.starts_with("$secret.") => self.executor.resolve_secret(&workflow.project, pin)
+                    .map_err(|e| anyhow::anyhow!("MQTTTrigger '{}': {}", node.id, e))?,
+                Some(password) => password.clone(),
+                None => String::new(),
+            };
+            options.set_credentials(username.clone(), password);
+        }
+        Ok(options)
+    }
+}
+
+/// One running MQTTTrigger subscription
+struct Subscription {
+    registry: Arc<WorkflowRegistry>,
+    engine: Arc<ExecutionEngine>,
+    devices: Arc<DeviceRegistry>,
+    project: String,
+    device: DeviceTrigger,
+    params: MqttParams,
+}
+
+impl Subscription {
+    /// Connection and worker of the node (runs until aborted)
+    async fn run(self, options: MqttOptions) {
+        let (client, mut eventloop) = AsyncClient::new(options, 10);
+        // Messages of exactly-once triggers carry their publish packet for the acknowledgment
+        let (sender, mut receiver) = mpsc::channel::<(String, Value, Option<Publish>)>(MESSAGE_BUFFER);
+        let trigger = format!("{}:{}", self.device.workflow_id, self.device.node_id);
+
+        let connection = async {
+            let mut delay = Duration::from_secs(1);
+            loop {
+                match eventloop.poll().await {
+                    Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                        tracing::info!("📡 MQTTTrigger {} connected, subscribing to '{}'", trigger, self.params.topic);
+                        delay = Duration::from_secs(1);
+                        if let Err(e) = client.try_subscribe(self.params.topic.clone(), self.params.qos) {
+                            tracing::error!("❌ MQTTTrigger {} failed to subscribe: {}", trigger, e);
+                        }
+                    }
+                    Ok(Event::Incoming(Packet::Publish(publish))) => {
+                        let item = message_item(&publish.payload, "mqtt", json!({
+                            "topic": publish.topic,
+                            "qos": publish.qos as u8,
+                            "retain": publish.retain,
+                        }));
+                        // Device state follows every message, aggregated or not
+                        let device_id = self.device.device_id(&item, &publish.topic);
+                        self.devices.record_message(&self.project, &self.device, &device_id, &item);
+                        let unacked = self.params.exactly_once_key.is_some().then(|| publish.clone());
+                        if sender.try_send((device_id, item, unacked)).is_err() {
+                            // Unacknowledged exactly-once messages come back with the next session
+                            tracing::warn!("⚠️ MQTTTrigger {} is falling behind, message dropped", trigger);
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        tracing::warn!("⚠️ MQTTTrigger {} connection error: {} (retrying in {:?})", trigger, e, delay);
+                        tokio::time::sleep(delay).await;
+                        delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                    }
+                }
+            }
+        };
+
+        let worker = async {
+            // Aggregation state per device (a wildcard filter matches several streams)
+            let mut streams: HashMap<String, Aggregator> = HashMap::new();
+            loop {
+                let deadline = streams.values().filter_map(Aggregator::deadline).min();
+                let received = match deadline {
+                    Some(deadline) => tokio::select! {
+                        message = receiver.recv() => Some(message),
+                        _ = tokio::time::sleep_until(deadline) => None,
+                    },
+                    None => Some(receiver.recv().await),
+                };
+
+                match received {
+                    Some(None) => break,
+                    Some(Some((device_id, item, Some(publish)))) => {
+                        self.execute_once(&client, &device_id, item, &publish).await;
+                    }
+                    Some(Some((device_id, item, None))) => {
+                        let run = streams.entry(device_id.clone())
+                            .or_insert_with(|| Aggregator::new(self.params.aggregate.clone()))
+                            .push(item, Instant::now());
+                        if let Some(run) = run {
+                            self.execute(&device_id, run).await;
+                        }
+                    }
+                    None => {
+                        let now = Instant::now();
+                        let due: Vec<(String, Aggregated)> = streams.iter_mut()
+                            .filter(|(_, aggregator)| aggregator.deadline().is_some_and(|deadline| deadline <= now))
+                            .filter_map(|(device_id, aggregator)| Some((device_id.clone(), aggregator.flush()?)))
+                            .collect();
+                        for (device_id, run) in due {
+                            self.execute(&device_id, run).await;
+                        }
+                    }
+                }
+            }
+        };
+
+        tokio::join!(connection, worker);
+    }
+
+    /// Run the workflow for one (aggregated) message
+    async fn execute(&self, device_id: &str, run: Aggregated) {
+        if let Some(compiled) = self.runnable_workflow() {
+            self.run_workflow(&compiled, device_id, run, None).await;
+        }
+    }
+
+    /// Run the workflow once per message key of an exactly-once trigger, then acknowledge the message
+    /// 
+    /// The key is claimed in the project inbox before the run. A message whose claim
+    /// fails, or that arrives while the workflow doesn't run (maintenance mode, snooze,
+    /// unknown workflow), isn't acknowledged, so the broker delivers it again.
+    async fn execute_once(&self, client: &AsyncClient, device_id: &str, item: Value, publish: &Publish) {
+        let (workflow_id, node_id) = (&self.device.workflow_id, &self.device.node_id);
+        let key_field = self.params.exactly_once_key.as_deref().unwrap_or_default();
+        let key = match item.get(key_field) {
+            Some(Value::String(key)) => Some(key.clone()),
+            Some(Value::Number(key)) => Some(key.to_string()),
+            _ => None,
+        };
+        let run = Aggregated { item, info: Value::Null };
+
+        match (self.runnable_workflow(), key) {
+            (None, _) => {
+                tracing::debug!("⏸️ MQTTTrigger {}:{} left a message unacknowledged for redelivery", workflow_id, node_id);
+                return;
+            }
+            (Some(compiled), None) => {
+                tracing::warn!("⚠️ MQTTTrigger {}:{} message has no '{}' key, running it without deduplication", workflow_id, node_id, key_field);
+                self.run_workflow(&compiled, device_id, run, None).await;
+            }
+            (Some(compiled), Some(key)) => {
+                let inbox = self.engine.executor().project_db_manager();
+                let project = &compiled.workflow.project;
+                let execution_id = uuid::Uuid::new_v4().to_string();
+                let reclaim_interrupted = !compiled.workflow.settings.resume_on_restart;
+                match inbox.inbox_claim(project, workflow_id, node_id, &key, &execution_id, reclaim_interrupted).await {
+                    Ok(true) => {
+                        let termination = self.run_workflow(&compiled, device_id, run, Some(&execution_id)).await;
+                        if let Err(e) = inbox.inbox_finish(project, workflow_id, node_id, &key, termination.status()).await {
+                            tracing::warn!("⚠️ MQTTTrigger {}:{} failed to record the outcome of '{}': {}", workflow_id, node_id, key, e);
+                        }
+                    }
+                    Ok(false) => {
+                        tracing::info!("⏭️ MQTTTrigger {}:{} skipped redelivered message '{}'", workflow_id, node_id, key);
+                    }
+                    Err(e) => {
+                        tracing::error!("❌ MQTTTrigger {}:{} failed to claim message '{}', leaving it unacknowledged: {}", workflow_id, node_id, key, e);
+                        return;
+                    }
+                }
+            }
+        }
+
+        if let Err(e) = client.ack(publish).await {
+            tracing::warn!("⚠️ MQTTTrigger {}:{} failed to acknowledge a message: {}", workflow_id, node_id, e);
+        }
+    }
+
+    /// The workflow, unless its runs are currently dropped (unknown workflow, maintenance mode, snooze)
+    fn runnable_workflow(&self) -> Option<CompiledWorkflow> {
+        let (workflow_id, node_id) = (&self.device.workflow_id, &self.device.node_id);
+        let Some(compiled) = self.registry.get_workflow(workflow_id) else {
+            tracing::warn!("⚠️ MQTTTrigger {}:{} received a message for an unknown workflow", workflow_id, node_id);
+            return None;
+        };
+        if self.engine.maintenance_mode().is_enabled() {
+            tracing::debug!("🚧 MQTTTrigger {}:{} dropped a message: maintenance mode", workflow_id, node_id);
+            return None;
+        }
+        if compiled.active_snooze().is_some() {
+            tracing::debug!("💤 MQTTTrigger {}:{} dropped a message: workflow is snoozed", workflow_id, node_id);
+            return None;
+        }
+        Some(compiled)
+    }
+
+    /// Run the workflow for one (aggregated) message, with a pre-assigned execution ID if given
+    async fn run_workflow(&self, compiled: &CompiledWorkflow, device_id: &str, run: Aggregated, execution_id: Option<&str>) -> Termination {
+        let (workflow_id, node_id) = (&self.device.workflow_id, &self.device.node_id);
+        let topic = run.item.get("mqtt").and_then(|mqtt| mqtt.get("topic")).cloned().unwrap_or(Value::Null);
+
+        let mut context = ExecutionContext::from_array_data(
+            workflow_id.clone(), vec![run.item], compiled.workflow.project.clone());
+        context.metadata.insert("trigger_type".to_string(), json!("mqtt"));
+        context.metadata.insert("trigger_node_id".to_string(), json!(node_id));
+        context.metadata.insert("mqtt_topic".to_string(), topic);
+        context.metadata.insert("device_id".to_string(), json!(device_id));
+        if !run.info.is_null() {
+            context.metadata.insert("aggregate".to_string(), run.info);
+        }
+        if let Some(execution_id) = execution_id {
+            context.metadata.insert("execution_id".to_string(), json!(execution_id));
+        }
+
+        tracing::info!("🚀 Executing MQTT-triggered workflow: {} ({}, device '{}')", workflow_id, node_id, device_id);
+        let result = self.engine.execute_workflow(compiled, node_id, context).await;
+        if let Err(e) = &result {
+            tracing::error!("❌ MQTT-triggered workflow failed: {} - Error: {}", workflow_id, e);
+        }
+        Termination::of_run(&result)
+    }
+}
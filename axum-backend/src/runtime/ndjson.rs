@@ -0,0 +1,144 @@
+//! NDJSON / JSON Lines parsing and writing
+//! 
+//! NdjsonParse reads one JSON value per line from its first input pin (a text
+//! string, an uploaded file or a binary item). With a "batch_size" the engine
+//! streams the lines through the downstream nodes one batch at a time, like a
+//! cursor-mode PGQuery, so multi-GB files never sit in one data array:
+//! 
+//! { "batch_size": 10000, "on_error": "skip" }   + inputs ["$json.data"]
+//! 
+//! NdjsonWrite serializes the data array to a file in the project's uploads
+//! directory. Across the batches of one stream it appends to the same file.
+
+use anyhow::Result;
+use bytes::Bytes;
+use serde_json::Value;
+use std::path::Path;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+
+/// Longest accepted line (a larger line fails the read instead of exhausting memory)
+pub const MAX_LINE_BYTES: u64 = 16 * 1024 * 1024;
+
+/// MIME type of written files
+pub const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// Batch size of a streaming NdjsonParse node, or None when it parses everything at once
+/// 
+/// Expected params: { "batch_size": 10000 }
+pub fn ndjson_batch_size(params: &Value) -> Result<Option<usize>> {
+    match params.get("batch_size") {
+        None | Some(Value::Null) => Ok(None),
+        Some(size) => match size.as_u64() {
+            Some(size) if size > 0 => Ok(Some(size as usize)),
+            _ => Err(anyhow::anyhow!("'batch_size' must be a positive integer, got {}", size)),
+        },
+    }
+}
+
+/// Line-by-line reader producing batches of parsed items
+pub struct NdjsonReader {
+    reader: Box<dyn AsyncBufRead + Send + Unpin>,
+    batch_size: usize,
+    skip_invalid: bool,
+    exhausted: bool,
+    line: Vec<u8>,
+    /// Lines read so far (including blank and skipped ones)
+    pub lines: u64,
+    /// Invalid lines skipped so far
+    pub skipped: u64,
+    /// Batches returned so far
+    pub batches: u64,
+    /// Items returned so far
+    pub rows: u64,
+}
+
+impl NdjsonReader {
+    /// Reader over in-memory content (text pins, in-memory binaries)
+    pub fn from_bytes(content: Bytes, batch_size: usize, skip_invalid: bool) -> Self {
+        Self::new(Box::new(std::io::Cursor::new(content)), batch_size, skip_invalid)
+    }
+
+    /// Reader streaming a file from disk
+    pub async fn open_file(path: &Path, batch_size: usize, skip_invalid: bool) -> Result<Self> {
+        let file = tokio::fs::File::open(path).await
+            .map_err(|e| anyhow::anyhow!("Can't open '{}': {}", path.display(), e))?;
+        Ok(Self::new(Box::new(BufReader::with_capacity(256 * 1024, file)), batch_size, skip_invalid))
+    }
+
+    fn new(reader: Box<dyn AsyncBufRead + Send + Unpin>, batch_size: usize, skip_invalid: bool) -> Self {
+        Self {
+            reader,
+            batch_size,
+            skip_invalid,
+            exhausted: false,
+            line: Vec::new(),
+            lines: 0,
+            skipped: 0,
+            batches: 0,
+            rows: 0,
+        }
+    }
+
+    /// Parse the next batch of lines (None once the input is drained)
+    pub async fn next_batch(&mut self) -> Result<Option<Vec<Value>>> {
+        let mut batch = Vec::new();
+        while !self.exhausted && batch.len() < self.batch_size {
+            self.line.clear();
+            let read = (&mut self.reader).take(MAX_LINE_BYTES + 1).read_until(b'\n', &mut self.line).await?;
+            if read == 0 {
+                self.exhausted = true;
+                break;
+            }
+            self.lines += 1;
+            if self.line.len() as u64 > MAX_LINE_BYTES {
+                return Err(anyhow::anyhow!("Line {} is longer than {} bytes", self.lines, MAX_LINE_BYTES));
+            }
+
+            let mut text = self.line.as_slice();
+            if self.lines == 1 {
+                text = text.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(text);
+            }
+            if text.trim_ascii().is_empty() {
+                continue;
+            }
+            match serde_json::from_slice::<Value>(text) {
+                Ok(item) => batch.push(item),
+                Err(e) if self.skip_invalid => {
+                    tracing::debug!("⏭️ Skipping invalid NDJSON line {}: {}", self.lines, e);
+                    self.skipped += 1;
+                }
+                Err(e) => return Err(anyhow::anyhow!("Invalid JSON on line {}: {}", self.lines, e)),
+            }
+        }
+        if batch.is_empty() {
+            return Ok(None);
+        }
+
+        self.batches += 1;
+        self.rows += batch.len() as u64;
+        Ok(Some(batch))
+    }
+}
+
+/// Write items as NDJSON lines, appending to or replacing `path`
+/// 
+/// Returns the file size after writing.
+pub async fn write_lines(path: &Path, items: &[Value], append: bool) -> Result<u64> {
+    let file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(path).await
+        .map_err(|e| anyhow::anyhow!("Can't open '{}' for writing: {}", path.display(), e))?;
+    let mut writer = tokio::io::BufWriter::with_capacity(256 * 1024, file);
+    let mut line = Vec::with_capacity(1024);
+    for item in items {
+        line.clear();
+        serde_json::to_writer(&mut line, item)?;
+        line.push(b'\n');
+        writer.write_all(&line).await?;
+    }
+    writer.flush().await?;
+    Ok(writer.get_ref().metadata().await?.len())
+}
@@ -0,0 +1,1481 @@
+//! OPC UA client (opc.tcp binary protocol) for OpcUa and OpcUaTrigger nodes
+//! 
+//! Talks to PLCs, SCADA gateways and historians directly, with the endpoint
+//! ("opc.tcp://plc.local:4840") from the node's secrets:
+//! 
+//! { "operation": "read", "nodes": ["ns=2;s=Line1.Temperature",
+//!     { "node_id": "ns=2;i=1042", "name": "pressure" }] }
+//!   -> one item { "ns=2;s=Line1.Temperature": 71.5, "pressure": 3.2 }
+//! 
+//! { "operation": "write", "nodes": [{ "node_id": "ns=2;s=Line1.Setpoint", "name": "setpoint" }] }
+//!   -> writes the "setpoint" field of every input item
+//! 
+//! { "operation": "browse", "node_id": "i=85", "max_depth": 2 }
+//!   -> one item per reference below the Objects folder
+//! 
+//! Only security mode None is supported (no signing / encryption), with
+//! anonymous or username login as the server's None endpoint allows. Each
+//! node execution opens its own secure channel and session and closes them
+//! afterwards; OpcUaTrigger subscriptions keep theirs open (see opcua_trigger).
+
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::{DateTime, TimeZone, Utc};
+use serde_json::{json, Map, Value};
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+const SECURITY_POLICY_NONE: &str = "http://opcfoundation.org/UA/SecurityPolicy#None";
+
+/// Largest message accepted from servers
+const MAX_MESSAGE_SIZE: u32 = 16 * 1024 * 1024;
+
+/// Chunk size offered to servers
+const BUFFER_SIZE: u32 = 65536;
+
+/// Requested secure channel lifetime (renewed at 75%)
+const CHANNEL_LIFETIME_MS: u32 = 3_600_000;
+
+/// Requested session timeout
+const SESSION_TIMEOUT_MS: f64 = 60_000.0;
+
+/// Ticks (100 ns) between 1601-01-01 and the Unix epoch
+const EPOCH_TICKS: i64 = 116_444_736_000_000_000;
+
+/// Attribute IDs
+pub const ATTRIBUTE_VALUE: u32 = 13;
+
+/// Binary encoding IDs (namespace 0) of the services and structures used here
+mod ids {
+    pub const OPEN_SECURE_CHANNEL_REQUEST: u32 = 446;
+    pub const OPEN_SECURE_CHANNEL_RESPONSE: u32 = 449;
+    pub const CLOSE_SECURE_CHANNEL_REQUEST: u32 = 452;
+    pub const CREATE_SESSION_REQUEST: u32 = 461;
+    pub const CREATE_SESSION_RESPONSE: u32 = 464;
+    pub const ACTIVATE_SESSION_REQUEST: u32 = 467;
+    pub const ACTIVATE_SESSION_RESPONSE: u32 = 470;
+    pub const CLOSE_SESSION_REQUEST: u32 = 473;
+    pub const CLOSE_SESSION_RESPONSE: u32 = 476;
+    pub const BROWSE_REQUEST: u32 = 527;
+    pub const BROWSE_RESPONSE: u32 = 530;
+    pub const BROWSE_NEXT_REQUEST: u32 = 533;
+    pub const BROWSE_NEXT_RESPONSE: u32 = 536;
+    pub const READ_REQUEST: u32 = 631;
+    pub const READ_RESPONSE: u32 = 634;
+    pub const WRITE_REQUEST: u32 = 673;
+    pub const WRITE_RESPONSE: u32 = 676;
+    pub const CREATE_MONITORED_ITEMS_REQUEST: u32 = 751;
+    pub const CREATE_MONITORED_ITEMS_RESPONSE: u32 = 754;
+    pub const CREATE_SUBSCRIPTION_REQUEST: u32 = 787;
+    pub const CREATE_SUBSCRIPTION_RESPONSE: u32 = 790;
+    pub const PUBLISH_REQUEST: u32 = 826;
+    pub const PUBLISH_RESPONSE: u32 = 829;
+    pub const SERVICE_FAULT: u32 = 397;
+    pub const ANONYMOUS_IDENTITY_TOKEN: u32 = 321;
+    pub const USER_NAME_IDENTITY_TOKEN: u32 = 324;
+    pub const DATA_CHANGE_FILTER: u32 = 724;
+    pub const DATA_CHANGE_NOTIFICATION: u32 = 811;
+    pub const STATUS_CHANGE_NOTIFICATION: u32 = 820;
+    pub const HIERARCHICAL_REFERENCES: u32 = 33;
+    pub const OBJECTS_FOLDER: u32 = 85;
+}
+
+/// Identifier part of a NodeId
+#[derive(Debug, Clone, PartialEq)]
+pub enum Identifier {
+    Numeric(u32),
+    String(String),
+    Guid(uuid::Uuid),
+    Opaque(Vec<u8>),
+}
+
+/// OPC UA NodeId, written like "ns=2;s=Line1.Temperature" or "i=85"
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeId {
+    pub namespace: u16,
+    pub identifier: Identifier,
+}
+
+impl NodeId {
+    pub fn numeric(namespace: u16, id: u32) -> Self {
+        Self { namespace, identifier: Identifier::Numeric(id) }
+    }
+
+    fn null() -> Self {
+        Self::numeric(0, 0)
+    }
+
+    /// Parse "ns=<index>;<i|s|g|b>=<identifier>" (namespace 0 when "ns=" is left out)
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim();
+        let (namespace, identifier) = match text.strip_prefix("ns=").and_then(|rest| rest.split_once(';')) {
+            Some((namespace, identifier)) => (namespace.parse::<u16>()
+                .map_err(|_| anyhow::anyhow!("invalid namespace index in node id '{}'", text))?, identifier),
+            None => (0, text),
+        };
+        let identifier = match identifier.split_once('=') {
+            Some(("i", id)) => Identifier::Numeric(id.parse()
+                .map_err(|_| anyhow::anyhow!("invalid numeric identifier in node id '{}'", text))?),
+            Some(("s", id)) if !id.is_empty() => Identifier::String(id.to_string()),
+            Some(("g", id)) => Identifier::Guid(uuid::Uuid::parse_str(id)
+                .map_err(|_| anyhow::anyhow!("invalid GUID identifier in node id '{}'", text))?),
+            Some(("b", id)) => Identifier::Opaque(BASE64.decode(id)
+                .map_err(|_| anyhow::anyhow!("invalid base64 identifier in node id '{}'", text))?),
+            _ => return Err(anyhow::anyhow!("invalid node id '{}' (expected e.g. \"ns=2;s=Temperature\" or \"i=2258\")", text)),
+        };
+        Ok(Self { namespace, identifier })
+    }
+}
+
+impl fmt::Display for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.namespace != 0 {
+            write!(f, "ns={};", self.namespace)?;
+        }
+        match &self.identifier {
+            Identifier::Numeric(id) => write!(f, "i={}", id),
+            Identifier::String(id) => write!(f, "s={}", id),
+            Identifier::Guid(id) => write!(f, "g={}", id),
+            Identifier::Opaque(id) => write!(f, "b={}", BASE64.encode(id)),
+        }
+    }
+}
+
+/// Value of a Read / data change, with its built-in type
+#[derive(Debug, Clone)]
+pub struct DataValue {
+    pub value: Value,
+    /// Built-in type ID of the variant (0 for null)
+    pub type_id: u8,
+    pub status: u32,
+    pub source_timestamp: Option<DateTime<Utc>>,
+    pub server_timestamp: Option<DateTime<Utc>>,
+}
+
+impl DataValue {
+    /// { node_id, name, value, data_type, status, source_timestamp, server_timestamp }
+    pub fn to_detailed(&self, node: &NodeRef) -> Value {
+        json!({
+            "node_id": node.node_id.to_string(),
+            "name": node.name,
+            "value": self.value,
+            "data_type": type_name(self.type_id),
+            "status": status_name(self.status),
+            "source_timestamp": self.source_timestamp.map(|t| t.to_rfc3339()),
+            "server_timestamp": self.server_timestamp.map(|t| t.to_rfc3339()),
+        })
+    }
+}
+
+/// Reference returned by Browse
+#[derive(Debug, Clone)]
+pub struct Reference {
+    pub node_id: String,
+    pub browse_name: String,
+    pub display_name: Option<String>,
+    pub node_class: u32,
+    pub type_definition: String,
+    pub reference_type: String,
+}
+
+/// Item to create in a subscription
+#[derive(Debug, Clone)]
+pub struct MonitoredItem {
+    pub node_id: NodeId,
+    pub client_handle: u32,
+    pub sampling_interval_ms: f64,
+    /// Absolute deadband of numeric values
+    pub deadband: Option<f64>,
+    pub queue_size: u32,
+}
+
+/// Result of one Publish call
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub subscription_id: u32,
+    pub sequence_number: u32,
+    /// (client handle, value) per data change, empty for keep-alives
+    pub changes: Vec<(u32, DataValue)>,
+}
+
+/// Login of a session
+#[derive(Debug, Clone, Default)]
+pub struct Credentials {
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+/// Built-in type name of a variant type ID
+pub fn type_name(type_id: u8) -> Option<&'static str> {
+    Some(match type_id {
+        1 => "Boolean",
+        2 => "SByte",
+        3 => "Byte",
+        4 => "Int16",
+        5 => "UInt16",
+        6 => "Int32",
+        7 => "UInt32",
+        8 => "Int64",
+        9 => "UInt64",
+        10 => "Float",
+        11 => "Double",
+        12 => "String",
+        13 => "DateTime",
+        14 => "Guid",
+        15 => "ByteString",
+        16 => "XmlElement",
+        17 => "NodeId",
+        18 => "ExpandedNodeId",
+        19 => "StatusCode",
+        20 => "QualifiedName",
+        21 => "LocalizedText",
+        22 => "ExtensionObject",
+        23 => "DataValue",
+        24 => "Variant",
+        25 => "DiagnosticInfo",
+        _ => return None,
+    })
+}
+
+/// Variant type ID of a writable built-in type name
+fn writable_type(name: &str) -> Option<u8> {
+    (1..=15).filter(|id| *id != 14)
+        .find(|id| type_name(*id).is_some_and(|type_name| type_name.eq_ignore_ascii_case(name)))
+}
+
+/// Name of a node class
+pub fn node_class_name(node_class: u32) -> &'static str {
+    match node_class {
+        1 => "Object",
+        2 => "Variable",
+        4 => "Method",
+        8 => "ObjectType",
+        16 => "VariableType",
+        32 => "ReferenceType",
+        64 => "DataType",
+        128 => "View",
+        _ => "Unspecified",
+    }
+}
+
+/// Whether a status code is Good or Uncertain
+pub fn status_is_usable(status: u32) -> bool {
+    status & 0x8000_0000 == 0
+}
+
+/// Symbolic name of a status code (hex for codes not listed here)
+pub fn status_name(status: u32) -> String {
+    let name = match status & 0xFFFF_0000 {
+        0x0000_0000 => "Good",
+        0x8001_0000 => "BadUnexpectedError",
+        0x8002_0000 => "BadInternalError",
+        0x8004_0000 => "BadResourceUnavailable",
+        0x8005_0000 => "BadCommunicationError",
+        0x8006_0000 => "BadEncodingError",
+        0x8007_0000 => "BadDecodingError",
+        0x8008_0000 => "BadEncodingLimitsExceeded",
+        0x800A_0000 => "BadTimeout",
+        0x800B_0000 => "BadServiceUnsupported",
+        0x800C_0000 => "BadShutdown",
+        0x800D_0000 => "BadServerNotConnected",
+        0x800E_0000 => "BadServerHalted",
+        0x800F_0000 => "BadNothingToDo",
+        0x8010_0000 => "BadTooManyOperations",
+        0x8013_0000 => "BadSecurityChecksFailed",
+        0x801F_0000 => "BadUserAccessDenied",
+        0x8020_0000 => "BadIdentityTokenInvalid",
+        0x8021_0000 => "BadIdentityTokenRejected",
+        0x8022_0000 => "BadSecureChannelIdInvalid",
+        0x8025_0000 => "BadSessionIdInvalid",
+        0x8026_0000 => "BadSessionClosed",
+        0x8027_0000 => "BadSessionNotActivated",
+        0x8028_0000 => "BadSubscriptionIdInvalid",
+        0x8032_0000 => "BadWaitingForInitialData",
+        0x8033_0000 => "BadNodeIdInvalid",
+        0x8034_0000 => "BadNodeIdUnknown",
+        0x8035_0000 => "BadAttributeIdInvalid",
+        0x803A_0000 => "BadNotReadable",
+        0x803B_0000 => "BadNotWritable",
+        0x803C_0000 => "BadOutOfRange",
+        0x803D_0000 => "BadNotSupported",
+        0x803E_0000 => "BadNotFound",
+        0x8043_0000 => "BadMonitoredItemFilterInvalid",
+        0x8044_0000 => "BadMonitoredItemFilterUnsupported",
+        0x8045_0000 => "BadFilterNotAllowed",
+        0x8056_0000 => "BadTooManySessions",
+        0x8074_0000 => "BadTypeMismatch",
+        0x8078_0000 => "BadTooManyPublishRequests",
+        0x8079_0000 => "BadNoSubscription",
+        0x807A_0000 => "BadSequenceNumberUnknown",
+        0x8083_0000 => "BadTcpEndpointUrlInvalid",
+        0x80AE_0000 => "BadConnectionClosed",
+        0x4092_0000 => "UncertainInitialValue",
+        _ => return format!("0x{:08X}", status),
+    };
+    name.to_string()
+}
+
+fn ticks(time: DateTime<Utc>) -> i64 {
+    time.timestamp_nanos_opt().map(|nanos| nanos / 100 + EPOCH_TICKS).unwrap_or(0)
+}
+
+/// Binary encoder
+#[derive(Default)]
+struct Encoder(Vec<u8>);
+
+impl Encoder {
+    fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    fn bool(&mut self, value: bool) {
+        self.0.push(u8::from(value));
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn i32(&mut self, value: i32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn i64(&mut self, value: i64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn f64(&mut self, value: f64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn string(&mut self, value: Option<&str>) {
+        self.byte_string(value.map(str::as_bytes));
+    }
+
+    fn byte_string(&mut self, value: Option<&[u8]>) {
+        match value {
+            Some(bytes) => {
+                self.i32(bytes.len() as i32);
+                self.0.extend_from_slice(bytes);
+            }
+            None => self.i32(-1),
+        }
+    }
+
+    fn node_id(&mut self, node_id: &NodeId) {
+        match &node_id.identifier {
+            Identifier::Numeric(id) if node_id.namespace == 0 && *id <= 0xFF => {
+                self.u8(0x00);
+                self.u8(*id as u8);
+            }
+            Identifier::Numeric(id) if node_id.namespace <= 0xFF && *id <= 0xFFFF => {
+                self.u8(0x01);
+                self.u8(node_id.namespace as u8);
+                self.u16(*id as u16);
+            }
+            Identifier::Numeric(id) => {
+                self.u8(0x02);
+                self.u16(node_id.namespace);
+                self.u32(*id);
+            }
+            Identifier::String(id) => {
+                self.u8(0x03);
+                self.u16(node_id.namespace);
+                self.string(Some(id));
+            }
+            Identifier::Guid(id) => {
+                self.u8(0x04);
+                self.u16(node_id.namespace);
+                self.guid(id);
+            }
+            Identifier::Opaque(id) => {
+                self.u8(0x05);
+                self.u16(node_id.namespace);
+                self.byte_string(Some(id));
+            }
+        }
+    }
+
+    fn guid(&mut self, guid: &uuid::Uuid) {
+        let (data1, data2, data3, data4) = guid.as_fields();
+        self.u32(data1);
+        self.u16(data2);
+        self.u16(data3);
+        self.0.extend_from_slice(data4);
+    }
+
+    fn extension_object(&mut self, type_id: u32, body: Option<&[u8]>) {
+        self.node_id(&NodeId::numeric(0, type_id));
+        match body {
+            Some(body) => {
+                self.u8(0x01);
+                self.byte_string(Some(body));
+            }
+            None => self.u8(0x00),
+        }
+    }
+
+    /// ReadValueId of a node attribute
+    fn read_value_id(&mut self, node_id: &NodeId, attribute: u32) {
+        self.node_id(node_id);
+        self.u32(attribute);
+        self.string(None);
+        self.u16(0);
+        self.string(None);
+    }
+
+    /// Variant of a JSON value (or array of values) as the given built-in type
+    fn variant(&mut self, type_id: u8, value: &Value) -> Result<()> {
+        match value {
+            Value::Array(values) => {
+                self.u8(type_id | 0x80);
+                self.i32(values.len() as i32);
+                for value in values {
+                    self.scalar(type_id, value)?;
+                }
+            }
+            value => {
+                self.u8(type_id);
+                self.scalar(type_id, value)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn scalar(&mut self, type_id: u8, value: &Value) -> Result<()> {
+        let expected = || anyhow::anyhow!("value {} can't be written as {}", value, type_name(type_id).unwrap_or("?"));
+        let integer = |min: i128, max: i128| -> Result<i128> {
+            let number = match value {
+                Value::Number(n) => n.as_i64().map(i128::from).or_else(|| n.as_u64().map(i128::from))
+                    .or_else(|| n.as_f64().filter(|f| f.fract() == 0.0).map(|f| f as i128)),
+                Value::Bool(flag) => Some(i128::from(*flag)),
+                Value::String(text) => text.trim().parse().ok(),
+                _ => None,
+            };
+            number.filter(|n| (min..=max).contains(n)).ok_or_else(expected)
+        };
+        let float = || -> Result<f64> {
+            match value {
+                Value::Number(n) => n.as_f64(),
+                Value::String(text) => text.trim().parse().ok(),
+                _ => None,
+            }.ok_or_else(expected)
+        };
+        match type_id {
+            1 => self.bool(match value {
+                Value::Bool(flag) => *flag,
+                Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+                Value::String(text) if text == "true" => true,
+                Value::String(text) if text == "false" => false,
+                _ => return Err(expected()),
+            }),
+            2 => self.u8(integer(i8::MIN.into(), i8::MAX.into())? as i8 as u8),
+            3 => self.u8(integer(0, u8::MAX.into())? as u8),
+            4 => self.0.extend_from_slice(&(integer(i16::MIN.into(), i16::MAX.into())? as i16).to_le_bytes()),
+            5 => self.u16(integer(0, u16::MAX.into())? as u16),
+            6 => self.i32(integer(i32::MIN.into(), i32::MAX.into())? as i32),
+            7 => self.u32(integer(0, u32::MAX.into())? as u32),
+            8 => self.i64(integer(i64::MIN.into(), i64::MAX.into())? as i64),
+            9 => self.0.extend_from_slice(&(integer(0, u64::MAX.into())? as u64).to_le_bytes()),
+            10 => self.0.extend_from_slice(&(float()? as f32).to_le_bytes()),
+            11 => self.f64(float()?),
+            12 => match value {
+                Value::String(text) => self.string(Some(text)),
+                Value::Null => self.string(None),
+                other => self.string(Some(&other.to_string())),
+            },
+            13 => {
+                let text = value.as_str().ok_or_else(expected)?;
+                let time = DateTime::parse_from_rfc3339(text).map_err(|_| expected())?;
+                self.i64(ticks(time.with_timezone(&Utc)));
+            }
+            15 => {
+                let text = value.as_str().ok_or_else(expected)?;
+                self.byte_string(Some(&BASE64.decode(text).map_err(|_| expected())?));
+            }
+            _ => return Err(expected()),
+        }
+        Ok(())
+    }
+}
+
+/// Binary decoder over a response body
+struct Decoder<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn take(&mut self, count: usize) -> Result<&'a [u8]> {
+        let end = self.position.checked_add(count).filter(|end| *end <= self.data.len())
+            .ok_or_else(|| anyhow::anyhow!("truncated response"))?;
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn array_of<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().expect("slice of length N"))
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn bool(&mut self) -> Result<bool> {
+        Ok(self.u8()? != 0)
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.array_of()?))
+    }
+
+    fn i16(&mut self) -> Result<i16> {
+        Ok(i16::from_le_bytes(self.array_of()?))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.array_of()?))
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_le_bytes(self.array_of()?))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.array_of()?))
+    }
+
+    fn i64(&mut self) -> Result<i64> {
+        Ok(i64::from_le_bytes(self.array_of()?))
+    }
+
+    fn f32(&mut self) -> Result<f32> {
+        Ok(f32::from_le_bytes(self.array_of()?))
+    }
+
+    fn f64(&mut self) -> Result<f64> {
+        Ok(f64::from_le_bytes(self.array_of()?))
+    }
+
+    fn byte_string(&mut self) -> Result<Option<Vec<u8>>> {
+        match self.i32()? {
+            length if length < 0 => Ok(None),
+            length => Ok(Some(self.take(length as usize)?.to_vec())),
+        }
+    }
+
+    fn string(&mut self) -> Result<Option<String>> {
+        Ok(self.byte_string()?.map(|bytes| String::from_utf8_lossy(&bytes).into_owned()))
+    }
+
+    fn date_time(&mut self) -> Result<Option<DateTime<Utc>>> {
+        let ticks = self.i64()?;
+        if ticks <= 0 || ticks == i64::MAX {
+            return Ok(None);
+        }
+        let unix = ticks - EPOCH_TICKS;
+        Ok(Utc.timestamp_opt(unix.div_euclid(10_000_000), (unix.rem_euclid(10_000_000) * 100) as u32).single())
+    }
+
+    fn guid(&mut self) -> Result<uuid::Uuid> {
+        let (data1, data2, data3) = (self.u32()?, self.u16()?, self.u16()?);
+        let data4: [u8; 8] = self.array_of()?;
+        Ok(uuid::Uuid::from_fields(data1, data2, data3, &data4))
+    }
+
+    /// Array of elements (null arrays are empty)
+    fn array<T>(&mut self, mut element: impl FnMut(&mut Self) -> Result<T>) -> Result<Vec<T>> {
+        let length = self.i32()?;
+        if length <= 0 {
+            return Ok(Vec::new());
+        }
+        // Every element takes at least one byte
+        if length as usize > self.data.len() - self.position {
+            return Err(anyhow::anyhow!("array length {} exceeds the response", length));
+        }
+        (0..length).map(|_| element(self)).collect()
+    }
+
+    /// NodeId, plus the flag bits of ExpandedNodeIds
+    fn node_id_with_flags(&mut self) -> Result<(NodeId, u8)> {
+        let encoding = self.u8()?;
+        let node_id = match encoding & 0x0F {
+            0x00 => NodeId::numeric(0, self.u8()? as u32),
+            0x01 => {
+                let namespace = self.u8()? as u16;
+                NodeId::numeric(namespace, self.u16()? as u32)
+            }
+            0x02 => {
+                let namespace = self.u16()?;
+                NodeId::numeric(namespace, self.u32()?)
+            }
+            0x03 => NodeId { namespace: self.u16()?, identifier: Identifier::String(self.string()?.unwrap_or_default()) },
+            0x04 => NodeId { namespace: self.u16()?, identifier: Identifier::Guid(self.guid()?) },
+            0x05 => NodeId { namespace: self.u16()?, identifier: Identifier::Opaque(self.byte_string()?.unwrap_or_default()) },
+            other => return Err(anyhow::anyhow!("invalid node id encoding {:#04x}", other)),
+        };
+        Ok((node_id, encoding & 0xC0))
+    }
+
+    fn node_id(&mut self) -> Result<NodeId> {
+        Ok(self.node_id_with_flags()?.0)
+    }
+
+    /// ExpandedNodeId as text ("nsu=<uri>;s=..." when it carries a namespace URI)
+    fn expanded_node_id(&mut self) -> Result<String> {
+        let (node_id, flags) = self.node_id_with_flags()?;
+        let uri = if flags & 0x80 != 0 { self.string()? } else { None };
+        if flags & 0x40 != 0 {
+            self.u32()?;
+        }
+        Ok(match uri {
+            Some(uri) => {
+                let text = node_id.to_string();
+                let identifier = text.split_once(';').filter(|_| node_id.namespace != 0).map(|(_, id)| id.to_string()).unwrap_or(text);
+                format!("nsu={};{}", uri, identifier)
+            }
+            None => node_id.to_string(),
+        })
+    }
+
+    /// QualifiedName as "name" (namespace 0) or "ns:name"
+    fn qualified_name(&mut self) -> Result<String> {
+        let namespace = self.u16()?;
+        let name = self.string()?.unwrap_or_default();
+        Ok(if namespace == 0 { name } else { format!("{}:{}", namespace, name) })
+    }
+
+    /// Text of a LocalizedText
+    fn localized_text(&mut self) -> Result<Option<String>> {
+        let mask = self.u8()?;
+        if mask & 0x01 != 0 {
+            self.string()?;
+        }
+        if mask & 0x02 != 0 { self.string() } else { Ok(None) }
+    }
+
+    /// Type and body of an ExtensionObject
+    fn extension_object(&mut self) -> Result<(NodeId, Option<Vec<u8>>)> {
+        let type_id = self.node_id()?;
+        let body = match self.u8()? {
+            0x00 => None,
+            0x01 | 0x02 => self.byte_string()?,
+            other => return Err(anyhow::anyhow!("invalid extension object encoding {:#04x}", other)),
+        };
+        Ok((type_id, body))
+    }
+
+    fn diagnostic_info(&mut self) -> Result<()> {
+        let mask = self.u8()?;
+        for bit in [0x01, 0x02, 0x04, 0x08] {
+            if mask & bit != 0 {
+                self.i32()?;
+            }
+        }
+        if mask & 0x10 != 0 {
+            self.string()?;
+        }
+        if mask & 0x20 != 0 {
+            self.u32()?;
+        }
+        if mask & 0x40 != 0 {
+            self.diagnostic_info()?;
+        }
+        Ok(())
+    }
+
+    /// Variant as (built-in type ID, JSON value); multi-dimensional arrays are flattened
+    fn variant(&mut self) -> Result<(u8, Value)> {
+        let mask = self.u8()?;
+        let type_id = mask & 0x3F;
+        let value = if mask & 0x80 != 0 {
+            let values = self.array(|decoder| decoder.scalar(type_id))?;
+            if mask & 0x40 != 0 {
+                self.array(|decoder| decoder.i32())?;
+            }
+            Value::Array(values)
+        } else {
+            self.scalar(type_id)?
+        };
+        Ok((type_id, value))
+    }
+
+    fn scalar(&mut self, type_id: u8) -> Result<Value> {
+        let text = |text: Option<String>| text.map(Value::String).unwrap_or(Value::Null);
+        Ok(match type_id {
+            0 => Value::Null,
+            1 => json!(self.bool()?),
+            2 => json!(self.u8()? as i8),
+            3 => json!(self.u8()?),
+            4 => json!(self.i16()?),
+            5 => json!(self.u16()?),
+            6 => json!(self.i32()?),
+            7 => json!(self.u32()?),
+            8 => json!(self.i64()?),
+            9 => json!(self.u64()?),
+            10 => json!(self.f32()? as f64),
+            11 => json!(self.f64()?),
+            12 | 16 => text(self.string()?),
+            13 => self.date_time()?.map(|time| json!(time.to_rfc3339())).unwrap_or(Value::Null),
+            14 => json!(self.guid()?.to_string()),
+            15 => self.byte_string()?.map(|bytes| json!(BASE64.encode(bytes))).unwrap_or(Value::Null),
+            17 => json!(self.node_id()?.to_string()),
+            18 => json!(self.expanded_node_id()?),
+            19 => json!(status_name(self.u32()?)),
+            20 => json!(self.qualified_name()?),
+            21 => text(self.localized_text()?),
+            22 => {
+                let (type_id, body) = self.extension_object()?;
+                json!({ "type_id": type_id.to_string(), "body": body.map(|body| BASE64.encode(body)) })
+            }
+            23 => self.data_value()?.value,
+            24 => self.variant()?.1,
+            25 => {
+                self.diagnostic_info()?;
+                Value::Null
+            }
+            other => return Err(anyhow::anyhow!("unsupported variant type {}", other)),
+        })
+    }
+
+    fn data_value(&mut self) -> Result<DataValue> {
+        let mask = self.u8()?;
+        let (type_id, value) = if mask & 0x01 != 0 { self.variant()? } else { (0, Value::Null) };
+        let status = if mask & 0x02 != 0 { self.u32()? } else { 0 };
+        let source_timestamp = if mask & 0x04 != 0 { self.date_time()? } else { None };
+        if mask & 0x10 != 0 {
+            self.u16()?;
+        }
+        let server_timestamp = if mask & 0x08 != 0 { self.date_time()? } else { None };
+        if mask & 0x20 != 0 {
+            self.u16()?;
+        }
+        Ok(DataValue { value, type_id, status, source_timestamp, server_timestamp })
+    }
+
+    /// ResponseHeader, returning the service result
+    fn response_header(&mut self) -> Result<u32> {
+        self.date_time()?;
+        self.u32()?;
+        let service_result = self.u32()?;
+        self.diagnostic_info()?;
+        self.array(|decoder| decoder.string())?;
+        self.extension_object()?;
+        Ok(service_result)
+    }
+}
+
+/// Endpoint and login from resolved secrets [endpoint, username, password] (login optional)
+pub fn connection_from_secrets(secrets: &[String]) -> (String, Credentials) {
+    let credentials = Credentials {
+        username: secrets.get(1).filter(|username| !username.is_empty()).cloned(),
+        password: secrets.get(2).cloned(),
+    };
+    (secrets.first().cloned().unwrap_or_default(), credentials)
+}
+
+/// Host:port and URL of an opc.tcp endpoint
+pub fn parse_endpoint(endpoint: &str) -> Result<(String, String)> {
+    let url = endpoint.trim();
+    let rest = url.strip_prefix("opc.tcp://")
+        .ok_or_else(|| anyhow::anyhow!("invalid OPC UA endpoint (expected opc.tcp://host:port)"))?;
+    let authority = rest.split('/').next().unwrap_or_default();
+    if authority.is_empty() {
+        return Err(anyhow::anyhow!("OPC UA endpoint has no host"));
+    }
+    let has_port = authority.rsplit_once(':')
+        .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok() && (!host.contains(':') || host.ends_with(']')));
+    let address = if has_port { authority.to_string() } else { format!("{}:4840", authority) };
+    Ok((address, url.to_string()))
+}
+
+/// Session on an OPC UA server over a secure channel with security mode None
+pub struct OpcUaClient {
+    stream: TcpStream,
+    endpoint_url: String,
+    timeout: Duration,
+    channel_id: u32,
+    token_id: u32,
+    renew_at: Instant,
+    sequence_number: u32,
+    request_id: u32,
+    request_handle: u32,
+    authentication_token: NodeId,
+    /// Largest message the server accepts (0 = no limit)
+    server_max_message: u32,
+}
+
+impl OpcUaClient {
+    /// Connect, open a secure channel and activate a session
+    pub async fn connect(endpoint: &str, credentials: &Credentials, timeout: Duration) -> Result<Self> {
+        let (address, endpoint_url) = parse_endpoint(endpoint)?;
+        let stream = tokio::time::timeout(timeout, TcpStream::connect(&address)).await
+            .map_err(|_| anyhow::anyhow!("connecting to {} timed out", address))?
+            .map_err(|e| anyhow::anyhow!("failed to connect to {}: {}", address, e))?;
+        stream.set_nodelay(true)?;
+
+        let mut client = Self {
+            stream,
+            endpoint_url,
+            timeout,
+            channel_id: 0,
+            token_id: 0,
+            renew_at: Instant::now(),
+            sequence_number: 0,
+            request_id: 0,
+            request_handle: 0,
+            authentication_token: NodeId::null(),
+            server_max_message: 0,
+        };
+        tokio::time::timeout(timeout, client.hello()).await
+            .map_err(|_| anyhow::anyhow!("no Acknowledge from {} within {:?}", address, timeout))??;
+        client.open_channel(false).await?;
+        client.create_session(credentials).await?;
+        Ok(client)
+    }
+
+    /// Close the session (deleting its subscriptions) and the secure channel
+    pub async fn close(mut self) {
+        let mut request = self.request_header(ids::CLOSE_SESSION_REQUEST);
+        request.bool(true);
+        if let Err(e) = self.call(request, ids::CLOSE_SESSION_RESPONSE, self.timeout).await {
+            tracing::debug!("🏭 OPC UA CloseSession failed: {}", e);
+        }
+        let request = self.request_header(ids::CLOSE_SECURE_CHANNEL_REQUEST);
+        self.request_id = self.request_id.wrapping_add(1);
+        let _ = self.send_request(b"CLO", self.request_id, &request.0).await;
+        let _ = self.stream.shutdown().await;
+    }
+
+    /// Read an attribute of nodes (one DataValue per node, in order)
+    pub async fn read(&mut self, nodes: &[NodeId], attribute: u32) -> Result<Vec<DataValue>> {
+        let mut request = self.request_header(ids::READ_REQUEST);
+        request.f64(0.0);
+        request.i32(2);
+        request.i32(nodes.len() as i32);
+        for node_id in nodes {
+            request.read_value_id(node_id, attribute);
+        }
+        let response = self.call(request, ids::READ_RESPONSE, self.timeout).await?;
+        let mut decoder = Decoder::new(&response);
+        let values = decoder.array(|decoder| decoder.data_value())?;
+        if values.len() != nodes.len() {
+            return Err(anyhow::anyhow!("Read returned {} values for {} nodes", values.len(), nodes.len()));
+        }
+        Ok(values)
+    }
+
+    /// Write values, as variants of the given built-in types, to the Value attribute of nodes
+    pub async fn write(&mut self, values: &[(NodeId, u8, Value)]) -> Result<Vec<u32>> {
+        let mut request = self.request_header(ids::WRITE_REQUEST);
+        request.i32(values.len() as i32);
+        for (node_id, type_id, value) in values {
+            request.node_id(node_id);
+            request.u32(ATTRIBUTE_VALUE);
+            request.string(None);
+            request.u8(0x01);
+            request.variant(*type_id, value)
+                .map_err(|e| anyhow::anyhow!("{}: {}", node_id, e))?;
+        }
+        let response = self.call(request, ids::WRITE_RESPONSE, self.timeout).await?;
+        let results = Decoder::new(&response).array(|decoder| decoder.u32())?;
+        if results.len() != values.len() {
+            return Err(anyhow::anyhow!("Write returned {} results for {} nodes", results.len(), values.len()));
+        }
+        Ok(results)
+    }
+
+    /// Forward hierarchical references of a node, following continuation points
+    pub async fn browse(&mut self, node_id: &NodeId) -> Result<Vec<Reference>> {
+        let mut request = self.request_header(ids::BROWSE_REQUEST);
+        request.node_id(&NodeId::null());
+        request.i64(0);
+        request.u32(0);
+        request.u32(1000);
+        request.i32(1);
+        request.node_id(node_id);
+        request.i32(0);
+        request.node_id(&NodeId::numeric(0, ids::HIERARCHICAL_REFERENCES));
+        request.bool(true);
+        request.u32(0);
+        request.u32(0x3F);
+
+        let mut references = Vec::new();
+        let mut response = self.call(request, ids::BROWSE_RESPONSE, self.timeout).await?;
+        loop {
+            let mut decoder = Decoder::new(&response);
+            let mut results = decoder.array(|decoder| {
+                let status = decoder.u32()?;
+                let continuation = decoder.byte_string()?;
+                let references = decoder.array(|decoder| {
+                    let reference_type = decoder.node_id()?.to_string();
+                    decoder.bool()?;
+                    Ok(Reference {
+                        node_id: decoder.expanded_node_id()?,
+                        browse_name: decoder.qualified_name()?,
+                        display_name: decoder.localized_text()?,
+                        node_class: decoder.i32()? as u32,
+                        type_definition: decoder.expanded_node_id()?,
+                        reference_type,
+                    })
+                })?;
+                Ok((status, continuation, references))
+            })?;
+            let (status, continuation, page) = results.pop()
+                .ok_or_else(|| anyhow::anyhow!("Browse returned no result"))?;
+            if !status_is_usable(status) {
+                return Err(anyhow::anyhow!("Browse of {} failed: {}", node_id, status_name(status)));
+            }
+            references.extend(page);
+
+            let Some(continuation) = continuation.filter(|point| !point.is_empty()) else { break };
+            let mut request = self.request_header(ids::BROWSE_NEXT_REQUEST);
+            request.bool(false);
+            request.i32(1);
+            request.byte_string(Some(&continuation));
+            response = self.call(request, ids::BROWSE_NEXT_RESPONSE, self.timeout).await?;
+        }
+        Ok(references)
+    }
+
+    /// Create a subscription, returning (subscription ID, revised publishing interval, keep-alive count)
+    pub async fn create_subscription(&mut self, publishing_interval_ms: f64) -> Result<(u32, f64, u32)> {
+        let mut request = self.request_header(ids::CREATE_SUBSCRIPTION_REQUEST);
+        request.f64(publishing_interval_ms);
+        request.u32(60);
+        request.u32(10);
+        request.u32(1000);
+        request.bool(true);
+        request.u8(0);
+        let response = self.call(request, ids::CREATE_SUBSCRIPTION_RESPONSE, self.timeout).await?;
+        let mut decoder = Decoder::new(&response);
+        let subscription_id = decoder.u32()?;
+        let interval = decoder.f64()?;
+        decoder.u32()?;
+        Ok((subscription_id, interval, decoder.u32()?))
+    }
+
+    /// Create reporting monitored items on the Value attribute, returning one status per item
+    pub async fn create_monitored_items(&mut self, subscription_id: u32, items: &[MonitoredItem]) -> Result<Vec<u32>> {
+        let mut request = self.request_header(ids::CREATE_MONITORED_ITEMS_REQUEST);
+        request.u32(subscription_id);
+        request.i32(2);
+        request.i32(items.len() as i32);
+        for item in items {
+            request.read_value_id(&item.node_id, ATTRIBUTE_VALUE);
+            request.i32(2);
+            request.u32(item.client_handle);
+            request.f64(item.sampling_interval_ms);
+            match item.deadband {
+                Some(deadband) => {
+                    let mut filter = Encoder::default();
+                    filter.i32(1);
+                    filter.u32(1);
+                    filter.f64(deadband);
+                    request.extension_object(ids::DATA_CHANGE_FILTER, Some(&filter.0));
+                }
+                None => request.extension_object(0, None),
+            }
+            request.u32(item.queue_size);
+            request.bool(true);
+        }
+        let response = self.call(request, ids::CREATE_MONITORED_ITEMS_RESPONSE, self.timeout).await?;
+        Decoder::new(&response).array(|decoder| {
+            let status = decoder.u32()?;
+            decoder.u32()?;
+            decoder.f64()?;
+            decoder.u32()?;
+            decoder.extension_object()?;
+            Ok(status)
+        })
+    }
+
+    /// Wait for the next notification message (or keep-alive), acknowledging earlier ones
+    pub async fn publish(&mut self, acknowledgements: &[(u32, u32)], wait: Duration) -> Result<Notification> {
+        let mut request = self.request_header_with_hint(ids::PUBLISH_REQUEST, wait);
+        request.i32(acknowledgements.len() as i32);
+        for (subscription_id, sequence_number) in acknowledgements {
+            request.u32(*subscription_id);
+            request.u32(*sequence_number);
+        }
+        let response = self.call(request, ids::PUBLISH_RESPONSE, wait + self.timeout).await?;
+        let mut decoder = Decoder::new(&response);
+        let subscription_id = decoder.u32()?;
+        decoder.array(|decoder| decoder.u32())?;
+        decoder.bool()?;
+        let sequence_number = decoder.u32()?;
+        decoder.date_time()?;
+
+        let mut changes = Vec::new();
+        for (type_id, body) in decoder.array(|decoder| decoder.extension_object())? {
+            let body = body.unwrap_or_default();
+            match type_id.identifier {
+                Identifier::Numeric(ids::DATA_CHANGE_NOTIFICATION) => {
+                    let mut body = Decoder::new(&body);
+                    changes.extend(body.array(|decoder| Ok((decoder.u32()?, decoder.data_value()?)))?);
+                }
+                Identifier::Numeric(ids::STATUS_CHANGE_NOTIFICATION) => {
+                    let status = Decoder::new(&body).u32()?;
+                    return Err(anyhow::anyhow!("subscription {} ended: {}", subscription_id, status_name(status)));
+                }
+                _ => {}
+            }
+        }
+        Ok(Notification { subscription_id, sequence_number, changes })
+    }
+
+    async fn hello(&mut self) -> Result<()> {
+        let mut hello = Encoder::default();
+        hello.u32(0);
+        hello.u32(BUFFER_SIZE);
+        hello.u32(BUFFER_SIZE);
+        hello.u32(MAX_MESSAGE_SIZE);
+        hello.u32(0);
+        hello.string(Some(&self.endpoint_url));
+        self.send(b"HEL", &hello.0).await?;
+
+        let (message_type, body) = self.receive_chunks().await?;
+        if &message_type != b"ACK" {
+            return Err(anyhow::anyhow!("expected Acknowledge, got {}", String::from_utf8_lossy(&message_type)));
+        }
+        let mut decoder = Decoder::new(&body);
+        decoder.u32()?;
+        decoder.u32()?;
+        decoder.u32()?;
+        self.server_max_message = decoder.u32()?;
+        Ok(())
+    }
+
+    /// Issue (or renew) the secure channel security token
+    async fn open_channel(&mut self, renew: bool) -> Result<()> {
+        let mut request = self.request_header(ids::OPEN_SECURE_CHANNEL_REQUEST);
+        request.u32(0);
+        request.i32(i32::from(renew));
+        request.i32(1);
+        request.byte_string(Some(&[]));
+        request.u32(CHANNEL_LIFETIME_MS);
+
+        let response = self.exchange(b"OPN", request, ids::OPEN_SECURE_CHANNEL_RESPONSE, self.timeout).await?;
+        let mut decoder = Decoder::new(&response);
+        decoder.u32()?;
+        self.channel_id = decoder.u32()?;
+        self.token_id = decoder.u32()?;
+        decoder.date_time()?;
+        let lifetime = decoder.u32()?.max(10_000);
+        self.renew_at = Instant::now() + Duration::from_millis(lifetime as u64 * 3 / 4);
+        tracing::debug!("🏭 OPC UA secure channel {} token {} ({} ms)", self.channel_id, self.token_id, lifetime);
+        Ok(())
+    }
+
+    async fn create_session(&mut self, credentials: &Credentials) -> Result<()> {
+        let mut request = self.request_header(ids::CREATE_SESSION_REQUEST);
+        request.string(Some("urn:mechaway:client"));
+        request.string(Some("urn:mechaway"));
+        request.u8(0x02);
+        request.string(Some("Mechaway"));
+        request.i32(1);
+        request.string(None);
+        request.string(None);
+        request.i32(0);
+        request.string(None);
+        request.string(Some(&self.endpoint_url.clone()));
+        request.string(Some(&format!("mechaway-{}", uuid::Uuid::new_v4())));
+        let nonce: Vec<u8> = [uuid::Uuid::new_v4(), uuid::Uuid::new_v4()].iter().flat_map(|id| *id.as_bytes()).collect();
+        request.byte_string(Some(&nonce));
+        request.byte_string(None);
+        request.f64(SESSION_TIMEOUT_MS);
+        request.u32(MAX_MESSAGE_SIZE);
+
+        let response = self.call(request, ids::CREATE_SESSION_RESPONSE, self.timeout).await?;
+        let mut decoder = Decoder::new(&response);
+        decoder.node_id()?;
+        let authentication_token = decoder.node_id()?;
+        decoder.f64()?;
+        decoder.byte_string()?;
+        decoder.byte_string()?;
+        // (security policy URI, [(policy ID, token type, token security policy URI)]) per endpoint
+        let endpoints = decoder.array(|decoder| {
+            decoder.string()?;
+            decoder.string()?;
+            decoder.string()?;
+            decoder.localized_text()?;
+            decoder.i32()?;
+            decoder.string()?;
+            decoder.string()?;
+            decoder.array(|decoder| decoder.string())?;
+            decoder.byte_string()?;
+            decoder.i32()?;
+            let security_policy = decoder.string()?.unwrap_or_default();
+            let policies = decoder.array(|decoder| {
+                let policy_id = decoder.string()?.unwrap_or_default();
+                let token_type = decoder.i32()?;
+                decoder.string()?;
+                decoder.string()?;
+                Ok((policy_id, token_type, decoder.string()?.unwrap_or_default()))
+            })?;
+            decoder.string()?;
+            decoder.u8()?;
+            Ok((security_policy, policies))
+        })?;
+        self.authentication_token = authentication_token;
+
+        // IDENTITY: the token policy of the None endpoint matching the login
+        let token_type = if credentials.username.is_some() { 1 } else { 0 };
+        let policies: Vec<(String, String)> = endpoints.into_iter()
+            .filter(|(security_policy, _)| security_policy == SECURITY_POLICY_NONE)
+            .flat_map(|(_, policies)| policies)
+            .filter(|(_, policy_type, _)| *policy_type == token_type)
+            .map(|(policy_id, _, security_policy)| (policy_id, security_policy))
+            .collect();
+        let policy_id = match policies.iter().find(|(_, security_policy)| security_policy.is_empty() || security_policy == SECURITY_POLICY_NONE) {
+            Some((policy_id, _)) => policy_id.clone(),
+            None if !policies.is_empty() => {
+                return Err(anyhow::anyhow!("server only accepts encrypted passwords, which security mode None can't send"));
+            }
+            // Servers that don't list endpoints in CreateSession
+            None => if token_type == 1 { "username" } else { "anonymous" }.to_string(),
+        };
+
+        let mut token = Encoder::default();
+        token.string(Some(&policy_id));
+        let token_encoding = match &credentials.username {
+            Some(username) => {
+                token.string(Some(username));
+                token.byte_string(Some(credentials.password.as_deref().unwrap_or_default().as_bytes()));
+                token.string(None);
+                ids::USER_NAME_IDENTITY_TOKEN
+            }
+            None => ids::ANONYMOUS_IDENTITY_TOKEN,
+        };
+
+        let mut request = self.request_header(ids::ACTIVATE_SESSION_REQUEST);
+        request.string(None);
+        request.byte_string(None);
+        request.i32(0);
+        request.i32(0);
+        request.extension_object(token_encoding, Some(&token.0));
+        request.string(None);
+        request.byte_string(None);
+        self.call(request, ids::ACTIVATE_SESSION_RESPONSE, self.timeout).await?;
+        Ok(())
+    }
+
+    fn request_header(&mut self, request_type: u32) -> Encoder {
+        let timeout = self.timeout;
+        self.request_header_with_hint(request_type, timeout)
+    }
+
+    /// Encoder starting with the request type and RequestHeader
+    fn request_header_with_hint(&mut self, request_type: u32, timeout_hint: Duration) -> Encoder {
+        self.request_handle = self.request_handle.wrapping_add(1);
+        let mut request = Encoder::default();
+        request.node_id(&NodeId::numeric(0, request_type));
+        // Secure channel requests don't belong to the session
+        match request_type {
+            ids::OPEN_SECURE_CHANNEL_REQUEST | ids::CLOSE_SECURE_CHANNEL_REQUEST => request.node_id(&NodeId::null()),
+            _ => request.node_id(&self.authentication_token),
+        }
+        request.i64(ticks(Utc::now()));
+        request.u32(self.request_handle);
+        request.u32(0);
+        request.string(None);
+        request.u32(timeout_hint.as_millis().min(u32::MAX as u128) as u32);
+        request.extension_object(0, None);
+        request
+    }
+
+    /// Send a service request (renewing the channel token when due) and return
+    /// the response body after its ResponseHeader
+    async fn call(&mut self, request: Encoder, response_type: u32, timeout: Duration) -> Result<Vec<u8>> {
+        if Instant::now() >= self.renew_at {
+            self.open_channel(true).await?;
+        }
+        self.exchange(b"MSG", request, response_type, timeout).await
+    }
+
+    async fn exchange(&mut self, message_type: &[u8; 3], request: Encoder, response_type: u32, timeout: Duration) -> Result<Vec<u8>> {
+        if self.server_max_message != 0 && request.0.len() as u32 + 64 > self.server_max_message {
+            return Err(anyhow::anyhow!("request of {} bytes exceeds the server's message limit", request.0.len()));
+        }
+
+        self.request_id = self.request_id.wrapping_add(1);
+        let request_id = self.request_id;
+        let exchange = async {
+            self.send_request(message_type, request_id, &request.0).await?;
+            loop {
+                let (received_type, body) = self.receive_chunks().await?;
+                let mut decoder = Decoder::new(&body);
+                match &received_type {
+                    b"OPN" => {
+                        decoder.u32()?;
+                        decoder.string()?;
+                        decoder.byte_string()?;
+                        decoder.byte_string()?;
+                    }
+                    b"MSG" => {
+                        decoder.u32()?;
+                        decoder.u32()?;
+                    }
+                    other => return Err(anyhow::anyhow!("unexpected {} message", String::from_utf8_lossy(other))),
+                }
+                decoder.u32()?;
+                // Late answers to earlier (timed out) requests are skipped
+                if decoder.u32()? == request_id {
+                    return Ok(body[decoder.position..].to_vec());
+                }
+            }
+        };
+        let response = tokio::time::timeout(timeout, exchange).await
+            .map_err(|_| anyhow::anyhow!("no response within {:?}", timeout))??;
+
+        let mut decoder = Decoder::new(&response);
+        let received_type = decoder.node_id()?;
+        let service_result = decoder.response_header()?;
+        if received_type == NodeId::numeric(0, ids::SERVICE_FAULT) || !status_is_usable(service_result) {
+            return Err(anyhow::anyhow!("service failed: {}", status_name(service_result)));
+        }
+        if received_type != NodeId::numeric(0, response_type) {
+            return Err(anyhow::anyhow!("unexpected response type {}", received_type));
+        }
+        Ok(response[decoder.position..].to_vec())
+    }
+
+    async fn send_request(&mut self, message_type: &[u8; 3], request_id: u32, body: &[u8]) -> Result<()> {
+        self.sequence_number = self.sequence_number.wrapping_add(1).max(1);
+        let mut message = Encoder::default();
+        message.u32(self.channel_id);
+        if message_type == b"OPN" {
+            message.string(Some(SECURITY_POLICY_NONE));
+            message.byte_string(None);
+            message.byte_string(None);
+        } else {
+            message.u32(self.token_id);
+        }
+        message.u32(self.sequence_number);
+        message.u32(request_id);
+        message.0.extend_from_slice(body);
+        self.send(message_type, &message.0).await
+    }
+
+    /// Write a message as one final chunk
+    async fn send(&mut self, message_type: &[u8; 3], body: &[u8]) -> Result<()> {
+        let mut chunk = Vec::with_capacity(8 + body.len());
+        chunk.extend_from_slice(message_type);
+        chunk.push(b'F');
+        chunk.extend_from_slice(&(8 + body.len() as u32).to_le_bytes());
+        chunk.extend_from_slice(body);
+        self.stream.write_all(&chunk).await?;
+        Ok(())
+    }
+
+    /// Read a complete message, joining intermediate chunks
+    /// 
+    /// Returns the message type and the body after the 8-byte header (the
+    /// security and sequence headers of the first chunk are kept; those of
+    /// later chunks are dropped).
+    async fn receive_chunks(&mut self) -> Result<([u8; 3], Vec<u8>)> {
+        let mut message: Option<([u8; 3], Vec<u8>)> = None;
+        loop {
+            let mut header = [0u8; 8];
+            self.stream.read_exact(&mut header).await?;
+            let message_type = [header[0], header[1], header[2]];
+            let size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+            if !(8..=MAX_MESSAGE_SIZE).contains(&size) {
+                return Err(anyhow::anyhow!("invalid message size {}", size));
+            }
+            let mut body = vec![0u8; size as usize - 8];
+            self.stream.read_exact(&mut body).await?;
+
+            if &message_type == b"ERR" {
+                let mut decoder = Decoder::new(&body);
+                let status = decoder.u32()?;
+                let reason = decoder.string()?.unwrap_or_default();
+                return Err(anyhow::anyhow!("server error {}: {}", status_name(status), reason));
+            }
+            match header[3] {
+                b'A' => return Err(anyhow::anyhow!("server aborted the response")),
+                b'C' | b'F' => {}
+                other => return Err(anyhow::anyhow!("invalid chunk type {:#04x}", other)),
+            }
+            match &mut message {
+                None => message = Some((message_type, body)),
+                Some((_, joined)) => {
+                    // Later MSG chunks repeat the 16 bytes of channel, token, sequence and request IDs
+                    joined.extend_from_slice(body.get(16..).unwrap_or_default());
+                    if joined.len() > MAX_MESSAGE_SIZE as usize {
+                        return Err(anyhow::anyhow!("response exceeds {} bytes", MAX_MESSAGE_SIZE));
+                    }
+                }
+            }
+            if header[3] == b'F' {
+                return Ok(message.expect("first chunk stored"));
+            }
+        }
+    }
+}
+
+/// Node of a read / write / subscription with the item field it maps to
+#[derive(Debug, Clone)]
+pub struct NodeRef {
+    pub node_id: NodeId,
+    pub name: String,
+    /// Built-in type of written values (read from the node when not given)
+    pub type_id: Option<u8>,
+}
+
+impl NodeRef {
+    /// Parse "ns=2;s=Temp" or { "node_id": "ns=2;s=Temp", "name": "temp", "type": "Double" }
+    pub fn from_value(value: &Value) -> Result<Self> {
+        let (node_id, name, type_name) = match value {
+            Value::String(node_id) => (node_id.as_str(), None, None),
+            Value::Object(node) => (
+                node.get("node_id").and_then(|id| id.as_str())
+                    .ok_or_else(|| anyhow::anyhow!("every node needs a 'node_id'"))?,
+                node.get("name").and_then(|n| n.as_str()).filter(|n| !n.is_empty()),
+                node.get("type").and_then(|t| t.as_str()),
+            ),
+            _ => return Err(anyhow::anyhow!("nodes must be node id strings or objects with a 'node_id'")),
+        };
+        let type_id = type_name
+            .map(|name| writable_type(name)
+                .ok_or_else(|| anyhow::anyhow!("unsupported type '{}' (expected e.g. Boolean, Int32, Float, Double, String)", name)))
+            .transpose()?;
+        Ok(Self {
+            node_id: NodeId::parse(node_id)?,
+            name: name.map(str::to_string).unwrap_or_else(|| node_id.trim().to_string()),
+            type_id,
+        })
+    }
+}
+
+/// What an OpcUa node does
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OpcUaOperation {
+    Read,
+    Write,
+    Browse,
+}
+
+/// OpcUa node configuration
+#[derive(Debug, Clone)]
+pub struct OpcUaParams {
+    pub operation: OpcUaOperation,
+    pub nodes: Vec<NodeRef>,
+    /// Read: one { node_id, name, value, status, ... } item per node
+    pub detailed: bool,
+    /// Browse: start node and depth
+    pub browse_root: NodeId,
+    pub max_depth: u32,
+    pub max_nodes: usize,
+    pub timeout: Duration,
+}
+
+impl OpcUaParams {
+    /// Parse node params
+    /// 
+    /// Expected params: { "operation": "read|write|browse", "nodes": ["ns=2;s=Temp", { "node_id", "name", "type" }],
+    ///                    "detailed": false, "node_id": "i=85", "max_depth": 1, "max_nodes": 1000, "timeout_secs": 10 }
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        let operation = match params.get("operation").and_then(|o| o.as_str()).unwrap_or("read") {
+            "read" => OpcUaOperation::Read,
+            "write" => OpcUaOperation::Write,
+            "browse" => OpcUaOperation::Browse,
+            other => return Err(anyhow::anyhow!("unsupported operation '{}' (expected read, write or browse)", other)),
+        };
+        let nodes = parse_nodes(params.get("nodes"))?;
+        if operation != OpcUaOperation::Browse && nodes.is_empty() {
+            return Err(anyhow::anyhow!("'nodes' must be a non-empty array for {}", if operation == OpcUaOperation::Read { "read" } else { "write" }));
+        }
+        Ok(Self {
+            operation,
+            nodes,
+            detailed: params.get("detailed").and_then(|d| d.as_bool()).unwrap_or(false),
+            browse_root: match params.get("node_id").and_then(|id| id.as_str()) {
+                Some(node_id) => NodeId::parse(node_id)?,
+                None => NodeId::numeric(0, ids::OBJECTS_FOLDER),
+            },
+            max_depth: params.get("max_depth").and_then(|d| d.as_u64()).unwrap_or(1).clamp(1, 10) as u32,
+            max_nodes: params.get("max_nodes").and_then(|m| m.as_u64()).unwrap_or(1000).max(1) as usize,
+            timeout: Duration::from_secs_f64(params.get("timeout_secs").and_then(|t| t.as_f64()).filter(|t| *t > 0.0).unwrap_or(10.0)),
+        })
+    }
+
+    /// Output item of a read: named values, or detailed items per node
+    pub fn read_output(&self, values: &[DataValue]) -> Vec<Value> {
+        if self.detailed {
+            return self.nodes.iter().zip(values).map(|(node, value)| value.to_detailed(node)).collect();
+        }
+        let item: Map<String, Value> = self.nodes.iter().zip(values)
+            .map(|(node, value)| {
+                let usable = status_is_usable(value.status);
+                if !usable {
+                    tracing::warn!("⚠️ OPC UA read of {} returned {}", node.node_id, status_name(value.status));
+                }
+                (node.name.clone(), if usable { value.value.clone() } else { Value::Null })
+            })
+            .collect();
+        vec![Value::Object(item)]
+    }
+}
+
+/// Parse a "nodes" array (missing is empty)
+pub fn parse_nodes(nodes: Option<&Value>) -> Result<Vec<NodeRef>> {
+    match nodes {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::Array(nodes)) => nodes.iter().map(NodeRef::from_value).collect(),
+        Some(_) => Err(anyhow::anyhow!("'nodes' must be an array")),
+    }
+}
+
+/// Breadth-first browse below a node, one item per reference
+pub async fn browse_tree(client: &mut OpcUaClient, params: &OpcUaParams) -> Result<Vec<Value>> {
+    let mut items = Vec::new();
+    let mut level = vec![(params.browse_root.clone(), params.browse_root.to_string())];
+    let mut seen = std::collections::HashSet::new();
+    for depth in 1..=params.max_depth {
+        let mut next = Vec::new();
+        for (node_id, path) in level {
+            for reference in client.browse(&node_id).await? {
+                if items.len() >= params.max_nodes {
+                    return Ok(items);
+                }
+                if !seen.insert(reference.node_id.clone()) {
+                    continue;
+                }
+                let child_path = format!("{}/{}", path, reference.browse_name);
+                items.push(json!({
+                    "node_id": reference.node_id,
+                    "browse_name": reference.browse_name,
+                    "display_name": reference.display_name,
+                    "node_class": node_class_name(reference.node_class),
+                    "type_definition": reference.type_definition,
+                    "reference_type": reference.reference_type,
+                    "parent": node_id.to_string(),
+                    "path": child_path,
+                    "depth": depth,
+                }));
+                // Children on other servers (nsu= without a local index) can't be browsed here
+                if let Ok(child) = NodeId::parse(&reference.node_id) {
+                    next.push((child, child_path));
+                }
+            }
+        }
+        level = next;
+    }
+    Ok(items)
+}
@@ -0,0 +1,320 @@
+//! Background OPC UA subscription service for OpcUaTrigger nodes
+//! 
+//! Keeps one session with a subscription per OpcUaTrigger node and starts the
+//! workflow for every notification message of its monitored items:
+//! 
+//! { "nodes": ["ns=2;s=Line1.Temperature", { "node_id": "ns=2;s=Line1.Running", "name": "running" }],
+//!   "publishing_interval_ms": 1000, "sampling_interval_ms": 250, "deadband": 0.5 }
+//! 
+//! Items: one { node_id, name, value, data_type, status, source_timestamp,
+//! server_timestamp } item per change, or with "output": "snapshot" a single
+//! item with the latest value of every monitored node. Filtering happens on the
+//! server (sampling interval, absolute deadband per node), so only real changes
+//! reach Mechaway. Runs of one trigger are executed one after another; when the
+//! workflow can't keep up, notifications beyond a bounded buffer are dropped
+//! (logged). Sessions are re-established with backoff and the subscription is
+//! re-created, which also delivers the current values again.
+
+use crate::{
+    runtime::{
+        engine::ExecutionEngine,
+        executor::NodeExecutor,
+        opcua::{self, status_is_usable, status_name, Credentials, DataValue, MonitoredItem, NodeRef, OpcUaClient},
+    },
+    workflow::{
+        registry::WorkflowRegistry,
+        types::{ExecutionContext, Node, NodeType, Workflow},
+    },
+};
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::{sync::mpsc, sync::Mutex, task::JoinHandle};
+
+/// Notification messages buffered per trigger while a run is in progress
+const NOTIFICATION_BUFFER: usize = 256;
+
+/// Longest wait between reconnect attempts
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// Monitored node with its server-side filtering
+#[derive(Debug, Clone)]
+pub struct MonitoredNode {
+    pub node: NodeRef,
+    pub sampling_interval_ms: f64,
+    pub deadband: Option<f64>,
+}
+
+/// OpcUaTrigger configuration
+#[derive(Debug, Clone)]
+pub struct OpcUaTriggerParams {
+    pub nodes: Vec<MonitoredNode>,
+    pub publishing_interval_ms: f64,
+    pub queue_size: u32,
+    /// One item with the latest value of every node instead of one item per change
+    pub snapshot: bool,
+    pub timeout: Duration,
+}
+
+impl OpcUaTriggerParams {
+    /// Parse node params
+    /// 
+    /// Expected params: { "nodes": ["ns=2;s=Temp", { "node_id", "name", "sampling_interval_ms", "deadband" }],
+    ///                    "publishing_interval_ms": 1000, "sampling_interval_ms": 1000, "deadband": 0.5,
+    ///                    "queue_size": 10, "output": "changes|snapshot", "timeout_secs": 10 }
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        let positive = |value: Option<&Value>, name: &str, default: f64| -> Result<f64> {
+            match value {
+                None => Ok(default),
+                Some(value) => value.as_f64().filter(|v| *v >= 0.0)
+                    .ok_or_else(|| anyhow::anyhow!("'{}' must be a non-negative number", name)),
+            }
+        };
+        let sampling_interval_ms = positive(params.get("sampling_interval_ms"), "sampling_interval_ms", 1000.0)?;
+        let deadband = params.get("deadband").map(|d| positive(Some(d), "deadband", 0.0)).transpose()?;
+
+        let refs = opcua::parse_nodes(params.get("nodes"))?;
+        if refs.is_empty() {
+            return Err(anyhow::anyhow!("'nodes' must be a non-empty array"));
+        }
+        let nodes = refs.into_iter().zip(params["nodes"].as_array().into_iter().flatten())
+            .map(|(node, raw)| Ok(MonitoredNode {
+                node,
+                sampling_interval_ms: positive(raw.get("sampling_interval_ms"), "sampling_interval_ms", sampling_interval_ms)?,
+                deadband: match raw.get("deadband") {
+                    Some(value) => Some(positive(Some(value), "deadband", 0.0)?),
+                    None => deadband,
+                },
+            }))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            nodes,
+            publishing_interval_ms: positive(params.get("publishing_interval_ms"), "publishing_interval_ms", 1000.0)?.max(50.0),
+            queue_size: params.get("queue_size").and_then(|q| q.as_u64()).unwrap_or(10).clamp(1, 10_000) as u32,
+            snapshot: match params.get("output").and_then(|o| o.as_str()).unwrap_or("changes") {
+                "changes" => false,
+                "snapshot" => true,
+                other => return Err(anyhow::anyhow!("unsupported output '{}' (expected changes or snapshot)", other)),
+            },
+            timeout: Duration::from_secs_f64(params.get("timeout_secs").and_then(|t| t.as_f64()).filter(|t| *t > 0.0).unwrap_or(10.0)),
+        })
+    }
+}
+
+/// HOT-RELOAD OPC UA subscription service
+pub struct OpcUaTriggerService {
+    registry: Arc<WorkflowRegistry>,
+    executor: Arc<NodeExecutor>,
+    engine: Arc<ExecutionEngine>,
+    /// workflow_id -> one subscription task per OpcUaTrigger node
+    subscriptions: Mutex<HashMap<String, Vec<JoinHandle<()>>>>,
+}
+
+impl OpcUaTriggerService {
+    /// Create new OPC UA trigger service
+    pub fn new(registry: Arc<WorkflowRegistry>, executor: Arc<NodeExecutor>, engine: Arc<ExecutionEngine>) -> Self {
+        Self { registry, executor, engine, subscriptions: Mutex::new(HashMap::new()) }
+    }
+
+    /// Subscribe the OpcUaTrigger nodes of all registered workflows
+    pub async fn start(&self) {
+        tracing::info!("🏭 Starting OPC UA trigger service");
+        for workflow in self.registry.get_all_workflows() {
+            if let Err(e) = self.add_or_update_workflow_opcua_triggers(&workflow).await {
+                tracing::error!("❌ Failed to subscribe OPC UA triggers of workflow {}: {}", workflow.id, e);
+            }
+        }
+    }
+
+    /// Number of active OPC UA subscriptions
+    pub async fn subscription_count(&self) -> usize {
+        self.subscriptions.lock().await.values().map(Vec::len).sum()
+    }
+
+    /// HOT-RELOAD: Replace the subscriptions of a workflow with its current OpcUaTrigger nodes
+    pub async fn add_or_update_workflow_opcua_triggers(&self, workflow: &Workflow) -> Result<()> {
+        let mut triggers = Vec::new();
+        for node in workflow.nodes.iter().filter(|node| matches!(node.node_type, NodeType::OpcUaTrigger)) {
+            let params = OpcUaTriggerParams::from_node_params(&node.params)
+                .map_err(|e| anyhow::anyhow!("OpcUaTrigger '{}': {}", node.id, e))?;
+            // Resolve the endpoint before dropping the running subscriptions
+            let (endpoint, credentials) = self.connection(&workflow.project, node)?;
+            triggers.push((node, params, endpoint, credentials));
+        }
+
+        self.remove_workflow_opcua_triggers(&workflow.id).await;
+        if triggers.is_empty() {
+            return Ok(());
+        }
+
+        let handles: Vec<JoinHandle<()>> = triggers.into_iter()
+            .map(|(node, params, endpoint, credentials)| {
+                tracing::info!("🏭 Subscribing OpcUaTrigger {}:{} to {} node(s)", workflow.id, node.id, params.nodes.len());
+                let subscription = Subscription {
+                    registry: Arc::clone(&self.registry),
+                    engine: Arc::clone(&self.engine),
+                    workflow_id: workflow.id.clone(),
+                    node_id: node.id.clone(),
+                    endpoint,
+                    credentials,
+                    params,
+                };
+                tokio::spawn(subscription.run())
+            })
+            .collect();
+
+        tracing::info!("🔥 Hot-reloaded {} OPC UA triggers for workflow: {}", handles.len(), workflow.id);
+        self.subscriptions.lock().await.insert(workflow.id.clone(), handles);
+        Ok(())
+    }
+
+    /// HOT-RELOAD: Close all OPC UA subscriptions of a workflow
+    pub async fn remove_workflow_opcua_triggers(&self, workflow_id: &str) {
+        if let Some(handles) = self.subscriptions.lock().await.remove(workflow_id) {
+            tracing::info!("🗑️ Removing {} OPC UA triggers for workflow: {}", handles.len(), workflow_id);
+            for handle in handles {
+                handle.abort();
+            }
+        }
+    }
+
+    fn connection(&self, project_slug: &str, node: &Node) -> Result<(String, Credentials)> {
+        let secrets = node.secrets.as_ref().filter(|s| !s.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("OpcUaTrigger '{}' REQUIRES secrets field with the endpoint (opc.tcp://host:port)", node.id))?;
+        let resolved = secrets.iter()
+            .map(|pin| self.executor.resolve_secret(project_slug, pin))
+            .collect::<Result<Vec<_>>>()
+            .map_err(|e| anyhow::anyhow!("OpcUaTrigger '{}': {}", node.id, e))?;
+        Ok(opcua::connection_from_secrets(&resolved))
+    }
+}
+
+/// One running OpcUaTrigger subscription
+struct Subscription {
+    registry: Arc<WorkflowRegistry>,
+    engine: Arc<ExecutionEngine>,
+    workflow_id: String,
+    node_id: String,
+    endpoint: String,
+    credentials: Credentials,
+    params: OpcUaTriggerParams,
+}
+
+impl Subscription {
+    /// Session and worker of the node (runs until aborted)
+    async fn run(self) {
+        let (sender, mut receiver) = mpsc::channel::<Vec<(u32, DataValue)>>(NOTIFICATION_BUFFER);
+        let trigger = format!("{}:{}", self.workflow_id, self.node_id);
+
+        let connection = async {
+            let mut delay = Duration::from_secs(1);
+            loop {
+                let error = match OpcUaClient::connect(&self.endpoint, &self.credentials, self.params.timeout).await {
+                    Ok(mut client) => {
+                        let result = self.subscribe(&mut client, &sender, &mut delay).await;
+                        client.close().await;
+                        result.err().unwrap_or_else(|| anyhow::anyhow!("subscription ended"))
+                    }
+                    Err(e) => e,
+                };
+                tracing::warn!("⚠️ OpcUaTrigger {} session error: {} (retrying in {:?})", trigger, error, delay);
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+            }
+        };
+
+        let worker = async {
+            // Latest value per monitored node (snapshot output)
+            let mut latest = vec![Value::Null; self.params.nodes.len()];
+            while let Some(changes) = receiver.recv().await {
+                let items: Vec<Value> = if self.params.snapshot {
+                    for (handle, value) in &changes {
+                        if let Some(slot) = latest.get_mut(*handle as usize) {
+                            *slot = if status_is_usable(value.status) { value.value.clone() } else { Value::Null };
+                        }
+                    }
+                    let snapshot: Map<String, Value> = self.params.nodes.iter().zip(&latest)
+                        .map(|(monitored, value)| (monitored.node.name.clone(), value.clone()))
+                        .collect();
+                    vec![Value::Object(snapshot)]
+                } else {
+                    changes.iter()
+                        .filter_map(|(handle, value)| Some(value.to_detailed(&self.params.nodes.get(*handle as usize)?.node)))
+                        .collect()
+                };
+                self.execute(items, changes.len()).await;
+            }
+        };
+
+        tokio::join!(connection, worker);
+    }
+
+    /// Create the subscription and forward notifications until the session fails
+    async fn subscribe(&self, client: &mut OpcUaClient, sender: &mpsc::Sender<Vec<(u32, DataValue)>>, delay: &mut Duration) -> Result<()> {
+        let trigger = format!("{}:{}", self.workflow_id, self.node_id);
+        let (subscription_id, interval, keep_alive) = client.create_subscription(self.params.publishing_interval_ms).await?;
+        let items: Vec<MonitoredItem> = self.params.nodes.iter().enumerate()
+            .map(|(handle, monitored)| MonitoredItem {
+                node_id: monitored.node.node_id.clone(),
+                client_handle: handle as u32,
+                sampling_interval_ms: monitored.sampling_interval_ms,
+                deadband: monitored.deadband,
+                queue_size: self.params.queue_size,
+            })
+            .collect();
+        let statuses = client.create_monitored_items(subscription_id, &items).await?;
+        for (monitored, status) in self.params.nodes.iter().zip(&statuses) {
+            if !status_is_usable(*status) {
+                tracing::warn!("⚠️ OpcUaTrigger {} can't monitor {}: {}", trigger, monitored.node.node_id, status_name(*status));
+            }
+        }
+        if !statuses.iter().any(|status| status_is_usable(*status)) {
+            return Err(anyhow::anyhow!("none of the nodes can be monitored"));
+        }
+        tracing::info!("🏭 OpcUaTrigger {} subscribed ({} ms publishing interval)", trigger, interval);
+        *delay = Duration::from_secs(1);
+
+        // The server answers at the latest with a keep-alive after interval * keep_alive
+        let wait = Duration::from_secs_f64(interval.max(1.0) * keep_alive.max(1) as f64 / 1000.0);
+        let mut acknowledgements = Vec::new();
+        loop {
+            let notification = client.publish(&acknowledgements, wait).await?;
+            acknowledgements.clear();
+            if notification.changes.is_empty() {
+                continue;
+            }
+            acknowledgements.push((notification.subscription_id, notification.sequence_number));
+            if sender.try_send(notification.changes).is_err() {
+                tracing::warn!("⚠️ OpcUaTrigger {} is falling behind, notification dropped", trigger);
+            }
+        }
+    }
+
+    /// Run the workflow for one notification message
+    async fn execute(&self, items: Vec<Value>, changes: usize) {
+        let Some(compiled) = self.registry.get_workflow(&self.workflow_id) else {
+            tracing::warn!("⚠️ OpcUaTrigger {}:{} received a notification for an unknown workflow", self.workflow_id, self.node_id);
+            return;
+        };
+        if self.engine.maintenance_mode().is_enabled() {
+            tracing::debug!("🚧 OpcUaTrigger {}:{} dropped a notification: maintenance mode", self.workflow_id, self.node_id);
+            return;
+        }
+        if compiled.active_snooze().is_some() {
+            tracing::debug!("💤 OpcUaTrigger {}:{} dropped a notification: workflow is snoozed", self.workflow_id, self.node_id);
+            return;
+        }
+
+        let mut context = ExecutionContext::from_array_data(
+            self.workflow_id.clone(), items, compiled.workflow.project.clone());
+        context.metadata.insert("trigger_type".to_string(), json!("opcua"));
+        context.metadata.insert("trigger_node_id".to_string(), json!(self.node_id));
+        context.metadata.insert("opcua_changes".to_string(), json!(changes));
+
+        tracing::info!("🚀 Executing OPC UA-triggered workflow: {} ({}, {} change(s))", self.workflow_id, self.node_id, changes);
+        if let Err(e) = self.engine.execute_workflow(&compiled, &self.node_id, context).await {
+            tracing::error!("❌ OPC UA-triggered workflow failed: {} - Error: {}", self.workflow_id, e);
+        }
+    }
+}
@@ -0,0 +1,190 @@
+//! Store-and-forward for nodes talking to remote services
+//! 
+//! HTTPClient, StripeAPI, GitHubAPI, TwilioSend and PushNotification nodes
+//! with "store_and_forward": true don't fail when their request can't reach
+//! the network (connect errors, timeouts). The executor parks the node's
+//! execution context in the project outbox and outputs
+//! { "success": false, "queued": true, "outbox_id": 7, "error": "..." }
+//! instead, stopping the branch.
+//! 
+//! The outbox flusher replays due entries in order by re-running the node
+//! (current workflow version) with the stored context: delivered entries are
+//! deleted, connectivity errors are retried with backoff (up to every
+//! MAX_RETRY_DELAY), any other error marks the entry failed for inspection.
+//! Delivery is at-least-once; `$binary` contents are not stored.
+
+use crate::{
+    project::ProjectDatabaseManager,
+    runtime::executor::NodeExecutor,
+    workflow::{
+        registry::WorkflowRegistry,
+        types::{ExecutionContext, Node, NodeType},
+    },
+};
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::json;
+use std::{fmt, sync::Arc, time::Duration};
+
+/// Entries replayed per project and pass
+const FLUSH_BATCH: usize = 100;
+
+/// First retry delay after a connectivity error (doubles per attempt)
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(15);
+
+/// Longest wait between replays of an entry
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(300);
+
+/// Failed request to a remote service, keeping the transport error for `is_connectivity_error`
+#[derive(Debug)]
+pub struct RequestError {
+    context: &'static str,
+    source: reqwest::Error,
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.context, self.source)
+    }
+}
+
+impl std::error::Error for RequestError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// Error of a request that failed before a response arrived ("Stripe request failed: ...")
+pub fn request_error(context: &'static str, source: reqwest::Error) -> anyhow::Error {
+    RequestError { context, source }.into()
+}
+
+/// Whether a node failed because the remote service was unreachable
+pub fn is_connectivity_error(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| cause.downcast_ref::<reqwest::Error>()
+        .is_some_and(|e| e.is_connect() || e.is_timeout()))
+}
+
+/// Whether a failed run of this node is parked in the outbox
+/// 
+/// Not in test runs (mocked), and not for replays, which the flusher reschedules itself.
+pub fn parks_on_connectivity_error(node: &Node, context: &ExecutionContext) -> bool {
+    matches!(node.node_type, NodeType::HTTPClient | NodeType::StripeAPI | NodeType::GitHubAPI | NodeType::TwilioSend | NodeType::PushNotification)
+        && node.params.get("store_and_forward").and_then(|s| s.as_bool()).unwrap_or(false)
+        && context.mocks.is_none()
+        && !context.metadata.contains_key("outbox_id")
+}
+
+/// Wait before the next replay of an entry after `attempts` failed ones
+pub fn retry_delay(attempts: u32) -> Duration {
+    FIRST_RETRY_DELAY.saturating_mul(1 << attempts.min(10)).min(MAX_RETRY_DELAY)
+}
+
+/// Unix milliseconds `delay` from now
+pub fn due_in(delay: Duration) -> i64 {
+    chrono::Utc::now().timestamp_millis() + delay.as_millis() as i64
+}
+
+/// What one flush pass did in a project
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FlushReport {
+    pub project: String,
+    pub delivered: u64,
+    pub rescheduled: u64,
+    pub failed: u64,
+    /// Whether the pass stopped early because the network is still down
+    pub offline: bool,
+}
+
+/// Background replay of parked node inputs
+pub struct OutboxFlusher {
+    registry: Arc<WorkflowRegistry>,
+    executor: Arc<NodeExecutor>,
+    project_db_manager: Arc<ProjectDatabaseManager>,
+    /// Seconds between passes; 0 disables the background task
+    interval_secs: u64,
+}
+
+impl OutboxFlusher {
+    pub fn new(registry: Arc<WorkflowRegistry>, executor: Arc<NodeExecutor>, project_db_manager: Arc<ProjectDatabaseManager>, interval_secs: u64) -> Self {
+        Self { registry, executor, project_db_manager, interval_secs }
+    }
+
+    /// Flush every `interval_secs` in the background
+    pub fn start(self: Arc<Self>) {
+        if self.interval_secs == 0 {
+            tracing::info!("📮 Outbox flusher disabled");
+            return;
+        }
+        tracing::info!("📮 Starting outbox flusher (every {}s)", self.interval_secs);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(Duration::from_secs(self.interval_secs));
+            loop {
+                ticker.tick().await;
+                for project in self.project_db_manager.known_projects().await {
+                    if let Err(e) = self.flush_project(&project).await {
+                        tracing::warn!("⚠️ Outbox flush failed for project {}: {}", project, e);
+                    }
+                }
+            }
+        });
+    }
+
+    /// Replay the due entries of a project once, oldest first
+    /// 
+    /// Stops at the first connectivity error: the remaining entries would most
+    /// likely fail the same way and wait for the next pass.
+    pub async fn flush_project(&self, project_slug: &str) -> Result<FlushReport> {
+        let mut report = FlushReport { project: project_slug.to_string(), ..Default::default() };
+        let due = self.project_db_manager.outbox_due(project_slug, chrono::Utc::now().timestamp_millis(), FLUSH_BATCH).await?;
+
+        for entry in due {
+            let attempts = entry.attempts + 1;
+            let Some(node) = self.registry.get_workflow(&entry.workflow_id)
+                .and_then(|compiled| compiled.workflow.nodes.iter().find(|node| node.id == entry.node_id).cloned())
+            else {
+                let error = format!("node {}:{} no longer exists", entry.workflow_id, entry.node_id);
+                self.project_db_manager.outbox_record_attempt(project_slug, entry.id, attempts, &error, None).await?;
+                report.failed += 1;
+                continue;
+            };
+            let mut context: ExecutionContext = match serde_json::from_value(entry.context) {
+                Ok(context) => context,
+                Err(e) => {
+                    let error = format!("stored context is unreadable: {}", e);
+                    self.project_db_manager.outbox_record_attempt(project_slug, entry.id, attempts, &error, None).await?;
+                    report.failed += 1;
+                    continue;
+                }
+            };
+            context.metadata.insert("outbox_id".to_string(), json!(entry.id));
+
+            match self.executor.execute_node(&node, context).await {
+                Ok(_) => {
+                    self.project_db_manager.outbox_delete(project_slug, entry.id).await?;
+                    tracing::info!("📮 Delivered outbox entry {} ({}:{}) after {} attempt(s)", entry.id, entry.workflow_id, entry.node_id, attempts);
+                    report.delivered += 1;
+                }
+                Err(e) if is_connectivity_error(&e) => {
+                    let next_attempt_at = due_in(retry_delay(attempts));
+                    self.project_db_manager.outbox_record_attempt(project_slug, entry.id, attempts, &e.to_string(), Some(next_attempt_at)).await?;
+                    tracing::debug!("📮 Outbox entry {} still offline (attempt {}): {}", entry.id, attempts, e);
+                    report.rescheduled += 1;
+                    report.offline = true;
+                    break;
+                }
+                Err(e) => {
+                    self.project_db_manager.outbox_record_attempt(project_slug, entry.id, attempts, &e.to_string(), None).await?;
+                    tracing::warn!("⚠️ Outbox entry {} ({}:{}) failed: {}", entry.id, entry.workflow_id, entry.node_id, e);
+                    report.failed += 1;
+                }
+            }
+        }
+
+        if report.delivered + report.failed > 0 {
+            tracing::info!("📮 Outbox flush for {}: {} delivered, {} failed, {} still offline",
+                project_slug, report.delivered, report.failed, report.rescheduled);
+        }
+        Ok(report)
+    }
+}
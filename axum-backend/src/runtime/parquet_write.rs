@@ -0,0 +1,356 @@
+//! ParquetWrite node encoding of data items as a Parquet file
+//! 
+//! The whole data array becomes one file, with columns declared in params or
+//! inferred from the items:
+//! 
+//! { "columns": [{ "name": "order_id", "type": "int64", "nullable": false },
+//!               { "name": "total", "type": "float64" }, { "name": "created_at", "type": "timestamp" }],
+//!   "compression": "zstd", "filename": "orders-%Y-%m-%d.parquet" }
+//! 
+//! Column types: boolean, int32, int64, float32, float64, string, json (nested
+//! values serialized as JSON text), date (days, from "YYYY-MM-DD") and timestamp
+//! (microseconds UTC, from RFC 3339 strings or Unix seconds). Inferred columns
+//! are nullable and sorted by name; integers mixed with floats become float64,
+//! objects/arrays become json and any other mix becomes string. The file is
+//! emitted as a binary item, ready for an HTTPClient PUT to object storage.
+
+use anyhow::Result;
+use arrow_array::{
+    builder::{
+        ArrayBuilder, BooleanBuilder, Date32Builder, Float32Builder, Float64Builder, Int32Builder, Int64Builder,
+        StringBuilder, TimestampMicrosecondBuilder,
+    },
+    ArrayRef, RecordBatch,
+};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use bytes::Bytes;
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use parquet::{
+    arrow::ArrowWriter,
+    basic::{Compression, ZstdLevel},
+    file::properties::WriterProperties,
+};
+use serde_json::{json, Value};
+use std::{collections::BTreeMap, sync::Arc};
+
+/// Rows per record batch / row group unless "row_group_size" is set
+const DEFAULT_ROW_GROUP_SIZE: usize = 65_536;
+
+/// MIME type of the produced file
+pub const PARQUET_CONTENT_TYPE: &str = "application/vnd.apache.parquet";
+
+/// Parquet column type
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColumnType {
+    Boolean,
+    Int32,
+    Int64,
+    Float32,
+    Float64,
+    String,
+    Json,
+    Date,
+    Timestamp,
+}
+
+impl ColumnType {
+    fn parse(name: &str) -> Result<Self> {
+        Ok(match name {
+            "boolean" | "bool" => ColumnType::Boolean,
+            "int32" => ColumnType::Int32,
+            "int64" | "integer" => ColumnType::Int64,
+            "float32" => ColumnType::Float32,
+            "float64" | "double" | "number" => ColumnType::Float64,
+            "string" | "utf8" => ColumnType::String,
+            "json" => ColumnType::Json,
+            "date" => ColumnType::Date,
+            "timestamp" => ColumnType::Timestamp,
+            other => return Err(anyhow::anyhow!(
+                "Unknown column type '{}' (boolean, int32, int64, float32, float64, string, json, date, timestamp)", other
+            )),
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColumnType::Boolean => "boolean",
+            ColumnType::Int32 => "int32",
+            ColumnType::Int64 => "int64",
+            ColumnType::Float32 => "float32",
+            ColumnType::Float64 => "float64",
+            ColumnType::String => "string",
+            ColumnType::Json => "json",
+            ColumnType::Date => "date",
+            ColumnType::Timestamp => "timestamp",
+        }
+    }
+
+    fn data_type(&self) -> DataType {
+        match self {
+            ColumnType::Boolean => DataType::Boolean,
+            ColumnType::Int32 => DataType::Int32,
+            ColumnType::Int64 => DataType::Int64,
+            ColumnType::Float32 => DataType::Float32,
+            ColumnType::Float64 => DataType::Float64,
+            ColumnType::String | ColumnType::Json => DataType::Utf8,
+            ColumnType::Date => DataType::Date32,
+            ColumnType::Timestamp => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+        }
+    }
+}
+
+/// One output column
+#[derive(Debug, Clone)]
+pub struct Column {
+    pub name: String,
+    pub column_type: ColumnType,
+    pub nullable: bool,
+}
+
+impl Column {
+    pub fn to_json(&self) -> Value {
+        json!({ "name": self.name, "type": self.column_type.name(), "nullable": self.nullable })
+    }
+}
+
+/// Parsed ParquetWrite node params
+#[derive(Debug, Clone)]
+pub struct ParquetParams {
+    /// Declared columns (None: inferred from the items)
+    pub columns: Option<Vec<Column>>,
+    pub compression: Compression,
+    pub row_group_size: usize,
+    /// Filename template, strftime specifiers expanded with the current UTC time
+    pub filename: Value,
+}
+
+impl ParquetParams {
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        let columns = match params.get("columns") {
+            None | Some(Value::Null) => None,
+            Some(Value::Array(specs)) => {
+                let mut columns = Vec::with_capacity(specs.len());
+                for spec in specs {
+                    let name = spec["name"].as_str().filter(|n| !n.is_empty())
+                        .ok_or_else(|| anyhow::anyhow!("Every column needs a 'name'"))?;
+                    if columns.iter().any(|c: &Column| c.name == name) {
+                        return Err(anyhow::anyhow!("Duplicate column '{}'", name));
+                    }
+                    let column_type = ColumnType::parse(spec["type"].as_str()
+                        .ok_or_else(|| anyhow::anyhow!("Column '{}' needs a 'type'", name))?)?;
+                    columns.push(Column {
+                        name: name.to_string(),
+                        column_type,
+                        nullable: spec.get("nullable").and_then(|n| n.as_bool()).unwrap_or(true),
+                    });
+                }
+                if columns.is_empty() {
+                    return Err(anyhow::anyhow!("'columns' is empty"));
+                }
+                Some(columns)
+            }
+            Some(_) => return Err(anyhow::anyhow!("'columns' must be an array of {{ name, type, nullable }}")),
+        };
+        let compression = match params.get("compression").and_then(|c| c.as_str()).unwrap_or("snappy") {
+            "snappy" => Compression::SNAPPY,
+            "zstd" => Compression::ZSTD(ZstdLevel::default()),
+            "none" | "uncompressed" => Compression::UNCOMPRESSED,
+            other => return Err(anyhow::anyhow!("Unknown compression '{}' (snappy, zstd, none)", other)),
+        };
+        Ok(Self {
+            columns,
+            compression,
+            row_group_size: params.get("row_group_size").and_then(|r| r.as_u64())
+                .map(|r| r.clamp(1, 10_000_000) as usize).unwrap_or(DEFAULT_ROW_GROUP_SIZE),
+            filename: params.get("filename").cloned().unwrap_or_else(|| json!("data-%Y%m%dT%H%M%SZ.parquet")),
+        })
+    }
+}
+
+/// Columns inferred from the keys and values of the items
+pub fn infer_columns(items: &[Value]) -> Result<Vec<Column>> {
+    let mut types: BTreeMap<&str, Option<ColumnType>> = BTreeMap::new();
+    for (index, item) in items.iter().enumerate() {
+        let object = item.as_object()
+            .ok_or_else(|| anyhow::anyhow!("Item {} is not an object", index))?;
+        for (key, value) in object {
+            let observed = match value {
+                Value::Null => None,
+                Value::Bool(_) => Some(ColumnType::Boolean),
+                Value::Number(n) if n.is_i64() => Some(ColumnType::Int64),
+                Value::Number(_) => Some(ColumnType::Float64),
+                Value::String(_) => Some(ColumnType::String),
+                Value::Array(_) | Value::Object(_) => Some(ColumnType::Json),
+            };
+            let merged = types.entry(key).or_insert(None);
+            *merged = match (*merged, observed) {
+                (known, None) => known,
+                (None, observed) => observed,
+                (Some(a), Some(b)) if a == b => Some(a),
+                (Some(ColumnType::Int64), Some(ColumnType::Float64))
+                | (Some(ColumnType::Float64), Some(ColumnType::Int64)) => Some(ColumnType::Float64),
+                _ => Some(ColumnType::String),
+            };
+        }
+    }
+    if types.is_empty() {
+        return Err(anyhow::anyhow!("No columns to infer, declare 'columns'"));
+    }
+    Ok(types.into_iter()
+        .map(|(name, column_type)| Column {
+            name: name.to_string(),
+            // Columns that are always null still need a type
+            column_type: column_type.unwrap_or(ColumnType::String),
+            nullable: true,
+        })
+        .collect())
+}
+
+/// Encode the items as a Parquet file
+pub fn write_parquet(items: &[Value], columns: &[Column], params: &ParquetParams) -> Result<Bytes> {
+    let schema = Arc::new(Schema::new(columns.iter()
+        .map(|column| Field::new(&column.name, column.column_type.data_type(), column.nullable))
+        .collect::<Vec<_>>()));
+    let properties = WriterProperties::builder()
+        .set_compression(params.compression)
+        .set_max_row_group_size(params.row_group_size)
+        .set_created_by(format!("mechaway {}", env!("CARGO_PKG_VERSION")))
+        .build();
+    let mut buffer = Vec::new();
+    let mut writer = ArrowWriter::try_new(&mut buffer, schema.clone(), Some(properties))?;
+
+    for (chunk_index, chunk) in items.chunks(params.row_group_size).enumerate() {
+        let mut arrays: Vec<ArrayRef> = Vec::with_capacity(columns.len());
+        for column in columns {
+            let mut builder = ColumnBuilder::new(column.column_type, chunk.len());
+            for (offset, item) in chunk.iter().enumerate() {
+                let row = chunk_index * params.row_group_size + offset;
+                let value = match item {
+                    Value::Object(object) => object.get(&column.name).unwrap_or(&Value::Null),
+                    _ => return Err(anyhow::anyhow!("Item {} is not an object", row)),
+                };
+                if value.is_null() && !column.nullable {
+                    return Err(anyhow::anyhow!("Row {}: column '{}' is not nullable", row, column.name));
+                }
+                builder.append(value)
+                    .map_err(|e| anyhow::anyhow!("Row {}: column '{}' ({}): {}", row, column.name, column.column_type.name(), e))?;
+            }
+            arrays.push(builder.finish());
+        }
+        writer.write(&RecordBatch::try_new(schema.clone(), arrays)?)?;
+    }
+    writer.close()?;
+    Ok(Bytes::from(buffer))
+}
+
+/// Typed Arrow builder for one column of a batch
+enum ColumnBuilder {
+    Boolean(BooleanBuilder),
+    Int32(Int32Builder),
+    Int64(Int64Builder),
+    Float32(Float32Builder),
+    Float64(Float64Builder),
+    String { builder: StringBuilder, json: bool },
+    Date(Date32Builder),
+    Timestamp(TimestampMicrosecondBuilder),
+}
+
+impl ColumnBuilder {
+    fn new(column_type: ColumnType, capacity: usize) -> Self {
+        match column_type {
+            ColumnType::Boolean => ColumnBuilder::Boolean(BooleanBuilder::with_capacity(capacity)),
+            ColumnType::Int32 => ColumnBuilder::Int32(Int32Builder::with_capacity(capacity)),
+            ColumnType::Int64 => ColumnBuilder::Int64(Int64Builder::with_capacity(capacity)),
+            ColumnType::Float32 => ColumnBuilder::Float32(Float32Builder::with_capacity(capacity)),
+            ColumnType::Float64 => ColumnBuilder::Float64(Float64Builder::with_capacity(capacity)),
+            ColumnType::String | ColumnType::Json => ColumnBuilder::String {
+                builder: StringBuilder::with_capacity(capacity, capacity * 16),
+                json: column_type == ColumnType::Json,
+            },
+            ColumnType::Date => ColumnBuilder::Date(Date32Builder::with_capacity(capacity)),
+            ColumnType::Timestamp => ColumnBuilder::Timestamp(
+                TimestampMicrosecondBuilder::with_capacity(capacity).with_timezone("UTC")
+            ),
+        }
+    }
+
+    fn append(&mut self, value: &Value) -> Result<()> {
+        if value.is_null() {
+            match self {
+                ColumnBuilder::Boolean(b) => b.append_null(),
+                ColumnBuilder::Int32(b) => b.append_null(),
+                ColumnBuilder::Int64(b) => b.append_null(),
+                ColumnBuilder::Float32(b) => b.append_null(),
+                ColumnBuilder::Float64(b) => b.append_null(),
+                ColumnBuilder::String { builder, .. } => builder.append_null(),
+                ColumnBuilder::Date(b) => b.append_null(),
+                ColumnBuilder::Timestamp(b) => b.append_null(),
+            }
+            return Ok(());
+        }
+        match self {
+            ColumnBuilder::Boolean(b) => b.append_value(match value {
+                Value::Bool(flag) => *flag,
+                Value::String(s) if s == "true" => true,
+                Value::String(s) if s == "false" => false,
+                other => return Err(anyhow::anyhow!("expected a boolean, got {}", other)),
+            }),
+            ColumnBuilder::Int32(b) => b.append_value(i32::try_from(as_i64(value)?)
+                .map_err(|_| anyhow::anyhow!("{} is out of int32 range", value))?),
+            ColumnBuilder::Int64(b) => b.append_value(as_i64(value)?),
+            ColumnBuilder::Float32(b) => b.append_value(as_f64(value)? as f32),
+            ColumnBuilder::Float64(b) => b.append_value(as_f64(value)?),
+            ColumnBuilder::String { builder, json } => match value {
+                Value::String(text) if !*json => builder.append_value(text),
+                other => builder.append_value(other.to_string()),
+            },
+            ColumnBuilder::Date(b) => {
+                let text = value.as_str().ok_or_else(|| anyhow::anyhow!("expected a date string, got {}", value))?;
+                let date = NaiveDate::parse_from_str(text.get(..10).unwrap_or(text), "%Y-%m-%d")
+                    .map_err(|_| anyhow::anyhow!("'{}' is not a YYYY-MM-DD date", text))?;
+                b.append_value((date - DateTime::UNIX_EPOCH.date_naive()).num_days() as i32);
+            }
+            ColumnBuilder::Timestamp(b) => b.append_value(match value {
+                Value::Number(_) => (as_f64(value)? * 1_000_000.0).round() as i64,
+                Value::String(text) => DateTime::parse_from_rfc3339(text).map(|t| t.to_utc())
+                    .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f").map(|t| t.and_utc()))
+                    .map_err(|_| anyhow::anyhow!("'{}' is not an RFC 3339 timestamp", text))?
+                    .timestamp_micros(),
+                other => return Err(anyhow::anyhow!("expected a timestamp, got {}", other)),
+            }),
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            ColumnBuilder::Boolean(b) => Arc::new(b.finish()),
+            ColumnBuilder::Int32(b) => Arc::new(b.finish()),
+            ColumnBuilder::Int64(b) => Arc::new(b.finish()),
+            ColumnBuilder::Float32(b) => Arc::new(b.finish()),
+            ColumnBuilder::Float64(b) => Arc::new(b.finish()),
+            ColumnBuilder::String { builder, .. } => ArrayBuilder::finish(builder),
+            ColumnBuilder::Date(b) => Arc::new(b.finish()),
+            ColumnBuilder::Timestamp(b) => Arc::new(b.finish()),
+        }
+    }
+}
+
+/// Integer from a number without fraction or a numeric string
+fn as_i64(value: &Value) -> Result<i64> {
+    match value {
+        Value::Number(n) => n.as_i64()
+            .or_else(|| n.as_f64().filter(|f| f.fract() == 0.0 && f.abs() < 9.2e18).map(|f| f as i64)),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }.ok_or_else(|| anyhow::anyhow!("expected an integer, got {}", value))
+}
+
+/// Float from a number or a numeric string
+fn as_f64(value: &Value) -> Result<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }.ok_or_else(|| anyhow::anyhow!("expected a number, got {}", value))
+}
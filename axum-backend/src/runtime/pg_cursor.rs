@@ -0,0 +1,96 @@
+//! Server-side cursors for PGQuery nodes
+//! 
+//! With `"fetch_mode": "cursor"` a PGQuery node declares a cursor for its query
+//! on a pooled connection and fetches `batch_size` rows at a time. The engine
+//! runs the downstream nodes once per batch, so result sets far larger than
+//! memory flow through the workflow without ever sitting in one data array:
+//! 
+//! { "query": "SELECT * FROM events WHERE day = $1", "fetch_mode": "cursor", "batch_size": 5000 }
+//! 
+//! The cursor lives in its own transaction, which is committed (closing the
+//! cursor) after the last batch, or rolled back when the connection is dropped
+//! early because a downstream node failed.
+
+use crate::project::{pg_pool::PgConnection, simpletable_pg::{db_error, json_to_param, row_to_json}};
+use anyhow::Result;
+use serde_json::Value;
+use tokio_postgres::types::ToSql;
+
+/// Rows per FETCH when the node sets no batch_size
+pub const DEFAULT_BATCH_SIZE: usize = 1000;
+
+/// Cursor name (one cursor per connection, so it never collides)
+const CURSOR_NAME: &str = "mway_cursor";
+
+/// Batch size of a cursor-mode PGQuery node, or None when it fetches all rows at once
+/// 
+/// Expected params: { "fetch_mode": "all|cursor", "batch_size": 1000 }
+pub fn cursor_batch_size(params: &Value) -> Result<Option<usize>> {
+    match params.get("fetch_mode").and_then(|m| m.as_str()).unwrap_or("all") {
+        "all" => Ok(None),
+        "cursor" => match params.get("batch_size") {
+            None => Ok(Some(DEFAULT_BATCH_SIZE)),
+            Some(size) => match size.as_u64() {
+                Some(size) if size > 0 => Ok(Some(size as usize)),
+                _ => Err(anyhow::anyhow!("'batch_size' must be a positive integer, got {}", size)),
+            },
+        },
+        other => Err(anyhow::anyhow!("Unknown fetch_mode '{}' (expected \"all\" or \"cursor\")", other)),
+    }
+}
+
+/// Open cursor streaming a query's rows in batches
+pub struct PgCursor {
+    connection: PgConnection,
+    batch_size: usize,
+    exhausted: bool,
+    /// Batches returned so far
+    pub batches: u64,
+    /// Rows returned so far
+    pub rows: u64,
+}
+
+impl PgCursor {
+    /// Declare the cursor for `query` (`$n` placeholders bound from `binds`)
+    pub async fn open(mut connection: PgConnection, query: &str, binds: &[Value], batch_size: usize) -> Result<Self> {
+        // Cursors only exist inside a transaction
+        connection.begin().await?;
+
+        let declare = format!("DECLARE {} NO SCROLL CURSOR FOR {}", CURSOR_NAME, query.trim().trim_end_matches(';'));
+        let statement = connection.prepare(&declare).await.map_err(db_error)?;
+        if binds.len() != statement.params().len() {
+            return Err(anyhow::anyhow!("Query expects {} bind parameters, got {}", statement.params().len(), binds.len()));
+        }
+        let params = binds.iter()
+            .zip(statement.params())
+            .map(|(value, ty)| json_to_param(value, ty))
+            .collect::<Result<Vec<_>>>()?;
+        let refs: Vec<&(dyn ToSql + Sync)> = params.iter().map(|p| p.as_ref() as &(dyn ToSql + Sync)).collect();
+        connection.execute(&statement, &refs).await.map_err(db_error)?;
+
+        Ok(Self { connection, batch_size, exhausted: false, batches: 0, rows: 0 })
+    }
+
+    /// Fetch the next batch as JSON rows (None once the result set is drained)
+    pub async fn next_batch(&mut self) -> Result<Option<Vec<Value>>> {
+        if self.exhausted {
+            return Ok(None);
+        }
+        let fetch = format!("FETCH FORWARD {} FROM {}", self.batch_size, CURSOR_NAME);
+        let rows = self.connection.query(&fetch, &[]).await.map_err(db_error)?;
+        // A short batch is the last one (saves a round trip for an empty FETCH)
+        self.exhausted = rows.len() < self.batch_size;
+        if rows.is_empty() {
+            return Ok(None);
+        }
+
+        self.batches += 1;
+        self.rows += rows.len() as u64;
+        Ok(Some(rows.iter().map(row_to_json).collect()))
+    }
+
+    /// Close the cursor and release the connection
+    pub async fn close(mut self) -> Result<()> {
+        self.connection.commit().await
+    }
+}
@@ -0,0 +1,289 @@
+//! PollingTrigger fetch and cursor bookkeeping
+//! 
+//! A PollingTrigger fetches new records on a schedule and starts its workflow
+//! with them as the data array, remembering how far it got in the project KV
+//! store, so incremental ingestion needs no CronTrigger + fetch + KVStore chain:
+//! 
+//! { "interval_secs": 300, "source": "http", "cursor_field": "updated_at",
+//!   "request": { "url": "https://api.example.com/orders", "headers": {...} },
+//!   "cursor_param": "updated_since", "items_path": "data" }
+//! 
+//! { "schedule": "0 */5 * * * *", "source": "sql", "cursor_field": "updated_at",
+//!   "query": "SELECT * FROM orders WHERE updated_at > ? ORDER BY updated_at LIMIT 500",
+//!   "initial_cursor": "1970-01-01T00:00:00Z" }
+//! 
+//! The next cursor is the largest "cursor_field" value among the fetched items.
+//! It is only saved after the workflow run succeeds, so a failed run fetches the
+//! same records again on the next tick (at-least-once delivery). Runs go through
+//! the execution queue; a run shed there counts as failed.
+
+use crate::{
+    runtime::{executor::NodeExecutor, queue::ExecutionQueue, schedule_calendar::ScheduleConstraints},
+    workflow::{
+        params::parse_timezone,
+        registry::CompiledWorkflow,
+        types::{ExecutionContext, Node, NodeType},
+    },
+};
+use anyhow::Result;
+use chrono_tz::Tz;
+use serde_json::{json, Map, Value};
+use std::{cmp::Ordering, time::Duration};
+
+/// Items fetched per poll from a PostgreSQL source (the rest follow on later ticks)
+pub const DEFAULT_MAX_ITEMS: usize = 1000;
+
+/// When the trigger fires
+#[derive(Debug, Clone, PartialEq)]
+pub enum PollSchedule {
+    /// Cron expression (with seconds, like CronTrigger)
+    Cron(String),
+    /// Fixed interval
+    Interval(Duration),
+}
+
+/// Where new records come from
+#[derive(Debug, Clone)]
+pub enum PollSource {
+    /// HTTPClient request with the cursor appended as a query parameter
+    Http {
+        request: Map<String, Value>,
+        cursor_param: String,
+        items_path: Vec<String>,
+    },
+    /// SQL query with the cursor as its bind parameter (SimpleTable, or PostgreSQL with a secret)
+    Sql {
+        query: String,
+        max_items: usize,
+    },
+}
+
+/// PollingTrigger configuration
+#[derive(Debug, Clone)]
+pub struct PollingParams {
+    pub schedule: PollSchedule,
+    pub source: PollSource,
+    /// Dotted path of the field the cursor is taken from
+    pub cursor_field: Vec<String>,
+    /// Cursor before the first successful run
+    pub initial_cursor: Value,
+    /// Random delay of up to this many seconds per tick
+    pub jitter_secs: u64,
+    /// Timezone of the cron schedule and the calendar constraints (default UTC)
+    pub timezone: Tz,
+    pub constraints: ScheduleConstraints,
+}
+
+impl PollingParams {
+    /// Parse node params
+    /// 
+    /// Expected params: { "schedule": "0 */5 * * * *" | "interval_secs": 300, "source": "http" | "sql",
+    ///                    "cursor_field": "updated_at", "initial_cursor": "2024-01-01T00:00:00Z", "jitter_secs": 10,
+    ///                    "timezone": "Europe/Berlin", "only_business_days": true }
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        let schedule = match (params.get("schedule").and_then(|s| s.as_str()), params.get("interval_secs")) {
+            (Some(_), Some(_)) => return Err(anyhow::anyhow!("set either 'schedule' or 'interval_secs', not both")),
+            (Some(schedule), None) => PollSchedule::Cron(schedule.to_string()),
+            (None, Some(secs)) => match secs.as_u64() {
+                Some(secs) if secs > 0 => PollSchedule::Interval(Duration::from_secs(secs)),
+                _ => return Err(anyhow::anyhow!("'interval_secs' must be a positive integer, got {}", secs)),
+            },
+            (None, None) => return Err(anyhow::anyhow!("missing 'schedule' or 'interval_secs' parameter")),
+        };
+
+        let cursor_field = params.get("cursor_field")
+            .and_then(|f| f.as_str())
+            .filter(|f| !f.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("missing 'cursor_field' parameter"))?
+            .split('.')
+            .map(|segment| segment.to_string())
+            .collect();
+        let initial_cursor = params.get("initial_cursor").cloned().unwrap_or(Value::Null);
+        let jitter_secs = match params.get("jitter_secs") {
+            None => 0,
+            Some(secs) => secs.as_u64()
+                .ok_or_else(|| anyhow::anyhow!("'jitter_secs' must be a non-negative integer, got {}", secs))?,
+        };
+        let timezone = parse_timezone(params.get("timezone").and_then(|tz| tz.as_str()))?;
+        let constraints = ScheduleConstraints::from_node_params(params)?;
+
+        let source = match params.get("source").and_then(|s| s.as_str()).unwrap_or("http") {
+            "http" => {
+                let request = params.get("request")
+                    .and_then(|r| r.as_object())
+                    .filter(|r| r.get("url").and_then(|u| u.as_str()).is_some())
+                    .ok_or_else(|| anyhow::anyhow!("http source requires a 'request' object with a 'url'"))?
+                    .clone();
+                let items_path = match params.get("items_path").and_then(|p| p.as_str()) {
+                    Some(path) if !path.is_empty() => path.split('.').map(|segment| segment.to_string()).collect(),
+                    _ => Vec::new(),
+                };
+                PollSource::Http {
+                    request,
+                    cursor_param: params.get("cursor_param").and_then(|p| p.as_str()).unwrap_or("updated_since").to_string(),
+                    items_path,
+                }
+            }
+            "sql" => {
+                let query = params.get("query")
+                    .and_then(|q| q.as_str())
+                    .ok_or_else(|| anyhow::anyhow!("sql source requires a 'query' parameter"))?
+                    .to_string();
+                // "updated_at > NULL" never matches, so the first run needs a real starting point
+                if initial_cursor.is_null() {
+                    return Err(anyhow::anyhow!("sql source requires an 'initial_cursor' parameter"));
+                }
+                let max_items = match params.get("max_items") {
+                    None => DEFAULT_MAX_ITEMS,
+                    Some(max) => match max.as_u64() {
+                        Some(max) if max > 0 => max as usize,
+                        _ => return Err(anyhow::anyhow!("'max_items' must be a positive integer, got {}", max)),
+                    },
+                };
+                PollSource::Sql { query, max_items }
+            }
+            other => return Err(anyhow::anyhow!("unknown source '{}' (expected http or sql)", other)),
+        };
+
+        Ok(Self { schedule, source, cursor_field, initial_cursor, jitter_secs, timezone, constraints })
+    }
+
+    /// Largest cursor value among the items, or the current cursor when none is larger
+    pub fn next_cursor(&self, items: &[Value], current: &Value) -> Value {
+        items.iter()
+            .filter_map(|item| self.cursor_field.iter().try_fold(item, |value, segment| value.get(segment.as_str())))
+            .fold(current.clone(), |best, value| match compare_cursors(value, &best) {
+                Some(Ordering::Greater) => value.clone(),
+                _ if best.is_null() && !value.is_null() => value.clone(),
+                _ => best,
+            })
+    }
+}
+
+/// KV store key of a trigger's cursor
+pub fn cursor_key(workflow_id: &str, node_id: &str) -> String {
+    format!("polling:cursor:{}:{}", workflow_id, node_id)
+}
+
+/// Order two cursor values: numbers numerically, strings lexically (RFC 3339 timestamps sort correctly)
+fn compare_cursors(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+/// Fetch the records after `cursor`
+pub async fn fetch(executor: &NodeExecutor, trigger: &Node, params: &PollingParams, cursor: &Value, project_slug: &str) -> Result<Vec<Value>> {
+    // HTTP and PostgreSQL fetches run as a synthetic HTTPClient / PGQuery node named after the trigger
+    let context = ExecutionContext::from_array_data(String::new(), vec![json!({ "cursor": cursor })], project_slug.to_string());
+    match &params.source {
+        PollSource::Http { request, cursor_param, items_path } => {
+            let mut request = request.clone();
+            if !cursor.is_null() {
+                let url = request.get("url").and_then(|u| u.as_str()).unwrap_or_default();
+                let mut url = reqwest::Url::parse(url)
+                    .map_err(|e| anyhow::anyhow!("invalid request url '{}': {}", url, e))?;
+                let text = match cursor {
+                    Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                };
+                url.query_pairs_mut().append_pair(cursor_param, &text);
+                request.insert("url".to_string(), json!(url.as_str()));
+            }
+            let node = fetch_node(trigger, NodeType::HTTPClient, Value::Object(request), None);
+            let result = executor.execute_node(&node, context).await?;
+            let response = result.data.into_iter().next().unwrap_or(Value::Null);
+            if response.get("success").and_then(|s| s.as_bool()) != Some(true) {
+                return Err(anyhow::anyhow!("request failed with status {}", response.get("status").unwrap_or(&Value::Null)));
+            }
+            let body = response.get("data").unwrap_or(&Value::Null);
+            let items = items_path.iter()
+                .try_fold(body, |value, segment| value.get(segment.as_str()))
+                .ok_or_else(|| anyhow::anyhow!("response has no '{}' field", items_path.join(".")))?;
+            match items {
+                Value::Array(items) => Ok(items.clone()),
+                Value::Null => Ok(Vec::new()),
+                other => Err(anyhow::anyhow!("expected an array of items in the response, got {}", json_type(other))),
+            }
+        }
+        PollSource::Sql { query, max_items } => {
+            match trigger.secrets.as_ref().filter(|secrets| !secrets.is_empty()) {
+                Some(secrets) => {
+                    let node = fetch_node(trigger, NodeType::PGQuery, json!({ "query": query }), Some(secrets.clone()));
+                    let mut cursor = executor.open_pgquery_cursor(&node, &context, *max_items).await?;
+                    let rows = cursor.next_batch().await?.unwrap_or_default();
+                    cursor.close().await?;
+                    Ok(rows)
+                }
+                None => executor.query_simpletable(query, std::slice::from_ref(cursor), &context).await,
+            }
+        }
+    }
+}
+
+/// Poll once: fetch, run the workflow with the new items and advance the cursor
+/// 
+/// Returns the number of items processed (0 when nothing new arrived, in which
+/// case the workflow doesn't run).
+pub async fn poll(queue: &ExecutionQueue, executor: &NodeExecutor, workflow: &CompiledWorkflow, trigger: &Node) -> Result<usize> {
+    let params = PollingParams::from_node_params(&trigger.params)
+        .map_err(|e| anyhow::anyhow!("PollingTrigger '{}': {}", trigger.id, e))?;
+    let project_slug = &workflow.workflow.project;
+    let key = cursor_key(&workflow.workflow.id, &trigger.id);
+    let kv = executor.project_db_manager();
+
+    let cursor = kv.kv_get(project_slug, &key).await?.unwrap_or_else(|| params.initial_cursor.clone());
+    let items = fetch(executor, trigger, &params, &cursor, project_slug).await
+        .map_err(|e| anyhow::anyhow!("PollingTrigger '{}' fetch failed: {}", trigger.id, e))?;
+    if items.is_empty() {
+        tracing::debug!("📭 PollingTrigger '{}' found no new items after cursor {}", trigger.id, cursor);
+        return Ok(0);
+    }
+
+    let next_cursor = params.next_cursor(&items, &cursor);
+    let count = items.len();
+    let mut context = ExecutionContext::from_polling_trigger(workflow.workflow.id.clone(), trigger.id.clone(), project_slug.clone(), items);
+    context.metadata.insert("polling".to_string(), json!({ "cursor": cursor, "next_cursor": next_cursor, "items": count }));
+
+    queue.execute(workflow, &trigger.id, context).await
+        .map_err(|shed| anyhow::anyhow!("PollingTrigger '{}' run not admitted: {}", trigger.id, shed))??;
+
+    if next_cursor == cursor {
+        tracing::warn!("⚠️ PollingTrigger '{}': no item had a '{}' beyond the cursor, the same items may be fetched again",
+            trigger.id, params.cursor_field.join("."));
+    } else {
+        kv.kv_set(project_slug, &key, &next_cursor, None).await?;
+    }
+    tracing::info!("✅ PollingTrigger '{}' processed {} item(s), cursor {} -> {}", trigger.id, count, cursor, next_cursor);
+    Ok(count)
+}
+
+fn fetch_node(trigger: &Node, node_type: NodeType, params: Value, secrets: Option<Vec<String>>) -> Node {
+    let inputs = matches!(node_type, NodeType::PGQuery).then(|| vec!["$json.cursor".to_string()]);
+    Node {
+        id: trigger.id.clone(),
+        node_type,
+        params,
+        inputs,
+        outputs: None,
+        secrets,
+        position: None,
+        notes: None,
+        disabled: false,
+        disabled_mode: Default::default(),
+        compiled_inputs: None,
+    }
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
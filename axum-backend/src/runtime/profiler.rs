@@ -0,0 +1,274 @@
+//! In-memory execution profiling
+//! 
+//! Aggregates node and workflow timings (p50/p95/p99 duration, error rate,
+//! throughput) per node type, per workflow and per workflow node. Percentiles
+//! come from a bounded window of recent samples, so memory per key is fixed.
+//! Snapshots are persisted periodically to the default project database
+//! (profiling_stats table) and loaded back on startup.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Recent durations kept per key for percentile estimation
+const SAMPLE_WINDOW: usize = 1024;
+
+/// Running statistics for one profiled key
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct TimingStats {
+    count: u64,
+    errors: u64,
+    total_ms: f64,
+    max_ms: f64,
+    /// Most recent durations (milliseconds), oldest first
+    samples: VecDeque<f64>,
+    /// First and last observation (RFC 3339), used for throughput
+    first_seen: Option<String>,
+    last_seen: Option<String>,
+}
+
+impl TimingStats {
+    fn record(&mut self, duration: Duration, failed: bool) {
+        let ms = duration.as_secs_f64() * 1000.0;
+        let now = chrono::Utc::now().to_rfc3339();
+        self.count += 1;
+        self.errors += u64::from(failed);
+        self.total_ms += ms;
+        self.max_ms = self.max_ms.max(ms);
+        if self.samples.len() == SAMPLE_WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(ms);
+        self.first_seen.get_or_insert_with(|| now.clone());
+        self.last_seen = Some(now);
+    }
+
+    /// Summary returned by the stats endpoints
+    fn summary(&self) -> TimingSummary {
+        let mut sorted: Vec<f64> = self.samples.iter().copied().collect();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let percentile = |p: f64| -> f64 {
+            if sorted.is_empty() {
+                return 0.0;
+            }
+            let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1]
+        };
+
+        // Throughput over the observed window (first to last execution)
+        let window_secs = match (&self.first_seen, &self.last_seen) {
+            (Some(first), Some(last)) => match (
+                chrono::DateTime::parse_from_rfc3339(first),
+                chrono::DateTime::parse_from_rfc3339(last),
+            ) {
+                (Ok(first), Ok(last)) => (last - first).num_milliseconds() as f64 / 1000.0,
+                _ => 0.0,
+            },
+            _ => 0.0,
+        };
+
+        TimingSummary {
+            count: self.count,
+            errors: self.errors,
+            error_rate: if self.count == 0 { 0.0 } else { self.errors as f64 / self.count as f64 },
+            avg_ms: if self.count == 0 { 0.0 } else { self.total_ms / self.count as f64 },
+            p50_ms: percentile(50.0),
+            p95_ms: percentile(95.0),
+            p99_ms: percentile(99.0),
+            max_ms: self.max_ms,
+            throughput_per_sec: if window_secs > 0.0 { self.count as f64 / window_secs } else { 0.0 },
+            first_seen: self.first_seen.clone(),
+            last_seen: self.last_seen.clone(),
+        }
+    }
+}
+
+/// Timing summary for one node type, workflow or workflow node
+#[derive(Debug, Clone, Serialize)]
+pub struct TimingSummary {
+    pub count: u64,
+    pub errors: u64,
+    pub error_rate: f64,
+    pub avg_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+    /// Executions per second between the first and last observation
+    pub throughput_per_sec: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_seen: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_seen: Option<String>,
+}
+
+/// Statistics of one workflow and its nodes
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkflowProfile {
+    pub workflow_id: String,
+    pub executions: TimingSummary,
+    pub nodes: BTreeMap<String, TimingSummary>,
+}
+
+/// All profiled keys
+#[derive(Debug, Default)]
+struct ProfileData {
+    /// node type -> stats
+    node_types: HashMap<String, TimingStats>,
+    /// workflow_id -> whole-execution stats
+    workflows: HashMap<String, TimingStats>,
+    /// (workflow_id, node_id) -> stats
+    workflow_nodes: HashMap<(String, String), TimingStats>,
+}
+
+/// Shared profiler fed by the execution engine
+#[derive(Debug, Default)]
+pub struct ExecutionProfiler {
+    data: Mutex<ProfileData>,
+}
+
+impl ExecutionProfiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record one node execution
+    pub fn record_node(&self, workflow_id: &str, node_id: &str, node_type: &str, duration: Duration, failed: bool) {
+        let mut data = self.data.lock().unwrap();
+        data.node_types.entry(node_type.to_string()).or_default().record(duration, failed);
+        data.workflow_nodes
+            .entry((workflow_id.to_string(), node_id.to_string()))
+            .or_default()
+            .record(duration, failed);
+    }
+
+    /// Record one workflow execution
+    pub fn record_workflow(&self, workflow_id: &str, duration: Duration, failed: bool) {
+        let mut data = self.data.lock().unwrap();
+        data.workflows.entry(workflow_id.to_string()).or_default().record(duration, failed);
+    }
+
+    /// Summaries per node type
+    pub fn node_type_stats(&self) -> BTreeMap<String, TimingSummary> {
+        let data = self.data.lock().unwrap();
+        data.node_types.iter().map(|(node_type, stats)| (node_type.clone(), stats.summary())).collect()
+    }
+
+    /// Summary of one workflow and its nodes (None if it never ran)
+    pub fn workflow_stats(&self, workflow_id: &str) -> Option<WorkflowProfile> {
+        let data = self.data.lock().unwrap();
+        let executions = data.workflows.get(workflow_id)?.summary();
+        let nodes = data.workflow_nodes.iter()
+            .filter(|((workflow, _), _)| workflow == workflow_id)
+            .map(|((_, node_id), stats)| (node_id.clone(), stats.summary()))
+            .collect();
+        Some(WorkflowProfile { workflow_id: workflow_id.to_string(), executions, nodes })
+    }
+
+    /// Load persisted statistics (merging into anything recorded since startup)
+    pub async fn load(&self, pool: &SqlitePool) -> Result<usize> {
+        let rows = sqlx::query("SELECT scope, key, node_id, stats FROM profiling_stats")
+            .fetch_all(pool)
+            .await?;
+
+        let mut data = self.data.lock().unwrap();
+        let mut loaded = 0;
+        for row in rows {
+            let scope: String = row.get("scope");
+            let key: String = row.get("key");
+            let node_id: String = row.get("node_id");
+            let stats_json: String = row.get("stats");
+            let Ok(stats) = serde_json::from_str::<TimingStats>(&stats_json) else { continue };
+            let slot = match scope.as_str() {
+                "node_type" => data.node_types.entry(key).or_default(),
+                "workflow" => data.workflows.entry(key).or_default(),
+                "workflow_node" => data.workflow_nodes.entry((key, node_id)).or_default(),
+                _ => continue,
+            };
+            merge(slot, stats);
+            loaded += 1;
+        }
+        Ok(loaded)
+    }
+
+    /// Persist a snapshot of all statistics
+    pub async fn persist(&self, pool: &SqlitePool) -> Result<()> {
+        // (scope, key, node_id, stats JSON); node_id is empty except for workflow nodes
+        let rows: Vec<(&'static str, String, String, String)> = {
+            let data = self.data.lock().unwrap();
+            let node_types = data.node_types.iter().map(|(k, s)| ("node_type", k.clone(), String::new(), s));
+            let workflows = data.workflows.iter().map(|(k, s)| ("workflow", k.clone(), String::new(), s));
+            let workflow_nodes = data.workflow_nodes.iter()
+                .map(|((workflow_id, node_id), s)| ("workflow_node", workflow_id.clone(), node_id.clone(), s));
+            node_types.chain(workflows).chain(workflow_nodes)
+                .map(|(scope, key, node_id, stats)| Ok((scope, key, node_id, serde_json::to_string(stats)?)))
+                .collect::<Result<_>>()?
+        };
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut tx = pool.begin().await?;
+        for (scope, key, node_id, stats) in &rows {
+            sqlx::query(
+                r#"
+                INSERT INTO profiling_stats (scope, key, node_id, stats, updated_at)
+                VALUES (?, ?, ?, ?, ?)
+                ON CONFLICT(scope, key, node_id) DO UPDATE SET stats = excluded.stats, updated_at = excluded.updated_at
+                "#,
+            )
+            .bind(scope)
+            .bind(key)
+            .bind(node_id)
+            .bind(stats)
+            .bind(&now)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        tracing::debug!("💾 Persisted {} profiling entries", rows.len());
+        Ok(())
+    }
+
+    /// Persist snapshots every `interval` in the background
+    pub fn start_persistence(self: &Arc<Self>, pool: SqlitePool, interval: Duration) {
+        let profiler = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                if let Err(e) = profiler.persist(&pool).await {
+                    tracing::warn!("⚠️ Failed to persist profiling stats: {}", e);
+                }
+            }
+        });
+    }
+}
+
+/// Fold persisted statistics into live ones
+fn merge(live: &mut TimingStats, persisted: TimingStats) {
+    live.count += persisted.count;
+    live.errors += persisted.errors;
+    live.total_ms += persisted.total_ms;
+    live.max_ms = live.max_ms.max(persisted.max_ms);
+
+    // Persisted samples are older than anything recorded since startup
+    let mut samples = persisted.samples;
+    samples.extend(live.samples.drain(..));
+    while samples.len() > SAMPLE_WINDOW {
+        samples.pop_front();
+    }
+    live.samples = samples;
+
+    if persisted.first_seen.is_some() {
+        live.first_seen = persisted.first_seen;
+    }
+    if live.last_seen.is_none() {
+        live.last_seen = persisted.last_seen;
+    }
+}
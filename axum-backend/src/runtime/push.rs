@@ -0,0 +1,385 @@
+//! PushNotification node delivery
+//! 
+//! Sends one notification per input item through Firebase Cloud Messaging
+//! (HTTP v1, service account JSON from the node's secrets) or Web Push
+//! (VAPID private key from the node's secrets):
+//! 
+//! { "provider": "fcm", "token": "$json.device_token", "title": "Alert", "body": "{{$json.sensor}} is offline" }
+//! { "provider": "webpush", "subscription": "$json.subscription", "vapid_subject": "mailto:ops@example.com",
+//!   "title": "Alert", "body": "{{$json.sensor}} is offline" }
+//! 
+//! FCM access tokens come from an RS256-signed service account JWT. Web Push
+//! payloads are encrypted for the subscription keys (RFC 8291, aes128gcm) and
+//! authorized with an ES256 VAPID JWT (RFC 8292). Targets the push service
+//! reports as gone (FCM UNREGISTERED, Web Push 404/410) don't fail the node;
+//! the result says `invalid_target: true` so the workflow can remove them.
+
+use crate::runtime::outbox;
+use aes_gcm::{
+    aead::{Aead, KeyInit, OsRng},
+    Aes128Gcm, Nonce,
+};
+use anyhow::Result;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hkdf::Hkdf;
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use serde_json::{json, Map, Value};
+use sha2::Sha256;
+
+/// Firebase messaging OAuth scope
+const FCM_SCOPE: &str = "https://www.googleapis.com/auth/firebase.messaging";
+
+/// FCM API host (overridable with "api_base" for tests)
+const DEFAULT_FCM_API_BASE: &str = "https://fcm.googleapis.com";
+
+/// Lifetime of VAPID JWTs (the spec allows at most 24 hours)
+const VAPID_JWT_TTL_SECS: i64 = 12 * 3600;
+
+/// Record size advertised in the aes128gcm header
+const RECORD_SIZE: u32 = 4096;
+
+/// Push provider
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PushProvider {
+    Fcm,
+    WebPush,
+}
+
+/// Parsed PushNotification node params (templates still unrendered)
+#[derive(Debug, Clone)]
+pub struct PushParams {
+    pub provider: PushProvider,
+    /// Rendered per input item: token / topic / subscription, title, body, data
+    pub templates: Value,
+    /// Web Push: contact URI sent in the VAPID JWT ("mailto:..." or "https://...")
+    pub vapid_subject: Option<String>,
+    /// Seconds the push service keeps undelivered messages
+    pub ttl_secs: u64,
+    /// Web Push urgency (very-low, low, normal, high)
+    pub urgency: Option<String>,
+    pub api_base: String,
+}
+
+impl PushParams {
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        let text = |name: &str| params.get(name).and_then(|v| v.as_str()).map(str::to_string);
+        let provider = match text("provider").as_deref() {
+            Some("fcm") => PushProvider::Fcm,
+            Some("webpush") => PushProvider::WebPush,
+            Some(other) => return Err(anyhow::anyhow!("Unknown push provider '{}' (fcm, webpush)", other)),
+            None => return Err(anyhow::anyhow!("Missing 'provider' parameter")),
+        };
+
+        let target_keys: &[&str] = match provider {
+            PushProvider::Fcm => &["token", "topic"],
+            PushProvider::WebPush => &["subscription"],
+        };
+        if !target_keys.iter().any(|key| params.get(*key).is_some_and(|v| !v.is_null())) {
+            return Err(anyhow::anyhow!("Missing target: one of {:?}", target_keys));
+        }
+        let vapid_subject = text("vapid_subject");
+        if provider == PushProvider::WebPush
+            && !vapid_subject.as_deref().is_some_and(|s| s.starts_with("mailto:") || s.starts_with("https://"))
+        {
+            return Err(anyhow::anyhow!("'vapid_subject' must be a mailto: or https: URI"));
+        }
+        let urgency = text("urgency");
+        if let Some(urgency) = &urgency {
+            if !["very-low", "low", "normal", "high"].contains(&urgency.as_str()) {
+                return Err(anyhow::anyhow!("'urgency' must be very-low, low, normal or high"));
+            }
+        }
+
+        let mut templates = Map::new();
+        for key in ["token", "topic", "subscription", "title", "body", "data", "image"] {
+            if let Some(value) = params.get(key).filter(|v| !v.is_null()) {
+                templates.insert(key.to_string(), value.clone());
+            }
+        }
+
+        Ok(Self {
+            provider,
+            templates: Value::Object(templates),
+            vapid_subject,
+            ttl_secs: params.get("ttl_secs").and_then(|t| t.as_u64()).unwrap_or(86400),
+            urgency,
+            api_base: text("api_base").unwrap_or_else(|| DEFAULT_FCM_API_BASE.to_string()),
+        })
+    }
+}
+
+/// FCM service account credentials
+pub struct FcmAccount {
+    project_id: String,
+    client_email: String,
+    token_uri: String,
+    private_key: rsa::RsaPrivateKey,
+}
+
+impl FcmAccount {
+    /// Parse a Google service account JSON key
+    pub fn from_json(json: &str) -> Result<Self> {
+        use rsa::pkcs8::DecodePrivateKey;
+
+        let account: Value = serde_json::from_str(json)
+            .map_err(|_| anyhow::anyhow!("FCM secret is not a service account JSON key"))?;
+        let field = |name: &str| account.get(name).and_then(|v| v.as_str()).map(str::to_string)
+            .ok_or_else(|| anyhow::anyhow!("Service account JSON has no '{}'", name));
+        Ok(Self {
+            project_id: field("project_id")?,
+            client_email: field("client_email")?,
+            token_uri: field("token_uri").unwrap_or_else(|_| "https://oauth2.googleapis.com/token".to_string()),
+            private_key: rsa::RsaPrivateKey::from_pkcs8_pem(&field("private_key")?)
+                .map_err(|e| anyhow::anyhow!("Invalid service account private key: {}", e))?,
+        })
+    }
+
+    /// Exchange a signed JWT for an access token (RFC 7523)
+    pub async fn access_token(&self, client: &reqwest::Client) -> Result<String> {
+        use rsa::signature::{SignatureEncoding, Signer};
+
+        let now = chrono::Utc::now().timestamp();
+        let claims = json!({
+            "iss": self.client_email,
+            "scope": FCM_SCOPE,
+            "aud": self.token_uri,
+            "iat": now,
+            "exp": now + 3600,
+        });
+        let signing_input = jwt_signing_input("RS256", &claims);
+        let signing_key = rsa::pkcs1v15::SigningKey::<Sha256>::new(self.private_key.clone());
+        let signature = signing_key.sign(signing_input.as_bytes()).to_vec();
+        let assertion = format!("{}.{}", signing_input, URL_SAFE_NO_PAD.encode(signature));
+
+        let response = client.post(&self.token_uri)
+            .form(&[("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"), ("assertion", assertion.as_str())])
+            .send()
+            .await
+            .map_err(|e| outbox::request_error("FCM token request failed", e))?;
+        let status = response.status();
+        let body: Value = response.json().await
+            .map_err(|e| anyhow::anyhow!("FCM token endpoint returned invalid JSON ({}): {}", status, e))?;
+        body.get("access_token").and_then(|t| t.as_str()).map(str::to_string)
+            .ok_or_else(|| anyhow::anyhow!("FCM token request failed ({}): {}", status,
+                body.get("error_description").or_else(|| body.get("error")).unwrap_or(&Value::Null)))
+    }
+}
+
+/// Send one FCM message (rendered templates) with an access token
+pub async fn send_fcm(
+    client: &reqwest::Client,
+    params: &PushParams,
+    account: &FcmAccount,
+    access_token: &str,
+    rendered: &Value,
+) -> Result<Value> {
+    let mut message = Map::new();
+    let target = match (rendered.get("token").and_then(|t| t.as_str()), rendered.get("topic").and_then(|t| t.as_str())) {
+        (Some(token), _) if !token.is_empty() => ("token", token),
+        (_, Some(topic)) if !topic.is_empty() => ("topic", topic),
+        _ => return Err(anyhow::anyhow!("FCM target (token or topic) rendered empty")),
+    };
+    message.insert(target.0.to_string(), json!(target.1));
+    message.insert("notification".to_string(), notification(rendered));
+    if let Some(Value::Object(data)) = rendered.get("data") {
+        // FCM data values must be strings
+        let data: Map<String, Value> = data.iter()
+            .map(|(k, v)| (k.clone(), Value::String(v.as_str().map(str::to_string).unwrap_or_else(|| v.to_string()))))
+            .collect();
+        message.insert("data".to_string(), Value::Object(data));
+    }
+    message.insert("android".to_string(), json!({ "ttl": format!("{}s", params.ttl_secs) }));
+
+    let url = format!("{}/v1/projects/{}/messages:send", params.api_base.trim_end_matches('/'), account.project_id);
+    let response = client.post(&url)
+        .bearer_auth(access_token)
+        .json(&json!({ "message": message }))
+        .send()
+        .await
+        .map_err(|e| outbox::request_error("FCM request failed", e))?;
+    let status = response.status();
+    let body: Value = response.json().await.unwrap_or(Value::Null);
+
+    if status.is_success() {
+        return Ok(json!({ "provider": "fcm", "target": target.1, "success": true, "id": body.get("name") }));
+    }
+    let error_status = body.pointer("/error/status").and_then(|s| s.as_str()).unwrap_or_default();
+    let fcm_error = body.pointer("/error/details")
+        .and_then(|d| d.as_array())
+        .and_then(|details| details.iter().find_map(|d| d.get("errorCode").and_then(|c| c.as_str())))
+        .unwrap_or(error_status);
+    let message = body.pointer("/error/message").and_then(|m| m.as_str()).unwrap_or("unknown error");
+    if fcm_error == "UNREGISTERED" || (status == reqwest::StatusCode::NOT_FOUND && target.0 == "token") {
+        return Ok(json!({ "provider": "fcm", "target": target.1, "success": false, "invalid_target": true, "error": message }));
+    }
+    Err(anyhow::anyhow!("FCM error {} ({}): {}", status.as_u16(), fcm_error, message))
+}
+
+/// VAPID key pair from the private key secret
+pub struct VapidKey {
+    signing_key: p256::ecdsa::SigningKey,
+}
+
+impl VapidKey {
+    /// Parse a base64url-encoded raw P-256 private key (as generated by web-push tools)
+    pub fn from_base64(encoded: &str) -> Result<Self> {
+        let bytes = decode_base64url(encoded)
+            .ok_or_else(|| anyhow::anyhow!("VAPID private key is not base64url"))?;
+        let signing_key = p256::ecdsa::SigningKey::from_slice(&bytes)
+            .map_err(|_| anyhow::anyhow!("VAPID private key must be a 32-byte P-256 key"))?;
+        Ok(Self { signing_key })
+    }
+
+    /// Uncompressed public key, base64url (the applicationServerKey of the subscription)
+    pub fn public_key(&self) -> String {
+        URL_SAFE_NO_PAD.encode(self.signing_key.verifying_key().to_encoded_point(false).as_bytes())
+    }
+
+    /// Authorization header value for a push service endpoint
+    fn authorization(&self, endpoint: &reqwest::Url, subject: &str) -> Result<String> {
+        use p256::ecdsa::signature::Signer;
+
+        let audience = endpoint.origin().ascii_serialization();
+        let claims = json!({
+            "aud": audience,
+            "exp": chrono::Utc::now().timestamp() + VAPID_JWT_TTL_SECS,
+            "sub": subject,
+        });
+        let signing_input = jwt_signing_input("ES256", &claims);
+        let signature: p256::ecdsa::Signature = self.signing_key.sign(signing_input.as_bytes());
+        Ok(format!("vapid t={}.{}, k={}", signing_input, URL_SAFE_NO_PAD.encode(signature.to_bytes()), self.public_key()))
+    }
+}
+
+/// Send one Web Push message (rendered templates) to a subscription
+pub async fn send_webpush(client: &reqwest::Client, params: &PushParams, vapid: &VapidKey, rendered: &Value) -> Result<Value> {
+    let subscription = rendered.get("subscription")
+        .ok_or_else(|| anyhow::anyhow!("Web Push subscription rendered empty"))?;
+    let endpoint = subscription.get("endpoint").and_then(|e| e.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Subscription has no 'endpoint'"))?;
+    let endpoint_url = reqwest::Url::parse(endpoint)
+        .map_err(|e| anyhow::anyhow!("Invalid subscription endpoint: {}", e))?;
+    let key = |name: &str| subscription.pointer(&format!("/keys/{}", name)).and_then(|k| k.as_str())
+        .and_then(decode_base64url)
+        .ok_or_else(|| anyhow::anyhow!("Subscription has no valid 'keys.{}'", name));
+    let (ua_public, auth_secret) = (key("p256dh")?, key("auth")?);
+
+    let mut payload = notification(rendered);
+    if let Some(data) = rendered.get("data") {
+        payload["data"] = data.clone();
+    }
+    let salt: [u8; 16] = rand_bytes();
+    let body = encrypt_aes128gcm(
+        serde_json::to_vec(&payload)?.as_slice(),
+        &ua_public,
+        &auth_secret,
+        &p256::SecretKey::random(&mut OsRng),
+        &salt,
+    )?;
+
+    let subject = params.vapid_subject.as_deref().unwrap_or_default();
+    let mut request = client.post(endpoint_url.clone())
+        .header("Authorization", vapid.authorization(&endpoint_url, subject)?)
+        .header("Content-Encoding", "aes128gcm")
+        .header("Content-Type", "application/octet-stream")
+        .header("TTL", params.ttl_secs.to_string())
+        .body(body);
+    if let Some(urgency) = &params.urgency {
+        request = request.header("Urgency", urgency.as_str());
+    }
+    let response = request.send().await
+        .map_err(|e| outbox::request_error("Web Push request failed", e))?;
+    let status = response.status();
+
+    if status.is_success() {
+        let id = response.headers().get("location").and_then(|l| l.to_str().ok()).map(str::to_string);
+        return Ok(json!({ "provider": "webpush", "target": endpoint, "success": true, "id": id }));
+    }
+    let text = response.text().await.unwrap_or_default();
+    if status == reqwest::StatusCode::NOT_FOUND || status == reqwest::StatusCode::GONE {
+        return Ok(json!({ "provider": "webpush", "target": endpoint, "success": false, "invalid_target": true,
+            "error": format!("subscription expired ({})", status.as_u16()) }));
+    }
+    Err(anyhow::anyhow!("Web Push error {}: {}", status.as_u16(), text.chars().take(200).collect::<String>()))
+}
+
+/// Encrypt a payload for one subscription (RFC 8291, single aes128gcm record)
+pub fn encrypt_aes128gcm(
+    plaintext: &[u8],
+    ua_public: &[u8],
+    auth_secret: &[u8],
+    as_secret: &p256::SecretKey,
+    salt: &[u8; 16],
+) -> Result<Vec<u8>> {
+    let ua_key = p256::PublicKey::from_sec1_bytes(ua_public)
+        .map_err(|_| anyhow::anyhow!("Subscription p256dh is not a P-256 public key"))?;
+    let as_public = as_secret.public_key().to_encoded_point(false);
+    let shared = p256::ecdh::diffie_hellman(as_secret.to_nonzero_scalar(), ua_key.as_affine());
+
+    // IKM = HKDF(auth_secret, ecdh_secret, "WebPush: info" || 0x00 || ua_public || as_public)
+    let mut key_info = b"WebPush: info\0".to_vec();
+    key_info.extend_from_slice(ua_public);
+    key_info.extend_from_slice(as_public.as_bytes());
+    let mut ikm = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(auth_secret), shared.raw_secret_bytes())
+        .expand(&key_info, &mut ikm)
+        .map_err(|_| anyhow::anyhow!("HKDF expand failed"))?;
+
+    let prk = Hkdf::<Sha256>::new(Some(salt), &ikm);
+    let mut cek = [0u8; 16];
+    let mut nonce = [0u8; 12];
+    prk.expand(b"Content-Encoding: aes128gcm\0", &mut cek)
+        .and_then(|()| prk.expand(b"Content-Encoding: nonce\0", &mut nonce))
+        .map_err(|_| anyhow::anyhow!("HKDF expand failed"))?;
+
+    // Single (last) record: plaintext followed by the 0x02 delimiter, no padding
+    let mut record = plaintext.to_vec();
+    record.push(0x02);
+    let ciphertext = Aes128Gcm::new_from_slice(&cek)?
+        .encrypt(Nonce::from_slice(&nonce), record.as_slice())
+        .map_err(|_| anyhow::anyhow!("Payload encryption failed"))?;
+    if ciphertext.len() > RECORD_SIZE as usize {
+        return Err(anyhow::anyhow!("Web Push payload too large ({} bytes)", plaintext.len()));
+    }
+
+    // Header: salt (16) || record size (4) || key id length (1) || key id (as_public)
+    let mut body = Vec::with_capacity(86 + ciphertext.len());
+    body.extend_from_slice(salt);
+    body.extend_from_slice(&RECORD_SIZE.to_be_bytes());
+    body.push(as_public.as_bytes().len() as u8);
+    body.extend_from_slice(as_public.as_bytes());
+    body.extend_from_slice(&ciphertext);
+    Ok(body)
+}
+
+/// Notification part shared by both providers
+fn notification(rendered: &Value) -> Value {
+    let mut notification = Map::new();
+    for key in ["title", "body", "image"] {
+        if let Some(value) = rendered.get(key).filter(|v| !v.is_null()) {
+            notification.insert(key.to_string(), value.clone());
+        }
+    }
+    Value::Object(notification)
+}
+
+/// "{header}.{claims}" of a compact JWS
+fn jwt_signing_input(alg: &str, claims: &Value) -> String {
+    let header = json!({ "alg": alg, "typ": "JWT" });
+    format!("{}.{}", URL_SAFE_NO_PAD.encode(header.to_string()), URL_SAFE_NO_PAD.encode(claims.to_string()))
+}
+
+/// Decode base64url with or without padding (standard alphabet tolerated)
+fn decode_base64url(encoded: &str) -> Option<Vec<u8>> {
+    let normalized: String = encoded.trim().trim_end_matches('=').chars()
+        .map(|c| match c { '+' => '-', '/' => '_', c => c })
+        .collect();
+    URL_SAFE_NO_PAD.decode(normalized).ok()
+}
+
+fn rand_bytes<const N: usize>() -> [u8; N] {
+    use aes_gcm::aead::rand_core::RngCore;
+    let mut bytes = [0u8; N];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
@@ -0,0 +1,212 @@
+//! Bounded execution queue with admission control
+//! 
+//! Limits how many workflow executions run concurrently and how many may wait
+//! for a slot. When the queue is saturated, new work is shed at ingress instead
+//! of piling up unbounded tasks:
+//! - queue depth >= throttle threshold -> Throttled (HTTP 429)
+//! - queue depth >= max depth          -> Overloaded (HTTP 503)
+//! 
+//! Runs of snoozed workflows are rejected as well (Snoozed, HTTP 503), and
+//! nothing is admitted in maintenance mode (Maintenance, HTTP 503). Free slots
+//! are shared across projects by weight (see fair_share), so one tenant's burst
+//! doesn't hold up the others.
+
+use crate::{
+    config::ExecutionConfig,
+    runtime::{
+        engine::ExecutionEngine,
+        executor::ExecutionResult,
+        fair_share::{parse_project_weights, FairSlots, ProjectShare},
+    },
+    workflow::{registry::CompiledWorkflow, types::ExecutionContext},
+};
+use anyhow::Result;
+use serde::Serialize;
+use std::sync::{
+    atomic::{AtomicU64, AtomicUsize, Ordering},
+    Arc,
+};
+
+/// Reason an execution was not admitted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdmissionError {
+    /// Queue is getting deep - caller should slow down (429)
+    Throttled { retry_after_secs: u64 },
+    /// Queue is full - work is rejected outright (503)
+    Overloaded { retry_after_secs: u64 },
+    /// The workflow is snoozed - its triggers are paused until the snooze ends (503)
+    Snoozed { retry_after_secs: u64 },
+    /// The server is in maintenance mode - no new runs are admitted (503)
+    Maintenance { retry_after_secs: u64 },
+}
+
+impl AdmissionError {
+    /// Suggested Retry-After value in seconds
+    pub fn retry_after_secs(&self) -> u64 {
+        match self {
+            AdmissionError::Throttled { retry_after_secs } => *retry_after_secs,
+            AdmissionError::Overloaded { retry_after_secs } => *retry_after_secs,
+            AdmissionError::Snoozed { retry_after_secs } => *retry_after_secs,
+            AdmissionError::Maintenance { retry_after_secs } => *retry_after_secs,
+        }
+    }
+}
+
+impl std::fmt::Display for AdmissionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AdmissionError::Throttled { .. } => write!(f, "execution queue is saturated, slow down"),
+            AdmissionError::Overloaded { .. } => write!(f, "execution queue is full"),
+            AdmissionError::Snoozed { .. } => write!(f, "workflow is snoozed"),
+            AdmissionError::Maintenance { .. } => write!(f, "server is under maintenance"),
+        }
+    }
+}
+
+/// Point-in-time queue statistics for the metrics endpoint
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueStats {
+    pub max_concurrent: usize,
+    pub running: usize,
+    pub queued: usize,
+    pub throttle_queue_depth: usize,
+    pub max_queue_depth: usize,
+    pub admitted_total: u64,
+    pub completed_total: u64,
+    pub failed_total: u64,
+    pub shed_throttled_total: u64,
+    pub shed_overloaded_total: u64,
+    /// Projects with running or queued executions and their weights
+    pub projects: Vec<ProjectShare>,
+}
+
+/// Increments a gauge on creation and decrements it on drop
+/// 
+/// Keeps queued/running counts correct when a waiting request is cancelled
+/// (e.g. the HTTP client disconnects and the handler future is dropped).
+struct GaugeGuard<'a>(&'a AtomicUsize);
+
+impl<'a> GaugeGuard<'a> {
+    fn increment(gauge: &'a AtomicUsize) -> Self {
+        gauge.fetch_add(1, Ordering::Relaxed);
+        Self(gauge)
+    }
+}
+
+impl Drop for GaugeGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Concurrency-limited execution queue shared by all trigger ingress paths
+#[derive(Debug)]
+pub struct ExecutionQueue {
+    engine: Arc<ExecutionEngine>,
+    config: ExecutionConfig,
+    /// Execution slots (one permit per running execution), shared fairly across projects
+    slots: Arc<FairSlots>,
+    /// Executions waiting for a slot
+    queued: AtomicUsize,
+    /// Executions currently holding a slot
+    running: AtomicUsize,
+    admitted_total: AtomicU64,
+    completed_total: AtomicU64,
+    failed_total: AtomicU64,
+    shed_throttled_total: AtomicU64,
+    shed_overloaded_total: AtomicU64,
+}
+
+impl ExecutionQueue {
+    /// Create new execution queue in front of the execution engine
+    pub fn new(engine: Arc<ExecutionEngine>, config: ExecutionConfig) -> Self {
+        Self {
+            slots: Arc::new(FairSlots::new(
+                config.max_concurrent_executions.max(1),
+                config.project_weights.as_deref().map(parse_project_weights).unwrap_or_default(),
+            )),
+            engine,
+            config,
+            queued: AtomicUsize::new(0),
+            running: AtomicUsize::new(0),
+            admitted_total: AtomicU64::new(0),
+            completed_total: AtomicU64::new(0),
+            failed_total: AtomicU64::new(0),
+            shed_throttled_total: AtomicU64::new(0),
+            shed_overloaded_total: AtomicU64::new(0),
+        }
+    }
+
+    /// Check whether new work may be admitted at the current queue depth (and outside maintenance mode)
+    pub fn check_admission(&self) -> std::result::Result<(), AdmissionError> {
+        if let Some(notice) = self.engine.maintenance_mode().current() {
+            return Err(AdmissionError::Maintenance { retry_after_secs: notice.retry_after_secs });
+        }
+        let depth = self.queued.load(Ordering::Relaxed);
+        let retry_after_secs = self.config.retry_after_secs;
+
+        if depth >= self.config.max_queue_depth {
+            self.shed_overloaded_total.fetch_add(1, Ordering::Relaxed);
+            return Err(AdmissionError::Overloaded { retry_after_secs });
+        }
+        if depth >= self.config.throttle_queue_depth {
+            self.shed_throttled_total.fetch_add(1, Ordering::Relaxed);
+            return Err(AdmissionError::Throttled { retry_after_secs });
+        }
+        Ok(())
+    }
+
+    /// Admit and execute a workflow, waiting for a free slot
+    /// 
+    /// The outer error means the work was shed (or the workflow is snoozed); the
+    /// inner result is the workflow execution outcome.
+    pub async fn execute(
+        &self,
+        workflow: &CompiledWorkflow,
+        start_node_id: &str,
+        context: ExecutionContext,
+    ) -> std::result::Result<Result<ExecutionResult>, AdmissionError> {
+        if let Some(snooze) = workflow.active_snooze() {
+            return Err(AdmissionError::Snoozed { retry_after_secs: snooze.retry_after_secs() });
+        }
+        self.check_admission()?;
+
+        self.admitted_total.fetch_add(1, Ordering::Relaxed);
+        let queued_guard = GaugeGuard::increment(&self.queued);
+        let permit = self.slots.acquire(&workflow.workflow.project).await;
+        drop(queued_guard);
+        // Waiters are only dropped unserved when the queue itself goes away
+        let _permit = match permit {
+            Some(permit) => permit,
+            None => return Ok(Err(anyhow::anyhow!("Execution queue is shutting down"))),
+        };
+
+        let running_guard = GaugeGuard::increment(&self.running);
+        let result = self.engine.execute_workflow(workflow, start_node_id, context).await;
+        drop(running_guard);
+
+        match &result {
+            Ok(_) => self.completed_total.fetch_add(1, Ordering::Relaxed),
+            Err(_) => self.failed_total.fetch_add(1, Ordering::Relaxed),
+        };
+
+        Ok(result)
+    }
+
+    /// Current queue statistics
+    pub fn stats(&self) -> QueueStats {
+        QueueStats {
+            max_concurrent: self.config.max_concurrent_executions,
+            running: self.running.load(Ordering::Relaxed),
+            queued: self.queued.load(Ordering::Relaxed),
+            throttle_queue_depth: self.config.throttle_queue_depth,
+            max_queue_depth: self.config.max_queue_depth,
+            admitted_total: self.admitted_total.load(Ordering::Relaxed),
+            completed_total: self.completed_total.load(Ordering::Relaxed),
+            failed_total: self.failed_total.load(Ordering::Relaxed),
+            shed_throttled_total: self.shed_throttled_total.load(Ordering::Relaxed),
+            shed_overloaded_total: self.shed_overloaded_total.load(Ordering::Relaxed),
+            projects: self.slots.stats(),
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! Startup recovery of interrupted executions
+//!
+//! Execution history records a run as "running" before it starts and updates it
+//! when it finishes, so records still "running" at startup belong to a process
+//! that crashed or was killed mid-run. They are marked "interrupted" (instead of
+//! staying "running" forever) and, for workflows with the `resume_on_restart`
+//! setting, run again from their stored trigger payload through the execution
+//! queue. The new run is recorded with trigger type "resume" and `replay_of`
+//! pointing at the interrupted one.
+
+use crate::{
+    project::{executions::ExecutionRecord, ProjectDatabaseManager},
+    runtime::queue::ExecutionQueue,
+    workflow::registry::WorkflowRegistry,
+};
+use serde_json::json;
+use std::sync::Arc;
+
+/// Outcome of a startup recovery pass
+#[derive(Debug, Default)]
+pub struct RecoveryReport {
+    /// Executions marked interrupted
+    pub interrupted: usize,
+    /// Interrupted executions started again
+    pub resumed: usize,
+}
+
+/// Mark interrupted executions of every project and resume the opted-in ones
+///
+/// Must run before any trigger is started, while no execution is in flight.
+/// Resumed runs execute in the background.
+pub async fn recover_interrupted_executions(
+    project_db_manager: &ProjectDatabaseManager,
+    registry: &WorkflowRegistry,
+    queue: &Arc<ExecutionQueue>,
+) -> RecoveryReport {
+    let mut report = RecoveryReport::default();
+
+    for project in project_db_manager.known_projects().await {
+        let interrupted = match project_db_manager.mark_interrupted_executions(&project).await {
+            Ok(interrupted) => interrupted,
+            Err(e) => {
+                tracing::warn!("⚠️ Failed to recover interrupted executions of project {}: {}", project, e);
+                continue;
+            }
+        };
+        report.interrupted += interrupted.len();
+
+        for execution in interrupted {
+            tracing::warn!("💥 Execution {} of workflow '{}' was interrupted by a restart", execution.id, execution.workflow_id);
+            if resume(registry, queue, execution) {
+                report.resumed += 1;
+            }
+        }
+    }
+
+    report
+}
+
+/// Start an interrupted execution again when its workflow opted in
+fn resume(registry: &WorkflowRegistry, queue: &Arc<ExecutionQueue>, execution: ExecutionRecord) -> bool {
+    let Some(workflow) = registry.get_workflow(&execution.workflow_id) else {
+        return false;
+    };
+    if !workflow.workflow.settings.resume_on_restart {
+        return false;
+    }
+
+    let mut context = execution.payload.into_context(
+        workflow.workflow.id.clone(),
+        workflow.workflow.project.clone(),
+    );
+    context.metadata.insert("trigger_type".to_string(), json!("resume"));
+    context.metadata.insert("replay_of".to_string(), json!(execution.id));
+
+    let queue = Arc::clone(queue);
+    tokio::spawn(async move {
+        tracing::info!("🔁 Resuming interrupted execution {} of workflow '{}'", execution.id, execution.workflow_id);
+        match queue.execute(&workflow, &execution.start_node_id, context).await {
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => tracing::error!("❌ Resumed execution {} failed: {}", execution.id, e),
+            Err(shed) => tracing::warn!("⚠️ Could not resume execution {}: {}", execution.id, shed),
+        }
+    });
+    true
+}
@@ -0,0 +1,265 @@
+//! Outgoing request signing for HTTPClient nodes
+//! 
+//! The "sign" param signs the final request right before it is sent, so
+//! workflows don't have to rebuild canonical strings in Lua:
+//! 
+//! { "sign": { "mode": "hmac", "secret": "$secret.partner_key", "header": "X-Signature",
+//!             "algorithm": "sha256", "encoding": "hex", "prefix": "sha256=",
+//!             "timestamp_header": "X-Timestamp" } }
+//! 
+//! { "sign": { "mode": "aws_sigv4", "access_key_id": "$secret.aws_key_id",
+//!             "secret_access_key": "$secret.aws_secret", "session_token": "$secret.aws_token",
+//!             "region": "eu-west-1", "service": "execute-api" } }
+//! 
+//! HMAC signs the raw body (prefixed with "{timestamp}." when a timestamp header
+//! is configured). SigV4 follows the AWS canonical request rules and adds the
+//! Authorization, X-Amz-Date and, for temporary credentials, X-Amz-Security-Token
+//! headers. Key material must come from `$secret.` pins. Multipart bodies are
+//! streamed and cannot be signed.
+
+use anyhow::Result;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use reqwest::{header::{HeaderName, HeaderValue}, Request};
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256, Sha512};
+
+/// Request signing settings of an HTTPClient node (params key "sign")
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum SignConfig {
+    Hmac {
+        /// `$secret.` pin of the shared key
+        secret: String,
+        #[serde(default = "default_signature_header")]
+        header: String,
+        #[serde(default)]
+        algorithm: HmacAlgorithm,
+        #[serde(default)]
+        encoding: SignatureEncoding,
+        /// Prepended to the encoded signature (e.g. "sha256=")
+        #[serde(default)]
+        prefix: String,
+        /// Send the unix time in this header and sign "{timestamp}.{body}"
+        #[serde(default)]
+        timestamp_header: Option<String>,
+    },
+    AwsSigv4 {
+        access_key_id: String,
+        secret_access_key: String,
+        #[serde(default)]
+        session_token: Option<String>,
+        region: String,
+        service: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HmacAlgorithm {
+    #[default]
+    Sha256,
+    Sha512,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureEncoding {
+    #[default]
+    Hex,
+    Base64,
+}
+
+fn default_signature_header() -> String {
+    "X-Signature".to_string()
+}
+
+impl SignConfig {
+    /// Read an HTTPClient node's "sign" param (None when requests are unsigned)
+    pub fn from_node_params(params: &Value) -> Result<Option<Self>> {
+        let Some(sign) = params.get("sign").filter(|s| !s.is_null()) else {
+            return Ok(None);
+        };
+        let config: Self = serde_json::from_value(sign.clone())
+            .map_err(|e| anyhow::anyhow!("Invalid 'sign' param: {}", e))?;
+        for pin in config.secret_pins() {
+            if !pin.starts_with("$secret.") {
+                return Err(anyhow::anyhow!("Signing keys must be $secret. pins, got '{}'", pin));
+            }
+        }
+        Ok(Some(config))
+    }
+
+    /// Secret pins holding key material
+    fn secret_pins(&self) -> Vec<&str> {
+        match self {
+            SignConfig::Hmac { secret, .. } => vec![secret],
+            SignConfig::AwsSigv4 { access_key_id, secret_access_key, session_token, .. } => {
+                let mut pins = vec![access_key_id.as_str(), secret_access_key.as_str()];
+                pins.extend(session_token.as_deref());
+                pins
+            }
+        }
+    }
+
+    /// Sign a built request in place, resolving `$secret.` pins through `resolve`
+    pub fn sign(
+        &self,
+        request: &mut Request,
+        now: chrono::DateTime<chrono::Utc>,
+        resolve: impl Fn(&str) -> Result<String>,
+    ) -> Result<()> {
+        let body: &[u8] = match request.body() {
+            None => &[],
+            Some(body) => body.as_bytes()
+                .ok_or_else(|| anyhow::anyhow!("Streamed (multipart) request bodies cannot be signed"))?,
+        };
+
+        match self {
+            SignConfig::Hmac { secret, header, algorithm, encoding, prefix, timestamp_header } => {
+                let key = resolve(secret)?;
+                let mut message = Vec::with_capacity(body.len() + 12);
+                let mut extra_headers = Vec::new();
+                if let Some(timestamp_header) = timestamp_header {
+                    let timestamp = now.timestamp().to_string();
+                    message.extend_from_slice(timestamp.as_bytes());
+                    message.push(b'.');
+                    extra_headers.push((timestamp_header.as_str(), timestamp));
+                }
+                message.extend_from_slice(body);
+
+                let digest = match algorithm {
+                    HmacAlgorithm::Sha256 => hmac_sha256(key.as_bytes(), &message),
+                    HmacAlgorithm::Sha512 => {
+                        let mut mac = Hmac::<Sha512>::new_from_slice(key.as_bytes()).expect("HMAC accepts any key length");
+                        mac.update(&message);
+                        mac.finalize().into_bytes().to_vec()
+                    }
+                };
+                let signature = match encoding {
+                    SignatureEncoding::Hex => hex::encode(digest),
+                    SignatureEncoding::Base64 => base64::engine::general_purpose::STANDARD.encode(digest),
+                };
+                extra_headers.push((header.as_str(), format!("{}{}", prefix, signature)));
+                for (name, value) in extra_headers {
+                    set_header(request, name, &value)?;
+                }
+            }
+            SignConfig::AwsSigv4 { access_key_id, secret_access_key, session_token, region, service } => {
+                let credentials = AwsCredentials {
+                    access_key_id: resolve(access_key_id)?,
+                    secret_access_key: resolve(secret_access_key)?,
+                    session_token: session_token.as_deref().map(&resolve).transpose()?,
+                };
+                let payload_hash = hex::encode(Sha256::digest(body));
+                sign_aws_v4(request, &credentials, region, service, &payload_hash, now)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+struct AwsCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+}
+
+/// AWS Signature Version 4 (header-based)
+fn sign_aws_v4(
+    request: &mut Request,
+    credentials: &AwsCredentials,
+    region: &str,
+    service: &str,
+    payload_hash: &str,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Result<()> {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+
+    // reqwest only adds Host when connecting, but it must be signed
+    let url = request.url().clone();
+    let host = match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{}:{}", host, port),
+        (Some(host), None) => host.to_string(),
+        (None, _) => return Err(anyhow::anyhow!("Cannot sign a request without a host")),
+    };
+    set_header(request, "host", &host)?;
+    set_header(request, "x-amz-date", &amz_date)?;
+    if let Some(token) = &credentials.session_token {
+        set_header(request, "x-amz-security-token", token)?;
+    }
+    if service == "s3" {
+        set_header(request, "x-amz-content-sha256", payload_hash)?;
+    }
+
+    // Canonical URI: S3 keys are encoded once, every other service twice
+    let canonical_uri = if service == "s3" {
+        url.path().to_string()
+    } else {
+        url.path().split('/').map(uri_encode).collect::<Vec<_>>().join("/")
+    };
+
+    let mut query: Vec<(String, String)> = url.query_pairs()
+        .map(|(k, v)| (uri_encode(&k), uri_encode(&v)))
+        .collect();
+    query.sort();
+    let canonical_query = query.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<_>>().join("&");
+
+    let mut headers: Vec<(String, String)> = request.headers().iter()
+        .map(|(name, value)| {
+            let value = String::from_utf8_lossy(value.as_bytes());
+            (name.as_str().to_lowercase(), value.split_whitespace().collect::<Vec<_>>().join(" "))
+        })
+        .collect();
+    headers.sort();
+    let canonical_headers: String = headers.iter().map(|(name, value)| format!("{}:{}\n", name, value)).collect();
+    let signed_headers = headers.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>().join(";");
+
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        request.method().as_str(), canonical_uri, canonical_query, canonical_headers, signed_headers, payload_hash,
+    );
+    let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date, scope, hex::encode(Sha256::digest(canonical_request.as_bytes())),
+    );
+
+    let signing_key = [region, service, "aws4_request"].iter().fold(
+        hmac_sha256(format!("AWS4{}", credentials.secret_access_key).as_bytes(), date.as_bytes()),
+        |key, part| hmac_sha256(&key, part.as_bytes()),
+    );
+    let signature = hex::encode(hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+
+    set_header(request, "authorization", &format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        credentials.access_key_id, scope, signed_headers, signature,
+    ))
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent-encode everything except RFC 3986 unreserved characters
+fn uri_encode(value: &str) -> String {
+    value.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn set_header(request: &mut Request, name: &str, value: &str) -> Result<()> {
+    let name = HeaderName::from_bytes(name.as_bytes())
+        .map_err(|e| anyhow::anyhow!("Invalid signature header name '{}': {}", name, e))?;
+    let value = HeaderValue::from_str(value)
+        .map_err(|e| anyhow::anyhow!("Invalid signature header value: {}", e))?;
+    request.headers_mut().insert(name, value);
+    Ok(())
+}
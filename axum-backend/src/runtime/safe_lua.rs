@@ -0,0 +1,109 @@
+//! Cached sandbox for safe Lua pin expressions
+//! 
+//! Creating a Lua VM and compiling the expression used to happen on every pin
+//! evaluation. Each worker thread now keeps one sandboxed VM and a cache of
+//! compiled chunks keyed by expression source, so repeated expressions only
+//! pay for the call itself.
+//! 
+//! SECURITY: Expressions are pre-filtered by `is_safe_lua_expression`; on top of
+//! that, globals and the math/string libraries are read-only so one request
+//! can't leave state behind for the next one on the same thread.
+
+use anyhow::Result;
+use std::{cell::RefCell, collections::HashMap};
+
+/// Compiled chunks kept per thread before the cache is reset
+const MAX_CACHED_CHUNKS: usize = 1024;
+
+/// Makes globals and shared library tables read-only
+const FREEZE_GLOBALS: &str = r#"
+local function readonly(tbl, name)
+    return setmetatable({}, {
+        __index = tbl,
+        __newindex = function(_, key) error("cannot modify " .. name .. "." .. tostring(key), 2) end,
+        __metatable = false,
+    })
+end
+math = readonly(math, "math")
+string = readonly(string, "string")
+setmetatable(_G, {
+    __newindex = function(_, key) error("cannot assign global '" .. tostring(key) .. "'", 2) end,
+    __metatable = false,
+})
+"#;
+
+/// mlua errors aren't Send/Sync, so flatten them into anyhow errors
+fn lua_error(e: mlua::Error) -> anyhow::Error {
+    anyhow::anyhow!("{}", e)
+}
+
+/// Per-thread Lua VM with the safe API and compiled chunk cache
+struct SafeLuaSandbox {
+    lua: mlua::Lua,
+    chunks: HashMap<String, mlua::Function>,
+}
+
+impl SafeLuaSandbox {
+    /// Create sandboxed Lua instance with safe time functions
+    fn new() -> Result<Self> {
+        let lua = mlua::Lua::new();
+        let globals = lua.globals();
+        
+        // Safe time functions (replace os.date, os.time)
+        globals.set("date", lua.create_function(|_, format: String| {
+            Ok(chrono::Utc::now().format(&format).to_string())
+        }).map_err(lua_error)?).map_err(lua_error)?;
+        globals.set("time", lua.create_function(|_, ()| {
+            Ok(chrono::Utc::now().timestamp())
+        }).map_err(lua_error)?).map_err(lua_error)?;
+        globals.set("now", lua.create_function(|_, ()| {
+            Ok(chrono::Utc::now().to_rfc3339())
+        }).map_err(lua_error)?).map_err(lua_error)?;
+        
+        // Remove dangerous globals
+        for name in ["os", "io", "debug", "package"] {
+            globals.set(name, mlua::Nil).map_err(lua_error)?;
+        }
+        
+        lua.load(FREEZE_GLOBALS).exec().map_err(lua_error)?;
+        
+        Ok(Self { lua, chunks: HashMap::new() })
+    }
+
+    /// Evaluate an expression, compiling it on first use
+    fn eval(&mut self, expr: &str) -> Result<mlua::Value> {
+        if let Some(function) = self.chunks.get(expr) {
+            return function.call(()).map_err(lua_error);
+        }
+        
+        // Expressions compile as `return <expr>`; fall back to a plain chunk
+        let function = match self.lua.load(format!("return {}", expr)).set_name("=pin").into_function() {
+            Ok(function) => function,
+            Err(_) => self.lua.load(expr).set_name("=pin").into_function().map_err(lua_error)?,
+        };
+        
+        if self.chunks.len() >= MAX_CACHED_CHUNKS {
+            self.chunks.clear();
+        }
+        self.chunks.insert(expr.to_string(), function.clone());
+        
+        function.call(()).map_err(lua_error)
+    }
+}
+
+thread_local! {
+    static SANDBOX: RefCell<Option<SafeLuaSandbox>> = const { RefCell::new(None) };
+}
+
+/// Evaluate a whitelisted Lua expression in this thread's cached sandbox
+pub fn eval_expression(expr: &str) -> Result<mlua::Value> {
+    SANDBOX.with(|cell| {
+        let mut sandbox = cell.borrow_mut();
+        if sandbox.is_none() {
+            *sandbox = Some(SafeLuaSandbox::new()?);
+        }
+        let sandbox = sandbox.as_mut().expect("sandbox initialized above");
+        sandbox.eval(expr)
+            .map_err(|e| anyhow::anyhow!("Safe Lua execution failed: {}", e))
+    })
+}
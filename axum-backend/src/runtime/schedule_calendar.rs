@@ -0,0 +1,174 @@
+//! Calendar constraints of scheduled triggers
+//!
+//! Optional params of CronTrigger and PollingTrigger nodes, evaluated in the
+//! trigger's timezone every time it fires. A run outside them is skipped, so
+//! "business days only" needs no cron-expression gymnastics:
+//!
+//! { "only_business_days": true, "business_hours": "08:00-18:00", "holiday_calendar": "berlin",
+//!   "blackout_windows": ["23:30-00:30", { "start": "2026-12-24", "end": "2027-01-02" }] }
+//!
+//! - only_business_days: Monday to Friday
+//! - business_hours: time-of-day window, start inclusive, end exclusive
+//! - holiday_calendar: name of a project holiday calendar (project/calendars.rs);
+//!   a calendar that doesn't exist is ignored with a warning
+//! - blackout_windows: daily "HH:MM-HH:MM" ranges (may cross midnight) or
+//!   periods with a local "start" and "end" ("2026-12-24" or "2026-12-24T18:00:00")
+
+use crate::project::calendars::{is_calendar_name, HolidayCalendar};
+use anyhow::Result;
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Utc, Weekday};
+use chrono_tz::Tz;
+use serde_json::Value;
+
+/// Time-of-day range ("08:00-18:00"); wraps past midnight when the end is earlier
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct TimeRange {
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl TimeRange {
+    fn parse(text: &str) -> Result<Self> {
+        let invalid = || anyhow::anyhow!("Invalid time range '{}' (expected \"HH:MM-HH:MM\")", text);
+        let (start, end) = text.split_once('-').ok_or_else(invalid)?;
+        let time = |part: &str| NaiveTime::parse_from_str(part.trim(), "%H:%M:%S")
+            .or_else(|_| NaiveTime::parse_from_str(part.trim(), "%H:%M"))
+            .map_err(|_| invalid());
+        let range = Self { start: time(start)?, end: time(end)? };
+        if range.start == range.end {
+            return Err(anyhow::anyhow!("Time range '{}' is empty", text));
+        }
+        Ok(range)
+    }
+
+    fn contains(&self, time: NaiveTime) -> bool {
+        if self.start < self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+impl std::fmt::Display for TimeRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.start.format("%H:%M"), self.end.format("%H:%M"))
+    }
+}
+
+/// Blackout window
+#[derive(Debug, Clone, PartialEq)]
+enum Blackout {
+    /// Every day
+    Daily(TimeRange),
+    /// Local period, end exclusive
+    Period(NaiveDateTime, NaiveDateTime),
+}
+
+impl Blackout {
+    fn parse(value: &Value) -> Result<Self> {
+        match value {
+            Value::String(range) => Ok(Blackout::Daily(TimeRange::parse(range)?)),
+            Value::Object(period) => {
+                let bound = |key: &str| -> Result<NaiveDateTime> {
+                    let text = period.get(key)
+                        .and_then(|v| v.as_str())
+                        .ok_or_else(|| anyhow::anyhow!("blackout window needs a '{}'", key))?;
+                    parse_local_datetime(text)
+                        .ok_or_else(|| anyhow::anyhow!("Invalid blackout {} '{}' (expected \"YYYY-MM-DD\" or \"YYYY-MM-DDTHH:MM:SS\")", key, text))
+                };
+                let (start, end) = (bound("start")?, bound("end")?);
+                if start >= end {
+                    return Err(anyhow::anyhow!("blackout window ends before it starts"));
+                }
+                Ok(Blackout::Period(start, end))
+            }
+            other => Err(anyhow::anyhow!("blackout window must be \"HH:MM-HH:MM\" or {{ \"start\", \"end\" }}, got {}", other)),
+        }
+    }
+
+    fn contains(&self, local: NaiveDateTime) -> bool {
+        match self {
+            Blackout::Daily(range) => range.contains(local.time()),
+            Blackout::Period(start, end) => *start <= local && local < *end,
+        }
+    }
+}
+
+impl std::fmt::Display for Blackout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Blackout::Daily(range) => write!(f, "{}", range),
+            Blackout::Period(start, end) => write!(f, "{} - {}", start, end),
+        }
+    }
+}
+
+/// Local date ("2026-12-24" = midnight) or date-time
+fn parse_local_datetime(text: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M"))
+        .ok()
+        .or_else(|| NaiveDate::parse_from_str(text, "%Y-%m-%d").ok().and_then(|date| date.and_hms_opt(0, 0, 0)))
+}
+
+/// Calendar constraints of one scheduled trigger
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScheduleConstraints {
+    only_business_days: bool,
+    business_hours: Option<TimeRange>,
+    /// Project holiday calendar skipped dates come from
+    pub holiday_calendar: Option<String>,
+    blackouts: Vec<Blackout>,
+}
+
+impl ScheduleConstraints {
+    /// Parse the constraint params of a CronTrigger / PollingTrigger node
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        let only_business_days = match params.get("only_business_days") {
+            None | Some(Value::Null) => false,
+            Some(flag) => flag.as_bool()
+                .ok_or_else(|| anyhow::anyhow!("'only_business_days' must be a boolean, got {}", flag))?,
+        };
+        let business_hours = match params.get("business_hours") {
+            None | Some(Value::Null) => None,
+            Some(Value::String(range)) => Some(TimeRange::parse(range)?),
+            Some(other) => return Err(anyhow::anyhow!("'business_hours' must be \"HH:MM-HH:MM\", got {}", other)),
+        };
+        let holiday_calendar = match params.get("holiday_calendar") {
+            None | Some(Value::Null) => None,
+            Some(Value::String(name)) if is_calendar_name(name) => Some(name.clone()),
+            Some(other) => return Err(anyhow::anyhow!("Invalid 'holiday_calendar' {}", other)),
+        };
+        let blackouts = match params.get("blackout_windows") {
+            None | Some(Value::Null) => Vec::new(),
+            Some(Value::Array(windows)) => windows.iter().map(Blackout::parse).collect::<Result<_>>()?,
+            Some(other) => return Err(anyhow::anyhow!("'blackout_windows' must be an array, got {}", other)),
+        };
+        Ok(Self { only_business_days, business_hours, holiday_calendar, blackouts })
+    }
+
+    /// No constraint set (every tick runs)
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Why a run at `time` is not allowed in `timezone`, or None when it may run
+    pub fn blocked(&self, time: DateTime<Utc>, timezone: Tz, holidays: Option<&HolidayCalendar>) -> Option<String> {
+        let local = time.with_timezone(&timezone).naive_local();
+        if self.only_business_days && matches!(local.weekday(), Weekday::Sat | Weekday::Sun) {
+            return Some(format!("not a business day ({})", local.weekday()));
+        }
+        if let (Some(name), Some(holidays)) = (&self.holiday_calendar, holidays) {
+            if holidays.contains(local.date()) {
+                return Some(format!("{} is a holiday in calendar '{}'", local.date(), name));
+            }
+        }
+        if let Some(hours) = self.business_hours.filter(|hours| !hours.contains(local.time())) {
+            return Some(format!("outside business hours {}", hours));
+        }
+        self.blackouts.iter()
+            .find(|blackout| blackout.contains(local))
+            .map(|blackout| format!("inside blackout window {}", blackout))
+    }
+}
@@ -0,0 +1,174 @@
+//! Dispatch of scheduled runs: spread, jitter and dispatch latency
+//!
+//! Workflows sharing a schedule ("0 * * * * *") would all start in the same
+//! second. Before a CronTrigger or PollingTrigger job runs, it waits
+//! - a stable offset in [0, spread) derived from its job ID (server-wide
+//!   MECHAWAY_SCHEDULE_SPREAD_SECS, capped at the job's own period), so jobs on
+//!   the same schedule are smeared evenly and each keeps a regular rhythm, plus
+//! - a random jitter in [0, jitter_secs] drawn per fire (node param "jitter_secs").
+//!
+//! Dispatch latency is the time between a job's scheduled tick plus its planned
+//! delay and the moment the run is handed to the execution queue, i.e. how late the
+//! scheduler itself is. It is reported on GET /api/metrics.
+
+use chrono::{DateTime, FixedOffset, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+/// When a scheduled job fires
+#[derive(Debug, Clone)]
+pub enum ScheduleClock {
+    /// Cron schedule at the UTC offset tokio-cron-scheduler uses for the job
+    Cron(Box<cron::Schedule>, FixedOffset),
+    /// Fixed interval (PollingTrigger "interval_secs")
+    Interval(Duration),
+}
+
+impl ScheduleClock {
+    /// Cron schedule in a timezone, at the zone's current UTC offset (as the scheduler registers it)
+    pub fn cron(schedule: cron::Schedule, timezone: Tz) -> Self {
+        let offset = timezone.offset_from_utc_datetime(&Utc::now().naive_utc()).fix();
+        ScheduleClock::Cron(Box::new(schedule), offset)
+    }
+
+    /// First tick after a point in time
+    fn next_after(&self, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            ScheduleClock::Cron(schedule, offset) => schedule
+                .after(&time.with_timezone(offset))
+                .next()
+                .map(|tick| tick.with_timezone(&Utc)),
+            ScheduleClock::Interval(interval) => chrono::Duration::from_std(*interval).ok().map(|interval| time + interval),
+        }
+    }
+
+    /// Time between two consecutive ticks (from now)
+    fn period(&self) -> Option<Duration> {
+        let first = self.next_after(Utc::now())?;
+        let second = self.next_after(first)?;
+        (second - first).to_std().ok()
+    }
+}
+
+/// Timing state of one registered job
+#[derive(Debug)]
+pub struct JobTiming {
+    /// Fixed offset from the spread window
+    offset: Duration,
+    /// Upper bound of the random per-fire jitter
+    jitter: Duration,
+    clock: ScheduleClock,
+    /// Tick the next activation belongs to
+    expected: Mutex<Option<DateTime<Utc>>>,
+}
+
+/// Dispatch counters for the metrics endpoint
+#[derive(Debug, Clone, Serialize)]
+pub struct DispatchStats {
+    pub spread_secs: u64,
+    pub dispatched_total: u64,
+    /// Mean planned delay (spread offset + jitter)
+    pub avg_delay_ms: u64,
+    /// Mean / worst / last dispatch latency beyond the planned delay
+    pub avg_latency_ms: u64,
+    pub max_latency_ms: u64,
+    pub last_latency_ms: u64,
+}
+
+/// Spreads and jitters scheduled runs and measures how late they start
+#[derive(Debug)]
+pub struct ScheduleDispatcher {
+    spread: Duration,
+    dispatched_total: AtomicU64,
+    delay_ms_total: AtomicU64,
+    latency_ms_total: AtomicU64,
+    latency_ms_max: AtomicU64,
+    latency_ms_last: AtomicU64,
+}
+
+impl ScheduleDispatcher {
+    /// Create a dispatcher smearing jobs over `spread_secs` (0 disables the spread)
+    pub fn new(spread_secs: u64) -> Self {
+        Self {
+            spread: Duration::from_secs(spread_secs),
+            dispatched_total: AtomicU64::new(0),
+            delay_ms_total: AtomicU64::new(0),
+            latency_ms_total: AtomicU64::new(0),
+            latency_ms_max: AtomicU64::new(0),
+            latency_ms_last: AtomicU64::new(0),
+        }
+    }
+
+    /// Timing of a job being registered
+    pub fn timing(&self, job_id: &str, clock: ScheduleClock, jitter_secs: u64) -> JobTiming {
+        let window = clock.period().map_or(self.spread, |period| period.min(self.spread));
+        let offset = match window.as_millis() as u64 {
+            0 => Duration::ZERO,
+            window_ms => Duration::from_millis(job_hash(job_id) % window_ms),
+        };
+        JobTiming {
+            offset,
+            jitter: Duration::from_secs(jitter_secs),
+            expected: Mutex::new(clock.next_after(Utc::now())),
+            clock,
+        }
+    }
+
+    /// Wait out a fired job's spread offset and jitter, recording its dispatch latency
+    pub async fn dispatch(&self, timing: &JobTiming) {
+        let fired_at = Utc::now();
+        let scheduled = {
+            let mut expected = timing.expected.lock().unwrap();
+            let tick = expected.filter(|tick| *tick <= fired_at).unwrap_or(fired_at);
+            *expected = timing.clock.next_after(fired_at);
+            tick
+        };
+
+        let jitter = match timing.jitter.as_millis() as u64 {
+            0 => Duration::ZERO,
+            jitter_ms => Duration::from_millis((uuid::Uuid::new_v4().as_u128() % u128::from(jitter_ms + 1)) as u64),
+        };
+        let delay = timing.offset + jitter;
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+
+        let planned = scheduled + chrono::Duration::from_std(delay).unwrap_or_default();
+        let latency_ms = (Utc::now() - planned).num_milliseconds().max(0) as u64;
+        self.dispatched_total.fetch_add(1, Ordering::Relaxed);
+        self.delay_ms_total.fetch_add(delay.as_millis() as u64, Ordering::Relaxed);
+        self.latency_ms_total.fetch_add(latency_ms, Ordering::Relaxed);
+        self.latency_ms_max.fetch_max(latency_ms, Ordering::Relaxed);
+        self.latency_ms_last.store(latency_ms, Ordering::Relaxed);
+    }
+
+    /// Current dispatch statistics
+    pub fn stats(&self) -> DispatchStats {
+        let dispatched = self.dispatched_total.load(Ordering::Relaxed);
+        let mean = |total: &AtomicU64| total.load(Ordering::Relaxed).checked_div(dispatched).unwrap_or(0);
+        DispatchStats {
+            spread_secs: self.spread.as_secs(),
+            dispatched_total: dispatched,
+            avg_delay_ms: mean(&self.delay_ms_total),
+            avg_latency_ms: mean(&self.latency_ms_total),
+            max_latency_ms: self.latency_ms_max.load(Ordering::Relaxed),
+            last_latency_ms: self.latency_ms_last.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Stable 64-bit hash of a job ID ("workflow:node")
+fn job_hash(job_id: &str) -> u64 {
+    let digest = Sha256::digest(job_id.as_bytes());
+    let mut prefix = [0u8; 8];
+    prefix.copy_from_slice(&digest[..8]);
+    u64::from_be_bytes(prefix)
+}
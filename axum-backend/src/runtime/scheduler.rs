@@ -1,18 +1,30 @@
-/// Background cron scheduler service
-/// 
-/// Manages scheduled workflows using tokio-cron-scheduler. Automatically
-/// registers CronTrigger nodes from workflows and executes them at scheduled times.
+//! Background cron scheduler service
+//! 
+//! Manages scheduled workflows using tokio-cron-scheduler. Automatically
+//! registers CronTrigger and PollingTrigger nodes from workflows and executes
+//! them at scheduled times. Job callbacks only hand the run off to a task that
+//! enqueues it on the execution queue, so a tick is dispatched without waiting
+//! for any workflow and slow jobs can't delay others.
 
 use crate::{
-    runtime::{engine::ExecutionEngine, executor::NodeExecutor},
+    runtime::{
+        engine::ExecutionEngine,
+        executor::NodeExecutor,
+        polling::{self, PollSchedule, PollingParams},
+        queue::ExecutionQueue,
+        schedule_calendar::ScheduleConstraints,
+        schedule_dispatch::{DispatchStats, ScheduleClock, ScheduleDispatcher},
+    },
     workflow::{
+        params::{parse_cron, CronTriggerParams},
         types::{ExecutionContext, Node, NodeType, Workflow},
-        registry::WorkflowRegistry,
+        registry::{CompiledWorkflow, WorkflowRegistry},
     },
 };
 use anyhow::Result;
-use std::{collections::HashMap, sync::Arc};
-use tokio::sync::{oneshot, RwLock};
+use chrono_tz::Tz;
+use std::{collections::HashMap, future::Future, pin::Pin, sync::Arc};
+use tokio::sync::{Mutex, RwLock};
 use tokio_cron_scheduler::{Job, JobScheduler};
 use uuid::Uuid;
 
@@ -26,6 +38,10 @@ pub struct CronSchedulerService {
     registry: Arc<WorkflowRegistry>,
     executor: Arc<NodeExecutor>,
     engine: Arc<ExecutionEngine>,
+    /// Runs are admitted through the execution queue like every other trigger
+    queue: Arc<ExecutionQueue>,
+    /// Spread / jitter of fired jobs and dispatch latency
+    dispatcher: Arc<ScheduleDispatcher>,
 }
 
 impl CronSchedulerService {
@@ -34,6 +50,7 @@ impl CronSchedulerService {
         registry: Arc<WorkflowRegistry>,
         executor: Arc<NodeExecutor>,
         engine: Arc<ExecutionEngine>,
+        queue: Arc<ExecutionQueue>,
     ) -> Result<Self> {
         let scheduler = JobScheduler::new().await?;
         
@@ -43,9 +60,22 @@ impl CronSchedulerService {
             registry,
             executor,
             engine,
+            queue,
+            dispatcher: Arc::new(ScheduleDispatcher::new(0)),
         })
     }
 
+    /// Smear fired jobs over a window of `spread_secs` (MECHAWAY_SCHEDULE_SPREAD_SECS)
+    pub fn with_spread(mut self, spread_secs: u64) -> Self {
+        self.dispatcher = Arc::new(ScheduleDispatcher::new(spread_secs));
+        self
+    }
+
+    /// Dispatch latency and delay statistics
+    pub fn dispatch_stats(&self) -> DispatchStats {
+        self.dispatcher.stats()
+    }
+
     /// Start the hot-reload scheduler and register all cron triggers from workflows
     pub async fn start(&self) -> Result<()> {
         tracing::info!("⏰ Starting hot-reload cron scheduler service");
@@ -98,7 +128,7 @@ impl CronSchedulerService {
         tracing::info!("🔥 Hot-reloading cron triggers for workflow: {}", workflow.id);
         
         let cron_nodes: Vec<&Node> = workflow.nodes.iter()
-            .filter(|node| matches!(node.node_type, NodeType::CronTrigger))
+            .filter(|node| matches!(node.node_type, NodeType::CronTrigger | NodeType::PollingTrigger))
             .collect();
 
         if cron_nodes.is_empty() {
@@ -118,6 +148,11 @@ impl CronSchedulerService {
         Ok(())
     }
 
+    /// Number of registered cron jobs
+    pub async fn job_count(&self) -> usize {
+        self.job_uuid_map.read().await.len()
+    }
+
     /// HOT-RELOAD: Remove all cron triggers for a workflow
     pub async fn remove_workflow_cron_triggers(&self, workflow_id: &str) {
         tracing::info!("🗑️ Removing all cron triggers for workflow: {}", workflow_id);
@@ -146,83 +181,203 @@ impl CronSchedulerService {
 
     /// HOT-RELOAD: Core add/update job function (scalable industrial pattern)
     async fn add_or_update_cron_job(&self, workflow_id: &str, cron_node: &Node) -> Result<()> {
-        let schedule = cron_node.params.get("schedule")
-            .and_then(|s| s.as_str())
-            .ok_or_else(|| anyhow::anyhow!("CronTrigger missing 'schedule' parameter"))?;
+        if matches!(cron_node.node_type, NodeType::PollingTrigger) {
+            return self.add_or_update_polling_job(workflow_id, cron_node).await;
+        }
+
+        let params = CronTriggerParams::from_node_params(&cron_node.params)
+            .map_err(|e| anyhow::anyhow!("CronTrigger '{}': {}", cron_node.id, e))?;
+        let timezone = params.tz()?;
+        let schedule = params.schedule;
 
         let job_id = format!("{}:{}", workflow_id, cron_node.id);
         
-        tracing::info!("🔥 Hot-reloading cron job: {} - schedule: {}", job_id, schedule);
+        tracing::info!("🔥 Hot-reloading cron job: {} - schedule: {} ({})", job_id, schedule, timezone);
 
         // STEP 1: CRITICAL FIX - Remove existing job from tokio-cron-scheduler
-        {
-            let mut job_uuid_map = self.job_uuid_map.write().await;
-            if let Some(old_job_uuid) = job_uuid_map.remove(&job_id) {
-                // Actually remove the old job from the scheduler
-                let scheduler = self.scheduler.read().await;
-                if let Err(e) = scheduler.remove(&old_job_uuid).await {
-                    tracing::warn!("⚠️ Failed to remove old job {} from scheduler: {}", job_id, e);
-                } else {
-                    tracing::debug!("🛑 Removed old cron job from scheduler: {}", job_id);
-                }
-            }
-        }
+        self.remove_job(&job_id).await;
 
         // STEP 2: Clone data for the job closure
         let workflow_id_owned = workflow_id.to_string();
         let cron_node_id = cron_node.id.clone();
         let registry = Arc::clone(&self.registry);
+        let executor = Arc::clone(&self.executor);
         let engine = Arc::clone(&self.engine);
+        let queue = Arc::clone(&self.queue);
+        let dispatcher = Arc::clone(&self.dispatcher);
+        let timing = Arc::new(dispatcher.timing(&job_id, ScheduleClock::cron(parse_cron(&schedule)?, timezone), params.jitter_secs));
+        let constraints = Arc::new(params.constraints);
 
         // STEP 3: Create the hot-reloadable job (simplified without oneshot for now)
-        let job = Job::new_async(schedule, move |_uuid, _l| {
+        // tokio-cron-scheduler keeps the UTC offset the timezone has at registration:
+        // across a DST change the job stays on the old offset until the workflow reloads
+        let job = Job::new_async_tz(schedule.as_str(), timezone, move |_uuid, _l| {
             let workflow_id = workflow_id_owned.clone();
             let cron_node_id = cron_node_id.clone();
             let registry = Arc::clone(&registry);
+            let executor = Arc::clone(&executor);
             let engine = Arc::clone(&engine);
+            let queue = Arc::clone(&queue);
+            let dispatcher = Arc::clone(&dispatcher);
+            let timing = Arc::clone(&timing);
+            let constraints = Arc::clone(&constraints);
 
+            // The run continues in its own task (spread, jitter and the queue wait
+            // included), so the callback returns at once and a slow workflow never
+            // holds up the scheduler's tick loop or other jobs
             Box::pin(async move {
-                tracing::debug!("🔔 Cron trigger activated: {} in workflow {}", cron_node_id, workflow_id);
-                
-                // Check if workflow still exists (scalable pattern)
-                if let Some(workflow) = registry.get_workflow(&workflow_id) {
-                    tracing::info!("🚀 Executing cron workflow: {}", workflow_id);
-                    
-                    // Create execution context from cron trigger
-                    let context = ExecutionContext::from_cron_trigger(workflow_id.clone(), cron_node_id.clone(), "default".to_string());
-                    
-                    // Execute the workflow starting from the cron trigger
-                    match engine.execute_workflow(&workflow, &cron_node_id, context).await {
-                        Ok(result) => {
-                            tracing::info!("✅ Cron-triggered workflow completed: {} (continue: {})", 
-                                workflow_id, result.should_continue);
+                tokio::spawn(async move {
+                    tracing::debug!("🔔 Cron trigger activated: {} in workflow {}", cron_node_id, workflow_id);
+                    dispatcher.dispatch(&timing).await;
+
+                    // Check if workflow still exists (scalable pattern)
+                    if let Some(workflow) = registry.get_workflow(&workflow_id) {
+                        if outside_schedule_constraints(&executor, &workflow, &cron_node_id, &constraints, timezone).await
+                            || skip_scheduled_run(&registry, &engine, &workflow).await
+                        {
+                            return;
                         }
-                        Err(e) => {
-                            tracing::error!("❌ Cron-triggered workflow failed: {} - Error: {}", 
-                                workflow_id, e);
+                        tracing::info!("🚀 Executing cron workflow: {}", workflow_id);
+
+                        // Create execution context from cron trigger
+                        let context = ExecutionContext::from_cron_trigger(workflow_id.clone(), cron_node_id.clone(), workflow.workflow.project.clone());
+
+                        // Execute the workflow starting from the cron trigger (through the execution queue)
+                        match queue.execute(&workflow, &cron_node_id, context).await {
+                            Ok(Ok(result)) => {
+                                tracing::info!("✅ Cron-triggered workflow completed: {} ({})", 
+                                    workflow_id, result.termination.status());
+                            }
+                            Ok(Err(e)) => {
+                                tracing::error!("❌ Cron-triggered workflow failed: {} - Error: {}", 
+                                    workflow_id, e);
+                            }
+                            Err(shed) => {
+                                tracing::warn!("⚠️ Cron-triggered run of workflow {} not admitted: {}", workflow_id, shed);
+                            }
                         }
+                    } else {
+                        // Workflow was deleted - job gracefully skips execution
+                        tracing::debug!("⏭️ Skipping cron trigger for deleted workflow: {}", workflow_id);
                     }
-                } else {
-                    // Workflow was deleted - job gracefully skips execution
-                    tracing::debug!("⏭️ Skipping cron trigger for deleted workflow: {}", workflow_id);
-                }
+                });
             })
         })?;
 
-        // STEP 4: Add job to scheduler and get UUID
+        // STEP 4: Add job to scheduler and track its UUID for proper removal
+        self.add_job(&job_id, job).await?;
+
+        tracing::info!("🔥 Hot-reloaded cron job: {} ({})", job_id, schedule);
+        Ok(())
+    }
+
+    /// HOT-RELOAD: Add/update a PollingTrigger job (fetch, run with new items, advance cursor)
+    async fn add_or_update_polling_job(&self, workflow_id: &str, polling_node: &Node) -> Result<()> {
+        let params = PollingParams::from_node_params(&polling_node.params)
+            .map_err(|e| anyhow::anyhow!("PollingTrigger '{}': {}", polling_node.id, e))?;
+        let job_id = format!("{}:{}", workflow_id, polling_node.id);
+        let schedule = match &params.schedule {
+            PollSchedule::Cron(schedule) => schedule.clone(),
+            PollSchedule::Interval(interval) => format!("every {}s", interval.as_secs()),
+        };
+
+        tracing::info!("🔥 Hot-reloading polling job: {} - {}", job_id, schedule);
+
+        self.remove_job(&job_id).await;
+
+        let workflow_id_owned = workflow_id.to_string();
+        let polling_node_id = polling_node.id.clone();
+        let registry = Arc::clone(&self.registry);
+        let executor = Arc::clone(&self.executor);
+        let engine = Arc::clone(&self.engine);
+        let queue = Arc::clone(&self.queue);
+        let dispatcher = Arc::clone(&self.dispatcher);
+        let timezone = params.timezone;
+        let constraints = Arc::new(params.constraints);
+        let clock = match &params.schedule {
+            PollSchedule::Cron(schedule) => ScheduleClock::cron(parse_cron(schedule)?, timezone),
+            PollSchedule::Interval(interval) => ScheduleClock::Interval(*interval),
+        };
+        let timing = Arc::new(dispatcher.timing(&job_id, clock, params.jitter_secs));
+        // A poll still running when the next tick fires makes that tick a no-op (no double ingestion)
+        let running = Arc::new(Mutex::new(()));
+
+        let run = move |_uuid: Uuid, _l: JobScheduler| {
+            let workflow_id = workflow_id_owned.clone();
+            let polling_node_id = polling_node_id.clone();
+            let registry = Arc::clone(&registry);
+            let executor = Arc::clone(&executor);
+            let engine = Arc::clone(&engine);
+            let queue = Arc::clone(&queue);
+            let running = Arc::clone(&running);
+            let dispatcher = Arc::clone(&dispatcher);
+            let timing = Arc::clone(&timing);
+            let constraints = Arc::clone(&constraints);
+
+            // Handed off like cron runs: the callback returns at once
+            Box::pin(async move {
+                tokio::spawn(async move {
+                    let Ok(_guard) = running.try_lock_owned() else {
+                        tracing::debug!("⏭️ Previous poll still running, skipping tick: {} in workflow {}", polling_node_id, workflow_id);
+                        return;
+                    };
+                    dispatcher.dispatch(&timing).await;
+                    let Some(workflow) = registry.get_workflow(&workflow_id) else {
+                        tracing::debug!("⏭️ Skipping polling trigger for deleted workflow: {}", workflow_id);
+                        return;
+                    };
+                    let Some(node) = workflow.workflow.nodes.iter().find(|node| node.id == polling_node_id) else {
+                        tracing::debug!("⏭️ Skipping removed polling trigger: {} in workflow {}", polling_node_id, workflow_id);
+                        return;
+                    };
+                    if outside_schedule_constraints(&executor, &workflow, &polling_node_id, &constraints, timezone).await
+                        || skip_scheduled_run(&registry, &engine, &workflow).await
+                    {
+                        return;
+                    }
+
+                    tracing::debug!("🔔 Polling trigger activated: {} in workflow {}", polling_node_id, workflow_id);
+                    if let Err(e) = polling::poll(&queue, &executor, &workflow, node).await {
+                        tracing::error!("❌ Polling-triggered workflow failed: {} - Error: {}", workflow_id, e);
+                    }
+                });
+            }) as Pin<Box<dyn Future<Output = ()> + Send>>
+        };
+        let job = match params.schedule {
+            PollSchedule::Cron(schedule) => Job::new_async_tz(schedule.as_str(), timezone, run)?,
+            PollSchedule::Interval(interval) => Job::new_repeated_async(interval, run)?,
+        };
+
+        self.add_job(&job_id, job).await?;
+
+        tracing::info!("🔥 Hot-reloaded polling job: {} ({})", job_id, schedule);
+        Ok(())
+    }
+
+    /// Remove a tracked job from tokio-cron-scheduler (no-op if it isn't registered)
+    async fn remove_job(&self, job_id: &str) {
+        let mut job_uuid_map = self.job_uuid_map.write().await;
+        if let Some(old_job_uuid) = job_uuid_map.remove(job_id) {
+            // Actually remove the old job from the scheduler
+            let scheduler = self.scheduler.read().await;
+            if let Err(e) = scheduler.remove(&old_job_uuid).await {
+                tracing::warn!("⚠️ Failed to remove old job {} from scheduler: {}", job_id, e);
+            } else {
+                tracing::debug!("🛑 Removed old cron job from scheduler: {}", job_id);
+            }
+        }
+    }
+
+    /// Add a job to the scheduler and track its UUID for proper removal
+    async fn add_job(&self, job_id: &str, job: Job) -> Result<()> {
         let new_job_uuid = {
             let scheduler = self.scheduler.write().await;
             scheduler.add(job).await?
         };
 
-        // STEP 5: CRITICAL FIX - Track job UUID for proper removal
-        {
-            let mut job_uuid_map = self.job_uuid_map.write().await;
-            job_uuid_map.insert(job_id.clone(), new_job_uuid);
-            tracing::debug!("📝 Tracked job UUID for: {}", job_id);
-        }
-
-        tracing::info!("🔥 Hot-reloaded cron job: {} ({})", job_id, schedule);
+        let mut job_uuid_map = self.job_uuid_map.write().await;
+        job_uuid_map.insert(job_id.to_string(), new_job_uuid);
+        tracing::debug!("📝 Tracked job UUID for: {}", job_id);
         Ok(())
     }
 
@@ -234,7 +389,7 @@ impl CronSchedulerService {
         
         for workflow in workflows {
             let trigger_count = workflow.nodes.iter()
-                .filter(|node| matches!(node.node_type, NodeType::CronTrigger))
+                .filter(|node| matches!(node.node_type, NodeType::CronTrigger | NodeType::PollingTrigger))
                 .count();
             
             if trigger_count > 0 {
@@ -247,69 +402,66 @@ impl CronSchedulerService {
             total_triggers, workflow_count);
         Ok(())
     }
+}
 
-    /// Register a single cron trigger node
-    async fn register_cron_trigger(&self, workflow_id: &str, cron_node: &Node) -> Result<()> {
-        let schedule = cron_node.params.get("schedule")
-            .and_then(|s| s.as_str())
-            .ok_or_else(|| anyhow::anyhow!("CronTrigger missing 'schedule' parameter"))?;
-
-        let timezone = cron_node.params.get("timezone")
-            .and_then(|tz| tz.as_str())
-            .unwrap_or("UTC");
-
-        tracing::debug!("⏰ Registering cron job: {} - schedule: {} ({})", 
-            cron_node.id, schedule, timezone);
-
-        // Clone data for the job closure
-        let workflow_id_owned = workflow_id.to_string();
-        let cron_node_id = cron_node.id.clone();
-        let registry = Arc::clone(&self.registry);
-        let engine = Arc::clone(&self.engine);
-
-        // Create the cron job with lifecycle management (scales to hundreds of workflows!)
-        let job = Job::new_async(schedule, move |_uuid, _l| {
-            let workflow_id = workflow_id_owned.clone();
-            let cron_node_id = cron_node_id.clone();
-            let registry = Arc::clone(&registry);
-            let engine = Arc::clone(&engine);
-
-            Box::pin(async move {
-                tracing::debug!("🔔 Cron trigger activated: {} in workflow {}", cron_node_id, workflow_id);
-                
-                // ✅ SCALABLE: Check if workflow still exists (zero downtime for other workflows)
-                if let Some(workflow) = registry.get_workflow(&workflow_id) {
-                    tracing::info!("🚀 Executing cron workflow: {}", workflow_id);
-                    
-                    // Create execution context from cron trigger
-                    let context = ExecutionContext::from_cron_trigger(workflow_id.clone(), cron_node_id.clone(), "default".to_string());
-                    
-                    // Execute the workflow starting from the cron trigger
-                    match engine.execute_workflow(&workflow, &cron_node_id, context).await {
-                        Ok(result) => {
-                            tracing::info!("✅ Cron-triggered workflow completed: {} (continue: {})", 
-                                workflow_id, result.should_continue);
-                        }
-                        Err(e) => {
-                            tracing::error!("❌ Cron-triggered workflow failed: {} - Error: {}", 
-                                workflow_id, e);
-                        }
-                    }
-                } else {
-                    // Workflow was deleted - job gracefully skips execution (no restart needed!)
-                    tracing::debug!("⏭️ Skipping cron trigger for deleted workflow: {}", workflow_id);
-                }
-            })
-        })?;
-
-        // Add job to scheduler with write lock
-        {
-            let scheduler = self.scheduler.write().await;
-            scheduler.add(job).await?;
+/// Whether a scheduled run falls outside its trigger's calendar constraints (business
+/// days, holidays, business hours, blackout windows) in the trigger's timezone
+async fn outside_schedule_constraints(
+    executor: &NodeExecutor,
+    workflow: &CompiledWorkflow,
+    node_id: &str,
+    constraints: &ScheduleConstraints,
+    timezone: Tz,
+) -> bool {
+    if constraints.is_empty() {
+        return false;
+    }
+    let workflow_id = &workflow.workflow.id;
+    let project = &workflow.workflow.project;
+    let holidays = match &constraints.holiday_calendar {
+        Some(name) => match executor.project_db_manager().calendar_get(project, name).await {
+            Ok(Some(calendar)) => Some(calendar),
+            Ok(None) => {
+                tracing::warn!("⚠️ Holiday calendar '{}' of project {} not found, ignored by {} in workflow {}", name, project, node_id, workflow_id);
+                None
+            }
+            Err(e) => {
+                tracing::error!("❌ Failed to load holiday calendar '{}' of project {}: {}", name, project, e);
+                None
+            }
+        },
+        None => None,
+    };
+    match constraints.blocked(chrono::Utc::now(), timezone, holidays.as_ref()) {
+        Some(reason) => {
+            tracing::info!("📅 Skipping scheduled run of {} in workflow {}: {}", node_id, workflow_id, reason);
+            true
         }
+        None => false,
+    }
+}
 
-        tracing::debug!("✅ Cron job registered: {} ({}) for workflow: {}", 
-            cron_node.id, schedule, workflow_id);
-        Ok(())
+/// Whether a scheduled (cron or polling) run is dropped: the server is in maintenance
+/// mode, the workflow is snoozed, or it has a pending one-time skip (consumed here)
+async fn skip_scheduled_run(registry: &WorkflowRegistry, engine: &ExecutionEngine, workflow: &CompiledWorkflow) -> bool {
+    let workflow_id = &workflow.workflow.id;
+    if engine.maintenance_mode().is_enabled() {
+        tracing::info!("🚧 Skipping scheduled run of workflow {}: maintenance mode", workflow_id);
+        return true;
+    }
+    if workflow.active_snooze().is_some() {
+        tracing::info!("💤 Skipping scheduled run of snoozed workflow: {}", workflow_id);
+        return true;
+    }
+    match registry.take_skip_next(workflow_id).await {
+        Ok(true) => {
+            tracing::info!("⏭️ Skipping scheduled run of workflow {} (one-time skip)", workflow_id);
+            true
+        }
+        Ok(false) => false,
+        Err(e) => {
+            tracing::error!("❌ Failed to check one-time skip of workflow {}: {}", workflow_id, e);
+            false
+        }
     }
 }
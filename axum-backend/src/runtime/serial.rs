@@ -0,0 +1,515 @@
+//! Serial port access for the Serial node and SerialTrigger (cargo feature "serial")
+//! 
+//! Edge boxes running next to PLCs, scales, barcode readers and sensor boards
+//! talk to them over RS-232/RS-485 or USB serial adapters:
+//! 
+//! { "port": "/dev/ttyUSB0", "baud_rate": 9600, "data_bits": 8, "parity": "none",
+//!   "stop_bits": 1, "flow_control": "none", "framing": "line" }
+//! 
+//! Received bytes are cut into frames: "line" (at "delimiter", default "\n",
+//! where a trailing "\r" is dropped), "fixed" ("frame_length" bytes) or "idle"
+//! (a frame ends after "idle_ms" of silence, for binary protocols). Frames are
+//! decoded as "text" (JSON objects become the item), "hex" or "base64".
+//! 
+//! While a SerialTrigger holds a port open, Serial nodes writing to the same
+//! port send through its connection (see SerialPorts), so commands sent by one
+//! workflow can be answered through the trigger's workflow.
+//! 
+//! Without the feature, parameters are still validated but opening a port
+//! fails with a message naming the feature.
+
+use crate::runtime::stream_aggregate::message_item;
+use anyhow::Result;
+use base64::Engine;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::sync::mpsc;
+
+/// Error for builds without the "serial" feature
+const UNSUPPORTED: &str = "serial port support is not compiled in (build with --features serial)";
+
+/// Longest frame kept before it is cut (e.g. line framing without a delimiter)
+const DEFAULT_MAX_FRAME: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parity {
+    None,
+    Odd,
+    Even,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlowControl {
+    None,
+    /// XON/XOFF
+    Software,
+    /// RTS/CTS
+    Hardware,
+}
+
+/// Port settings shared by the Serial node and SerialTrigger
+#[derive(Debug, Clone)]
+pub struct SerialSettings {
+    /// Device path ("/dev/ttyUSB0", "COM3")
+    pub port: String,
+    pub baud_rate: u32,
+    /// 5 to 8
+    pub data_bits: u8,
+    pub parity: Parity,
+    /// 1 or 2
+    pub stop_bits: u8,
+    pub flow_control: FlowControl,
+}
+
+impl SerialSettings {
+    /// Parse node params
+    /// 
+    /// Expected params: { "port": "/dev/ttyUSB0", "baud_rate": 9600, "data_bits": 8, "parity": "none" | "odd" | "even",
+    ///                    "stop_bits": 1 | 2, "flow_control": "none" | "software" | "hardware" }
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        let port = params.get("port")
+            .and_then(|p| p.as_str())
+            .filter(|p| !p.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("missing 'port' parameter (e.g. \"/dev/ttyUSB0\")"))?;
+        let number = |name: &str, default: u64, valid: fn(u64) -> bool| -> Result<u64> {
+            match params.get(name) {
+                None => Ok(default),
+                Some(value) => value.as_u64().filter(|n| valid(*n))
+                    .ok_or_else(|| anyhow::anyhow!("invalid '{}' {}", name, value)),
+            }
+        };
+
+        Ok(Self {
+            port: port.to_string(),
+            baud_rate: number("baud_rate", 9600, |baud| (50..=4_000_000).contains(&baud))? as u32,
+            data_bits: number("data_bits", 8, |bits| (5..=8).contains(&bits))? as u8,
+            parity: match text(params, "parity", "none") {
+                "none" => Parity::None,
+                "odd" => Parity::Odd,
+                "even" => Parity::Even,
+                other => return Err(anyhow::anyhow!("unsupported parity '{}' (expected none, odd or even)", other)),
+            },
+            stop_bits: number("stop_bits", 1, |bits| bits == 1 || bits == 2)? as u8,
+            flow_control: match text(params, "flow_control", "none") {
+                "none" => FlowControl::None,
+                "software" => FlowControl::Software,
+                "hardware" => FlowControl::Hardware,
+                other => return Err(anyhow::anyhow!("unsupported flow_control '{}' (expected none, software or hardware)", other)),
+            },
+        })
+    }
+}
+
+/// How received bytes are cut into frames
+#[derive(Debug, Clone, PartialEq)]
+pub enum Framing {
+    /// Frames end at the delimiter (not included)
+    Line(Vec<u8>),
+    /// Frames of exactly this many bytes
+    Fixed(usize),
+    /// Frames end after this much silence
+    Idle(Duration),
+}
+
+impl Framing {
+    /// Parse node params
+    /// 
+    /// Expected params: { "framing": "line", "delimiter": "\n" } | { "framing": "fixed", "frame_length": 8 }
+    ///                  | { "framing": "idle", "idle_ms": 50 }
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        match text(params, "framing", "line") {
+            "line" => {
+                let delimiter = text(params, "delimiter", "\n");
+                if delimiter.is_empty() {
+                    return Err(anyhow::anyhow!("'delimiter' must not be empty"));
+                }
+                Ok(Framing::Line(delimiter.as_bytes().to_vec()))
+            }
+            "fixed" => params.get("frame_length")
+                .and_then(|l| l.as_u64())
+                .filter(|l| (1..=65_536).contains(l))
+                .map(|length| Framing::Fixed(length as usize))
+                .ok_or_else(|| anyhow::anyhow!("fixed framing REQUIRES 'frame_length' (1 to 65536 bytes)")),
+            "idle" => {
+                let idle_ms = params.get("idle_ms").map_or(Some(50), |i| i.as_u64()).filter(|i| (1..=60_000).contains(i))
+                    .ok_or_else(|| anyhow::anyhow!("'idle_ms' must be 1 to 60000"))?;
+                Ok(Framing::Idle(Duration::from_millis(idle_ms)))
+            }
+            other => Err(anyhow::anyhow!("unsupported framing '{}' (expected line, fixed or idle)", other)),
+        }
+    }
+}
+
+/// How frames and commands map to JSON values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Text,
+    Hex,
+    Base64,
+}
+
+impl Encoding {
+    /// Expected params: { "encoding": "text" | "hex" | "base64" } (default text)
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        match text(params, "encoding", "text") {
+            "text" => Ok(Encoding::Text),
+            "hex" => Ok(Encoding::Hex),
+            "base64" => Ok(Encoding::Base64),
+            other => Err(anyhow::anyhow!("unsupported encoding '{}' (expected text, hex or base64)", other)),
+        }
+    }
+
+    /// Bytes to write for a command: strings in this encoding, arrays of byte
+    /// values as raw bytes, any other JSON as its text
+    pub fn encode(self, command: &Value) -> Result<Vec<u8>> {
+        match command {
+            Value::String(text) => match self {
+                Encoding::Text => Ok(text.as_bytes().to_vec()),
+                Encoding::Hex => hex::decode(text.chars().filter(|c| !c.is_whitespace()).collect::<String>())
+                    .map_err(|e| anyhow::anyhow!("invalid hex command: {}", e)),
+                Encoding::Base64 => base64::engine::general_purpose::STANDARD.decode(text)
+                    .map_err(|e| anyhow::anyhow!("invalid base64 command: {}", e)),
+            },
+            Value::Array(values) => values.iter()
+                .map(|value| value.as_u64().filter(|byte| *byte <= 255).map(|byte| byte as u8))
+                .collect::<Option<Vec<u8>>>()
+                .ok_or_else(|| anyhow::anyhow!("command arrays must hold byte values (0-255)")),
+            Value::Null => Err(anyhow::anyhow!("command is null")),
+            other => Ok(other.to_string().into_bytes()),
+        }
+    }
+}
+
+/// Workflow item for a received frame
+/// 
+/// Text frames go through message_item (JSON objects become the item, other
+/// text is put under "value"); hex / base64 frames are put under "value".
+/// "serial": { "port", "bytes" } is added either way.
+pub fn frame_item(frame: &[u8], encoding: Encoding, port: &str) -> Value {
+    let envelope = json!({ "port": port, "bytes": frame.len() });
+    match encoding {
+        Encoding::Text => message_item(frame, "serial", envelope),
+        Encoding::Hex => json!({ "value": hex::encode(frame), "serial": envelope }),
+        Encoding::Base64 => json!({ "value": base64::engine::general_purpose::STANDARD.encode(frame), "serial": envelope }),
+    }
+}
+
+/// Cuts a byte stream into frames
+#[derive(Debug)]
+pub struct FrameReader {
+    framing: Framing,
+    max_frame: usize,
+    buffer: Vec<u8>,
+}
+
+impl FrameReader {
+    pub fn new(framing: Framing, max_frame: usize) -> Self {
+        let max_frame = match framing {
+            Framing::Fixed(length) => max_frame.max(length),
+            _ => max_frame,
+        };
+        Self { framing, max_frame, buffer: Vec::new() }
+    }
+
+    /// Append received bytes, returning the completed frames
+    /// 
+    /// Empty lines are skipped; bytes piling up past the maximum frame size
+    /// are cut into a frame of their own.
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<Vec<u8>> {
+        self.buffer.extend_from_slice(bytes);
+        let mut frames = Vec::new();
+        match &self.framing {
+            Framing::Line(delimiter) => {
+                while let Some(position) = self.buffer.windows(delimiter.len()).position(|window| window == delimiter.as_slice()) {
+                    let mut frame: Vec<u8> = self.buffer.drain(..position + delimiter.len()).collect();
+                    frame.truncate(position);
+                    if delimiter == b"\n" && frame.last() == Some(&b'\r') {
+                        frame.pop();
+                    }
+                    if !frame.is_empty() {
+                        frames.push(frame);
+                    }
+                }
+            }
+            Framing::Fixed(length) => {
+                while self.buffer.len() >= *length {
+                    frames.push(self.buffer.drain(..*length).collect());
+                }
+            }
+            Framing::Idle(_) => {}
+        }
+        while self.buffer.len() >= self.max_frame {
+            frames.push(self.buffer.drain(..self.max_frame).collect());
+        }
+        frames
+    }
+
+    /// Silence that completes the buffered bytes as a frame (idle framing)
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        match self.framing {
+            Framing::Idle(idle) if !self.buffer.is_empty() => Some(idle),
+            _ => None,
+        }
+    }
+
+    /// Take the buffered bytes as a frame
+    pub fn flush(&mut self) -> Option<Vec<u8>> {
+        (!self.buffer.is_empty()).then(|| std::mem::take(&mut self.buffer))
+    }
+}
+
+/// Reply expected after every command of a Serial node
+#[derive(Debug, Clone)]
+pub struct SerialReply {
+    pub framing: Framing,
+    pub timeout: Duration,
+    pub max_frame: usize,
+}
+
+/// Serial node configuration
+#[derive(Debug, Clone)]
+pub struct SerialParams {
+    pub settings: SerialSettings,
+    pub encoding: Encoding,
+    /// Appended to every command
+    pub line_ending: Vec<u8>,
+    pub reply: Option<SerialReply>,
+}
+
+impl SerialParams {
+    /// Parse node params
+    /// 
+    /// Expected params: port settings (see SerialSettings), { "encoding": "text", "line_ending": "\r\n",
+    ///                    "read_response": true, "response_timeout_ms": 1000, "framing": "line", ... }
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        let encoding = Encoding::from_node_params(params)?;
+        let line_ending = match params.get("line_ending") {
+            None if encoding == Encoding::Text => b"\n".to_vec(),
+            None => Vec::new(),
+            Some(ending) => ending.as_str()
+                .ok_or_else(|| anyhow::anyhow!("'line_ending' must be a string"))?
+                .as_bytes().to_vec(),
+        };
+        let reply = match params.get("read_response").and_then(|r| r.as_bool()).unwrap_or(false) {
+            false => None,
+            true => {
+                let timeout_ms = params.get("response_timeout_ms").map_or(Some(1000), |t| t.as_u64())
+                    .filter(|t| (1..=600_000).contains(t))
+                    .ok_or_else(|| anyhow::anyhow!("'response_timeout_ms' must be 1 to 600000"))?;
+                Some(SerialReply {
+                    framing: Framing::from_node_params(params)?,
+                    timeout: Duration::from_millis(timeout_ms),
+                    max_frame: max_frame(params)?,
+                })
+            }
+        };
+
+        Ok(Self {
+            settings: SerialSettings::from_node_params(params)?,
+            encoding,
+            line_ending,
+            reply,
+        })
+    }
+}
+
+/// Expected params: { "max_frame_bytes": 4096 }
+pub fn max_frame(params: &Value) -> Result<usize> {
+    params.get("max_frame_bytes")
+        .map_or(Some(DEFAULT_MAX_FRAME as u64), |m| m.as_u64())
+        .filter(|m| (1..=1_048_576).contains(m))
+        .map(|m| m as usize)
+        .ok_or_else(|| anyhow::anyhow!("'max_frame_bytes' must be 1 to 1048576"))
+}
+
+/// String param with a default
+fn text<'a>(params: &'a Value, name: &str, default: &'a str) -> &'a str {
+    params.get(name).and_then(|v| v.as_str()).unwrap_or(default)
+}
+
+/// Ports held open by SerialTriggers, shared by the executor and the trigger service
+/// 
+/// Maps the port path to the trigger's write queue, plus one lock per port so
+/// concurrent Serial node runs take turns instead of failing on a busy device.
+#[derive(Debug, Clone, Default)]
+pub struct SerialPorts {
+    writers: Arc<Mutex<HashMap<String, mpsc::Sender<Vec<u8>>>>>,
+    locks: Arc<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>>,
+}
+
+impl SerialPorts {
+    /// Write queue of the SerialTrigger holding the port, if any
+    pub fn writer(&self, port: &str) -> Option<mpsc::Sender<Vec<u8>>> {
+        self.writers.lock().unwrap().get(port).cloned()
+    }
+
+    /// Route writes to the port through a trigger's queue until the hold is dropped
+    pub fn hold(&self, port: &str, writer: mpsc::Sender<Vec<u8>>) -> PortHold {
+        self.writers.lock().unwrap().insert(port.to_string(), writer.clone());
+        PortHold { writers: Arc::clone(&self.writers), port: port.to_string(), writer }
+    }
+
+    /// Lock serializing direct access to a port
+    pub fn lock(&self, port: &str) -> Arc<tokio::sync::Mutex<()>> {
+        Arc::clone(self.locks.lock().unwrap().entry(port.to_string()).or_default())
+    }
+}
+
+/// Registration of a trigger's write queue (removed on drop)
+pub struct PortHold {
+    writers: Arc<Mutex<HashMap<String, mpsc::Sender<Vec<u8>>>>>,
+    port: String,
+    writer: mpsc::Sender<Vec<u8>>,
+}
+
+impl Drop for PortHold {
+    fn drop(&mut self) {
+        let mut writers = self.writers.lock().unwrap();
+        if writers.get(&self.port).is_some_and(|writer| writer.same_channel(&self.writer)) {
+            writers.remove(&self.port);
+        }
+    }
+}
+
+/// Fails when the binary was built without serial port support
+pub fn ensure_supported() -> Result<()> {
+    if cfg!(feature = "serial") {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(UNSUPPORTED))
+    }
+}
+
+/// An open serial port
+#[cfg(feature = "serial")]
+pub struct Port {
+    stream: tokio_serial::SerialStream,
+    name: String,
+}
+
+/// An open serial port (uninhabited: this build can't open ports)
+#[cfg(not(feature = "serial"))]
+pub enum Port {}
+
+#[cfg(feature = "serial")]
+impl Port {
+    /// Open the port with its settings
+    pub fn open(settings: &SerialSettings) -> Result<Self> {
+        use tokio_serial::{DataBits, SerialPortBuilderExt, StopBits};
+
+        let builder = tokio_serial::new(&settings.port, settings.baud_rate)
+            .data_bits(match settings.data_bits {
+                5 => DataBits::Five,
+                6 => DataBits::Six,
+                7 => DataBits::Seven,
+                _ => DataBits::Eight,
+            })
+            .parity(match settings.parity {
+                Parity::None => tokio_serial::Parity::None,
+                Parity::Odd => tokio_serial::Parity::Odd,
+                Parity::Even => tokio_serial::Parity::Even,
+            })
+            .stop_bits(if settings.stop_bits == 2 { StopBits::Two } else { StopBits::One })
+            .flow_control(match settings.flow_control {
+                FlowControl::None => tokio_serial::FlowControl::None,
+                FlowControl::Software => tokio_serial::FlowControl::Software,
+                FlowControl::Hardware => tokio_serial::FlowControl::Hardware,
+            });
+        let stream = builder.open_native_async()
+            .map_err(|e| anyhow::anyhow!("failed to open {}: {}", settings.port, e))?;
+        Ok(Self { stream, name: settings.port.clone() })
+    }
+
+    /// Read available bytes (0 when the device went away)
+    pub async fn read(&mut self, buffer: &mut [u8]) -> Result<usize> {
+        use tokio::io::AsyncReadExt;
+        self.stream.read(buffer).await
+            .map_err(|e| anyhow::anyhow!("read from {} failed: {}", self.name, e))
+    }
+
+    /// Write all bytes
+    pub async fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        use tokio::io::AsyncWriteExt;
+        self.stream.write_all(bytes).await
+            .and(self.stream.flush().await)
+            .map_err(|e| anyhow::anyhow!("write to {} failed: {}", self.name, e))
+    }
+
+    /// Discard received bytes nobody read yet
+    pub fn clear_input(&self) -> Result<()> {
+        use tokio_serial::SerialPort;
+        self.stream.clear(tokio_serial::ClearBuffer::Input)
+            .map_err(|e| anyhow::anyhow!("failed to clear {}: {}", self.name, e))
+    }
+}
+
+#[cfg(not(feature = "serial"))]
+impl Port {
+    /// Open the port with its settings
+    pub fn open(_settings: &SerialSettings) -> Result<Self> {
+        Err(anyhow::anyhow!(UNSUPPORTED))
+    }
+
+    /// Read available bytes (0 when the device went away)
+    pub async fn read(&mut self, _buffer: &mut [u8]) -> Result<usize> {
+        match *self {}
+    }
+
+    /// Write all bytes
+    pub async fn write(&mut self, _bytes: &[u8]) -> Result<()> {
+        match *self {}
+    }
+
+    /// Discard received bytes nobody read yet
+    pub fn clear_input(&self) -> Result<()> {
+        match *self {}
+    }
+}
+
+/// Write commands to a port the Serial node opens itself, reading one reply frame per command if configured
+/// 
+/// Returns the reply items (empty without `read_response`).
+pub async fn exchange(ports: &SerialPorts, params: &SerialParams, commands: &[Vec<u8>]) -> Result<Vec<Value>> {
+    let lock = ports.lock(&params.settings.port);
+    let _turn = lock.lock().await;
+    let mut port = Port::open(&params.settings)?;
+
+    let mut replies = Vec::new();
+    for command in commands {
+        let Some(reply) = &params.reply else {
+            port.write(command).await?;
+            continue;
+        };
+        port.clear_input()?;
+        port.write(command).await?;
+        let mut reader = FrameReader::new(reply.framing.clone(), reply.max_frame);
+        let frame = tokio::time::timeout(reply.timeout, read_frame(&mut port, &mut reader)).await
+            .map_err(|_| anyhow::anyhow!("no reply from {} within {:?}", params.settings.port, reply.timeout))??;
+        replies.push(frame_item(&frame, params.encoding, &params.settings.port));
+    }
+    Ok(replies)
+}
+
+/// Read until the reader completes a frame
+pub async fn read_frame(port: &mut Port, reader: &mut FrameReader) -> Result<Vec<u8>> {
+    let mut buffer = [0u8; 1024];
+    loop {
+        let read = match reader.idle_timeout() {
+            Some(idle) => match tokio::time::timeout(idle, port.read(&mut buffer)).await {
+                Ok(read) => read?,
+                Err(_) => return Ok(reader.flush().unwrap_or_default()),
+            },
+            None => port.read(&mut buffer).await?,
+        };
+        if read == 0 {
+            return Err(anyhow::anyhow!("port closed"));
+        }
+        if let Some(frame) = reader.push(&buffer[..read]).into_iter().next() {
+            return Ok(frame);
+        }
+    }
+}
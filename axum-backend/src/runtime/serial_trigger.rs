@@ -0,0 +1,252 @@
+//! Background reader service for SerialTrigger nodes (cargo feature "serial")
+//! 
+//! Holds the serial port of every SerialTrigger node open and starts the
+//! workflow for every received frame (see serial for framing and decoding):
+//! 
+//! { "port": "/dev/ttyACM0", "baud_rate": 115200, "framing": "line" }
+//! 
+//! Runs of one trigger are executed one after another, in frame order. When
+//! the workflow can't keep up, frames beyond a bounded buffer are dropped
+//! (logged). Unplugged or busy devices are reopened with backoff. While the
+//! port is open, Serial nodes writing to it go through this connection.
+//! 
+//! Item: the decoded frame plus "serial": { "port", "bytes" }.
+
+use crate::{
+    runtime::{
+        engine::ExecutionEngine,
+        serial::{self, Encoding, FrameReader, Framing, Port, SerialPorts, SerialSettings},
+    },
+    workflow::{
+        registry::WorkflowRegistry,
+        types::{ExecutionContext, Node, NodeType, Workflow},
+    },
+};
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::{sync::mpsc, sync::Mutex, task::JoinHandle};
+
+/// Received frames buffered per trigger while a run is in progress
+const FRAME_BUFFER: usize = 1024;
+
+/// Commands from Serial nodes queued per trigger
+const WRITE_BUFFER: usize = 64;
+
+/// Longest wait between reopen attempts
+const MAX_REOPEN_DELAY: Duration = Duration::from_secs(30);
+
+/// SerialTrigger configuration
+#[derive(Debug, Clone)]
+pub struct SerialTriggerParams {
+    pub settings: SerialSettings,
+    pub framing: Framing,
+    pub encoding: Encoding,
+    pub max_frame: usize,
+}
+
+impl SerialTriggerParams {
+    /// Parse node params
+    /// 
+    /// Expected params: port settings (see SerialSettings), { "framing": "line" | "fixed" | "idle",
+    ///                    "delimiter": "\n", "frame_length": 8, "idle_ms": 50, "encoding": "text" | "hex" | "base64",
+    ///                    "max_frame_bytes": 4096 }
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        Ok(Self {
+            settings: SerialSettings::from_node_params(params)?,
+            framing: Framing::from_node_params(params)?,
+            encoding: Encoding::from_node_params(params)?,
+            max_frame: serial::max_frame(params)?,
+        })
+    }
+}
+
+/// HOT-RELOAD serial port reader service
+pub struct SerialTriggerService {
+    registry: Arc<WorkflowRegistry>,
+    engine: Arc<ExecutionEngine>,
+    /// Write queues of the open ports, shared with the executor's Serial nodes
+    ports: SerialPorts,
+    /// workflow_id -> one reader task per SerialTrigger node
+    readers: Mutex<HashMap<String, Vec<JoinHandle<()>>>>,
+}
+
+impl SerialTriggerService {
+    /// Create new serial trigger service
+    pub fn new(registry: Arc<WorkflowRegistry>, engine: Arc<ExecutionEngine>, ports: SerialPorts) -> Self {
+        Self { registry, engine, ports, readers: Mutex::new(HashMap::new()) }
+    }
+
+    /// Open the SerialTrigger ports of all registered workflows
+    pub async fn start(&self) {
+        tracing::info!("🔌 Starting serial trigger service");
+        for workflow in self.registry.get_all_workflows() {
+            if let Err(e) = self.add_or_update_workflow_serial_triggers(&workflow).await {
+                tracing::error!("❌ Failed to open serial triggers of workflow {}: {}", workflow.id, e);
+            }
+        }
+    }
+
+    /// Number of open (or reopening) serial trigger ports
+    pub async fn reader_count(&self) -> usize {
+        self.readers.lock().await.values().map(Vec::len).sum()
+    }
+
+    /// HOT-RELOAD: Replace the readers of a workflow with its current SerialTrigger nodes
+    pub async fn add_or_update_workflow_serial_triggers(&self, workflow: &Workflow) -> Result<()> {
+        let triggers: Vec<(&Node, SerialTriggerParams)> = workflow.nodes.iter()
+            .filter(|node| matches!(node.node_type, NodeType::SerialTrigger))
+            .map(|node| SerialTriggerParams::from_node_params(&node.params)
+                .and_then(|params| serial::ensure_supported().map(|_| params))
+                .map(|params| (node, params))
+                .map_err(|e| anyhow::anyhow!("SerialTrigger '{}': {}", node.id, e)))
+            .collect::<Result<_>>()?;
+
+        self.remove_workflow_serial_triggers(&workflow.id).await;
+        if triggers.is_empty() {
+            return Ok(());
+        }
+
+        let handles: Vec<JoinHandle<()>> = triggers.into_iter()
+            .map(|(node, params)| {
+                tracing::info!("🔌 Opening SerialTrigger {}:{} on {} at {} baud",
+                    workflow.id, node.id, params.settings.port, params.settings.baud_rate);
+                let reader = Reader {
+                    registry: Arc::clone(&self.registry),
+                    engine: Arc::clone(&self.engine),
+                    ports: self.ports.clone(),
+                    workflow_id: workflow.id.clone(),
+                    node_id: node.id.clone(),
+                    params,
+                };
+                tokio::spawn(reader.run())
+            })
+            .collect();
+
+        tracing::info!("🔥 Hot-reloaded {} serial triggers for workflow: {}", handles.len(), workflow.id);
+        self.readers.lock().await.insert(workflow.id.clone(), handles);
+        Ok(())
+    }
+
+    /// HOT-RELOAD: Close all serial trigger ports of a workflow
+    pub async fn remove_workflow_serial_triggers(&self, workflow_id: &str) {
+        if let Some(handles) = self.readers.lock().await.remove(workflow_id) {
+            tracing::info!("🗑️ Removing {} serial triggers for workflow: {}", handles.len(), workflow_id);
+            for handle in handles {
+                handle.abort();
+            }
+        }
+    }
+}
+
+/// One running SerialTrigger
+struct Reader {
+    registry: Arc<WorkflowRegistry>,
+    engine: Arc<ExecutionEngine>,
+    ports: SerialPorts,
+    workflow_id: String,
+    node_id: String,
+    params: SerialTriggerParams,
+}
+
+impl Reader {
+    /// Port connection and worker of the node (runs until aborted)
+    async fn run(self) {
+        let (sender, mut receiver) = mpsc::channel::<Vec<u8>>(FRAME_BUFFER);
+        let trigger = format!("{}:{}", self.workflow_id, self.node_id);
+
+        let connection = async {
+            let mut delay = Duration::from_secs(1);
+            loop {
+                match Port::open(&self.params.settings) {
+                    Ok(port) => {
+                        tracing::info!("🔌 SerialTrigger {} opened {}", trigger, self.params.settings.port);
+                        delay = Duration::from_secs(1);
+                        let error = self.read_port(port, &sender, &trigger).await;
+                        tracing::warn!("⚠️ SerialTrigger {} lost {}: {} (reopening in {:?})", trigger, self.params.settings.port, error, delay);
+                    }
+                    Err(e) => {
+                        tracing::warn!("⚠️ SerialTrigger {}: {} (retrying in {:?})", trigger, e, delay);
+                    }
+                }
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_REOPEN_DELAY);
+            }
+        };
+
+        let worker = async {
+            while let Some(frame) = receiver.recv().await {
+                self.execute(&frame).await;
+            }
+        };
+
+        tokio::join!(connection, worker);
+    }
+
+    /// Forward frames and write queued commands until the port fails
+    async fn read_port(&self, mut port: Port, frames: &mpsc::Sender<Vec<u8>>, trigger: &str) -> anyhow::Error {
+        let (writer, mut writes) = mpsc::channel::<Vec<u8>>(WRITE_BUFFER);
+        // Serial nodes write through this connection while the hold lives
+        let _hold = self.ports.hold(&self.params.settings.port, writer);
+        let mut reader = FrameReader::new(self.params.framing.clone(), self.params.max_frame);
+        let mut buffer = [0u8; 1024];
+
+        loop {
+            let idle = reader.idle_timeout();
+            tokio::select! {
+                read = port.read(&mut buffer) => {
+                    let read = match read {
+                        Ok(0) => return anyhow::anyhow!("device closed"),
+                        Ok(read) => read,
+                        Err(e) => return e,
+                    };
+                    for frame in reader.push(&buffer[..read]) {
+                        if frames.try_send(frame).is_err() {
+                            tracing::warn!("⚠️ SerialTrigger {} is falling behind, frame dropped", trigger);
+                        }
+                    }
+                }
+                Some(command) = writes.recv() => {
+                    if let Err(e) = port.write(&command).await {
+                        return e;
+                    }
+                }
+                _ = tokio::time::sleep(idle.unwrap_or_default()), if idle.is_some() => {
+                    if let Some(frame) = reader.flush() {
+                        if frames.try_send(frame).is_err() {
+                            tracing::warn!("⚠️ SerialTrigger {} is falling behind, frame dropped", trigger);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Run the workflow for one frame
+    async fn execute(&self, frame: &[u8]) {
+        let Some(compiled) = self.registry.get_workflow(&self.workflow_id) else {
+            tracing::warn!("⚠️ SerialTrigger {}:{} received a frame for an unknown workflow", self.workflow_id, self.node_id);
+            return;
+        };
+        if self.engine.maintenance_mode().is_enabled() {
+            tracing::debug!("🚧 SerialTrigger {}:{} dropped a frame: maintenance mode", self.workflow_id, self.node_id);
+            return;
+        }
+        if compiled.active_snooze().is_some() {
+            tracing::debug!("💤 SerialTrigger {}:{} dropped a frame: workflow is snoozed", self.workflow_id, self.node_id);
+            return;
+        }
+        let item = serial::frame_item(frame, self.params.encoding, &self.params.settings.port);
+
+        let mut context = ExecutionContext::from_array_data(
+            self.workflow_id.clone(), vec![item], compiled.workflow.project.clone());
+        context.metadata.insert("trigger_type".to_string(), json!("serial"));
+        context.metadata.insert("trigger_node_id".to_string(), json!(self.node_id));
+        context.metadata.insert("serial_port".to_string(), json!(self.params.settings.port));
+
+        tracing::info!("🚀 Executing serial-triggered workflow: {} ({}, {} bytes)", self.workflow_id, self.node_id, frame.len());
+        if let Err(e) = self.engine.execute_workflow(&compiled, &self.node_id, context).await {
+            tracing::error!("❌ Serial-triggered workflow failed: {} - Error: {}", self.workflow_id, e);
+        }
+    }
+}
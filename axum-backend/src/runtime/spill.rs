@@ -0,0 +1,141 @@
+//! Disk spilling for oversized intermediate node outputs
+//! 
+//! A node that produces more data than the per-run threshold has its output
+//! written to {data_dir}/{slug}/spill/ as JSON Lines and dropped from memory.
+//! The next node rehydrates it right before it runs, so only one oversized
+//! output is resident at a time instead of piling up across the run.
+
+use anyhow::Result;
+use serde_json::Value;
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+/// Default in-memory threshold per node output (64 MiB of serialized JSON)
+pub const DEFAULT_SPILL_THRESHOLD_BYTES: usize = 64 * 1024 * 1024;
+
+/// Subdirectory of the project directory that holds spill files
+const SPILL_DIR_NAME: &str = "spill";
+
+/// Writer that only counts bytes and aborts once the limit is exceeded
+struct CountingWriter {
+    written: usize,
+    limit: usize,
+}
+
+impl Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.written += buf.len();
+        if self.written > self.limit {
+            // Stop serializing - we only need to know the limit was crossed
+            return Err(std::io::Error::other("spill threshold exceeded"));
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Check whether a data array serializes to more than `threshold_bytes`
+/// 
+/// A threshold of 0 disables spilling. Cost is bounded by the threshold,
+/// not the payload size, and no buffer is allocated.
+pub fn exceeds_threshold(data: &[Value], threshold_bytes: usize) -> bool {
+    if threshold_bytes == 0 {
+        return false;
+    }
+    let mut writer = CountingWriter { written: 0, limit: threshold_bytes };
+    for item in data {
+        if serde_json::to_writer(&mut writer, item).is_err() {
+            return true;
+        }
+        // Account for the separator between items
+        writer.written += 1;
+    }
+    writer.written > threshold_bytes
+}
+
+/// Node output that was moved out of memory into a spill file
+/// 
+/// The file is removed when the handle is dropped, including when the
+/// execution fails before a downstream node rehydrates it.
+#[derive(Debug)]
+pub struct SpilledData {
+    path: PathBuf,
+    items: usize,
+}
+
+impl SpilledData {
+    /// Write data items to a new spill file under the project directory
+    pub async fn write(project_dir: &Path, data: Vec<Value>) -> Result<Self> {
+        let spill_dir = project_dir.join(SPILL_DIR_NAME);
+        let path = spill_dir.join(format!("{}.jsonl", uuid::Uuid::new_v4()));
+        let items = data.len();
+
+        let file_path = path.clone();
+        tokio::task::spawn_blocking(move || -> Result<()> {
+            std::fs::create_dir_all(&spill_dir)?;
+            let mut writer = BufWriter::new(File::create(&file_path)?);
+            for item in &data {
+                serde_json::to_writer(&mut writer, item)?;
+                writer.write_all(b"\n")?;
+            }
+            writer.flush()?;
+            Ok(())
+        })
+        .await??;
+
+        tracing::info!("💾 Spilled {} items to {}", items, path.display());
+        Ok(Self { path, items })
+    }
+
+    /// Read the data back into memory and remove the spill file
+    pub async fn rehydrate(self) -> Result<Vec<Value>> {
+        let path = self.path.clone();
+        let items = self.items;
+        let data = tokio::task::spawn_blocking(move || -> Result<Vec<Value>> {
+            let reader = BufReader::new(File::open(&path)?);
+            let mut data = Vec::with_capacity(items);
+            for line in reader.lines() {
+                let line = line?;
+                if !line.is_empty() {
+                    data.push(serde_json::from_str(&line)?);
+                }
+            }
+            Ok(data)
+        })
+        .await??;
+
+        tracing::debug!("📤 Rehydrated {} items from {}", data.len(), self.path.display());
+        // Dropping self removes the file
+        Ok(data)
+    }
+}
+
+impl Drop for SpilledData {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                tracing::warn!("⚠️ Failed to remove spill file {}: {}", self.path.display(), e);
+            }
+        }
+    }
+}
+
+/// Remove spill files left behind by a previous process (e.g. after a crash)
+pub fn cleanup_stale_spills(data_dir: &str) {
+    let Ok(projects) = std::fs::read_dir(data_dir) else { return };
+    for project in projects.flatten() {
+        let spill_dir = project.path().join(SPILL_DIR_NAME);
+        if spill_dir.is_dir() {
+            match std::fs::remove_dir_all(&spill_dir) {
+                Ok(()) => tracing::info!("🧹 Removed stale spill directory: {}", spill_dir.display()),
+                Err(e) => tracing::warn!("⚠️ Failed to remove spill directory {}: {}", spill_dir.display(), e),
+            }
+        }
+    }
+}
@@ -0,0 +1,398 @@
+//! Stream aggregation for real-time triggers
+//! 
+//! MQTTTrigger and WebSocketTrigger nodes take an optional "aggregate" param
+//! that is evaluated in the trigger service, before any execution starts, so
+//! a 1kHz sensor doesn't spawn a thousand workflow runs per second:
+//! 
+//! { "mode": "sample", "every": 100 }                   every 100th message
+//! { "mode": "average", "window_secs": 1, "fields": ["temperature"] }
+//!                                                      one run per second with mean values
+//! { "mode": "threshold", "field": "temperature", "above": 80, "hysteresis": 2 }
+//!                                                      only when the value crosses the limit
+//! 
+//! Averaged runs get the window's last message with the "fields" (dotted
+//! paths; default: all top-level numbers) replaced by their means. Every
+//! aggregated run carries metadata "aggregate" describing what it stands for.
+//! State is kept per stream: per MQTT device (topic by default), per WebSocket connection.
+
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Aggregation settings of a trigger node (params key "aggregate")
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamAggregate {
+    /// Pass on every Nth message
+    Sample { every: u64 },
+    /// Pass on the means over tumbling windows
+    Average { window: Duration, fields: Option<Vec<String>> },
+    /// Pass on messages whose value crosses a limit
+    Threshold { field: String, above: Option<f64>, below: Option<f64>, hysteresis: f64 },
+}
+
+impl StreamAggregate {
+    /// Read the aggregation settings of a trigger node (None if it passes every message on)
+    pub fn from_node_params(params: &Value) -> Result<Option<Self>> {
+        let settings = match params.get("aggregate") {
+            None | Some(Value::Null) => return Ok(None),
+            Some(settings @ Value::Object(_)) => settings,
+            Some(_) => return Err(anyhow::anyhow!("'aggregate' must be an object")),
+        };
+        let number = |name: &str| settings.get(name).and_then(|n| n.as_f64());
+
+        match settings.get("mode").and_then(|m| m.as_str()) {
+            Some("sample") => {
+                let every = settings.get("every").and_then(|e| e.as_u64()).filter(|e| *e > 0)
+                    .ok_or_else(|| anyhow::anyhow!("'aggregate.every' must be a positive integer"))?;
+                Ok(Some(StreamAggregate::Sample { every }))
+            }
+            Some("average") => {
+                let window_secs = number("window_secs").filter(|w| *w > 0.0)
+                    .ok_or_else(|| anyhow::anyhow!("'aggregate.window_secs' must be a positive number"))?;
+                let fields = match settings.get("fields") {
+                    None | Some(Value::Null) => None,
+                    Some(Value::Array(fields)) if !fields.is_empty() => Some(fields.iter()
+                        .map(|f| f.as_str().map(str::to_string).ok_or_else(|| anyhow::anyhow!("'aggregate.fields' must be field paths")))
+                        .collect::<Result<Vec<_>>>()?),
+                    Some(_) => return Err(anyhow::anyhow!("'aggregate.fields' must be a non-empty array of field paths")),
+                };
+                Ok(Some(StreamAggregate::Average { window: Duration::from_secs_f64(window_secs), fields }))
+            }
+            Some("threshold") => {
+                let field = settings.get("field").and_then(|f| f.as_str()).filter(|f| !f.is_empty())
+                    .ok_or_else(|| anyhow::anyhow!("'aggregate.field' is required for threshold mode"))?
+                    .to_string();
+                let (above, below) = (number("above"), number("below"));
+                match (above, below) {
+                    (None, None) => return Err(anyhow::anyhow!("threshold mode needs 'aggregate.above' and/or 'aggregate.below'")),
+                    (Some(above), Some(below)) if below >= above => {
+                        return Err(anyhow::anyhow!("'aggregate.below' ({}) must be lower than 'aggregate.above' ({})", below, above));
+                    }
+                    _ => {}
+                }
+                let hysteresis = number("hysteresis").unwrap_or(0.0);
+                if hysteresis < 0.0 {
+                    return Err(anyhow::anyhow!("'aggregate.hysteresis' can't be negative"));
+                }
+                Ok(Some(StreamAggregate::Threshold { field, above, below, hysteresis }))
+            }
+            Some(other) => Err(anyhow::anyhow!("unsupported aggregate mode '{}' (expected sample, average or threshold)", other)),
+            None => Err(anyhow::anyhow!("missing 'aggregate.mode' (sample, average or threshold)")),
+        }
+    }
+}
+
+/// Message to start an execution with, plus metadata "aggregate" (null when not aggregated)
+#[derive(Debug, Clone)]
+pub struct Aggregated {
+    pub item: Value,
+    pub info: Value,
+}
+
+/// Where a threshold-watched value currently is
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Level {
+    Normal,
+    Above,
+    Below,
+}
+
+impl Level {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Level::Normal => "normal",
+            Level::Above => "above",
+            Level::Below => "below",
+        }
+    }
+}
+
+/// Running statistics of one averaged field
+#[derive(Debug, Clone, Copy)]
+struct FieldStats {
+    sum: f64,
+    count: u64,
+    min: f64,
+    max: f64,
+}
+
+/// Messages of the current average window
+#[derive(Debug)]
+struct OpenWindow {
+    started: Instant,
+    started_at: chrono::DateTime<chrono::Utc>,
+    messages: u64,
+    stats: BTreeMap<String, FieldStats>,
+    last: Value,
+}
+
+/// Aggregation state of one stream
+#[derive(Debug)]
+pub struct Aggregator {
+    config: Option<StreamAggregate>,
+    seen: u64,
+    level: Level,
+    window: Option<OpenWindow>,
+}
+
+impl Aggregator {
+    pub fn new(config: Option<StreamAggregate>) -> Self {
+        Self { config, seen: 0, level: Level::Normal, window: None }
+    }
+
+    /// Feed one message; returns what to start an execution with, if anything
+    /// 
+    /// An average window that is already due is closed first, so a late timer
+    /// never merges two windows.
+    pub fn push(&mut self, item: Value, now: Instant) -> Option<Aggregated> {
+        self.seen += 1;
+        match self.config.clone() {
+            None => Some(Aggregated { item, info: Value::Null }),
+            Some(StreamAggregate::Sample { every }) => {
+                if self.seen < every {
+                    return None;
+                }
+                self.seen = 0;
+                Some(Aggregated { item, info: json!({ "mode": "sample", "every": every }) })
+            }
+            Some(StreamAggregate::Average { window, fields }) => {
+                let closed = match &self.window {
+                    Some(open) if now >= open.started + window => self.flush(),
+                    _ => None,
+                };
+                let open = self.window.get_or_insert_with(|| OpenWindow {
+                    started: now,
+                    started_at: chrono::Utc::now(),
+                    messages: 0,
+                    stats: BTreeMap::new(),
+                    last: Value::Null,
+                });
+                open.messages += 1;
+                let values: Vec<(String, f64)> = match &fields {
+                    Some(fields) => fields.iter()
+                        .filter_map(|field| Some((field.clone(), lookup(&item, field)?.as_f64()?)))
+                        .collect(),
+                    None => item.as_object().into_iter()
+                        .flat_map(|object| object.iter())
+                        .filter_map(|(field, value)| Some((field.clone(), value.as_f64()?)))
+                        .collect(),
+                };
+                for (field, value) in values {
+                    let stats = open.stats.entry(field).or_insert(FieldStats { sum: 0.0, count: 0, min: value, max: value });
+                    stats.sum += value;
+                    stats.count += 1;
+                    stats.min = stats.min.min(value);
+                    stats.max = stats.max.max(value);
+                }
+                open.last = item;
+                closed
+            }
+            Some(StreamAggregate::Threshold { field, above, below, hysteresis }) => {
+                let value = lookup(&item, &field).and_then(|v| v.as_f64())?;
+                let previous = self.level;
+                let mut level = previous;
+                if level == Level::Above && above.is_some_and(|limit| value <= limit - hysteresis) {
+                    level = Level::Normal;
+                }
+                if level == Level::Below && below.is_some_and(|limit| value >= limit + hysteresis) {
+                    level = Level::Normal;
+                }
+                if level != Level::Above && above.is_some_and(|limit| value > limit) {
+                    level = Level::Above;
+                } else if level != Level::Below && below.is_some_and(|limit| value < limit) {
+                    level = Level::Below;
+                }
+                if level == previous {
+                    return None;
+                }
+                self.level = level;
+                Some(Aggregated {
+                    item,
+                    info: json!({ "mode": "threshold", "field": field, "value": value, "state": level.as_str(), "previous": previous.as_str() }),
+                })
+            }
+        }
+    }
+
+    /// When the open average window is due (None for other modes or without messages)
+    pub fn deadline(&self) -> Option<Instant> {
+        match (&self.config, &self.window) {
+            (Some(StreamAggregate::Average { window, .. }), Some(open)) => Some(open.started + *window),
+            _ => None,
+        }
+    }
+
+    /// Close the open average window
+    pub fn flush(&mut self) -> Option<Aggregated> {
+        let open = self.window.take()?;
+        let mut item = open.last;
+        let mut min = Map::new();
+        let mut max = Map::new();
+        for (field, stats) in &open.stats {
+            set_path(&mut item, field, json!(stats.sum / stats.count as f64));
+            min.insert(field.clone(), json!(stats.min));
+            max.insert(field.clone(), json!(stats.max));
+        }
+        Some(Aggregated {
+            item,
+            info: json!({
+                "mode": "average",
+                "messages": open.messages,
+                "window_start": open.started_at.to_rfc3339(),
+                "window_end": chrono::Utc::now().to_rfc3339(),
+                "min": min,
+                "max": max,
+            }),
+        })
+    }
+}
+
+/// Value at a dotted field path
+fn lookup<'a>(item: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(item, |value, segment| value.get(segment))
+}
+
+/// Replace the value at a dotted field path (missing objects are created)
+fn set_path(item: &mut Value, path: &str, value: Value) {
+    let mut current = item;
+    let mut segments = path.split('.').peekable();
+    while let Some(segment) = segments.next() {
+        if !current.is_object() {
+            *current = Value::Object(Map::new());
+        }
+        let object = current.as_object_mut().expect("just made an object");
+        if segments.peek().is_none() {
+            object.insert(segment.to_string(), value);
+            return;
+        }
+        current = object.entry(segment.to_string()).or_insert(Value::Null);
+    }
+}
+
+/// Workflow item of a real-time message
+/// 
+/// JSON object payloads become the item itself; anything else (other JSON,
+/// text) is put under "value". The transport details go under `source`
+/// ("mqtt" / "websocket"), where `$mqtt.*` / `$websocket.*` pins read them.
+pub fn message_item(payload: &[u8], source: &str, envelope: Value) -> Value {
+    let parsed = serde_json::from_slice(payload)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(payload).into_owned()));
+    let mut item = match parsed {
+        Value::Object(object) => object,
+        other => {
+            let mut object = Map::new();
+            object.insert("value".to_string(), other);
+            object
+        }
+    };
+    item.insert(source.to_string(), envelope);
+    Value::Object(item)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aggregator(settings: Value) -> Aggregator {
+        Aggregator::new(StreamAggregate::from_node_params(&json!({ "aggregate": settings })).unwrap())
+    }
+
+    #[test]
+    fn parses_settings() {
+        assert_eq!(StreamAggregate::from_node_params(&json!({})).unwrap(), None);
+        assert_eq!(
+            StreamAggregate::from_node_params(&json!({ "aggregate": { "mode": "sample", "every": 5 } })).unwrap(),
+            Some(StreamAggregate::Sample { every: 5 })
+        );
+        for invalid in [
+            json!({ "mode": "sample", "every": 0 }),
+            json!({ "mode": "average", "window_secs": 0 }),
+            json!({ "mode": "average", "window_secs": 1, "fields": [] }),
+            json!({ "mode": "threshold", "field": "t" }),
+            json!({ "mode": "threshold", "field": "t", "above": 10, "below": 20 }),
+            json!({ "mode": "threshold", "field": "t", "above": 10, "hysteresis": -1 }),
+            json!({ "mode": "median" }),
+            json!("sample"),
+        ] {
+            assert!(StreamAggregate::from_node_params(&json!({ "aggregate": invalid })).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn without_settings_every_message_passes() {
+        let mut aggregator = Aggregator::new(None);
+        let passed = aggregator.push(json!({ "n": 1 }), Instant::now()).unwrap();
+        assert_eq!(passed.item, json!({ "n": 1 }));
+        assert_eq!(passed.info, Value::Null);
+    }
+
+    #[test]
+    fn sample_passes_every_nth_message() {
+        let mut aggregator = aggregator(json!({ "mode": "sample", "every": 3 }));
+        let now = Instant::now();
+        let passed: Vec<Value> = (1..=7)
+            .filter_map(|n| aggregator.push(json!({ "n": n }), now))
+            .map(|aggregated| aggregated.item["n"].clone())
+            .collect();
+        assert_eq!(passed, vec![json!(3), json!(6)]);
+        assert_eq!(aggregator.deadline(), None);
+    }
+
+    #[test]
+    fn average_closes_the_window_with_means() {
+        let mut aggregator = aggregator(json!({ "mode": "average", "window_secs": 1 }));
+        let start = Instant::now();
+        assert!(aggregator.push(json!({ "t": 10, "unit": "C" }), start).is_none());
+        assert!(aggregator.push(json!({ "t": 20, "unit": "C" }), start + Duration::from_millis(500)).is_none());
+        assert_eq!(aggregator.deadline(), Some(start + Duration::from_secs(1)));
+
+        // A message after the deadline closes the window before opening the next one
+        let closed = aggregator.push(json!({ "t": 30, "unit": "F" }), start + Duration::from_secs(1)).unwrap();
+        assert_eq!(closed.item, json!({ "t": 15.0, "unit": "C" }));
+        assert_eq!(closed.info["messages"], 2);
+        assert_eq!(closed.info["min"], json!({ "t": 10.0 }));
+        assert_eq!(closed.info["max"], json!({ "t": 20.0 }));
+
+        let flushed = aggregator.flush().unwrap();
+        assert_eq!(flushed.item, json!({ "t": 30.0, "unit": "F" }));
+        assert!(aggregator.flush().is_none());
+        assert_eq!(aggregator.deadline(), None);
+    }
+
+    #[test]
+    fn average_of_selected_fields() {
+        let mut aggregator = aggregator(json!({ "mode": "average", "window_secs": 1, "fields": ["sensor.t"] }));
+        let now = Instant::now();
+        aggregator.push(json!({ "sensor": { "t": 1 }, "n": 1 }), now);
+        aggregator.push(json!({ "sensor": { "t": 2 }, "n": 2 }), now);
+        let closed = aggregator.flush().unwrap();
+        assert_eq!(closed.item, json!({ "sensor": { "t": 1.5 }, "n": 2 }));
+    }
+
+    #[test]
+    fn threshold_passes_crossings_with_hysteresis() {
+        let mut aggregator = aggregator(json!({ "mode": "threshold", "field": "t", "above": 80, "below": 10, "hysteresis": 2 }));
+        let now = Instant::now();
+        let states: Vec<(f64, String)> = [79.0, 81.0, 85.0, 79.0, 77.5, 81.0, 9.0, 11.0, 12.5]
+            .into_iter()
+            .filter_map(|t| aggregator.push(json!({ "t": t }), now))
+            .map(|aggregated| (aggregated.item["t"].as_f64().unwrap(), aggregated.info["state"].as_str().unwrap().to_string()))
+            .collect();
+        assert_eq!(states, vec![
+            (81.0, "above".to_string()),
+            (77.5, "normal".to_string()),
+            (81.0, "above".to_string()),
+            (9.0, "below".to_string()),
+            (12.5, "normal".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn threshold_ignores_messages_without_the_field() {
+        let mut aggregator = aggregator(json!({ "mode": "threshold", "field": "t", "above": 80 }));
+        assert!(aggregator.push(json!({ "t": "hot" }), Instant::now()).is_none());
+        assert!(aggregator.push(json!({ "other": 90 }), Instant::now()).is_none());
+    }
+}
@@ -0,0 +1,176 @@
+//! StripeAPI node requests
+//! 
+//! Covers the calls billing workflows make most, with the key taken from the
+//! node's secrets (`["$secret.stripe_secret_key"]`):
+//! 
+//! { "operation": "create_customer", "fields": ["email", "name", "metadata[order_id]"] }
+//! { "operation": "create_payment_link", "params": { "line_items": [{ "price": "price_123", "quantity": 1 }] } }
+//! { "operation": "request", "method": "GET", "path": "/v1/customers/cus_123" }
+//! 
+//! Input pins fill `fields` positionally; `params` holds static values. Both are
+//! form-encoded the way the Stripe API expects (`line_items[0][price]=...`).
+//! An optional `idempotency_key` pin (e.g. "$json.order_id") is sent as the
+//! Idempotency-Key header so retried runs don't create duplicates.
+
+use crate::runtime::outbox;
+use anyhow::Result;
+use serde_json::Value;
+
+/// Production API host (overridable with "api_base", e.g. for stripe-mock)
+const DEFAULT_API_BASE: &str = "https://api.stripe.com";
+
+/// Supported StripeAPI operations
+#[derive(Debug, Clone, PartialEq)]
+pub enum StripeOperation {
+    CreateCustomer,
+    CreatePaymentLink,
+    /// Any endpoint: method + path under /v1/
+    Request { method: reqwest::Method, path: String },
+}
+
+impl StripeOperation {
+    fn method(&self) -> reqwest::Method {
+        match self {
+            StripeOperation::CreateCustomer | StripeOperation::CreatePaymentLink => reqwest::Method::POST,
+            StripeOperation::Request { method, .. } => method.clone(),
+        }
+    }
+
+    fn path(&self) -> &str {
+        match self {
+            StripeOperation::CreateCustomer => "/v1/customers",
+            StripeOperation::CreatePaymentLink => "/v1/payment_links",
+            StripeOperation::Request { path, .. } => path,
+        }
+    }
+}
+
+/// Parsed StripeAPI node params
+#[derive(Debug, Clone)]
+pub struct StripeApiParams {
+    pub operation: StripeOperation,
+    /// Form keys filled by the input pins, in order
+    pub fields: Vec<String>,
+    /// Static form values
+    pub params: Option<Value>,
+    /// Pin expression for the Idempotency-Key header
+    pub idempotency_key: Option<String>,
+    /// Stripe-Version header
+    pub api_version: Option<String>,
+    pub api_base: String,
+}
+
+impl StripeApiParams {
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        let str_param = |name: &str| params.get(name).and_then(|v| v.as_str()).map(str::to_string);
+
+        let operation = match str_param("operation").as_deref() {
+            Some("create_customer") => StripeOperation::CreateCustomer,
+            Some("create_payment_link") => StripeOperation::CreatePaymentLink,
+            Some("request") => {
+                let method = str_param("method").unwrap_or_else(|| "GET".to_string()).to_uppercase();
+                let method = match method.as_str() {
+                    "GET" => reqwest::Method::GET,
+                    "POST" => reqwest::Method::POST,
+                    "DELETE" => reqwest::Method::DELETE,
+                    _ => return Err(anyhow::anyhow!("Unsupported Stripe method: {}", method)),
+                };
+                let path = str_param("path").ok_or_else(|| anyhow::anyhow!("'request' operation needs a 'path'"))?;
+                if !path.starts_with("/v1/") {
+                    return Err(anyhow::anyhow!("Stripe path must start with /v1/, got '{}'", path));
+                }
+                StripeOperation::Request { method, path }
+            }
+            Some(other) => return Err(anyhow::anyhow!(
+                "Unknown Stripe operation '{}' (create_customer, create_payment_link, request)", other
+            )),
+            None => return Err(anyhow::anyhow!("Missing 'operation' parameter")),
+        };
+
+        let fields = match params.get("fields") {
+            None | Some(Value::Null) => Vec::new(),
+            Some(Value::Array(fields)) => fields.iter()
+                .map(|f| f.as_str().map(str::to_string).ok_or_else(|| anyhow::anyhow!("'fields' must be strings")))
+                .collect::<Result<_>>()?,
+            Some(_) => return Err(anyhow::anyhow!("'fields' must be an array of form keys")),
+        };
+
+        Ok(Self {
+            operation,
+            fields,
+            params: params.get("params").filter(|p| !p.is_null()).cloned(),
+            idempotency_key: str_param("idempotency_key"),
+            api_version: str_param("api_version"),
+            api_base: str_param("api_base").unwrap_or_else(|| DEFAULT_API_BASE.to_string()),
+        })
+    }
+
+    /// Form pairs from the static params and the evaluated input pins
+    pub fn form(&self, input_values: &[Value]) -> Vec<(String, String)> {
+        let mut form = Vec::new();
+        if let Some(Value::Object(map)) = &self.params {
+            for (key, value) in map {
+                flatten_form(key, value, &mut form);
+            }
+        }
+        for (field, value) in self.fields.iter().zip(input_values) {
+            flatten_form(field, value, &mut form);
+        }
+        form
+    }
+
+    /// Send the request, returning the Stripe object (errors carry Stripe's message)
+    pub async fn send(&self, api_key: &str, form: &[(String, String)], idempotency_key: Option<String>) -> Result<Value> {
+        let url = format!("{}{}", self.api_base.trim_end_matches('/'), self.operation.path());
+        let method = self.operation.method();
+        let mut request = reqwest::Client::new()
+            .request(method.clone(), &url)
+            .bearer_auth(api_key);
+        request = if method == reqwest::Method::GET || method == reqwest::Method::DELETE {
+            request.query(form)
+        } else {
+            request.form(form)
+        };
+        if let Some(key) = idempotency_key {
+            request = request.header("Idempotency-Key", key);
+        }
+        if let Some(version) = &self.api_version {
+            request = request.header("Stripe-Version", version);
+        }
+
+        let response = request.send().await
+            .map_err(|e| outbox::request_error("Stripe request failed", e))?;
+        let status = response.status();
+        let body: Value = response.json().await
+            .map_err(|e| anyhow::anyhow!("Stripe returned invalid JSON ({}): {}", status, e))?;
+        if !status.is_success() {
+            let message = body.pointer("/error/message").and_then(|m| m.as_str()).unwrap_or("unknown error");
+            let code = body.pointer("/error/code").or_else(|| body.pointer("/error/type"))
+                .and_then(|c| c.as_str()).unwrap_or("error");
+            return Err(anyhow::anyhow!("Stripe API error {} ({}): {}", status.as_u16(), code, message));
+        }
+        Ok(body)
+    }
+}
+
+/// Form-encode a JSON value under a key with Stripe's bracket notation
+/// 
+/// { "metadata": { "a": 1 } } -> metadata[a]=1, { "items": ["x"] } -> items[0]=x.
+/// Nulls are skipped.
+fn flatten_form(key: &str, value: &Value, form: &mut Vec<(String, String)>) {
+    match value {
+        Value::Null => {}
+        Value::Object(map) => {
+            for (child, value) in map {
+                flatten_form(&format!("{}[{}]", key, child), value, form);
+            }
+        }
+        Value::Array(items) => {
+            for (index, value) in items.iter().enumerate() {
+                flatten_form(&format!("{}[{}]", key, index), value, form);
+            }
+        }
+        Value::String(s) => form.push((key.to_string(), s.clone())),
+        other => form.push((key.to_string(), other.to_string())),
+    }
+}
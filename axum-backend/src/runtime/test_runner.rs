@@ -0,0 +1,110 @@
+//! Isolated runner for workflow test cases
+//! 
+//! Each run gets its own node executor backed by in-memory project databases,
+//! a private event bus and a throwaway data directory, so test executions never
+//! touch production tables, KV state or event subscribers. Mocked nodes return
+//! their canned results; every other node executes for real.
+
+use crate::{
+    project::ProjectDatabaseManager,
+    runtime::{
+        engine::ExecutionEngine,
+        events::EventBus,
+        executor::{ExecutionResult, NodeExecutor},
+        spill::DEFAULT_SPILL_THRESHOLD_BYTES,
+    },
+    testing::temp_data_dir,
+    workflow::{
+        registry::{CompiledWorkflow, WorkflowRegistry},
+        test_cases::{TestCaseResult, TestRunReport, WorkflowTestCase},
+        types::ExecutionContext,
+    },
+};
+use anyhow::Result;
+use std::{path::PathBuf, sync::Arc};
+
+/// Sandbox engine for executing workflow test cases
+pub struct WorkflowTestRunner {
+    engine: ExecutionEngine,
+    data_dir: PathBuf,
+}
+
+impl WorkflowTestRunner {
+    /// Create a runner with fresh in-memory databases
+    /// 
+    /// The registry is shared so ExecuteWorkflow nodes still resolve their
+    /// targets; those sub-workflows run inside the same sandbox.
+    pub fn new(registry: Arc<WorkflowRegistry>) -> Result<Self> {
+        let data_dir = temp_data_dir("mechaway-workflow-tests");
+        let project_db_manager = Arc::new(ProjectDatabaseManager::new_in_memory(
+            data_dir.to_string_lossy().into_owned(),
+        ));
+        let executor = Arc::new(NodeExecutor::new(project_db_manager, Arc::new(EventBus::new()))?);
+        let engine = ExecutionEngine::new(executor, registry, DEFAULT_SPILL_THRESHOLD_BYTES);
+
+        Ok(Self { engine, data_dir })
+    }
+
+    /// Run all test cases of a workflow, in order
+    pub async fn run(&self, workflow: &CompiledWorkflow, cases: &[WorkflowTestCase]) -> TestRunReport {
+        let mut results = Vec::with_capacity(cases.len());
+        for case in cases {
+            results.push(self.run_case(workflow, case).await);
+        }
+
+        let passed = results.iter().filter(|r| r.passed).count();
+        tracing::info!("🧪 Workflow '{}' tests: {} passed, {} failed",
+            workflow.workflow.id, passed, results.len() - passed);
+
+        TestRunReport {
+            workflow_id: workflow.workflow.id.clone(),
+            passed,
+            failed: results.len() - passed,
+            results,
+        }
+    }
+
+    /// Execute a single test case and evaluate its assertions
+    pub async fn run_case(&self, workflow: &CompiledWorkflow, case: &WorkflowTestCase) -> TestCaseResult {
+        let started = std::time::Instant::now();
+        let outcome = self.execute(workflow, case).await.map_err(|e| e.to_string());
+        TestCaseResult::evaluate(case, outcome, started.elapsed().as_millis() as u64)
+    }
+
+    async fn execute(&self, workflow: &CompiledWorkflow, case: &WorkflowTestCase) -> Result<Vec<serde_json::Value>> {
+        let start_node = match &case.start_node {
+            Some(start) => start.clone(),
+            None => workflow.start_node_ids.first()
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("Workflow '{}' has no start node", workflow.workflow.id))?,
+        };
+
+        let mut context = ExecutionContext::from_webhook_data(
+            workflow.workflow.id.clone(),
+            case.input.clone(),
+            workflow.workflow.project.clone(),
+        );
+        context.query = case.query.clone();
+        context.headers = case.headers.clone();
+        context.metadata.insert("test_case".to_string(), serde_json::json!(case.name));
+        context.mocks = Some(Arc::new(case.mocks.clone()));
+
+        Ok(self.execute_context(workflow, &start_node, context).await?.data)
+    }
+
+    /// Execute a prepared context in the sandbox (e.g. a replayed trigger payload)
+    pub async fn execute_context(
+        &self,
+        workflow: &CompiledWorkflow,
+        start_node: &str,
+        context: ExecutionContext,
+    ) -> Result<ExecutionResult> {
+        self.engine.execute_workflow(workflow, start_node, context).await
+    }
+}
+
+impl Drop for WorkflowTestRunner {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.data_dir);
+    }
+}
@@ -0,0 +1,150 @@
+//! Per-node and per-branch outcomes of a run
+//! 
+//! The engine records every node it visits while walking the plan. Leaf nodes
+//! (no outgoing edges) end a branch of the DAG; with several leaves each branch
+//! keeps its own output, since the run's final data is only the last leaf's.
+//! The trace is returned in the run's metadata under "trace":
+//! 
+//! ```json
+//! { "nodes": [{ "node_id": "score", "node_type": "FunLogic", "status": "success", "items": 1, "runs": 1, "duration_ms": 2 }],
+//!   "branches": [{ "leaf": "store", "status": "completed", "items": 1, "data": [...] }] }
+//! ```
+
+use crate::runtime::executor::{ExecutionResult, Termination};
+use serde::Serialize;
+use serde_json::Value;
+use std::time::Duration;
+
+/// Metadata key of the run trace
+pub const TRACE_METADATA_KEY: &str = "trace";
+
+/// What happened to a node during a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeStatus {
+    Success,
+    /// Ended the run early (Termination::StoppedEarly)
+    Stopped,
+    /// Disabled and passed its input through
+    Skipped,
+    /// Disabled in "block" mode
+    Blocked,
+    /// Behind an output port the run didn't take (e.g. the "false" port of a FeatureFlag)
+    NotTaken,
+}
+
+/// Outcome of one node (streamed nodes downstream of a batch source run once per batch)
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeTrace {
+    pub node_id: String,
+    pub node_type: String,
+    pub status: NodeStatus,
+    /// Output items (summed over batches)
+    pub items: usize,
+    pub runs: usize,
+    pub duration_ms: u64,
+}
+
+/// Outcome of a branch, identified by its leaf node
+#[derive(Debug, Clone, Serialize)]
+pub struct BranchOutcome {
+    pub leaf: String,
+    /// "completed", "stopped", "skipped" (disabled leaf), "not_taken" (behind a port the run didn't take)
+    /// or "not_reached" (the run stopped before the leaf)
+    pub status: &'static str,
+    pub items: usize,
+    /// Leaf output (last batch when streaming); only kept when the run has several branches
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Vec<Value>>,
+}
+
+/// Node outcomes collected while walking a plan
+#[derive(Debug, Default)]
+pub struct RunTrace {
+    nodes: Vec<NodeTrace>,
+    /// Leaf node IDs in execution order
+    leaves: Vec<String>,
+    branch_data: Vec<(String, Vec<Value>)>,
+}
+
+impl RunTrace {
+    pub fn new(leaves: Vec<String>) -> Self {
+        Self { leaves, ..Self::default() }
+    }
+
+    /// Whether leaf outputs are kept (only runs with several branches need them)
+    fn keeps_branch_data(&self, node_id: &str) -> bool {
+        self.leaves.len() > 1 && self.leaves.iter().any(|leaf| leaf == node_id)
+    }
+
+    /// Record a node's result
+    pub fn record(&mut self, node_id: &str, node_type: &str, result: &ExecutionResult, duration: Duration) {
+        let status = match result.termination {
+            Termination::StoppedEarly { .. } => NodeStatus::Stopped,
+            _ => NodeStatus::Success,
+        };
+        self.push(node_id, node_type, status, result.data.len(), duration);
+        if self.keeps_branch_data(node_id) {
+            match self.branch_data.iter_mut().find(|(leaf, _)| leaf == node_id) {
+                Some((_, data)) => *data = result.data.clone(),
+                None => self.branch_data.push((node_id.to_string(), result.data.clone())),
+            }
+        }
+    }
+
+    /// Record a node without a result of its own (disabled nodes, stream sources)
+    /// 
+    /// A node is only "not taken" if no walk of the run (split ports) executed it.
+    pub fn push(&mut self, node_id: &str, node_type: &str, status: NodeStatus, items: usize, duration: Duration) {
+        let duration_ms = duration.as_millis() as u64;
+        match self.nodes.iter_mut().find(|node| node.node_id == node_id) {
+            Some(_) if status == NodeStatus::NotTaken => {}
+            Some(node) if node.status == NodeStatus::NotTaken => {
+                node.status = status;
+                node.items = items;
+                node.runs = 1;
+                node.duration_ms = duration_ms;
+            }
+            Some(node) => {
+                node.status = status;
+                node.items += items;
+                node.runs += 1;
+                node.duration_ms += duration_ms;
+            }
+            None => self.nodes.push(NodeTrace {
+                node_id: node_id.to_string(),
+                node_type: node_type.to_string(),
+                status,
+                items,
+                runs: 1,
+                duration_ms,
+            }),
+        }
+    }
+
+    /// Per-branch outcomes
+    fn branches(&mut self) -> Vec<BranchOutcome> {
+        let leaves = std::mem::take(&mut self.leaves);
+        leaves.into_iter().map(|leaf| {
+            let node = self.nodes.iter().find(|node| node.node_id == leaf);
+            let status = match node.map(|node| node.status) {
+                None => "not_reached",
+                Some(NodeStatus::Stopped) | Some(NodeStatus::Blocked) => "stopped",
+                Some(NodeStatus::Skipped) => "skipped",
+                Some(NodeStatus::NotTaken) => "not_taken",
+                Some(NodeStatus::Success) => "completed",
+            };
+            let items = node.map_or(0, |node| node.items);
+            let data = self.branch_data.iter()
+                .position(|(id, _)| *id == leaf)
+                .map(|index| self.branch_data.swap_remove(index).1);
+            BranchOutcome { leaf, status, items, data }
+        }).collect()
+    }
+
+    /// JSON form stored in the run's metadata
+    pub fn into_json(mut self) -> Value {
+        let branches = self.branches();
+        serde_json::json!({ "nodes": self.nodes, "branches": branches })
+    }
+}
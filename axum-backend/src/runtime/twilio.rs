@@ -0,0 +1,163 @@
+//! TwilioSend node requests
+//! 
+//! Sends SMS or WhatsApp messages through the Twilio Messages API with the
+//! account SID and auth token from the node's secrets:
+//! 
+//! { "channel": "sms", "from": "+15550100", "to": "$json.phone",
+//!   "body": "Hi {{$json.name}}, order {{$json.order_id}} has shipped" }
+//! 
+//! `to`, `from` and `body` are rendered per input item like Mock templates. A
+//! `from` starting with "MG" is used as a Messaging Service SID. WhatsApp
+//! numbers get the "whatsapp:" prefix automatically. With `await_status_secs`
+//! the node polls the message until it reaches a final delivery status.
+
+use crate::runtime::outbox;
+use anyhow::Result;
+use serde_json::{json, Value};
+
+/// Public Twilio API (overridable with "api_base" for tests)
+const DEFAULT_API_BASE: &str = "https://api.twilio.com";
+
+/// Upper bound for await_status_secs
+const MAX_AWAIT_STATUS_SECS: u64 = 60;
+
+/// Statuses after which a message doesn't change anymore (except read receipts)
+const FINAL_STATUSES: &[&str] = &["delivered", "undelivered", "failed", "read", "canceled"];
+
+/// Messaging channel
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TwilioChannel {
+    Sms,
+    WhatsApp,
+}
+
+/// Parsed TwilioSend node params (templates still unrendered)
+#[derive(Debug, Clone)]
+pub struct TwilioParams {
+    pub channel: TwilioChannel,
+    pub to: Value,
+    pub from: Value,
+    pub body: Value,
+    /// Optional media URL template (MMS / WhatsApp media)
+    pub media_url: Option<Value>,
+    pub status_callback: Option<String>,
+    /// Seconds to wait for a final delivery status (0 = return right after queueing)
+    pub await_status_secs: u64,
+    pub api_base: String,
+}
+
+/// One message to send, rendered for an input item
+#[derive(Debug, Clone)]
+pub struct TwilioMessage {
+    pub to: String,
+    pub from: String,
+    pub body: String,
+    pub media_url: Option<String>,
+}
+
+impl TwilioParams {
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        let required = |name: &str| params.get(name).filter(|v| !v.is_null()).cloned()
+            .ok_or_else(|| anyhow::anyhow!("Missing '{}' parameter", name));
+
+        let channel = match params.get("channel").and_then(|c| c.as_str()).unwrap_or("sms") {
+            "sms" => TwilioChannel::Sms,
+            "whatsapp" => TwilioChannel::WhatsApp,
+            other => return Err(anyhow::anyhow!("Unknown channel '{}' (sms, whatsapp)", other)),
+        };
+
+        Ok(Self {
+            channel,
+            to: required("to")?,
+            from: required("from")?,
+            body: required("body")?,
+            media_url: params.get("media_url").filter(|v| !v.is_null()).cloned(),
+            status_callback: params.get("status_callback").and_then(|s| s.as_str()).map(str::to_string),
+            await_status_secs: params.get("await_status_secs").and_then(|s| s.as_u64()).unwrap_or(0)
+                .min(MAX_AWAIT_STATUS_SECS),
+            api_base: params.get("api_base").and_then(|s| s.as_str()).unwrap_or(DEFAULT_API_BASE).to_string(),
+        })
+    }
+
+    /// Queue a message, optionally waiting for its delivery status
+    pub async fn send(&self, account_sid: &str, auth_token: &str, message: &TwilioMessage) -> Result<Value> {
+        if account_sid.is_empty() || !account_sid.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(anyhow::anyhow!("Account SID secret is not a Twilio SID"));
+        }
+        if message.to.is_empty() {
+            return Err(anyhow::anyhow!("Recipient ('to') is empty"));
+        }
+        if message.body.is_empty() && message.media_url.is_none() {
+            return Err(anyhow::anyhow!("Message body is empty"));
+        }
+
+        let mut form = vec![
+            ("To", self.address(&message.to)),
+            ("Body", message.body.clone()),
+        ];
+        if message.from.starts_with("MG") {
+            form.push(("MessagingServiceSid", message.from.clone()));
+        } else {
+            form.push(("From", self.address(&message.from)));
+        }
+        if let Some(media_url) = &message.media_url {
+            form.push(("MediaUrl", media_url.clone()));
+        }
+        if let Some(callback) = &self.status_callback {
+            form.push(("StatusCallback", callback.clone()));
+        }
+
+        let client = reqwest::Client::new();
+        let url = format!("{}/2010-04-01/Accounts/{}/Messages.json", self.api_base.trim_end_matches('/'), account_sid);
+        let mut resource = twilio_request(client.post(&url).basic_auth(account_sid, Some(auth_token)).form(&form)).await?;
+
+        // DELIVERY STATUS: Poll the message resource until it settles or time runs out
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(self.await_status_secs);
+        while !is_final(&resource) && std::time::Instant::now() < deadline {
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+            let sid = resource.get("sid").and_then(|s| s.as_str()).unwrap_or_default();
+            let message_url = format!("{}/2010-04-01/Accounts/{}/Messages/{}.json",
+                self.api_base.trim_end_matches('/'), account_sid, sid);
+            resource = twilio_request(client.get(&message_url).basic_auth(account_sid, Some(auth_token))).await?;
+        }
+
+        Ok(json!({
+            "sid": resource.get("sid"),
+            "status": resource.get("status"),
+            "channel": match self.channel { TwilioChannel::Sms => "sms", TwilioChannel::WhatsApp => "whatsapp" },
+            "to": resource.get("to"),
+            "from": resource.get("from"),
+            "error_code": resource.get("error_code"),
+            "error_message": resource.get("error_message"),
+            "num_segments": resource.get("num_segments"),
+            "date_created": resource.get("date_created"),
+        }))
+    }
+
+    /// Channel-specific address ("whatsapp:+1555..." for WhatsApp)
+    fn address(&self, number: &str) -> String {
+        match self.channel {
+            TwilioChannel::WhatsApp if !number.starts_with("whatsapp:") => format!("whatsapp:{}", number),
+            _ => number.to_string(),
+        }
+    }
+}
+
+fn is_final(resource: &Value) -> bool {
+    resource.get("status").and_then(|s| s.as_str()).is_some_and(|status| FINAL_STATUSES.contains(&status))
+}
+
+/// Send a Twilio API request, turning error responses into Twilio's code and message
+async fn twilio_request(request: reqwest::RequestBuilder) -> Result<Value> {
+    let response = request.send().await
+        .map_err(|e| outbox::request_error("Twilio request failed", e))?;
+    let status = response.status();
+    let body: Value = response.json().await
+        .map_err(|e| anyhow::anyhow!("Twilio returned invalid JSON ({}): {}", status, e))?;
+    if !status.is_success() {
+        let code = body.get("code").map(|c| c.to_string()).unwrap_or_else(|| "-".to_string());
+        let message = body.get("message").and_then(|m| m.as_str()).unwrap_or("unknown error");
+        return Err(anyhow::anyhow!("Twilio API error {} (code {}): {}", status.as_u16(), code, message));
+    }
+    Ok(body)
+}
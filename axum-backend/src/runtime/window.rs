@@ -0,0 +1,179 @@
+//! Window node batching across executions
+//! 
+//! Buffers incoming items per node in the project DB and only continues the
+//! run when a window closes, e.g. to write high-frequency MQTT readings in
+//! batches:
+//! 
+//! { "count": 100 }                                   tumbling count window
+//! { "count": 100, "slide": 20 }                      sliding: last 100 items every 20 items
+//! { "duration_secs": 60 }                            tumbling time window
+//! { "duration_secs": 300, "slide_secs": 60 }         sliding: last 5 minutes every minute
+//! 
+//! Time windows are aligned to the Unix epoch and use the arrival time of
+//! items. They close on the first execution after their end: items arriving
+//! later start the next window, and an execution started by a CronTrigger of
+//! the same workflow only closes due windows (its item isn't buffered), so a
+//! cron tick flushes windows during quiet periods.
+//! 
+//! Output: the items of the closed windows, or with "output": "windows" one
+//! item per window { "start", "end", "count", "items" }. While no window
+//! closes the run stops after this node with { "window": "open", "buffered": n }.
+
+use crate::project::windows::BufferedItem;
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+/// Default cap on buffered items per node (oldest are dropped beyond it)
+const DEFAULT_MAX_ITEMS: usize = 100_000;
+
+/// Most overlapping windows per item (duration / slide) for sliding time windows
+const MAX_OVERLAP: i64 = 1_000;
+
+/// How a window is delimited
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindowKind {
+    /// Closes every `slide` items with the last `size` items
+    Count { size: usize, slide: usize },
+    /// Epoch-aligned windows of `duration_ms`, one starting every `slide_ms`
+    Time { duration_ms: i64, slide_ms: i64 },
+}
+
+/// Shape of the output of closed windows
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindowOutput {
+    /// The windows' items, window after window
+    Items,
+    /// One summary item per window with its items
+    Windows,
+}
+
+/// Window node configuration
+#[derive(Debug, Clone)]
+pub struct WindowParams {
+    pub kind: WindowKind,
+    pub output: WindowOutput,
+    /// Cap on buffered items (oldest dropped first)
+    pub max_items: usize,
+}
+
+/// Items of one closed window
+#[derive(Debug, Clone)]
+pub struct ClosedWindow {
+    /// Window bounds (Unix milliseconds, time windows only)
+    pub bounds: Option<(i64, i64)>,
+    pub items: Vec<Value>,
+}
+
+impl ClosedWindow {
+    /// Summary item for "output": "windows"
+    pub fn to_item(&self) -> Value {
+        let timestamp = |ms: i64| chrono::DateTime::from_timestamp_millis(ms).map(|t| json!(t.to_rfc3339())).unwrap_or(Value::Null);
+        json!({
+            "start": self.bounds.map(|(start, _)| timestamp(start)).unwrap_or(Value::Null),
+            "end": self.bounds.map(|(_, end)| timestamp(end)).unwrap_or(Value::Null),
+            "count": self.items.len(),
+            "items": self.items,
+        })
+    }
+}
+
+impl WindowParams {
+    /// Parse node params
+    /// 
+    /// Expected params: { "count": 100, "slide": 20 } or { "duration_secs": 60, "slide_secs": 10 },
+    ///                  plus optional { "output": "items" | "windows", "max_items": 100000 }
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        let positive = |name: &str| -> Result<Option<u64>> {
+            match params.get(name) {
+                None | Some(Value::Null) => Ok(None),
+                Some(value) => value.as_u64().filter(|n| *n > 0)
+                    .map(Some)
+                    .ok_or_else(|| anyhow::anyhow!("'{}' must be a positive integer", name)),
+            }
+        };
+
+        let kind = match (positive("count")?, positive("duration_secs")?) {
+            (Some(size), None) => {
+                let slide = positive("slide")?.unwrap_or(size);
+                if slide > size {
+                    return Err(anyhow::anyhow!("'slide' ({}) can't be larger than 'count' ({})", slide, size));
+                }
+                WindowKind::Count { size: size as usize, slide: slide as usize }
+            }
+            (None, Some(duration)) => {
+                let slide = positive("slide_secs")?.unwrap_or(duration);
+                if slide > duration {
+                    return Err(anyhow::anyhow!("'slide_secs' ({}) can't be larger than 'duration_secs' ({})", slide, duration));
+                }
+                if duration.div_ceil(slide) as i64 > MAX_OVERLAP {
+                    return Err(anyhow::anyhow!("'duration_secs' / 'slide_secs' must be at most {}", MAX_OVERLAP));
+                }
+                WindowKind::Time { duration_ms: duration as i64 * 1000, slide_ms: slide as i64 * 1000 }
+            }
+            (Some(_), Some(_)) => return Err(anyhow::anyhow!("set either 'count' or 'duration_secs', not both")),
+            (None, None) => return Err(anyhow::anyhow!("missing window size ('count' or 'duration_secs')")),
+        };
+
+        let output = match params.get("output").and_then(|o| o.as_str()).unwrap_or("items") {
+            "items" => WindowOutput::Items,
+            "windows" => WindowOutput::Windows,
+            other => return Err(anyhow::anyhow!("unsupported output '{}' (expected items or windows)", other)),
+        };
+
+        let max_items = positive("max_items")?.map(|n| n as usize).unwrap_or(DEFAULT_MAX_ITEMS);
+        if let WindowKind::Count { size, .. } = kind {
+            if max_items < size {
+                return Err(anyhow::anyhow!("'max_items' ({}) must be at least 'count' ({})", max_items, size));
+            }
+        }
+
+        Ok(Self { kind, output, max_items })
+    }
+}
+
+/// End of the latest time window closed at `now` (all windows ending later are still open)
+pub fn closed_until(now: i64, duration_ms: i64, slide_ms: i64) -> i64 {
+    (now - duration_ms).div_euclid(slide_ms) * slide_ms + duration_ms
+}
+
+/// Arrival time before which items belong to closed windows only
+pub fn retention_cutoff(now: i64, duration_ms: i64, slide_ms: i64) -> i64 {
+    ((now - duration_ms).div_euclid(slide_ms) + 1) * slide_ms
+}
+
+/// Group items into the time windows ending in (`previously_closed`, `closed_until`]
+/// 
+/// Windows without items are left out; windows come out oldest first.
+pub fn close_time_windows(items: &[BufferedItem], duration_ms: i64, slide_ms: i64, previously_closed: i64, closed_until: i64) -> Vec<ClosedWindow> {
+    let mut windows: BTreeMap<i64, Vec<Value>> = BTreeMap::new();
+    for buffered in items {
+        // Windows [k * slide, k * slide + duration) containing the item
+        let first = (buffered.received_at - duration_ms).div_euclid(slide_ms) + 1;
+        let last = buffered.received_at.div_euclid(slide_ms);
+        for k in first..=last {
+            let end = k * slide_ms + duration_ms;
+            if end > previously_closed && end <= closed_until {
+                windows.entry(k).or_default().push(buffered.item.clone());
+            }
+        }
+    }
+    windows.into_iter()
+        .map(|(k, items)| ClosedWindow { bounds: Some((k * slide_ms, k * slide_ms + duration_ms)), items })
+        .collect()
+}
+
+/// Per-node locks so concurrent executions don't close the same window twice
+#[derive(Debug, Default)]
+pub struct WindowLocks {
+    locks: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl WindowLocks {
+    /// Wait for exclusive access to a node's buffer
+    pub async fn lock(&self, key: String) -> tokio::sync::OwnedMutexGuard<()> {
+        let lock = self.locks.lock().unwrap().entry(key).or_default().clone();
+        lock.lock_owned().await
+    }
+}
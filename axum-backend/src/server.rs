@@ -1,20 +1,58 @@
-/// Server setup and initialization
-/// 
-/// Wires together all components: storage, registry, execution engine, and HTTP routes.
-/// Provides the main application factory function for creating the Axum app.
+//! Server setup and initialization
+//! 
+//! Wires together all components: storage, registry, execution engine, and HTTP routes.
+//! Provides the main application factory function for creating the Axum app.
 
 use crate::{
     api::{
+        admin::{create_admin_routes, reject_during_maintenance, reject_writes},
+        chat::create_chat_routes,
+        mcp::create_mcp_routes,
+        websocket::create_websocket_routes,
+        stats::create_stats_routes,
+        debug::create_debug_routes,
+        executions::create_execution_routes,
+        cron::create_cron_routes,
+        expressions::create_expression_routes,
+        forms::create_form_routes,
+        metrics::create_metrics_routes,
+        oauth::create_oauth_routes,
+        secrets::create_secret_routes,
+        projects::create_project_routes,
+        templates::create_template_routes,
         webhooks::{register_webhook_routes_for_workflows, WebhookAppState},
         workflows::{create_workflow_routes, AppState},
     },
     config::Config,
-    project::ProjectDatabaseManager,
-    runtime::{engine::ExecutionEngine, executor::NodeExecutor, scheduler::CronSchedulerService},
+    logging::init_logging,
+    project::{compression::StorageCompression, crypto::MasterKey, pg_pool::ExternalPgPoolManager, ProjectDatabaseManager},
+    runtime::{
+        alerts::AlertMonitor,
+        budget::ResourceLimits,
+        debugger::DebugSessionManager,
+        devices::DeviceRegistry,
+        engine::ExecutionEngine,
+        janitor::RetentionJanitor,
+        outbox::OutboxFlusher,
+        maintenance::DatabaseMaintenance,
+        maintenance_mode::MaintenanceMode,
+        events::{EventBus, EventTriggerService},
+        executor::NodeExecutor,
+        mqtt::MqttTriggerService,
+        opcua_trigger::OpcUaTriggerService,
+        serial_trigger::SerialTriggerService,
+        ble::BleTriggerService,
+        profiler::ExecutionProfiler,
+        queue::ExecutionQueue,
+        recovery::recover_interrupted_executions,
+        scheduler::CronSchedulerService,
+        spill::cleanup_stale_spills,
+    },
     workflow::{registry::WorkflowRegistry, storage::WorkflowStorage},
 };
 use anyhow::Result;
 use axum::{
+    middleware,
     routing::get,
     Router,
 };
@@ -32,17 +70,56 @@ pub async fn create_app(config: Config) -> Result<Router> {
     std::fs::create_dir_all(&config.database.project_data_dir)
         .map_err(|e| anyhow::anyhow!("Failed to create project data directory: {}", e))?;
 
+    // Follower instances read the databases of a primary sharing the data directory
+    let follower = config.database.follower;
+    if follower {
+        if config.database.in_memory {
+            return Err(anyhow::anyhow!("MECHAWAY_FOLLOWER needs the primary's database files (MECHAWAY_IN_MEMORY_DB is set)"));
+        }
+        tracing::info!("📖 Follower mode: project databases are opened read-only, triggers and background writers stay off");
+    } else {
+        // Spill files from a previous process are never rehydrated (a follower would delete the primary's)
+        cleanup_stale_spills(&config.database.project_data_dir);
+    }
+
     // Initialize project database manager for isolated multi-tenant storage
     tracing::info!("🏗️ Initializing project database manager");
     let data_dir = config.database.project_data_dir.clone();
     tracing::debug!("📁 Project data directory: {}", data_dir);
-    let project_db_manager = Arc::new(ProjectDatabaseManager::new(data_dir));
+    let pg_pools = Arc::new(ExternalPgPoolManager::new(
+        config.database.external_pg_max_pools,
+        config.database.external_pg_pool_size,
+        std::time::Duration::from_secs(config.database.external_pg_idle_secs),
+    ));
+    pg_pools.start_eviction();
+    let master_key = match config.security.master_key.as_deref().map(MasterKey::parse).transpose() {
+        Ok(Some(key)) => Some(Arc::new(key)),
+        Ok(None) => {
+            tracing::warn!("🔐 MECHAWAY_MASTER_KEY is not set - secrets and OAuth credentials are disabled");
+            None
+        }
+        Err(e) => return Err(anyhow::anyhow!("Invalid MECHAWAY_MASTER_KEY: {}", e)),
+    };
+    let storage_compression = StorageCompression::from_config(&config.database.storage_compression);
+    let project_db_manager = Arc::new(if config.database.in_memory {
+        tracing::warn!("🧪 Using in-memory project databases - data will not be persisted");
+        ProjectDatabaseManager::new_in_memory(data_dir)
+    } else {
+        ProjectDatabaseManager::new(data_dir)
+    }.with_pg_pools(pg_pools)
+        .with_master_key(master_key)
+        .with_compression(storage_compression)
+        .with_read_only(follower));
+    
+    // Sealed $secret. values of every project, resolved synchronously by nodes
+    tracing::info!("🔐 Loaded {} stored secrets", project_db_manager.load_secrets().await);
     
     // Initialize workflow storage using default project database
     tracing::info!("📋 Initializing workflow storage (default project)");
     let default_project_pool = project_db_manager.get_project_pool("default").await
         .map_err(|e| anyhow::anyhow!("Failed to get default project database: {}", e))?;
-    let workflow_storage = WorkflowStorage::new(default_project_pool);
+    let workflow_storage = WorkflowStorage::new(default_project_pool.clone())
+        .with_compression(storage_compression);
 
     // Initialize workflow registry and load existing workflows
     tracing::info!("📊 Initializing workflow registry");
@@ -51,15 +128,61 @@ pub async fn create_app(config: Config) -> Result<Router> {
     tracing::info!("📥 Loading existing workflows from storage");
     workflow_registry.init_from_storage().await
         .map_err(|e| anyhow::anyhow!("Failed to load workflows from storage: {}", e))?;
+    if follower {
+        Arc::clone(&workflow_registry).start_periodic_reload(config.database.follower_refresh_secs);
+    }
     
     // Initialize execution components
     tracing::info!("⚙️ Initializing node executor with project isolation");
-    let node_executor = NodeExecutor::new(Arc::clone(&project_db_manager))
-        .map_err(|e| anyhow::anyhow!("Failed to initialize node executor: {}", e))?;
+    let event_bus = Arc::new(EventBus::new());
+
+    // Device states (digital twins), seeded from the project databases
+    let devices = Arc::new(DeviceRegistry::new(Arc::clone(&project_db_manager)));
+    tracing::info!("📟 Loaded {} stored device states", devices.load().await);
+    if !follower {
+        Arc::clone(&devices).start();
+    }
+
+    let node_executor = NodeExecutor::new(Arc::clone(&project_db_manager), Arc::clone(&event_bus))
+        .map_err(|e| anyhow::anyhow!("Failed to initialize node executor: {}", e))?
+        .with_devices(Arc::clone(&devices));
+    
+    // Execution profiling, seeded from the last persisted snapshot
+    let profiler = Arc::new(ExecutionProfiler::new());
+    match profiler.load(&default_project_pool).await {
+        Ok(loaded) => tracing::info!("⏱️ Loaded {} persisted profiling entries", loaded),
+        Err(e) => tracing::warn!("⚠️ Failed to load profiling stats: {}", e),
+    }
+    if config.execution.stats_persist_interval_secs > 0 && !follower {
+        profiler.start_persistence(
+            default_project_pool.clone(),
+            std::time::Duration::from_secs(config.execution.stats_persist_interval_secs),
+        );
+    }
     
     tracing::info!("🚀 Initializing execution engine");
     let node_executor_arc = Arc::new(node_executor);
-    let execution_engine = Arc::new(ExecutionEngine::new(Arc::clone(&node_executor_arc)));
+    let maintenance_mode = Arc::new(MaintenanceMode::new(config.execution.retry_after_secs));
+    let execution_engine = Arc::new(ExecutionEngine::new(
+        Arc::clone(&node_executor_arc),
+        Arc::clone(&workflow_registry),
+        config.execution.spill_threshold_bytes,
+    ).with_history(config.execution.record_history)
+        .with_profiler(Arc::clone(&profiler))
+        .with_callbacks()
+        .with_maintenance_mode(Arc::clone(&maintenance_mode))
+        .with_resource_limits(ResourceLimits {
+            max_memory_bytes: config.execution.max_run_memory_bytes,
+            max_cpu_ms: config.execution.max_run_cpu_ms,
+        }));
+
+    // Bounded execution queue for backpressure at trigger ingress
+    tracing::info!("🚦 Initializing execution queue (max concurrent: {}, max depth: {})",
+        config.execution.max_concurrent_executions, config.execution.max_queue_depth);
+    let execution_queue = Arc::new(ExecutionQueue::new(
+        Arc::clone(&execution_engine),
+        config.execution.clone(),
+    ));
 
     // Initialize cron scheduler service  
     tracing::info!("⏰ Initializing cron scheduler service");
@@ -67,19 +190,93 @@ pub async fn create_app(config: Config) -> Result<Router> {
         CronSchedulerService::new(
             Arc::clone(&workflow_registry),
             Arc::clone(&node_executor_arc), 
-            Arc::clone(&execution_engine)
+            Arc::clone(&execution_engine),
+            Arc::clone(&execution_queue),
         ).await
         .map_err(|e| anyhow::anyhow!("Failed to initialize cron scheduler: {}", e))?
+        .with_spread(config.execution.schedule_spread_secs)
     );
 
-    // Start the cron scheduler in background
-    tracing::info!("🚀 Starting cron scheduler");
-    let scheduler_clone = Arc::clone(&cron_scheduler);
-    tokio::spawn(async move {
-        if let Err(e) = scheduler_clone.start().await {
-            tracing::error!("❌ Failed to start cron scheduler: {}", e);
+    // Trigger and background services (started below on primaries only)
+    let mqtt_triggers = Arc::new(MqttTriggerService::new(
+        Arc::clone(&workflow_registry),
+        Arc::clone(&node_executor_arc),
+        Arc::clone(&execution_engine),
+        Arc::clone(&devices),
+    ));
+    let opcua_triggers = Arc::new(OpcUaTriggerService::new(
+        Arc::clone(&workflow_registry),
+        Arc::clone(&node_executor_arc),
+        Arc::clone(&execution_engine),
+    ));
+    let serial_triggers = Arc::new(SerialTriggerService::new(
+        Arc::clone(&workflow_registry),
+        Arc::clone(&execution_engine),
+        node_executor_arc.serial_ports().clone(),
+    ));
+    let ble_triggers = Arc::new(BleTriggerService::new(
+        Arc::clone(&workflow_registry),
+        Arc::clone(&execution_engine),
+        Arc::clone(&devices),
+    ));
+    let janitor = Arc::new(RetentionJanitor::new(Arc::clone(&project_db_manager), config.maintenance.clone()));
+    let outbox = Arc::new(OutboxFlusher::new(
+        Arc::clone(&workflow_registry),
+        Arc::clone(&node_executor_arc),
+        Arc::clone(&project_db_manager),
+        config.execution.outbox_flush_interval_secs,
+    ));
+    let maintenance = Arc::new(DatabaseMaintenance::new(Arc::clone(&project_db_manager), config.maintenance.clone()));
+
+    // Followers only serve reads: the primary runs every trigger and owns the databases
+    if !follower {
+        // Executions a previous process left running are marked interrupted (and resumed
+        // where the workflow opted in) before any trigger starts
+        let recovery = recover_interrupted_executions(&project_db_manager, &workflow_registry, &execution_queue).await;
+        if recovery.interrupted > 0 {
+            tracing::warn!("💥 Recovered {} interrupted execution(s), {} resumed", recovery.interrupted, recovery.resumed);
         }
-    });
+
+        // Start the internal event trigger service (EmitEvent -> EventTrigger)
+        tracing::info!("📣 Starting event trigger service");
+        EventTriggerService::new(
+            Arc::clone(&event_bus),
+            Arc::clone(&workflow_registry),
+            Arc::clone(&execution_engine),
+        ).start();
+
+        // Start the cron scheduler in background
+        tracing::info!("🚀 Starting cron scheduler");
+        let scheduler_clone = Arc::clone(&cron_scheduler);
+        tokio::spawn(async move {
+            if let Err(e) = scheduler_clone.start().await {
+                tracing::error!("❌ Failed to start cron scheduler: {}", e);
+            }
+        });
+
+        // Start the MQTT, OPC UA, serial port and BLE trigger services
+        mqtt_triggers.start().await;
+        opcua_triggers.start().await;
+        serial_triggers.start().await;
+        ble_triggers.start().await;
+
+        // Start the failure alert monitor (rules come from project settings)
+        tracing::info!("🚨 Starting alert monitor");
+        Arc::new(AlertMonitor::new(
+            Arc::clone(&workflow_registry),
+            Arc::clone(&project_db_manager),
+            config.alerts.clone(),
+        )).start();
+
+        // Start the retention janitor (limits come from project settings)
+        Arc::clone(&janitor).start();
+
+        // Start the outbox flusher (replays store-and-forward nodes parked while offline)
+        Arc::clone(&outbox).start();
+
+        // Start SQLite maintenance (runs inside the configured window)
+        Arc::clone(&maintenance).start();
+    }
 
     // Create application states
     tracing::info!("🏗️ Creating application states");
@@ -87,6 +284,20 @@ pub async fn create_app(config: Config) -> Result<Router> {
         storage: workflow_storage,
         registry: workflow_registry.clone(),
         scheduler: Arc::clone(&cron_scheduler),
+        mqtt: mqtt_triggers,
+        opcua: opcua_triggers,
+        serial: serial_triggers,
+        ble: ble_triggers,
+        devices,
+        project_db_manager: Arc::clone(&project_db_manager),
+        queue: execution_queue,
+        debugger: Arc::new(DebugSessionManager::new(Arc::clone(&execution_engine))),
+        profiler,
+        janitor,
+        maintenance,
+        maintenance_mode,
+        outbox,
+        started_at: std::time::Instant::now(),
     };
 
     let webhook_state = WebhookAppState {
@@ -96,7 +307,27 @@ pub async fn create_app(config: Config) -> Result<Router> {
 
     // Build webhook routes (dynamically registered based on active workflows)
     tracing::info!("🔗 Registering webhook routes");
-    let webhook_routes = register_webhook_routes_for_workflows(&*workflow_registry).await;
+    let webhook_routes = register_webhook_routes_for_workflows(&workflow_registry).await;
+
+    // Trigger ingress, rejected with 503 while the server is in maintenance mode
+    let ingress_routes = Router::new()
+        // Hosted FormTrigger forms
+        .merge(create_form_routes().with_state(webhook_state.clone()))
+        
+        // OpenAI-compatible chat completions (ChatTrigger)
+        .merge(create_chat_routes().with_state(webhook_state.clone()))
+        
+        // WebSocketTrigger connections
+        .merge(create_websocket_routes().with_state(webhook_state.clone()))
+        
+        // MCP server endpoint (MCPTrigger tools)
+        .merge(create_mcp_routes().with_state(webhook_state.clone()))
+        
+        // Dynamic webhook execution routes  
+        .merge(webhook_routes.with_state(webhook_state))
+        .layer(middleware::from_fn_with_state(app_state.clone(), reject_during_maintenance));
+    // Followers don't run workflows, so they serve no trigger ingress
+    let ingress_routes = if follower { Router::new() } else { ingress_routes };
 
     // Create the main application router
     tracing::info!("📡 Creating HTTP router with all endpoints");
@@ -105,10 +336,45 @@ pub async fn create_app(config: Config) -> Result<Router> {
         .route("/healthz", get(health_check))
         
         // Workflow management API routes
-        .merge(create_workflow_routes().with_state(app_state))
+        .merge(create_workflow_routes().with_state(app_state.clone()))
         
-        // Dynamic webhook execution routes  
-        .merge(webhook_routes.with_state(webhook_state));
+        // Project settings API routes
+        .merge(create_project_routes().with_state(app_state.clone()))
+        
+        // Execution history and replay routes
+        .merge(create_execution_routes().with_state(app_state.clone()))
+        
+        // Step-through debugger routes
+        .merge(create_debug_routes().with_state(app_state.clone()))
+        
+        // Runtime metrics routes
+        .merge(create_metrics_routes().with_state(app_state.clone()))
+        
+        // Execution profiling routes
+        .merge(create_stats_routes().with_state(app_state.clone()))
+        
+        // OAuth2 credential routes (authorization flow, provider callback)
+        .merge(create_oauth_routes().with_state(app_state.clone()))
+        
+        // Project secret routes ($secret. pin values)
+        .merge(create_secret_routes().with_state(app_state.clone()))
+        
+        // Expression preview routes
+        .merge(create_expression_routes().with_state(app_state.clone()))
+        
+        // Cron schedule preview routes
+        .merge(create_cron_routes().with_state(app_state.clone()))
+        
+        // Workflow template gallery routes
+        .merge(create_template_routes().with_state(app_state.clone()))
+        
+        // Administrative routes (log level, maintenance mode)
+        .merge(create_admin_routes().with_state(app_state))
+        
+        // Webhooks, forms, chat, WebSocket and MCP triggers
+        .merge(ingress_routes);
+    // Followers answer reads only
+    let app = if follower { app.layer(middleware::from_fn(reject_writes)) } else { app };
 
     tracing::info!("✅ Application initialized successfully");
     
@@ -119,12 +385,8 @@ pub async fn create_app(config: Config) -> Result<Router> {
 /// 
 /// Creates the application and starts the Axum server on the configured address and port.
 pub async fn start_server(config: Config) -> Result<()> {
-    // Initialize tracing subscriber for logging
-    tracing_subscriber::fmt()
-        .with_target(false)
-        .with_thread_ids(true)
-        .with_level(true)
-        .init();
+    // Initialize tracing subscriber; the guard flushes file logs on shutdown
+    let _log_guard = init_logging(&config.logging)?;
 
     tracing::info!("Starting Mechaway server...");
     
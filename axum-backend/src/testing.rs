@@ -0,0 +1,159 @@
+//! Integration test fixtures
+//! 
+//! Spins up the full application in-process (`create_app`) and talks to the
+//! Axum router directly, so integration tests need neither a network port nor a
+//! database on disk:
+//! 
+//! ```no_run
+//! # use axum::http::StatusCode;
+//! # use serde_json::json;
+//! # async fn example() -> anyhow::Result<()> {
+//! let app = mechaway::testing::TestApp::spawn().await?;
+//! app.create_workflow(json!({
+//!     "id": "wf-echo",
+//!     "name": "Echo",
+//!     "nodes": [{ "id": "hook", "node_type": "Webhook", "params": { "path": "/echo" } }],
+//!     "edges": []
+//! })).await?;
+//! let response = app.webhook("wf-echo", "/echo", json!({ "hello": "world" })).await?;
+//! assert_eq!(response.status, StatusCode::OK);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{config::Config, server::create_app};
+use anyhow::Result;
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use serde_json::Value;
+use std::{
+    ops::Deref,
+    path::{Path, PathBuf},
+};
+use tower::ServiceExt;
+
+/// Configuration for in-process tests
+/// 
+/// In-memory SQLite for every project, working files under `data_dir`, and no
+/// load shedding so bursts of test requests are never rejected.
+pub fn test_config(data_dir: &Path) -> Config {
+    let mut config = Config::default();
+    config.database.project_data_dir = data_dir.to_string_lossy().into_owned();
+    config.database.in_memory = true;
+    config.execution.throttle_queue_depth = usize::MAX;
+    config.execution.max_queue_depth = usize::MAX;
+    config.alerts.check_interval_secs = 0;
+    config.maintenance.janitor_interval_secs = 0;
+    config.maintenance.db_interval_secs = 0;
+    config
+}
+
+/// Fresh temporary data directory path (created lazily by the app)
+pub fn temp_data_dir(prefix: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("{}-{}", prefix, uuid::Uuid::new_v4()))
+}
+
+/// Response captured from the in-process router
+#[derive(Debug, Clone)]
+pub struct TestResponse {
+    pub status: StatusCode,
+    /// Parsed JSON body (Value::Null for empty or non-JSON bodies)
+    pub body: Value,
+}
+
+/// Cloneable request handle for an in-process application
+#[derive(Clone)]
+pub struct TestClient {
+    router: Router,
+}
+
+impl TestClient {
+    /// Send a request with an optional JSON body
+    pub async fn request(&self, method: &str, uri: &str, body: Option<&Value>) -> Result<TestResponse> {
+        let mut builder = Request::builder().method(method).uri(uri);
+        let body = match body {
+            Some(json) => {
+                builder = builder.header("content-type", "application/json");
+                Body::from(serde_json::to_vec(json)?)
+            }
+            None => Body::empty(),
+        };
+
+        let response = self.router.clone().oneshot(builder.body(body)?).await?;
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await?;
+        let body = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+
+        Ok(TestResponse { status, body })
+    }
+
+    /// Register a workflow through the workflow API
+    /// 
+    /// Fails unless the API accepts it, so tests stop at the setup step.
+    pub async fn create_workflow(&self, workflow: Value) -> Result<()> {
+        let payload = serde_json::json!({ "workflow": workflow });
+        let response = self.request("POST", "/api/workflows", Some(&payload)).await?;
+        if !response.status.is_success() {
+            return Err(anyhow::anyhow!("Failed to create workflow {}: {}", payload["workflow"]["id"], response.status));
+        }
+        Ok(())
+    }
+
+    /// Fire a webhook: POST /webhook/{workflow_id}{path}
+    pub async fn webhook(&self, workflow_id: &str, path: &str, body: Value) -> Result<TestResponse> {
+        let uri = format!("/webhook/{}/{}", workflow_id, path.trim_start_matches('/'));
+        self.request("POST", &uri, Some(&body)).await
+    }
+}
+
+/// In-process application for integration tests
+/// 
+/// Derefs to `TestClient`. The data directory is removed on drop.
+pub struct TestApp {
+    client: TestClient,
+    data_dir: PathBuf,
+}
+
+impl TestApp {
+    /// Start an application with in-memory databases and a temp data directory
+    pub async fn spawn() -> Result<Self> {
+        let data_dir = temp_data_dir("mechaway-test");
+        Self::spawn_with(test_config(&data_dir)).await
+    }
+
+    /// Start an application with a custom configuration
+    /// 
+    /// `config.database.project_data_dir` is owned by the app and removed on drop.
+    pub async fn spawn_with(config: Config) -> Result<Self> {
+        let data_dir = PathBuf::from(&config.database.project_data_dir);
+        let router = create_app(config).await?;
+        Ok(Self { client: TestClient { router }, data_dir })
+    }
+
+    /// Cloneable request handle (e.g. for concurrent requests from spawned tasks)
+    pub fn client(&self) -> TestClient {
+        self.client.clone()
+    }
+
+    /// Data directory used by this app (uploads, spill files)
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+}
+
+impl Deref for TestApp {
+    type Target = TestClient;
+
+    fn deref(&self) -> &TestClient {
+        &self.client
+    }
+}
+
+impl Drop for TestApp {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.data_dir);
+    }
+}
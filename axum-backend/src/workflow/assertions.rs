@@ -0,0 +1,197 @@
+//! JSONPath assertions over a data array
+//! 
+//! Shared by workflow test cases (checked against the final output) and Assert
+//! nodes (checked against the data flowing through the node):
+//! 
+//! ```json
+//! { "path": "$[0].status", "equals": "paid" }
+//! { "path": "$[*].amount", "all": true, "greater_than": 0 }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// A JSONPath selector plus one or more checks
+/// 
+/// Paths are evaluated against the data array, so `$[0].total` is the `total`
+/// field of the first item. Checks apply to the first match, or to every match
+/// with `"all": true`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Assertion {
+    pub path: String,
+    /// Apply the value checks to every match instead of only the first
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub all: bool,
+    /// Value equals this value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub equals: Option<Value>,
+    /// Value differs from this value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_equals: Option<Value>,
+    /// Value contains this value (substring, array element or object subset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contains: Option<Value>,
+    /// Path matches (true) or doesn't match (false) anything
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exists: Option<bool>,
+    /// Value is an array/string/object of this length
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub length: Option<usize>,
+    /// Value is a number greater than this
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub greater_than: Option<f64>,
+    /// Value is a number less than this
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub less_than: Option<f64>,
+}
+
+/// Outcome of one assertion
+#[derive(Debug, Clone, Serialize)]
+pub struct AssertionResult {
+    pub path: String,
+    pub passed: bool,
+    /// First value matched by the path (null when nothing matched)
+    pub actual: Value,
+    /// Failure description, absent when the assertion passed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Structured error raised by an Assert node whose conditions failed
+#[derive(Debug, Clone, Serialize)]
+pub struct AssertionError {
+    pub node_id: String,
+    pub message: String,
+    /// Only the failed conditions
+    pub failures: Vec<AssertionResult>,
+}
+
+impl AssertionError {
+    /// JSON body for API error responses
+    pub fn to_json(&self) -> Value {
+        json!({
+            "error": "assertion_failed",
+            "node_id": self.node_id,
+            "message": self.message,
+            "failures": self.failures,
+        })
+    }
+}
+
+impl std::fmt::Display for AssertionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let details: Vec<String> = self.failures.iter()
+            .map(|r| format!("{}: {}", r.path, r.message.as_deref().unwrap_or("failed")))
+            .collect();
+        write!(f, "Assertion failed in '{}': {} ({})", self.node_id, self.message, details.join("; "))
+    }
+}
+
+impl std::error::Error for AssertionError {}
+
+impl Assertion {
+    /// Evaluate this assertion against a data array (or any JSON document)
+    pub fn evaluate(&self, output: &Value) -> AssertionResult {
+        let matches = match jsonpath_lib::select(output, &self.path) {
+            Ok(matches) => matches,
+            Err(e) => return self.result(Value::Null, Some(format!("Invalid JSONPath '{}': {}", self.path, e))),
+        };
+        let actual = matches.first().map(|v| (*v).clone());
+        let mut failures = Vec::new();
+
+        if let Some(expected) = self.exists {
+            if actual.is_some() != expected {
+                failures.push(if expected { "expected path to exist".to_string() } else { "expected path not to exist".to_string() });
+            }
+        }
+
+        let checks_value = self.equals.is_some() || self.not_equals.is_some() || self.contains.is_some()
+            || self.length.is_some() || self.greater_than.is_some() || self.less_than.is_some();
+        if checks_value {
+            if matches.is_empty() {
+                failures.push("path matched nothing".to_string());
+            }
+            let checked = if self.all { matches.len() } else { matches.len().min(1) };
+            for (index, value) in matches.iter().take(checked).enumerate() {
+                let value_failures = self.check_value(value);
+                if !value_failures.is_empty() {
+                    // With "all", report the first offending match only
+                    let prefix = if self.all { format!("match {}: ", index) } else { String::new() };
+                    failures.push(format!("{}{}", prefix, value_failures.join("; ")));
+                    break;
+                }
+            }
+        }
+
+        let message = (!failures.is_empty()).then(|| failures.join("; "));
+        self.result(actual.unwrap_or(Value::Null), message)
+    }
+
+    /// Value checks (everything except `exists`) for a single match
+    fn check_value(&self, actual: &Value) -> Vec<String> {
+        let mut failures = Vec::new();
+
+        if let Some(expected) = &self.equals {
+            if actual != expected {
+                failures.push(format!("expected {} to equal {}", actual, expected));
+            }
+        }
+        if let Some(unexpected) = &self.not_equals {
+            if actual == unexpected {
+                failures.push(format!("expected value to differ from {}", unexpected));
+            }
+        }
+        if let Some(needle) = &self.contains {
+            if !contains(actual, needle) {
+                failures.push(format!("expected {} to contain {}", actual, needle));
+            }
+        }
+        if let Some(expected) = self.length {
+            match value_length(actual) {
+                Some(len) if len == expected => {}
+                Some(len) => failures.push(format!("expected length {}, got {}", expected, len)),
+                None => failures.push(format!("expected length {}, but {} has no length", expected, actual)),
+            }
+        }
+        if let Some(bound) = self.greater_than {
+            if !actual.as_f64().is_some_and(|n| n > bound) {
+                failures.push(format!("expected {} to be greater than {}", actual, bound));
+            }
+        }
+        if let Some(bound) = self.less_than {
+            if !actual.as_f64().is_some_and(|n| n < bound) {
+                failures.push(format!("expected {} to be less than {}", actual, bound));
+            }
+        }
+
+        failures
+    }
+
+    fn result(&self, actual: Value, message: Option<String>) -> AssertionResult {
+        AssertionResult {
+            path: self.path.clone(),
+            passed: message.is_none(),
+            actual,
+            message,
+        }
+    }
+}
+
+/// Substring, array element or object subset containment
+fn contains(haystack: &Value, needle: &Value) -> bool {
+    match (haystack, needle) {
+        (Value::String(h), Value::String(n)) => h.contains(n.as_str()),
+        (Value::Array(items), needle) => items.iter().any(|item| item == needle),
+        (Value::Object(h), Value::Object(n)) => n.iter().all(|(k, v)| h.get(k) == Some(v)),
+        _ => false,
+    }
+}
+
+fn value_length(value: &Value) -> Option<usize> {
+    match value {
+        Value::Array(items) => Some(items.len()),
+        Value::String(s) => Some(s.chars().count()),
+        Value::Object(map) => Some(map.len()),
+        _ => None,
+    }
+}
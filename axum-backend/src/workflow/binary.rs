@@ -0,0 +1,130 @@
+//! Binary data items for the execution context
+//! 
+//! Data items stay JSON (`Vec<Value>`), but binary payloads no longer need to be
+//! base64-encoded into them. An item carries a small `$binary` reference instead:
+//! 
+//! { "$binary": { "id": "...", "filename": "report.pdf", "content_type": "application/pdf", "size": 48213 } }
+//! 
+//! The bytes live either in the execution's `BinaryStore` (in-memory, shared by
+//! all nodes of one execution) or in a project-scoped file when `path` is set.
+//! Uploaded files (`$file.*` pins) are accepted wherever a reference is expected.
+
+use anyhow::Result;
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use crate::workflow::types::FileInfo;
+
+/// JSON key that marks a binary reference inside a data item
+pub const BINARY_KEY: &str = "$binary";
+
+/// Reference to binary content flowing between nodes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BinaryRef {
+    /// Binary identifier within the execution's store
+    pub id: String,
+    /// Original or suggested filename
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+    /// MIME type (e.g., "image/png")
+    pub content_type: String,
+    /// Content size in bytes
+    pub size: u64,
+    /// Local file path (project-scoped) for file-backed content
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+impl BinaryRef {
+    /// Reference for file-backed content (no bytes held in memory)
+    pub fn from_file(path: String, filename: Option<String>, content_type: String, size: u64) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            filename,
+            content_type,
+            size,
+            path: Some(path),
+        }
+    }
+
+    /// Wrap the reference as a data item: { "$binary": { ... } }
+    pub fn to_item(&self) -> Value {
+        json!({ BINARY_KEY: self })
+    }
+
+    /// Extract a binary reference from a pin value or data item
+    /// 
+    /// Accepts `{ "$binary": {...} }` items, bare references, and uploaded
+    /// file objects produced by `$file.*` pins.
+    pub fn from_value(value: &Value) -> Option<Self> {
+        let object = value.as_object()?;
+        if let Some(inner) = object.get(BINARY_KEY) {
+            return serde_json::from_value(inner.clone()).ok();
+        }
+        if object.contains_key("id") && object.contains_key("content_type") {
+            return serde_json::from_value(value.clone()).ok();
+        }
+        // Uploaded file info: { filename, content_type, size, path }
+        serde_json::from_value::<FileInfo>(value.clone()).ok().map(|file| Self::from(&file))
+    }
+}
+
+impl From<&FileInfo> for BinaryRef {
+    fn from(file: &FileInfo) -> Self {
+        Self::from_file(file.path.clone(), Some(file.filename.clone()), file.content_type.clone(), file.size)
+    }
+}
+
+/// Execution-scoped in-memory store for binary content
+/// 
+/// Cloning is cheap and shares the same store, so every node of an execution
+/// (and its sub-workflows) sees the same binaries. `Bytes` is reference-counted,
+/// so reading content never copies it.
+#[derive(Clone, Default)]
+pub struct BinaryStore {
+    entries: Arc<RwLock<HashMap<String, Bytes>>>,
+}
+
+impl std::fmt::Debug for BinaryStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let count = self.entries.read().map(|e| e.len()).unwrap_or(0);
+        f.debug_struct("BinaryStore").field("entries", &count).finish()
+    }
+}
+
+impl BinaryStore {
+    /// Store in-memory content and return its reference
+    pub fn put(&self, data: Bytes, filename: Option<String>, content_type: String) -> BinaryRef {
+        let reference = BinaryRef {
+            id: uuid::Uuid::new_v4().to_string(),
+            filename,
+            content_type,
+            size: data.len() as u64,
+            path: None,
+        };
+        if let Ok(mut entries) = self.entries.write() {
+            entries.insert(reference.id.clone(), data);
+        }
+        reference
+    }
+
+    /// Load the content behind a reference (from memory or from its file)
+    pub async fn read(&self, reference: &BinaryRef) -> Result<Bytes> {
+        if let Some(path) = &reference.path {
+            let data = tokio::fs::read(path).await
+                .map_err(|e| anyhow::anyhow!("Failed to read binary file '{}': {}", path, e))?;
+            return Ok(Bytes::from(data));
+        }
+
+        self.entries.read()
+            .map_err(|_| anyhow::anyhow!("Binary store lock poisoned"))?
+            .get(&reference.id)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Binary data not found: {} (binaries are execution-scoped)", reference.id))
+    }
+}
@@ -0,0 +1,141 @@
+//! Canary releases of a workflow
+//! 
+//! A workflow can have one candidate version next to its stable definition.
+//! Webhook requests run the candidate with the configured probability
+//! (`percent` of the traffic) and the stable version otherwise; other triggers
+//! always run the stable version. Outcomes are counted per version so the
+//! candidate can be promoted (it becomes the workflow definition) or dropped.
+//! Counters live in memory and restart when the canary is replaced or the
+//! server restarts.
+
+use crate::{
+    runtime::executor::Termination,
+    workflow::{registry::CompiledWorkflow, types::Workflow},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    sync::Arc,
+    time::Duration,
+};
+
+/// Candidate version of a workflow, stored next to the stable definition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowCanary {
+    /// Rollout ID (new for every candidate definition)
+    pub id: String,
+    /// Share of webhook traffic routed to the candidate (0-100)
+    pub percent: u8,
+    pub workflow: Workflow,
+    /// RFC 3339
+    pub created_at: String,
+}
+
+impl WorkflowCanary {
+    pub fn new(workflow: Workflow, percent: u8) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            percent,
+            workflow,
+            created_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// Version of a workflow that served a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkflowVersion {
+    Stable,
+    Canary,
+}
+
+impl WorkflowVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WorkflowVersion::Stable => "stable",
+            WorkflowVersion::Canary => "canary",
+        }
+    }
+}
+
+/// Run outcomes of one version
+#[derive(Debug, Default)]
+pub struct VersionStats {
+    runs: AtomicU64,
+    completed: AtomicU64,
+    stopped: AtomicU64,
+    failed: AtomicU64,
+    duration_ms: AtomicU64,
+}
+
+impl VersionStats {
+    fn record(&self, termination: &Termination, duration: Duration) {
+        self.runs.fetch_add(1, Ordering::Relaxed);
+        self.duration_ms.fetch_add(duration.as_millis() as u64, Ordering::Relaxed);
+        let outcome = match termination {
+            Termination::Completed => &self.completed,
+            Termination::StoppedEarly { .. } => &self.stopped,
+            Termination::Failed { .. } => &self.failed,
+        };
+        outcome.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// { "runs", "completed", "stopped", "failed", "success_rate", "avg_duration_ms" }
+    /// (stopped runs count as successful)
+    fn to_json(&self) -> Value {
+        let runs = self.runs.load(Ordering::Relaxed);
+        let failed = self.failed.load(Ordering::Relaxed);
+        let ratio = |value: u64| if runs == 0 { None } else { Some(value as f64 / runs as f64) };
+        json!({
+            "runs": runs,
+            "completed": self.completed.load(Ordering::Relaxed),
+            "stopped": self.stopped.load(Ordering::Relaxed),
+            "failed": failed,
+            "success_rate": ratio(runs - failed),
+            "avg_duration_ms": ratio(self.duration_ms.load(Ordering::Relaxed)),
+        })
+    }
+}
+
+/// Per-version outcomes of a canary rollout
+#[derive(Debug, Default)]
+pub struct CanaryMetrics {
+    stable: VersionStats,
+    canary: VersionStats,
+}
+
+impl CanaryMetrics {
+    pub fn record(&self, version: WorkflowVersion, termination: &Termination, duration: Duration) {
+        match version {
+            WorkflowVersion::Stable => self.stable.record(termination, duration),
+            WorkflowVersion::Canary => self.canary.record(termination, duration),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({ "stable": self.stable.to_json(), "canary": self.canary.to_json() })
+    }
+}
+
+/// Compiled candidate version held by the registry next to the stable one
+#[derive(Debug)]
+pub struct CompiledCanary {
+    pub id: String,
+    pub percent: u8,
+    pub workflow: CompiledWorkflow,
+    /// Kept across registry reloads of the same rollout
+    pub metrics: Arc<CanaryMetrics>,
+}
+
+impl CompiledCanary {
+    /// Pick the version serving a webhook request
+    pub fn pick(&self) -> WorkflowVersion {
+        if (uuid::Uuid::new_v4().as_u128() % 100) < u128::from(self.percent) {
+            WorkflowVersion::Canary
+        } else {
+            WorkflowVersion::Stable
+        }
+    }
+}
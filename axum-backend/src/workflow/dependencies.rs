@@ -0,0 +1,215 @@
+//! External dependencies of a workflow, computed when it is compiled
+//! 
+//! Lists what has to exist before a workflow can run elsewhere (pre-deployment
+//! checks, export bundles):
+//! - secrets: names of every `$secret.` pin in node secrets, params and settings
+//! - sub_workflows: ExecuteWorkflow targets
+//! - external_hosts: hosts of HTTPClient / PollingTrigger URLs, MQTT brokers,
+//!   Stripe / GitHub / Twilio / FCM / exchange rate APIs and callback URLs;
+//!   nodes whose host is only known at run time are listed in dynamic_hosts
+//! - topics: internal events, MQTT topics, WebSocket paths and FCM topics
+//! - tables: SimpleTable / PostgreSQL tables (see lineage)
+
+use crate::{
+    runtime::{
+        currency::CurrencyParams,
+        github_api::GitHubApiParams,
+        mqtt::MqttParams,
+        push::{PushParams, PushProvider},
+        stripe_api::StripeApiParams,
+        twilio::TwilioParams,
+    },
+    workflow::{
+        lineage::{self, Access},
+        types::{Node, NodeType, Workflow},
+    },
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+/// Sub-workflow called by an ExecuteWorkflow node
+#[derive(Debug, Clone, Serialize)]
+pub struct SubWorkflowDependency {
+    pub node_id: String,
+    pub workflow_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_node: Option<String>,
+}
+
+/// Topic, event or path a node publishes to or subscribes to
+#[derive(Debug, Clone, Serialize)]
+pub struct TopicDependency {
+    pub node_id: String,
+    /// "event", "mqtt", "websocket" or "fcm"
+    pub kind: &'static str,
+    pub name: String,
+    /// "publish" or "subscribe"
+    pub direction: &'static str,
+}
+
+/// Table read or written by a node
+#[derive(Debug, Clone, Serialize)]
+pub struct TableDependency {
+    pub node_id: String,
+    /// Lineage table ID ("simpletable:orders", "postgres:$secret.db:public.orders")
+    pub table: String,
+    pub access: Access,
+}
+
+/// Everything a workflow references outside itself
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct WorkflowDependencies {
+    pub secrets: BTreeSet<String>,
+    pub sub_workflows: Vec<SubWorkflowDependency>,
+    pub external_hosts: BTreeSet<String>,
+    /// Nodes whose target host is only known at run time
+    pub dynamic_hosts: Vec<String>,
+    pub topics: Vec<TopicDependency>,
+    pub tables: Vec<TableDependency>,
+}
+
+impl WorkflowDependencies {
+    /// Collect the dependencies of a workflow definition
+    pub fn analyze(workflow: &Workflow) -> Self {
+        let mut deps = Self::default();
+
+        if let Some(callbacks) = &workflow.settings.callbacks {
+            for url in callbacks.on_complete.iter().chain(&callbacks.on_failure) {
+                deps.add_url("settings", url);
+            }
+            if let Some(secret) = &callbacks.secret {
+                deps.add_secrets(&Value::String(secret.clone()));
+            }
+        }
+
+        for node in &workflow.nodes {
+            deps.add_node(node);
+        }
+        deps
+    }
+
+    fn add_node(&mut self, node: &Node) {
+        for secret in node.secrets.iter().flatten() {
+            self.add_secrets(&Value::String(secret.clone()));
+        }
+        self.add_secrets(&node.params);
+        for input in node.inputs.iter().flatten() {
+            self.add_secrets(&Value::String(input.clone()));
+        }
+
+        let param = |key: &str| node.params.get(key).and_then(|v| v.as_str());
+        let topic = |kind, name: &str, direction| TopicDependency {
+            node_id: node.id.clone(),
+            kind,
+            name: name.to_string(),
+            direction,
+        };
+
+        match node.node_type {
+            NodeType::EmitEvent => {
+                self.topics.extend(param("event").map(|event| topic("event", event, "publish")));
+            }
+            NodeType::EventTrigger => {
+                self.topics.extend(param("event").map(|event| topic("event", event, "subscribe")));
+            }
+            NodeType::WebSocketTrigger => {
+                self.topics.extend(param("path").map(|path| topic("websocket", path, "subscribe")));
+            }
+            NodeType::MQTTTrigger => {
+                self.topics.extend(param("topic").map(|name| topic("mqtt", name, "subscribe")));
+                if let Ok(mqtt) = MqttParams::from_node_params(&node.params) {
+                    self.external_hosts.insert(format!("{}:{}", mqtt.host, mqtt.port));
+                }
+            }
+            NodeType::PushNotification => {
+                self.topics.extend(param("topic").map(|name| topic("fcm", name, "publish")));
+                match PushParams::from_node_params(&node.params) {
+                    // Web Push endpoints come from the subscriptions
+                    Ok(push) if matches!(push.provider, PushProvider::Fcm) => self.add_url(&node.id, &push.api_base),
+                    _ => self.dynamic_hosts.push(node.id.clone()),
+                }
+            }
+            NodeType::ExecuteWorkflow => {
+                if let Some(workflow_id) = param("workflow_id") {
+                    self.sub_workflows.push(SubWorkflowDependency {
+                        node_id: node.id.clone(),
+                        workflow_id: workflow_id.to_string(),
+                        start_node: param("start_node").map(str::to_string),
+                    });
+                }
+            }
+            NodeType::HTTPClient => {
+                if let Some(url) = param("url") {
+                    self.add_url(&node.id, url);
+                }
+            }
+            NodeType::PollingTrigger => {
+                if let Some(url) = node.params.pointer("/request/url").and_then(|u| u.as_str()) {
+                    self.add_url(&node.id, url);
+                }
+            }
+            NodeType::StripeAPI => {
+                if let Ok(stripe) = StripeApiParams::from_node_params(&node.params) {
+                    self.add_url(&node.id, &stripe.api_base);
+                }
+            }
+            NodeType::GitHubAPI => {
+                if let Ok(github) = GitHubApiParams::from_node_params(&node.params) {
+                    self.add_url(&node.id, &github.api_base);
+                }
+            }
+            NodeType::TwilioSend => {
+                if let Ok(twilio) = TwilioParams::from_node_params(&node.params) {
+                    self.add_url(&node.id, &twilio.api_base);
+                }
+            }
+            NodeType::CurrencyConvert => {
+                if let Ok(currency) = CurrencyParams::from_node_params(&node.params) {
+                    self.add_url(&node.id, &currency.api_base);
+                }
+            }
+            _ => {}
+        }
+
+        for (table, access) in lineage::node_tables(node) {
+            self.tables.push(TableDependency { node_id: node.id.clone(), table: table.id(), access });
+        }
+    }
+
+    /// Record the host of a URL, or the node as dynamic when the URL isn't static
+    fn add_url(&mut self, node_id: &str, url: &str) {
+        let host = reqwest::Url::parse(url).ok()
+            .filter(|_| !url.contains("{{"))
+            .and_then(|url| Some(match url.port() {
+                Some(port) => format!("{}:{}", url.host_str()?, port),
+                None => url.host_str()?.to_string(),
+            }));
+        match host {
+            Some(host) => {
+                self.external_hosts.insert(host);
+            }
+            None if !self.dynamic_hosts.iter().any(|id| id == node_id) => self.dynamic_hosts.push(node_id.to_string()),
+            None => {}
+        }
+    }
+
+    /// Collect `$secret.NAME` pins anywhere in a JSON value
+    fn add_secrets(&mut self, value: &Value) {
+        match value {
+            Value::String(text) => {
+                for (start, _) in text.match_indices("$secret.") {
+                    let name: String = text[start + "$secret.".len()..].chars()
+                        .take_while(|c| c.is_alphanumeric() || matches!(c, '_' | '-'))
+                        .collect();
+                    if !name.is_empty() {
+                        self.secrets.insert(name);
+                    }
+                }
+            }
+            Value::Array(items) => items.iter().for_each(|item| self.add_secrets(item)),
+            Value::Object(map) => map.values().for_each(|item| self.add_secrets(item)),
+            _ => {}
+        }
+    }
+}
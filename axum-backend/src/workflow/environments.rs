@@ -0,0 +1,152 @@
+//! Workflow environment profiles (dev / staging / prod)
+//! 
+//! A workflow may carry named profiles that override parts of its definition,
+//! so one definition can target several environments:
+//! 
+//! ```json
+//! "environments": {
+//!   "staging": {
+//!     "vars": { "base_url": "https://staging.example.com" },
+//!     "secrets": { "stripe_key": "stripe_key_test" },
+//!     "nodes": { "fetch": { "url": "https://staging.example.com/orders" } }
+//!   }
+//! }
+//! ```
+//! 
+//! - vars: replace workflow variables of the same name
+//! - secrets: rename `$secret.` pins in node secrets, params and callback settings
+//! - nodes: replace params of the given nodes, key by key
+//! 
+//! Each profile is compiled into its own variant when the workflow is loaded.
+//! A run uses the environment named by the X-Mechaway-Environment header on
+//! manual runs (webhook calls, replays, debug sessions), otherwise the project
+//! settings' "environment" when the workflow has a profile of that name, otherwise
+//! the plain definition.
+
+use crate::{
+    project::Project,
+    workflow::types::{Node, Workflow},
+};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Request header selecting the environment of a manual run
+pub const ENVIRONMENT_HEADER: &str = "x-mechaway-environment";
+
+/// Metadata key of the environment a run uses
+pub const ENVIRONMENT_METADATA_KEY: &str = "environment";
+
+/// Overrides of one environment
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EnvironmentProfile {
+    /// Variable overrides (`$vars.name`)
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub vars: Map<String, Value>,
+    /// Secret renames: name in the definition -> name used in this environment
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub secrets: BTreeMap<String, String>,
+    /// Param overrides per node ID
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub nodes: BTreeMap<String, Map<String, Value>>,
+}
+
+impl EnvironmentProfile {
+    /// Reject overrides of unknown nodes and invalid secret names
+    pub fn validate(&self, workflow: &Workflow) -> Result<()> {
+        if let Some(node_id) = self.nodes.keys().find(|id| !workflow.nodes.iter().any(|node| &node.id == *id)) {
+            return Err(anyhow::anyhow!("overrides params of unknown node '{}'", node_id));
+        }
+        for (from, to) in &self.secrets {
+            if !is_secret_name(from) || !is_secret_name(to) {
+                return Err(anyhow::anyhow!("has an invalid secret rename '{}' -> '{}' (use plain names without '$secret.')", from, to));
+            }
+        }
+        Ok(())
+    }
+
+    /// The workflow definition as seen in this environment (without profiles)
+    pub fn apply(&self, workflow: &Workflow) -> Workflow {
+        let mut variant = workflow.clone();
+        variant.environments.clear();
+        for node in &mut variant.nodes {
+            if let Some(overrides) = self.nodes.get(&node.id) {
+                match &mut node.params {
+                    Value::Object(params) => params.extend(overrides.clone()),
+                    params => *params = Value::Object(overrides.clone()),
+                }
+            }
+            if !self.secrets.is_empty() {
+                self.rename_node_secrets(node);
+            }
+        }
+        if let Some(secret) = variant.settings.callbacks.as_mut().and_then(|callbacks| callbacks.secret.as_mut()) {
+            *secret = self.rename_secrets(secret);
+        }
+        variant
+    }
+
+    /// Workflow variables with this environment's overrides
+    pub fn variables(&self, variables: &Map<String, Value>) -> Map<String, Value> {
+        let mut merged = variables.clone();
+        merged.extend(self.vars.clone());
+        merged
+    }
+
+    fn rename_node_secrets(&self, node: &mut Node) {
+        for secret in node.secrets.iter_mut().flatten() {
+            *secret = self.rename_secrets(secret);
+        }
+        self.rename_value_secrets(&mut node.params);
+    }
+
+    fn rename_value_secrets(&self, value: &mut Value) {
+        match value {
+            Value::String(text) if text.contains("$secret.") => *text = self.rename_secrets(text),
+            Value::Array(items) => items.iter_mut().for_each(|item| self.rename_value_secrets(item)),
+            Value::Object(map) => map.values_mut().for_each(|item| self.rename_value_secrets(item)),
+            _ => {}
+        }
+    }
+
+    /// Rename every `$secret.NAME` pin in a string
+    fn rename_secrets(&self, text: &str) -> String {
+        let mut renamed = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find("$secret.") {
+            let name_start = start + "$secret.".len();
+            let name_len = rest[name_start..].find(|c: char| !is_secret_char(c)).unwrap_or(rest.len() - name_start);
+            let name = &rest[name_start..name_start + name_len];
+            renamed.push_str(&rest[..name_start]);
+            renamed.push_str(self.secrets.get(name).map_or(name, String::as_str));
+            rest = &rest[name_start + name_len..];
+        }
+        renamed.push_str(rest);
+        renamed
+    }
+}
+
+fn is_secret_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-')
+}
+
+fn is_secret_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(is_secret_char)
+}
+
+/// Check the profiles of a workflow (names are project-slug-like: "prod", "staging-eu")
+pub fn validate_environments(workflow: &Workflow) -> Result<()> {
+    for (name, profile) in &workflow.environments {
+        if !Project::is_valid_slug(name) {
+            return Err(anyhow::anyhow!("Invalid environment name '{}'", name));
+        }
+        profile.validate(workflow).map_err(|e| anyhow::anyhow!("Environment '{}' {}", name, e))?;
+    }
+    Ok(())
+}
+
+/// Default environment of a project (project settings key "environment")
+pub fn project_environment(settings: &Value) -> Option<String> {
+    settings.get("environment").and_then(|e| e.as_str()).filter(|e| !e.is_empty()).map(|e| e.to_string())
+}
@@ -0,0 +1,305 @@
+//! Precompiled execution plans for workflows
+//! 
+//! Building the petgraph DAG, running toposort, and computing reachability used
+//! to happen on every execution. The plan is now built once when a workflow is
+//! compiled into the registry, so hot webhooks only walk a precomputed node list.
+//! 
+//! Edges may leave from a named output port of their source node (FeatureFlag
+//! "true" / "false", ABSplit variants); the graph keeps each edge's port. A node
+//! reports the port it picked in its result metadata (OUTPUT_PORT_METADATA_KEY)
+//! and only the edges of that port (and port-less edges) carry its output. A
+//! node can instead split its output across ports (OUTPUT_SPLIT_METADATA_KEY),
+//! each port's edges carrying that port's items (see runtime::branches).
+//! 
+//! Nodes read by `$node("id")` pins are recorded up front, so the engine only
+//! keeps the outputs some later node asks for. A pin may only read a node
+//! upstream of its own: siblings run concurrently and may not have finished.
+
+use crate::runtime::ab_split::ABSplitParams;
+use crate::workflow::{
+    pins::{pin_node_reference, template_node_references},
+    types::{Node, NodeType, Workflow},
+};
+use anyhow::Result;
+use petgraph::algo::{has_path_connecting, toposort};
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet, VecDeque},
+};
+
+/// Metadata key a node sets to the output port it picked (removed by the engine)
+pub const OUTPUT_PORT_METADATA_KEY: &str = "output_port";
+
+/// Metadata key a node sets to split its output across ports: a list of PortSplit,
+/// its output holding each port's items in that order (removed by the engine)
+pub const OUTPUT_SPLIT_METADATA_KEY: &str = "output_split";
+
+/// One port's share of a split output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortSplit {
+    pub port: String,
+    pub items: usize,
+}
+
+/// Output ports of a node: fixed per type, or the variant names of an ABSplit
+fn output_ports(node: &Node) -> Option<Vec<String>> {
+    match node.node_type {
+        NodeType::ABSplit => Some(ABSplitParams::from_node_params(&node.params)
+            .map(|params| params.ports())
+            .unwrap_or_default()),
+        _ => node.node_type.output_ports()
+            .map(|ports| ports.iter().map(|port| port.to_string()).collect()),
+    }
+}
+
+/// Petgraph DAG plus precomputed execution orders for a workflow
+#[derive(Debug)]
+pub struct ExecutionPlan {
+    /// The petgraph DiGraph structure (edge weights: the output port an edge leaves from)
+    pub graph: DiGraph<Node, Option<String>>,
+    /// Mapping from node ID to graph node index
+    pub node_id_to_index: HashMap<String, NodeIndex>,
+    /// Full topological order of the graph
+    pub topo_order: Vec<NodeIndex>,
+    /// Nodes to execute per entry point, in topological order
+    /// (reachable from the entry point, entry points themselves excluded)
+    entry_orders: HashMap<String, Vec<NodeIndex>>,
+    /// Nodes whose output is read by a `$node("id")` pin (kept by the engine during a run)
+    referenced_outputs: HashSet<NodeIndex>,
+}
+
+impl ExecutionPlan {
+    /// Build the DAG and execution orders for a workflow
+    /// 
+    /// Fails on duplicate node IDs, edges or `$node("id")` pins referencing an
+    /// unknown node and cycles.
+    pub fn build(workflow: &Workflow) -> Result<Self> {
+        tracing::debug!("🏗️ Building execution plan for '{}' ({} nodes, {} edges)",
+            workflow.id, workflow.nodes.len(), workflow.edges.len());
+
+        let mut graph = DiGraph::new();
+        let mut node_id_to_index = HashMap::new();
+
+        for node in &workflow.nodes {
+            let node_index = graph.add_node(node.clone());
+            if node_id_to_index.insert(node.id.clone(), node_index).is_some() {
+                return Err(anyhow::anyhow!("Duplicate node id: {}", node.id));
+            }
+        }
+
+        for edge in &workflow.edges {
+            let from_index = node_id_to_index.get(&edge.from)
+                .ok_or_else(|| anyhow::anyhow!("Edge references unknown node: {}", edge.from))?;
+            let to_index = node_id_to_index.get(&edge.to)
+                .ok_or_else(|| anyhow::anyhow!("Edge references unknown node: {}", edge.to))?;
+            graph.add_edge(*from_index, *to_index, edge.port.clone());
+            if let Some(port) = &edge.port {
+                let from_type = &graph[*from_index].node_type;
+                let ports = output_ports(&graph[*from_index])
+                    .ok_or_else(|| anyhow::anyhow!("Edge {} -> {} has port '{}', but {:?} nodes have no ports", edge.from, edge.to, port, from_type))?;
+                if !ports.contains(port) {
+                    return Err(anyhow::anyhow!("Edge {} -> {} has unknown port '{}' ({:?} ports: {})", edge.from, edge.to, port, from_type, ports.join(", ")));
+                }
+            }
+        }
+
+        let topo_order = toposort(&graph, None)
+            .map_err(|_| anyhow::anyhow!("Workflow contains cycles - must be a DAG"))?;
+
+        // Only upstream nodes have always finished when a node runs (branches run concurrently)
+        let mut referenced_outputs = HashSet::new();
+        for node in &workflow.nodes {
+            let reader = node_id_to_index[&node.id];
+            let mut texts = Vec::new();
+            collect_strings(&node.params, &mut texts);
+            let pins = node.inputs.iter().flatten().filter_map(|pin| pin_node_reference(pin));
+            for referenced in pins.chain(texts.into_iter().flat_map(template_node_references)) {
+                let index = *node_id_to_index.get(referenced)
+                    .ok_or_else(|| anyhow::anyhow!("Node '{}' references unknown node '{}' with $node(\"{}\")", node.id, referenced, referenced))?;
+                if index == reader || !has_path_connecting(&graph, index, reader, None) {
+                    return Err(anyhow::anyhow!("Node '{}' reads $node(\"{}\"), but '{}' is not upstream of it", node.id, referenced, referenced));
+                }
+                referenced_outputs.insert(index);
+            }
+        }
+
+        let mut plan = Self {
+            graph,
+            node_id_to_index,
+            topo_order,
+            entry_orders: HashMap::new(),
+            referenced_outputs,
+        };
+
+        // Precompute the execution order for every entry point
+        let entry_points: Vec<(String, NodeIndex)> = plan.node_id_to_index.iter()
+            .filter(|(_, &idx)| plan.graph[idx].node_type.is_entry_point())
+            .map(|(id, &idx)| (id.clone(), idx))
+            .collect();
+        for (node_id, start_index) in entry_points {
+            let order = plan.compute_order(start_index);
+            plan.entry_orders.insert(node_id, order);
+        }
+
+        Ok(plan)
+    }
+
+    /// Nodes to execute when starting from `start_node_id`
+    /// 
+    /// Entry points use their precomputed order; any other start node (e.g. an
+    /// ExecuteWorkflow `start_node` pointing mid-graph) is computed on demand.
+    pub fn execution_order(&self, start_node_id: &str) -> Result<Cow<'_, [NodeIndex]>> {
+        if let Some(order) = self.entry_orders.get(start_node_id) {
+            return Ok(Cow::Borrowed(order));
+        }
+        let start_index = self.node_id_to_index.get(start_node_id)
+            .ok_or_else(|| anyhow::anyhow!("Start node not found: {}", start_node_id))?;
+        Ok(Cow::Owned(self.compute_order(*start_index)))
+    }
+
+    /// Whether a `$node("id")` pin reads the node's output
+    pub fn is_output_referenced(&self, index: NodeIndex) -> bool {
+        self.referenced_outputs.contains(&index)
+    }
+
+    /// IDs of the nodes in `steps` that end a branch (no outgoing edges)
+    pub fn leaves(&self, steps: &[NodeIndex]) -> Vec<String> {
+        steps.iter()
+            .filter(|&&idx| self.graph.neighbors(idx).next().is_none())
+            .map(|&idx| self.graph[idx].id.clone())
+            .collect()
+    }
+
+    /// Edges into a node: (source, output port)
+    pub fn incoming(&self, index: NodeIndex) -> impl Iterator<Item = (NodeIndex, Option<&str>)> + '_ {
+        self.graph.edges_directed(index, Direction::Incoming)
+            .map(|edge| (edge.source(), edge.weight().as_deref()))
+    }
+
+    /// Nodes of `steps` downstream of `from`, in the order of `steps`
+    pub fn downstream(&self, from: NodeIndex, steps: &[NodeIndex]) -> Vec<NodeIndex> {
+        let reachable = self.find_reachable_nodes(from);
+        steps.iter()
+            .copied()
+            .filter(|&index| index != from && reachable.contains(&index))
+            .collect()
+    }
+
+    /// Topologically ordered non-entry nodes reachable from `start_index`
+    fn compute_order(&self, start_index: NodeIndex) -> Vec<NodeIndex> {
+        let reachable = self.find_reachable_nodes(start_index);
+        self.topo_order.iter()
+            .filter(|&&idx| reachable.contains(&idx) && !self.graph[idx].node_type.is_entry_point())
+            .cloned()
+            .collect()
+    }
+
+    /// Find all nodes reachable from the starting node using BFS
+    fn find_reachable_nodes(&self, start_index: NodeIndex) -> HashSet<NodeIndex> {
+        let mut reachable = HashSet::new();
+        let mut queue = VecDeque::new();
+
+        queue.push_back(start_index);
+        reachable.insert(start_index);
+
+        while let Some(current) = queue.pop_front() {
+            for target in self.graph.neighbors(current) {
+                if reachable.insert(target) {
+                    queue.push_back(target);
+                }
+            }
+        }
+
+        reachable
+    }
+}
+
+/// Every string inside a JSON value (templated params can sit at any depth)
+fn collect_strings<'a>(value: &'a Value, texts: &mut Vec<&'a str>) {
+    match value {
+        Value::String(text) => texts.push(text),
+        Value::Array(items) => items.iter().for_each(|item| collect_strings(item, texts)),
+        Value::Object(map) => map.values().for_each(|item| collect_strings(item, texts)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn build(reader: Value) -> Result<ExecutionPlan> {
+        let workflow: Workflow = serde_json::from_value(json!({
+            "id": "wf-refs",
+            "name": "References",
+            "nodes": [
+                { "id": "hook", "node_type": "Webhook", "params": { "path": "/refs" } },
+                { "id": "fetch", "node_type": "FunLogic", "params": { "script": "return data" } },
+                reader
+            ],
+            "edges": [{ "from": "hook", "to": "fetch" }, { "from": "fetch", "to": "reader" }]
+        }))?;
+        ExecutionPlan::build(&workflow)
+    }
+
+    fn fetch_is_referenced(plan: &ExecutionPlan) -> bool {
+        plan.is_output_referenced(plan.node_id_to_index["fetch"])
+    }
+
+    #[test]
+    fn input_pins_and_templates_reference_outputs() {
+        let plan = build(json!({ "id": "reader", "node_type": "FunLogic",
+            "params": { "script": "return data" }, "inputs": ["$node(\"fetch\").email"] })).unwrap();
+        assert!(fetch_is_referenced(&plan));
+
+        let plan = build(json!({ "id": "reader", "node_type": "HTTPClient",
+            "params": { "url": "https://api.example.com/{{ $node('fetch').id }}" } })).unwrap();
+        assert!(fetch_is_referenced(&plan));
+    }
+
+    #[test]
+    fn scripts_mentioning_a_pin_reference_nothing() {
+        let plan = build(json!({ "id": "reader", "node_type": "FunLogic",
+            "params": { "script": "-- not a pin: $node(\"ghost\")\nreturn data" } })).unwrap();
+        assert!(!fetch_is_referenced(&plan));
+    }
+
+    #[test]
+    fn unknown_node_reference_fails_the_build() {
+        let error = build(json!({ "id": "reader", "node_type": "FunLogic",
+            "params": { "script": "return data" }, "inputs": ["$node(\"ghost\")"] })).unwrap_err();
+        assert!(error.to_string().contains("unknown node 'ghost'"), "{}", error);
+    }
+
+    #[test]
+    fn sibling_or_downstream_reference_fails_the_build() {
+        let workflow = |reader_inputs: Value| -> Result<ExecutionPlan> {
+            let workflow: Workflow = serde_json::from_value(json!({
+                "id": "wf-siblings",
+                "name": "Siblings",
+                "nodes": [
+                    { "id": "hook", "node_type": "Webhook", "params": { "path": "/siblings" } },
+                    { "id": "left", "node_type": "FunLogic", "params": { "script": "return data" } },
+                    { "id": "right", "node_type": "FunLogic", "params": { "script": "return data" }, "inputs": reader_inputs },
+                    { "id": "after", "node_type": "FunLogic", "params": { "script": "return data" } }
+                ],
+                "edges": [{ "from": "hook", "to": "left" }, { "from": "hook", "to": "right" }, { "from": "right", "to": "after" }]
+            }))?;
+            ExecutionPlan::build(&workflow)
+        };
+
+        let error = workflow(json!(["$node(\"left\").id"])).unwrap_err();
+        assert!(error.to_string().contains("'left' is not upstream"), "{}", error);
+        let error = workflow(json!(["$node(\"after\").id"])).unwrap_err();
+        assert!(error.to_string().contains("'after' is not upstream"), "{}", error);
+        let error = workflow(json!(["$node(\"right\").id"])).unwrap_err();
+        assert!(error.to_string().contains("'right' is not upstream"), "{}", error);
+        assert!(workflow(json!(["$node(\"hook\").id"])).is_ok());
+    }
+}
@@ -0,0 +1,366 @@
+//! Data lineage: which workflows read and write which tables
+//! 
+//! Derived from the node params of a project's registered workflows, without
+//! running anything:
+//! - SimpleTableWriter -> write, SimpleTableReader -> read ("table" param)
+//! - SimpleTableQuery, PGQuery -> tables named in the SQL ("query" param);
+//!   INSERT/UPDATE/DELETE/MERGE/DDL targets are writes, FROM/JOIN sources reads
+//! - PGDynTableWriter -> write to mway_dynamic_tables.{table}
+//! - PollingTrigger with an SQL source -> tables read by its query
+//! 
+//! SimpleTable tables belong to the project ("simpletable" store); PostgreSQL
+//! tables are keyed by the secret pin naming their database, since secrets are
+//! not resolved here. SQL is scanned, not parsed: tables named in dynamic SQL
+//! or functions are missed.
+
+use crate::{
+    project::pg_dyn_table::DYNAMIC_SCHEMA,
+    workflow::types::{Node, NodeType, Workflow},
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+/// Read or write access of a node to a table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Access {
+    Read,
+    Write,
+}
+
+/// A table as identified by lineage: store, database and name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableRef {
+    /// "simpletable" or "postgres"
+    pub store: &'static str,
+    /// Secret pin of the PostgreSQL database
+    pub database: Option<String>,
+    pub name: String,
+}
+
+impl TableRef {
+    fn simpletable(name: String) -> Self {
+        Self { store: "simpletable", database: None, name }
+    }
+
+    fn postgres(database: Option<String>, name: String) -> Self {
+        Self { store: "postgres", database, name }
+    }
+
+    /// "simpletable:{name}" or "postgres:{secret}:{name}"
+    pub fn id(&self) -> String {
+        match &self.database {
+            Some(database) => format!("{}:{}:{}", self.store, database, self.name),
+            None => format!("{}:{}", self.store, self.name),
+        }
+    }
+}
+
+/// A workflow of the lineage graph
+#[derive(Debug, Clone, Serialize)]
+pub struct LineageWorkflow {
+    pub id: String,
+    pub name: String,
+}
+
+/// A table of the lineage graph, with the workflows touching it
+#[derive(Debug, Clone, Serialize)]
+pub struct LineageTable {
+    /// "simpletable:{name}" or "postgres:{secret}:{name}"
+    pub id: String,
+    /// "simpletable" or "postgres"
+    pub store: &'static str,
+    /// Secret pin of the PostgreSQL database
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database: Option<String>,
+    pub name: String,
+    pub readers: BTreeSet<String>,
+    pub writers: BTreeSet<String>,
+}
+
+/// One node reading or writing one table
+#[derive(Debug, Clone, Serialize)]
+pub struct LineageEdge {
+    pub workflow_id: String,
+    pub node_id: String,
+    pub table: String,
+    pub access: Access,
+}
+
+/// Workflow <-> table graph of a project
+#[derive(Debug, Clone, Serialize)]
+pub struct LineageGraph {
+    pub project: String,
+    pub workflows: Vec<LineageWorkflow>,
+    pub tables: Vec<LineageTable>,
+    pub edges: Vec<LineageEdge>,
+}
+
+/// Build the lineage graph of a project's workflows
+pub fn project_lineage(project_slug: &str, workflows: &[Workflow]) -> LineageGraph {
+    let mut workflows: Vec<&Workflow> = workflows.iter()
+        .filter(|w| w.project == project_slug)
+        .collect();
+    workflows.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let mut tables: BTreeMap<String, LineageTable> = BTreeMap::new();
+    let mut edges = Vec::new();
+    let mut touching = Vec::new();
+    for workflow in workflows {
+        let mut touches = false;
+        for node in &workflow.nodes {
+            for (table, access) in node_tables(node) {
+                let id = table.id();
+                let table = tables.entry(id.clone()).or_insert_with(|| LineageTable {
+                    id: id.clone(),
+                    store: table.store,
+                    database: table.database,
+                    name: table.name,
+                    readers: BTreeSet::new(),
+                    writers: BTreeSet::new(),
+                });
+                match access {
+                    Access::Read => table.readers.insert(workflow.id.clone()),
+                    Access::Write => table.writers.insert(workflow.id.clone()),
+                };
+                edges.push(LineageEdge { workflow_id: workflow.id.clone(), node_id: node.id.clone(), table: id, access });
+                touches = true;
+            }
+        }
+        if touches {
+            touching.push(LineageWorkflow { id: workflow.id.clone(), name: workflow.name.clone() });
+        }
+    }
+
+    LineageGraph {
+        project: project_slug.to_string(),
+        workflows: touching,
+        tables: tables.into_values().collect(),
+        edges,
+    }
+}
+
+/// Tables a node reads or writes
+pub fn node_tables(node: &Node) -> Vec<(TableRef, Access)> {
+    let param = |key: &str| node.params.get(key).and_then(|v| v.as_str());
+    let database = || node.secrets.as_ref().and_then(|secrets| secrets.first()).cloned();
+    let simpletable = |tables: Vec<(String, Access)>| tables.into_iter()
+        .map(|(table, access)| (TableRef::simpletable(table), access))
+        .collect();
+    let postgres = |tables: Vec<(String, Access)>| tables.into_iter()
+        .map(|(table, access)| (TableRef::postgres(database(), table), access))
+        .collect();
+
+    match node.node_type {
+        NodeType::SimpleTableWriter => param("table")
+            .map(|table| vec![(TableRef::simpletable(table.to_lowercase()), Access::Write)])
+            .unwrap_or_default(),
+        NodeType::SimpleTableReader => param("table")
+            .map(|table| vec![(TableRef::simpletable(table.to_lowercase()), Access::Read)])
+            .unwrap_or_default(),
+        NodeType::SimpleTableQuery => {
+            let mut found = param("query").map(sql_tables).unwrap_or_default();
+            // "table" is informational for SimpleTableQuery; only used when the SQL names none
+            if found.is_empty() {
+                found.extend(param("table").map(|table| (table.to_lowercase(), Access::Read)));
+            }
+            simpletable(found)
+        }
+        NodeType::PGQuery => postgres(param("query").map(sql_tables).unwrap_or_default()),
+        NodeType::PGDynTableWriter => param("table")
+            .map(|table| vec![(TableRef::postgres(database(), format!("{}.{}", DYNAMIC_SCHEMA, table)), Access::Write)])
+            .unwrap_or_default(),
+        // Polled SQL runs against PostgreSQL when the node has a secret, SimpleTable otherwise
+        NodeType::PollingTrigger if param("source") == Some("sql") => {
+            let found = param("query").map(sql_tables).unwrap_or_default();
+            match database() {
+                Some(_) => postgres(found),
+                None => simpletable(found),
+            }
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// SQL token: a (possibly qualified) name or a punctuation character
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// Name as written (quoted parts keep their case) and its uppercase keyword form
+    Name { name: String, keyword: String },
+    Punct(char),
+}
+
+/// Keywords ending a table reference (never aliases)
+const CLAUSE_KEYWORDS: &[&str] = &[
+    "WHERE", "JOIN", "LEFT", "RIGHT", "INNER", "OUTER", "FULL", "CROSS", "NATURAL", "ON", "USING",
+    "GROUP", "ORDER", "HAVING", "LIMIT", "OFFSET", "FETCH", "UNION", "EXCEPT", "INTERSECT", "WINDOW",
+    "SET", "VALUES", "SELECT", "RETURNING", "DEFAULT", "FOR", "WHEN", "LATERAL", "AS", "ONLY",
+];
+
+/// Tables named in a SQL statement with their access, deduplicated
+/// 
+/// Unquoted names are lowercased (SQLite and PostgreSQL fold them), CTE names
+/// are left out. FROM inside function calls (EXTRACT, SUBSTRING) is ignored.
+fn sql_tables(sql: &str) -> Vec<(String, Access)> {
+    let tokens = tokenize(sql);
+    let keyword = |i: usize| match tokens.get(i) {
+        Some(Token::Name { keyword, .. }) => keyword.as_str(),
+        _ => "",
+    };
+
+    // WITH name AS ( ... ): the CTE names aren't tables
+    let mut ctes = HashSet::new();
+    for (i, token) in tokens.iter().enumerate() {
+        if let Token::Name { name, .. } = token {
+            if keyword(i + 1) == "AS" && tokens.get(i + 2) == Some(&Token::Punct('(')) {
+                ctes.insert(name.clone());
+            }
+        }
+    }
+
+    let statement = keyword(0);
+    let mut found: Vec<(String, Access)> = Vec::new();
+    // Per open parenthesis: whether it belongs to a function call
+    let mut parens: Vec<bool> = Vec::new();
+    let mut delete_target_seen = false;
+
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i] {
+            Token::Punct('(') => {
+                let function_call = i > 0 && matches!(&tokens[i - 1], Token::Name { keyword, .. }
+                    if !CLAUSE_KEYWORDS.contains(&keyword.as_str())
+                        && !matches!(keyword.as_str(), "FROM" | "IN" | "EXISTS" | "ANY" | "ALL" | "INTO" | "TABLE"));
+                parens.push(function_call);
+            }
+            Token::Punct(')') => {
+                parens.pop();
+            }
+            Token::Name { keyword: kw, .. } if !parens.last().copied().unwrap_or(false) => {
+                let access = match kw.as_str() {
+                    "FROM" if statement == "DELETE" && !delete_target_seen => {
+                        delete_target_seen = true;
+                        Some(Access::Write)
+                    }
+                    "FROM" | "JOIN" => Some(Access::Read),
+                    "INTO" | "TABLE" | "TRUNCATE" => Some(Access::Write),
+                    // Not ON CONFLICT DO UPDATE SET, FOR [NO KEY] UPDATE, ON UPDATE CASCADE
+                    "UPDATE" if keyword(i + 1) != "SET" && !matches!(keyword(i.wrapping_sub(1)), "DO" | "FOR" | "KEY" | "ON") => Some(Access::Write),
+                    _ => None,
+                };
+                if let Some(access) = access {
+                    // Skip IF [NOT] EXISTS / ONLY / TABLE between the keyword and the name
+                    let mut j = i + 1;
+                    while matches!(keyword(j), "IF" | "NOT" | "EXISTS" | "ONLY" | "TABLE") {
+                        j += 1;
+                    }
+                    // FROM a x, b AS y: every comma-separated source
+                    while let Some(Token::Name { name, keyword: table_kw }) = tokens.get(j) {
+                        if CLAUSE_KEYWORDS.contains(&table_kw.as_str()) {
+                            break;
+                        }
+                        if !ctes.contains(name) && !found.iter().any(|(n, a)| n == name && *a == access) {
+                            found.push((name.clone(), access));
+                        }
+                        j += 1;
+                        if keyword(j) == "AS" {
+                            j += 1;
+                        }
+                        if matches!(tokens.get(j), Some(Token::Name { keyword, .. }) if !CLAUSE_KEYWORDS.contains(&keyword.as_str())) {
+                            j += 1;
+                        }
+                        if access == Access::Read && tokens.get(j) == Some(&Token::Punct(',')) {
+                            j += 1;
+                            continue;
+                        }
+                        break;
+                    }
+                    i = j;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    found
+}
+
+/// Split SQL into names and punctuation, dropping literals, comments and placeholders
+fn tokenize(sql: &str) -> Vec<Token> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '-' && chars.get(i + 1) == Some(&'-') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && chars.get(i + 1) == Some(&'*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                i += 1;
+            }
+            i += 2;
+        } else if c == '\'' {
+            i += 1;
+            while i < chars.len() {
+                if chars[i] == '\'' && chars.get(i + 1) == Some(&'\'') {
+                    i += 2;
+                } else if chars[i] == '\'' {
+                    break;
+                } else {
+                    i += 1;
+                }
+            }
+            i += 1;
+        } else if c == '$' || c == '?' || c.is_ascii_digit() {
+            // Placeholders ($1, ?) and numbers
+            i += 1;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '.' || chars[i] == '_') {
+                i += 1;
+            }
+        } else if c == '"' || c == '`' || c.is_alphabetic() || c == '_' {
+            // Name with optional qualifiers: schema.table, "Schema"."Table"
+            let mut name = String::new();
+            let mut keyword = String::new();
+            loop {
+                if i < chars.len() && (chars[i] == '"' || chars[i] == '`') {
+                    let quote = chars[i];
+                    i += 1;
+                    while i < chars.len() && chars[i] != quote {
+                        name.push(chars[i]);
+                        i += 1;
+                    }
+                    i += 1;
+                } else {
+                    let start = i;
+                    while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                        i += 1;
+                    }
+                    let part: String = chars[start..i].iter().collect();
+                    keyword.push_str(&part.to_uppercase());
+                    name.push_str(&part.to_lowercase());
+                }
+                if i < chars.len() && chars[i] == '.'
+                    && chars.get(i + 1).is_some_and(|n| n.is_alphabetic() || *n == '_' || *n == '"' || *n == '`')
+                {
+                    name.push('.');
+                    keyword.push('.');
+                    i += 1;
+                } else {
+                    break;
+                }
+            }
+            tokens.push(Token::Name { name, keyword });
+        } else {
+            tokens.push(Token::Punct(c));
+            i += 1;
+        }
+    }
+    tokens
+}
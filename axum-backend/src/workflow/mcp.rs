@@ -0,0 +1,120 @@
+//! MCP tool definitions of MCPTrigger nodes
+//!
+//! Every MCPTrigger node of an active workflow is published as one tool of the
+//! server's MCP endpoint (api/mcp.rs):
+//!
+//! { "tool": "lookup_order", "description": "Look up an order by its ID",
+//!   "arguments": { "order_id": { "type": "string", "description": "Order ID", "required": true } },
+//!   "api_key": "$secret.mcp_api_key" }
+//!
+//! The tool's input schema is the node's `input_schema` param (a JSON Schema
+//! object), else built from `arguments` (a JSON Schema per argument, plus
+//! `"required": true`), else inferred from the `$mcp.x` / `$json.x` pins of the
+//! nodes fed directly by the trigger (all arguments optional, any type).
+
+use crate::workflow::{pins::PinExpr, types::Workflow};
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeSet;
+
+/// Tool names: letters, digits, '_', '-' and '.' (at most 128)
+pub fn is_tool_name(name: &str) -> bool {
+    !name.is_empty() && name.len() <= 128
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+/// Parsed MCPTrigger params
+#[derive(Debug, Clone)]
+pub struct McpToolParams {
+    pub tool: String,
+    pub description: Option<String>,
+    input_schema: Option<Value>,
+    arguments: Option<Map<String, Value>>,
+}
+
+impl McpToolParams {
+    /// Parse node params
+    ///
+    /// Expected params: { "tool": "lookup_order", "description": "...",
+    ///                    "arguments": { "order_id": { "type": "string", "required": true } } | "input_schema": {...},
+    ///                    "api_key": "$secret.NAME", "priority": 0 }
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        let tool = params.get("tool")
+            .and_then(|t| t.as_str())
+            .ok_or_else(|| anyhow::anyhow!("missing 'tool' parameter (the MCP tool name)"))?;
+        if !is_tool_name(tool) {
+            return Err(anyhow::anyhow!("invalid tool name '{}' (letters, digits, '_', '-' and '.', at most 128)", tool));
+        }
+        let description = match params.get("description") {
+            None | Some(Value::Null) => None,
+            Some(Value::String(description)) => Some(description.clone()),
+            Some(other) => return Err(anyhow::anyhow!("'description' must be a string, got {}", other)),
+        };
+        let input_schema = match params.get("input_schema") {
+            None | Some(Value::Null) => None,
+            Some(schema) if schema.get("type").and_then(|t| t.as_str()) == Some("object") => Some(schema.clone()),
+            Some(_) => return Err(anyhow::anyhow!("'input_schema' must be a JSON Schema with \"type\": \"object\"")),
+        };
+        let arguments = match params.get("arguments") {
+            None | Some(Value::Null) => None,
+            Some(Value::Object(arguments)) => {
+                if let Some((name, _)) = arguments.iter().find(|(_, schema)| !schema.is_object()) {
+                    return Err(anyhow::anyhow!("argument '{}' must be a JSON Schema object", name));
+                }
+                Some(arguments.clone())
+            }
+            Some(other) => return Err(anyhow::anyhow!("'arguments' must be an object of argument schemas, got {}", other)),
+        };
+        if input_schema.is_some() && arguments.is_some() {
+            return Err(anyhow::anyhow!("set either 'input_schema' or 'arguments', not both"));
+        }
+        Ok(Self { tool: tool.to_string(), description, input_schema, arguments })
+    }
+
+    /// JSON Schema of the tool's arguments
+    pub fn input_schema(&self, workflow: &Workflow, node_id: &str) -> Value {
+        if let Some(schema) = &self.input_schema {
+            return schema.clone();
+        }
+        let Some(arguments) = &self.arguments else {
+            let properties: Map<String, Value> = pinned_arguments(workflow, node_id).into_iter()
+                .map(|name| (name, json!({})))
+                .collect();
+            return json!({ "type": "object", "properties": properties });
+        };
+
+        let mut required = Vec::new();
+        let properties: Map<String, Value> = arguments.iter()
+            .map(|(name, schema)| {
+                let mut schema = schema.clone();
+                let is_required = schema.as_object_mut()
+                    .and_then(|schema| schema.remove("required"))
+                    .and_then(|required| required.as_bool())
+                    .unwrap_or(false);
+                if is_required {
+                    required.push(name.clone());
+                }
+                (name.clone(), schema)
+            })
+            .collect();
+        json!({ "type": "object", "properties": properties, "required": required })
+    }
+}
+
+/// Top-level argument names read by `$mcp.x` / `$json.x` pins of the nodes the trigger feeds
+fn pinned_arguments(workflow: &Workflow, node_id: &str) -> BTreeSet<String> {
+    let direct: BTreeSet<&str> = workflow.edges.iter()
+        .filter(|edge| edge.from == node_id)
+        .map(|edge| edge.to.as_str())
+        .collect();
+    workflow.nodes.iter()
+        .filter(|node| direct.contains(node.id.as_str()))
+        .filter_map(|node| node.inputs.as_deref())
+        .flat_map(|pins| PinExpr::compile_all(pins).to_vec())
+        .filter_map(|pin| match pin {
+            PinExpr::Mcp(field) | PinExpr::JsonPath(field) => field.split('.').next().map(|name| name.to_string()),
+            _ => None,
+        })
+        .filter(|name| !name.is_empty() && name != "mcp")
+        .collect()
+}
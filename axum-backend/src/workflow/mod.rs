@@ -1,10 +1,10 @@
-/// Workflow Management Layer
-/// 
-/// This module handles workflow definitions, persistence, and hot-reload registry.
-/// It provides the core workflow management functionality including:
-/// - Type definitions (Workflow, Node, Edge)  
-/// - SQLite persistence with sqlx
-/// - Lock-free hot-reload registry using ArcSwap
+//! Workflow Management Layer
+//! 
+//! This module handles workflow definitions, persistence, and hot-reload registry.
+//! It provides the core workflow management functionality including:
+//! - Type definitions (Workflow, Node, Edge)  
+//! - SQLite persistence with sqlx
+//! - Lock-free hot-reload registry using ArcSwap
 
 // Core workflow type definitions
 pub mod types;
@@ -12,8 +12,54 @@ pub mod types;
 // SQLite persistence layer for workflow storage
 pub mod storage;
 
+// Binary data items (in-memory or file-backed) flowing between nodes
+pub mod binary;
+
+// Precompiled DAG and execution orders (built once per workflow version)
+pub mod graph;
+
+// Pin expressions parsed once at compile time
+pub mod pins;
+
+// Precompiled (method, path) -> webhook node index
+pub mod webhook_index;
+
+// JSONPath assertions shared by test cases and Assert nodes
+pub mod assertions;
+
+// Test cases (inputs, node mocks, output assertions) stored per workflow
+pub mod test_cases;
+
+// OpenAPI documents generated from webhook nodes and their pins
+pub mod openapi;
+
+// MCP tool definitions (names, input schemas) of MCPTrigger nodes
+pub mod mcp;
+
+// Workflow <-> table lineage derived from table node params and SQL
+pub mod lineage;
+
+// Secrets, sub-workflows, hosts, topics and tables a workflow references
+pub mod dependencies;
+
+// Typed node params checked when a workflow is saved
+pub mod params;
+
+// Per-environment (dev / staging / prod) overrides of a workflow
+pub mod environments;
+
+// Canary versions receiving a share of webhook traffic
+pub mod canary;
+
+// Snoozed triggers (maintenance windows) and one-time skips of scheduled runs
+pub mod snooze;
+
+// Built-in workflow templates instantiated as inactive drafts
+pub mod templates;
+
 // Hot-reload registry using ArcSwap for zero-downtime updates  
 pub mod registry;
 
 // Re-export commonly used types
-pub use types::{Workflow, Node, NodeType, Edge, ExecutionContext};
+pub use types::{Workflow, WorkflowSettings, Node, NodeType, Edge, ExecutionContext};
+pub use binary::{BinaryRef, BinaryStore};
@@ -0,0 +1,200 @@
+//! OpenAPI 3 generation for a project's webhook endpoints
+//! 
+//! Every Webhook node of an active workflow becomes one operation at
+//! `/webhook/{workflow_id}{path}`. Request schemas come from the node's optional
+//! `schema` param (a JSON Schema object) or are inferred from pins:
+//! - `$json.a.b` pins on nodes fed directly by the webhook -> body properties
+//! - `$query.x` / `$headers.x` pins on any reachable node -> query / header parameters
+//! - `{id}` or `:id` path segments -> path parameters
+//! 
+//! Responses describe the webhook envelope: the final data array on success,
+//! 422 for execution failures and 429/503 with Retry-After when shedding load.
+
+use crate::workflow::{
+    pins::PinExpr,
+    types::{NodeType, Workflow},
+};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+
+/// Build the OpenAPI document for all webhook routes of a project
+pub fn project_openapi(project_slug: &str, workflows: &[Workflow]) -> Value {
+    let mut workflows: Vec<&Workflow> = workflows.iter()
+        .filter(|w| w.project == project_slug)
+        .collect();
+    workflows.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let mut paths: BTreeMap<String, Map<String, Value>> = BTreeMap::new();
+    for workflow in workflows {
+        for node in &workflow.nodes {
+            if !matches!(node.node_type, NodeType::Webhook) {
+                continue;
+            }
+            let Some(path) = node.params.get("path").and_then(|p| p.as_str()) else { continue };
+
+            let (openapi_path, path_params) = openapi_path(&workflow.id, path);
+            // Webhooks without a method accept any; POST is the documented default
+            let method = node.params.get("method")
+                .and_then(|m| m.as_str())
+                .map(|m| m.to_lowercase())
+                .filter(|m| m != "*")
+                .unwrap_or_else(|| "post".to_string());
+
+            let operation = webhook_operation(workflow, &node.id, &node.params, &path_params, &method);
+            paths.entry(openapi_path).or_default().insert(method, operation);
+        }
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": format!("Mechaway webhooks: {}", project_slug),
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": paths,
+        "components": {
+            "schemas": {
+                "ExecutionError": {
+                    "type": "object",
+                    "properties": { "error": { "type": "string" } },
+                },
+            },
+        },
+    })
+}
+
+/// Webhook path as an OpenAPI path template, plus its parameter names
+fn openapi_path(workflow_id: &str, path: &str) -> (String, Vec<String>) {
+    let mut params = Vec::new();
+    let segments: Vec<String> = path.split('/')
+        .filter(|s| !s.is_empty())
+        .map(|segment| {
+            let name = segment.strip_prefix(':')
+                .or_else(|| segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')));
+            match name {
+                Some(name) => {
+                    params.push(name.to_string());
+                    format!("{{{}}}", name)
+                }
+                None => segment.to_string(),
+            }
+        })
+        .collect();
+    (format!("/webhook/{}/{}", workflow_id, segments.join("/")), params)
+}
+
+/// Describe one webhook node as an OpenAPI operation
+fn webhook_operation(workflow: &Workflow, node_id: &str, params: &Value, path_params: &[String], method: &str) -> Value {
+    let pins = PinUsage::collect(workflow, node_id);
+
+    let mut parameters: Vec<Value> = path_params.iter()
+        .map(|name| json!({ "name": name, "in": "path", "required": true, "schema": { "type": "string" } }))
+        .collect();
+    parameters.extend(pins.query.iter()
+        .map(|name| json!({ "name": name, "in": "query", "required": false, "schema": { "type": "string" } })));
+    parameters.extend(pins.headers.iter()
+        .map(|name| json!({ "name": name, "in": "header", "required": false, "schema": { "type": "string" } })));
+
+    let request_schema = params.get("schema").cloned().unwrap_or_else(|| pins.body_schema());
+    let item_schema = params.get("response_schema").cloned().unwrap_or_else(|| json!({ "type": "object" }));
+    let shed_response = |description: &str| json!({
+        "description": description,
+        "headers": { "Retry-After": { "schema": { "type": "integer" } } },
+        "content": { "application/json": { "schema": { "$ref": "#/components/schemas/ExecutionError" } } },
+    });
+
+    let mut operation = json!({
+        "operationId": format!("{}__{}", workflow.id, node_id),
+        "summary": workflow.name,
+        "tags": [workflow.id],
+        "parameters": parameters,
+        "responses": {
+            "200": {
+                "description": "Output items of the last executed node",
+                "content": { "application/json": { "schema": { "type": "array", "items": item_schema } } },
+            },
+            "404": { "description": "No webhook route for this path" },
+            "405": { "description": "Method not allowed for this path" },
+            "422": { "description": "Workflow execution failed (Assert node failures include a JSON body)" },
+            "429": shed_response("Execution queue is saturated"),
+            "503": shed_response("Execution queue is full"),
+        },
+    });
+    if method != "get" && method != "head" {
+        operation["requestBody"] = json!({
+            "required": false,
+            "content": {
+                "application/json": { "schema": request_schema },
+                "multipart/form-data": { "schema": { "type": "object" } },
+            },
+        });
+    }
+    operation
+}
+
+/// Pins referencing request data, gathered from the nodes a webhook triggers
+#[derive(Default)]
+struct PinUsage {
+    /// `$json` field paths read by nodes fed directly by the webhook
+    body_fields: BTreeSet<String>,
+    query: BTreeSet<String>,
+    headers: BTreeSet<String>,
+}
+
+impl PinUsage {
+    fn collect(workflow: &Workflow, webhook_node_id: &str) -> Self {
+        let mut usage = Self::default();
+        let successors = |id: &str| -> Vec<String> {
+            workflow.edges.iter().filter(|e| e.from == id).map(|e| e.to.clone()).collect()
+        };
+        let direct: HashSet<String> = successors(webhook_node_id).into_iter().collect();
+
+        // Breadth-first walk over nodes reachable from the webhook
+        let mut seen = HashSet::new();
+        let mut queue: VecDeque<String> = direct.iter().cloned().collect();
+        while let Some(id) = queue.pop_front() {
+            if !seen.insert(id.clone()) {
+                continue;
+            }
+            if let Some(node) = workflow.nodes.iter().find(|n| n.id == id) {
+                let pins = node.inputs.as_deref().map(PinExpr::compile_all);
+                for pin in pins.iter().flat_map(|pins| pins.iter()) {
+                    match pin {
+                        PinExpr::JsonPath(field) if direct.contains(&id) => {
+                            usage.body_fields.insert(field.clone());
+                        }
+                        PinExpr::Query(name) => {
+                            usage.query.insert(name.clone());
+                        }
+                        PinExpr::Header(name) => {
+                            usage.headers.insert(name.clone());
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            queue.extend(successors(&id));
+        }
+
+        usage
+    }
+
+    /// Nested object schema from dotted `$json` field paths
+    fn body_schema(&self) -> Value {
+        let mut root = json!({ "type": "object", "properties": {} });
+        for field in &self.body_fields {
+            let mut current = &mut root;
+            for part in field.split('.') {
+                if current.get("properties").is_none() {
+                    *current = json!({ "type": "object", "properties": {} });
+                }
+                current = current["properties"]
+                    .as_object_mut()
+                    .expect("properties is an object")
+                    .entry(part.to_string())
+                    .or_insert_with(|| json!({}));
+            }
+        }
+        root
+    }
+}
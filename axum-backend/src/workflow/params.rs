@@ -0,0 +1,365 @@
+//! Typed node parameters, validated when a workflow is saved
+//! 
+//! Node params are free-form JSON in the workflow definition. The structs here
+//! give the core nodes a typed shape (deserialized with serde), and
+//! `validate_workflow_params` runs them together with the runtime parsers of
+//! the other nodes, so a bad config is rejected by the save instead of failing
+//! when the trigger fires. Unknown params are ignored; disabled nodes are not checked.
+
+use crate::{
+    project::pg_dyn_table::SchemaDriftPolicy,
+    runtime::{
+        ab_split::ABSplitParams,
+        anonymize::AnonymizeParams,
+        ble::BleParams,
+        calendar::CalendarParams,
+        currency::CurrencyParams,
+        diff::DiffParams,
+        excel::{ExcelOperation, ExcelWriteParams},
+        feature_flag::FeatureFlagParams,
+        github_api::GitHubApiParams,
+        math::MathProgram,
+        memory::MemoryParams,
+        modbus::ModbusParams,
+        mqtt::MqttParams,
+        ndjson::ndjson_batch_size,
+        opcua::OpcUaParams,
+        opcua_trigger::OpcUaTriggerParams,
+        parquet_write::ParquetParams,
+        pg_cursor::cursor_batch_size,
+        polling::{PollSchedule, PollingParams},
+        schedule_calendar::ScheduleConstraints,
+        push::PushParams,
+        request_signing::SignConfig,
+        serial::SerialParams,
+        serial_trigger::SerialTriggerParams,
+        stream_aggregate::StreamAggregate,
+        stripe_api::StripeApiParams,
+        twilio::TwilioParams,
+        window::WindowParams,
+    },
+    workflow::{
+        environments::validate_environments,
+        mcp::McpToolParams,
+        types::{Node, NodeType, Workflow},
+    },
+};
+use anyhow::Result;
+use chrono_tz::Tz;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{Map, Value};
+
+/// Deserialize node params into a typed struct
+fn from_params<T: DeserializeOwned>(params: &Value) -> Result<T> {
+    T::deserialize(params).map_err(|e| anyhow::anyhow!("{}", e))
+}
+
+/// HTTP method of an HTTPClient request (case-insensitive in params)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum HttpMethod {
+    #[default]
+    Get,
+    Post,
+    Put,
+    Delete,
+    Patch,
+}
+
+impl HttpMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HttpMethod::Get => "GET",
+            HttpMethod::Post => "POST",
+            HttpMethod::Put => "PUT",
+            HttpMethod::Delete => "DELETE",
+            HttpMethod::Patch => "PATCH",
+        }
+    }
+
+    /// Whether the first input pin is sent as the request body
+    pub fn has_body(&self) -> bool {
+        matches!(self, HttpMethod::Post | HttpMethod::Put | HttpMethod::Patch)
+    }
+}
+
+impl TryFrom<String> for HttpMethod {
+    type Error = String;
+
+    fn try_from(method: String) -> Result<Self, Self::Error> {
+        match method.to_uppercase().as_str() {
+            "GET" => Ok(HttpMethod::Get),
+            "POST" => Ok(HttpMethod::Post),
+            "PUT" => Ok(HttpMethod::Put),
+            "DELETE" => Ok(HttpMethod::Delete),
+            "PATCH" => Ok(HttpMethod::Patch),
+            _ => Err(format!("unsupported HTTP method '{}' (GET, POST, PUT, DELETE or PATCH)", method)),
+        }
+    }
+}
+
+impl std::fmt::Display for HttpMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// How HTTPClient input pins become the request body
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BodyMode {
+    /// First input pin as JSON, text or binary body
+    #[default]
+    Auto,
+    /// Every input pin becomes a form part
+    Multipart,
+}
+
+/// How an HTTPClient response body is returned
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseFormat {
+    /// Binary item for non-textual content types, parsed text otherwise
+    #[default]
+    Auto,
+    Binary,
+    Text,
+}
+
+/// HTTPClient params
+/// 
+/// Expected params: { "url": "https://api.example.com/orders", "method": "POST", "headers": { ... },
+///                    "oauth": "github", "body_mode": "auto" | "multipart", "download_to_file": false,
+///                    "response_format": "auto" | "binary" | "text" } (plus "sign", see SignConfig)
+#[derive(Debug, Clone, Deserialize)]
+pub struct HttpClientParams {
+    pub url: String,
+    #[serde(default)]
+    pub method: HttpMethod,
+    /// String values only; others are skipped
+    #[serde(default)]
+    pub headers: Map<String, Value>,
+    /// Project OAuth credential whose access token is sent as Bearer token
+    #[serde(default)]
+    pub oauth: Option<String>,
+    #[serde(default)]
+    pub body_mode: BodyMode,
+    #[serde(default)]
+    pub download_to_file: bool,
+    #[serde(default)]
+    pub response_format: ResponseFormat,
+}
+
+impl HttpClientParams {
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        let parsed: Self = from_params(params)?;
+        if parsed.url.is_empty() {
+            return Err(anyhow::anyhow!("'url' must not be empty"));
+        }
+        SignConfig::from_node_params(params)?;
+        Ok(parsed)
+    }
+}
+
+/// SimpleTableWriter params
+/// 
+/// Expected params: { "table": "grades", "columns": ["id", "score"], "audit_columns": false }
+#[derive(Debug, Clone, Deserialize)]
+pub struct SimpleTableWriterParams {
+    pub table: String,
+    pub columns: Vec<String>,
+    #[serde(default)]
+    pub audit_columns: bool,
+}
+
+impl SimpleTableWriterParams {
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        let parsed: Self = from_params(params)?;
+        if parsed.table.is_empty() || !parsed.table.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return Err(anyhow::anyhow!("Invalid table name '{}'", parsed.table));
+        }
+        if parsed.columns.is_empty() {
+            return Err(anyhow::anyhow!("'columns' cannot be empty"));
+        }
+        Ok(parsed)
+    }
+
+    /// Input pins, when given, fill the columns one by one
+    pub fn check_inputs(&self, inputs: Option<&[String]>) -> Result<()> {
+        match inputs {
+            Some(inputs) if inputs.len() != self.columns.len() => Err(anyhow::anyhow!(
+                "Input pins count ({}) must match columns count ({})", inputs.len(), self.columns.len())),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// FunLogic params
+/// 
+/// Expected params: { "script": "return { total = data[1].price * data[1].qty }" }
+#[derive(Debug, Clone, Deserialize)]
+pub struct FunLogicParams {
+    pub script: String,
+}
+
+impl FunLogicParams {
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        from_params(params)
+    }
+
+    /// Compile the script without running it (catches Lua syntax errors)
+    /// 
+    /// Scripts are evaluated as an expression when they parse as one, as a block otherwise.
+    pub fn check_syntax(&self) -> Result<()> {
+        let lua = mlua::Lua::new();
+        if lua.load(format!("return {}", self.script)).into_function().is_ok() {
+            return Ok(());
+        }
+        lua.load(&self.script).set_name("script").into_function()
+            .map(drop)
+            .map_err(|e| anyhow::anyhow!("Lua syntax error: {}", e))
+    }
+}
+
+/// CronTrigger params
+/// 
+/// Expected params: { "schedule": "0 0 2 * * *", "timezone": "UTC" } (cron with seconds)
+/// Optional: "jitter_secs" (random delay of up to that many seconds per run) and
+/// the calendar constraints of runtime/schedule_calendar.rs ("only_business_days", ...)
+#[derive(Debug, Clone, Deserialize)]
+pub struct CronTriggerParams {
+    pub schedule: String,
+    #[serde(default)]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub jitter_secs: u64,
+    #[serde(skip)]
+    pub constraints: ScheduleConstraints,
+}
+
+impl CronTriggerParams {
+    pub fn from_node_params(params: &Value) -> Result<Self> {
+        let mut parsed: Self = from_params(params)?;
+        check_cron(&parsed.schedule)?;
+        parse_timezone(parsed.timezone.as_deref())?;
+        parsed.constraints = ScheduleConstraints::from_node_params(params)?;
+        Ok(parsed)
+    }
+
+    /// Timezone the schedule fires in (UTC when unset)
+    pub fn tz(&self) -> Result<Tz> {
+        parse_timezone(self.timezone.as_deref())
+    }
+}
+
+/// Parse a cron expression the way the scheduler does
+/// 
+/// Six or seven fields with seconds: "sec min hour day-of-month month day-of-week [year]".
+pub fn parse_cron(schedule: &str) -> Result<cron::Schedule> {
+    schedule.parse::<cron::Schedule>()
+        .map_err(|e| anyhow::anyhow!("Invalid cron schedule '{}': {}", schedule, e))
+}
+
+/// Parse an IANA timezone name ("Europe/Berlin"); UTC when unset
+pub fn parse_timezone(timezone: Option<&str>) -> Result<Tz> {
+    match timezone {
+        None => Ok(Tz::UTC),
+        Some(timezone) => timezone.parse::<Tz>().map_err(|_| anyhow::anyhow!("Unknown timezone '{}'", timezone)),
+    }
+}
+
+fn check_cron(schedule: &str) -> Result<()> {
+    parse_cron(schedule).map(drop)
+}
+
+/// Precedence of a ChatTrigger or MCPTrigger over other workflows serving the same model or tool
+/// 
+/// Params: { "priority": 10 } (integer, default 0). The highest priority serves;
+/// saving a trigger with the same priority as another workflow's is rejected.
+pub fn trigger_priority(params: &Value) -> Result<i64> {
+    match params.get("priority") {
+        None | Some(Value::Null) => Ok(0),
+        Some(priority) => priority.as_i64().ok_or_else(|| anyhow::anyhow!("'priority' must be an integer")),
+    }
+}
+
+/// Check the params of one node
+pub fn validate_node_params(node: &Node) -> Result<()> {
+    let params = &node.params;
+    match node.node_type {
+        NodeType::HTTPClient => HttpClientParams::from_node_params(params).map(drop),
+        NodeType::SimpleTableWriter => {
+            SimpleTableWriterParams::from_node_params(params)?.check_inputs(node.inputs.as_deref())
+        }
+        NodeType::FunLogic => FunLogicParams::from_node_params(params)?.check_syntax(),
+        NodeType::CronTrigger => CronTriggerParams::from_node_params(params).map(drop),
+        NodeType::PollingTrigger => match PollingParams::from_node_params(params)?.schedule {
+            PollSchedule::Cron(schedule) => check_cron(&schedule),
+            PollSchedule::Interval(_) => Ok(()),
+        },
+        NodeType::PGQuery => cursor_batch_size(params).map(drop),
+        NodeType::PGDynTableWriter => SchemaDriftPolicy::from_node_params(params).map(drop),
+        NodeType::StripeAPI => StripeApiParams::from_node_params(params).map(drop),
+        NodeType::GitHubAPI => GitHubApiParams::from_node_params(params).map(drop),
+        NodeType::TwilioSend => TwilioParams::from_node_params(params).map(drop),
+        NodeType::PushNotification => PushParams::from_node_params(params).map(drop),
+        NodeType::CalendarEvent => CalendarParams::from_node_params(params).map(drop),
+        NodeType::Math => MathProgram::from_node_params(params).map(drop),
+        NodeType::CurrencyConvert => CurrencyParams::from_node_params(params).map(drop),
+        NodeType::ParquetWrite => ParquetParams::from_node_params(params).map(drop),
+        NodeType::NdjsonParse => ndjson_batch_size(params).map(drop),
+        NodeType::Excel => match ExcelOperation::from_node_params(params)? {
+            ExcelOperation::Write => ExcelWriteParams::from_node_params(params).map(drop),
+            ExcelOperation::Read => Ok(()),
+        },
+        NodeType::Anonymize => AnonymizeParams::from_node_params(params).map(drop),
+        NodeType::Diff => DiffParams::from_node_params(params).map(drop),
+        NodeType::Window => WindowParams::from_node_params(params).map(drop),
+        NodeType::Modbus => ModbusParams::from_node_params(params).map(drop),
+        NodeType::OpcUa => OpcUaParams::from_node_params(params).map(drop),
+        NodeType::Serial => SerialParams::from_node_params(params).map(drop),
+        NodeType::Memory => MemoryParams::from_node_params(params).map(drop),
+        NodeType::FeatureFlag => FeatureFlagParams::from_node_params(params)?.check_inputs(node.inputs.as_deref()),
+        NodeType::ABSplit => ABSplitParams::from_node_params(params)?.check_inputs(node.inputs.as_deref()),
+        NodeType::WebSocketTrigger => StreamAggregate::from_node_params(params).map(drop),
+        NodeType::ChatTrigger => trigger_priority(params).map(drop),
+        NodeType::MCPTrigger => {
+            trigger_priority(params)?;
+            McpToolParams::from_node_params(params).map(drop)
+        }
+        NodeType::MQTTTrigger => MqttParams::from_node_params(params).map(drop),
+        NodeType::OpcUaTrigger => OpcUaTriggerParams::from_node_params(params).map(drop),
+        NodeType::SerialTrigger => SerialTriggerParams::from_node_params(params).map(drop),
+        NodeType::BLETrigger => BleParams::from_node_params(params).map(drop),
+        _ => Ok(()),
+    }
+}
+
+/// Check the params of every enabled node of a workflow and of its environment variants
+/// 
+/// Returns one error listing every invalid node ("Node 'fetch' (HTTPClient): missing field `url`").
+pub fn validate_workflow_params(workflow: &Workflow) -> Result<()> {
+    validate_environments(workflow)?;
+    let node_error = |node: &Node, environment: Option<&str>| {
+        validate_node_params(node).err().map(|e| match environment {
+            Some(environment) => format!("Node '{}' ({:?}) in environment '{}': {}", node.id, node.node_type, environment, e),
+            None => format!("Node '{}' ({:?}): {}", node.id, node.node_type, e),
+        })
+    };
+    let mut errors: Vec<String> = workflow.nodes.iter()
+        .filter(|node| !node.disabled)
+        .filter_map(|node| node_error(node, None))
+        .collect();
+    // Environments only change the params of the nodes they override
+    for (name, profile) in &workflow.environments {
+        let variant = profile.apply(workflow);
+        errors.extend(variant.nodes.iter()
+            .filter(|node| !node.disabled && profile.nodes.contains_key(&node.id))
+            .filter_map(|node| node_error(node, Some(name))));
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("Invalid node params: {}", errors.join("; ")))
+    }
+}
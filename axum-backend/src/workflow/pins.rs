@@ -0,0 +1,279 @@
+//! Pin expression pre-compilation
+//! 
+//! Input pins used to be re-parsed from strings on every execution (prefix
+//! matching plus the safe-Lua whitelist scan). The registry now parses them once
+//! when a workflow is compiled and stores the classified `PinExpr`s on the node.
+
+use serde_json::Value;
+use std::{borrow::Cow, sync::Arc};
+
+use crate::workflow::types::Node;
+
+/// A parsed input pin expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum PinExpr {
+    /// `$json.field.path` - field of the first data item
+    JsonPath(String),
+    /// `$json` - the whole first data item
+    JsonItem,
+    /// `$file.field` - uploaded file info
+    File(String),
+    /// `$query.param` - URL query parameter
+    Query(String),
+    /// `$headers.name` - HTTP header
+    Header(String),
+    /// `$params.name` - webhook path parameter (e.g. `{id}` in `/orders/{id}`)
+    PathParam(String),
+    /// `$websocket.field` - WebSocket message field
+    WebSocket(String),
+    /// `$mqtt.field` - MQTT message field
+    Mqtt(String),
+    /// `$mcp.field` - MCP request field
+    Mcp(String),
+    /// `$memory.field` - conversation session loaded by a Memory node (e.g. `$memory.transcript`)
+    Memory(String),
+    /// `$vars.name.path` - workflow variable
+    Var(String),
+    /// `$device.field` / `$device[id].field` - state of the run's device, or of device `id`
+    Device { device_id: Option<String>, path: String },
+    /// `$node("id").field.path` - field of the first output item of upstream node `id`
+    /// (also `$node('id')` and `$node["id"]`; without a path, the whole item)
+    NodeOutput { node_id: String, path: String },
+    /// Whitelisted single-line Lua expression (e.g. `date('%Y-%m-%d')`)
+    Lua(String),
+    /// Literal JSON value or plain string
+    Literal(Value),
+}
+
+impl PinExpr {
+    /// Classify a pin expression string
+    pub fn parse(expr: &str) -> Self {
+        if let Some(field_path) = expr.strip_prefix("$json.") {
+            PinExpr::JsonPath(field_path.to_string())
+        } else if expr == "$json" {
+            PinExpr::JsonItem
+        } else if let Some(field_name) = expr.strip_prefix("$file.") {
+            PinExpr::File(field_name.to_string())
+        } else if let Some(param_name) = expr.strip_prefix("$query.") {
+            PinExpr::Query(param_name.to_string())
+        } else if let Some(header_name) = expr.strip_prefix("$headers.") {
+            PinExpr::Header(header_name.to_string())
+        } else if let Some(param_name) = expr.strip_prefix("$params.") {
+            PinExpr::PathParam(param_name.to_string())
+        } else if let Some(field_name) = expr.strip_prefix("$websocket.") {
+            PinExpr::WebSocket(field_name.to_string())
+        } else if let Some(field_name) = expr.strip_prefix("$mqtt.") {
+            PinExpr::Mqtt(field_name.to_string())
+        } else if let Some(field_name) = expr.strip_prefix("$mcp.") {
+            PinExpr::Mcp(field_name.to_string())
+        } else if let Some(field_path) = expr.strip_prefix("$memory.") {
+            PinExpr::Memory(field_path.to_string())
+        } else if let Some(var_path) = expr.strip_prefix("$vars.") {
+            PinExpr::Var(var_path.to_string())
+        } else if let Some(field_path) = expr.strip_prefix("$device.") {
+            PinExpr::Device { device_id: None, path: field_path.to_string() }
+        } else if let Some((device_id, rest)) = expr.strip_prefix("$device[").and_then(|rest| rest.split_once(']')) {
+            PinExpr::Device { device_id: Some(device_id.to_string()), path: rest.trim_start_matches('.').to_string() }
+        } else if let Some((node_id, path)) = parse_node_pin(expr) {
+            PinExpr::NodeOutput { node_id: node_id.to_string(), path: path.to_string() }
+        } else if is_safe_lua_expression(expr) {
+            PinExpr::Lua(expr.to_string())
+        } else {
+            // Fallback: literal value
+            PinExpr::Literal(serde_json::from_str(expr).unwrap_or_else(|_| Value::String(expr.to_string())))
+        }
+    }
+
+    /// Pin kind name reported by expression previews
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PinExpr::JsonPath(_) => "json_path",
+            PinExpr::JsonItem => "json_item",
+            PinExpr::File(_) => "file",
+            PinExpr::Query(_) => "query",
+            PinExpr::Header(_) => "header",
+            PinExpr::PathParam(_) => "path_param",
+            PinExpr::WebSocket(_) => "websocket",
+            PinExpr::Mqtt(_) => "mqtt",
+            PinExpr::Mcp(_) => "mcp",
+            PinExpr::Memory(_) => "memory",
+            PinExpr::Var(_) => "var",
+            PinExpr::Device { .. } => "device",
+            PinExpr::NodeOutput { .. } => "node",
+            PinExpr::Lua(_) => "lua",
+            PinExpr::Literal(_) => "literal",
+        }
+    }
+
+    /// Parse a list of pin expressions
+    pub fn compile_all(pins: &[String]) -> Arc<[PinExpr]> {
+        pins.iter().map(|pin| PinExpr::parse(pin)).collect()
+    }
+}
+
+impl Node {
+    /// Parsed input pins (precompiled by the registry, parsed on demand otherwise)
+    pub fn input_pins(&self) -> Option<Cow<'_, [PinExpr]>> {
+        match (&self.compiled_inputs, &self.inputs) {
+            (Some(compiled), _) => Some(Cow::Borrowed(compiled)),
+            (None, Some(inputs)) => Some(Cow::Owned(inputs.iter().map(|pin| PinExpr::parse(pin)).collect())),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Split `$node("id")...` into the node id and the rest of the expression
+/// 
+/// The id is quoted with `"` or `'` inside `(...)` or `[...]`; spaces are allowed
+/// around it inside the brackets.
+fn parse_node_reference(expr: &str) -> Option<(&str, &str)> {
+    let rest = expr.strip_prefix("$node")?;
+    let close = match rest.chars().next()? {
+        '(' => ')',
+        '[' => ']',
+        _ => return None,
+    };
+    let rest = rest[1..].trim_start();
+    let quote = rest.chars().next().filter(|quote| matches!(quote, '"' | '\''))?;
+    let (node_id, rest) = rest[1..].split_once(quote)?;
+    let rest = rest.trim_start().strip_prefix(close)?;
+    Some((node_id, rest)).filter(|(node_id, _)| !node_id.is_empty())
+}
+
+/// Node id and field path of a whole `$node("id").field.path` pin
+fn parse_node_pin(pin: &str) -> Option<(&str, &str)> {
+    let (node_id, rest) = parse_node_reference(pin)?;
+    match rest.strip_prefix('.') {
+        Some(path) => Some((node_id, path)),
+        None if rest.is_empty() => Some((node_id, "")),
+        None => None,
+    }
+}
+
+/// Node id read by an input pin
+pub fn pin_node_reference(pin: &str) -> Option<&str> {
+    parse_node_pin(pin).map(|(node_id, _)| node_id)
+}
+
+/// Node ids read by a param string where templates evaluate pins
+/// 
+/// Mirrors template rendering: a string that is a single `$` pin, or the `{{...}}`
+/// placeholders of a longer one. Other text (Lua scripts, SQL) is not scanned.
+pub fn template_node_references(text: &str) -> Vec<&str> {
+    if text.starts_with('$') && !text.contains("{{") {
+        return pin_node_reference(text).into_iter().collect();
+    }
+    let mut references = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else { break };
+        references.extend(pin_node_reference(rest[start + 2..start + end].trim()));
+        rest = &rest[start + end + 2..];
+    }
+    references
+}
+
+/// SECURITY: Check if expression is safe for Lua execution (millions of traffic)
+pub fn is_safe_lua_expression(expr: &str) -> bool {
+    // Whitelist approach for maximum security
+    let safe_patterns = [
+        "date(", "time()", "now()",
+        "math.", "string.", 
+        "uuid()", "hash(",
+    ];
+    
+    // Block dangerous patterns
+    let dangerous_patterns = [
+        "os.", "io.", "debug.", "package.", "require", "load", "dofile", 
+        "loadfile", "loadstring", "rawget", "rawset", "getmetatable", 
+        "setmetatable", "_G", "_ENV", "coroutine", "collectgarbage"
+    ];
+    
+    // Check for dangerous patterns first
+    for pattern in &dangerous_patterns {
+        if expr.contains(pattern) {
+            tracing::warn!("🚨 Blocked dangerous Lua expression: {}", expr);
+            return false;
+        }
+    }
+    
+    // Check for safe patterns or simple expressions
+    for pattern in &safe_patterns {
+        if expr.contains(pattern) {
+            return true;
+        }
+    }
+    
+    // Allow simple expressions (numbers, strings, basic operations)
+    expr.len() < 200 && expr.chars().all(|c| c.is_alphanumeric() || " +-*/()[]{}.,\"'_%".contains(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn node_reference_quoting() {
+        assert_eq!(parse_node_reference(r#"$node("fetch")"#), Some(("fetch", "")));
+        assert_eq!(parse_node_reference("$node('fetch').email"), Some(("fetch", ".email")));
+        assert_eq!(parse_node_reference(r#"$node["fetch"].a.b"#), Some(("fetch", ".a.b")));
+        assert_eq!(parse_node_reference("$node['fetch']"), Some(("fetch", "")));
+        // The other quote character is part of the id
+        assert_eq!(parse_node_reference(r#"$node("it's")"#), Some(("it's", "")));
+    }
+
+    #[test]
+    fn node_reference_whitespace() {
+        assert_eq!(parse_node_reference(r#"$node( "fetch" ).email"#), Some(("fetch", ".email")));
+        assert_eq!(parse_node_reference("$node[ 'fetch' ]"), Some(("fetch", "")));
+        assert_eq!(parse_node_reference(r#"$node(" fetch ")"#), Some((" fetch ", "")));
+        assert_eq!(parse_node_reference(r#"$node ("fetch")"#), None);
+    }
+
+    #[test]
+    fn malformed_node_references() {
+        for expr in [
+            "$node",
+            "$node(",
+            "$node()",
+            r#"$node("")"#,
+            "$node(fetch)",
+            r#"$node("fetch)"#,
+            r#"$node("fetch')"#,
+            r#"$node("fetch"]"#,
+            r#"$node["fetch")"#,
+            r#"$nodes("fetch")"#,
+            r#"node("fetch")"#,
+        ] {
+            assert_eq!(parse_node_reference(expr), None, "{}", expr);
+        }
+    }
+
+    #[test]
+    fn node_pins() {
+        assert_eq!(
+            PinExpr::parse(r#"$node("fetch").customer.email"#),
+            PinExpr::NodeOutput { node_id: "fetch".to_string(), path: "customer.email".to_string() }
+        );
+        assert_eq!(
+            PinExpr::parse("$node('fetch')"),
+            PinExpr::NodeOutput { node_id: "fetch".to_string(), path: String::new() }
+        );
+        assert_eq!(pin_node_reference(r#"$node("fetch")email"#), None);
+        assert_eq!(pin_node_reference(r#"prefix $node("fetch")"#), None);
+    }
+
+    #[test]
+    fn template_references_only_in_pin_positions() {
+        assert_eq!(template_node_references(r#"$node("fetch").email"#), ["fetch"]);
+        assert_eq!(
+            template_node_references(r#"Hi {{ $node("fetch").name }}, order {{$node('order').id}} {{ $json.x }}"#),
+            ["fetch", "order"]
+        );
+        // Lua scripts and other text mentioning a pin are not templates
+        assert!(template_node_references(r#"return { email = '$node("fetch").email' }"#).is_empty());
+        assert!(template_node_references(r#"-- reads $node("fetch") later"#).is_empty());
+        assert!(template_node_references(r#"$json.x and $node("fetch")"#).is_empty());
+        assert!(template_node_references(r#"{{ $node("fetch") "#).is_empty());
+    }
+}
@@ -1,13 +1,90 @@
-/// Hot-reload workflow registry using ArcSwap
-/// 
-/// Provides lock-free, atomic updates to the in-memory workflow registry.
-/// Each workflow update swaps the entire registry pointer, ensuring zero-downtime
-/// hot reloads while concurrent executions continue uninterrupted.
+//! Hot-reload workflow registry using ArcSwap
+//! 
+//! Provides lock-free, atomic updates to the in-memory workflow registry.
+//! Each workflow update swaps the entire registry pointer, ensuring zero-downtime
+//! hot reloads while concurrent executions continue uninterrupted. Writes are
+//! serialized (read storage, compile, swap), so concurrent saves can't drop each
+//! other's update; every swap bumps the registry generation.
 
-use crate::workflow::{storage::WorkflowStorage, types::Workflow};
+use crate::workflow::{
+    canary::{CanaryMetrics, CompiledCanary, WorkflowCanary},
+    dependencies::WorkflowDependencies, environments::validate_environments, graph::ExecutionPlan,
+    params::{trigger_priority, validate_workflow_params}, pins::PinExpr, snooze::WorkflowSnooze,
+    storage::WorkflowStorage, types::Workflow, webhook_index::WebhookRouteIndex,
+};
 use anyhow::Result;
 use arc_swap::ArcSwap;
-use std::{collections::HashMap, sync::Arc};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::{
+    collections::HashMap,
+    sync::{atomic::{AtomicU64, Ordering}, Arc},
+};
+use tokio::sync::Mutex;
+
+/// Kind of a ChatTrigger model in conflict reports
+const CHAT_MODEL: &str = "chat model";
+/// Kind of an MCPTrigger tool in conflict reports
+const MCP_TOOL: &str = "MCP tool";
+
+/// A chat model or MCP tool (server-wide namespaces) served by a trigger node
+#[derive(Debug)]
+struct ServedTrigger {
+    kind: &'static str,
+    value: String,
+    node_id: String,
+    priority: i64,
+}
+
+/// Chat models and MCP tools served by a workflow's trigger nodes
+fn served_triggers(workflow: &Workflow) -> Vec<ServedTrigger> {
+    workflow.nodes.iter()
+        .filter_map(|node| {
+            let (kind, param) = match node.node_type {
+                crate::workflow::NodeType::ChatTrigger => (CHAT_MODEL, "model"),
+                crate::workflow::NodeType::MCPTrigger => (MCP_TOOL, "tool"),
+                _ => return None,
+            };
+            Some(ServedTrigger {
+                kind,
+                value: node.params.get(param)?.as_str()?.to_string(),
+                node_id: node.id.clone(),
+                priority: trigger_priority(&node.params).unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+/// A trigger of a saved workflow claiming a model or tool another workflow serves at the same priority
+#[derive(Debug, Clone, Serialize)]
+pub struct TriggerConflict {
+    /// "chat model" or "MCP tool"
+    pub kind: &'static str,
+    /// The contested model or tool
+    pub value: String,
+    /// Trigger node of the saved workflow
+    pub node_id: String,
+    pub conflicting_workflow_id: String,
+    pub conflicting_node_id: String,
+    /// Priority shared by both triggers
+    pub priority: i64,
+}
+
+/// Save-time trigger conflicts of a workflow (answered with 409 by the API)
+#[derive(Debug)]
+pub struct TriggerConflicts(pub Vec<TriggerConflict>);
+
+impl std::fmt::Display for TriggerConflicts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let conflicts: Vec<String> = self.0.iter()
+            .map(|c| format!("node '{}': {} '{}' is already served by workflow '{}' (node '{}')",
+                c.node_id, c.kind, c.value, c.conflicting_workflow_id, c.conflicting_node_id))
+            .collect();
+        write!(f, "Trigger conflict: {}", conflicts.join("; "))
+    }
+}
+
+impl std::error::Error for TriggerConflicts {}
 
 /// Lock-free workflow registry for hot-reload capabilities
 /// 
@@ -22,6 +99,12 @@ pub struct WorkflowRegistry {
     
     /// Reference to persistent storage for reload operations
     storage: WorkflowStorage,
+    
+    /// Held by every write from the storage read to the swap (readers never wait)
+    write_lock: Mutex<()>,
+    
+    /// Number of registry swaps since startup (debugging hot reloads)
+    generation: AtomicU64,
 }
 
 /// Compiled workflow with execution metadata
@@ -37,9 +120,38 @@ pub struct CompiledWorkflow {
     /// Format: ["/grade", "/payment"] - used to register Axum routes
     pub webhook_paths: Vec<String>,
     
-    /// Node IDs that are entry points (WebhookNode or CronTrigger types)
+    /// Node IDs that are entry points (WebhookNode, CronTrigger or EventTrigger types)
     /// Used to start execution when webhook is triggered or cron schedule fires
     pub start_node_ids: Vec<String>,
+    
+    /// Precompiled DAG, topological order and per-entry-point execution orders
+    /// Shared across executions so hot paths never rebuild the graph
+    pub plan: Arc<ExecutionPlan>,
+    
+    /// (method, path) -> webhook node lookup, including parameterized paths
+    pub webhook_index: Arc<WebhookRouteIndex>,
+    
+    /// Secrets, sub-workflows, hosts, topics and tables referenced by the nodes
+    pub dependencies: Arc<WorkflowDependencies>,
+    
+    /// Workflow variables (JSON object) exposed as `$vars.name` pins and the Lua `vars` table
+    pub variables: Arc<Value>,
+    
+    /// The workflow compiled once per environment profile (see environments)
+    pub environments: Arc<HashMap<String, CompiledWorkflow>>,
+    
+    /// Candidate version receiving a share of webhook traffic (see canary)
+    pub canary: Option<Arc<CompiledCanary>>,
+    
+    /// Paused triggers and pending one-time skip (see snooze)
+    pub snooze: Option<Arc<WorkflowSnooze>>,
+}
+
+impl CompiledWorkflow {
+    /// The workflow's snooze, while its triggers are paused
+    pub fn active_snooze(&self) -> Option<&WorkflowSnooze> {
+        self.snooze.as_deref().filter(|snooze| snooze.snoozed_until().is_some())
+    }
 }
 
 impl WorkflowRegistry {
@@ -48,6 +160,8 @@ impl WorkflowRegistry {
         Self {
             workflows: ArcSwap::new(Arc::new(HashMap::new())),
             storage,
+            write_lock: Mutex::new(()),
+            generation: AtomicU64::new(0),
         }
     }
 
@@ -56,11 +170,25 @@ impl WorkflowRegistry {
     /// Called during application startup to populate the in-memory registry.
     /// Compiles each workflow and extracts execution metadata.
     pub async fn init_from_storage(&self) -> Result<()> {
-        let stored_workflows = self.storage.load_all_workflows().await?;
-        let compiled_workflows = self.compile_workflows(stored_workflows)?;
+        let _write = self.write_lock.lock().await;
+        let mut stored_workflows = self.storage.load_all_workflows().await?;
+        stored_workflows.retain(|_, workflow| workflow.active);
+        // Workflows saved before their params were checked still load; their nodes fail when run
+        for workflow in stored_workflows.values() {
+            if let Err(e) = validate_workflow_params(workflow) {
+                tracing::warn!("⚠️ Workflow {}: {}", workflow.id, e);
+            }
+        }
+        let variables = self.storage.load_all_variables().await?;
+        let canaries = self.storage.load_all_canaries().await?;
+        let mut snoozes = self.storage.load_all_snoozes().await?;
+        let mut compiled_workflows = self.compile_workflows(stored_workflows, variables, canaries)?;
+        for (id, compiled) in compiled_workflows.iter_mut() {
+            compiled.snooze = snoozes.remove(id).map(Arc::new);
+        }
         
         // Atomic swap of the entire registry
-        self.workflows.store(Arc::new(compiled_workflows));
+        self.swap(compiled_workflows);
         
         tracing::info!("Initialized workflow registry with {} workflows", 
             self.workflows.load().len());
@@ -68,31 +196,135 @@ impl WorkflowRegistry {
         Ok(())
     }
 
+    /// Reload every workflow from storage every `interval_secs` (0 disables)
+    /// 
+    /// Follower instances don't see the hot reloads of the primary that saves
+    /// the workflows, so they pick its changes up this way.
+    pub fn start_periodic_reload(self: Arc<Self>, interval_secs: u64) {
+        if interval_secs == 0 {
+            return;
+        }
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+            // The first tick completes immediately; the registry was just loaded
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.init_from_storage().await {
+                    tracing::warn!("⚠️ Failed to reload workflows from storage: {}", e);
+                }
+            }
+        });
+    }
+
     /// Hot-reload a single workflow
     /// 
     /// Updates or adds a workflow to the registry using atomic pointer swap.
-    /// This operation is lock-free and doesn't block concurrent executions.
+    /// This operation doesn't block concurrent executions; concurrent reloads run one
+    /// after another, each from the latest stored state.
+    /// Inactive workflows are compiled (validated) and taken out of the registry.
     pub async fn reload_workflow(&self, workflow_id: &str) -> Result<()> {
+        let _write = self.write_lock.lock().await;
+        
         // Load fresh workflow from storage
         let workflow = self.storage.get_workflow(workflow_id).await?
             .ok_or_else(|| anyhow::anyhow!("Workflow not found: {}", workflow_id))?;
         
+        let variables = self.storage.get_variables(workflow_id).await?;
+        let canary = self.storage.get_canary(workflow_id).await?;
+        let snooze = self.storage.get_snooze(workflow_id).await?;
+        
         // Compile the workflow
-        let compiled = self.compile_single_workflow(workflow)?;
+        let mut compiled = self.compile_single_workflow(workflow, variables.clone())?;
+        if !compiled.workflow.active {
+            self.remove_locked(workflow_id);
+            return Ok(());
+        }
         
         // Clone current registry and update it
         let current = self.workflows.load();
+        let previous = current.get(workflow_id).and_then(|compiled| compiled.canary.as_deref());
+        compiled.canary = canary.and_then(|canary| self.compile_canary(canary, variables, previous));
+        compiled.snooze = snooze.map(Arc::new);
         let mut new_registry = (**current).clone();
         new_registry.insert(workflow_id.to_string(), compiled);
         
         // Atomic swap to new registry
-        self.workflows.store(Arc::new(new_registry));
+        self.swap(new_registry);
         
         tracing::info!("Hot-reloaded workflow: {}", workflow_id);
         
         Ok(())
     }
 
+    /// Consume a workflow's pending one-time skip; true when this scheduled run is to be skipped
+    pub async fn take_skip_next(&self, workflow_id: &str) -> Result<bool> {
+        let pending = self.get_workflow(workflow_id)
+            .and_then(|compiled| compiled.snooze)
+            .is_some_and(|snooze| snooze.skip_next);
+        if !pending || !self.storage.take_skip_next(workflow_id).await? {
+            return Ok(false);
+        }
+        self.reload_workflow(workflow_id).await?;
+        Ok(true)
+    }
+
+    /// Check that a workflow definition compiles, without registering it
+    /// 
+    /// Covers everything a hot reload would reject (start nodes, duplicate node IDs,
+    /// edges to unknown nodes or ports, cycles, duplicate webhook routes and WebSocket
+    /// paths, settings, environment profiles) and, for active workflows, chat models
+    /// and MCP tools already served by another workflow. Node params are checked by
+    /// `validate_workflow_params`.
+    pub fn validate_workflow(&self, workflow: &Workflow) -> Result<()> {
+        self.compile_single_workflow(workflow.clone(), Map::new())?;
+        if workflow.active {
+            self.check_trigger_conflicts(workflow)?;
+        }
+        Ok(())
+    }
+
+    /// Reject chat models and MCP tools (server-wide namespaces) already served
+    /// by another registered workflow
+    /// 
+    /// A trigger with a higher `priority` than the other workflow's takes the
+    /// model or tool over (and a lower one leaves it to the other workflow);
+    /// equal priorities are a conflict. Every conflict is reported at once.
+    fn check_trigger_conflicts(&self, workflow: &Workflow) -> Result<()> {
+        let own = served_triggers(workflow);
+        for (index, trigger) in own.iter().enumerate() {
+            if let Some(other) = own[..index].iter().find(|other| other.kind == trigger.kind && other.value == trigger.value) {
+                return Err(anyhow::anyhow!("Nodes '{}' and '{}' both serve {} '{}'", other.node_id, trigger.node_id, trigger.kind, trigger.value));
+            }
+        }
+
+        let mut conflicts = Vec::new();
+        for (other_id, other) in self.workflows.load().iter() {
+            if *other_id == workflow.id {
+                continue;
+            }
+            for served in served_triggers(&other.workflow) {
+                let clashing = own.iter()
+                    .filter(|trigger| trigger.kind == served.kind && trigger.value == served.value && trigger.priority == served.priority);
+                for trigger in clashing {
+                    conflicts.push(TriggerConflict {
+                        kind: trigger.kind,
+                        value: trigger.value.clone(),
+                        node_id: trigger.node_id.clone(),
+                        conflicting_workflow_id: other_id.clone(),
+                        conflicting_node_id: served.node_id.clone(),
+                        priority: trigger.priority,
+                    });
+                }
+            }
+        }
+        if conflicts.is_empty() {
+            return Ok(());
+        }
+        conflicts.sort_by(|a, b| (&a.node_id, &a.conflicting_workflow_id).cmp(&(&b.node_id, &b.conflicting_workflow_id)));
+        Err(TriggerConflicts(conflicts).into())
+    }
+
     /// Get a workflow by ID (lock-free read)
     /// 
     /// Returns a cloned CompiledWorkflow for execution. The clone is cheap since
@@ -109,6 +341,43 @@ impl WorkflowRegistry {
             .collect()
     }
 
+    /// Models served by ChatTrigger nodes as (model, workflow_id, node_id)
+    /// 
+    /// Sorted by model, then by trigger priority (highest first) and workflow ID,
+    /// so the first entry of a model is the one serving it.
+    pub fn get_chat_models(&self) -> Vec<(String, String, String)> {
+        self.served_by_priority(CHAT_MODEL)
+    }
+
+    /// Tools served by MCPTrigger nodes as (tool, workflow_id, node_id), ordered like `get_chat_models`
+    pub fn get_mcp_tools(&self) -> Vec<(String, String, String)> {
+        self.served_by_priority(MCP_TOOL)
+    }
+
+    /// Values of one trigger kind as (value, workflow_id, node_id), serving trigger first per value
+    fn served_by_priority(&self, kind: &str) -> Vec<(String, String, String)> {
+        let workflows = self.workflows.load();
+        let mut served: Vec<(String, std::cmp::Reverse<i64>, String, String)> = workflows.iter()
+            .flat_map(|(workflow_id, compiled)| served_triggers(&compiled.workflow).into_iter()
+                .filter(|trigger| trigger.kind == kind)
+                .map(move |trigger| (trigger.value, std::cmp::Reverse(trigger.priority), workflow_id.clone(), trigger.node_id)))
+            .collect();
+        served.sort();
+        served.into_iter()
+            .map(|(value, _, workflow_id, node_id)| (value, workflow_id, node_id))
+            .collect()
+    }
+
+    /// The WebSocketTrigger of one workflow serving a path, as (workflow, node_id)
+    pub fn get_workflow_websocket_trigger(&self, workflow_id: &str, path: &str) -> Option<(Workflow, String)> {
+        let workflows = self.workflows.load();
+        let compiled = workflows.get(workflow_id)?;
+        compiled.workflow.nodes.iter()
+            .find(|node| matches!(node.node_type, crate::workflow::NodeType::WebSocketTrigger)
+                && node.params.get("path").and_then(|p| p.as_str()) == Some(path))
+            .map(|node| (compiled.workflow.clone(), node.id.clone()))
+    }
+
     /// List all active workflow IDs
     pub fn list_workflow_ids(&self) -> Vec<String> {
         self.workflows.load().keys().cloned().collect()
@@ -133,37 +402,97 @@ impl WorkflowRegistry {
 
     /// Remove a workflow from registry
     pub async fn remove_workflow(&self, workflow_id: &str) -> Result<()> {
+        let _write = self.write_lock.lock().await;
+        self.remove_locked(workflow_id);
+        Ok(())
+    }
+
+    /// Number of registry swaps since startup
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Relaxed)
+    }
+
+    /// Remove a workflow (caller holds the write lock)
+    fn remove_locked(&self, workflow_id: &str) {
         let current = self.workflows.load();
         let mut new_registry = (**current).clone();
         
         if new_registry.remove(workflow_id).is_some() {
-            self.workflows.store(Arc::new(new_registry));
+            self.swap(new_registry);
             tracing::info!("Removed workflow from registry: {}", workflow_id);
         }
-        
-        Ok(())
+    }
+
+    /// Publish a new workflow map (caller holds the write lock)
+    fn swap(&self, workflows: HashMap<String, CompiledWorkflow>) {
+        self.workflows.store(Arc::new(workflows));
+        let generation = self.generation.fetch_add(1, Ordering::Relaxed) + 1;
+        tracing::debug!("Registry generation {}", generation);
     }
 
     /// Compile multiple workflows into execution-ready format
-    fn compile_workflows(&self, workflows: HashMap<String, Workflow>) -> Result<HashMap<String, CompiledWorkflow>> {
+    fn compile_workflows(
+        &self,
+        workflows: HashMap<String, Workflow>,
+        mut variables: HashMap<String, Map<String, Value>>,
+        mut canaries: HashMap<String, WorkflowCanary>,
+    ) -> Result<HashMap<String, CompiledWorkflow>> {
         let mut compiled = HashMap::new();
         
         for (id, workflow) in workflows {
-            let compiled_workflow = self.compile_single_workflow(workflow)?;
+            let variables = variables.remove(&id).unwrap_or_default();
+            // Workflows stored before save-time validation may not compile; they stay out of the registry
+            let mut compiled_workflow = match self.compile_single_workflow(workflow, variables.clone()) {
+                Ok(compiled_workflow) => compiled_workflow,
+                Err(e) => {
+                    tracing::error!("❌ Workflow {} doesn't compile and is not loaded: {}", id, e);
+                    continue;
+                }
+            };
+            compiled_workflow.canary = canaries.remove(&id).and_then(|canary| self.compile_canary(canary, variables, None));
             compiled.insert(id, compiled_workflow);
         }
         
         Ok(compiled)
     }
 
+    /// Compile a workflow's canary version
+    /// 
+    /// A canary that no longer compiles is skipped (with a warning) so the stable
+    /// version keeps serving. Metrics carry over from `previous` while the rollout
+    /// ID is unchanged (e.g. only the percentage moved).
+    fn compile_canary(&self, canary: WorkflowCanary, variables: Map<String, Value>, previous: Option<&CompiledCanary>) -> Option<Arc<CompiledCanary>> {
+        let workflow_id = canary.workflow.id.clone();
+        let workflow = match self.compile_single_workflow(canary.workflow, variables) {
+            Ok(workflow) => workflow,
+            Err(e) => {
+                tracing::warn!("⚠️ Skipping canary {} of workflow {}: {}", canary.id, workflow_id, e);
+                return None;
+            }
+        };
+        let metrics = previous.filter(|previous| previous.id == canary.id)
+            .map(|previous| previous.metrics.clone())
+            .unwrap_or_else(|| Arc::new(CanaryMetrics::default()));
+        Some(Arc::new(CompiledCanary { id: canary.id, percent: canary.percent, workflow, metrics }))
+    }
+
     /// Compile a single workflow and extract execution metadata
     /// 
     /// Analyzes the workflow to extract:
     /// - Webhook paths from WebhookNode params
     /// - Start node IDs (nodes with WebhookNode or CronTrigger type)
     /// - Validation of node structure
-    fn compile_single_workflow(&self, workflow: Workflow) -> Result<CompiledWorkflow> {
+    /// - Precompiled input pin expressions
+    /// - The webhook route index
+    /// - The execution plan (DAG, toposort, reachability per entry point)
+    fn compile_single_workflow(&self, mut workflow: Workflow, variables: Map<String, Value>) -> Result<CompiledWorkflow> {
         let mut webhook_paths = Vec::new();
+        let mut websocket_paths: HashMap<&str, &str> = HashMap::new();
+        
+        // Parse pin expressions once instead of on every execution
+        for node in &mut workflow.nodes {
+            node.compiled_inputs = node.inputs.as_deref().map(PinExpr::compile_all);
+        }
         let mut start_node_ids = Vec::new();
         
         // Extract metadata from nodes
@@ -181,19 +510,90 @@ impl WorkflowRegistry {
                     start_node_ids.push(node.id.clone());
                     // CronTrigger nodes are also valid start nodes (background triggers)
                 }
+                crate::workflow::NodeType::PollingTrigger => {
+                    start_node_ids.push(node.id.clone());
+                    // PollingTrigger nodes start executions from the scheduler when new records arrive
+                }
+                crate::workflow::NodeType::FormTrigger => {
+                    start_node_ids.push(node.id.clone());
+                    // FormTrigger nodes start executions from hosted form submissions
+                }
+                crate::workflow::NodeType::ChatTrigger => {
+                    start_node_ids.push(node.id.clone());
+                    // ChatTrigger nodes start executions from chat completion requests
+                }
+                crate::workflow::NodeType::EventTrigger => {
+                    start_node_ids.push(node.id.clone());
+                    // EventTrigger nodes start executions from the internal event bus
+                }
+                crate::workflow::NodeType::MQTTTrigger => {
+                    start_node_ids.push(node.id.clone());
+                    // MQTTTrigger nodes start executions from the MQTT subscriber service
+                }
+                crate::workflow::NodeType::WebSocketTrigger => {
+                    start_node_ids.push(node.id.clone());
+                    // WebSocketTrigger nodes start executions from WebSocket client messages
+                    // (served under the workflow's ID, so paths only have to be unique per workflow)
+                    if let Some(path) = node.params.get("path").and_then(|p| p.as_str()) {
+                        if let Some(other) = websocket_paths.insert(path, &node.id) {
+                            return Err(anyhow::anyhow!("Nodes '{}' and '{}' both serve WebSocket path '{}'", other, node.id, path));
+                        }
+                    }
+                }
+                crate::workflow::NodeType::OpcUaTrigger => {
+                    start_node_ids.push(node.id.clone());
+                    // OpcUaTrigger nodes start executions from the OPC UA subscription service
+                }
+                crate::workflow::NodeType::SerialTrigger => {
+                    start_node_ids.push(node.id.clone());
+                    // SerialTrigger nodes start executions from the serial port reader service
+                }
+                crate::workflow::NodeType::BLETrigger => {
+                    start_node_ids.push(node.id.clone());
+                    // BLETrigger nodes start executions from the BLE trigger service
+                }
+                crate::workflow::NodeType::MCPTrigger => {
+                    start_node_ids.push(node.id.clone());
+                    // MCPTrigger nodes start executions from MCP tool calls
+                }
                 _ => {}
             }
         }
         
-        // Validate that workflow has at least one start node (Webhook or CronTrigger)
+        // Validate that workflow has at least one start node (Webhook, CronTrigger, PollingTrigger, FormTrigger, ChatTrigger, EventTrigger, MQTTTrigger, WebSocketTrigger, OpcUaTrigger, SerialTrigger, BLETrigger or MCPTrigger)
         if start_node_ids.is_empty() {
-            return Err(anyhow::anyhow!("Workflow must have at least one start node (Webhook or CronTrigger)"));
+            return Err(anyhow::anyhow!("Workflow must have at least one start node (Webhook, CronTrigger, PollingTrigger, FormTrigger, ChatTrigger, EventTrigger, MQTTTrigger, WebSocketTrigger, OpcUaTrigger, SerialTrigger, BLETrigger or MCPTrigger)"));
         }
         
+        if let Some(callbacks) = &workflow.settings.callbacks {
+            callbacks.validate().map_err(|e| anyhow::anyhow!("Invalid callbacks setting: {}", e))?;
+        }
+        
+        let plan = Arc::new(ExecutionPlan::build(&workflow)?);
+        let webhook_index = Arc::new(WebhookRouteIndex::build(&workflow)?);
+        let dependencies = Arc::new(WorkflowDependencies::analyze(&workflow));
+        
+        // Each environment profile gets its own variant (without profiles of its own)
+        validate_environments(&workflow)?;
+        let environments = workflow.environments.iter()
+            .map(|(name, profile)| {
+                let variant = self.compile_single_workflow(profile.apply(&workflow), profile.variables(&variables))
+                    .map_err(|e| anyhow::anyhow!("Environment '{}': {}", name, e))?;
+                Ok((name.clone(), variant))
+            })
+            .collect::<Result<HashMap<_, _>>>()?;
+        
         Ok(CompiledWorkflow {
             workflow,
             webhook_paths,
             start_node_ids,
+            plan,
+            webhook_index,
+            dependencies,
+            variables: Arc::new(Value::Object(variables)),
+            environments: Arc::new(environments),
+            canary: None,
+            snooze: None,
         })
     }
 }
@@ -0,0 +1,42 @@
+//! Snoozed workflows
+//! 
+//! Snoozing pauses every trigger of a workflow until a given time (maintenance
+//! windows): scheduled runs are skipped, webhooks answer 503 with Retry-After,
+//! the execution queue rejects runs and the trigger services drop their events.
+//! A one-time skip only drops the next scheduled (cron or polling) run. Both are
+//! stored next to the definition and survive restarts; a snooze that has
+//! expired simply stops applying.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Snooze state of a workflow
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkflowSnooze {
+    /// Triggers are paused until this time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<DateTime<Utc>>,
+    /// The next scheduled run is skipped (then the flag clears)
+    #[serde(default)]
+    pub skip_next: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl WorkflowSnooze {
+    /// End of the snooze, while it lasts
+    pub fn snoozed_until(&self) -> Option<DateTime<Utc>> {
+        self.until.filter(|until| *until > Utc::now())
+    }
+
+    /// Whether the snooze or the skip still applies
+    pub fn is_active(&self) -> bool {
+        self.skip_next || self.snoozed_until().is_some()
+    }
+
+    /// Seconds until the snooze ends (Retry-After), at least 1
+    pub fn retry_after_secs(&self) -> u64 {
+        self.snoozed_until()
+            .map_or(1, |until| (until - Utc::now()).num_seconds().max(1) as u64)
+    }
+}
@@ -1,10 +1,13 @@
-/// SQLite persistence layer for workflow storage
-/// 
-/// Handles workflow CRUD operations in the main SQLite database.
-/// Workflows are stored as JSON for flexibility while maintaining structured queries.
+//! SQLite persistence layer for workflow storage
+//! 
+//! Handles workflow CRUD operations in the main SQLite database.
+//! Workflows are stored as JSON for flexibility while maintaining structured queries.
+//! Large definitions are stored compressed (see project::compression).
 
-use crate::workflow::types::Workflow;
+use crate::project::compression::{self, StorageCompression};
+use crate::workflow::{canary::WorkflowCanary, snooze::WorkflowSnooze, test_cases::WorkflowTestCase, types::Workflow};
 use anyhow::Result;
+use serde_json::{Map, Value};
 use sqlx::{sqlite::SqlitePool, Row};
 use std::collections::HashMap;
 
@@ -16,17 +19,25 @@ use std::collections::HashMap;
 pub struct WorkflowStorage {
     /// SQLite connection pool for workflow database
     pool: SqlitePool,
+    /// Codec for large definitions
+    compression: StorageCompression,
 }
 
 impl WorkflowStorage {
     /// Create new storage instance with database connection
     pub fn new(pool: SqlitePool) -> Self {
-        Self { pool }
+        Self { pool, compression: StorageCompression::default() }
+    }
+
+    /// Compress large definitions with the given codec (zstd by default)
+    pub fn with_compression(mut self, compression: StorageCompression) -> Self {
+        self.compression = compression;
+        self
     }
 
     /// Initialize the workflow storage schema
     /// 
-    /// Creates the workflows table with JSON storage and necessary indexes.
+    /// Creates the workflows, workflow_tests, workflow_variables, workflow_canaries and workflow_snoozes tables with JSON storage and necessary indexes.
     /// Safe to call multiple times (uses IF NOT EXISTS).
     pub async fn init_schema(&self) -> Result<()> {
         sqlx::query(
@@ -43,6 +54,54 @@ impl WorkflowStorage {
         .execute(&self.pool)
         .await?;
 
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS workflow_tests (
+                workflow_id TEXT PRIMARY KEY,
+                cases JSON NOT NULL,
+                updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS workflow_variables (
+                workflow_id TEXT PRIMARY KEY,
+                variables JSON NOT NULL,
+                updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS workflow_canaries (
+                workflow_id TEXT PRIMARY KEY,
+                canary JSON NOT NULL,
+                updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS workflow_snoozes (
+                workflow_id TEXT PRIMARY KEY,
+                snooze JSON NOT NULL,
+                updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
         // Create index on name for fast lookups
         sqlx::query(
             r#"
@@ -61,7 +120,7 @@ impl WorkflowStorage {
     /// Uses UPSERT to handle both create and update operations atomically.
     /// Updates the updated_at timestamp automatically.
     pub async fn save_workflow(&self, workflow: &Workflow) -> Result<()> {
-        let definition_json = serde_json::to_string(workflow)?;
+        let definition = self.compression.encode(serde_json::to_string(workflow)?)?;
 
         sqlx::query(
             r#"
@@ -75,7 +134,7 @@ impl WorkflowStorage {
         )
         .bind(&workflow.id)
         .bind(&workflow.name)  
-        .bind(&definition_json)
+        .bind(definition)
         .execute(&self.pool)
         .await?;
 
@@ -91,7 +150,7 @@ impl WorkflowStorage {
 
         match row {
             Some(row) => {
-                let definition_json: String = row.get("definition");
+                let definition_json = compression::decode(row.get("definition"))?;
                 let workflow: Workflow = serde_json::from_str(&definition_json)?;
                 Ok(Some(workflow))
             }
@@ -102,18 +161,39 @@ impl WorkflowStorage {
     /// List all workflows with basic metadata
     pub async fn list_workflows(&self) -> Result<Vec<WorkflowMetadata>> {
         let rows = sqlx::query(
-            "SELECT id, name, created_at, updated_at FROM workflows ORDER BY updated_at DESC"
+            r#"
+            SELECT w.id, w.name, w.created_at, w.updated_at, s.snooze,
+                   CASE WHEN typeof(w.definition) = 'blob' THEN w.definition END AS compressed_definition,
+                   CASE WHEN typeof(w.definition) = 'text' THEN COALESCE(json_extract(w.definition, '$.active'), 1) END AS active
+            FROM workflows w
+            LEFT JOIN workflow_snoozes s ON s.workflow_id = w.id
+            ORDER BY w.updated_at DESC
+            "#
         )
         .fetch_all(&self.pool)
         .await?;
 
         let mut workflows = Vec::new();
         for row in rows {
+            let snooze = match row.get::<Option<String>, _>("snooze") {
+                Some(snooze_json) => Some(serde_json::from_str::<WorkflowSnooze>(&snooze_json)?),
+                None => None,
+            };
+            // Compressed definitions can't be read by json_extract
+            let active = match row.get::<Option<Vec<u8>>, _>("compressed_definition") {
+                Some(definition) => serde_json::from_str::<Value>(&compression::decode(definition)?)?
+                    .get("active")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(true),
+                None => row.get::<i64, _>("active") != 0,
+            };
             workflows.push(WorkflowMetadata {
                 id: row.get("id"),
                 name: row.get("name"),
                 created_at: row.get("created_at"),
                 updated_at: row.get("updated_at"),
+                active,
+                snooze: snooze.filter(WorkflowSnooze::is_active),
             });
         }
 
@@ -132,7 +212,7 @@ impl WorkflowStorage {
         let mut workflows = HashMap::new();
         for row in rows {
             let id: String = row.get("id");
-            let definition_json: String = row.get("definition");
+            let definition_json = compression::decode(row.get("definition"))?;
             let workflow: Workflow = serde_json::from_str(&definition_json)?;
             workflows.insert(id, workflow);
         }
@@ -140,13 +220,244 @@ impl WorkflowStorage {
         Ok(workflows)
     }
 
-    /// Delete a workflow by ID
+    /// Delete a workflow (and its test cases, variables and canary) by ID
     pub async fn delete_workflow(&self, id: &str) -> Result<bool> {
         let result = sqlx::query("DELETE FROM workflows WHERE id = ?")
             .bind(id)
             .execute(&self.pool)
             .await?;
 
+        sqlx::query("DELETE FROM workflow_tests WHERE workflow_id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("DELETE FROM workflow_variables WHERE workflow_id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        self.delete_canary(id).await?;
+        self.delete_snooze(id).await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Get the test cases attached to a workflow (empty when none are stored)
+    pub async fn get_tests(&self, workflow_id: &str) -> Result<Vec<WorkflowTestCase>> {
+        let row = sqlx::query("SELECT cases FROM workflow_tests WHERE workflow_id = ?")
+            .bind(workflow_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        match row {
+            Some(row) => {
+                let cases_json: String = row.get("cases");
+                Ok(serde_json::from_str(&cases_json)?)
+            }
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Replace the test cases attached to a workflow
+    pub async fn save_tests(&self, workflow_id: &str, cases: &[WorkflowTestCase]) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO workflow_tests (workflow_id, cases, updated_at)
+            VALUES (?, ?, CURRENT_TIMESTAMP)
+            ON CONFLICT(workflow_id) DO UPDATE SET
+                cases = excluded.cases,
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(workflow_id)
+        .bind(serde_json::to_string(cases)?)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Get a workflow's variables (empty when none are stored)
+    pub async fn get_variables(&self, workflow_id: &str) -> Result<Map<String, Value>> {
+        let row = sqlx::query("SELECT variables FROM workflow_variables WHERE workflow_id = ?")
+            .bind(workflow_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        match row {
+            Some(row) => {
+                let variables_json: String = row.get("variables");
+                Ok(serde_json::from_str(&variables_json)?)
+            }
+            None => Ok(Map::new()),
+        }
+    }
+
+    /// Load the variables of all workflows that have any
+    pub async fn load_all_variables(&self) -> Result<HashMap<String, Map<String, Value>>> {
+        let rows = sqlx::query("SELECT workflow_id, variables FROM workflow_variables")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut variables = HashMap::new();
+        for row in rows {
+            let variables_json: String = row.get("variables");
+            variables.insert(row.get("workflow_id"), serde_json::from_str(&variables_json)?);
+        }
+        Ok(variables)
+    }
+
+    /// Replace a workflow's variables
+    pub async fn save_variables(&self, workflow_id: &str, variables: &Map<String, Value>) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO workflow_variables (workflow_id, variables, updated_at)
+            VALUES (?, ?, CURRENT_TIMESTAMP)
+            ON CONFLICT(workflow_id) DO UPDATE SET
+                variables = excluded.variables,
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(workflow_id)
+        .bind(serde_json::to_string(variables)?)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Get a workflow's canary version, if one is rolling out
+    pub async fn get_canary(&self, workflow_id: &str) -> Result<Option<WorkflowCanary>> {
+        let row = sqlx::query("SELECT canary FROM workflow_canaries WHERE workflow_id = ?")
+            .bind(workflow_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        match row {
+            Some(row) => {
+                let canary_json: String = row.get("canary");
+                Ok(Some(serde_json::from_str(&canary_json)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Load the canaries of all workflows that have one
+    pub async fn load_all_canaries(&self) -> Result<HashMap<String, WorkflowCanary>> {
+        let rows = sqlx::query("SELECT workflow_id, canary FROM workflow_canaries")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut canaries = HashMap::new();
+        for row in rows {
+            let canary_json: String = row.get("canary");
+            canaries.insert(row.get("workflow_id"), serde_json::from_str(&canary_json)?);
+        }
+        Ok(canaries)
+    }
+
+    /// Store (or replace) a workflow's canary version
+    pub async fn save_canary(&self, workflow_id: &str, canary: &WorkflowCanary) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO workflow_canaries (workflow_id, canary, updated_at)
+            VALUES (?, ?, CURRENT_TIMESTAMP)
+            ON CONFLICT(workflow_id) DO UPDATE SET
+                canary = excluded.canary,
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(workflow_id)
+        .bind(serde_json::to_string(canary)?)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Drop a workflow's canary version; true when one existed
+    pub async fn delete_canary(&self, workflow_id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM workflow_canaries WHERE workflow_id = ?")
+            .bind(workflow_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Get a workflow's snooze state, if it has one (possibly expired)
+    pub async fn get_snooze(&self, workflow_id: &str) -> Result<Option<WorkflowSnooze>> {
+        let row = sqlx::query("SELECT snooze FROM workflow_snoozes WHERE workflow_id = ?")
+            .bind(workflow_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        match row {
+            Some(row) => {
+                let snooze_json: String = row.get("snooze");
+                Ok(Some(serde_json::from_str(&snooze_json)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Load the snooze state of all workflows that have one
+    pub async fn load_all_snoozes(&self) -> Result<HashMap<String, WorkflowSnooze>> {
+        let rows = sqlx::query("SELECT workflow_id, snooze FROM workflow_snoozes")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut snoozes = HashMap::new();
+        for row in rows {
+            let snooze_json: String = row.get("snooze");
+            snoozes.insert(row.get("workflow_id"), serde_json::from_str(&snooze_json)?);
+        }
+        Ok(snoozes)
+    }
+
+    /// Store (or replace) a workflow's snooze state
+    pub async fn save_snooze(&self, workflow_id: &str, snooze: &WorkflowSnooze) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO workflow_snoozes (workflow_id, snooze, updated_at)
+            VALUES (?, ?, CURRENT_TIMESTAMP)
+            ON CONFLICT(workflow_id) DO UPDATE SET
+                snooze = excluded.snooze,
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(workflow_id)
+        .bind(serde_json::to_string(snooze)?)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Clear a workflow's snooze state; true when one existed
+    pub async fn delete_snooze(&self, workflow_id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM workflow_snoozes WHERE workflow_id = ?")
+            .bind(workflow_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Clear a pending one-time skip; true for the one caller that cleared it
+    pub async fn take_skip_next(&self, workflow_id: &str) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE workflow_snoozes
+            SET snooze = json_set(snooze, '$.skip_next', json('false')), updated_at = CURRENT_TIMESTAMP
+            WHERE workflow_id = ? AND json_extract(snooze, '$.skip_next') = 1
+            "#,
+        )
+        .bind(workflow_id)
+        .execute(&self.pool)
+        .await?;
+
         Ok(result.rows_affected() > 0)
     }
 }
@@ -158,4 +469,9 @@ pub struct WorkflowMetadata {
     pub name: String,
     pub created_at: String,
     pub updated_at: String,
+    /// false for drafts
+    pub active: bool,
+    /// Present while the workflow is snoozed or its next scheduled run is skipped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snooze: Option<WorkflowSnooze>,
 }
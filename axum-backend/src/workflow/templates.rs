@@ -0,0 +1,212 @@
+//! Built-in workflow templates
+//! 
+//! A small library of starting points served by the templates API. Template
+//! workflows contain `${name}` placeholders that are filled from the template's
+//! parameters (request values or defaults) plus `${project}` and `${workflow_id}`.
+//! A placeholder making up a whole string is replaced by the parameter's JSON
+//! value (so numbers stay numbers), otherwise by its text.
+//! 
+//! Instantiated workflows are inactive drafts: they are validated and stored,
+//! but their triggers only fire once the workflow is saved with "active": true.
+
+use crate::workflow::types::Workflow;
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+/// Template parameter with its default value
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateParam {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub default: Value,
+}
+
+/// Built-in workflow template
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkflowTemplate {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    /// "ingest", "etl", "ai" or "iot"
+    pub category: &'static str,
+    pub params: Vec<TemplateParam>,
+    /// Workflow definition with placeholders (no id / project)
+    pub workflow: Value,
+}
+
+impl WorkflowTemplate {
+    /// Build a draft workflow of `project` from this template
+    /// 
+    /// `values` override parameter defaults; unknown names are rejected.
+    pub fn instantiate(&self, project: &str, workflow_id: &str, name: Option<&str>, values: &Map<String, Value>) -> Result<Workflow> {
+        if let Some(unknown) = values.keys().find(|key| !self.params.iter().any(|param| param.name == key.as_str())) {
+            return Err(anyhow::anyhow!("Unknown parameter '{}' for template '{}'", unknown, self.id));
+        }
+
+        let mut vars: Map<String, Value> = self.params.iter()
+            .map(|param| (param.name.to_string(), values.get(param.name).cloned().unwrap_or_else(|| param.default.clone())))
+            .collect();
+        vars.insert("project".to_string(), json!(project));
+        vars.insert("workflow_id".to_string(), json!(workflow_id));
+
+        let mut definition = fill(&self.workflow, &vars)?;
+        definition["id"] = json!(workflow_id);
+        definition["name"] = json!(name.unwrap_or(self.name));
+        definition["project"] = json!(project);
+        definition["active"] = json!(false);
+        Ok(serde_json::from_value(definition)?)
+    }
+}
+
+/// Replace `${name}` placeholders throughout a JSON value
+fn fill(value: &Value, vars: &Map<String, Value>) -> Result<Value> {
+    Ok(match value {
+        Value::String(text) => {
+            if let Some(name) = text.strip_prefix("${").and_then(|rest| rest.strip_suffix('}')).filter(|name| vars.contains_key(*name)) {
+                return Ok(vars[name].clone());
+            }
+            let mut filled = String::new();
+            let mut rest = text.as_str();
+            while let Some(start) = rest.find("${") {
+                let end = rest[start..].find('}')
+                    .ok_or_else(|| anyhow::anyhow!("Unterminated placeholder in '{}'", text))? + start;
+                let name = &rest[start + 2..end];
+                let var = vars.get(name).ok_or_else(|| anyhow::anyhow!("Unknown placeholder '${{{}}}'", name))?;
+                filled.push_str(&rest[..start]);
+                match var {
+                    Value::String(s) => filled.push_str(s),
+                    other => filled.push_str(&other.to_string()),
+                }
+                rest = &rest[end + 1..];
+            }
+            filled.push_str(rest);
+            Value::String(filled)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(|item| fill(item, vars)).collect::<Result<_>>()?),
+        Value::Object(map) => Value::Object(map.iter()
+            .map(|(key, item)| Ok((key.clone(), fill(item, vars)?)))
+            .collect::<Result<_>>()?),
+        other => other.clone(),
+    })
+}
+
+/// Look up a built-in template
+pub fn find_template(id: &str) -> Option<WorkflowTemplate> {
+    builtin_templates().into_iter().find(|template| template.id == id)
+}
+
+/// All built-in templates
+pub fn builtin_templates() -> Vec<WorkflowTemplate> {
+    let param = |name, description, default| TemplateParam { name, description, default };
+    vec![
+        WorkflowTemplate {
+            id: "webhook-to-table",
+            name: "Webhook to table",
+            description: "Receive JSON on a webhook, reshape it with Lua and store it in a SimpleTable (with run audit columns)",
+            category: "ingest",
+            params: vec![
+                param("path", "Webhook path", json!("/ingest")),
+                param("table", "SimpleTable to write", json!("events")),
+            ],
+            workflow: json!({
+                "nodes": [
+                    { "id": "receive", "node_type": "Webhook", "params": { "path": "${path}", "method": "POST" } },
+                    { "id": "transform", "node_type": "FunLogic", "params": { "script":
+                        "local item = data[1] return { name = item.name, value = item.value }" } },
+                    { "id": "store", "node_type": "SimpleTableWriter",
+                      "params": { "table": "${table}", "columns": ["name", "value"], "audit_columns": true },
+                      "inputs": ["$json.name", "$json.value"] }
+                ],
+                "edges": [
+                    { "from": "receive", "to": "transform" },
+                    { "from": "transform", "to": "store" }
+                ]
+            }),
+        },
+        WorkflowTemplate {
+            id: "cron-etl",
+            name: "Nightly ETL rollup",
+            description: "Every night, aggregate yesterday's rows in a source PostgreSQL database and load the rollup into a warehouse table",
+            category: "etl",
+            params: vec![
+                param("schedule", "Cron schedule (with seconds)", json!("0 0 2 * * *")),
+                param("source_secret", "Secret holding the source database URL", json!("source_db")),
+                param("target_secret", "Secret holding the warehouse database URL", json!("warehouse_db")),
+                param("table", "Warehouse table (in mway_dynamic_tables)", json!("daily_orders")),
+                param("query", "Extraction query returning day, orders and revenue", json!(
+                    "SELECT CAST(CURRENT_DATE - 1 AS TEXT) AS day, COUNT(*) AS orders, COALESCE(SUM(total), 0)::float8 AS revenue \
+                     FROM orders WHERE created_at >= CURRENT_DATE - 1 AND created_at < CURRENT_DATE")),
+            ],
+            workflow: json!({
+                "nodes": [
+                    { "id": "nightly", "node_type": "CronTrigger", "params": { "schedule": "${schedule}", "timezone": "UTC" } },
+                    { "id": "extract", "node_type": "PGQuery", "secrets": ["$secret.${source_secret}"],
+                      "params": { "query": "${query}" } },
+                    { "id": "load", "node_type": "PGDynTableWriter", "secrets": ["$secret.${target_secret}"],
+                      "params": { "table": "${table}", "columns": ["day", "orders", "revenue"], "audit_columns": true },
+                      "inputs": ["$json.day", "$json.orders", "$json.revenue"] }
+                ],
+                "edges": [
+                    { "from": "nightly", "to": "extract" },
+                    { "from": "extract", "to": "load" }
+                ]
+            }),
+        },
+        WorkflowTemplate {
+            id: "chatbot",
+            name: "Chatbot with memory",
+            description: "OpenAI-compatible chat model that keeps a conversation window per user; replace the Lua reply with your own logic",
+            category: "ai",
+            params: vec![
+                param("model", "Model name served at /v1/chat/completions", json!("assistant")),
+                param("window", "Messages of history loaded per request", json!(20)),
+            ],
+            workflow: json!({
+                "nodes": [
+                    { "id": "chat", "node_type": "ChatTrigger", "params": { "model": "${model}" } },
+                    { "id": "history", "node_type": "Memory",
+                      "params": { "operation": "load", "session_id": "$json.user", "window": "${window}" } },
+                    { "id": "respond", "node_type": "FunLogic", "params": { "script":
+                        "local m = data[1] return { user = m.user, last_message = m.last_message, reply = 'You said: ' .. tostring(m.last_message) }" } },
+                    { "id": "remember", "node_type": "Memory",
+                      "params": { "operation": "append", "session_id": "$json.user" },
+                      "inputs": ["$json.last_message", "$json.reply"] }
+                ],
+                "edges": [
+                    { "from": "chat", "to": "history" },
+                    { "from": "history", "to": "respond" },
+                    { "from": "respond", "to": "remember" }
+                ]
+            }),
+        },
+        WorkflowTemplate {
+            id: "iot-alerting",
+            name: "IoT threshold alerting",
+            description: "Subscribe to sensor telemetry over MQTT and emit an alert event whenever a reading crosses a limit (with hysteresis)",
+            category: "iot",
+            params: vec![
+                param("broker", "MQTT broker URL", json!("mqtt://localhost:1883")),
+                param("topic", "Telemetry topic filter", json!("sensors/+/telemetry")),
+                param("field", "Payload field to watch", json!("temperature")),
+                param("above", "Alert when the field rises above this value", json!(80)),
+                param("hysteresis", "Back to normal once the value drops this far below the limit", json!(2)),
+                param("event", "Event emitted for alerts (subscribe with an EventTrigger)", json!("sensor.alert")),
+            ],
+            workflow: json!({
+                "nodes": [
+                    { "id": "telemetry", "node_type": "MQTTTrigger", "params": {
+                        "broker": "${broker}", "topic": "${topic}", "qos": 1,
+                        "aggregate": { "mode": "threshold", "field": "${field}", "above": "${above}", "hysteresis": "${hysteresis}" } } },
+                    { "id": "describe", "node_type": "FunLogic", "params": { "script":
+                        "local value = data[1]['${field}'] return { field = '${field}', value = value, limit = ${above}, state = (value > ${above}) and 'above' or 'normal' }" } },
+                    { "id": "alert", "node_type": "EmitEvent", "params": { "event": "${event}" } }
+                ],
+                "edges": [
+                    { "from": "telemetry", "to": "describe" },
+                    { "from": "describe", "to": "alert" }
+                ]
+            }),
+        },
+    ]
+}
@@ -0,0 +1,119 @@
+//! Workflow test cases: input payloads, node mocks and output assertions
+//! 
+//! Test cases are stored next to the workflow definition and executed in an
+//! isolated engine by `runtime::test_runner`:
+//! 
+//! ```json
+//! {
+//!   "name": "geocodes a known address",
+//!   "input": { "address": "Jl. Sudirman 1" },
+//!   "mocks": { "geocode": { "data": [{ "lat": -6.2, "lng": 106.8 }] } },
+//!   "assertions": [
+//!     { "path": "$[0].lat", "equals": -6.2 },
+//!     { "path": "$[0].lng", "exists": true }
+//!   ]
+//! }
+//! ```
+
+use crate::workflow::assertions::{Assertion, AssertionResult};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// A single workflow test case
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowTestCase {
+    /// Human-readable test name (unique per workflow)
+    pub name: String,
+    /// Entry node to start from (defaults to the workflow's first entry point)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_node: Option<String>,
+    /// Trigger payload, becomes `[input]` like a webhook request body
+    #[serde(default)]
+    pub input: Value,
+    /// Query parameters visible to `$query.` pins
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub query: HashMap<String, String>,
+    /// Headers visible to `$header.` pins (lowercase keys)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+    /// Mocked node results keyed by node id (e.g. HTTPClient, PGQuery)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub mocks: HashMap<String, NodeMock>,
+    /// The test expects the workflow to fail (assertions then run against `{"error": ...}`)
+    #[serde(default)]
+    pub expect_error: bool,
+    /// Assertions evaluated against the final output array
+    #[serde(default)]
+    pub assertions: Vec<Assertion>,
+}
+
+/// Replacement result for a mocked node
+/// 
+/// `{"data": [...]}` returns the items as the node output (a non-array value is
+/// wrapped), `{"error": "..."}` makes the node fail with that message.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NodeMock {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl NodeMock {
+    /// Output items returned by the mocked node
+    pub fn items(&self) -> Vec<Value> {
+        match &self.data {
+            Some(Value::Array(items)) => items.clone(),
+            Some(value) => vec![value.clone()],
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Outcome of one test case
+#[derive(Debug, Clone, Serialize)]
+pub struct TestCaseResult {
+    pub name: String,
+    pub passed: bool,
+    pub duration_ms: u64,
+    /// Final output array (or `{"error": ...}` when execution failed)
+    pub output: Value,
+    /// Execution error, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub assertions: Vec<AssertionResult>,
+}
+
+/// Report for a full test run of one workflow
+#[derive(Debug, Clone, Serialize)]
+pub struct TestRunReport {
+    pub workflow_id: String,
+    pub passed: usize,
+    pub failed: usize,
+    pub results: Vec<TestCaseResult>,
+}
+
+impl TestCaseResult {
+    /// Build a result from a finished execution (output or error) and the case's assertions
+    pub fn evaluate(case: &WorkflowTestCase, outcome: Result<Vec<Value>, String>, duration_ms: u64) -> Self {
+        let (output, error) = match outcome {
+            Ok(data) => (Value::Array(data), None),
+            Err(e) => (json!({ "error": e }), Some(e)),
+        };
+
+        let assertions: Vec<AssertionResult> = case.assertions.iter()
+            .map(|assertion| assertion.evaluate(&output))
+            .collect();
+        let passed = error.is_some() == case.expect_error && assertions.iter().all(|a| a.passed);
+
+        Self {
+            name: case.name.clone(),
+            passed,
+            duration_ms,
+            output,
+            error,
+            assertions,
+        }
+    }
+}
@@ -1,11 +1,15 @@
-/// Core workflow type definitions
-/// 
-/// Defines the fundamental structures for workflows, nodes, and edges as specified
-/// in the README. These types are serialized/deserialized from JSON for persistence.
+//! Core workflow type definitions
+//! 
+//! Defines the fundamental structures for workflows, nodes, and edges as specified
+//! in the README. These types are serialized/deserialized from JSON for persistence.
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::{collections::{BTreeMap, HashMap}, sync::Arc};
+
+use crate::project::transaction::TransactionSlot;
+use crate::runtime::budget::RunBudget;
+use crate::workflow::{binary::BinaryStore, environments::EnvironmentProfile, pins::PinExpr, test_cases::NodeMock};
 
 /// A complete workflow definition containing nodes and their connections
 /// 
@@ -17,10 +21,139 @@ pub struct Workflow {
     pub id: String,
     /// Human-readable workflow name  
     pub name: String,
+    /// Owning project slug (e.g., "default", "utility")
+    /// Executions run against this project's databases and settings
+    #[serde(default = "default_project_slug")]
+    pub project: String,
     /// List of nodes in this workflow
     pub nodes: Vec<Node>,
     /// List of edges connecting nodes
     pub edges: Vec<Edge>,
+    /// Per-workflow execution settings (all optional, engine defaults apply)
+    #[serde(default)]
+    pub settings: WorkflowSettings,
+    /// Inactive workflows (drafts) are stored and validated but not registered:
+    /// their triggers don't fire until the workflow is saved with "active": true
+    #[serde(default = "default_active")]
+    pub active: bool,
+    /// Per-environment overrides (key: environment name, e.g. "staging")
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub environments: BTreeMap<String, EnvironmentProfile>,
+}
+
+/// Per-workflow execution settings
+/// 
+/// Stored with the workflow definition; missing fields fall back to the
+/// server-wide configuration.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkflowSettings {
+    /// In-memory threshold (bytes of serialized JSON) above which intermediate
+    /// node outputs are spilled to disk. 0 disables spilling for this workflow.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spill_threshold_bytes: Option<usize>,
+    /// Most verbose level logged while this workflow runs (caps the global filter)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_level: Option<LogLevel>,
+    /// How much of the node payloads debug logs may show
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_data: Option<LogDataPolicy>,
+    /// URLs notified with a summary when a run finishes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub callbacks: Option<CallbackSettings>,
+    /// How much of a failed node's error message webhook responses include
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_detail: Option<ErrorDetail>,
+    /// Run executions interrupted by a crash or restart again on startup
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub resume_on_restart: bool,
+    /// Memory budget of a run (bytes); only lowers the server-wide limit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_memory_bytes: Option<usize>,
+    /// CPU budget of a run (milliseconds); only lowers the server-wide limit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_cpu_ms: Option<u64>,
+}
+
+/// Run result callbacks for per-workflow settings
+/// 
+/// The engine POSTs a summary of every top-level run to `on_complete` (success)
+/// or `on_failure` (error), HMAC-signed when `secret` is set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CallbackSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_complete: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_failure: Option<String>,
+    /// `$secret.` pin of the HMAC-SHA256 key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    /// Include the final output items in the summary
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub include_output: bool,
+}
+
+impl CallbackSettings {
+    /// Reject non-HTTP URLs and keys that aren't `$secret.` pins
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for url in self.on_complete.iter().chain(&self.on_failure) {
+            if !(url.starts_with("http://") || url.starts_with("https://")) {
+                return Err(anyhow::anyhow!("callback URL '{}' must start with http:// or https://", url));
+            }
+        }
+        if self.secret.as_deref().is_some_and(|secret| !secret.starts_with("$secret.")) {
+            return Err(anyhow::anyhow!("callback 'secret' must be a $secret. pin"));
+        }
+        Ok(())
+    }
+}
+
+/// Log verbosity for per-workflow settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+/// Payload logging policy for per-workflow settings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogDataPolicy {
+    /// Never log payloads (PII-heavy workflows)
+    None,
+    /// Log bounded previews (default)
+    #[default]
+    Truncated,
+    /// Log complete payloads
+    Full,
+}
+
+/// Error detail policy for per-workflow settings
+/// 
+/// Failed runs always report the node ID, node type and run ID; this decides
+/// what the error message shows to the caller.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorDetail {
+    /// No message (public endpoints)
+    Minimal,
+    /// First line of the message without source locations or URL credentials (default)
+    #[default]
+    Sanitized,
+    /// Raw message, including Lua tracebacks
+    Full,
+}
+
+/// Default project slug for workflows saved before projects were introduced
+fn default_project_slug() -> String {
+    "default".to_string()
+}
+
+fn default_active() -> bool {
+    true
 }
 
 /// A single node in the workflow DAG
@@ -48,6 +181,45 @@ pub struct Node {
     /// If None, node doesn't require secrets (backwards compatible)
     /// If Some, evaluates expressions like ["$secret.postgres_main"] to get credentials
     pub secrets: Option<Vec<String>>,
+    /// Editor canvas position, persisted and returned untouched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<NodePosition>,
+    /// Free-form notes shown in the editor, persisted and returned untouched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// Disabled nodes are skipped by the engine (see `disabled_mode`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub disabled: bool,
+    /// What a disabled node does with its input
+    #[serde(default, skip_serializing_if = "DisabledMode::is_passthrough")]
+    pub disabled_mode: DisabledMode,
+    /// Input pins parsed once at compile time (filled by the registry)
+    #[serde(skip)]
+    pub compiled_inputs: Option<Arc<[PinExpr]>>,
+}
+
+/// Behavior of a disabled node
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DisabledMode {
+    /// Hand the node's input unchanged to the next node (default)
+    #[default]
+    Passthrough,
+    /// Stop the run at this node; its input becomes the run's output
+    Block,
+}
+
+impl DisabledMode {
+    fn is_passthrough(&self) -> bool {
+        matches!(self, DisabledMode::Passthrough)
+    }
+}
+
+/// Node position on the editor canvas
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct NodePosition {
+    pub x: f64,
+    pub y: f64,
 }
 
 /// Available node types for the mechaway engine
@@ -61,14 +233,18 @@ pub struct Node {
 pub enum NodeType {
     /// HTTP webhook trigger node - creates dynamic endpoints
     /// Expected params: { "path": "/grade", "method": "POST" }
+    /// Optional params: { "replay_protection": { "max_skew_secs": 300 } } requires fresh timestamp + nonce headers
+    /// Optional params: { "response": "envelope" } wraps the output with the run status and per-branch outcomes
     Webhook,
     
     /// Embedded Lua script execution node  
     /// Expected params: { "script": "return {result = data.score * 2}" }
+    /// Behavior: `data` holds the input items; workflow variables are a read-only `vars` table
     FunLogic,
     
     /// Simple table writer to data SQLite database
     /// Expected params: { "table": "grades", "columns": ["id", "score", "result"] }
+    /// Optional params: { "audit_columns": true } appends _run_id, _workflow_id and _executed_at to every insert
     SimpleTableWriter,
     
     /// Simple table reader from data SQLite database
@@ -85,9 +261,35 @@ pub enum NodeType {
     /// Starts workflow execution based on cron schedule
     CronTrigger,
     
+    /// Background trigger fetching new records since a persisted cursor (stored in the project KV store)
+    /// Expected params: { "interval_secs": 300 (or "schedule": "0 */5 * * * *"), "cursor_field": "updated_at",
+    /// "source": "http", "request": { "url": "...", "headers": {...} }, "cursor_param": "updated_since", "items_path": "data" }
+    /// or { "source": "sql", "query": "SELECT ... WHERE updated_at > ? ORDER BY updated_at", "initial_cursor": "..." }
+    /// Behavior: Starts the workflow with the fetched records as the data array when there are any, then saves the
+    /// largest cursor_field value; secrets ["$secret.postgres_main"] query PostgreSQL instead of SimpleTable
+    PollingTrigger,
+    
+    /// Hosted HTML form trigger served at /form/{workflow_id}/{path}
+    /// Expected params: { "path": "/feedback", "title": "Feedback", "fields": [{ "name": "email", "type": "email", "required": true },
+    /// { "name": "topic", "type": "select", "options": ["Bug", "Idea"] }] }
+    /// Optional params: { "description", "submit_label", "success_message", "redirect_url" }
+    /// Behavior: Validates submissions server-side and starts the workflow with one item of typed field values
+    FormTrigger,
+    
+    /// OpenAI-compatible chat model served at /v1/chat/completions
+    /// Expected params: { "model": "support-bot" }
+    /// Optional params: { "api_key": "$secret.chat_api_key", "reply_field": "reply" }
+    /// Behavior: Starts the workflow with the chat request (plus "last_message") as one item and answers
+    /// with the first result item's reply field (default: "content", "reply", "message", "text", "output")
+    ChatTrigger,
+    
     /// HTTP client for external API calls
     /// Expected params: { "url": "https://api.example.com/data", "method": "GET", "headers": {...} }
     /// Expected inputs: ["$json.payload"] for request body/query params
+    /// Optional params: { "sign": { "mode": "hmac|aws_sigv4", ... } } signs the outgoing request
+    /// Optional params: { "oauth": "google" } sends the project OAuth credential's access token (auto-refreshed)
+    /// Optional params: { "store_and_forward": true } parks the input in the project outbox when the network is
+    /// unreachable (outputs { queued: true, outbox_id }) and replays the request later
     HTTPClient,
     
     /// PostgreSQL query execution node (MANDATORY secret required)
@@ -101,25 +303,322 @@ pub enum NodeType {
     /// Expected inputs: ["$json.customer_id", "$json.name", "$json.email"] for data values
     /// Expected secrets: ["$secret.customer_db_url"] - MANDATORY, no fallbacks!
     /// Behavior: Auto-creates mway_dynamic_tables schema and table if not exists
+    /// Optional params: { "schema_drift": { "mode": "ignore|fail|add_columns", "allow": ["tier", "utm_*"], "types": "text|infer" } }
+    /// decides what happens to item fields the table doesn't have (published as a "schema.drift" event)
+    /// Optional params: { "audit_columns": true } (see SimpleTableWriter)
     PGDynTableWriter,
     
-    /// MCP (Model Context Protocol) trigger for AI model integration
-    /// Expected params: { "path": "/ai/chat", "model": "gpt-4", "tools": ["image_analysis"] }
-    /// Behavior: Creates WebSocket endpoint for AI model communication
-    /// Data: Receives AI requests, sends responses via WebSocket
+    /// Stripe API call (create customer, create payment link, or any /v1/ request)
+    /// Expected params: { "operation": "create_customer|create_payment_link|request", "fields": ["email", "metadata[order_id]"] }
+    /// Expected inputs: ["$json.email", "$json.order_id"] filling "fields" in order
+    /// Expected secrets: ["$secret.stripe_secret_key"] - MANDATORY
+    /// Optional params: { "params": {...static form values}, "idempotency_key": "$json.order_id", "api_version": "2024-06-20",
+    /// "method"/"path" for "request" }
+    /// Behavior: Outputs the Stripe object; Stripe errors fail the node with Stripe's message
+    /// Optional params: { "store_and_forward": true } (see HTTPClient)
+    StripeAPI,
+    
+    /// GitHub API call (create issue, issue/PR comment, or commit status)
+    /// Expected params: { "operation": "create_issue|create_comment|create_status", "repo": "acme/api", "fields": ["title", "body"] }
+    /// Expected inputs: ["$json.title", "$json.body"] filling "fields" in order
+    /// Expected secrets: ["$secret.github_token"] - MANDATORY
+    /// Optional params: { "issue_number": "$json.number", "sha": "$json.git.sha", "params": {...static body} }
+    /// Behavior: Outputs GitHub's response object; API errors fail the node
+    /// Optional params: { "store_and_forward": true } (see HTTPClient)
+    GitHubAPI,
+    
+    /// Twilio SMS / WhatsApp message, one per input item
+    /// Expected params: { "channel": "sms|whatsapp", "from": "+15550100", "to": "$json.phone", "body": "Hi {{$json.name}}" }
+    /// Expected secrets: ["$secret.twilio_account_sid", "$secret.twilio_auth_token"] - MANDATORY
+    /// Optional params: { "media_url": "...", "status_callback": "https://...", "await_status_secs": 10 }
+    /// Behavior: Outputs { sid, status, to, from, error_code, error_message, ... } per message
+    /// Optional params: { "store_and_forward": true } (see HTTPClient)
+    TwilioSend,
+    
+    /// Push notification to mobile (FCM) or browser (Web Push) clients, one per input item
+    /// Expected params: { "provider": "fcm", "token": "$json.device_token", "title": "Alert", "body": "{{$json.sensor}} is offline" }
+    /// or { "provider": "webpush", "subscription": "$json.subscription", "vapid_subject": "mailto:ops@example.com", ... }
+    /// Expected secrets: ["$secret.fcm_service_account"] (fcm) or ["$secret.vapid_private_key"] (webpush) - MANDATORY
+    /// Optional params: { "topic": "alerts" (fcm instead of token), "data": {...}, "image": "...", "ttl_secs": 3600, "urgency": "high" }
+    /// Behavior: Outputs { provider, target, success, id } per message; expired targets give invalid_target: true
+    /// Optional params: { "store_and_forward": true } (see HTTPClient)
+    PushNotification,
+    
+    /// iCalendar (.ics) invite creation or parsing
+    /// Expected params: { "operation": "create", "summary": "Call with {{$json.name}}", "start": "$json.slot",
+    /// "organizer": "sales@example.com", "attendees": ["$json.email"] } or { "operation": "parse", "ics": "$json.attachment" }
+    /// Optional params: { "method": "REQUEST|PUBLISH|CANCEL|REPLY", "end": "...", "duration_minutes": 30, "timezone": "Europe/Berlin",
+    /// "uid", "description", "location", "url", "status", "sequence", "rrule", "alarm_minutes", "filename" }
+    /// Behavior: create outputs { uid, ics, filename, content_type, ... } per item; parse outputs one item per VEVENT
+    CalendarEvent,
+    
+    /// IP geolocation from the project's local MaxMind databases (project settings "geoip")
+    /// Expected params: { "ip": "$headers.x-forwarded-for" } (or "$json.client_ip")
+    /// Optional params: { "output_field": "geo", "language": "en" }
+    /// Behavior: Adds { ip, found, country_code, country, city, latitude, longitude, time_zone, asn, asn_org, ... }
+    /// under output_field of each item (null for unparseable IPs)
+    GeoIP,
+    
+    /// Numeric expressions with unit conversion, evaluated per item without Lua
+    /// Expected params: { "variables": ["temp_f"], "expressions": { "temp_c": "round(convert(temp_f, 'F', 'C'), 1)" } }
+    /// Expected inputs: ["$json.temperature"] filling "variables" in order
+    /// Optional params: { "output_field": "calc", "on_error": "fail|null" }
+    /// Behavior: Adds each expression result to the item (or under output_field); non-numeric inputs,
+    /// division by zero and non-finite results fail the node unless on_error is "null"
+    Math,
+    
+    /// Currency conversion with exchange rates cached per project
+    /// Expected params: { "amount": "$json.total", "from": "$json.currency", "to": "EUR" }
+    /// Expected secrets: ["$secret.rates_api_key"] (not needed for the default "ecb" provider)
+    /// Optional params: { "provider": "ecb|openexchangerates|exchangerate_api|fixer", "ttl_seconds": 3600,
+    /// "decimals": 2, "output_field": "conversion" }
+    /// Behavior: Adds { amount, from, to, rate, value, provider, as_of, stale } under output_field of each item;
+    /// value is rounded to the target currency's minor units, stale rates are used when the provider is down
+    CurrencyConvert,
+    
+    /// Parquet export of the whole data array (e.g. nightly cron ETL into a data lake)
+    /// Optional params: { "columns": [{ "name": "id", "type": "int64", "nullable": false }, ...] (inferred if absent),
+    /// "compression": "snappy|zstd|none", "row_group_size": 65536, "filename": "orders-%Y-%m-%d.parquet" }
+    /// Behavior: Outputs one binary item { "$binary": {...}, rows, columns } for an HTTPClient PUT (aws_sigv4 "s3")
+    /// to object storage; values that don't fit their declared column type fail the node
+    ParquetWrite,
+    
+    /// JSON Lines / NDJSON parsing, one item per line
+    /// Expected inputs: ["$json.body"] (NDJSON text), an uploaded file ("$file.upload") or a binary item ("$json")
+    /// Optional params: { "batch_size": 10000, "on_error": "fail|skip" }
+    /// Behavior: Without batch_size outputs every line as one data array; with batch_size the following
+    /// nodes run once per batch (streamed from disk for files), like a cursor-mode PGQuery
+    NdjsonParse,
+    
+    /// JSON Lines / NDJSON serialization of the data array into a project uploads file
+    /// Optional params: { "filename": "events-%Y-%m-%d.ndjson" }
+    /// Behavior: Outputs one binary item { "$binary": {...}, rows }; the batches of a stream append to one file
+    NdjsonWrite,
+    
+    /// Excel workbooks: read a sheet into items or write the data array as a formatted .xlsx project file
+    /// Expected params: { "operation": "read" } + inputs ["$file.upload"] or { "operation": "write" }
+    /// Optional params: read { "sheet": "Orders", "header": true, "skip_rows": 0 };
+    /// write { "sheet": "Report", "filename": "report-%Y-%m-%d.xlsx", "columns": [{ "key", "header", "num_format", "width" }] }
+    /// Behavior: Read outputs one item per non-empty row keyed by the header row (column letters without one);
+    /// write outputs one binary item { "$binary": {...}, rows, sheet } stored in the project's uploads directory
+    Excel,
+    
+    /// PII scrubbing before analytics writes or LLM calls
+    /// Expected params: { "fields": { "email": "hash", "card": { "strategy": "mask", "keep_last": 4 }, "notes": "redact",
+    /// "name": { "strategy": "fake", "kind": "name" }, "age": { "strategy": "bucket", "size": 10 } } }
+    /// Optional params: { "k": 5 }; secrets ["$secret.anonymize_key"] keys hash/fake (HMAC-SHA256)
+    /// Behavior: Rewrites the fields (dotted paths, "*" for array elements) of every item; with k, bucketed
+    /// fields of items in groups smaller than k are suppressed to "*"
+    Anonymize,
+    
+    /// Change detection against the node's snapshot from the previous run (stored in the project DB)
+    /// Expected params: { "key": "id" } (or ["tenant", "sku"] for a composite key)
+    /// Optional params: { "ignore": ["synced_at"], "compare": ["price"], "emit": ["added", "changed", "removed", "unchanged"],
+    /// "mode": "full" | "incremental" }
+    /// Behavior: Outputs one { change, key, item, previous, changed_fields } item per emitted change and updates the
+    /// snapshot; in full mode keys missing from the data array are removed
+    Diff,
+    
+    /// Count or time window batching across executions (buffered in the project DB)
+    /// Expected params: { "count": 100 } or { "duration_secs": 60 }
+    /// Optional params: { "slide": 20 } / { "slide_secs": 10 } for sliding windows, { "output": "items" | "windows", "max_items": 100000 }
+    /// Behavior: Buffers the data array and stops the run until a window closes, then outputs the closed
+    /// windows' items; time windows close on the next execution (a CronTrigger tick only closes, never buffers)
+    Window,
+    
+    /// Modbus TCP reads / writes against PLCs and meters, without an external protocol bridge
+    /// Expected params: { "operation": "read_holding" | "read_input" | "read_coils" | "read_discrete_inputs"
+    /// | "write_coils" | "write_registers", "registers": [{ "name": "temp", "address": 100, "type": "i16", "scale": 0.1 }] }
+    /// Expected secrets: ["$secret.plc_endpoint"] (host:port or modbus://host:port, default port 502)
+    /// Optional params: { "unit_id": 1, "word_order": "big" | "little", "timeout_secs": 5 }; without "registers",
+    /// { "address": 0, "count": 10 } for raw reads and writes of the first input pin's value (or array)
+    /// Behavior: Reads output one item of named (scaled) values; writes set each item's mapped fields and
+    /// pass the data array through. Register types: bool (coils), u16, i16, u32, i32, f32
+    Modbus,
+    
+    /// OPC UA reads, writes and browsing against PLCs, SCADA gateways and historians
+    /// Expected params: { "operation": "read" | "write" | "browse",
+    /// "nodes": ["ns=2;s=Line1.Temperature", { "node_id": "ns=2;i=1042", "name": "pressure", "type": "Double" }] }
+    /// Expected secrets: ["$secret.opcua_endpoint"] (opc.tcp://host:port), optionally followed by username and password secrets
+    /// Optional params: { "detailed": true } (read), { "node_id": "i=85", "max_depth": 1, "max_nodes": 1000 } (browse),
+    /// { "timeout_secs": 10 }
+    /// Behavior: Read outputs one item of named values (detailed: one item per node with status and timestamps);
+    /// write sets each item's named fields (type from "type" or the node's current value) and passes the data
+    /// array through; browse outputs one item per reference. Security mode None only
+    OpcUa,
+    
+    /// Serial port output for devices wired to the edge box (cargo feature "serial")
+    /// Expected params: { "port": "/dev/ttyUSB0", "baud_rate": 9600 }
+    /// Expected inputs: ["$json.command"] - written once per item (strings, byte arrays or other JSON as text)
+    /// Optional params: { "data_bits": 8, "parity": "none", "stop_bits": 1, "flow_control": "none",
+    ///                    "encoding": "text" | "hex" | "base64", "line_ending": "\r\n",
+    ///                    "read_response": true, "response_timeout_ms": 1000, "framing": "line", ... }
+    /// Behavior: Passes the data array through, or with read_response outputs one reply item per command.
+    /// Writes go through the SerialTrigger holding the same port, if any
+    Serial,
+    
+    /// MCP (Model Context Protocol) trigger publishing the workflow as a tool for AI agents
+    /// Expected params: { "tool": "lookup_order", "description": "Look up an order by its ID" }
+    /// Optional params: { "arguments": { "order_id": { "type": "string", "required": true } } | "input_schema": {...},
+    ///                    "api_key": "$secret.NAME", "priority": 0 }
+    /// Behavior: Listed by tools/list on POST /mcp; every tools/call runs the workflow with the
+    ///           arguments as its item (also under `mcp`, for `$mcp.order_id` pins) and answers
+    ///           with the output items as the tool result
     MCPTrigger,
     
     /// WebSocket trigger for real-time bidirectional communication
-    /// Expected params: { "path": "/robot/sensors", "protocol": "robot-v1" }
-    /// Behavior: Creates WebSocket endpoint for real-time data streams
-    /// Data: Receives real-time sensor data, sends control commands
+    /// Expected params: { "path": "/robot/sensors" } (served at ws://host/ws/{workflow_id}/robot/sensors)
+    /// Optional params: { "aggregate": { "mode": "sample" | "average" | "threshold", ... } } (per connection),
+    ///                  { "device_field": "robot.id", "offline_after_secs": 60 } (device state, `$device.*` pins)
+    /// Behavior: Starts the workflow for every client message (or aggregated run) and
+    /// sends { "data": [...] } back on the connection; `$websocket.connection_id` / `$websocket.path` pins
     WebSocketTrigger,
     
     /// MQTT trigger for IoT sensor data and messaging
-    /// Expected params: { "path": "/mqtt/sensors", "topic": "sensors/+/data", "qos": 2 }
-    /// Behavior: Creates MQTT subscriber endpoint for IoT data streams
-    /// Data: Receives sensor data, publishes control messages
+    /// Expected params: { "broker": "mqtt://broker.local:1883", "topic": "sensors/+/data", "qos": 1 }
+    /// Optional params: { "client_id", "username", "password": "$secret.NAME", "keep_alive_secs": 30,
+    ///                    "aggregate": { "mode": "sample" | "average" | "threshold", ... } } (per device),
+    ///                  { "device_field": "serial", "offline_after_secs": 60 } (device ID defaults to the topic)
+    /// Behavior: Subscribed by the MQTT trigger service; runs the workflow per message (or aggregated run),
+    /// one at a time in message order; `$mqtt.topic` / `$mqtt.qos` / `$mqtt.retain` pins
     MQTTTrigger,
+    
+    /// OPC UA subscription trigger for monitored items
+    /// Expected params: { "nodes": ["ns=2;s=Line1.Temperature", { "node_id": "ns=2;s=Line1.Running", "name": "running" }] }
+    /// Expected secrets: ["$secret.opcua_endpoint"] (opc.tcp://host:port), optionally followed by username and password secrets
+    /// Optional params: { "publishing_interval_ms": 1000, "sampling_interval_ms": 1000, "deadband": 0.5, "queue_size": 10,
+    ///                    "output": "changes" | "snapshot" } (per-node "sampling_interval_ms" / "deadband" override)
+    /// Behavior: Subscribed by the OPC UA trigger service; runs the workflow per notification message with one
+    /// { node_id, name, value, status, ... } item per change (snapshot: one item of the latest values), in order
+    OpcUaTrigger,
+    
+    /// Serial port trigger for devices wired to the edge box (cargo feature "serial")
+    /// Expected params: { "port": "/dev/ttyACM0", "baud_rate": 115200 }
+    /// Optional params: { "data_bits", "parity", "stop_bits", "flow_control" (see Serial),
+    ///                    "framing": "line" | "fixed" | "idle", "delimiter": "\n", "frame_length": 8, "idle_ms": 50,
+    ///                    "encoding": "text" | "hex" | "base64", "max_frame_bytes": 4096 }
+    /// Behavior: Read by the serial trigger service, which keeps the port open (reopened with backoff);
+    /// runs the workflow per frame, one at a time in order, with the decoded frame plus "serial": { port, bytes }
+    SerialTrigger,
+    
+    /// Bluetooth Low Energy sensor trigger via BlueZ (cargo feature "ble", Linux)
+    /// Expected params: { "mode": "advertisement" } with filters { "addresses": [...], "name_prefix": "ATC_",
+    ///                    "manufacturer_id": 1177, "service_uuids": ["fcd2"] },
+    ///                  or { "mode": "notify", "address": "A4:C1:38:12:34:56", "characteristic_uuids": ["2a6e"] }
+    /// Optional params: { "adapter": "hci0", "decoder": "raw" | "bthome",
+    ///                    "fields": [{ "name": "temperature", "offset": 0, "type": "i16", "endian": "little", "scale": 0.01 }],
+    ///                    "min_interval_ms": 1000, "device_field": "name", "offline_after_secs": 300 }
+    /// Behavior: Scanned / subscribed by the BLE trigger service (reconnected with backoff); runs the workflow per
+    /// payload, one at a time in order, with the decoded fields plus "ble": { address, name, rssi, source, uuid, data }
+    BLETrigger,
+    
+    /// Sub-workflow execution node, optionally across project boundaries
+    /// Expected params: { "workflow_id": "wf-geocode", "start_node": "entry" }
+    /// Expected inputs: ["$json.address"] (optional) - becomes the sub-workflow data array
+    /// Behavior: Orchestrated by the execution engine; cross-project calls require the
+    /// target project's settings to list the caller in "allowed_callers"
+    ExecuteWorkflow,
+    
+    /// Internal event emitter for decoupled workflow communication
+    /// Expected params: { "event": "order.created" }
+    /// Expected inputs: ["$json.order"] (optional) - becomes the event payload items
+    /// Behavior: Publishes to the in-process event bus scoped to the workflow's project,
+    /// passes its input data through unchanged
+    EmitEvent,
+    
+    /// Internal event trigger (subscriber side of EmitEvent)
+    /// Expected params: { "event": "order.created" } ("*" subscribes to every event)
+    /// Behavior: Starts workflow execution whenever a matching event is emitted in the same project
+    EventTrigger,
+    
+    /// Project-scoped key-value store for state between executions
+    /// Expected params: { "operation": "get|set|delete|increment", "key": "orders_cursor", "ttl_seconds": 3600 }
+    /// Expected inputs: ["$json.updated_at"] - value for set, amount for increment (default 1)
+    /// Behavior: "key" may be a pin expression (e.g. "$json.order_id") for dedupe markers
+    KVStore,
+    
+    /// Conversation memory per session for chat workflows (project DB)
+    /// Expected params: { "operation": "load|append|summarize|clear", "session_id": "$json.user", "window": 20 }
+    /// Optional params: { "max_chars": 8000, "max_messages": 200, "summarize_after": 40, "keep_last": 4, "roles": ["user", "assistant"] }
+    /// Expected inputs: ["$json.last_message", "$json.reply"] - messages for append, summary text for summarize
+    /// Behavior: Passes data through; metadata "memory" holds the session window, read with $memory.* pins
+    Memory,
+    
+    /// Cache-aside node with TTL for expensive lookups (HTTP/PG calls in hot paths)
+    /// Expected params: { "mode": "lookup|store", "namespace": "geocode", "ttl_seconds": 300 }
+    /// Expected inputs: ["$json.address"] - key parts hashed into the cache key
+    /// Behavior: "lookup" returns the cached items and stops the run on a hit, or passes
+    /// data through and records the miss key; a later "store" node caches its input data
+    Cache,
+    
+    /// Stand-in for external dependencies (staging runs without live credentials)
+    /// Expected params: { "response": [{ "status": "ok" }] } or { "template": { "id": "$json.id", "msg": "hi {{$json.name}}" } },
+    /// plus optional { "latency_ms": 150, "jitter_ms": 50, "failure_rate": 0.1, "error": "upstream timeout" }
+    /// Behavior: "response" is returned as-is (arrays become items); "template" is rendered once
+    /// per input item, where whole-string pins keep their JSON type and {{pin}} interpolates text
+    Mock,
+    
+    /// Pipeline guardrail evaluating JSONPath conditions against the data array
+    /// Expected params: { "conditions": [{ "path": "$[*].amount", "all": true, "greater_than": 0 }],
+    ///                    "message": "refund amounts must be positive", "on_failure": "error|stop" }
+    /// Behavior: Passes data through unchanged when every condition holds; otherwise fails the
+    /// run with a structured assertion error ("error", default) or stops it quietly ("stop")
+    Assert,
+    
+    /// Opens a transaction on the project's SimpleTable storage (SQLite or PostgreSQL)
+    /// Expected params: {} (none)
+    /// Behavior: SimpleTable nodes after it run inside the transaction until a TransactionCommit
+    /// or TransactionRollback; a run ending with the transaction still open rolls it back.
+    /// Passes data through unchanged
+    TransactionBegin,
+    
+    /// Commits the transaction opened by TransactionBegin
+    /// Expected params: {} (none)
+    /// Behavior: Makes all SimpleTable writes since TransactionBegin visible at once,
+    /// passes data through unchanged; fails if no transaction is open
+    TransactionCommit,
+    
+    /// Discards the writes made since TransactionBegin
+    /// Expected params: {} (none)
+    /// Behavior: Rolls back the open transaction (no-op if none is open), passes data through unchanged
+    TransactionRollback,
+    
+    /// Evaluates a project feature flag and routes the run to its "true" or "false" port
+    /// Expected params: { "flag": "new_checkout" }
+    /// Optional params: { "attributes": ["key", "country", "plan"], "default": false }
+    /// Expected inputs: ["$json.user_id", "$json.country", "$json.plan"] filling "attributes" in order
+    /// ("key" buckets percentage rollouts)
+    /// Behavior: Passes data through unchanged; edges with "port": "true" / "false" only run when the
+    /// flag evaluates that way (metadata "feature_flags" records the value and reason per flag).
+    /// Flags missing from the project evaluate to "default"
+    FeatureFlag,
+    
+    /// Assigns every item to an experiment variant and routes it to that variant's port
+    /// Expected params: { "variants": [{ "name": "control", "weight": 50 }, { "name": "blue", "weight": 50 }] }
+    /// Optional params: { "experiment": "checkout_button", "field": "variant" }
+    /// Expected inputs: ["$json.user_id"] (the key, evaluated per item)
+    /// Behavior: Hashes experiment and key into the weighted variants (same key, same variant);
+    /// edges with "port": "<variant>" only receive that variant's items. Metadata "ab_split"
+    /// records the assignment counts per experiment; "field" also sets the variant on each item
+    ABSplit,
+}
+
+impl NodeType {
+    /// Entry point nodes only start executions and don't process data themselves
+    pub fn is_entry_point(&self) -> bool {
+        matches!(self, NodeType::Webhook | NodeType::CronTrigger | NodeType::PollingTrigger | NodeType::FormTrigger | NodeType::ChatTrigger | NodeType::EventTrigger
+            | NodeType::MQTTTrigger | NodeType::WebSocketTrigger | NodeType::OpcUaTrigger | NodeType::SerialTrigger
+            | NodeType::BLETrigger | NodeType::MCPTrigger)
+    }
+
+    /// Fixed output ports edges can leave from (`"port"` on the edge); None for nodes without
+    /// fixed ports (ABSplit ports are its variant names, see graph)
+    pub fn output_ports(&self) -> Option<&'static [&'static str]> {
+        match self {
+            NodeType::FeatureFlag => Some(&["true", "false"]),
+            _ => None,
+        }
+    }
 }
 
 /// Connection between two nodes in the workflow DAG
@@ -132,6 +631,10 @@ pub struct Edge {
     pub from: String,
     /// Target node ID
     pub to: String,
+    /// Output port of the source node the edge leaves from (e.g. "true" / "false" of a
+    /// FeatureFlag node, variant of an ABSplit node); edges without a port always run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<String>,
 }
 
 /// File information for uploaded files
@@ -172,6 +675,27 @@ pub struct ExecutionContext {
     /// Project slug for database isolation (e.g., "default", "ecommerce", "analytics")
     /// Determines which project.db and simpletable.db files to use
     pub project_slug: String,
+    /// In-memory binary content referenced by `$binary` items (execution-scoped)
+    #[serde(skip)]
+    pub binaries: BinaryStore,
+    /// Mocked node results keyed by node id (workflow test runs only)
+    #[serde(skip)]
+    pub mocks: Option<Arc<HashMap<String, NodeMock>>>,
+    /// Open SimpleTable transaction of this execution (TransactionBegin/Commit nodes)
+    #[serde(skip)]
+    pub transaction: TransactionSlot,
+    /// Variables of the running workflow (`$vars.name`), set by the engine
+    #[serde(skip)]
+    pub vars: Arc<Value>,
+    /// Memory and CPU budget of the run (shared with sub-workflows), set by the engine
+    #[serde(skip)]
+    pub budget: RunBudget,
+    /// Outputs of the nodes that ran so far, keyed by node id (`$node("id").field`)
+    /// 
+    /// Set by the engine, and only for nodes some `$node` pin of the workflow reads.
+    /// Sub-workflows start with none.
+    #[serde(skip)]
+    pub node_outputs: HashMap<String, Arc<Vec<Value>>>,
 }
 
 impl ExecutionContext {
@@ -192,7 +716,13 @@ impl ExecutionContext {
             query: HashMap::new(),
             headers: HashMap::new(),
             metadata, 
-            project_slug 
+            project_slug,
+            binaries: BinaryStore::default(),
+            transaction: TransactionSlot::default(),
+            budget: RunBudget::default(),
+            mocks: None,
+            vars: Arc::default(),
+            node_outputs: HashMap::new(),
         }
     }
     
@@ -209,7 +739,13 @@ impl ExecutionContext {
             query: HashMap::new(),
             headers: HashMap::new(),
             metadata, 
-            project_slug 
+            project_slug,
+            binaries: BinaryStore::default(),
+            transaction: TransactionSlot::default(),
+            budget: RunBudget::default(),
+            mocks: None,
+            vars: Arc::default(),
+            node_outputs: HashMap::new(),
         }
     }
     
@@ -237,7 +773,22 @@ impl ExecutionContext {
             query: HashMap::new(),
             headers: HashMap::new(),
             metadata, 
-            project_slug 
+            project_slug,
+            binaries: BinaryStore::default(),
+            transaction: TransactionSlot::default(),
+            budget: RunBudget::default(),
+            mocks: None,
+            vars: Arc::default(),
+            node_outputs: HashMap::new(),
         }
     }
+    
+    /// Create execution context from a polling trigger
+    /// The fetched records become the data array
+    pub fn from_polling_trigger(workflow_id: String, trigger_node_id: String, project_slug: String, items: Vec<Value>) -> Self {
+        let mut context = Self::from_array_data(workflow_id, items, project_slug);
+        context.metadata.insert("trigger_node_id".to_string(), Value::String(trigger_node_id));
+        context.metadata.insert("trigger_type".to_string(), Value::String("polling".to_string()));
+        context
+    }
 }
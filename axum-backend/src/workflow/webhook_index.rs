@@ -0,0 +1,173 @@
+//! Precompiled webhook route index
+//! 
+//! Replaces the per-request scan over all workflow nodes with a lookup table
+//! built when the workflow is compiled. Static paths resolve with a single hash
+//! lookup; parameterized paths (`/orders/{id}`) are matched segment by segment,
+//! most specific pattern first.
+//! 
+//! Webhook node params: { "path": "/orders/{id}", "method": "POST" }
+//! `method` is optional - without it the node accepts any HTTP method.
+
+use crate::workflow::types::{NodeType, Workflow};
+use anyhow::Result;
+use std::collections::HashMap;
+
+/// Method key used for webhook nodes that accept any HTTP method
+const ANY_METHOD: &str = "*";
+
+/// Result of resolving a webhook request to its start node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebhookLookup {
+    /// Matching webhook node with extracted path parameters
+    Found {
+        node_id: String,
+        path_params: HashMap<String, String>,
+    },
+    /// The path exists but not for this HTTP method
+    MethodNotAllowed,
+    /// No webhook node serves this path
+    NotFound,
+}
+
+/// One segment of a parameterized webhook path
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Static(String),
+    Param(String),
+}
+
+/// Parameterized webhook route
+#[derive(Debug, Clone)]
+struct PatternRoute {
+    method: String,
+    segments: Vec<Segment>,
+    node_id: String,
+}
+
+impl PatternRoute {
+    /// Number of static segments (higher = more specific)
+    fn specificity(&self) -> usize {
+        self.segments.iter().filter(|s| matches!(s, Segment::Static(_))).count()
+    }
+
+    /// Match request path segments, returning extracted parameters
+    fn matches(&self, parts: &[&str]) -> Option<HashMap<String, String>> {
+        if parts.len() != self.segments.len() {
+            return None;
+        }
+        let mut params = HashMap::new();
+        for (segment, part) in self.segments.iter().zip(parts) {
+            match segment {
+                Segment::Static(expected) if expected == part => {}
+                Segment::Static(_) => return None,
+                Segment::Param(name) => {
+                    params.insert(name.clone(), part.to_string());
+                }
+            }
+        }
+        Some(params)
+    }
+}
+
+/// Webhook route index for a single workflow
+#[derive(Debug, Default)]
+pub struct WebhookRouteIndex {
+    /// (method, normalized path) -> node_id for static paths
+    exact: HashMap<(String, String), String>,
+    /// Parameterized routes sorted by specificity (most specific first)
+    patterns: Vec<PatternRoute>,
+}
+
+impl WebhookRouteIndex {
+    /// Build the index from the workflow's enabled Webhook nodes
+    /// 
+    /// Fails when two webhook nodes claim the same method and path.
+    pub fn build(workflow: &Workflow) -> Result<Self> {
+        let mut index = Self::default();
+
+        for node in &workflow.nodes {
+            if !matches!(node.node_type, NodeType::Webhook) || node.disabled {
+                continue;
+            }
+            let Some(path) = node.params.get("path").and_then(|p| p.as_str()) else {
+                tracing::debug!("  ⚠️ Webhook node '{}' has no path parameter", node.id);
+                continue;
+            };
+            let method = node.params.get("method")
+                .and_then(|m| m.as_str())
+                .map(|m| m.to_uppercase())
+                .unwrap_or_else(|| ANY_METHOD.to_string());
+            let path = normalize_path(path);
+            let segments = parse_segments(&path);
+
+            if segments.iter().any(|s| matches!(s, Segment::Param(_))) {
+                let duplicate = index.patterns.iter()
+                    .any(|route| route.method == method && route.segments == segments);
+                if duplicate {
+                    return Err(anyhow::anyhow!("Duplicate webhook route {} {} (node '{}')", method, path, node.id));
+                }
+                index.patterns.push(PatternRoute { method, segments, node_id: node.id.clone() });
+            } else if let Some(existing) = index.exact.insert((method.clone(), path.clone()), node.id.clone()) {
+                return Err(anyhow::anyhow!("Duplicate webhook route {} {} (nodes '{}' and '{}')",
+                    method, path, existing, node.id));
+            }
+        }
+
+        // Most specific pattern wins; ties keep definition order (stable sort)
+        index.patterns.sort_by_key(|route| std::cmp::Reverse(route.specificity()));
+
+        Ok(index)
+    }
+
+    /// Resolve a request to the webhook node that should start the execution
+    pub fn resolve(&self, method: &str, path: &str) -> WebhookLookup {
+        let path = normalize_path(path);
+        let method = method.to_uppercase();
+
+        // Fast path: static routes (exact method first, then any-method nodes)
+        for key_method in [method.as_str(), ANY_METHOD] {
+            if let Some(node_id) = self.exact.get(&(key_method.to_string(), path.clone())) {
+                return WebhookLookup::Found { node_id: node_id.clone(), path_params: HashMap::new() };
+            }
+        }
+
+        let parts: Vec<&str> = path.split('/').filter(|p| !p.is_empty()).collect();
+        let mut path_matched = false;
+        for route in &self.patterns {
+            if let Some(path_params) = route.matches(&parts) {
+                if route.method == method || route.method == ANY_METHOD {
+                    return WebhookLookup::Found { node_id: route.node_id.clone(), path_params };
+                }
+                path_matched = true;
+            }
+        }
+
+        if path_matched || self.exact.keys().any(|(_, p)| *p == path) {
+            WebhookLookup::MethodNotAllowed
+        } else {
+            WebhookLookup::NotFound
+        }
+    }
+}
+
+/// Normalize a webhook path to a leading slash without a trailing slash
+fn normalize_path(path: &str) -> String {
+    let trimmed = path.trim_matches('/');
+    format!("/{}", trimmed)
+}
+
+/// Split a route path into static and `{param}` / `:param` segments
+fn parse_segments(path: &str) -> Vec<Segment> {
+    path.split('/')
+        .filter(|p| !p.is_empty())
+        .map(|part| {
+            if let Some(name) = part.strip_prefix('{').and_then(|p| p.strip_suffix('}')) {
+                Segment::Param(name.to_string())
+            } else if let Some(name) = part.strip_prefix(':') {
+                Segment::Param(name.to_string())
+            } else {
+                Segment::Static(part.to_string())
+            }
+        })
+        .collect()
+}
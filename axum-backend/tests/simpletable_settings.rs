@@ -0,0 +1,58 @@
+//! Simpletable settings never hand out a plaintext connection string
+
+use axum::http::StatusCode;
+use mechaway::testing::TestApp;
+use serde_json::json;
+
+const DSN: &str = "postgres://app:hunter2@db:5432/app";
+
+#[tokio::test]
+async fn plaintext_connection_string_is_redacted() -> anyhow::Result<()> {
+    let app = TestApp::spawn().await?;
+    let simpletable = json!({ "backend": "postgres", "connection_string": DSN, "schema": "mway_shop" });
+
+    let response = app.request("PUT", "/api/projects/shop/simpletable", Some(&simpletable)).await?;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.body["connection_string"], "[redacted]");
+
+    let response = app.request("GET", "/api/projects/shop/simpletable", None).await?;
+    assert_eq!(response.body["connection_string"], "[redacted]");
+
+    let response = app.request("GET", "/api/projects/shop/settings", None).await?;
+    assert_eq!(response.body["simpletable"]["connection_string"], "[redacted]");
+    assert!(!response.body.to_string().contains("hunter2"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn redacted_connection_string_keeps_the_stored_one() -> anyhow::Result<()> {
+    let app = TestApp::spawn().await?;
+    let simpletable = json!({ "backend": "postgres", "connection_string": DSN });
+    app.request("PUT", "/api/projects/shop/simpletable", Some(&simpletable)).await?;
+
+    // Round trip of the settings as read from the API
+    let mut settings = app.request("GET", "/api/projects/shop/settings", None).await?.body;
+    settings["allowed_callers"] = json!(["ecommerce"]);
+    let response = app.request("PUT", "/api/projects/shop/settings", Some(&settings)).await?;
+    assert_eq!(response.status, StatusCode::OK);
+
+    // "[redacted]" is only accepted while a real connection string is stored
+    let simpletable = json!({ "backend": "postgres", "connection_string": "[redacted]", "schema": "mway_shop" });
+    let response = app.request("PUT", "/api/projects/shop/simpletable", Some(&simpletable)).await?;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let response = app.request("PUT", "/api/projects/fresh/simpletable", Some(&simpletable)).await?;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    Ok(())
+}
+
+#[tokio::test]
+async fn secret_reference_is_returned_as_is() -> anyhow::Result<()> {
+    let app = TestApp::spawn().await?;
+    let simpletable = json!({ "backend": "postgres", "connection_string": "$secret.simpletable_dsn" });
+    app.request("PUT", "/api/projects/shop/simpletable", Some(&simpletable)).await?;
+
+    let response = app.request("GET", "/api/projects/shop/simpletable", None).await?;
+    assert_eq!(response.body["connection_string"], "$secret.simpletable_dsn");
+    Ok(())
+}
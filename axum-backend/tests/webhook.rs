@@ -0,0 +1,42 @@
+//! Webhook round trip through the in-process application
+
+use axum::http::StatusCode;
+use mechaway::testing::TestApp;
+use serde_json::json;
+
+#[tokio::test]
+async fn webhook_returns_workflow_output() -> anyhow::Result<()> {
+    let app = TestApp::spawn().await?;
+    app.create_workflow(json!({
+        "id": "wf-greet",
+        "name": "Greet",
+        "nodes": [
+            { "id": "hook", "node_type": "Webhook", "params": { "path": "/greet" } },
+            { "id": "greet", "node_type": "FunLogic", "params": { "script":
+                "return { greeting = 'hello ' .. data[1].name }" } }
+        ],
+        "edges": [{ "from": "hook", "to": "greet" }]
+    })).await?;
+
+    let response = app.webhook("wf-greet", "/greet", json!({ "name": "world" })).await?;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.body, json!([{ "greeting": "hello world" }]));
+    Ok(())
+}
+
+#[tokio::test]
+async fn webhook_for_unknown_path_is_not_found() -> anyhow::Result<()> {
+    let app = TestApp::spawn().await?;
+    app.create_workflow(json!({
+        "id": "wf-greet",
+        "name": "Greet",
+        "nodes": [{ "id": "hook", "node_type": "Webhook", "params": { "path": "/greet" } }],
+        "edges": []
+    })).await?;
+
+    let response = app.webhook("wf-greet", "/missing", json!({})).await?;
+
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    Ok(())
+}